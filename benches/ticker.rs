@@ -0,0 +1,51 @@
+//! Decoding throughput of ticker frames
+//!
+//! Run with `cargo bench --bench ticker`. Frames carry 3000 packets, the most
+//! instruments a single connection can subscribe to.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use kiteconnect::ticker::decode_frame;
+use std::hint::black_box;
+
+const PACKETS: usize = 3000;
+
+/// Builds a frame repeating the packet of the given length from the mock frame
+fn frame(packet_len: usize) -> Vec<u8> {
+    let mock = std::fs::read("mocks/ticker_frame.bin").unwrap();
+    let mut offset = 2;
+    let packet = loop {
+        let len = u16::from_be_bytes([mock[offset], mock[offset + 1]]) as usize;
+        if len == packet_len {
+            break &mock[offset..offset + 2 + len];
+        }
+        offset += 2 + len;
+    };
+    let mut frame = (PACKETS as u16).to_be_bytes().to_vec();
+    for _ in 0..PACKETS {
+        frame.extend_from_slice(packet);
+    }
+    frame
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_frame");
+    group.throughput(Throughput::Elements(PACKETS as u64));
+    for (mode, packet_len) in [("ltp", 8), ("quote", 44), ("full", 184)] {
+        let frame = frame(packet_len);
+        group.bench_with_input(BenchmarkId::new("iterate", mode), &frame, |b, frame| {
+            b.iter(|| decode_frame(black_box(frame)).map(|tick| tick.last_price).sum::<f64>())
+        });
+        let mut ticks = Vec::with_capacity(PACKETS);
+        group.bench_with_input(BenchmarkId::new("reused_buffer", mode), &frame, |b, frame| {
+            b.iter(|| {
+                ticks.clear();
+                ticks.extend(decode_frame(black_box(frame)));
+                ticks.len()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
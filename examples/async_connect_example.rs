@@ -5,8 +5,6 @@ use serde_json::Value as JsonValue;
 use std::collections::HashMap;
 use url::Url;
 
-#[cfg(not(target_arch = "wasm32"))]
-use reqwest;
 #[cfg(not(target_arch = "wasm32"))]
 use sha2::{Sha256, Digest};
 
@@ -34,6 +32,7 @@ trait RequestHandler {
 pub struct KiteConnect {
     api_key: String,
     access_token: String,
+    #[allow(dead_code)]
     session_expiry_hook: Option<fn() -> ()>,
     #[cfg(not(target_arch = "wasm32"))]
     client: reqwest::Client,
@@ -123,7 +122,7 @@ impl KiteConnect {
         data.insert("checksum", checksum.as_str());
 
         let url = self.build_url("/session/token", None);
-        let mut resp = self.send_request(url, "POST", Some(data)).await?;
+        let resp = self.send_request(url, "POST", Some(data)).await?;
 
         if resp.status().is_success() {
             let jsn: JsonValue = resp.json().await?;
@@ -159,6 +158,7 @@ impl KiteConnect {
     }
 
     /// Place an order (now async)
+    #[allow(clippy::too_many_arguments)]
     pub async fn place_order(
         &self,
         variety: &str,
@@ -9,7 +9,7 @@ use std::error::Error;
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     // Initialize the KiteConnect client
-    let mut client = KiteConnect::new("your_api_key", "");
+    let client = KiteConnect::new("your_api_key", "");
     
     // Step 1: Authentication Flow
     println!("=== Authentication ===");
@@ -17,16 +17,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Generate login URL for user authentication
     let login_url = client.login_url();
     println!("1. Visit this URL to login: {}", login_url);
-    println!("2. After login, copy the request_token from the redirect URL");
+    println!("2. After login, the request_token is read from the redirect URL");
     
     // In a real application, you would:
+    // - Register http://127.0.0.1:5000/ as the app's redirect URL
     // - Open the login URL in a browser
     // - User completes login
-    // - Extract request_token from callback URL
-    // - Use it here:
+    // - Capture the request_token from the callback and use it here:
     
     // Uncomment and use real tokens:
-    // let session = client.generate_session("your_request_token", "your_api_secret").await?;
+    // let request_token = kiteconnect::auth::capture_request_token(5000).await?;
+    // let session = client.generate_session(&request_token, "your_api_secret").await?;
     // println!("Session created: {:?}", session);
     
     // For demo purposes, set access token directly
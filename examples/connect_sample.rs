@@ -1,18 +1,21 @@
 extern crate kiteconnect;
 extern crate serde_json as json;
 
+use kiteconnect::auth::capture_request_token;
 use kiteconnect::connect::KiteConnect;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut kiteconnect = KiteConnect::new("<API-KEY>", "");
+    let kiteconnect = KiteConnect::new("<API-KEY>", "");
 
-    // Open browser with this URL and get the request token from the callback
+    // Open browser with this URL; with `http://127.0.0.1:5000/` registered as the
+    // app's redirect URL the request token is captured from the callback
     let loginurl = kiteconnect.login_url();
     println!("{:?}", loginurl);
+    let request_token = capture_request_token(5000).await?;
 
     // Generate access token with the above request token
-    let resp = kiteconnect.generate_session("<REQUEST-TOKEN>", "<API-SECRET>").await?;
+    let resp = kiteconnect.generate_session(&request_token, "<API-SECRET>").await?;
     // `generate_session` internally sets the access token from the response
     println!("{:?}", resp);
 
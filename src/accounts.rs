@@ -0,0 +1,269 @@
+//! # Multiple Accounts
+//!
+//! [`KiteAccounts`] keeps named clients for several Kite accounts, e.g. family
+//! or client accounts managed together, and runs the same operation on all of
+//! them concurrently:
+//!
+//! ```rust,no_run
+//! use kiteconnect::accounts::KiteAccounts;
+//! use kiteconnect::connect::KiteConnect;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> kiteconnect::error::Result<()> {
+//! let mut accounts = KiteAccounts::new();
+//! accounts.insert("self", KiteConnect::new("api_key", "token_1"));
+//! accounts.insert("spouse", KiteConnect::new("api_key", "token_2"));
+//!
+//! // Place the same order in every account
+//! let results = accounts
+//!     .fan_out(|client| async move {
+//!         client
+//!             .place_order(
+//!                 "regular", "NSE", "INFY", "BUY", "1", Some("CNC"), Some("MARKET"),
+//!                 None, None, None, None, None, None, None, None,
+//!             )
+//!             .await
+//!     })
+//!     .await;
+//! for (account, result) in results {
+//!     println!("{}: {:?}", account, result);
+//! }
+//!
+//! // Combined holdings across accounts
+//! for holding in accounts.aggregate_holdings().await? {
+//!     println!("{}:{} x {}", holding.exchange, holding.tradingsymbol, holding.quantity);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::auth::TokenStore;
+use crate::connect::KiteConnect;
+use crate::error::Result;
+use crate::models::Holding;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+
+/// Results of an operation run on several accounts, tagged with the account name
+pub type AccountResults<T> = Vec<(String, Result<T>)>;
+
+/// Named clients for several Kite accounts
+#[derive(Clone, Debug, Default)]
+pub struct KiteAccounts {
+    accounts: BTreeMap<String, KiteConnect>,
+}
+
+/// Holdings of one instrument summed over several accounts
+#[derive(Clone, Debug, PartialEq)]
+pub struct AggregatedHolding {
+    /// Exchange of the instrument
+    pub exchange: String,
+    /// Trading symbol of the instrument
+    pub tradingsymbol: String,
+    /// Total settled quantity over all accounts
+    pub quantity: i64,
+    /// Total T1 quantity over all accounts
+    pub t1_quantity: i64,
+    /// The individual holdings, tagged with the account name
+    pub holdings: Vec<(String, Holding)>,
+}
+
+impl KiteAccounts {
+    /// Creates an empty set of accounts
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a client under the given name, returning the client it replaced
+    pub fn insert(&mut self, name: &str, client: KiteConnect) -> Option<KiteConnect> {
+        self.accounts.insert(name.to_string(), client)
+    }
+
+    /// Adds a client whose session tokens are persisted in `store`
+    ///
+    /// If the client has no access token yet, the one saved in the store is
+    /// restored. Returns an error if the store fails to load.
+    pub fn insert_with_token_store(
+        &mut self,
+        name: &str,
+        mut client: KiteConnect,
+        store: Arc<dyn TokenStore>,
+    ) -> Result<Option<KiteConnect>> {
+        if client.access_token().is_empty() {
+            if let Some(tokens) = store.load()? {
+                client.set_access_token(&tokens.access_token);
+            }
+        }
+        client.set_token_store(store);
+        Ok(self.insert(name, client))
+    }
+
+    /// Removes and returns the client with the given name
+    pub fn remove(&mut self, name: &str) -> Option<KiteConnect> {
+        self.accounts.remove(name)
+    }
+
+    /// Returns the client with the given name
+    pub fn get(&self, name: &str) -> Option<&KiteConnect> {
+        self.accounts.get(name)
+    }
+
+    /// Returns the account names in sorted order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.accounts.keys().map(String::as_str)
+    }
+
+    /// Returns the accounts and their clients in name order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &KiteConnect)> {
+        self.accounts.iter().map(|(name, client)| (name.as_str(), client))
+    }
+
+    /// Returns the number of accounts
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Returns `true` if no account was added
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Runs an operation on every account concurrently
+    ///
+    /// The closure receives a clone of each client, which shares its access
+    /// token and connection pool. Results are returned in name order; a failure
+    /// in one account does not affect the others.
+    pub async fn fan_out<F, Fut, T>(&self, operation: F) -> AccountResults<T>
+    where
+        F: Fn(KiteConnect) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let calls = self.accounts.values().map(|client| operation(client.clone()));
+        let results = futures::future::join_all(calls).await;
+        self.accounts.keys().cloned().zip(results).collect()
+    }
+
+    /// Fetches the holdings of every account
+    pub async fn holdings(&self) -> AccountResults<Vec<Holding>> {
+        self.fan_out(|client| async move { client.holdings_typed().await }).await
+    }
+
+    /// Fetches the holdings of every account and groups them by instrument
+    ///
+    /// Returns the first error if the holdings of any account cannot be fetched.
+    pub async fn aggregate_holdings(&self) -> Result<Vec<AggregatedHolding>> {
+        let mut aggregated: BTreeMap<(String, String), AggregatedHolding> = BTreeMap::new();
+        for (account, holdings) in self.holdings().await {
+            for holding in holdings? {
+                let entry = aggregated
+                    .entry((holding.exchange.clone(), holding.tradingsymbol.clone()))
+                    .or_insert_with(|| AggregatedHolding {
+                        exchange: holding.exchange.clone(),
+                        tradingsymbol: holding.tradingsymbol.clone(),
+                        quantity: 0,
+                        t1_quantity: 0,
+                        holdings: Vec::new(),
+                    });
+                entry.quantity += holding.quantity;
+                entry.t1_quantity += holding.t1_quantity;
+                entry.holdings.push((account.clone(), holding));
+            }
+        }
+        Ok(aggregated.into_values().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{MemoryTokenStore, StoredTokens};
+    use crate::error::KiteError;
+    use mockito::{Matcher, Server};
+
+    fn client(server: &Server, token: &str) -> KiteConnect {
+        KiteConnect::builder("API_KEY")
+            .access_token(token)
+            .base_url(&server.url())
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fan_out() {
+        let mut server = Server::new_async().await;
+        let _ok = server
+            .mock("POST", "/orders/regular")
+            .match_header("authorization", "token API_KEY:token_a")
+            .with_body(r#"{"status":"success","data":{"order_id":"1"}}"#)
+            .create_async()
+            .await;
+        let _rejected = server
+            .mock("POST", "/orders/regular")
+            .match_header("authorization", "token API_KEY:token_b")
+            .with_status(400)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"status":"error","message":"Insufficient funds","error_type":"MarginException"}"#)
+            .create_async()
+            .await;
+
+        let mut accounts = KiteAccounts::new();
+        accounts.insert("b", client(&server, "token_b"));
+        accounts.insert("a", client(&server, "token_a"));
+        assert_eq!(accounts.names().collect::<Vec<_>>(), ["a", "b"]);
+
+        let results = accounts
+            .fan_out(|client| async move {
+                client
+                    .place_order(
+                        "regular", "NSE", "INFY", "BUY", "1", Some("CNC"), Some("MARKET"),
+                        None, None, None, None, None, None, None, None,
+                    )
+                    .await
+            })
+            .await;
+        assert_eq!(results[0].0, "a");
+        assert_eq!(results[0].1.as_ref().unwrap()["data"]["order_id"], "1");
+        assert_eq!(results[1].0, "b");
+        assert!(matches!(results[1].1, Err(KiteError::Margin { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_holdings() {
+        let mut server = Server::new_async().await;
+        let _holdings = server
+            .mock("GET", "/portfolio/holdings")
+            .match_header("authorization", Matcher::Any)
+            .with_body_from_file("mocks/holdings.json")
+            .expect(2)
+            .create_async()
+            .await;
+
+        let store = Arc::new(MemoryTokenStore::new());
+        store
+            .save(&StoredTokens { access_token: "saved".to_string(), ..Default::default() })
+            .unwrap();
+        let mut accounts = KiteAccounts::new();
+        accounts.insert("a", client(&server, "token_a"));
+        accounts
+            .insert_with_token_store("b", client(&server, ""), store)
+            .unwrap();
+        assert_eq!(accounts.get("b").unwrap().access_token(), "saved");
+
+        let aggregated = accounts.aggregate_holdings().await.unwrap();
+        let mock: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string("mocks/holdings.json").unwrap()).unwrap();
+        let single: Vec<Holding> = serde_json::from_value(mock["data"].clone()).unwrap();
+        assert!(!aggregated.is_empty());
+        for holding in &aggregated {
+            let expected: i64 = single
+                .iter()
+                .filter(|h| h.exchange == holding.exchange && h.tradingsymbol == holding.tradingsymbol)
+                .map(|h| h.quantity)
+                .sum();
+            assert_eq!(holding.quantity, expected * 2);
+            assert!(holding.holdings.iter().any(|(account, _)| account == "a"));
+            assert!(holding.holdings.iter().any(|(account, _)| account == "b"));
+        }
+    }
+}
@@ -0,0 +1,296 @@
+//! Headless login with user credentials and a TOTP secret
+
+use super::totp;
+use crate::connect::KiteConnect;
+use crate::error::{KiteError, Result};
+use crate::models::UserSession;
+use reqwest::header::{CONTENT_TYPE, LOCATION};
+use serde_json::Value as JsonValue;
+use std::fmt;
+use std::time::Duration;
+
+/// Default host serving the Kite web login
+pub const DEFAULT_LOGIN_BASE_URL: &str = "https://kite.zerodha.com";
+
+/// Maximum number of redirects followed while looking for the request token
+const MAX_REDIRECTS: usize = 10;
+
+/// Drives the Kite web login and TOTP two-factor flow without a browser
+///
+/// The account must have TOTP enabled; `totp_secret` is the base32 key shown
+/// when setting up an authenticator app. The Connect app's redirect URL does not
+/// need to be reachable, the request token is read from the redirect itself.
+#[derive(Clone)]
+pub struct AutoLogin {
+    user_id: String,
+    password: String,
+    totp_secret: String,
+    login_base_url: String,
+    timeout: Duration,
+}
+
+impl fmt::Debug for AutoLogin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AutoLogin")
+            .field("user_id", &self.user_id)
+            .field("password", &"***")
+            .field("totp_secret", &"***")
+            .field("login_base_url", &self.login_base_url)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl AutoLogin {
+    /// Creates a login helper for the given Kite user
+    pub fn new(user_id: &str, password: &str, totp_secret: &str) -> Self {
+        AutoLogin {
+            user_id: user_id.to_string(),
+            password: password.to_string(),
+            totp_secret: totp_secret.to_string(),
+            login_base_url: DEFAULT_LOGIN_BASE_URL.to_string(),
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Overrides the login host, e.g. to point at a mock server in tests
+    pub fn login_base_url(mut self, url: &str) -> Self {
+        self.login_base_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Sets the timeout for each request of the login flow
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Logs in and exchanges the request token for a session on `client`
+    ///
+    /// The access token is set on the client, exactly as with
+    /// [`KiteConnect::generate_session`].
+    pub async fn login(&self, client: &KiteConnect, api_secret: &str) -> Result<UserSession> {
+        let request_token = self.request_token(client).await?;
+        client.generate_session(&request_token, api_secret).await
+    }
+
+    /// Logs in and returns a fresh request token for the client's API key
+    pub async fn request_token(&self, client: &KiteConnect) -> Result<String> {
+        let mut builder = reqwest::Client::builder()
+            .cookie_store(true)
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(self.timeout);
+        if let Some(proxy) = client.proxy() {
+            builder = builder.proxy(proxy.to_reqwest()?);
+        }
+        let http = builder.build()?;
+
+        let login_url = format!(
+            "{}/connect/login?v=3&api_key={}",
+            self.login_base_url,
+            client.api_key()
+        );
+        let login_page = match follow_redirects(&http, &login_url).await? {
+            Redirect::RequestToken(token) => return Ok(token),
+            Redirect::Final(url) => url,
+        };
+
+        let login = post_form(
+            &http,
+            &format!("{}/api/login", self.login_base_url),
+            &[("user_id", &self.user_id), ("password", &self.password)],
+        )
+        .await?;
+        let request_id = login["data"]["request_id"]
+            .as_str()
+            .ok_or_else(|| KiteError::Other("login response did not contain a request_id".to_string()))?
+            .to_string();
+
+        let twofa_value = totp::now(&self.totp_secret)?;
+        post_form(
+            &http,
+            &format!("{}/api/twofa", self.login_base_url),
+            &[
+                ("user_id", &self.user_id),
+                ("request_id", &request_id),
+                ("twofa_value", &twofa_value),
+                ("twofa_type", "totp"),
+                ("skip_totp", "true"),
+            ],
+        )
+        .await?;
+
+        // With the session cookie set, the login page redirects to the app
+        let mut finish = login_page;
+        finish.query_pairs_mut().append_pair("skip_session", "true");
+        match follow_redirects(&http, finish.as_str()).await? {
+            Redirect::RequestToken(token) => Ok(token),
+            Redirect::Final(url) => Err(KiteError::Other(format!(
+                "login did not redirect with a request token, stopped at {}",
+                crate::redact::redact_url(&url)
+            ))),
+        }
+    }
+}
+
+/// Where a chain of redirects ended
+enum Redirect {
+    /// A redirect carried a request token
+    RequestToken(String),
+    /// The last URL requested, which did not redirect further
+    Final(url::Url),
+}
+
+/// Follows redirects until one carries a `request_token` or a page is served
+async fn follow_redirects(http: &reqwest::Client, url: &str) -> Result<Redirect> {
+    let mut current = url::Url::parse(url)
+        .map_err(|e| KiteError::Other(format!("invalid login URL: {}", e)))?;
+
+    for _ in 0..MAX_REDIRECTS {
+        let resp = http.get(current.clone()).send().await?;
+        let location = match resp.headers().get(LOCATION) {
+            Some(location) if resp.status().is_redirection() => location
+                .to_str()
+                .map_err(|_| KiteError::Other("invalid redirect location".to_string()))?
+                .to_string(),
+            _ => {
+                let status = resp.status();
+                if !status.is_success() {
+                    return Err(error_from(resp).await);
+                }
+                return Ok(Redirect::Final(current));
+            }
+        };
+
+        current = current
+            .join(&location)
+            .map_err(|e| KiteError::Other(format!("invalid redirect location: {}", e)))?;
+        if let Some((_, token)) = current.query_pairs().find(|(key, _)| key == "request_token") {
+            return Ok(Redirect::RequestToken(token.into_owned()));
+        }
+    }
+    Err(KiteError::Other(format!("too many redirects during login (more than {})", MAX_REDIRECTS)))
+}
+
+/// Posts a form to the login API and returns the decoded JSON response
+async fn post_form(http: &reqwest::Client, url: &str, form: &[(&str, &str)]) -> Result<JsonValue> {
+    let resp = http.post(url).form(form).send().await?;
+    if !resp.status().is_success() {
+        return Err(error_from(resp).await);
+    }
+    Ok(resp.json().await?)
+}
+
+async fn error_from(resp: reqwest::Response) -> KiteError {
+    let status = resp.status().as_u16();
+    let content_type = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    match resp.text().await {
+        Ok(body) => KiteError::from_response(status, content_type.as_deref(), &body),
+        Err(e) => e.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+
+    #[tokio::test]
+    async fn test_auto_login() {
+        let mut server = mockito::Server::new_async().await;
+        let base = server.url();
+
+        let _entry = server
+            .mock("GET", "/connect/login")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("api_key".into(), "key".into()),
+                Matcher::UrlEncoded("v".into(), "3".into()),
+            ]))
+            .with_status(302)
+            .with_header("location", "/connect/login?api_key=key&sess_id=abc")
+            .create_async()
+            .await;
+        let _page = server
+            .mock("GET", "/connect/login")
+            .match_query(Matcher::Regex("^api_key=key&sess_id=abc$".into()))
+            .with_status(200)
+            .with_header("set-cookie", "kf_session=xyz; Path=/")
+            .with_body("<html></html>")
+            .create_async()
+            .await;
+        let login = server
+            .mock("POST", "/api/login")
+            .match_header("cookie", Matcher::Regex("kf_session=xyz".into()))
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("user_id".into(), "AB1234".into()),
+                Matcher::UrlEncoded("password".into(), "hunter2".into()),
+            ]))
+            .with_body(r#"{"status":"success","data":{"user_id":"AB1234","request_id":"req-1","twofa_type":"totp"}}"#)
+            .create_async()
+            .await;
+        let twofa = server
+            .mock("POST", "/api/twofa")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("request_id".into(), "req-1".into()),
+                Matcher::UrlEncoded("twofa_type".into(), "totp".into()),
+                Matcher::Regex("twofa_value=[0-9]{6}".into()),
+            ]))
+            .with_body(r#"{"status":"success","data":{"profile":{}}}"#)
+            .create_async()
+            .await;
+        let _finish = server
+            .mock("GET", "/connect/login")
+            .match_query(Matcher::UrlEncoded("skip_session".into(), "true".into()))
+            .with_status(302)
+            .with_header("location", "/connect/finish?sess_id=abc")
+            .create_async()
+            .await;
+        let _redirect = server
+            .mock("GET", "/connect/finish")
+            .match_query(Matcher::Any)
+            .with_status(302)
+            .with_header(
+                "location",
+                "https://example.com/callback?action=login&status=success&request_token=tok123",
+            )
+            .create_async()
+            .await;
+
+        let client = KiteConnect::new("key", "");
+        let auto = AutoLogin::new("AB1234", "hunter2", "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").login_base_url(&base);
+        assert_eq!(auto.request_token(&client).await.unwrap(), "tok123");
+        login.assert_async().await;
+        twofa.assert_async().await;
+        assert!(!format!("{:?}", auto).contains("hunter2"));
+    }
+
+    #[tokio::test]
+    async fn test_auto_login_invalid_credentials() {
+        let mut server = mockito::Server::new_async().await;
+        let _page = server
+            .mock("GET", "/connect/login")
+            .match_query(Matcher::Any)
+            .with_status(200)
+            .create_async()
+            .await;
+        let _login = server
+            .mock("POST", "/api/login")
+            .with_status(403)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"status":"error","message":"Invalid username or password.","error_type":"InputException"}"#)
+            .create_async()
+            .await;
+
+        let client = KiteConnect::new("key", "");
+        let err = AutoLogin::new("AB1234", "wrong", "GEZDGNBVGY3TQOJQ")
+            .login_base_url(&server.url())
+            .request_token(&client)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KiteError::Input { status: 403, .. }));
+    }
+}
@@ -0,0 +1,150 @@
+//! Local HTTP listener capturing the request token from the login redirect
+
+use crate::error::{KiteError, Result};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Largest request head read from a connection
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+const SUCCESS_PAGE: &str = "<html><body><h3>Login successful</h3>\
+    <p>You can close this window and return to the application.</p></body></html>";
+
+/// Waits for the Kite login redirect on `http://127.0.0.1:{port}/` and returns its request token
+///
+/// Register `http://127.0.0.1:{port}/` (any path works) as the redirect URL of
+/// the Connect app, open [`KiteConnect::login_url`] in a browser and await this
+/// function. Requests without a `request_token`, such as the browser asking for
+/// a favicon, are answered with `404` and ignored.
+///
+/// ```rust,no_run
+/// use kiteconnect::auth::capture_request_token;
+/// use kiteconnect::connect::KiteConnect;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = KiteConnect::new("api_key", "");
+/// println!("Log in at {}", client.login_url());
+/// let request_token = capture_request_token(5000).await?;
+/// client.generate_session(&request_token, "api_secret").await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`KiteConnect::login_url`]: crate::connect::KiteConnect::login_url
+pub async fn capture_request_token(port: u16) -> Result<String> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .map_err(|e| KiteError::Other(format!("failed to listen on port {}: {}", port, e)))?;
+    accept_request_token(listener).await
+}
+
+/// Serves connections on `listener` until one carries a request token
+pub(crate) async fn accept_request_token(listener: TcpListener) -> Result<String> {
+    loop {
+        let (mut stream, _) = listener
+            .accept()
+            .await
+            .map_err(|e| KiteError::Other(format!("failed to accept connection: {}", e)))?;
+
+        let token = match read_request_target(&mut stream).await {
+            Some(target) => request_token_from(&target),
+            None => None,
+        };
+        match token {
+            Some(Ok(token)) => {
+                respond(&mut stream, "200 OK", SUCCESS_PAGE).await;
+                return Ok(token);
+            }
+            Some(Err(message)) => {
+                respond(&mut stream, "400 Bad Request", &message).await;
+                return Err(KiteError::Other(message));
+            }
+            None => respond(&mut stream, "404 Not Found", "Not Found").await,
+        }
+    }
+}
+
+/// Reads the request head and returns the request target of its first line
+async fn read_request_target(stream: &mut TcpStream) -> Option<String> {
+    let mut buffer = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    while !buffer.windows(4).any(|window| window == b"\r\n\r\n") && buffer.len() < MAX_REQUEST_BYTES {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    let head = String::from_utf8_lossy(&buffer);
+    let mut parts = head.lines().next()?.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => Some(target.to_string()),
+        _ => None,
+    }
+}
+
+/// Extracts the request token from a redirect target such as `/?request_token=..&status=success`
+///
+/// Returns `None` for unrelated requests and an error message for failed logins.
+fn request_token_from(target: &str) -> Option<std::result::Result<String, String>> {
+    let url = url::Url::parse("http://127.0.0.1").ok()?.join(target).ok()?;
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    match (param("request_token"), param("status")) {
+        (_, Some(status)) if status != "success" => Some(Err(format!("login failed with status {:?}", status))),
+        (Some(token), _) if !token.is_empty() => Some(Ok(token)),
+        _ => None,
+    }
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    if let Err(e) = stream.write_all(response.as_bytes()).await {
+        log::debug!("Failed to answer login callback: {}", e);
+    }
+    let _ = stream.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_token_from() {
+        assert_eq!(
+            request_token_from("/callback?action=login&status=success&request_token=abc"),
+            Some(Ok("abc".to_string()))
+        );
+        assert_eq!(request_token_from("/?request_token=abc"), Some(Ok("abc".to_string())));
+        assert_eq!(request_token_from("/favicon.ico"), None);
+        assert!(matches!(request_token_from("/?status=error"), Some(Err(_))));
+    }
+
+    #[tokio::test]
+    async fn test_capture_request_token() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(accept_request_token(listener));
+
+        let http = reqwest::Client::new();
+        let favicon = http.get(format!("{}/favicon.ico", base)).send().await.unwrap();
+        assert_eq!(favicon.status(), 404);
+        let callback = http
+            .get(format!("{}/?action=login&status=success&request_token=tok123", base))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(callback.status(), 200);
+
+        assert_eq!(server.await.unwrap().unwrap(), "tok123");
+    }
+}
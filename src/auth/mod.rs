@@ -0,0 +1,55 @@
+//! # Authentication Helpers
+//!
+//! Utilities around the Kite Connect login flow.
+//!
+//! A [`TokenStore`] set on the client saves the tokens of new sessions, so a
+//! restarted process can resume the session instead of logging in again.
+//! [`FileTokenStore`] keeps them in a JSON file and [`MemoryTokenStore`] in memory.
+//!
+//! Apps with a refresh token can keep a long-running client logged in with
+//! [`TokenRenewal`], which renews the access token every morning in the
+//! background (native targets only).
+//!
+//! On native targets, [`capture_request_token`] listens on a local port for the
+//! login redirect, so command line tools can register `http://127.0.0.1:{port}/`
+//! as their redirect URL instead of asking users to paste the request token.
+//!
+//! With the `autologin` feature enabled (native targets only), [`AutoLogin`]
+//! drives the Zerodha login, TOTP two-factor authentication and request token
+//! redirect without a browser, so long-running services can recover their
+//! session every morning without human interaction:
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "autologin")]
+//! # async fn example() -> kiteconnect::error::Result<()> {
+//! use kiteconnect::auth::AutoLogin;
+//! use kiteconnect::connect::KiteConnect;
+//!
+//! let client = KiteConnect::new("api_key", "");
+//! let session = AutoLogin::new("AB1234", "password", "BASE32TOTPSECRET")
+//!     .login(&client, "api_secret")
+//!     .await?;
+//! println!("Logged in as {}", session.user_id);
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(all(feature = "autologin", not(target_arch = "wasm32")))]
+mod autologin;
+#[cfg(not(target_arch = "wasm32"))]
+mod callback;
+#[cfg(not(target_arch = "wasm32"))]
+mod renewal;
+mod store;
+#[cfg(all(feature = "autologin", not(target_arch = "wasm32")))]
+pub mod totp;
+
+#[cfg(all(feature = "autologin", not(target_arch = "wasm32")))]
+pub use autologin::{AutoLogin, DEFAULT_LOGIN_BASE_URL};
+#[cfg(not(target_arch = "wasm32"))]
+pub use callback::capture_request_token;
+#[cfg(not(target_arch = "wasm32"))]
+pub use renewal::{RenewalHandle, RenewalHook, TokenRenewal};
+#[cfg(not(target_arch = "wasm32"))]
+pub use store::FileTokenStore;
+pub use store::{MemoryTokenStore, StoredTokens, TokenStore};
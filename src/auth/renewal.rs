@@ -0,0 +1,236 @@
+//! Background renewal of the access token with a refresh token
+
+use crate::connect::KiteConnect;
+use crate::error::KiteError;
+use crate::models::{ist, Timestamp};
+use chrono::{Days, NaiveTime};
+use serde_json::Value as JsonValue;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Callback notified with the new access token, or the error, after every renewal attempt
+pub type RenewalHook = Arc<dyn Fn(std::result::Result<&str, &KiteError>) + Send + Sync>;
+
+/// Schedule for renewing the access token of a client every day
+///
+/// Kite access tokens expire early every morning. Apps with a refresh token can
+/// spawn a background task that renews the token ahead of that, sets it on the
+/// client (and all its clones) and saves it to the client's token store:
+///
+/// ```rust,no_run
+/// use kiteconnect::auth::TokenRenewal;
+/// use kiteconnect::connect::KiteConnect;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let client = KiteConnect::new("api_key", "access_token");
+/// let renewal = TokenRenewal::new("refresh_token", "api_secret")
+///     .on_renewal(|result| match result {
+///         Ok(_) => println!("access token renewed"),
+///         Err(e) => eprintln!("renewal failed: {}", e),
+///     })
+///     .spawn(&client);
+/// // Renewal stops when `renewal` is dropped
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct TokenRenewal {
+    refresh_token: String,
+    api_secret: String,
+    renew_at: NaiveTime,
+    retry_delay: Duration,
+    hook: Option<RenewalHook>,
+}
+
+impl fmt::Debug for TokenRenewal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenRenewal")
+            .field("renew_at", &self.renew_at)
+            .field("retry_delay", &self.retry_delay)
+            .field("hook", &self.hook.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl TokenRenewal {
+    /// Renews daily at 05:30 IST with the given refresh token and API secret
+    pub fn new(refresh_token: &str, api_secret: &str) -> Self {
+        TokenRenewal {
+            refresh_token: refresh_token.to_string(),
+            api_secret: api_secret.to_string(),
+            renew_at: NaiveTime::from_hms_opt(5, 30, 0).expect("valid time"),
+            retry_delay: Duration::from_secs(60),
+            hook: None,
+        }
+    }
+
+    /// Sets the time of day, in IST, at which the token is renewed
+    pub fn renew_at(mut self, time: NaiveTime) -> Self {
+        self.renew_at = time;
+        self
+    }
+
+    /// Sets the delay before a failed renewal is attempted again, one minute by default
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Sets a callback notified after every renewal attempt
+    pub fn on_renewal<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(std::result::Result<&str, &KiteError>) + Send + Sync + 'static,
+    {
+        self.hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Starts renewing the token of `client` in a background task
+    ///
+    /// Failed attempts are retried after the retry delay, except when the refresh
+    /// token itself is rejected, which ends the task. The task stops when the
+    /// returned handle is dropped.
+    pub fn spawn(mut self, client: &KiteConnect) -> RenewalHandle {
+        let client = client.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(until_next(ist::now(), self.renew_at)).await;
+                loop {
+                    match self.renew(&client).await {
+                        Ok(()) => break,
+                        Err(e) if e.is_token_error() => {
+                            log::error!("Refresh token rejected, stopping access token renewal: {}", e);
+                            return;
+                        }
+                        Err(_) => tokio::time::sleep(self.retry_delay).await,
+                    }
+                }
+            }
+        });
+        RenewalHandle { task }
+    }
+
+    /// Renews the token once, keeping a rotated refresh token for the next renewal
+    async fn renew(&mut self, client: &KiteConnect) -> std::result::Result<(), KiteError> {
+        let result = client.renew_access_token(&self.refresh_token, &self.api_secret).await;
+        match result {
+            Ok(jsn) => {
+                let data = match jsn.get("data") {
+                    Some(data @ JsonValue::Object(_)) => data,
+                    _ => &jsn,
+                };
+                if let Some(refresh_token) = data["refresh_token"].as_str().filter(|t| !t.is_empty()) {
+                    self.refresh_token = refresh_token.to_string();
+                }
+                log::info!("Access token renewed");
+                if let Some(hook) = &self.hook {
+                    hook(Ok(&client.access_token()));
+                }
+                Ok(())
+            }
+            Err(e) => {
+                log::warn!("Failed to renew access token: {}", e);
+                if let Some(hook) = &self.hook {
+                    hook(Err(&e));
+                }
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Handle of a background renewal task; dropping it stops the task
+#[derive(Debug)]
+pub struct RenewalHandle {
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl RenewalHandle {
+    /// Returns `true` once the task has ended, e.g. after the refresh token was rejected
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Stops the renewal task
+    pub fn stop(self) {}
+}
+
+impl Drop for RenewalHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Returns the time from `now` until the next occurrence of `at` (IST)
+fn until_next(now: Timestamp, at: NaiveTime) -> Duration {
+    let today = ist::from_naive(now.date_naive().and_time(at));
+    let next = if today > now {
+        today
+    } else {
+        ist::from_naive(
+            now.date_naive()
+                .checked_add_days(Days::new(1))
+                .expect("date in range")
+                .and_time(at),
+        )
+    };
+    (next - now).to_std().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Server;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_until_next() {
+        let at = NaiveTime::from_hms_opt(5, 30, 0).unwrap();
+        let now = ist::parse_timestamp("2024-03-01 05:00:00").unwrap();
+        assert_eq!(until_next(now, at), Duration::from_secs(30 * 60));
+        let now = ist::parse_timestamp("2024-03-01 05:30:00").unwrap();
+        assert_eq!(until_next(now, at), Duration::from_secs(24 * 3600));
+        let now = ist::parse_timestamp("2024-03-01 15:30:00").unwrap();
+        assert_eq!(until_next(now, at), Duration::from_secs(14 * 3600));
+    }
+
+    #[tokio::test]
+    async fn test_renew() {
+        let mut server = Server::new_async().await;
+        let _renewed = server
+            .mock("POST", "/session/refresh_token")
+            .match_body(mockito::Matcher::UrlEncoded("access_token".into(), "refresh_1".into()))
+            .with_body(r#"{"status":"success","data":{"access_token":"access_2","refresh_token":"refresh_2"}}"#)
+            .create_async()
+            .await;
+        let _rejected = server
+            .mock("POST", "/session/refresh_token")
+            .match_body(mockito::Matcher::UrlEncoded("access_token".into(), "refresh_2".into()))
+            .with_status(403)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"status":"error","message":"Invalid refresh token","error_type":"TokenException"}"#)
+            .create_async()
+            .await;
+
+        let client = KiteConnect::builder("API_KEY")
+            .access_token("access_1")
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        let mut renewal = TokenRenewal::new("refresh_1", "secret").on_renewal(move |result| {
+            recorded.lock().unwrap().push(result.map(str::to_string).map_err(|e| e.to_string()));
+        });
+
+        renewal.renew(&client).await.unwrap();
+        assert_eq!(client.access_token(), "access_2");
+        assert!(renewal.renew(&client).await.unwrap_err().is_token_error());
+        assert_eq!(client.access_token(), "access_2");
+
+        let events = events.lock().unwrap();
+        assert_eq!(events[0], Ok("access_2".to_string()));
+        assert!(events[1].is_err());
+    }
+}
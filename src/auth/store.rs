@@ -0,0 +1,200 @@
+//! Persistence of session tokens across restarts
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::KiteError;
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Mutex;
+
+/// Tokens of a session, as saved by a [`TokenStore`]
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredTokens {
+    /// Token authenticating API calls
+    pub access_token: String,
+    /// Token used to renew the access token, empty if the app has none
+    #[serde(default)]
+    pub refresh_token: String,
+    /// Kite user the tokens belong to
+    #[serde(default)]
+    pub user_id: String,
+}
+
+impl fmt::Debug for StoredTokens {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoredTokens")
+            .field("access_token", &"***")
+            .field("refresh_token", &"***")
+            .field("user_id", &self.user_id)
+            .finish()
+    }
+}
+
+/// Storage for session tokens, used by the client to survive restarts
+///
+/// A client built with a store loads the saved access token when no token is
+/// given to the builder, and saves new tokens after `generate_session` and
+/// `renew_access_token`.
+///
+/// ```rust,no_run
+/// use kiteconnect::auth::FileTokenStore;
+/// use kiteconnect::connect::KiteConnect;
+/// use std::sync::Arc;
+///
+/// # fn main() -> kiteconnect::error::Result<()> {
+/// let client = KiteConnect::builder("api_key")
+///     .token_store(Arc::new(FileTokenStore::new("/var/lib/bot/kite-session.json")))
+///     .build()?;
+/// if client.access_token().is_empty() {
+///     println!("Log in at {}", client.login_url());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub trait TokenStore: Send + Sync {
+    /// Returns the saved tokens, or `None` if nothing was saved
+    fn load(&self) -> Result<Option<StoredTokens>>;
+
+    /// Saves the tokens, replacing any saved earlier
+    fn save(&self, tokens: &StoredTokens) -> Result<()>;
+
+    /// Removes the saved tokens
+    fn clear(&self) -> Result<()>;
+}
+
+/// Token store keeping tokens in memory, e.g. to share them between clients
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    tokens: Mutex<Option<StoredTokens>>,
+}
+
+impl MemoryTokenStore {
+    /// Creates an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self) -> Result<Option<StoredTokens>> {
+        Ok(self.tokens.lock().unwrap_or_else(|e| e.into_inner()).clone())
+    }
+
+    fn save(&self, tokens: &StoredTokens) -> Result<()> {
+        *self.tokens.lock().unwrap_or_else(|e| e.into_inner()) = Some(tokens.clone());
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        *self.tokens.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        Ok(())
+    }
+}
+
+/// Token store saving tokens as JSON in a file
+///
+/// The file is replaced atomically and, on Unix, readable by its owner only.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Debug)]
+pub struct FileTokenStore {
+    path: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileTokenStore {
+    /// Creates a store backed by the file at `path`, which need not exist yet
+    pub fn new(path: impl Into<std::path::PathBuf>) -> Self {
+        FileTokenStore { path: path.into() }
+    }
+
+    /// Returns the path of the backing file
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    fn io_error(&self, action: &str, e: std::io::Error) -> KiteError {
+        KiteError::Other(format!("failed to {} token file {}: {}", action, self.path.display(), e))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TokenStore for FileTokenStore {
+    fn load(&self) -> Result<Option<StoredTokens>> {
+        match std::fs::read(&self.path) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(self.io_error("read", e)),
+        }
+    }
+
+    fn save(&self, tokens: &StoredTokens) -> Result<()> {
+        use std::io::Write;
+
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        let tmp = std::path::PathBuf::from(tmp);
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = options.open(&tmp).map_err(|e| self.io_error("write", e))?;
+        file.write_all(&serde_json::to_vec_pretty(tokens)?)
+            .and_then(|_| file.sync_all())
+            .map_err(|e| self.io_error("write", e))?;
+        std::fs::rename(&tmp, &self.path).map_err(|e| self.io_error("write", e))
+    }
+
+    fn clear(&self) -> Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(self.io_error("remove", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens() -> StoredTokens {
+        StoredTokens {
+            access_token: "access".to_string(),
+            refresh_token: "refresh".to_string(),
+            user_id: "AB1234".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_memory_token_store() {
+        let store = MemoryTokenStore::new();
+        assert_eq!(store.load().unwrap(), None);
+        store.save(&tokens()).unwrap();
+        assert_eq!(store.load().unwrap(), Some(tokens()));
+        store.clear().unwrap();
+        assert_eq!(store.load().unwrap(), None);
+        assert!(!format!("{:?}", tokens()).contains("\"access\""));
+    }
+
+    #[test]
+    fn test_file_token_store() {
+        let path = std::env::temp_dir().join(format!("kiteconnect-tokens-{}.json", std::process::id()));
+        let store = FileTokenStore::new(&path);
+        assert_eq!(store.load().unwrap(), None);
+        store.save(&tokens()).unwrap();
+        assert_eq!(FileTokenStore::new(&path).load().unwrap(), Some(tokens()));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        store.clear().unwrap();
+        assert!(!path.exists());
+        store.clear().unwrap();
+    }
+}
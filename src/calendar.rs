@@ -0,0 +1,475 @@
+//! # Market Hours
+//!
+//! Trading sessions and holidays of the exchanges Kite routes to, in IST, for
+//! telling whether orders can be placed now or have to wait for the next
+//! session:
+//!
+//! ```rust
+//! use kiteconnect::calendar;
+//! use kiteconnect::models::ist;
+//!
+//! let at = ist::parse_timestamp("2024-03-21 10:00:00").unwrap();
+//! assert!(calendar::is_market_open("NSE", at));
+//! // Holi
+//! assert!(!calendar::is_market_open("NSE", ist::parse_timestamp("2024-03-25 10:00:00").unwrap()));
+//!
+//! if let Some(session) = calendar::next_session("NFO") {
+//!     println!("NFO opens at {}", session.open);
+//! }
+//! ```
+//!
+//! Holidays of 2024 to 2026 are built in. Later years, or days the exchanges
+//! close at short notice, are added with [`add_holidays`]; [`set_holidays`]
+//! replaces the list of an exchange, e.g. with one loaded from a file. Days
+//! MCX trades only its evening session are not holidays here, nor are special
+//! sessions such as Muhurat trading modelled.
+//!
+//! With [`AfterHours`] set on the client, orders placed while their exchange
+//! is closed become after market orders or fail with
+//! [`KiteError::MarketClosed`](crate::error::KiteError::MarketClosed)
+//! instead of being rejected by the OMS. After market orders are only
+//! accepted within the [`amo_hours`] of the exchange; commodity orders are
+//! left to the OMS.
+
+use crate::models::{ist, Timestamp};
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, Weekday};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{OnceLock, RwLock};
+
+/// Exchanges following the equity holiday list
+const EQUITY_EXCHANGES: [&str; 6] = ["NSE", "BSE", "NFO", "BFO", "CDS", "BCD"];
+
+/// Exchanges following the commodity holiday list
+const COMMODITY_EXCHANGES: [&str; 2] = ["MCX", "NCO"];
+
+/// Weekdays the equity and derivative segments are closed
+const EQUITY_HOLIDAYS: &[(i32, u32, u32)] = &[
+    (2024, 1, 22),
+    (2024, 1, 26),
+    (2024, 3, 8),
+    (2024, 3, 25),
+    (2024, 3, 29),
+    (2024, 4, 11),
+    (2024, 4, 17),
+    (2024, 5, 1),
+    (2024, 5, 20),
+    (2024, 6, 17),
+    (2024, 7, 17),
+    (2024, 8, 15),
+    (2024, 10, 2),
+    (2024, 11, 1),
+    (2024, 11, 15),
+    (2024, 11, 20),
+    (2024, 12, 25),
+    (2025, 2, 26),
+    (2025, 3, 14),
+    (2025, 3, 31),
+    (2025, 4, 10),
+    (2025, 4, 14),
+    (2025, 4, 18),
+    (2025, 5, 1),
+    (2025, 8, 15),
+    (2025, 8, 27),
+    (2025, 10, 2),
+    (2025, 10, 21),
+    (2025, 10, 22),
+    (2025, 11, 5),
+    (2025, 12, 25),
+    (2026, 1, 26),
+    (2026, 3, 3),
+    (2026, 3, 26),
+    (2026, 3, 31),
+    (2026, 4, 3),
+    (2026, 4, 14),
+    (2026, 5, 1),
+    (2026, 5, 28),
+    (2026, 6, 26),
+    (2026, 9, 14),
+    (2026, 10, 2),
+    (2026, 10, 20),
+    (2026, 11, 10),
+    (2026, 11, 24),
+    (2026, 12, 25),
+];
+
+/// Weekdays both commodity sessions are closed
+const COMMODITY_HOLIDAYS: &[(i32, u32, u32)] = &[
+    (2024, 1, 26),
+    (2024, 3, 29),
+    (2024, 8, 15),
+    (2024, 10, 2),
+    (2024, 12, 25),
+    (2025, 4, 18),
+    (2025, 8, 15),
+    (2025, 10, 2),
+    (2025, 12, 25),
+    (2026, 1, 26),
+    (2026, 4, 3),
+    (2026, 10, 2),
+    (2026, 12, 25),
+];
+
+/// Days a session may be searched ahead for, longer than any run of holidays
+const MAX_CLOSED_DAYS: i64 = 30;
+
+/// What [`place_order`](crate::connect::KiteConnect::place_order) does with
+/// regular orders placed while their exchange is closed
+///
+/// See [`KiteConnect::set_after_hours`](crate::connect::KiteConnect::set_after_hours).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AfterHours {
+    /// Place them as after market orders (variety `amo`) for the next session,
+    /// and fail with [`KiteError::MarketClosed`](crate::error::KiteError::MarketClosed)
+    /// outside the [`amo_hours`] of the exchange
+    Amo,
+    /// Fail with [`KiteError::MarketClosed`](crate::error::KiteError::MarketClosed)
+    Reject,
+}
+
+/// A trading session of an exchange
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Session {
+    /// Start of the pre-open call auction, for exchanges holding one
+    pub pre_open: Option<Timestamp>,
+    /// Start of continuous trading
+    pub open: Timestamp,
+    /// End of continuous trading
+    pub close: Timestamp,
+}
+
+impl Session {
+    /// Returns `true` if continuous trading is on at `at`
+    pub fn is_open(&self, at: Timestamp) -> bool {
+        (self.open..self.close).contains(&at)
+    }
+
+    /// Returns `true` if the pre-open call auction is on at `at`
+    pub fn is_pre_open(&self, at: Timestamp) -> bool {
+        self.pre_open.is_some_and(|pre_open| (pre_open..self.open).contains(&at))
+    }
+}
+
+/// Opening and closing time of the regular session of an exchange on `date`,
+/// in IST
+///
+/// Commodities follow the US markets and close at 23:55 while US daylight
+/// saving time is on, from the second Sunday of March until the first Sunday
+/// of November, and at 23:30 otherwise.
+///
+/// ```rust
+/// use chrono::{NaiveDate, NaiveTime};
+/// use kiteconnect::calendar;
+///
+/// let close = |date| calendar::session_hours("MCX", date).unwrap().1;
+/// assert_eq!(close(NaiveDate::from_ymd_opt(2024, 1, 15).unwrap()), NaiveTime::from_hms_opt(23, 30, 0).unwrap());
+/// assert_eq!(close(NaiveDate::from_ymd_opt(2024, 7, 15).unwrap()), NaiveTime::from_hms_opt(23, 55, 0).unwrap());
+/// ```
+///
+/// Returns `None` for exchanges not known here.
+pub fn session_hours(exchange: &str, date: NaiveDate) -> Option<(NaiveTime, NaiveTime)> {
+    let hours = |open: (u32, u32), close: (u32, u32)| Some((time(open)?, time(close)?));
+    match exchange.to_uppercase().as_str() {
+        "NSE" | "BSE" | "NFO" | "BFO" => hours((9, 15), (15, 30)),
+        "CDS" | "BCD" => hours((9, 0), (17, 0)),
+        "MCX" | "NCO" if is_us_daylight_saving(date) => hours((9, 0), (23, 55)),
+        "MCX" | "NCO" => hours((9, 0), (23, 30)),
+        _ => None,
+    }
+}
+
+/// Start of the pre-open call auction of an exchange, in IST
+///
+/// Only the cash markets hold one, from 09:00 until the session opens at
+/// 09:15. Orders placed during it take part in the auction fixing the opening
+/// price.
+pub fn pre_open_time(exchange: &str) -> Option<NaiveTime> {
+    match exchange.to_uppercase().as_str() {
+        "NSE" | "BSE" => time((9, 0)),
+        _ => None,
+    }
+}
+
+/// Hours after market orders are accepted for an exchange, in IST
+///
+/// The window opens at the first time on the evening of a trading day and
+/// closes at the second on the morning of the next one, staying open over
+/// weekends and holidays. Between the close of the session and the opening of
+/// the window, and between its closing and the open, no orders are accepted.
+///
+/// Returns `None` for commodity exchanges, whose after market orders are not
+/// modelled here, and for exchanges not known here.
+pub fn amo_hours(exchange: &str) -> Option<(NaiveTime, NaiveTime)> {
+    let hours = |start: (u32, u32), end: (u32, u32)| Some((time(start)?, time(end)?));
+    match exchange.to_uppercase().as_str() {
+        "NSE" | "BSE" => hours((16, 0), (8, 59)),
+        "NFO" | "BFO" => hours((16, 0), (9, 10)),
+        "CDS" | "BCD" => hours((17, 30), (8, 59)),
+        _ => None,
+    }
+}
+
+/// Returns the holidays of `exchange`, in order
+pub fn holidays(exchange: &str) -> Vec<NaiveDate> {
+    let registry = registry().read().unwrap_or_else(|e| e.into_inner());
+    registry
+        .get(&exchange.to_uppercase())
+        .map(|dates| dates.iter().copied().collect())
+        .unwrap_or_default()
+}
+
+/// Adds holidays to the list of `exchange`
+///
+/// ```rust
+/// use chrono::NaiveDate;
+/// use kiteconnect::calendar;
+///
+/// let closed = NaiveDate::from_ymd_opt(2027, 1, 26).unwrap();
+/// calendar::add_holidays("NSE", [closed]);
+/// assert!(!calendar::is_trading_day("NSE", closed));
+/// ```
+pub fn add_holidays(exchange: &str, dates: impl IntoIterator<Item = NaiveDate>) {
+    let mut registry = registry().write().unwrap_or_else(|e| e.into_inner());
+    registry.entry(exchange.to_uppercase()).or_default().extend(dates);
+}
+
+/// Replaces the holiday list of `exchange`
+pub fn set_holidays(exchange: &str, dates: impl IntoIterator<Item = NaiveDate>) {
+    let mut registry = registry().write().unwrap_or_else(|e| e.into_inner());
+    registry.insert(exchange.to_uppercase(), dates.into_iter().collect());
+}
+
+/// Returns `true` if `exchange` trades on `date`, i.e. on weekdays that are
+/// not holidays
+pub fn is_trading_day(exchange: &str, date: NaiveDate) -> bool {
+    if matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+        return false;
+    }
+    let registry = registry().read().unwrap_or_else(|e| e.into_inner());
+    !registry
+        .get(&exchange.to_uppercase())
+        .is_some_and(|dates| dates.contains(&date))
+}
+
+/// Returns the session of `exchange` on `date`, `None` if it does not trade
+/// then or is not known here
+pub fn session_on(exchange: &str, date: NaiveDate) -> Option<Session> {
+    let (open, close) = session_hours(exchange, date)?;
+    if !is_trading_day(exchange, date) {
+        return None;
+    }
+    Some(Session {
+        pre_open: pre_open_time(exchange).map(|pre_open| ist::from_naive(date.and_time(pre_open))),
+        open: ist::from_naive(date.and_time(open)),
+        close: ist::from_naive(date.and_time(close)),
+    })
+}
+
+/// Returns `true` if the regular session of `exchange` is on at `at`
+///
+/// Exchanges not known here count as open, so orders for them are left to
+/// the OMS.
+pub fn is_market_open(exchange: &str, at: Timestamp) -> bool {
+    let date = at.with_timezone(&ist::offset()).date_naive();
+    if session_hours(exchange, date).is_none() {
+        return true;
+    }
+    session_on(exchange, date).is_some_and(|session| session.is_open(at))
+}
+
+/// Returns `true` if the pre-open call auction of `exchange` is on at `at`
+pub fn is_pre_open(exchange: &str, at: Timestamp) -> bool {
+    let date = at.with_timezone(&ist::offset()).date_naive();
+    session_on(exchange, date).is_some_and(|session| session.is_pre_open(at))
+}
+
+/// Returns `true` if after market orders for `exchange` are accepted at `at`
+///
+/// ```rust
+/// use kiteconnect::calendar;
+/// use kiteconnect::models::ist;
+///
+/// assert!(calendar::accepts_amo("NSE", ist::parse_timestamp("2024-03-21 18:00:00").unwrap()));
+/// // Between the close and the opening of the AMO window
+/// assert!(!calendar::accepts_amo("NSE", ist::parse_timestamp("2024-03-21 15:45:00").unwrap()));
+/// ```
+pub fn accepts_amo(exchange: &str, at: Timestamp) -> bool {
+    let (Some((start, end)), Some(next)) = (amo_hours(exchange), next_session_after(exchange, at)) else {
+        return false;
+    };
+    let date = |timestamp: Timestamp| timestamp.with_timezone(&ist::offset()).date_naive();
+    if at >= ist::from_naive(date(next.open).and_time(end)) {
+        return false;
+    }
+    // Closed for the day, but not yet taking orders for the next session
+    let today = date(at);
+    let opens = ist::from_naive(today.and_time(start));
+    !session_on(exchange, today).is_some_and(|session| (session.close..opens).contains(&at))
+}
+
+/// Returns the session of `exchange` in progress, or else the next to open
+///
+/// Returns `None` for exchanges not known here. See [`next_session_after`].
+pub fn next_session(exchange: &str) -> Option<Session> {
+    next_session_after(exchange, ist::now())
+}
+
+/// Returns the session of `exchange` in progress at `at`, or else the next
+/// to open after it
+///
+/// ```rust
+/// use kiteconnect::calendar;
+/// use kiteconnect::models::ist;
+///
+/// // After the close on the Thursday before Good Friday
+/// let at = ist::parse_timestamp("2024-03-28 16:00:00").unwrap();
+/// let session = calendar::next_session_after("NSE", at).unwrap();
+/// assert_eq!(session.open, ist::parse_timestamp("2024-04-01 09:15:00").unwrap());
+/// ```
+pub fn next_session_after(exchange: &str, at: Timestamp) -> Option<Session> {
+    let today = at.with_timezone(&ist::offset()).date_naive();
+    session_hours(exchange, today)?;
+    (0..=MAX_CLOSED_DAYS)
+        .filter_map(|days| session_on(exchange, today + Duration::days(days)))
+        .find(|session| session.close > at)
+}
+
+/// Returns `true` if US daylight saving time is on during the Indian trading
+/// day `date`
+///
+/// The clocks change early on Sundays in the US, before or after the trading
+/// days either side of the change.
+fn is_us_daylight_saving(date: NaiveDate) -> bool {
+    let sunday = |month, n| NaiveDate::from_weekday_of_month_opt(date.year(), month, Weekday::Sun, n);
+    match (sunday(3, 2), sunday(11, 1)) {
+        (Some(start), Some(end)) => (start..end).contains(&date),
+        _ => false,
+    }
+}
+
+fn time((hour, minute): (u32, u32)) -> Option<NaiveTime> {
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+/// Holiday lists by exchange, starting out with the built in ones
+fn registry() -> &'static RwLock<HashMap<String, BTreeSet<NaiveDate>>> {
+    static HOLIDAYS: OnceLock<RwLock<HashMap<String, BTreeSet<NaiveDate>>>> = OnceLock::new();
+    HOLIDAYS.get_or_init(|| {
+        let dates = |holidays: &[(i32, u32, u32)]| -> BTreeSet<NaiveDate> {
+            holidays
+                .iter()
+                .filter_map(|&(year, month, day)| NaiveDate::from_ymd_opt(year, month, day))
+                .collect()
+        };
+        let mut registry = HashMap::new();
+        for exchange in EQUITY_EXCHANGES {
+            registry.insert(exchange.to_string(), dates(EQUITY_HOLIDAYS));
+        }
+        for exchange in COMMODITY_EXCHANGES {
+            registry.insert(exchange.to_string(), dates(COMMODITY_HOLIDAYS));
+        }
+        RwLock::new(registry)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> Timestamp {
+        ist::parse_timestamp(value).unwrap()
+    }
+
+    #[test]
+    fn test_is_market_open() {
+        assert!(is_market_open("NSE", at("2024-03-21 09:15:00")));
+        assert!(is_market_open("nfo", at("2024-03-21 15:29:59")));
+        assert!(!is_market_open("NSE", at("2024-03-21 15:30:00")));
+        assert!(!is_market_open("NSE", at("2024-03-21 09:14:59")));
+        assert!(is_market_open("MCX", at("2024-03-21 21:00:00")));
+        assert!(!is_market_open("CDS", at("2024-03-21 17:30:00")));
+        // Weekends, in IST whatever the offset of the timestamp
+        assert!(!is_market_open("NSE", at("2024-03-23 10:00:00")));
+        assert!(!is_market_open("NSE", at("2024-03-22T23:00:00+00:00")));
+        assert!(is_market_open("NSE", at("2024-03-21T04:00:00+00:00")));
+        // Holidays, Holi for equities and Good Friday for both
+        assert!(!is_market_open("NFO", at("2024-03-25 10:00:00")));
+        assert!(is_market_open("MCX", at("2024-03-25 10:00:00")));
+        assert!(!is_market_open("MCX", at("2024-03-29 20:00:00")));
+        // Unknown exchanges are left to the OMS
+        assert!(is_market_open("XYZ", at("2024-03-23 03:00:00")));
+    }
+
+    #[test]
+    fn test_commodity_close() {
+        // US daylight saving time started on 2024-03-10 and ended on 2024-11-03
+        assert!(!is_market_open("MCX", at("2024-03-08 23:40:00")));
+        assert!(is_market_open("MCX", at("2024-03-11 23:40:00")));
+        assert!(!is_market_open("MCX", at("2024-03-11 23:55:00")));
+        assert!(is_market_open("NCO", at("2024-10-31 23:50:00")));
+        assert!(!is_market_open("NCO", at("2024-11-04 23:50:00")));
+
+        let session = next_session_after("MCX", at("2024-03-11 23:40:00")).unwrap();
+        assert_eq!(session.close, at("2024-03-11 23:55:00"));
+        let session = next_session_after("MCX", at("2024-03-08 23:40:00")).unwrap();
+        assert_eq!(session.open, at("2024-03-11 09:00:00"));
+    }
+
+    #[test]
+    fn test_pre_open() {
+        assert!(is_pre_open("NSE", at("2024-03-21 09:00:00")));
+        assert!(is_pre_open("bse", at("2024-03-21 09:14:59")));
+        assert!(!is_pre_open("NSE", at("2024-03-21 09:15:00")));
+        assert!(!is_pre_open("NSE", at("2024-03-21 08:59:59")));
+        assert!(!is_pre_open("NFO", at("2024-03-21 09:05:00")));
+        assert!(!is_pre_open("NSE", at("2024-03-23 09:05:00")));
+    }
+
+    #[test]
+    fn test_accepts_amo() {
+        assert!(accepts_amo("NSE", at("2024-03-21 16:00:00")));
+        assert!(accepts_amo("NSE", at("2024-03-22 08:58:59")));
+        assert!(!accepts_amo("NSE", at("2024-03-21 15:59:59")));
+        assert!(!accepts_amo("NSE", at("2024-03-22 08:59:00")));
+        assert!(!accepts_amo("NSE", at("2024-03-22 09:05:00")));
+        assert!(!accepts_amo("NSE", at("2024-03-22 10:00:00")));
+        assert!(accepts_amo("NFO", at("2024-03-22 09:05:00")));
+        // Over weekends and holidays
+        assert!(accepts_amo("NSE", at("2024-03-23 12:00:00")));
+        assert!(accepts_amo("NFO", at("2024-03-25 10:00:00")));
+        assert!(!accepts_amo("MCX", at("2024-03-23 12:00:00")));
+        assert!(!accepts_amo("XYZ", at("2024-03-23 12:00:00")));
+    }
+
+    #[test]
+    fn test_next_session() {
+        let session = next_session_after("NSE", at("2024-03-21 10:00:00")).unwrap();
+        assert_eq!(session.pre_open, Some(at("2024-03-21 09:00:00")));
+        assert_eq!(session.open, at("2024-03-21 09:15:00"));
+        assert_eq!(session.close, at("2024-03-21 15:30:00"));
+        assert!(session.is_open(at("2024-03-21 10:00:00")));
+
+        // Past Good Friday and the weekend
+        let session = next_session_after("NFO", at("2024-03-28 15:30:00")).unwrap();
+        assert_eq!(session.open, at("2024-04-01 09:15:00"));
+        assert_eq!(session.pre_open, None);
+        let session = next_session_after("MCX", at("2024-03-22 23:55:00")).unwrap();
+        assert_eq!(session.open, at("2024-03-25 09:00:00"));
+        assert!(next_session_after("XYZ", at("2024-03-21 10:00:00")).is_none());
+    }
+
+    #[test]
+    fn test_holidays() {
+        let date = |value: &str| value.parse::<NaiveDate>().unwrap();
+        assert!(holidays("NSE").contains(&date("2025-10-02")));
+        assert!(!is_trading_day("BSE", date("2025-10-02")));
+        assert!(is_trading_day("BSE", date("2025-10-03")));
+        assert!(!is_trading_day("XYZ", date("2025-10-04")));
+
+        add_holidays("bcd", [date("2030-01-01")]);
+        assert!(!is_trading_day("BCD", date("2030-01-01")));
+        assert!(is_trading_day("BCD", date("2030-01-02")));
+        set_holidays("BCD", [date("2030-01-02")]);
+        assert_eq!(holidays("BCD"), [date("2030-01-02")]);
+        assert!(is_trading_day("BCD", date("2030-01-01")));
+    }
+}
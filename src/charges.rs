@@ -0,0 +1,332 @@
+//! # Brokerage and Statutory Charges
+//!
+//! Computes the brokerage, STT, exchange transaction charges, SEBI fee, GST
+//! and stamp duty of an order locally, without the charges endpoint, e.g. to
+//! account for costs in a backtest. [`RateTable::default`] holds the rates
+//! Zerodha publishes for NSE, NFO, CDS and MCX; rates of other exchanges,
+//! brokerage plans or later revisions are set per [`Segment`] with
+//! [`RateTable::with`].
+//!
+//! ```rust
+//! use kiteconnect::charges::{RateTable, Segment};
+//! use kiteconnect::orders::OrderParams;
+//!
+//! let rates = RateTable::default();
+//! let order = OrderParams::buy("NSE", "INFY", 100).product("CNC").price("1000".parse().unwrap());
+//! let charges = rates.order_charges(&order).unwrap();
+//! assert_eq!(charges.stt.to_string(), "100");
+//! assert_eq!(charges.stamp_duty.to_string(), "15");
+//!
+//! // Same trade on a plan charging brokerage on delivery
+//! let delivery = rates.rates(Segment::EquityDelivery).brokerage(0.001, 20);
+//! let rates = rates.with(Segment::EquityDelivery, delivery);
+//! assert_eq!(rates.order_charges(&order).unwrap().brokerage.to_string(), "20");
+//! ```
+//!
+//! Every charge is rounded to the paisa. Exchanges and brokers may round STT
+//! and stamp duty differently on the contract note, so the figures are close
+//! estimates rather than the exact amounts debited.
+
+use crate::error::{KiteError, Result};
+use crate::instruments::DerivativeSymbol;
+use crate::models::Price;
+use crate::orders::OrderParams;
+use std::collections::HashMap;
+
+/// Segments with their own charges
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Segment {
+    /// Equity bought or sold for delivery, the `CNC` and `MTF` products
+    EquityDelivery,
+    /// Equity squared off the same day, the `MIS` product
+    EquityIntraday,
+    /// Equity and index futures
+    EquityFutures,
+    /// Equity and index options
+    EquityOptions,
+    /// Currency futures
+    CurrencyFutures,
+    /// Currency options
+    CurrencyOptions,
+    /// Commodity futures
+    CommodityFutures,
+    /// Commodity options
+    CommodityOptions,
+}
+
+impl Segment {
+    /// Returns the segment of an order from its exchange, product and
+    /// tradingsymbol, or `None` for an unknown exchange
+    ///
+    /// Derivatives are options if their tradingsymbol ends in `CE` or `PE`
+    /// and futures otherwise.
+    pub fn of(exchange: &str, product: Option<&str>, tradingsymbol: &str) -> Option<Segment> {
+        let option = DerivativeSymbol::parse(tradingsymbol)
+            .map(|symbol| symbol.option_type.is_some())
+            .unwrap_or_else(|| tradingsymbol.ends_with("CE") || tradingsymbol.ends_with("PE"));
+        let segment = match exchange {
+            "NSE" | "BSE" => match product {
+                Some("CNC" | "MTF") => Segment::EquityDelivery,
+                _ => Segment::EquityIntraday,
+            },
+            "NFO" | "BFO" if option => Segment::EquityOptions,
+            "NFO" | "BFO" => Segment::EquityFutures,
+            "CDS" | "BCD" if option => Segment::CurrencyOptions,
+            "CDS" | "BCD" => Segment::CurrencyFutures,
+            "MCX" | "NCO" if option => Segment::CommodityOptions,
+            "MCX" | "NCO" => Segment::CommodityFutures,
+            _ => return None,
+        };
+        Some(segment)
+    }
+}
+
+/// Rates of the charges of a segment, as fractions of the turnover
+///
+/// Options are charged on their premium, which is their turnover.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChargeRates {
+    /// Brokerage rate, capped at [`brokerage_max`](Self::brokerage_max); zero
+    /// for a flat brokerage of `brokerage_max`
+    pub brokerage_rate: Price,
+    /// Largest brokerage of an order, or the flat brokerage
+    pub brokerage_max: Price,
+    /// Securities or commodities transaction tax on buys
+    pub stt_buy: Price,
+    /// Securities or commodities transaction tax on sells
+    pub stt_sell: Price,
+    /// Transaction charges of the exchange
+    pub exchange_txn: Price,
+    /// Stamp duty on buys
+    pub stamp_duty: Price,
+}
+
+impl ChargeRates {
+    /// Sets the brokerage to `rate` of the turnover capped at `max`, or to a
+    /// flat `max` with a zero rate
+    pub fn brokerage(mut self, rate: f64, max: u32) -> Self {
+        self.brokerage_rate = rate_of(rate);
+        self.brokerage_max = Price::from(max);
+        self
+    }
+
+    /// Sets the transaction tax on buys and sells
+    pub fn stt(mut self, buy: f64, sell: f64) -> Self {
+        self.stt_buy = rate_of(buy);
+        self.stt_sell = rate_of(sell);
+        self
+    }
+
+    /// Sets the transaction charges of the exchange
+    pub fn exchange_txn(mut self, rate: f64) -> Self {
+        self.exchange_txn = rate_of(rate);
+        self
+    }
+
+    /// Sets the stamp duty on buys
+    pub fn stamp_duty(mut self, rate: f64) -> Self {
+        self.stamp_duty = rate_of(rate);
+        self
+    }
+}
+
+/// Charges of an order
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Charges {
+    /// Value of the order, price times quantity
+    pub turnover: Price,
+    /// Brokerage
+    pub brokerage: Price,
+    /// Securities or commodities transaction tax
+    pub stt: Price,
+    /// Transaction charges of the exchange
+    pub exchange_txn: Price,
+    /// SEBI turnover fee
+    pub sebi: Price,
+    /// GST on the brokerage, transaction charges and SEBI fee
+    pub gst: Price,
+    /// Stamp duty
+    pub stamp_duty: Price,
+}
+
+impl Charges {
+    /// Returns the sum of all charges
+    pub fn total(&self) -> Price {
+        round_paise(self.brokerage + self.stt + self.exchange_txn + self.sebi + self.gst + self.stamp_duty)
+    }
+}
+
+/// Charge rates by segment, with the SEBI fee and GST common to all of them
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateTable {
+    rates: HashMap<Segment, ChargeRates>,
+    /// SEBI turnover fee, ₹10 per crore by default
+    pub sebi_rate: Price,
+    /// GST on brokerage, transaction charges and SEBI fee, 18% by default
+    pub gst_rate: Price,
+}
+
+impl Default for RateTable {
+    /// Zerodha's rates for NSE, NFO, CDS and MCX
+    fn default() -> Self {
+        let rates = |brokerage: (f64, u32), stt: (f64, f64), exchange_txn: f64, stamp_duty: f64| {
+            ChargeRates::default()
+                .brokerage(brokerage.0, brokerage.1)
+                .stt(stt.0, stt.1)
+                .exchange_txn(exchange_txn)
+                .stamp_duty(stamp_duty)
+        };
+        RateTable {
+            rates: HashMap::from([
+                (Segment::EquityDelivery, rates((0.0, 0), (0.001, 0.001), 0.0000297, 0.00015)),
+                (Segment::EquityIntraday, rates((0.0003, 20), (0.0, 0.00025), 0.0000297, 0.00003)),
+                (Segment::EquityFutures, rates((0.0003, 20), (0.0, 0.0002), 0.0000173, 0.00002)),
+                (Segment::EquityOptions, rates((0.0, 20), (0.0, 0.001), 0.0003503, 0.00003)),
+                (Segment::CurrencyFutures, rates((0.0003, 20), (0.0, 0.0), 0.0000035, 0.000001)),
+                (Segment::CurrencyOptions, rates((0.0, 20), (0.0, 0.0), 0.000311, 0.000001)),
+                (Segment::CommodityFutures, rates((0.0003, 20), (0.0, 0.0001), 0.000021, 0.00002)),
+                (Segment::CommodityOptions, rates((0.0, 20), (0.0, 0.0005), 0.000418, 0.00003)),
+            ]),
+            sebi_rate: rate_of(0.000001),
+            gst_rate: rate_of(0.18),
+        }
+    }
+}
+
+impl RateTable {
+    /// Returns the rates of `segment`
+    pub fn rates(&self, segment: Segment) -> ChargeRates {
+        self.rates[&segment]
+    }
+
+    /// Sets the rates of `segment`
+    pub fn with(mut self, segment: Segment, rates: ChargeRates) -> Self {
+        self.rates.insert(segment, rates);
+        self
+    }
+
+    /// Computes the charges of buying or selling `quantity` at `price` in
+    /// `segment`
+    pub fn charges(&self, segment: Segment, buy: bool, quantity: u32, price: Price) -> Charges {
+        let rates = self.rates(segment);
+        let turnover = price * Price::from(quantity);
+        let brokerage = if rates.brokerage_rate > Price::default() {
+            (turnover * rates.brokerage_rate).min(rates.brokerage_max)
+        } else {
+            rates.brokerage_max
+        };
+        let stt = turnover * if buy { rates.stt_buy } else { rates.stt_sell };
+        let exchange_txn = turnover * rates.exchange_txn;
+        let sebi = turnover * self.sebi_rate;
+        let gst = (brokerage + exchange_txn + sebi) * self.gst_rate;
+        let stamp_duty = if buy { turnover * rates.stamp_duty } else { Price::default() };
+        Charges {
+            turnover,
+            brokerage: round_paise(brokerage),
+            stt: round_paise(stt),
+            exchange_txn: round_paise(exchange_txn),
+            sebi: round_paise(sebi),
+            gst: round_paise(gst),
+            stamp_duty: round_paise(stamp_duty),
+        }
+    }
+
+    /// Computes the charges of an order at its price
+    ///
+    /// The segment follows from the exchange, product and tradingsymbol of
+    /// the order. Fails for unknown exchanges and orders without a price; in a
+    /// backtest, set the simulated fill price of market orders as their price.
+    pub fn order_charges(&self, params: &OrderParams) -> Result<Charges> {
+        let segment = Segment::of(&params.exchange, params.product.as_deref(), &params.tradingsymbol)
+            .ok_or_else(|| KiteError::Other(format!("no charges known for exchange {}", params.exchange)))?;
+        let price = params
+            .price
+            .ok_or_else(|| KiteError::Other(format!("order of {} lacks a price to charge", params.tradingsymbol)))?;
+        let buy = params.transaction_type.eq_ignore_ascii_case("BUY");
+        Ok(self.charges(segment, buy, params.quantity, price))
+    }
+}
+
+/// Converts a rate into a price, exactly for decimal prices
+fn rate_of(rate: f64) -> Price {
+    rate.to_string().parse().unwrap_or_default()
+}
+
+#[cfg(not(feature = "rust_decimal"))]
+fn round_paise(amount: Price) -> Price {
+    (amount * 100.0).round() / 100.0
+}
+
+#[cfg(feature = "rust_decimal")]
+fn round_paise(amount: Price) -> Price {
+    amount.round_dp(2).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(value: &str) -> Price {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_segment_of() {
+        assert_eq!(Segment::of("NSE", Some("CNC"), "INFY"), Some(Segment::EquityDelivery));
+        assert_eq!(Segment::of("NSE", Some("MIS"), "INFY"), Some(Segment::EquityIntraday));
+        assert_eq!(Segment::of("NFO", Some("NRML"), "NIFTY24JUNFUT"), Some(Segment::EquityFutures));
+        assert_eq!(Segment::of("NFO", Some("NRML"), "NIFTY24JUN22000CE"), Some(Segment::EquityOptions));
+        assert_eq!(Segment::of("MCX", None, "GOLDM24JULFUT"), Some(Segment::CommodityFutures));
+        assert_eq!(Segment::of("CDS", None, "USDINR24JUN83.5PE"), Some(Segment::CurrencyOptions));
+        assert_eq!(Segment::of("NYSE", None, "IBM"), None);
+    }
+
+    #[test]
+    fn test_charges() {
+        let rates = RateTable::default();
+
+        let delivery = rates.charges(Segment::EquityDelivery, true, 100, price("1000"));
+        assert_eq!(delivery.turnover, price("100000"));
+        assert_eq!(delivery.brokerage, price("0"));
+        assert_eq!(delivery.stt, price("100"));
+        assert_eq!(delivery.exchange_txn, price("2.97"));
+        assert_eq!(delivery.sebi, price("0.1"));
+        assert_eq!(delivery.gst, price("0.55"));
+        assert_eq!(delivery.stamp_duty, price("15"));
+        assert_eq!(delivery.total(), price("118.62"));
+
+        // Brokerage is capped at ₹20 and sells pay no stamp duty
+        let intraday = rates.charges(Segment::EquityIntraday, false, 100, price("1000"));
+        assert_eq!(intraday.brokerage, price("20"));
+        assert_eq!(intraday.stt, price("25"));
+        assert_eq!(intraday.gst, price("4.15"));
+        assert_eq!(intraday.stamp_duty, price("0"));
+        assert_eq!(intraday.total(), price("52.22"));
+        let small = rates.charges(Segment::EquityIntraday, true, 10, price("500"));
+        assert_eq!(small.brokerage, price("1.5"));
+
+        // Options pay flat brokerage and STT on the premium of sells only
+        let option = rates.charges(Segment::EquityOptions, true, 75, price("120"));
+        assert_eq!(option.brokerage, price("20"));
+        assert_eq!(option.stt, price("0"));
+        assert_eq!(option.exchange_txn, price("3.15"));
+        assert_eq!(option.gst, price("4.17"));
+        assert_eq!(option.stamp_duty, price("0.27"));
+        assert_eq!(rates.charges(Segment::EquityOptions, false, 75, price("120")).stt, price("9"));
+    }
+
+    #[test]
+    fn test_order_charges() {
+        let rates = RateTable::default();
+        let order = OrderParams::sell("NFO", "NIFTY24JUN22000CE", 75).product("NRML").price(price("120"));
+        assert_eq!(
+            rates.order_charges(&order).unwrap(),
+            rates.charges(Segment::EquityOptions, false, 75, price("120"))
+        );
+        let flat = rates.clone().with(Segment::EquityOptions, rates.rates(Segment::EquityOptions).brokerage(0.0, 10));
+        assert_eq!(flat.order_charges(&order).unwrap().brokerage, price("10"));
+
+        assert!(rates.order_charges(&OrderParams::sell("NFO", "NIFTY24JUNFUT", 75)).is_err());
+        assert!(rates.order_charges(&OrderParams::buy("NYSE", "IBM", 1).price(price("1"))).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! # Configuration
+//!
+//! [`KiteConfig`] collects credentials and connection settings from the
+//! environment or a TOML file, for twelve-factor deployments and command line
+//! tools that should not hard-code them.
+//!
+//! | Environment variable          | TOML key               | Meaning                          |
+//! |-------------------------------|------------------------|----------------------------------|
+//! | `KITE_API_KEY`                | `api_key`              | API key (required)               |
+//! | `KITE_API_SECRET`             | `api_secret`           | API secret                       |
+//! | `KITE_ACCESS_TOKEN`           | `access_token`         | Access token of a live session   |
+//! | `KITE_BASE_URL`               | `base_url`             | Root URL of the REST API         |
+//! | `KITE_TIMEOUT_SECS`           | `timeout_secs`         | Timeout of each request attempt  |
+//! | `KITE_CONNECT_TIMEOUT_SECS`   | `connect_timeout_secs` | Timeout for opening connections  |
+//!
+//! Timeouts are given in seconds and may be fractional. Reading TOML files
+//! requires the `toml` feature.
+//!
+//! ```rust,no_run
+//! use kiteconnect::config::KiteConfig;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> kiteconnect::error::Result<()> {
+//! let config = KiteConfig::from_env()?;
+//! let client = config.build()?;
+//! if let Some(secret) = &config.api_secret {
+//!     // e.g. renew the session with the secret
+//! #   let _ = secret;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::connect::{KiteConnect, KiteConnectBuilder};
+use crate::error::{KiteError, Result};
+use serde::Deserialize;
+use std::fmt;
+use std::time::Duration;
+
+/// Credentials and connection settings of a client
+#[derive(Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KiteConfig {
+    /// API key of the Connect app
+    pub api_key: String,
+    /// API secret of the Connect app, needed to create or renew sessions
+    #[serde(default)]
+    pub api_secret: Option<String>,
+    /// Access token of an existing session
+    #[serde(default)]
+    pub access_token: Option<String>,
+    /// Root URL of the REST API, defaults to [`DEFAULT_BASE_URL`](crate::connect::DEFAULT_BASE_URL)
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Timeout of each request attempt, in seconds
+    #[serde(default)]
+    pub timeout_secs: Option<f64>,
+    /// Timeout for establishing connections, in seconds
+    #[serde(default)]
+    pub connect_timeout_secs: Option<f64>,
+}
+
+impl fmt::Debug for KiteConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KiteConfig")
+            .field("api_key", &self.api_key)
+            .field("api_secret", &self.api_secret.as_ref().map(|_| "***"))
+            .field("access_token", &self.access_token.as_ref().map(|_| "***"))
+            .field("base_url", &self.base_url)
+            .field("timeout_secs", &self.timeout_secs)
+            .field("connect_timeout_secs", &self.connect_timeout_secs)
+            .finish()
+    }
+}
+
+impl KiteConfig {
+    /// Reads the configuration from `KITE_*` environment variables
+    ///
+    /// Returns an error if `KITE_API_KEY` is missing or a timeout is not a number.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let var = |name: &str| var(name).filter(|value| !value.trim().is_empty());
+        let secs = |name: &str| {
+            var(name)
+                .map(|value| {
+                    value.trim().parse::<f64>().map_err(|_| {
+                        KiteError::Other(format!("{} must be a number of seconds, got {:?}", name, value))
+                    })
+                })
+                .transpose()
+        };
+
+        let config = KiteConfig {
+            api_key: var("KITE_API_KEY")
+                .ok_or_else(|| KiteError::Other("KITE_API_KEY is not set".to_string()))?,
+            api_secret: var("KITE_API_SECRET"),
+            access_token: var("KITE_ACCESS_TOKEN"),
+            base_url: var("KITE_BASE_URL"),
+            timeout_secs: secs("KITE_TIMEOUT_SECS")?,
+            connect_timeout_secs: secs("KITE_CONNECT_TIMEOUT_SECS")?,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parses the configuration from a TOML document
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(document: &str) -> Result<Self> {
+        let config: KiteConfig = toml::from_str(document)
+            .map_err(|e| KiteError::Other(format!("invalid configuration: {}", e)))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Reads the configuration from a TOML file
+    #[cfg(all(feature = "toml", not(target_arch = "wasm32")))]
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let document = std::fs::read_to_string(path).map_err(|e| {
+            KiteError::Other(format!("failed to read configuration {}: {}", path.display(), e))
+        })?;
+        Self::from_toml_str(&document)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.api_key.trim().is_empty() {
+            return Err(KiteError::Other("api_key must not be empty".to_string()));
+        }
+        for (name, secs) in [("timeout_secs", self.timeout_secs), ("connect_timeout_secs", self.connect_timeout_secs)] {
+            if let Some(secs) = secs {
+                Duration::try_from_secs_f64(secs)
+                    .map_err(|_| KiteError::Other(format!("{} must be a positive number, got {}", name, secs)))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a builder configured with these settings
+    pub fn builder(&self) -> KiteConnectBuilder {
+        let mut builder = KiteConnect::builder(&self.api_key);
+        if let Some(access_token) = &self.access_token {
+            builder = builder.access_token(access_token);
+        }
+        if let Some(base_url) = &self.base_url {
+            builder = builder.base_url(base_url);
+        }
+        if let Some(secs) = self.timeout_secs {
+            builder = builder.timeout(Duration::from_secs_f64(secs));
+        }
+        if let Some(secs) = self.connect_timeout_secs {
+            builder = builder.connect_timeout(Duration::from_secs_f64(secs));
+        }
+        builder
+    }
+
+    /// Builds a client with these settings
+    pub fn build(&self) -> Result<KiteConnect> {
+        self.builder().build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_config_from_vars() {
+        let vars = HashMap::from([
+            ("KITE_API_KEY", "key"),
+            ("KITE_API_SECRET", "secret"),
+            ("KITE_ACCESS_TOKEN", ""),
+            ("KITE_BASE_URL", "http://127.0.0.1:8080/"),
+            ("KITE_TIMEOUT_SECS", "2.5"),
+        ]);
+        let config = KiteConfig::from_vars(|name| vars.get(name).map(|v| v.to_string())).unwrap();
+        assert_eq!(config.api_key, "key");
+        assert_eq!(config.api_secret.as_deref(), Some("secret"));
+        assert_eq!(config.access_token, None);
+        assert_eq!(config.timeout_secs, Some(2.5));
+        assert!(!format!("{:?}", config).contains("\"secret\""));
+
+        let client = config.build().unwrap();
+        assert_eq!(client.api_key(), "key");
+        assert_eq!(client.base_url(), "http://127.0.0.1:8080");
+
+        assert!(KiteConfig::from_vars(|_| None).is_err());
+        let bad_timeout = |name: &str| match name {
+            "KITE_API_KEY" => Some("key".to_string()),
+            "KITE_TIMEOUT_SECS" => Some("soon".to_string()),
+            _ => None,
+        };
+        assert!(KiteConfig::from_vars(bad_timeout).is_err());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_config_from_toml() {
+        let config = KiteConfig::from_toml_str(
+            r#"
+            api_key = "key"
+            access_token = "token"
+            connect_timeout_secs = 5
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.access_token.as_deref(), Some("token"));
+        assert_eq!(config.connect_timeout_secs, Some(5.0));
+        assert_eq!(config.build().unwrap().access_token(), "token");
+
+        assert!(KiteConfig::from_toml_str("api_key = \"key\"\ntimeout = 5").is_err());
+        assert!(KiteConfig::from_toml_str("access_token = \"token\"").is_err());
+    }
+}
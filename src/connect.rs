@@ -45,14 +45,43 @@
 //! # }
 //! ```
 
+use futures::future::BoxFuture;
+#[cfg(not(target_arch = "wasm32"))]
+use futures::{Stream, TryStreamExt};
+use serde::de::DeserializeOwned;
 use serde_json::Value as JsonValue;
-use anyhow::{anyhow, Context, Result};
 use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use reqwest::header::{HeaderMap, AUTHORIZATION, USER_AGENT};
 
+use crate::auth::{StoredTokens, TokenStore};
+use crate::calendar::{self, AfterHours};
+use crate::error::{KiteError, Result};
+use crate::instruments::{
+    validate_order_price, validate_order_quantity, AutoSlice, DerivativeSymbol, FreezeLimits, InstrumentFilter,
+    InstrumentStore,
+};
+use crate::interceptor::Interceptor;
+use crate::metrics::{self, MetricsSink, RequestMetrics};
+use crate::options::RequestOptions;
+use crate::orders::{DedupeKey, OrderDedupe};
+use crate::proxy::ProxyConfig;
+use crate::ratelimit::{EndpointCategory, RateLimiter, RateLimits};
+use crate::readonly::{self, ReadOnlyMode};
+use crate::redact;
+use crate::retry::{self, RetryPolicy};
+use crate::models::{
+    ist, Exchange, Holding, Instrument, InstrumentToken, KiteModel, MfInstrument, MfOrder, Order, OrderMargin, Positions,
+    Price, Profile, SegmentMargin, Timestamp, Trade, TriggerRange, UserMargins, UserSession,
+};
+
 // Conditional imports for different targets
 #[cfg(not(target_arch = "wasm32"))]
-use {csv::ReaderBuilder, sha2::{Sha256, Digest}};
+use sha2::{Sha256, Digest};
 
 #[cfg(target_arch = "wasm32")]
 use {
@@ -61,11 +90,8 @@ use {
     web_sys::window,
 };
 
-#[cfg(not(test))]
-const URL: &str = "https://api.kite.trade";
-
-#[cfg(test)]
-const URL: &str = "http://127.0.0.1:1234";
+/// Root URL of the KiteConnect REST API
+pub const DEFAULT_BASE_URL: &str = "https://api.kite.trade";
 
 /// Async trait for handling HTTP requests across different platforms
 trait RequestHandler {
@@ -77,6 +103,19 @@ trait RequestHandler {
     ) -> Result<reqwest::Response>;
 }
 
+/// Payload of a request
+enum RequestBody<'a> {
+    /// Form fields, if any
+    Form(Option<HashMap<&'a str, &'a str>>),
+    /// A JSON document, for the endpoints taking one
+    Json(&'a JsonValue),
+}
+
+/// Async callback invoked when an API call fails with a `TokenException`
+///
+/// See [`KiteConnect::set_async_session_expiry_hook`].
+pub type SessionExpiryHook = Arc<dyn Fn() -> BoxFuture<'static, ()> + Send + Sync>;
+
 /// Main client for interacting with the KiteConnect API
 /// 
 /// This struct provides async methods for all KiteConnect REST API endpoints.
@@ -110,6 +149,12 @@ trait RequestHandler {
 /// 
 /// ## Cloning for Concurrent Use
 /// 
+/// Clones share one access token: a token set with
+/// [`set_access_token`](Self::set_access_token), by
+/// [`generate_session`](Self::generate_session) or by a renewal on any clone
+/// is used by all of them. Clients for different accounts are built
+/// separately rather than cloned.
+/// 
 /// ```rust,no_run
 /// use kiteconnect::connect::KiteConnect;
 /// 
@@ -129,33 +174,552 @@ trait RequestHandler {
 /// # Ok(())
 /// # }
 /// ```
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct KiteConnect {
     /// API key for authentication
     api_key: String,
-    /// Access token for authenticated requests
-    access_token: String,
+    /// Root URL of the REST API, without a trailing slash
+    base_url: String,
+    /// Access token for authenticated requests (shared between clones)
+    access_token: Arc<RwLock<SessionToken>>,
     /// Optional callback for session expiry handling
-    session_expiry_hook: Option<fn() -> ()>,
+    session_expiry_hook: Option<SessionExpiryHook>,
+    /// Whether responses are unwrapped from the `{ "status", "data" }` envelope
+    unwrap_envelope: bool,
+    /// Whether typed responses with unknown fields are rejected
+    strict_mode: bool,
+    /// Retry policy applied to idempotent requests
+    retry_policy: RetryPolicy,
+    /// Optional client-side rate limiter (shared between clones)
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Overrides applied to every request made by this instance
+    options: RequestOptions,
+    /// Proxy used for REST requests, for handing on to the ticker
+    proxy: Option<ProxyConfig>,
+    /// Default timeout of each request attempt
+    timeout: Option<Duration>,
+    /// Optional receiver of per-request metrics
+    metrics: Option<Arc<dyn MetricsSink>>,
+    /// Hooks run around every HTTP request, in registration order
+    interceptors: Arc<[Arc<dyn Interceptor>]>,
+    /// Optional storage the session tokens are saved to
+    token_store: Option<Arc<dyn TokenStore>>,
+    /// Instruments orders are checked against before they are placed
+    order_checks: Option<Arc<InstrumentStore>>,
+    /// Freeze quantities sliced orders are split by
+    freeze_limits: Arc<FreezeLimits>,
+    /// Slicing applied to every order placed, if enabled
+    auto_slice: Option<AutoSlice>,
+    /// Orders placed within the dedupe window, if enabled (shared between clones)
+    dedupe: Option<Arc<OrderDedupe>>,
+    /// What happens to requests that would trade, if they are not sent
+    read_only: Option<ReadOnlyMode>,
+    /// Whether requests that would trade are blocked (shared between clones)
+    kill_switch: Arc<AtomicBool>,
+    /// What happens to regular orders placed while their exchange is closed
+    after_hours: Option<AfterHours>,
+    /// Whether requests and responses are logged in full (with secrets masked)
+    debug: bool,
     /// HTTP client for making requests (shared and reusable)
     client: reqwest::Client,
 }
 
+/// Access token along with the time Kite is expected to invalidate it
+#[derive(Debug)]
+struct SessionToken {
+    value: String,
+    expires_at: Option<Timestamp>,
+    /// Whether the session expiry hook already ran for this token
+    expiry_notified: bool,
+}
+
+impl SessionToken {
+    fn new(value: &str) -> Self {
+        SessionToken {
+            value: value.to_string(),
+            expires_at: (!value.is_empty()).then(|| next_token_expiry(ist::now())),
+            expiry_notified: false,
+        }
+    }
+
+    fn is_stale(&self, now: Timestamp) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    /// Flags the token as expired after the API rejected it
+    fn mark_stale(&mut self, now: Timestamp) {
+        if !self.is_stale(now) {
+            self.expires_at = Some(now);
+        }
+        self.expiry_notified = true;
+    }
+}
+
+/// Returns the first 6 AM IST after `now`, when Kite invalidates access tokens
+fn next_token_expiry(now: Timestamp) -> Timestamp {
+    let six = chrono::NaiveTime::from_hms_opt(6, 0, 0).expect("valid time");
+    let today = ist::from_naive(now.date_naive().and_time(six));
+    if today > now {
+        today
+    } else {
+        today + chrono::Duration::days(1)
+    }
+}
+
+impl fmt::Debug for KiteConnect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KiteConnect")
+            .field("api_key", &self.api_key)
+            .field("base_url", &self.base_url)
+            .field("access_token", &(!self.access_token().is_empty()).then_some(redact::MASK))
+            .field("session_expiry_hook", &self.session_expiry_hook.is_some())
+            .field("unwrap_envelope", &self.unwrap_envelope)
+            .field("strict_mode", &self.strict_mode)
+            .field("retry_policy", &self.retry_policy)
+            .field("rate_limits", &self.rate_limiter.as_ref().map(|l| l.limits()))
+            .field("options", &self.options)
+            .field("proxy", &self.proxy)
+            .field("timeout", &self.timeout)
+            .field("metrics", &self.metrics.is_some())
+            .field("interceptors", &self.interceptors.len())
+            .field("token_store", &self.token_store.is_some())
+            .field("order_checks", &self.order_checks.is_some())
+            .field("freeze_limits", &self.freeze_limits)
+            .field("auto_slice", &self.auto_slice)
+            .field("dedupe_window", &self.dedupe_window())
+            .field("read_only", &self.read_only)
+            .field("kill_switch", &self.is_kill_switch_active())
+            .field("after_hours", &self.after_hours)
+            .field("debug", &self.debug)
+            .field("client", &self.client)
+            .finish()
+    }
+}
+
 impl Default for KiteConnect {
     fn default() -> Self {
         KiteConnect {
             api_key: "<API-KEY>".to_string(),
-            access_token: "<ACCESS-TOKEN>".to_string(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            access_token: Arc::new(RwLock::new(SessionToken::new("<ACCESS-TOKEN>"))),
             session_expiry_hook: None,
+            unwrap_envelope: false,
+            strict_mode: false,
+            retry_policy: RetryPolicy::none(),
+            rate_limiter: None,
+            options: RequestOptions::default(),
+            proxy: None,
+            timeout: None,
+            metrics: None,
+            interceptors: Arc::new([]),
+            token_store: None,
+            order_checks: None,
+            freeze_limits: Default::default(),
+            auto_slice: None,
+            dedupe: None,
+            read_only: None,
+            kill_switch: Arc::new(AtomicBool::new(false)),
+            after_hours: None,
+            debug: false,
             client: reqwest::Client::new(),
         }
     }
 }
 
+/// Builder for [`KiteConnect`] clients with non-default configuration
+///
+/// # Example
+///
+/// ```rust
+/// use kiteconnect::connect::KiteConnect;
+/// use kiteconnect::ratelimit::RateLimits;
+/// use kiteconnect::retry::RetryPolicy;
+///
+/// # fn main() -> kiteconnect::error::Result<()> {
+/// let client = KiteConnect::builder("api_key")
+///     .access_token("access_token")
+///     .unwrap_envelope(true)
+///     .retry_policy(RetryPolicy::default())
+///     .rate_limits(RateLimits::default())
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct KiteConnectBuilder {
+    api_key: String,
+    base_url: String,
+    access_token: String,
+    unwrap_envelope: bool,
+    strict_mode: bool,
+    retry_policy: RetryPolicy,
+    rate_limits: Option<RateLimits>,
+    proxy: Option<ProxyConfig>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    http2_prior_knowledge: bool,
+    tcp_keepalive: Option<Duration>,
+    http2_keep_alive_interval: Option<Duration>,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+    token_store: Option<Arc<dyn TokenStore>>,
+    order_checks: Option<Arc<InstrumentStore>>,
+    freeze_limits: Arc<FreezeLimits>,
+    auto_slice: Option<AutoSlice>,
+    dedupe_window: Option<Duration>,
+    read_only: Option<ReadOnlyMode>,
+    after_hours: Option<AfterHours>,
+    debug: bool,
+}
+
+impl fmt::Debug for KiteConnectBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KiteConnectBuilder")
+            .field("api_key", &self.api_key)
+            .field("base_url", &self.base_url)
+            .field("unwrap_envelope", &self.unwrap_envelope)
+            .field("strict_mode", &self.strict_mode)
+            .field("retry_policy", &self.retry_policy)
+            .field("rate_limits", &self.rate_limits)
+            .field("proxy", &self.proxy)
+            .field("timeout", &self.timeout)
+            .field("metrics", &self.metrics.is_some())
+            .field("interceptors", &self.interceptors.len())
+            .field("token_store", &self.token_store.is_some())
+            .field("order_checks", &self.order_checks.is_some())
+            .field("freeze_limits", &self.freeze_limits)
+            .field("auto_slice", &self.auto_slice)
+            .field("dedupe_window", &self.dedupe_window)
+            .field("read_only", &self.read_only)
+            .field("after_hours", &self.after_hours)
+            .field("debug", &self.debug)
+            .finish_non_exhaustive()
+    }
+}
+
+impl KiteConnectBuilder {
+    /// Creates a builder for a client using the given API key
+    pub fn new(api_key: &str) -> Self {
+        KiteConnectBuilder {
+            api_key: api_key.to_string(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            access_token: String::new(),
+            unwrap_envelope: false,
+            strict_mode: false,
+            retry_policy: RetryPolicy::none(),
+            rate_limits: None,
+            proxy: None,
+            timeout: None,
+            connect_timeout: None,
+            pool_idle_timeout: None,
+            pool_max_idle_per_host: None,
+            http2_prior_knowledge: false,
+            tcp_keepalive: None,
+            http2_keep_alive_interval: None,
+            metrics: None,
+            interceptors: Vec::new(),
+            token_store: None,
+            order_checks: None,
+            freeze_limits: Default::default(),
+            auto_slice: None,
+            dedupe_window: None,
+            read_only: None,
+            after_hours: None,
+            debug: false,
+        }
+    }
+
+    /// Sets the root URL of the REST API, e.g. to point the client at a mock
+    /// server or a reverse proxy
+    ///
+    /// Defaults to [`DEFAULT_BASE_URL`].
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    /// Sets the access token, if one is already available
+    pub fn access_token(mut self, access_token: &str) -> Self {
+        self.access_token = access_token.to_string();
+        self
+    }
+
+    /// Enables or disables unwrapping of the response envelope
+    pub fn unwrap_envelope(mut self, enabled: bool) -> Self {
+        self.unwrap_envelope = enabled;
+        self
+    }
+
+    /// Enables or disables strict mode for typed responses
+    pub fn strict_mode(mut self, enabled: bool) -> Self {
+        self.strict_mode = enabled;
+        self
+    }
+
+    /// Sets the retry policy for idempotent requests
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Enables client-side rate limiting with the given per-category limits
+    pub fn rate_limits(mut self, limits: RateLimits) -> Self {
+        self.rate_limits = Some(limits);
+        self
+    }
+
+    /// Routes REST requests through a proxy
+    ///
+    /// The ticker is given the same proxy with
+    /// [`TickerBuilder::proxy`](crate::ticker::TickerBuilder::proxy).
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Sets the total timeout of each request attempt, from connecting until the
+    /// response body has been read
+    ///
+    /// [`RequestOptions::timeout`] overrides this for individual calls.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for establishing a connection (native only)
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how long idle connections are kept in the pool (native only)
+    ///
+    /// Defaults to 90 seconds.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the maximum number of idle connections kept per host (native only)
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Forces HTTP/2 without negotiating it first (native only)
+    ///
+    /// Saves a round trip on new connections to servers known to speak HTTP/2.
+    pub fn http2_prior_knowledge(mut self) -> Self {
+        self.http2_prior_knowledge = true;
+        self
+    }
+
+    /// Enables TCP keepalive probes on idle connections (native only)
+    ///
+    /// Keeps pooled connections from being dropped by NATs and firewalls, so
+    /// the first order after a quiet period doesn't pay for a new handshake.
+    pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Sends HTTP/2 pings on idle connections at the given interval (native only)
+    pub fn http2_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.http2_keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Reports timing, status and retry count of every request to the given sink
+    pub fn metrics_sink(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.metrics = Some(sink);
+        self
+    }
+
+    /// Registers an interceptor; interceptors run in registration order
+    pub fn interceptor(mut self, interceptor: Arc<dyn Interceptor>) -> Self {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    /// Persists session tokens in the given store
+    ///
+    /// Unless an access token is passed to the builder, [`build`](Self::build)
+    /// loads the saved one. See [`KiteConnect::set_token_store`].
+    pub fn token_store(mut self, store: Arc<dyn TokenStore>) -> Self {
+        self.token_store = Some(store);
+        self
+    }
+
+    /// Checks orders against the tick and lot sizes of these instruments
+    /// before placing them
+    ///
+    /// See [`KiteConnect::set_order_checks`].
+    pub fn order_checks(mut self, instruments: Arc<InstrumentStore>) -> Self {
+        self.order_checks = Some(instruments);
+        self
+    }
+
+    /// Sets the freeze quantities orders are sliced by
+    ///
+    /// See [`KiteConnect::set_freeze_limits`].
+    pub fn freeze_limits(mut self, limits: FreezeLimits) -> Self {
+        self.freeze_limits = Arc::new(limits);
+        self
+    }
+
+    /// Slices every order placed
+    ///
+    /// See [`KiteConnect::set_auto_slice`].
+    pub fn auto_slice(mut self, auto_slice: AutoSlice) -> Self {
+        self.auto_slice = Some(auto_slice);
+        self
+    }
+
+    /// Ignores repeats of orders placed within `window`
+    ///
+    /// See [`KiteConnect::set_dedupe_window`].
+    pub fn dedupe_window(mut self, window: Duration) -> Self {
+        self.dedupe_window = Some(window);
+        self
+    }
+
+    /// Keeps the client from trading
+    ///
+    /// See [`KiteConnect::set_read_only`].
+    pub fn read_only(mut self, mode: ReadOnlyMode) -> Self {
+        self.read_only = Some(mode);
+        self
+    }
+
+    /// Places orders as AMO, or rejects them, while their exchange is closed
+    ///
+    /// See [`KiteConnect::set_after_hours`].
+    pub fn after_hours(mut self, after_hours: AfterHours) -> Self {
+        self.after_hours = Some(after_hours);
+        self
+    }
+
+    /// Enables debug logging of full requests and responses
+    ///
+    /// See [`KiteConnect::set_debug`].
+    pub fn debug(mut self, enabled: bool) -> Self {
+        self.debug = enabled;
+        self
+    }
+
+    /// Builds the client
+    ///
+    /// Returns an error if the base URL is not a valid absolute URL, or if the
+    /// proxy configuration is invalid, or if the token store fails to load.
+    pub fn build(self) -> Result<KiteConnect> {
+        let base_url = self.base_url.trim_end_matches('/').to_string();
+        reqwest::Url::parse(&base_url)
+            .map_err(|e| KiteError::Other(format!("invalid base URL {:?}: {}", self.base_url, e)))?;
+        let client = self.build_http_client()?;
+
+        let mut access_token = self.access_token;
+        if let (true, Some(store)) = (access_token.is_empty(), &self.token_store) {
+            if let Some(tokens) = store.load()? {
+                access_token = tokens.access_token;
+            }
+        }
+
+        Ok(KiteConnect {
+            api_key: self.api_key,
+            base_url,
+            access_token: Arc::new(RwLock::new(SessionToken::new(&access_token))),
+            session_expiry_hook: None,
+            unwrap_envelope: self.unwrap_envelope,
+            strict_mode: self.strict_mode,
+            retry_policy: self.retry_policy,
+            rate_limiter: self.rate_limits.map(|limits| Arc::new(RateLimiter::new(limits))),
+            options: RequestOptions::default(),
+            proxy: self.proxy,
+            timeout: self.timeout,
+            metrics: self.metrics,
+            interceptors: self.interceptors.into(),
+            token_store: self.token_store,
+            order_checks: self.order_checks,
+            freeze_limits: self.freeze_limits,
+            auto_slice: self.auto_slice,
+            dedupe: self.dedupe_window.map(|window| Arc::new(OrderDedupe::new(window))),
+            read_only: self.read_only,
+            kill_switch: Arc::new(AtomicBool::new(false)),
+            after_hours: self.after_hours,
+            debug: self.debug,
+            client,
+        })
+    }
+
+    /// Creates the HTTP client shared by all clones of the built client
+    #[cfg(not(target_arch = "wasm32"))]
+    fn build_http_client(&self) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy.to_reqwest()?);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        if let Some(interval) = self.tcp_keepalive {
+            builder = builder.tcp_keepalive(interval);
+        }
+        if let Some(interval) = self.http2_keep_alive_interval {
+            builder = builder
+                .http2_keep_alive_interval(interval)
+                .http2_keep_alive_while_idle(true);
+        }
+        Ok(builder.build()?)
+    }
+
+    /// Creates the HTTP client shared by all clones of the built client
+    #[cfg(target_arch = "wasm32")]
+    fn build_http_client(&self) -> Result<reqwest::Client> {
+        // Browsers apply their own proxy settings to fetch requests
+        if let Some(proxy) = &self.proxy {
+            proxy.validate()?;
+        }
+        Ok(reqwest::Client::new())
+    }
+}
+
+/// Returns the `Content-Type` header of a response
+fn content_type(resp: &reqwest::Response) -> Option<String> {
+    resp.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Checks the `status` field of a Kite response envelope and returns its `data` payload
+///
+/// Responses with `"status": "error"` are converted into a [`KiteError::Api`] carrying
+/// the `error_type` and `message` fields sent by the API.
+pub(crate) fn extract_data(jsn: JsonValue) -> Result<JsonValue> {
+    match jsn["status"].as_str() {
+        Some("error") => Err(KiteError::from_envelope(200, &jsn)),
+        _ => match jsn {
+            JsonValue::Object(mut obj) => Ok(obj.remove("data").unwrap_or(JsonValue::Null)),
+            other => Ok(other),
+        },
+    }
+}
+
 impl KiteConnect {
     /// Constructs url for the given path and query params
     pub(crate) fn build_url(&self, path: &str, param: Option<Vec<(&str, &str)>>) -> reqwest::Url {
-        let url: &str = &format!("{}/{}", URL, &path[1..]);
+        let url: &str = &format!("{}/{}", self.base_url, &path[1..]);
         let mut url = reqwest::Url::parse(url).unwrap();
 
         if let Some(data) = param {
@@ -185,550 +749,893 @@ impl KiteConnect {
     pub fn new(api_key: &str, access_token: &str) -> Self {
         Self {
             api_key: api_key.to_string(),
-            access_token: access_token.to_string(),
+            access_token: Arc::new(RwLock::new(SessionToken::new(access_token))),
             client: reqwest::Client::new(),
             ..Default::default()
         }
     }
 
+    /// Returns a [`KiteConnectBuilder`] for configuring retries, rate limits and more
+    pub fn builder(api_key: &str) -> KiteConnectBuilder {
+        KiteConnectBuilder::new(api_key)
+    }
+
+    /// Creates a client from `KITE_*` environment variables
+    ///
+    /// See [`KiteConfig`](crate::config::KiteConfig) for the variables read. Use
+    /// `KiteConfig::from_env` directly to also get the API secret.
+    pub fn from_env() -> Result<Self> {
+        crate::config::KiteConfig::from_env()?.build()
+    }
+
+    /// Creates a client from a TOML configuration file
+    ///
+    /// See [`KiteConfig`](crate::config::KiteConfig) for the keys read.
+    #[cfg(all(feature = "toml", not(target_arch = "wasm32")))]
+    pub fn from_config_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        crate::config::KiteConfig::from_file(path)?.build()
+    }
+
     /// Helper method to raise or return json response for async responses
     async fn raise_or_return_json(&self, resp: reqwest::Response) -> Result<JsonValue> {
-        if resp.status().is_success() {
-            let jsn: JsonValue = resp.json().await.with_context(|| "Serialization failed")?;
-            Ok(jsn)
-        } else {
-            let error_text = resp.text().await?;
-            Err(anyhow!(error_text))
+        self.parse_response(resp, self.unwrap_envelope).await
+    }
+
+    /// Helper method to raise or return the typed `data` payload of a response
+    async fn raise_or_return_typed<T>(&self, resp: reqwest::Response) -> Result<T>
+    where
+        T: DeserializeOwned + KiteModel,
+    {
+        let data = self.parse_response(resp, true).await?;
+        let model: T = serde_json::from_value(data)?;
+
+        if self.strict_mode {
+            let unknown = model.unknown_fields();
+            if !unknown.is_empty() {
+                return Err(KiteError::UnknownFields(unknown));
+            }
         }
+        Ok(model)
     }
 
-    /// Sets a session expiry callback hook for this instance
-    /// 
-    /// This hook will be called when a session expires, allowing you to handle
-    /// re-authentication or cleanup logic.
-    /// 
-    /// # Arguments
-    /// 
-    /// * `method` - Callback function to execute on session expiry
-    /// 
+    /// Parses a response body, optionally unwrapping the response envelope
+    async fn parse_response(&self, resp: reqwest::Response, unwrap: bool) -> Result<JsonValue> {
+        let sent = self.access_token();
+        if !resp.status().is_success() {
+            return Err(self.response_error(resp).await);
+        }
+        let body = resp.text().await?;
+        if self.debug {
+            log::debug!("<-- body: {}", redact::redact_body(&body));
+        }
+
+        let jsn: JsonValue = serde_json::from_str(&body)?;
+        if !unwrap {
+            return Ok(jsn);
+        }
+        match extract_data(jsn) {
+            Ok(data) => Ok(data),
+            Err(e) => Err(self.handle_api_error(e, &sent).await),
+        }
+    }
+
+    /// Builds the error of an unsuccessful response from its body
+    async fn response_error(&self, resp: reqwest::Response) -> KiteError {
+        let sent = self.access_token();
+        let status = resp.status();
+        let retry_after = retry::retry_after(resp.headers());
+        let content_type = content_type(&resp);
+        let body = match resp.text().await {
+            Ok(body) => body,
+            Err(e) => return e.into(),
+        };
+        if self.debug {
+            log::debug!("<-- body: {}", redact::redact_body(&body));
+        }
+        let err = KiteError::from_response(status.as_u16(), content_type.as_deref(), &body)
+            .with_retry_after(retry_after);
+        self.handle_api_error(err, &sent).await
+    }
+
+    /// Fires the session expiry hook if the error signals an expired session
+    ///
+    /// Only `TokenException` errors invalidate the session; other 403 responses such
+    /// as `PermissionException` leave the hook untouched. The rejected token is marked
+    /// stale and the hook runs once for it, however many requests fail concurrently.
+    /// The hook is awaited before the error is returned, so a hook that re-authenticates
+    /// has finished by the time the caller sees the error.
+    ///
+    /// `sent` is the token the failed request was sent with; a rejection arriving after
+    /// the token was replaced leaves the new token alone.
+    async fn handle_api_error(&self, err: KiteError, sent: &str) -> KiteError {
+        if err.is_token_error() {
+            let expired = {
+                let mut token = self.access_token.write().unwrap();
+                if token.value == sent {
+                    let notified = token.expiry_notified;
+                    token.mark_stale(ist::now());
+                    !notified
+                } else {
+                    false
+                }
+            };
+            if expired {
+                if let Some(hook) = &self.session_expiry_hook {
+                    hook().await;
+                }
+            }
+        }
+        err
+    }
+
+    /// Enables or disables unwrapping of the Kite response envelope
+    ///
+    /// Every Kite endpoint responds with `{ "status": "...", "data": ... }`. When
+    /// enabled, the `status` field is checked, error responses are converted into
+    /// errors built from `error_type` and `message`, and only the `data` payload
+    /// is returned from the JSON endpoint methods.
+    ///
     /// # Example
-    /// 
-    /// ```rust
+    ///
+    /// ```rust,no_run
     /// use kiteconnect::connect::KiteConnect;
-    /// 
-    /// fn handle_session_expiry() {
-    ///     println!("Session expired! Please re-authenticate.");
-    /// }
-    /// 
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// let mut client = KiteConnect::new("api_key", "access_token");
-    /// client.set_session_expiry_hook(handle_session_expiry);
+    /// client.set_unwrap_envelope(true);
+    ///
+    /// // `holdings` is now the array held in the `data` field
+    /// let holdings = client.holdings().await?;
+    /// println!("Number of holdings: {}", holdings.as_array().map_or(0, |h| h.len()));
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn set_session_expiry_hook(&mut self, method: fn() -> ()) {
-        self.session_expiry_hook = Some(method);
+    pub fn set_unwrap_envelope(&mut self, enabled: bool) {
+        self.unwrap_envelope = enabled;
     }
 
-    /// Gets the current session expiry hook
-    /// 
-    /// Returns the session expiry callback function if one has been set.
-    /// 
-    /// # Returns
-    /// 
-    /// `Option<fn() -> ()>` - The callback function, or `None` if not set
-    pub fn session_expiry_hook(&self) -> Option<fn() -> ()> {
-        self.session_expiry_hook
+    /// Returns whether the response envelope is unwrapped for this instance
+    pub fn unwrap_envelope(&self) -> bool {
+        self.unwrap_envelope
     }
 
-    /// Sets the access token for authenticated API requests
-    /// 
-    /// This is typically called automatically by `generate_session`, but can
-    /// be used manually if you have a pre-existing access token.
-    /// 
-    /// # Arguments
-    /// 
-    /// * `access_token` - The access token string
-    /// 
+    /// Enables or disables strict deserialization of typed models
+    ///
+    /// Typed models capture fields they do not know about in their `extra` map.
+    /// In strict mode the `*_typed` endpoint methods instead return an error when
+    /// a response contains unknown fields, which is useful to detect schema drift.
+    ///
     /// # Example
-    /// 
+    ///
     /// ```rust
     /// use kiteconnect::connect::KiteConnect;
-    /// 
-    /// let mut client = KiteConnect::new("api_key", "");
-    /// client.set_access_token("your_access_token");
+    ///
+    /// let mut client = KiteConnect::new("api_key", "access_token");
+    /// client.set_strict_mode(true);
+    /// assert!(client.strict_mode());
     /// ```
-    pub fn set_access_token(&mut self, access_token: &str) {
-        self.access_token = access_token.to_string();
+    pub fn set_strict_mode(&mut self, enabled: bool) {
+        self.strict_mode = enabled;
     }
 
-    /// Gets the access token for this instance
-    pub fn access_token(&self) -> &str {
-        &self.access_token
+    /// Returns whether strict deserialization is enabled for this instance
+    pub fn strict_mode(&self) -> bool {
+        self.strict_mode
     }
 
-    /// Generates the KiteConnect login URL for user authentication
-    /// 
-    /// This URL should be opened in a browser to allow the user to log in to their
-    /// Zerodha account. After successful login, the user will be redirected to your
-    /// redirect URL with a `request_token` parameter.
-    /// 
-    /// # Returns
-    /// 
-    /// A login URL string that can be opened in a browser
-    /// 
+    /// Sets the retry policy for idempotent requests
+    ///
+    /// Retries are disabled by default. With a policy set, `GET` requests failing
+    /// with `429`, a `5xx` status or a connection timeout are retried with
+    /// exponential backoff. See [`RetryPolicy`] for what is retried.
+    ///
     /// # Example
-    /// 
+    ///
     /// ```rust
     /// use kiteconnect::connect::KiteConnect;
-    /// 
-    /// let client = KiteConnect::new("your_api_key", "");
-    /// let login_url = client.login_url();
-    /// 
-    /// println!("Please visit: {}", login_url);
-    /// // User visits URL, logs in, and is redirected with request_token
+    /// use kiteconnect::retry::RetryPolicy;
+    ///
+    /// let mut client = KiteConnect::new("api_key", "access_token");
+    /// client.set_retry_policy(RetryPolicy::default());
     /// ```
-    /// 
-    /// # Authentication Flow
-    /// 
-    /// 1. Generate login URL with this method
-    /// 2. Direct user to the URL in a browser
-    /// 3. User completes login and is redirected with `request_token`
-    /// 4. Use `generate_session()` with the request token to get access token
-    pub fn login_url(&self) -> String {
-        format!("https://kite.trade/connect/login?api_key={}&v3", self.api_key)
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = policy;
     }
 
-    /// Compute checksum for authentication - different implementations for native vs WASM
-    #[cfg(not(target_arch = "wasm32"))]
-    async fn compute_checksum(&self, input: &str) -> Result<String> {
-        let mut hasher = Sha256::new();
-        hasher.update(input.as_bytes());
-        let result = hasher.finalize();
-        Ok(hex::encode(result))
+    /// Returns the retry policy for this instance
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
     }
 
-    #[cfg(target_arch = "wasm32")]
-    async fn compute_checksum(&self, input: &str) -> Result<String> {
-        // WASM implementation using Web Crypto API
-        let window = window().ok_or_else(|| anyhow!("No window object"))?;
-        let crypto = window.crypto().map_err(|_| anyhow!("No crypto object"))?;
-        let subtle = crypto.subtle();
+    /// Sets the store session tokens are saved to
+    ///
+    /// Tokens obtained with [`generate_session`](Self::generate_session) and
+    /// [`renew_access_token`](Self::renew_access_token) are saved to the store, so
+    /// a restarted process can pick up the session instead of logging in again.
+    /// A failure to save is logged and does not fail the call.
+    pub fn set_token_store(&mut self, store: Arc<dyn TokenStore>) {
+        self.token_store = Some(store);
+    }
 
-        let data = Uint8Array::from(input.as_bytes());
-        let digest_promise = subtle
-            .digest_with_str_and_u8_array("SHA-256", &data.to_vec())
-            .map_err(|_| anyhow!("Failed to create digest"))?;
+    /// Returns the token store of this instance, if any
+    pub fn token_store(&self) -> Option<&Arc<dyn TokenStore>> {
+        self.token_store.as_ref()
+    }
 
-        let digest_result = JsFuture::from(digest_promise)
-            .await
-            .map_err(|_| anyhow!("Failed to compute hash"))?;
+    /// Checks orders against the tick and lot sizes of these instruments
+    /// before placing them
+    ///
+    /// [`place_order`](Self::place_order) then rejects prices and trigger
+    /// prices that are not a multiple of the tick size, and quantities that are
+    /// not a multiple of the lot size, without sending the order, instead of
+    /// leaving the rejection to the exchange. Orders for instruments missing
+    /// from the store are sent unchecked. Pass the store of an
+    /// [`InstrumentCache`](crate::instruments::InstrumentCache) again after it
+    /// refreshes to check against the current dump.
+    ///
+    /// ```rust
+    /// use kiteconnect::connect::KiteConnect;
+    /// use kiteconnect::instruments::InstrumentStore;
+    /// use std::sync::Arc;
+    ///
+    /// let mut client = KiteConnect::new("api_key", "access_token");
+    /// client.set_order_checks(Arc::new(InstrumentStore::new(Vec::new())));
+    /// ```
+    pub fn set_order_checks(&mut self, instruments: Arc<InstrumentStore>) {
+        self.order_checks = Some(instruments);
+    }
 
-        let digest_array = Uint8Array::new(&digest_result);
-        let digest_vec: Vec<u8> = digest_array.to_vec();
-        Ok(hex::encode(digest_vec))
+    /// Returns the instruments orders are checked against, if any
+    pub fn order_checks(&self) -> Option<&Arc<InstrumentStore>> {
+        self.order_checks.as_ref()
     }
 
-    /// Generates an access token using the request token from login
-    /// 
-    /// This method completes the authentication flow by exchanging the request token
-    /// (obtained after user login) for an access token that can be used for API calls.
-    /// The access token is automatically stored in the client instance.
-    /// 
-    /// # Arguments
-    /// 
-    /// * `request_token` - The request token received after user login
-    /// * `api_secret` - Your KiteConnect API secret
-    /// 
-    /// # Returns
-    /// 
-    /// A `Result<JsonValue>` containing the session information including access token
-    /// 
-    /// # Errors
-    /// 
-    /// Returns an error if:
-    /// - The request token is invalid or expired
-    /// - The API secret is incorrect
-    /// - Network request fails
-    /// - Response parsing fails
-    /// 
-    /// # Example
-    /// 
-    /// ```rust,no_run
+    /// Sets the freeze quantities [`place_order_sliced`](Self::place_order_sliced)
+    /// splits orders by
+    ///
+    /// Defaults to the NFO index limits of [`FreezeLimits::default`].
+    pub fn set_freeze_limits(&mut self, limits: FreezeLimits) {
+        self.freeze_limits = Arc::new(limits);
+    }
+
+    /// Returns the freeze quantities orders are sliced by
+    pub fn freeze_limits(&self) -> &FreezeLimits {
+        &self.freeze_limits
+    }
+
+    /// Slices every order [`place_order`](Self::place_order) places, or stops
+    /// slicing with `None`
+    ///
+    /// Orders reaching the freeze quantity of their instrument or the maximum
+    /// clip of `auto_slice` are placed as several orders like with
+    /// [`place_order_sliced`](Self::place_order_sliced), which then also
+    /// honours the clip and the delay between slices. Off by default, so
+    /// orders are sent as they are.
+    ///
+    /// ```rust
     /// use kiteconnect::connect::KiteConnect;
-    /// 
-    /// # #[tokio::main]
-    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let mut client = KiteConnect::new("your_api_key", "");
-    /// 
-    /// // After user completes login and you receive the request_token
-    /// let session_data = client
-    ///     .generate_session("request_token_from_callback", "your_api_secret")
-    ///     .await?;
-    /// 
-    /// println!("Session created: {:?}", session_data);
-    /// // Access token is now automatically set in the client
-    /// # Ok(())
-    /// # }
+    /// use kiteconnect::instruments::AutoSlice;
+    /// use std::time::Duration;
+    ///
+    /// let mut client = KiteConnect::new("api_key", "access_token");
+    /// client.set_auto_slice(Some(AutoSlice::new().max_clip(500).delay(Duration::from_millis(250))));
     /// ```
-    /// 
-    /// # Authentication Flow
-    /// 
-    /// 1. Call `login_url()` to get login URL
-    /// 2. User visits URL and completes login
-    /// 3. User is redirected with `request_token` parameter
-    /// 4. Call this method with the request token and API secret
-    /// 5. Access token is automatically set for subsequent API calls
-    pub async fn generate_session(
-        &mut self,
-        request_token: &str,
-        api_secret: &str,
-    ) -> Result<JsonValue> {
-        // Create a hex digest from api key, request token, api secret
-        let input = format!("{}{}{}", self.api_key, request_token, api_secret);
-        let checksum = self.compute_checksum(&input).await?;
+    pub fn set_auto_slice(&mut self, auto_slice: Option<AutoSlice>) {
+        self.auto_slice = auto_slice;
+    }
 
-        let api_key: &str = &self.api_key.clone();
-        let mut data = HashMap::new();
-        data.insert("api_key", api_key);
-        data.insert("request_token", request_token);
-        data.insert("checksum", checksum.as_str());
+    /// Returns the slicing applied to every order placed, if enabled
+    pub fn auto_slice(&self) -> Option<&AutoSlice> {
+        self.auto_slice.as_ref()
+    }
 
-        let url = self.build_url("/session/token", None);
-        let resp = self.send_request(url, "POST", Some(data)).await?;
+    /// Ignores repeats of orders [`place_order`](Self::place_order) placed
+    /// within `window`, or stops with `None`
+    ///
+    /// An order repeats another when its exchange, tradingsymbol, transaction
+    /// type, quantity and tag are the same. A repeat of an order that was
+    /// placed returns the response of the first placement without a request,
+    /// and a repeat of one still being placed fails. When a placement fails in
+    /// a way that leaves open whether the order went through, such as a
+    /// timeout or a `5xx` response, its repeat looks for the order in the
+    /// order book and is only placed if it is not there, so retrying after a
+    /// timeout does not double the position. Orders meant to be repeated
+    /// within the window need distinct tags. Off by default.
+    ///
+    /// ```rust
+    /// use kiteconnect::connect::KiteConnect;
+    /// use std::time::Duration;
+    ///
+    /// let mut client = KiteConnect::new("api_key", "access_token");
+    /// client.set_dedupe_window(Some(Duration::from_secs(30)));
+    /// ```
+    pub fn set_dedupe_window(&mut self, window: Option<Duration>) {
+        self.dedupe = window.map(|window| Arc::new(OrderDedupe::new(window)));
+    }
 
-        if resp.status().is_success() {
-            let jsn: JsonValue = resp.json().await?;
-            self.set_access_token(jsn["data"]["access_token"].as_str().unwrap());
-            Ok(jsn)
-        } else {
-            let error_text = resp.text().await?;
-            Err(anyhow!(error_text))
+    /// Returns the window repeats of orders are ignored within, if enabled
+    pub fn dedupe_window(&self) -> Option<Duration> {
+        self.dedupe.as_ref().map(|dedupe| dedupe.window())
+    }
+
+    /// Keeps the client from placing, modifying or cancelling orders, GTT
+    /// triggers and position conversions, or lets it trade again with `None`
+    ///
+    /// Requests that would trade are logged with their payload and rejected
+    /// or answered with a simulated success, as `mode` says; every other
+    /// request is sent as usual. See [`crate::readonly`].
+    ///
+    /// ```rust
+    /// use kiteconnect::connect::KiteConnect;
+    /// use kiteconnect::readonly::ReadOnlyMode;
+    ///
+    /// let mut client = KiteConnect::new("api_key", "access_token");
+    /// client.set_read_only(Some(ReadOnlyMode::Reject));
+    /// ```
+    pub fn set_read_only(&mut self, mode: Option<ReadOnlyMode>) {
+        self.read_only = mode;
+    }
+
+    /// Returns what happens to requests that would trade, if the client is
+    /// read-only
+    pub fn read_only(&self) -> Option<ReadOnlyMode> {
+        self.read_only
+    }
+
+    /// Turns the kill switch on, blocking every request that would trade, or
+    /// off again
+    ///
+    /// While it is on, placing, modifying or cancelling orders, GTT triggers
+    /// and position conversions fail with [`KiteError::KillSwitchActive`]
+    /// without a request, in this client and every clone of it, including
+    /// requests already waiting in an [`OrderGateway`](crate::orders::OrderGateway).
+    /// It stops the software only; orders already with the OMS stay open, and
+    /// the kill switch of Kite itself is not touched.
+    ///
+    /// ```rust
+    /// use kiteconnect::connect::KiteConnect;
+    ///
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// let risk = client.clone();
+    /// risk.kill_switch(true);
+    /// assert!(client.is_kill_switch_active());
+    /// ```
+    pub fn kill_switch(&self, active: bool) {
+        if self.kill_switch.swap(active, Ordering::SeqCst) != active {
+            log::warn!("Kill switch turned {}", if active { "on" } else { "off" });
         }
     }
 
-    /// Invalidates the access token
-    pub async fn invalidate_access_token(&self, access_token: &str) -> Result<reqwest::Response> {
-        let url = self.build_url("/session/token", None);
-        let mut data = HashMap::new();
-        data.insert("access_token", access_token);
+    /// Returns `true` while the kill switch blocks requests that would trade
+    pub fn is_kill_switch_active(&self) -> bool {
+        self.kill_switch.load(Ordering::SeqCst)
+    }
 
-        self.send_request(url, "DELETE", Some(data)).await
+    /// Decides what [`place_order`](Self::place_order) does with regular
+    /// orders placed while their exchange is closed, or leaves them to the OMS
+    /// with `None`
+    ///
+    /// The [market hours and holidays](crate::calendar) of the exchange of the
+    /// order are checked before it is placed; the pre-open auction counts as
+    /// open. With [`AfterHours::Amo`] the order is
+    /// placed as an after market order for the next session, within the
+    /// [AMO hours](crate::calendar::amo_hours) of the exchange, and otherwise
+    /// fails with [`KiteError::MarketClosed`] like with [`AfterHours::Reject`].
+    /// Orders of other varieties, and orders for exchanges without AMO hours
+    /// such as the commodity ones, are placed as they are. Off by default.
+    ///
+    /// ```rust
+    /// use kiteconnect::calendar::AfterHours;
+    /// use kiteconnect::connect::KiteConnect;
+    ///
+    /// let mut client = KiteConnect::new("api_key", "access_token");
+    /// client.set_after_hours(Some(AfterHours::Amo));
+    /// ```
+    pub fn set_after_hours(&mut self, after_hours: Option<AfterHours>) {
+        self.after_hours = after_hours;
     }
 
-    /// Request for new access token
-    pub async fn renew_access_token(
-        &mut self,
-        access_token: &str,
-        api_secret: &str,
-    ) -> Result<JsonValue> {
-        // Create a hex digest from api key, request token, api secret
-        let input = format!("{}{}{}", self.api_key, access_token, api_secret);
-        let checksum = self.compute_checksum(&input).await?;
+    /// Returns what happens to orders placed while their exchange is closed,
+    /// if set
+    pub fn after_hours(&self) -> Option<AfterHours> {
+        self.after_hours
+    }
 
-        let api_key: &str = &self.api_key.clone();
-        let mut data = HashMap::new();
-        data.insert("api_key", api_key);
-        data.insert("access_token", access_token);
-        data.insert("checksum", checksum.as_str());
+    /// Returns the variety to place an order of `variety` as at `now`
+    fn session_variety<'a>(&self, variety: &'a str, exchange: &str, now: Timestamp) -> Result<&'a str> {
+        match self.after_hours {
+            Some(after_hours)
+                if variety == "regular"
+                    && calendar::amo_hours(exchange).is_some()
+                    && !calendar::is_market_open(exchange, now)
+                    && !calendar::is_pre_open(exchange, now) =>
+            {
+                match after_hours {
+                    AfterHours::Amo if calendar::accepts_amo(exchange, now) => {
+                        log::info!("{} is closed, placing the order as AMO", exchange);
+                        Ok("amo")
+                    }
+                    _ => Err(KiteError::MarketClosed {
+                        exchange: exchange.to_string(),
+                    }),
+                }
+            }
+            _ => Ok(variety),
+        }
+    }
 
-        let url = self.build_url("/session/refresh_token", None);
-        let resp = self.send_request(url, "POST", Some(data)).await?;
+    /// Rejects orders whose price, trigger price or quantity the exchange
+    /// would reject for the tick or lot size, if order checks are enabled
+    fn check_order(
+        &self,
+        exchange: &str,
+        tradingsymbol: &str,
+        quantity: &str,
+        prices: [Option<&str>; 2],
+    ) -> Result<()> {
+        let Some(instrument) = self
+            .order_checks
+            .as_ref()
+            .and_then(|store| store.find(exchange, tradingsymbol))
+        else {
+            return Ok(());
+        };
+        let quantity = quantity
+            .trim()
+            .parse()
+            .map_err(|_| KiteError::Other(format!("invalid quantity {:?}", quantity)))?;
+        validate_order_quantity(quantity, instrument)?;
+        for price in prices.into_iter().flatten() {
+            let value: Price = price
+                .trim()
+                .parse()
+                .map_err(|_| KiteError::Other(format!("invalid price {:?}", price)))?;
+            // Market orders are sent with a zero price
+            if value != Price::default() {
+                validate_order_price(value, instrument)?;
+            }
+        }
+        Ok(())
+    }
 
-        if resp.status().is_success() {
-            let jsn: JsonValue = resp.json().await?;
-            self.set_access_token(jsn["access_token"].as_str().unwrap());
-            Ok(jsn)
-        } else {
-            let error_text = resp.text().await?;
-            Err(anyhow!(error_text))
+    /// Saves tokens to the token store, if one is configured
+    fn persist_tokens(&self, tokens: StoredTokens) {
+        if let Some(store) = &self.token_store {
+            if let Err(e) = store.save(&tokens) {
+                log::warn!("Failed to save session tokens: {}", e);
+            }
         }
     }
 
-    /// Invalidates the refresh token
-    pub async fn invalidate_refresh_token(&self, refresh_token: &str) -> Result<reqwest::Response> {
-        let url = self.build_url("/session/refresh_token", None);
-        let mut data = HashMap::new();
-        data.insert("refresh_token", refresh_token);
+    /// Enables or disables debug logging of full requests and responses
+    ///
+    /// When enabled, the method, URL, headers and form body of every request and
+    /// the status and body of every response are logged at `debug` level through
+    /// the `log` crate. The API key, access token, checksum, TOTP and similar
+    /// credentials are masked in the output.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use kiteconnect::connect::KiteConnect;
+    ///
+    /// let mut client = KiteConnect::new("api_key", "access_token");
+    /// client.set_debug(true);
+    /// ```
+    pub fn set_debug(&mut self, enabled: bool) {
+        self.debug = enabled;
+    }
 
-        self.send_request(url, "DELETE", Some(data)).await
+    /// Returns whether debug logging is enabled for this instance
+    pub fn debug(&self) -> bool {
+        self.debug
     }
 
-    /// Retrieves account balance and margin details
-    /// 
-    /// Returns margin information for trading segments including available cash,
-    /// used margins, and available margins for different product types.
-    /// 
-    /// # Arguments
-    /// 
-    /// * `segment` - Optional trading segment ("equity" or "commodity"). If None, returns all segments
-    /// 
-    /// # Returns
-    /// 
-    /// A `Result<JsonValue>` containing margin data with fields like:
-    /// - `available` - Available margin for trading
-    /// - `utilised` - Currently utilized margin
-    /// - `net` - Net available margin
-    /// - `enabled` - Whether the segment is enabled
-    /// 
-    /// # Errors
-    /// 
-    /// Returns an error if the API request fails or the user is not authenticated.
-    /// 
+    /// Returns a clone of this client that applies the given request options
+    ///
+    /// The clone shares the access token, rate limiter and connection pool with
+    /// this instance; only the timeout, retry policy and extra headers differ.
+    ///
     /// # Example
-    /// 
+    ///
     /// ```rust,no_run
     /// use kiteconnect::connect::KiteConnect;
-    /// 
+    /// use kiteconnect::options::RequestOptions;
+    /// use std::time::Duration;
+    ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// let client = KiteConnect::new("api_key", "access_token");
-    /// 
-    /// // Get margins for all segments
-    /// let all_margins = client.margins(None).await?;
-    /// println!("All margins: {:?}", all_margins);
-    /// 
-    /// // Get margins for specific segment
-    /// let equity_margins = client.margins(Some("equity".to_string())).await?;
-    /// println!("Equity available margin: {}", 
-    ///     equity_margins["data"]["available"]["live_balance"]);
+    /// let positions = client
+    ///     .with_options(RequestOptions::new().timeout(Duration::from_millis(300)))
+    ///     .positions()
+    ///     .await?;
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn margins(&self, segment: Option<String>) -> Result<JsonValue> {
-        let url: reqwest::Url = if let Some(segment) = segment {
-            self.build_url(&format!("/user/margins/{}", segment), None)
-        } else {
-            self.build_url("/user/margins", None)
-        };
+    pub fn with_options(&self, options: RequestOptions) -> KiteConnect {
+        KiteConnect {
+            options,
+            ..self.clone()
+        }
+    }
 
-        let resp = self.send_request(url, "GET", None).await?;
-        self.raise_or_return_json(resp).await
+    /// Returns a clone of this client that authenticates as another user
+    ///
+    /// Multi-tenant servers can keep one client and call any endpoint with the
+    /// access token of the user being served. The clone shares the connection
+    /// pool, rate limiter and configuration with this instance, but has its own
+    /// access token, and neither runs the session expiry hook nor saves tokens to
+    /// the token store, since both belong to this instance's user.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = KiteConnect::new("api_key", "");
+    /// let holdings = client.as_user("user_access_token").holdings().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn as_user(&self, access_token: &str) -> KiteConnect {
+        KiteConnect {
+            access_token: Arc::new(RwLock::new(SessionToken::new(access_token))),
+            session_expiry_hook: None,
+            token_store: None,
+            ..self.clone()
+        }
     }
 
-    /// Get user profile details
-    pub async fn profile(&self) -> Result<JsonValue> {
-        let url = self.build_url("/user/profile", None);
-        let resp = self.send_request(url, "GET", None).await?;
-        self.raise_or_return_json(resp).await
+    /// Returns the request options applied by this instance
+    pub fn request_options(&self) -> &RequestOptions {
+        &self.options
     }
 
-    /// Retrieves the user's holdings (stocks held in demat account)
-    /// 
-    /// Holdings represent stocks that are held in the user's demat account.
-    /// This includes information about quantity, average price, current market value,
-    /// profit/loss, and more.
-    /// 
-    /// # Returns
-    /// 
-    /// A `Result<JsonValue>` containing holdings data with fields like:
-    /// - `tradingsymbol` - Trading symbol of the instrument
-    /// - `quantity` - Total quantity held
-    /// - `average_price` - Average buying price
-    /// - `last_price` - Current market price
-    /// - `pnl` - Profit and loss
-    /// - `product` - Product type (CNC, MIS, etc.)
-    /// 
-    /// # Errors
-    /// 
-    /// Returns an error if the API request fails or the user is not authenticated.
-    /// 
+    /// Opens a connection to the API ahead of time
+    ///
+    /// Performs the DNS lookup, TCP and TLS handshakes by requesting the API root,
+    /// leaving the connection in the pool for the next request. Call it shortly
+    /// before market open so the first order doesn't pay the connection setup
+    /// latency. Any HTTP response counts as success; only transport failures
+    /// are returned as errors.
+    ///
     /// # Example
-    /// 
+    ///
     /// ```rust,no_run
     /// use kiteconnect::connect::KiteConnect;
-    /// 
+    /// use std::time::Duration;
+    ///
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let client = KiteConnect::new("api_key", "access_token");
-    /// 
-    /// let holdings = client.holdings().await?;
-    /// println!("Holdings: {:?}", holdings);
-    /// 
-    /// // Access specific fields
-    /// if let Some(data) = holdings["data"].as_array() {
-    ///     for holding in data {
-    ///         println!("Symbol: {}, Quantity: {}", 
-    ///             holding["tradingsymbol"], holding["quantity"]);
-    ///     }
-    /// }
+    /// let client = KiteConnect::builder("api_key")
+    ///     .access_token("access_token")
+    ///     .tcp_keepalive(Duration::from_secs(30))
+    ///     .build()?;
+    /// client.warm_up().await?;
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn holdings(&self) -> Result<JsonValue> {
-        let url = self.build_url("/portfolio/holdings", None);
-        let resp = self.send_request(url, "GET", None).await?;
-        self.raise_or_return_json(resp).await
+    pub async fn warm_up(&self) -> Result<()> {
+        let url = self.build_url("/", None);
+        let resp = self.client.get(url).send().await?;
+        // Drain the body so the connection is returned to the pool
+        resp.bytes().await?;
+        Ok(())
     }
 
-    /// Retrieves the user's positions (open positions for the day)
-    /// 
-    /// Positions represent open trading positions for the current trading day.
-    /// This includes both intraday and carry-forward positions with details about
-    /// profit/loss, margin requirements, and position status.
-    /// 
-    /// # Returns
+    /// Sets a session expiry callback hook for this instance
     /// 
-    /// A `Result<JsonValue>` containing positions data with fields like:
-    /// - `tradingsymbol` - Trading symbol of the instrument
-    /// - `quantity` - Net position quantity
-    /// - `buy_quantity` - Total buy quantity
-    /// - `sell_quantity` - Total sell quantity
-    /// - `average_price` - Average position price
-    /// - `pnl` - Realized and unrealized P&L
-    /// - `product` - Product type (MIS, CNC, NRML)
+    /// This hook will be called when a session expires, allowing you to handle
+    /// re-authentication or cleanup logic. It is invoked whenever an API call fails
+    /// with a `TokenException`, right before the error is returned to the caller.
     /// 
-    /// # Errors
+    /// # Arguments
     /// 
-    /// Returns an error if the API request fails or the user is not authenticated.
+    /// * `method` - Callback function to execute on session expiry
     /// 
     /// # Example
     /// 
-    /// ```rust,no_run
+    /// ```rust
     /// use kiteconnect::connect::KiteConnect;
     /// 
-    /// # #[tokio::main]
-    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let client = KiteConnect::new("api_key", "access_token");
+    /// fn handle_session_expiry() {
+    ///     println!("Session expired! Please re-authenticate.");
+    /// }
     /// 
-    /// let positions = client.positions().await?;
-    /// println!("Positions: {:?}", positions);
+    /// let mut client = KiteConnect::new("api_key", "access_token");
+    /// client.set_session_expiry_hook(handle_session_expiry);
+    /// ```
     /// 
-    /// // Check for open positions
-    /// if let Some(day_positions) = positions["data"]["day"].as_array() {
-    ///     for position in day_positions {
-    ///         if position["quantity"].as_i64().unwrap_or(0) != 0 {
-    ///             println!("Open position: {} qty {}", 
-    ///                 position["tradingsymbol"], position["quantity"]);
-    ///         }
+    /// Use [`set_async_session_expiry_hook`](Self::set_async_session_expiry_hook)
+    /// for hooks that need to capture state or perform async work.
+    pub fn set_session_expiry_hook(&mut self, method: fn() -> ()) {
+        self.session_expiry_hook = Some(Arc::new(move || {
+            method();
+            Box::pin(async {}) as BoxFuture<'static, ()>
+        }));
+    }
+
+    /// Sets an async, closure-based session expiry hook for this instance
+    /// 
+    /// The closure may capture state, such as a clone of the client or a database
+    /// handle, and the returned future is awaited before the `TokenException` is
+    /// returned to the caller. Since clones of a client share their access token,
+    /// a hook can re-authenticate and update the token on a captured clone.
+    /// 
+    /// # Example
+    /// 
+    /// ```rust
+    /// use kiteconnect::connect::KiteConnect;
+    /// 
+    /// async fn load_token_from_db() -> String {
+    ///     "fresh_access_token".to_string()
+    /// }
+    /// 
+    /// let mut client = KiteConnect::new("api_key", "access_token");
+    /// let handle = client.clone();
+    /// client.set_async_session_expiry_hook(move || {
+    ///     let handle = handle.clone();
+    ///     async move {
+    ///         handle.set_access_token(&load_token_from_db().await);
     ///     }
+    /// });
+    /// ```
+    pub fn set_async_session_expiry_hook<F, Fut>(&mut self, hook: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.session_expiry_hook = Some(Arc::new(move || Box::pin(hook()) as BoxFuture<'static, ()>));
+    }
+
+    /// Gets the current session expiry hook
+    /// 
+    /// Returns the session expiry hook if one has been set, wrapped as an async hook
+    /// regardless of how it was registered.
+    pub fn session_expiry_hook(&self) -> Option<SessionExpiryHook> {
+        self.session_expiry_hook.clone()
+    }
+
+    /// Sets the access token for authenticated API requests
+    /// 
+    /// This is typically called automatically by `generate_session`, but can
+    /// be used manually if you have a pre-existing access token.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `access_token` - The access token string
+    /// 
+    /// # Example
+    /// 
+    /// ```rust
+    /// use kiteconnect::connect::KiteConnect;
+    /// 
+    /// let client = KiteConnect::new("api_key", "");
+    /// client.set_access_token("your_access_token");
+    /// ```
+    /// 
+    /// The token is shared between clones of the client, so updating it on one
+    /// clone updates it for all of them.
+    pub fn set_access_token(&self, access_token: &str) {
+        *self.access_token.write().unwrap() = SessionToken::new(access_token);
+    }
+
+    /// Gets the access token for this instance
+    pub fn access_token(&self) -> String {
+        self.access_token.read().unwrap().value.clone()
+    }
+
+    /// Returns when the access token is expected to expire
+    ///
+    /// Kite invalidates access tokens every morning at 6 AM IST, so a token
+    /// expires at the first 6 AM after it was set on the client. Returns `None`
+    /// if no token is set.
+    pub fn token_expires_at(&self) -> Option<Timestamp> {
+        self.access_token.read().unwrap().expires_at
+    }
+
+    /// Returns `true` if the access token has expired or was rejected by the API
+    pub fn is_token_stale(&self) -> bool {
+        self.access_token.read().unwrap().is_stale(ist::now())
+    }
+
+    /// Checks whether the access token is still valid with a cheap profile request
+    ///
+    /// Returns `Ok(false)` and flags the token as stale if the API rejects the
+    /// token, which also runs the session expiry hook. Other failures, such as
+    /// network errors, are returned as errors since they say nothing about the
+    /// token.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// if !client.validate_session().await? {
+    ///     println!("Please log in again: {}", client.login_url());
     /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn positions(&self) -> Result<JsonValue> {
-        let url = self.build_url("/portfolio/positions", None);
+    pub async fn validate_session(&self) -> Result<bool> {
+        let url = self.build_url("/user/profile", None);
         let resp = self.send_request(url, "GET", None).await?;
-        self.raise_or_return_json(resp).await
+        match self.parse_response(resp, true).await {
+            Ok(_) => Ok(true),
+            Err(e) if e.is_token_error() => Ok(false),
+            Err(e) => Err(e),
+        }
     }
 
-    /// Place an order
-    pub async fn place_order(
-        &self,
-        variety: &str,
-        exchange: &str,
-        tradingsymbol: &str,
-        transaction_type: &str,
-        quantity: &str,
-        product: Option<&str>,
-        order_type: Option<&str>,
-        price: Option<&str>,
-        validity: Option<&str>,
-        disclosed_quantity: Option<&str>,
-        trigger_price: Option<&str>,
-        squareoff: Option<&str>,
-        stoploss: Option<&str>,
-        trailing_stoploss: Option<&str>,
-        tag: Option<&str>,
-    ) -> Result<JsonValue> {
-        let mut params = HashMap::new();
-        params.insert("variety", variety);
-        params.insert("exchange", exchange);
-        params.insert("tradingsymbol", tradingsymbol);
-        params.insert("transaction_type", transaction_type);
-        params.insert("quantity", quantity);
-        
-        if let Some(product) = product { params.insert("product", product); }
-        if let Some(order_type) = order_type { params.insert("order_type", order_type); }
-        if let Some(price) = price { params.insert("price", price); }
-        if let Some(validity) = validity { params.insert("validity", validity); }
-        if let Some(disclosed_quantity) = disclosed_quantity { params.insert("disclosed_quantity", disclosed_quantity); }
-        if let Some(trigger_price) = trigger_price { params.insert("trigger_price", trigger_price); }
-        if let Some(squareoff) = squareoff { params.insert("squareoff", squareoff); }
-        if let Some(stoploss) = stoploss { params.insert("stoploss", stoploss); }
-        if let Some(trailing_stoploss) = trailing_stoploss { params.insert("trailing_stoploss", trailing_stoploss); }
-        if let Some(tag) = tag { params.insert("tag", tag); }
+    /// Runs the session expiry hook once when the token passes its expiry time
+    ///
+    /// Called before every request, so that the hook can log in again before a
+    /// flood of calls fails with a `TokenException`.
+    async fn check_token_expiry(&self) {
+        let expired = {
+            let mut token = self.access_token.write().unwrap();
+            if token.is_stale(ist::now()) && !token.expiry_notified {
+                token.expiry_notified = true;
+                true
+            } else {
+                false
+            }
+        };
+        if expired {
+            log::warn!("Access token expired at {:?}", self.token_expires_at());
+            if let Some(hook) = &self.session_expiry_hook {
+                hook().await;
+            }
+        }
+    }
 
-        let url = self.build_url(&format!("/orders/{}", variety), None);
-        let resp = self.send_request(url, "POST", Some(params)).await?;
-        self.raise_or_return_json(resp).await
+    /// Returns the API key of this instance
+    pub fn api_key(&self) -> &str {
+        &self.api_key
     }
 
-    /// Modify an open order
-    pub async fn modify_order(
-        &self,
-        order_id: &str,
-        variety: &str,
-        quantity: Option<&str>,
-        price: Option<&str>,
-        order_type: Option<&str>,
-        validity: Option<&str>,
-        disclosed_quantity: Option<&str>,
-        trigger_price: Option<&str>,
-        parent_order_id: Option<&str>,
-    ) -> Result<JsonValue> {
-        let mut params = HashMap::new();
-        params.insert("order_id", order_id);
-        params.insert("variety", variety);
-        
-        if let Some(quantity) = quantity { params.insert("quantity", quantity); }
-        if let Some(price) = price { params.insert("price", price); }
-        if let Some(order_type) = order_type { params.insert("order_type", order_type); }
-        if let Some(validity) = validity { params.insert("validity", validity); }
-        if let Some(disclosed_quantity) = disclosed_quantity { params.insert("disclosed_quantity", disclosed_quantity); }
-        if let Some(trigger_price) = trigger_price { params.insert("trigger_price", trigger_price); }
-        if let Some(parent_order_id) = parent_order_id { params.insert("parent_order_id", parent_order_id); }
+    /// Returns the root URL of the REST API used by this instance
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
 
-        let url = self.build_url(&format!("/orders/{}/{}", variety, order_id), None);
-        let resp = self.send_request(url, "PUT", Some(params)).await?;
-        self.raise_or_return_json(resp).await
+    /// Returns the proxy configured for this instance, if any, e.g. to route
+    /// the ticker through it as well
+    pub fn proxy(&self) -> Option<&ProxyConfig> {
+        self.proxy.as_ref()
     }
 
-    /// Cancel an order
-    pub async fn cancel_order(
-        &self,
-        order_id: &str,
-        variety: &str,
-        parent_order_id: Option<&str>,
-    ) -> Result<JsonValue> {
-        let mut params = HashMap::new();
-        params.insert("order_id", order_id);
-        params.insert("variety", variety);
-        if let Some(parent_order_id) = parent_order_id {
-            params.insert("parent_order_id", parent_order_id);
+    /// Generates the KiteConnect login URL for user authentication
+    /// 
+    /// This URL should be opened in a browser to allow the user to log in to their
+    /// Zerodha account. After successful login, the user will be redirected to your
+    /// redirect URL with a `request_token` parameter.
+    /// 
+    /// # Returns
+    /// 
+    /// A login URL string that can be opened in a browser
+    /// 
+    /// # Example
+    /// 
+    /// ```rust
+    /// use kiteconnect::connect::KiteConnect;
+    /// 
+    /// let client = KiteConnect::new("your_api_key", "");
+    /// let login_url = client.login_url();
+    /// 
+    /// println!("Please visit: {}", login_url);
+    /// // User visits URL, logs in, and is redirected with request_token
+    /// ```
+    /// 
+    /// # Authentication Flow
+    /// 
+    /// 1. Generate login URL with this method
+    /// 2. Direct user to the URL in a browser
+    /// 3. User completes login and is redirected with `request_token`
+    /// 4. Use `generate_session()` with the request token to get access token
+    pub fn login_url(&self) -> String {
+        format!("https://kite.trade/connect/login?api_key={}&v3", self.api_key)
+    }
+
+    /// Generates the login URL with extra parameters echoed back on the redirect
+    ///
+    /// Kite appends `redirect_params` to the query string of the redirect URL
+    /// alongside the `request_token`, which lets web apps carry their own context
+    /// through the login. The parameters are sent URL-encoded in a single
+    /// `redirect_params` value as described in the Kite documentation.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use kiteconnect::connect::KiteConnect;
+    ///
+    /// let client = KiteConnect::new("your_api_key", "");
+    /// let login_url = client.login_url_with_params(&[("return_to", "/dashboard")]);
+    /// assert!(login_url.ends_with("&redirect_params=return_to%3D%252Fdashboard"));
+    /// ```
+    pub fn login_url_with_params(&self, redirect_params: &[(&str, &str)]) -> String {
+        let mut login_url = self.login_url();
+        if !redirect_params.is_empty() {
+            let params = url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(redirect_params)
+                .finish();
+            login_url.push_str("&redirect_params=");
+            login_url.extend(url::form_urlencoded::byte_serialize(params.as_bytes()));
         }
+        login_url
+    }
 
-        let url = self.build_url(&format!("/orders/{}/{}", variety, order_id), None);
-        let resp = self.send_request(url, "DELETE", Some(params)).await?;
-        self.raise_or_return_json(resp).await
+    /// Generates the login URL with an opaque `state` value echoed back on the redirect
+    ///
+    /// Multi-user web apps can pass a random per-login value here and compare the
+    /// `state` query parameter of the callback with it, to tie the callback to the
+    /// user who started the login and to reject forged callbacks.
+    pub fn login_url_with_state(&self, state: &str) -> String {
+        self.login_url_with_params(&[("state", state)])
     }
 
-    /// Exit a BO/CO order
-    pub async fn exit_order(
-        &self,
-        order_id: &str,
-        variety: &str,
-        parent_order_id: Option<&str>,
-    ) -> Result<JsonValue> {
-        self.cancel_order(order_id, variety, parent_order_id).await
+    /// Compute checksum for authentication - different implementations for native vs WASM
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn compute_checksum(&self, input: &str) -> Result<String> {
+        let mut hasher = Sha256::new();
+        hasher.update(input.as_bytes());
+        let result = hasher.finalize();
+        Ok(hex::encode(result))
     }
 
-    /// Retrieves a list of all orders for the current trading day
+    #[cfg(target_arch = "wasm32")]
+    async fn compute_checksum(&self, input: &str) -> Result<String> {
+        // WASM implementation using Web Crypto API
+        let window = window().ok_or_else(|| KiteError::Other("No window object".to_string()))?;
+        let crypto = window
+            .crypto()
+            .map_err(|_| KiteError::Other("No crypto object".to_string()))?;
+        let subtle = crypto.subtle();
+
+        let data = Uint8Array::from(input.as_bytes());
+        let digest_promise = subtle
+            .digest_with_str_and_u8_array("SHA-256", &data.to_vec())
+            .map_err(|_| KiteError::Other("Failed to create digest".to_string()))?;
+
+        let digest_result = JsFuture::from(digest_promise)
+            .await
+            .map_err(|_| KiteError::Other("Failed to compute hash".to_string()))?;
+
+        let digest_array = Uint8Array::new(&digest_result);
+        let digest_vec: Vec<u8> = digest_array.to_vec();
+        Ok(hex::encode(digest_vec))
+    }
+
+    /// Generates an access token using the request token from login
     /// 
-    /// Returns all orders placed by the user for the current trading day,
-    /// including pending, completed, rejected, and cancelled orders.
+    /// This method completes the authentication flow by exchanging the request token
+    /// (obtained after user login) for an access token that can be used for API calls.
+    /// The access token is automatically stored in the client instance.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `request_token` - The request token received after user login
+    /// * `api_secret` - Your KiteConnect API secret
     /// 
     /// # Returns
     /// 
-    /// A `Result<JsonValue>` containing orders data with fields like:
-    /// - `order_id` - Unique order identifier
-    /// - `tradingsymbol` - Trading symbol
-    /// - `quantity` - Order quantity
-    /// - `price` - Order price
-    /// - `status` - Order status (OPEN, COMPLETE, CANCELLED, REJECTED)
-    /// - `order_type` - Order type (MARKET, LIMIT, SL, SL-M)
-    /// - `product` - Product type (MIS, CNC, NRML)
+    /// A `Result<UserSession>` containing the session information including the
+    /// access token, refresh token, public token, user ID and login time
     /// 
     /// # Errors
     /// 
-    /// Returns an error if the API request fails or the user is not authenticated.
+    /// Returns an error if:
+    /// - The request token is invalid or expired
+    /// - The API secret is incorrect
+    /// - Network request fails
+    /// - Response parsing fails or the response carries no access token
     /// 
     /// # Example
     /// 
@@ -737,267 +1644,2382 @@ impl KiteConnect {
     /// 
     /// # #[tokio::main]
     /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    /// let client = KiteConnect::new("api_key", "access_token");
+    /// let mut client = KiteConnect::new("your_api_key", "");
     /// 
-    /// let orders = client.orders().await?;
-    /// println!("Orders: {:?}", orders);
+    /// // After user completes login and you receive the request_token
+    /// let session = client
+    ///     .generate_session("request_token_from_callback", "your_api_secret")
+    ///     .await?;
     /// 
-    /// // Check order statuses
-    /// if let Some(data) = orders["data"].as_array() {
-    ///     for order in data {
-    ///         println!("Order {}: {} - {}", 
-    ///             order["order_id"], 
-    ///             order["tradingsymbol"], 
-    ///             order["status"]);
-    ///     }
-    /// }
+    /// println!("Logged in as {} at {:?}", session.user_id, session.login_time);
+    /// // Access token is now automatically set in the client
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn orders(&self) -> Result<JsonValue> {
-        let url = self.build_url("/orders", None);
-        let resp = self.send_request(url, "GET", None).await?;
-        self.raise_or_return_json(resp).await
-    }
+    /// 
+    /// # Authentication Flow
+    /// 
+    /// 1. Call `login_url()` to get login URL
+    /// 2. User visits URL and completes login
+    /// 3. User is redirected with `request_token` parameter
+    /// 4. Call this method with the request token and API secret
+    /// 5. Access token is automatically set for subsequent API calls
+    pub async fn generate_session(
+        &self,
+        request_token: &str,
+        api_secret: &str,
+    ) -> Result<UserSession> {
+        // Create a hex digest from api key, request token, api secret
+        let input = format!("{}{}{}", self.api_key, request_token, api_secret);
+        let checksum = self.compute_checksum(&input).await?;
 
-    /// Get the list of order history
-    pub async fn order_history(&self, order_id: &str) -> Result<JsonValue> {
-        let params = vec![("order_id", order_id)];
-        let url = self.build_url("/orders", Some(params));
-        let resp = self.send_request(url, "GET", None).await?;
-        self.raise_or_return_json(resp).await
-    }
+        let api_key: &str = &self.api_key.clone();
+        let mut data = HashMap::new();
+        data.insert("api_key", api_key);
+        data.insert("request_token", request_token);
+        data.insert("checksum", checksum.as_str());
 
-    /// Get all trades
-    pub async fn trades(&self) -> Result<JsonValue> {
-        let url = self.build_url("/trades", None);
-        let resp = self.send_request(url, "GET", None).await?;
-        self.raise_or_return_json(resp).await
+        let url = self.build_url("/session/token", None);
+        let resp = self.send_request(url, "POST", Some(data)).await?;
+        let session: UserSession = self.raise_or_return_typed(resp).await?;
+
+        if session.access_token.is_empty() {
+            return Err(KiteError::Other(
+                "Session response did not contain an access token".to_string(),
+            ));
+        }
+        self.set_access_token(&session.access_token);
+        self.persist_tokens(StoredTokens {
+            access_token: session.access_token.clone(),
+            refresh_token: session.refresh_token.clone(),
+            user_id: session.user_id.clone(),
+        });
+        Ok(session)
     }
 
-    /// Get all trades for a specific order
-    pub async fn order_trades(&self, order_id: &str) -> Result<JsonValue> {
-        let url = self.build_url(&format!("/orders/{}/trades", order_id), None);
-        let resp = self.send_request(url, "GET", None).await?;
-        self.raise_or_return_json(resp).await
+    /// Invalidates the access token
+    pub async fn invalidate_access_token(&self, access_token: &str) -> Result<reqwest::Response> {
+        let url = self.build_url("/session/token", None);
+        let mut data = HashMap::new();
+        data.insert("access_token", access_token);
+
+        self.send_request(url, "DELETE", Some(data)).await
     }
 
-    /// Modify an open position product type
-    pub async fn convert_position(
+    /// Request for new access token
+    pub async fn renew_access_token(
         &self,
-        exchange: &str,
-        tradingsymbol: &str,
-        transaction_type: &str,
-        position_type: &str,
-        quantity: &str,
-        old_product: &str,
-        new_product: &str,
+        access_token: &str,
+        api_secret: &str,
     ) -> Result<JsonValue> {
-        let mut params = HashMap::new();
-        params.insert("exchange", exchange);
-        params.insert("tradingsymbol", tradingsymbol);
-        params.insert("transaction_type", transaction_type);
-        params.insert("position_type", position_type);
-        params.insert("quantity", quantity);
-        params.insert("old_product", old_product);
-        params.insert("new_product", new_product);
+        // Create a hex digest from api key, request token, api secret
+        let input = format!("{}{}{}", self.api_key, access_token, api_secret);
+        let checksum = self.compute_checksum(&input).await?;
 
-        let url = self.build_url("/portfolio/positions", None);
-        let resp = self.send_request(url, "PUT", Some(params)).await?;
-        self.raise_or_return_json(resp).await
+        let api_key: &str = &self.api_key.clone();
+        let mut data = HashMap::new();
+        data.insert("api_key", api_key);
+        data.insert("access_token", access_token);
+        data.insert("checksum", checksum.as_str());
+
+        let url = self.build_url("/session/refresh_token", None);
+        let resp = self.send_request(url, "POST", Some(data)).await?;
+
+        if resp.status().is_success() {
+            let jsn: JsonValue = resp.json().await?;
+            let data = if jsn["data"].is_object() { &jsn["data"] } else { &jsn };
+            let access_token = data["access_token"].as_str().ok_or_else(|| {
+                KiteError::Other("Renewal response did not contain an access token".to_string())
+            })?;
+            self.set_access_token(access_token);
+
+            if let Some(store) = &self.token_store {
+                let previous = store.load().ok().flatten().unwrap_or_default();
+                self.persist_tokens(StoredTokens {
+                    access_token: access_token.to_string(),
+                    refresh_token: data["refresh_token"]
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or(previous.refresh_token),
+                    user_id: data["user_id"].as_str().map(str::to_string).unwrap_or(previous.user_id),
+                });
+            }
+            if self.unwrap_envelope {
+                extract_data(jsn)
+            } else {
+                Ok(jsn)
+            }
+        } else {
+            let sent = self.access_token();
+            let status = resp.status().as_u16();
+            let retry_after = retry::retry_after(resp.headers());
+            let content_type = content_type(&resp);
+            let error_text = resp.text().await?;
+            let err = KiteError::from_response(status, content_type.as_deref(), &error_text)
+                .with_retry_after(retry_after);
+            Err(self.handle_api_error(err, &sent).await)
+        }
     }
 
-    /// Get all mutual fund orders or individual order info
-    pub async fn mf_orders(&self, order_id: Option<&str>) -> Result<JsonValue> {
-        let url: reqwest::Url = if let Some(order_id) = order_id {
-            self.build_url(&format!("/mf/orders/{}", order_id), None)
+    /// Invalidates the refresh token
+    pub async fn invalidate_refresh_token(&self, refresh_token: &str) -> Result<reqwest::Response> {
+        let url = self.build_url("/session/refresh_token", None);
+        let mut data = HashMap::new();
+        data.insert("refresh_token", refresh_token);
+
+        self.send_request(url, "DELETE", Some(data)).await
+    }
+
+    /// Retrieves account balance and margin details
+    /// 
+    /// Returns margin information for trading segments including available cash,
+    /// used margins, and available margins for different product types.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `segment` - Optional trading segment ("equity" or "commodity"). If None, returns all segments
+    /// 
+    /// # Returns
+    /// 
+    /// A `Result<JsonValue>` containing margin data with fields like:
+    /// - `available` - Available margin for trading
+    /// - `utilised` - Currently utilized margin
+    /// - `net` - Net available margin
+    /// - `enabled` - Whether the segment is enabled
+    /// 
+    /// # Errors
+    /// 
+    /// Returns an error if the API request fails or the user is not authenticated.
+    /// 
+    /// # Example
+    /// 
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    /// 
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// 
+    /// // Get margins for all segments
+    /// let all_margins = client.margins(None).await?;
+    /// println!("All margins: {:?}", all_margins);
+    /// 
+    /// // Get margins for specific segment
+    /// let equity_margins = client.margins(Some("equity".to_string())).await?;
+    /// println!("Equity available margin: {}", 
+    ///     equity_margins["data"]["available"]["live_balance"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn margins(&self, segment: Option<String>) -> Result<JsonValue> {
+        let url: reqwest::Url = if let Some(segment) = segment {
+            self.build_url(&format!("/user/margins/{}", segment), None)
         } else {
-            self.build_url("/mf/orders", None)
+            self.build_url("/user/margins", None)
         };
 
         let resp = self.send_request(url, "GET", None).await?;
         self.raise_or_return_json(resp).await
     }
 
-    /// Get the trigger range for a list of instruments
-    pub async fn trigger_range(
-        &self,
-        transaction_type: &str,
-        instruments: Vec<&str>,
-    ) -> Result<JsonValue> {
-        let mut params: Vec<(&str, &str)> = Vec::new();
-        params.push(("transaction_type", transaction_type));
-        
-        for instrument in instruments {
-            params.push(("instruments", instrument));
-        }
+    /// Retrieves account balance and margin details for all segments as a typed model
+    pub async fn margins_typed(&self) -> Result<UserMargins> {
+        let url = self.build_url("/user/margins", None);
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_typed(resp).await
+    }
 
-        let url = self.build_url("/instruments/trigger_range", Some(params));
+    /// Retrieves account balance and margin details for a single segment as a typed model
+    pub async fn segment_margins_typed(&self, segment: &str) -> Result<SegmentMargin> {
+        let url = self.build_url(&format!("/user/margins/{}", segment), None);
         let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_typed(resp).await
+    }
+
+    /// Get the margins orders would block, without placing them
+    ///
+    /// `orders` is a JSON array of orders, each with the `exchange`,
+    /// `tradingsymbol`, `transaction_type`, `variety`, `product`,
+    /// `order_type`, `quantity`, `price` and `trigger_price` of the order.
+    pub async fn order_margins(&self, orders: &JsonValue) -> Result<JsonValue> {
+        let url = self.build_url("/margins/orders", None);
+        let resp = self.send_json_request(url, "POST", orders).await?;
         self.raise_or_return_json(resp).await
     }
 
-    /// Get instruments list
-    #[cfg(not(target_arch = "wasm32"))]
-    pub async fn instruments(&self, exchange: Option<&str>) -> Result<JsonValue> {
-        let url: reqwest::Url = if let Some(exchange) = exchange {
-            self.build_url(&format!("/instruments/{}", exchange), None)
-        } else {
-            self.build_url("/instruments", None)
-        };
+    /// Get the margins orders would block as typed models
+    pub async fn order_margins_typed(&self, orders: &JsonValue) -> Result<Vec<OrderMargin>> {
+        let url = self.build_url("/margins/orders", None);
+        let resp = self.send_json_request(url, "POST", orders).await?;
+        self.raise_or_return_typed(resp).await
+    }
 
+    /// Get user profile details
+    pub async fn profile(&self) -> Result<JsonValue> {
+        let url = self.build_url("/user/profile", None);
         let resp = self.send_request(url, "GET", None).await?;
-        let body = resp.text().await?;
-        
-        // Parse CSV response
-        let mut rdr = ReaderBuilder::new().from_reader(body.as_bytes());
-        let mut result = Vec::new();
-        
-        let headers = rdr.headers()?.clone();
-        for record in rdr.records() {
-            let record = record?;
-            let mut obj = serde_json::Map::new();
-            
-            for (i, field) in record.iter().enumerate() {
-                if let Some(header) = headers.get(i) {
-                    obj.insert(header.to_string(), JsonValue::String(field.to_string()));
-                }
-            }
-            result.push(JsonValue::Object(obj));
-        }
-        
-        Ok(JsonValue::Array(result))
+        self.raise_or_return_json(resp).await
     }
 
-    /// Get instruments list (WASM version - returns raw CSV as string)
-    #[cfg(target_arch = "wasm32")]
-    pub async fn instruments(&self, exchange: Option<&str>) -> Result<JsonValue> {
-        let url: reqwest::Url = if let Some(exchange) = exchange {
-            self.build_url(&format!("/instruments/{}", exchange), None)
-        } else {
-            self.build_url("/instruments", None)
-        };
-
+    /// Get user profile details as a typed model
+    pub async fn profile_typed(&self) -> Result<Profile> {
+        let url = self.build_url("/user/profile", None);
         let resp = self.send_request(url, "GET", None).await?;
-        let body = resp.text().await?;
-        
-        // For WASM, return the raw CSV data as a string
-        // Users can parse it client-side using JS CSV libraries
-        Ok(JsonValue::String(body))
+        self.raise_or_return_typed(resp).await
     }
 
-    /// Get mutual fund instruments list
-    #[cfg(not(target_arch = "wasm32"))]
-    pub async fn mf_instruments(&self) -> Result<JsonValue> {
-        let url = self.build_url("/mf/instruments", None);
-        let resp = self.send_request(url, "GET", None).await?;
-        let body = resp.text().await?;
-        
-        // Parse CSV response
-        let mut rdr = ReaderBuilder::new().from_reader(body.as_bytes());
-        let mut result = Vec::new();
-        
-        let headers = rdr.headers()?.clone();
-        for record in rdr.records() {
-            let record = record?;
-            let mut obj = serde_json::Map::new();
-            
-            for (i, field) in record.iter().enumerate() {
-                if let Some(header) = headers.get(i) {
-                    obj.insert(header.to_string(), JsonValue::String(field.to_string()));
+    /// Retrieves the user's holdings (stocks held in demat account)
+    /// 
+    /// Holdings represent stocks that are held in the user's demat account.
+    /// This includes information about quantity, average price, current market value,
+    /// profit/loss, and more.
+    /// 
+    /// # Returns
+    /// 
+    /// A `Result<JsonValue>` containing holdings data with fields like:
+    /// - `tradingsymbol` - Trading symbol of the instrument
+    /// - `quantity` - Total quantity held
+    /// - `average_price` - Average buying price
+    /// - `last_price` - Current market price
+    /// - `pnl` - Profit and loss
+    /// - `product` - Product type (CNC, MIS, etc.)
+    /// 
+    /// # Errors
+    /// 
+    /// Returns an error if the API request fails or the user is not authenticated.
+    /// 
+    /// # Example
+    /// 
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    /// 
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// 
+    /// let holdings = client.holdings().await?;
+    /// println!("Holdings: {:?}", holdings);
+    /// 
+    /// // Access specific fields
+    /// if let Some(data) = holdings["data"].as_array() {
+    ///     for holding in data {
+    ///         println!("Symbol: {}, Quantity: {}", 
+    ///             holding["tradingsymbol"], holding["quantity"]);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn holdings(&self) -> Result<JsonValue> {
+        let url = self.build_url("/portfolio/holdings", None);
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_json(resp).await
+    }
+
+    /// Retrieves the user's holdings as typed models
+    pub async fn holdings_typed(&self) -> Result<Vec<Holding>> {
+        let url = self.build_url("/portfolio/holdings", None);
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_typed(resp).await
+    }
+
+    /// Retrieves the user's positions (open positions for the day)
+    /// 
+    /// Positions represent open trading positions for the current trading day.
+    /// This includes both intraday and carry-forward positions with details about
+    /// profit/loss, margin requirements, and position status.
+    /// 
+    /// # Returns
+    /// 
+    /// A `Result<JsonValue>` containing positions data with fields like:
+    /// - `tradingsymbol` - Trading symbol of the instrument
+    /// - `quantity` - Net position quantity
+    /// - `buy_quantity` - Total buy quantity
+    /// - `sell_quantity` - Total sell quantity
+    /// - `average_price` - Average position price
+    /// - `pnl` - Realized and unrealized P&L
+    /// - `product` - Product type (MIS, CNC, NRML)
+    /// 
+    /// # Errors
+    /// 
+    /// Returns an error if the API request fails or the user is not authenticated.
+    /// 
+    /// # Example
+    /// 
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    /// 
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// 
+    /// let positions = client.positions().await?;
+    /// println!("Positions: {:?}", positions);
+    /// 
+    /// // Check for open positions
+    /// if let Some(day_positions) = positions["data"]["day"].as_array() {
+    ///     for position in day_positions {
+    ///         if position["quantity"].as_i64().unwrap_or(0) != 0 {
+    ///             println!("Open position: {} qty {}", 
+    ///                 position["tradingsymbol"], position["quantity"]);
+    ///         }
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn positions(&self) -> Result<JsonValue> {
+        let url = self.build_url("/portfolio/positions", None);
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_json(resp).await
+    }
+
+    /// Retrieves the user's net and day positions as typed models
+    pub async fn positions_typed(&self) -> Result<Positions> {
+        let url = self.build_url("/portfolio/positions", None);
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_typed(resp).await
+    }
+
+    /// Place an order
+    ///
+    /// With [order checks](Self::set_order_checks) enabled, the price, trigger
+    /// price and quantity are checked against the instrument first. With
+    /// [auto slicing](Self::set_auto_slice) enabled, a quantity beyond the
+    /// freeze quantity or the maximum clip is placed as several orders like
+    /// with [`place_order_sliced`](Self::place_order_sliced); the response
+    /// then carries the ID of the first slice as `order_id` and the IDs of all
+    /// of them as `order_ids`. With a [dedupe window](Self::set_dedupe_window)
+    /// set, repeats of an order placed within it are not placed again. With
+    /// [after hours](Self::set_after_hours) handling set, regular orders
+    /// placed while the exchange is closed become AMO orders or fail.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn place_order(
+        &self,
+        variety: &str,
+        exchange: &str,
+        tradingsymbol: &str,
+        transaction_type: &str,
+        quantity: &str,
+        product: Option<&str>,
+        order_type: Option<&str>,
+        price: Option<&str>,
+        validity: Option<&str>,
+        disclosed_quantity: Option<&str>,
+        trigger_price: Option<&str>,
+        squareoff: Option<&str>,
+        stoploss: Option<&str>,
+        trailing_stoploss: Option<&str>,
+        tag: Option<&str>,
+    ) -> Result<JsonValue> {
+        self.place_order_protected(
+            variety, exchange, tradingsymbol, transaction_type, quantity, product, order_type, price, validity,
+            disclosed_quantity, trigger_price, squareoff, stoploss, trailing_stoploss, tag, None,
+        )
+        .await
+    }
+
+    /// Places an order like [`place_order`](Self::place_order), sending the
+    /// market protection percentage of market and SL-M orders if given
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn place_order_protected(
+        &self,
+        variety: &str,
+        exchange: &str,
+        tradingsymbol: &str,
+        transaction_type: &str,
+        quantity: &str,
+        product: Option<&str>,
+        order_type: Option<&str>,
+        price: Option<&str>,
+        validity: Option<&str>,
+        disclosed_quantity: Option<&str>,
+        trigger_price: Option<&str>,
+        squareoff: Option<&str>,
+        stoploss: Option<&str>,
+        trailing_stoploss: Option<&str>,
+        tag: Option<&str>,
+        market_protection: Option<&str>,
+    ) -> Result<JsonValue> {
+        let variety = self.session_variety(variety, exchange, ist::now())?;
+        let place = self.place_order_slices(
+            variety, exchange, tradingsymbol, transaction_type, quantity, product, order_type, price, validity,
+            disclosed_quantity, trigger_price, squareoff, stoploss, trailing_stoploss, tag, market_protection,
+        );
+        match &self.dedupe {
+            Some(dedupe) => {
+                let key = DedupeKey::new(exchange, tradingsymbol, transaction_type, quantity, tag);
+                self.place_deduped(dedupe, key, place).await
+            }
+            None => place.await,
+        }
+    }
+
+    /// Places an order, sliced if auto slicing is enabled
+    #[allow(clippy::too_many_arguments)]
+    async fn place_order_slices(
+        &self,
+        variety: &str,
+        exchange: &str,
+        tradingsymbol: &str,
+        transaction_type: &str,
+        quantity: &str,
+        product: Option<&str>,
+        order_type: Option<&str>,
+        price: Option<&str>,
+        validity: Option<&str>,
+        disclosed_quantity: Option<&str>,
+        trigger_price: Option<&str>,
+        squareoff: Option<&str>,
+        stoploss: Option<&str>,
+        trailing_stoploss: Option<&str>,
+        tag: Option<&str>,
+        market_protection: Option<&str>,
+    ) -> Result<JsonValue> {
+        if self.auto_slice.is_some() {
+            let slices = self.order_slices(exchange, tradingsymbol, quantity)?;
+            if slices.len() > 1 {
+                let order_ids = self
+                    .place_slices(
+                        &slices, variety, exchange, tradingsymbol, transaction_type, product, order_type, price,
+                        validity, disclosed_quantity, trigger_price, squareoff, stoploss, trailing_stoploss, tag,
+                        market_protection,
+                    )
+                    .await?;
+                let data = serde_json::json!({ "order_id": order_ids[0], "order_ids": order_ids });
+                if self.unwrap_envelope {
+                    return Ok(data);
+                }
+                return Ok(serde_json::json!({ "status": "success", "data": data }));
+            }
+        }
+        self.place_single_order(
+            variety, exchange, tradingsymbol, transaction_type, quantity, product, order_type, price, validity,
+            disclosed_quantity, trigger_price, squareoff, stoploss, trailing_stoploss, tag, market_protection,
+        )
+        .await
+    }
+
+    /// Places an order as it is, after the order checks
+    #[allow(clippy::too_many_arguments)]
+    async fn place_single_order(
+        &self,
+        variety: &str,
+        exchange: &str,
+        tradingsymbol: &str,
+        transaction_type: &str,
+        quantity: &str,
+        product: Option<&str>,
+        order_type: Option<&str>,
+        price: Option<&str>,
+        validity: Option<&str>,
+        disclosed_quantity: Option<&str>,
+        trigger_price: Option<&str>,
+        squareoff: Option<&str>,
+        stoploss: Option<&str>,
+        trailing_stoploss: Option<&str>,
+        tag: Option<&str>,
+        market_protection: Option<&str>,
+    ) -> Result<JsonValue> {
+        self.check_order(exchange, tradingsymbol, quantity, [price, trigger_price])?;
+
+        let mut params = HashMap::new();
+        params.insert("variety", variety);
+        params.insert("exchange", exchange);
+        params.insert("tradingsymbol", tradingsymbol);
+        params.insert("transaction_type", transaction_type);
+        params.insert("quantity", quantity);
+        
+        if let Some(product) = product { params.insert("product", product); }
+        if let Some(order_type) = order_type { params.insert("order_type", order_type); }
+        if let Some(price) = price { params.insert("price", price); }
+        if let Some(validity) = validity { params.insert("validity", validity); }
+        if let Some(disclosed_quantity) = disclosed_quantity { params.insert("disclosed_quantity", disclosed_quantity); }
+        if let Some(trigger_price) = trigger_price { params.insert("trigger_price", trigger_price); }
+        if let Some(squareoff) = squareoff { params.insert("squareoff", squareoff); }
+        if let Some(stoploss) = stoploss { params.insert("stoploss", stoploss); }
+        if let Some(trailing_stoploss) = trailing_stoploss { params.insert("trailing_stoploss", trailing_stoploss); }
+        if let Some(tag) = tag { params.insert("tag", tag); }
+        if let Some(market_protection) = market_protection { params.insert("market_protection", market_protection); }
+
+        self.send_mutation(&format!("/orders/{}", variety), "POST", Some(params)).await
+    }
+
+    /// Place an order, split into several orders if its quantity reaches the
+    /// freeze quantity of the instrument, returning the IDs of all of them
+    ///
+    /// Takes the parameters of [`place_order`](Self::place_order). The orders
+    /// are placed one after another, each of the largest multiple of the lot
+    /// size below the [freeze quantity](Self::set_freeze_limits). The
+    /// underlying and lot size come from the
+    /// [order checks](Self::set_order_checks) store when the instrument is
+    /// listed there, and are otherwise taken from the tradingsymbol with a lot
+    /// size of one. Quantities below the freeze quantity are placed as a
+    /// single order. With [auto slicing](Self::set_auto_slice) enabled, its
+    /// clip and the delay between slices apply as well.
+    ///
+    /// Placement stops at the first rejected slice and returns its error; the
+    /// slices placed before it stay open and are logged.
+    ///
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> kiteconnect::error::Result<()> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// let order_ids = client
+    ///     .place_order_sliced(
+    ///         "regular", "NFO", "NIFTY24JUNFUT", "BUY", "4500", Some("NRML"), Some("MARKET"),
+    ///         None, None, None, None, None, None, None, None,
+    ///     )
+    ///     .await?;
+    /// println!("Placed {} orders", order_ids.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub async fn place_order_sliced(
+        &self,
+        variety: &str,
+        exchange: &str,
+        tradingsymbol: &str,
+        transaction_type: &str,
+        quantity: &str,
+        product: Option<&str>,
+        order_type: Option<&str>,
+        price: Option<&str>,
+        validity: Option<&str>,
+        disclosed_quantity: Option<&str>,
+        trigger_price: Option<&str>,
+        squareoff: Option<&str>,
+        stoploss: Option<&str>,
+        trailing_stoploss: Option<&str>,
+        tag: Option<&str>,
+    ) -> Result<Vec<String>> {
+        let slices = self.order_slices(exchange, tradingsymbol, quantity)?;
+        self.place_slices(
+            &slices, variety, exchange, tradingsymbol, transaction_type, product, order_type, price, validity,
+            disclosed_quantity, trigger_price, squareoff, stoploss, trailing_stoploss, tag, None,
+        )
+        .await
+    }
+
+    /// Splits the quantity of an order by the freeze quantity of its
+    /// instrument and the auto slicing clip
+    fn order_slices(&self, exchange: &str, tradingsymbol: &str, quantity: &str) -> Result<Vec<u32>> {
+        let total: u32 = quantity
+            .trim()
+            .parse()
+            .map_err(|_| KiteError::Other(format!("invalid quantity {:?}", quantity)))?;
+        let instrument = self
+            .order_checks
+            .as_ref()
+            .and_then(|store| store.find(exchange, tradingsymbol))
+            .cloned()
+            .unwrap_or_else(|| Instrument {
+                name: DerivativeSymbol::parse(tradingsymbol)
+                    .map(|symbol| symbol.underlying)
+                    .unwrap_or_default(),
+                tradingsymbol: tradingsymbol.to_string(),
+                exchange: exchange.to_string(),
+                lot_size: 1,
+                ..Default::default()
+            });
+        Ok(match &self.auto_slice {
+            Some(auto_slice) => auto_slice.slice(&self.freeze_limits, total, &instrument),
+            None => self.freeze_limits.slice(total, &instrument),
+        })
+    }
+
+    /// Places the slices of an order one after another, pausing between them
+    /// as auto slicing says
+    #[allow(clippy::too_many_arguments)]
+    async fn place_slices(
+        &self,
+        slices: &[u32],
+        variety: &str,
+        exchange: &str,
+        tradingsymbol: &str,
+        transaction_type: &str,
+        product: Option<&str>,
+        order_type: Option<&str>,
+        price: Option<&str>,
+        validity: Option<&str>,
+        disclosed_quantity: Option<&str>,
+        trigger_price: Option<&str>,
+        squareoff: Option<&str>,
+        stoploss: Option<&str>,
+        trailing_stoploss: Option<&str>,
+        tag: Option<&str>,
+        market_protection: Option<&str>,
+    ) -> Result<Vec<String>> {
+        let delay = self.auto_slice.map(|auto_slice| auto_slice.delay).unwrap_or_default();
+        let mut order_ids = Vec::with_capacity(slices.len());
+        for slice in slices {
+            if !order_ids.is_empty() && !delay.is_zero() {
+                crate::rt::sleep(delay).await;
+            }
+            let placed = self
+                .place_single_order(
+                    variety, exchange, tradingsymbol, transaction_type, &slice.to_string(), product,
+                    order_type, price, validity, disclosed_quantity, trigger_price, squareoff, stoploss,
+                    trailing_stoploss, tag, market_protection,
+                )
+                .await;
+            let order_id = placed.and_then(|data| crate::orders::order_id(&data));
+            match order_id {
+                Ok(order_id) => order_ids.push(order_id),
+                Err(e) => {
+                    if !order_ids.is_empty() {
+                        log::warn!(
+                            "Placed {} of {} slices of {}:{} before failing, orders {}",
+                            order_ids.len(),
+                            slices.len(),
+                            exchange,
+                            tradingsymbol,
+                            order_ids.join(", ")
+                        );
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(order_ids)
+    }
+
+    /// Modify an open order
+    ///
+    /// A modification turned down because the order is still being processed
+    /// is only sent again if the retry policy allows it, see
+    /// [`RetryPolicy::order_processing_retries`]. The default client never retries.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn modify_order(
+        &self,
+        order_id: &str,
+        variety: &str,
+        quantity: Option<&str>,
+        price: Option<&str>,
+        order_type: Option<&str>,
+        validity: Option<&str>,
+        disclosed_quantity: Option<&str>,
+        trigger_price: Option<&str>,
+        parent_order_id: Option<&str>,
+    ) -> Result<JsonValue> {
+        let mut params = HashMap::new();
+        params.insert("order_id", order_id);
+        params.insert("variety", variety);
+        
+        if let Some(quantity) = quantity { params.insert("quantity", quantity); }
+        if let Some(price) = price { params.insert("price", price); }
+        if let Some(order_type) = order_type { params.insert("order_type", order_type); }
+        if let Some(validity) = validity { params.insert("validity", validity); }
+        if let Some(disclosed_quantity) = disclosed_quantity { params.insert("disclosed_quantity", disclosed_quantity); }
+        if let Some(trigger_price) = trigger_price { params.insert("trigger_price", trigger_price); }
+        if let Some(parent_order_id) = parent_order_id { params.insert("parent_order_id", parent_order_id); }
+
+        self.send_mutation(&format!("/orders/{}/{}", variety, order_id), "PUT", Some(params)).await
+    }
+
+    /// Cancel an order
+    ///
+    /// Like [`modify_order`](Self::modify_order), a cancellation of an order
+    /// still being processed is only retried when the retry policy opts in with
+    /// [`RetryPolicy::order_processing_retries`].
+    pub async fn cancel_order(
+        &self,
+        order_id: &str,
+        variety: &str,
+        parent_order_id: Option<&str>,
+    ) -> Result<JsonValue> {
+        let mut params = HashMap::new();
+        params.insert("order_id", order_id);
+        params.insert("variety", variety);
+        if let Some(parent_order_id) = parent_order_id {
+            params.insert("parent_order_id", parent_order_id);
+        }
+
+        self.send_mutation(&format!("/orders/{}/{}", variety, order_id), "DELETE", Some(params)).await
+    }
+
+    /// Exit a BO/CO order
+    pub async fn exit_order(
+        &self,
+        order_id: &str,
+        variety: &str,
+        parent_order_id: Option<&str>,
+    ) -> Result<JsonValue> {
+        self.cancel_order(order_id, variety, parent_order_id).await
+    }
+
+    /// Retrieves a list of all orders for the current trading day
+    /// 
+    /// Returns all orders placed by the user for the current trading day,
+    /// including pending, completed, rejected, and cancelled orders.
+    /// 
+    /// # Returns
+    /// 
+    /// A `Result<JsonValue>` containing orders data with fields like:
+    /// - `order_id` - Unique order identifier
+    /// - `tradingsymbol` - Trading symbol
+    /// - `quantity` - Order quantity
+    /// - `price` - Order price
+    /// - `status` - Order status (OPEN, COMPLETE, CANCELLED, REJECTED)
+    /// - `order_type` - Order type (MARKET, LIMIT, SL, SL-M)
+    /// - `product` - Product type (MIS, CNC, NRML)
+    /// 
+    /// # Errors
+    /// 
+    /// Returns an error if the API request fails or the user is not authenticated.
+    /// 
+    /// # Example
+    /// 
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    /// 
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// 
+    /// let orders = client.orders().await?;
+    /// println!("Orders: {:?}", orders);
+    /// 
+    /// // Check order statuses
+    /// if let Some(data) = orders["data"].as_array() {
+    ///     for order in data {
+    ///         println!("Order {}: {} - {}", 
+    ///             order["order_id"], 
+    ///             order["tradingsymbol"], 
+    ///             order["status"]);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn orders(&self) -> Result<JsonValue> {
+        let url = self.build_url("/orders", None);
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_json(resp).await
+    }
+
+    /// Retrieves all orders for the current trading day as typed models
+    pub async fn orders_typed(&self) -> Result<Vec<Order>> {
+        let url = self.build_url("/orders", None);
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_typed(resp).await
+    }
+
+    /// Get the list of order history
+    pub async fn order_history(&self, order_id: &str) -> Result<JsonValue> {
+        let params = vec![("order_id", order_id)];
+        let url = self.build_url("/orders", Some(params));
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_json(resp).await
+    }
+
+    /// Get the list of order history as typed models
+    pub async fn order_history_typed(&self, order_id: &str) -> Result<Vec<Order>> {
+        let params = vec![("order_id", order_id)];
+        let url = self.build_url("/orders", Some(params));
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_typed(resp).await
+    }
+
+    /// Get all trades
+    pub async fn trades(&self) -> Result<JsonValue> {
+        let url = self.build_url("/trades", None);
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_json(resp).await
+    }
+
+    /// Get all trades as typed models
+    pub async fn trades_typed(&self) -> Result<Vec<Trade>> {
+        let url = self.build_url("/trades", None);
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_typed(resp).await
+    }
+
+    /// Get all trades for a specific order
+    pub async fn order_trades(&self, order_id: &str) -> Result<JsonValue> {
+        let url = self.build_url(&format!("/orders/{}/trades", order_id), None);
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_json(resp).await
+    }
+
+    /// Get all trades for a specific order as typed models
+    pub async fn order_trades_typed(&self, order_id: &str) -> Result<Vec<Trade>> {
+        let url = self.build_url(&format!("/orders/{}/trades", order_id), None);
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_typed(resp).await
+    }
+
+    /// Modify an open position product type
+    #[allow(clippy::too_many_arguments)]
+    pub async fn convert_position(
+        &self,
+        exchange: &str,
+        tradingsymbol: &str,
+        transaction_type: &str,
+        position_type: &str,
+        quantity: &str,
+        old_product: &str,
+        new_product: &str,
+    ) -> Result<JsonValue> {
+        let mut params = HashMap::new();
+        params.insert("exchange", exchange);
+        params.insert("tradingsymbol", tradingsymbol);
+        params.insert("transaction_type", transaction_type);
+        params.insert("position_type", position_type);
+        params.insert("quantity", quantity);
+        params.insert("old_product", old_product);
+        params.insert("new_product", new_product);
+
+        self.send_mutation("/portfolio/positions", "PUT", Some(params)).await
+    }
+
+    /// Place a GTT (good till triggered) trigger
+    ///
+    /// `trigger_type` is `single` or `two-leg`. `condition` holds the
+    /// `exchange`, `tradingsymbol`, `trigger_values` and `last_price` of the
+    /// trigger, and `orders` the orders placed when it fires, one per trigger
+    /// value. See [`place_gtt_oco`](Self::place_gtt_oco) for building a
+    /// two-leg trigger.
+    pub async fn place_gtt(&self, trigger_type: &str, condition: &JsonValue, orders: &JsonValue) -> Result<JsonValue> {
+        let condition = condition.to_string();
+        let orders = orders.to_string();
+        let mut params = HashMap::new();
+        params.insert("type", trigger_type);
+        params.insert("condition", condition.as_str());
+        params.insert("orders", orders.as_str());
+
+        self.send_mutation("/gtt/triggers", "POST", Some(params)).await
+    }
+
+    /// Get all GTT triggers
+    pub async fn gtts(&self) -> Result<JsonValue> {
+        let url = self.build_url("/gtt/triggers", None);
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_json(resp).await
+    }
+
+    /// Get a GTT trigger
+    pub async fn gtt(&self, trigger_id: u64) -> Result<JsonValue> {
+        let url = self.build_url(&format!("/gtt/triggers/{}", trigger_id), None);
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_json(resp).await
+    }
+
+    /// Delete a GTT trigger
+    pub async fn delete_gtt(&self, trigger_id: u64) -> Result<JsonValue> {
+        self.send_mutation(&format!("/gtt/triggers/{}", trigger_id), "DELETE", None).await
+    }
+
+    /// Get all mutual fund orders or individual order info
+    pub async fn mf_orders(&self, order_id: Option<&str>) -> Result<JsonValue> {
+        let url: reqwest::Url = if let Some(order_id) = order_id {
+            self.build_url(&format!("/mf/orders/{}", order_id), None)
+        } else {
+            self.build_url("/mf/orders", None)
+        };
+
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_json(resp).await
+    }
+
+    /// Get all mutual fund orders as typed models
+    pub async fn mf_orders_typed(&self) -> Result<Vec<MfOrder>> {
+        let url = self.build_url("/mf/orders", None);
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_typed(resp).await
+    }
+
+    /// Get an individual mutual fund order as a typed model
+    pub async fn mf_order_typed(&self, order_id: &str) -> Result<MfOrder> {
+        let url = self.build_url(&format!("/mf/orders/{}", order_id), None);
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_typed(resp).await
+    }
+
+    /// Get full market quotes of instruments, given as `EXCHANGE:TRADINGSYMBOL`
+    /// names or instrument tokens
+    pub async fn quote(&self, instruments: Vec<&str>) -> Result<JsonValue> {
+        let params: Vec<(&str, &str)> = instruments.into_iter().map(|instrument| ("i", instrument)).collect();
+        let url = self.build_url("/quote", Some(params));
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_json(resp).await
+    }
+
+    /// Get the last traded prices of instruments, given as `EXCHANGE:TRADINGSYMBOL`
+    /// names or instrument tokens
+    pub async fn ltp(&self, instruments: Vec<&str>) -> Result<JsonValue> {
+        let params: Vec<(&str, &str)> = instruments.into_iter().map(|instrument| ("i", instrument)).collect();
+        let url = self.build_url("/quote/ltp", Some(params));
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_json(resp).await
+    }
+
+    /// Get full market quotes of instruments by instrument token
+    ///
+    /// The quotes are keyed by the instrument tokens, formatted as strings.
+    pub async fn quote_tokens(
+        &self,
+        tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>,
+    ) -> Result<JsonValue> {
+        let tokens: Vec<String> = tokens.into_iter().map(|token| token.into().to_string()).collect();
+        self.quote(tokens.iter().map(String::as_str).collect()).await
+    }
+
+    /// Get the last traded prices of instruments by instrument token
+    ///
+    /// The prices are keyed by the instrument tokens, formatted as strings.
+    pub async fn ltp_tokens(
+        &self,
+        tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>,
+    ) -> Result<JsonValue> {
+        let tokens: Vec<String> = tokens.into_iter().map(|token| token.into().to_string()).collect();
+        self.ltp(tokens.iter().map(String::as_str).collect()).await
+    }
+
+    /// Get the trigger range for a list of instruments
+    pub async fn trigger_range(
+        &self,
+        transaction_type: &str,
+        instruments: Vec<&str>,
+    ) -> Result<JsonValue> {
+        let mut params: Vec<(&str, &str)> = Vec::new();
+        params.push(("transaction_type", transaction_type));
+        
+        for instrument in instruments {
+            params.push(("instruments", instrument));
+        }
+
+        let url = self.build_url("/instruments/trigger_range", Some(params));
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_json(resp).await
+    }
+
+    /// Get the trigger ranges of cover orders for instruments, keyed by their
+    /// `EXCHANGE:TRADINGSYMBOL` names
+    ///
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    /// use kiteconnect::models::Exchange;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> kiteconnect::error::Result<()> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// let ranges = client
+    ///     .trigger_range_typed("BUY", &[(Exchange::Nse, "INFY"), (Exchange::Nse, "RELIANCE")])
+    ///     .await?;
+    /// let infy = &ranges["NSE:INFY"];
+    /// println!("Trigger between {} and {}", infy.lower, infy.upper);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn trigger_range_typed(
+        &self,
+        transaction_type: &str,
+        instruments: &[(Exchange, &str)],
+    ) -> Result<HashMap<String, TriggerRange>> {
+        let names: Vec<String> = instruments
+            .iter()
+            .map(|(exchange, tradingsymbol)| exchange.instrument(tradingsymbol))
+            .collect();
+        let mut params = vec![("transaction_type", transaction_type)];
+        params.extend(names.iter().map(|name| ("instruments", name.as_str())));
+
+        let url = self.build_url("/instruments/trigger_range", Some(params));
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_typed(resp).await
+    }
+
+    /// Get historical candles of an instrument between `from` and `to`, given
+    /// as `yyyy-mm-dd` dates or `yyyy-mm-dd hh:mm:ss` timestamps
+    ///
+    /// `interval` is one of `minute`, `3minute`, `5minute`, `10minute`,
+    /// `15minute`, `30minute`, `60minute` or `day`. `continuous` stitches the
+    /// candles of expired futures together, and `oi` adds the open interest
+    /// to every candle.
+    pub async fn historical_data(
+        &self,
+        instrument_token: impl Into<InstrumentToken>,
+        interval: &str,
+        from: &str,
+        to: &str,
+        continuous: bool,
+        oi: bool,
+    ) -> Result<JsonValue> {
+        let flag = |enabled: bool| if enabled { "1" } else { "0" };
+        let params = vec![("from", from), ("to", to), ("continuous", flag(continuous)), ("oi", flag(oi))];
+        let url = self.build_url(
+            &format!("/instruments/historical/{}/{}", instrument_token.into(), interval),
+            Some(params),
+        );
+        let resp = self.send_request(url, "GET", None).await?;
+        self.raise_or_return_json(resp).await
+    }
+
+    /// Get instruments list
+    ///
+    /// Every row of the CSV dump becomes an object keyed by the header, with
+    /// every field as a string, on native and `wasm32` targets alike.
+    pub async fn instruments(&self, exchange: Option<&str>) -> Result<JsonValue> {
+        let url: reqwest::Url = if let Some(exchange) = exchange {
+            self.build_url(&format!("/instruments/{}", exchange), None)
+        } else {
+            self.build_url("/instruments", None)
+        };
+
+        let resp = self.send_request(url, "GET", None).await?;
+        let body = resp.bytes().await?;
+        crate::instruments::csv_to_json(&body)
+    }
+
+    /// Streams the instruments list as typed instruments, parsed while the
+    /// dump downloads
+    ///
+    /// Only a few rows are held in memory at a time, unlike [`instruments`](Self::instruments)
+    /// which buffers the whole dump. Rows that cannot be parsed are yielded as
+    /// errors without ending the stream. The dump is downloaded gzip compressed
+    /// when the server supports it. See the [`instruments`](crate::instruments)
+    /// module for an example.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn instruments_stream(
+        &self,
+        exchange: Option<&str>,
+    ) -> Result<impl Stream<Item = Result<Instrument>> + Send + 'static> {
+        let resp = self.instruments_response(exchange).await?;
+        Ok(crate::instruments::parse_stream(resp.bytes_stream()))
+    }
+
+    /// Downloads the instruments list to the CSV file at `path`, returning the
+    /// number of bytes written
+    ///
+    /// The dump is written as it arrives, without parsing it or holding it in
+    /// memory, for archiving it or processing it with other tools. An existing
+    /// file at `path` is overwritten.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn instruments_to_file(
+        &self,
+        exchange: Option<&str>,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<u64> {
+        use tokio::io::AsyncWriteExt;
+
+        let path = path.as_ref();
+        let error = |e: std::io::Error| {
+            KiteError::Other(format!("failed to write instruments to {}: {}", path.display(), e))
+        };
+        let mut resp = self.instruments_response(exchange).await?;
+        let mut file = tokio::fs::File::create(path).await.map_err(error)?;
+        let mut written = 0;
+        while let Some(chunk) = resp.chunk().await? {
+            file.write_all(&chunk).await.map_err(error)?;
+            written += chunk.len() as u64;
+        }
+        file.flush().await.map_err(error)?;
+        Ok(written)
+    }
+
+    /// Requests the instruments dump, failing on an error response
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn instruments_response(&self, exchange: Option<&str>) -> Result<reqwest::Response> {
+        self.instruments_if_modified(exchange)
+            .await?
+            .ok_or_else(|| KiteError::Other("instruments dump was not modified".to_string()))
+    }
+
+    /// Requests the instruments dump, failing on an error response
+    ///
+    /// Returns `None` when a conditional request, made with `If-None-Match` or
+    /// `If-Modified-Since` request option headers, finds the dump unchanged.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) async fn instruments_if_modified(&self, exchange: Option<&str>) -> Result<Option<reqwest::Response>> {
+        let url: reqwest::Url = if let Some(exchange) = exchange {
+            self.build_url(&format!("/instruments/{}", exchange), None)
+        } else {
+            self.build_url("/instruments", None)
+        };
+
+        let resp = self.send_request(url, "GET", None).await?;
+        if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(self.response_error(resp).await);
+        }
+        Ok(Some(resp))
+    }
+
+    /// Get the instruments list as typed instruments
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn instruments_typed(&self, exchange: Option<&str>) -> Result<Vec<Instrument>> {
+        self.instruments_stream(exchange).await?.try_collect().await
+    }
+
+    /// Get the instruments list as typed instruments
+    #[cfg(target_arch = "wasm32")]
+    pub async fn instruments_typed(&self, exchange: Option<&str>) -> Result<Vec<Instrument>> {
+        let url: reqwest::Url = if let Some(exchange) = exchange {
+            self.build_url(&format!("/instruments/{}", exchange), None)
+        } else {
+            self.build_url("/instruments", None)
+        };
+
+        let resp = self.send_request(url, "GET", None).await?;
+        if !resp.status().is_success() {
+            return Err(self.response_error(resp).await);
+        }
+        let body = resp.bytes().await?;
+        crate::instruments::parse_instruments(&body)
+    }
+
+    /// Get the instruments matching `filter`
+    ///
+    /// The dump is filtered while it downloads, so only the matching
+    /// instruments are held in memory, and only the dump of the exchange of the
+    /// filter is downloaded if it names one.
+    ///
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    /// use kiteconnect::instruments::InstrumentFilter;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> kiteconnect::error::Result<()> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// let filter = InstrumentFilter::new()
+    ///     .exchange("NFO")
+    ///     .instrument_type("FUT")
+    ///     .name_contains("NIFTY");
+    /// for future in client.instruments_filtered(&filter).await? {
+    ///     println!("{} expires on {:?}", future.tradingsymbol, future.expiry);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn instruments_filtered(&self, filter: &InstrumentFilter) -> Result<Vec<Instrument>> {
+        self.instruments_stream(filter.exchange.as_deref())
+            .await?
+            .try_filter(|instrument| futures::future::ready(filter.matches(instrument)))
+            .try_collect()
+            .await
+    }
+
+    /// Get the instruments matching `filter`
+    ///
+    /// Only the dump of the exchange of the filter is downloaded if it names one.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn instruments_filtered(&self, filter: &InstrumentFilter) -> Result<Vec<Instrument>> {
+        let mut instruments = self.instruments_typed(filter.exchange.as_deref()).await?;
+        instruments.retain(|instrument| filter.matches(instrument));
+        Ok(instruments)
+    }
+
+    /// Get mutual fund instruments list
+    ///
+    /// Rows are converted like those of [`instruments`](Self::instruments).
+    pub async fn mf_instruments(&self) -> Result<JsonValue> {
+        let url = self.build_url("/mf/instruments", None);
+        let resp = self.send_request(url, "GET", None).await?;
+        let body = resp.bytes().await?;
+        crate::instruments::csv_to_json(&body)
+    }
+
+    /// Get mutual fund instruments list as typed schemes
+    ///
+    /// See [`MfInstrumentStore`](crate::instruments::MfInstrumentStore) for
+    /// lookups, filters and search over them.
+    pub async fn mf_instruments_typed(&self) -> Result<Vec<MfInstrument>> {
+        let url = self.build_url("/mf/instruments", None);
+        let resp = self.send_request(url, "GET", None).await?;
+        if !resp.status().is_success() {
+            return Err(self.response_error(resp).await);
+        }
+        let body = resp.bytes().await?;
+        crate::instruments::parse_mf_instruments(&body)
+    }
+}
+
+/// Implement the async request handler for KiteConnect struct
+impl RequestHandler for KiteConnect {
+    async fn send_request(
+        &self,
+        url: reqwest::Url,
+        method: &str,
+        data: Option<HashMap<&str, &str>>,
+    ) -> Result<reqwest::Response> {
+        self.send_body(url, method, RequestBody::Form(data)).await
+    }
+}
+
+impl KiteConnect {
+    /// Sends a request that trades, unless the kill switch is on or the
+    /// client is read-only
+    async fn send_mutation(&self, path: &str, method: &str, data: Option<HashMap<&str, &str>>) -> Result<JsonValue> {
+        if self.is_kill_switch_active() {
+            log::warn!("Kill switch active, not sending {} {}", method, path);
+            return Err(KiteError::KillSwitchActive {
+                method: method.to_string(),
+                endpoint: path.to_string(),
+            });
+        }
+        if let Some(mode) = self.read_only {
+            let payload = data.as_ref().map(redact::redact_form).unwrap_or_default();
+            log::info!("Read-only mode, not sending {} {} {}", method, path, payload);
+            return match mode {
+                ReadOnlyMode::Reject => Err(KiteError::ReadOnly {
+                    method: method.to_string(),
+                    endpoint: path.to_string(),
+                }),
+                ReadOnlyMode::Simulate => {
+                    let data = readonly::simulated_data(method, path);
+                    if self.unwrap_envelope {
+                        Ok(data)
+                    } else {
+                        Ok(serde_json::json!({ "status": "success", "data": data }))
+                    }
+                }
+            };
+        }
+
+        // Modifications and cancellations of orders still being processed had
+        // no effect and may be sent again
+        let policy = self.options.retry_policy.as_ref().unwrap_or(&self.retry_policy);
+        let retries = if method != "POST" && path.starts_with("/orders/") {
+            policy.order_processing_retries
+        } else {
+            0
+        };
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let url = self.build_url(path, None);
+            let outcome = match self.send_request(url, method, data.clone()).await {
+                Ok(resp) => self.raise_or_return_json(resp).await,
+                Err(err) => Err(err),
+            };
+            match outcome {
+                Err(err) if attempt <= retries && err.is_order_processing() => {
+                    let delay = policy.delay(attempt);
+                    log::debug!("{} {} hit an order still being processed, retrying in {:?}", method, path, delay);
+                    crate::rt::sleep(delay).await;
+                }
+                outcome => return outcome,
+            }
+        }
+    }
+
+    /// Sends a request with a JSON body
+    async fn send_json_request(&self, url: reqwest::Url, method: &str, body: &JsonValue) -> Result<reqwest::Response> {
+        self.send_body(url, method, RequestBody::Json(body)).await
+    }
+
+    /// Sends a request with its retries, logging and metrics
+    async fn send_body(&self, url: reqwest::Url, method: &str, body: RequestBody<'_>) -> Result<reqwest::Response> {
+        self.check_token_expiry().await;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("XKiteVersion", "3".parse().unwrap());
+        headers.insert(
+            AUTHORIZATION,
+            format!("token {}:{}", self.api_key, self.access_token())
+                .parse()
+                .unwrap(),
+        );
+        headers.insert(USER_AGENT, "Rust".parse().unwrap());
+        self.options.apply_headers(&mut headers);
+
+        if self.debug {
+            log::debug!(
+                "--> {} {} [{}] {}",
+                method,
+                redact::redact_url(&url),
+                redact::redact_headers(&headers),
+                match &body {
+                    RequestBody::Form(data) => data.as_ref().map(redact::redact_form).unwrap_or_default(),
+                    RequestBody::Json(json) => redact::redact_body(&json.to_string()),
+                }
+            );
+        }
+
+        let started = crate::rt::now_secs();
+
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            "kite_request",
+            method,
+            endpoint = url.path(),
+            url = %redact::redact_url(&url),
+            status = tracing::field::Empty,
+            attempts = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+        );
+        #[cfg(feature = "tracing")]
+        let (outcome, attempts) = {
+            use tracing::Instrument;
+            self.execute(&url, method, &body, &headers)
+                .instrument(span.clone())
+                .await
+        };
+        #[cfg(not(feature = "tracing"))]
+        let (outcome, attempts) = self.execute(&url, method, &body, &headers).await;
+
+        let duration = Duration::from_secs_f64((crate::rt::now_secs() - started).max(0.0));
+        let status = match &outcome {
+            Ok(resp) => Some(resp.status().as_u16()),
+            Err(err) => err.status(),
+        };
+
+        #[cfg(feature = "tracing")]
+        {
+            span.record("attempts", attempts);
+            span.record("latency_ms", duration.as_millis() as u64);
+            if let Some(status) = status {
+                span.record("status", status);
+            }
+            if let Err(err) = &outcome {
+                span.in_scope(|| tracing::warn!(error = %err, "request failed"));
+            }
+        }
+
+        if let Some(sink) = &self.metrics {
+            sink.record(&RequestMetrics {
+                method,
+                endpoint: &metrics::endpoint_template(url.path()),
+                path: url.path(),
+                duration,
+                status,
+                retries: attempts.saturating_sub(1),
+                error: outcome.as_ref().err(),
+            });
+        }
+        outcome
+    }
+}
+
+impl KiteConnect {
+    /// Sends a single request through the registered interceptors
+    async fn dispatch(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let outcome = match request.build() {
+            Ok(mut request) => match self
+                .interceptors
+                .iter()
+                .try_for_each(|interceptor| interceptor.before_request(&mut request))
+            {
+                Ok(()) => self.client.execute(request).await.map_err(KiteError::from),
+                Err(err) => Err(err),
+            },
+            Err(err) => Err(KiteError::from(err)),
+        };
+
+        if self.debug {
+            match &outcome {
+                Ok(resp) => log::debug!(
+                    "<-- {} {} [{}]",
+                    resp.status(),
+                    redact::redact_url(resp.url()),
+                    redact::redact_headers(resp.headers())
+                ),
+                Err(err) => log::debug!("<-- error: {}", err),
+            }
+        }
+        for interceptor in self.interceptors.iter() {
+            match &outcome {
+                Ok(resp) => interceptor.after_response(resp),
+                Err(err) => interceptor.on_error(err),
+            }
+        }
+        outcome
+    }
+
+    /// Sends a request, applying rate limits and the retry policy
+    ///
+    /// Returns the outcome of the last attempt along with the number of attempts made.
+    async fn execute(
+        &self,
+        url: &reqwest::Url,
+        method: &str,
+        body: &RequestBody<'_>,
+        headers: &HeaderMap,
+    ) -> (Result<reqwest::Response>, u32) {
+        let policy = self.options.retry_policy.as_ref().unwrap_or(&self.retry_policy);
+        let attempts = policy.attempts_for(method, url.path());
+        let category = EndpointCategory::for_request(method, url.path());
+        let mut attempt = 1;
+        loop {
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire(category).await;
+            }
+            let request = match method {
+                "GET" => self.client.get(url.clone()),
+                "POST" => self.client.post(url.clone()),
+                "DELETE" => self.client.delete(url.clone()),
+                "PUT" => self.client.put(url.clone()),
+                _ => return (Err(KiteError::Other(format!("Unknown method: {}", method))), attempt),
+            };
+            let request = match body {
+                RequestBody::Json(json) => request.json(json),
+                RequestBody::Form(_) if method == "GET" => request,
+                RequestBody::Form(data) if method == "DELETE" => request.json(data),
+                RequestBody::Form(data) => request.form(data),
+            };
+            let mut request = request.headers(headers.clone());
+            if let Some(timeout) = self.options.timeout.or(self.timeout) {
+                request = request.timeout(timeout);
+            }
+            let outcome = self.dispatch(request).await;
+
+            let rate_limit_delay = retry::rate_limit_delay(&outcome);
+            if let (Some(limiter), Some(wait)) = (&self.rate_limiter, rate_limit_delay) {
+                limiter.pause(category, wait);
+            }
+            if attempt >= attempts || !retry::should_retry(&outcome) {
+                return (outcome, attempt);
+            }
+            let delay = match rate_limit_delay {
+                Some(wait) if wait > policy.max_delay => return (outcome, attempt),
+                // The limiter already holds the next attempt back
+                Some(_) if self.rate_limiter.is_some() => Duration::ZERO,
+                Some(wait) => wait,
+                None => policy.delay(attempt),
+            };
+            log::debug!("Retrying {} {} after failed attempt {}", method, url.path(), attempt);
+            crate::rt::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_client;
+    use mockito::{Server, Matcher};
+
+    #[tokio::test]
+    async fn test_build_url() {
+        let kiteconnect = KiteConnect::new("key", "token");
+        let url = kiteconnect.build_url("/my-holdings", None);
+        assert_eq!(url.as_str(), format!("{}/my-holdings", DEFAULT_BASE_URL).as_str());
+
+        let params: Vec<(&str, &str)> = vec![("one", "1")];
+        let url = kiteconnect.build_url("/my-holdings", Some(params));
+        assert_eq!(url.as_str(), format!("{}/my-holdings?one=1", DEFAULT_BASE_URL).as_str());
+    }
+
+    #[tokio::test]
+    async fn test_builder() {
+        let kiteconnect = KiteConnect::builder("key")
+            .access_token("token")
+            .unwrap_envelope(true)
+            .retry_policy(RetryPolicy::default())
+            .rate_limits(RateLimits::default())
+            .build()
+            .unwrap();
+        assert_eq!(kiteconnect.access_token(), "token");
+        assert!(!format!("{:?}", kiteconnect).contains("\"token\""));
+        assert!(kiteconnect.unwrap_envelope());
+        assert!(!kiteconnect.strict_mode());
+        assert_eq!(kiteconnect.retry_policy(), &RetryPolicy::default());
+
+        // Clones share the same limiter and access token
+        let clone = kiteconnect.clone();
+        assert!(Arc::ptr_eq(
+            kiteconnect.rate_limiter.as_ref().unwrap(),
+            clone.rate_limiter.as_ref().unwrap()
+        ));
+        clone.set_access_token("renewed");
+        assert_eq!(kiteconnect.access_token(), "renewed");
+    }
+
+    #[tokio::test]
+    async fn test_with_options() {
+        let kiteconnect = KiteConnect::new("key", "token");
+        let fast = kiteconnect.with_options(
+            RequestOptions::new()
+                .timeout(std::time::Duration::from_millis(300))
+                .retry_policy(RetryPolicy::default()),
+        );
+        assert_eq!(fast.request_options().timeout, Some(std::time::Duration::from_millis(300)));
+        assert_eq!(kiteconnect.request_options().timeout, None);
+
+        // The clone shares the access token with the original client
+        fast.set_access_token("new_token");
+        assert_eq!(kiteconnect.access_token(), "new_token");
+    }
+
+    #[tokio::test]
+    async fn test_set_access_token() {
+        let kiteconnect = KiteConnect::new("key", "token");
+        assert_eq!(kiteconnect.access_token(), "token");
+        kiteconnect.set_access_token("my_token");
+        assert_eq!(kiteconnect.access_token(), "my_token");
+    }
+
+    #[tokio::test]
+    async fn test_session_expiry_hook() {
+        let mut kiteconnect = KiteConnect::new("key", "token");
+        assert!(kiteconnect.session_expiry_hook().is_none());
+
+        fn mock_hook() { 
+            println!("Session expired");
+        }
+
+        kiteconnect.set_session_expiry_hook(mock_hook);
+        assert!(kiteconnect.session_expiry_hook().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_unwrap_envelope() {
+        let mut kiteconnect = KiteConnect::new("key", "token");
+        assert!(!kiteconnect.unwrap_envelope());
+        kiteconnect.set_unwrap_envelope(true);
+        assert!(kiteconnect.unwrap_envelope());
+
+        let success = serde_json::json!({"status": "success", "data": {"user_id": "AB1234"}});
+        let data = extract_data(success).unwrap();
+        assert_eq!(data["user_id"].as_str(), Some("AB1234"));
+
+        let error = serde_json::json!({
+            "status": "error",
+            "error_type": "TokenException",
+            "message": "Incorrect `api_key` or `access_token`."
+        });
+        let err = extract_data(error).unwrap_err();
+        assert_eq!(err.to_string(), "TokenException (200): Incorrect `api_key` or `access_token`.");
+    }
+
+    #[tokio::test]
+    async fn test_session_expiry_hook_fires_on_token_exception() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        fn count_hook() {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+        }
+
+        let mut kiteconnect = KiteConnect::new("key", "token");
+        kiteconnect.set_session_expiry_hook(count_hook);
+
+        let err = kiteconnect.handle_api_error(KiteError::from_response(
+            403,
+            None,
+            r#"{"status": "error", "error_type": "PermissionException", "message": "No access"}"#,
+        ), "token").await;
+        assert!(matches!(err, KiteError::Permission { .. }));
+        assert_eq!(CALLS.load(Ordering::SeqCst), 0);
+
+        let err = kiteconnect.handle_api_error(KiteError::from_response(
+            403,
+            None,
+            r#"{"status": "error", "error_type": "TokenException", "message": "Session expired"}"#,
+        ), "token").await;
+        assert!(err.is_token_error());
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_async_session_expiry_hook_updates_token() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut kiteconnect = KiteConnect::new("key", "expired_token");
+        let handle = kiteconnect.clone();
+        let counter = calls.clone();
+        kiteconnect.set_async_session_expiry_hook(move || {
+            let handle = handle.clone();
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                handle.set_access_token("fresh_token");
+            }
+        });
+
+        let err = kiteconnect.handle_api_error(KiteError::from_response(
+            403,
+            None,
+            r#"{"status": "error", "error_type": "TokenException", "message": "Session expired"}"#,
+        ), "expired_token").await;
+        assert!(err.is_token_error());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(kiteconnect.access_token(), "fresh_token");
+    }
+
+    #[tokio::test]
+    async fn test_as_user() {
+        let mut server = Server::new_async().await;
+        let kiteconnect = mock_client(&server);
+        let _mock = server.mock("GET", "/portfolio/holdings")
+            .match_header("authorization", "token API_KEY:USER_TOKEN")
+            .with_body_from_file("mocks/holdings.json")
+            .create_async()
+            .await;
+
+        let user = kiteconnect.as_user("USER_TOKEN");
+        user.holdings().await.unwrap();
+        assert_eq!(user.access_token(), "USER_TOKEN");
+
+        // Tokens are independent of the original client
+        user.set_access_token("OTHER");
+        assert_eq!(kiteconnect.access_token(), "ACCESS_TOKEN");
+    }
+
+    #[test]
+    fn test_token_expiry() {
+        let at = |ts: &str| ist::parse_timestamp(ts).unwrap();
+        assert_eq!(next_token_expiry(at("2024-03-01 05:59:59")), at("2024-03-01 06:00:00"));
+        assert_eq!(next_token_expiry(at("2024-03-01 06:00:00")), at("2024-03-02 06:00:00"));
+        assert_eq!(next_token_expiry(at("2024-03-01 21:15:00")), at("2024-03-02 06:00:00"));
+
+        let mut token = SessionToken::new("token");
+        assert!(!token.is_stale(ist::now()));
+        assert!(token.is_stale(token.expires_at.unwrap()));
+        token.mark_stale(ist::now());
+        assert!(token.is_stale(ist::now()));
+        assert_eq!(SessionToken::new("").expires_at, None);
+    }
+
+    #[tokio::test]
+    async fn test_validate_session() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let mut server = Server::new_async().await;
+        let mut kiteconnect = mock_client(&server);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let handle = kiteconnect.clone();
+        kiteconnect.set_async_session_expiry_hook(move || {
+            let counter = counter.clone();
+            let handle = handle.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                handle.set_access_token("fresh_token");
+            }
+        });
+
+        let _valid = server.mock("GET", "/user/profile")
+            .match_header("authorization", "token API_KEY:ACCESS_TOKEN")
+            .with_body_from_file("mocks/profile.json")
+            .create_async()
+            .await;
+        assert!(kiteconnect.validate_session().await.unwrap());
+        assert!(!kiteconnect.is_token_stale());
+
+        // A token past its expiry time runs the hook before the next request
+        kiteconnect.access_token.write().unwrap().expires_at = Some(ist::now());
+        assert!(kiteconnect.is_token_stale());
+        let _fresh = server.mock("GET", "/user/profile")
+            .match_header("authorization", "token API_KEY:fresh_token")
+            .with_status(403)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"status":"error","message":"Incorrect api_key or access_token.","error_type":"TokenException"}"#)
+            .create_async()
+            .await;
+        assert!(!kiteconnect.validate_session().await.unwrap());
+        // Once proactively, once for the rejected token
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_token_exceptions() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let mut server = Server::new_async().await;
+        let mut kiteconnect = mock_client(&server);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        kiteconnect.set_async_session_expiry_hook(move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        let _rejected = server.mock("GET", "/portfolio/holdings")
+            .with_status(403)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"status":"error","message":"Incorrect api_key or access_token.","error_type":"TokenException"}"#)
+            .create_async()
+            .await;
+
+        let results = futures::future::join_all((0..5).map(|_| kiteconnect.holdings())).await;
+        assert!(results.iter().all(|r| r.as_ref().is_err_and(KiteError::is_token_error)));
+        assert!(kiteconnect.is_token_stale());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Later requests with the rejected token don't run the hook again
+        assert!(kiteconnect.holdings().await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_base_url() {
+        let kiteconnect = KiteConnect::new("key", "token");
+        assert_eq!(kiteconnect.base_url(), DEFAULT_BASE_URL);
+
+        let kiteconnect = KiteConnect::builder("key")
+            .base_url("http://localhost:8080/kite/")
+            .build()
+            .unwrap();
+        assert_eq!(kiteconnect.base_url(), "http://localhost:8080/kite");
+        let url = kiteconnect.build_url("/portfolio/holdings", None);
+        assert_eq!(url.as_str(), "http://localhost:8080/kite/portfolio/holdings");
+
+        assert!(KiteConnect::builder("key").base_url("not a url").build().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_proxy() {
+        use crate::proxy::ProxyConfig;
+
+        // The mock server acts as the proxy for an unreachable API host
+        let mut server = Server::new_async().await;
+        let kiteconnect = KiteConnect::builder("API_KEY")
+            .access_token("ACCESS_TOKEN")
+            .base_url("http://api.kite.invalid")
+            .proxy(ProxyConfig::http(&server.url()))
+            .build()
+            .unwrap();
+        assert_eq!(kiteconnect.proxy().map(|p| p.url.as_str()), Some(server.url().as_str()));
+
+        let mock = server.mock("GET", "/portfolio/holdings")
+            .with_body_from_file("mocks/holdings.json")
+            .create_async()
+            .await;
+        let data: JsonValue = kiteconnect.holdings().await.unwrap();
+        assert!(data.is_object());
+        mock.assert_async().await;
+
+        let invalid = KiteConnect::builder("API_KEY").proxy(ProxyConfig::all("ftp://proxy")).build();
+        assert!(invalid.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_timeouts() {
+        // A listener that never answers makes every request hang
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let kiteconnect = KiteConnect::builder("API_KEY")
+            .base_url(&format!("http://{}", listener.local_addr().unwrap()))
+            .timeout(std::time::Duration::from_millis(100))
+            .connect_timeout(std::time::Duration::from_secs(1))
+            .pool_idle_timeout(std::time::Duration::from_secs(30))
+            .pool_max_idle_per_host(4)
+            .build()
+            .unwrap();
+
+        let err = kiteconnect.holdings().await.unwrap_err();
+        assert!(matches!(&err, KiteError::Http(e) if e.is_timeout()), "{:?}", err);
+        assert!(err.is_retryable());
+
+        // Per-call options take precedence over the client timeout
+        let patient = kiteconnect.with_options(
+            RequestOptions::new().timeout(std::time::Duration::from_millis(300)),
+        );
+        let started = std::time::Instant::now();
+        assert!(patient.holdings().await.is_err());
+        assert!(started.elapsed() >= std::time::Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn test_warm_up() {
+        let mut server = Server::new_async().await;
+        let kiteconnect = KiteConnect::builder("API_KEY")
+            .base_url(&server.url())
+            .tcp_keepalive(std::time::Duration::from_secs(30))
+            .http2_keep_alive_interval(std::time::Duration::from_secs(15))
+            .build()
+            .unwrap();
+
+        let mock = server.mock("GET", "/").with_status(404).create_async().await;
+        kiteconnect.warm_up().await.unwrap();
+        mock.assert_async().await;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let unreachable = KiteConnect::builder("API_KEY")
+            .base_url(&format!("http://{}", addr))
+            .build()
+            .unwrap();
+        assert!(unreachable.warm_up().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_metrics_sink() {
+        use crate::metrics::{MetricsSink, RequestMetrics};
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<String>>);
+
+        impl MetricsSink for Recorder {
+            fn record(&self, metrics: &RequestMetrics<'_>) {
+                self.0.lock().unwrap().push(format!(
+                    "{} {} {:?} retries={}",
+                    metrics.method, metrics.endpoint, metrics.status, metrics.retries
+                ));
+            }
+        }
+
+        let mut server = Server::new_async().await;
+        let recorder = Arc::new(Recorder::default());
+        let kiteconnect = KiteConnect::builder("API_KEY")
+            .base_url(&server.url())
+            .retry_policy(
+                RetryPolicy::default()
+                    .base_delay(std::time::Duration::from_millis(1))
+                    .retry_order_reads(true),
+            )
+            .metrics_sink(recorder.clone())
+            .build()
+            .unwrap();
+
+        let _unavailable = server.mock("GET", "/orders/171229000724687/trades")
+            .with_status(502)
+            .expect(1)
+            .create_async()
+            .await;
+        let _ok = server.mock("GET", "/orders/171229000724687/trades")
+            .with_body_from_file("mocks/order_trades.json")
+            .create_async()
+            .await;
+        kiteconnect.order_trades("171229000724687").await.unwrap();
+
+        let recorded = recorder.0.lock().unwrap().clone();
+        assert_eq!(recorded, vec!["GET /orders/:id/trades Some(200) retries=1"]);
+    }
+
+    #[tokio::test]
+    async fn test_debug_logging() {
+        let mut server = Server::new_async().await;
+        let mut kiteconnect = mock_client(&server);
+        assert!(!kiteconnect.debug());
+        kiteconnect.set_debug(true);
+        assert!(kiteconnect.debug());
+
+        let _ = env_logger::builder().is_test(true).try_init();
+        let _mock = server.mock("POST", "/session/token")
+            .with_body_from_file("mocks/generate_session.json")
+            .create_async()
+            .await;
+        let session = kiteconnect.generate_session("request_token", "secret").await.unwrap();
+        assert_eq!(session.access_token, "yyyyyy");
+    }
+
+    #[tokio::test]
+    async fn test_generate_session_strict() {
+        let mut server = Server::new_async().await;
+        let mut kiteconnect = mock_client(&server);
+        kiteconnect.set_strict_mode(true);
+        let _mock = server.mock("POST", "/session/token")
+            .with_body_from_file("mocks/generate_session.json")
+            .create_async()
+            .await;
+        let session = kiteconnect.generate_session("request_token", "secret").await.unwrap();
+        assert_eq!(session.enctoken, "T9k+9YtT8LHt4pg0tmgIEzE8ZE6VOHYlh");
+        assert_eq!(kiteconnect.access_token(), "yyyyyy");
+    }
+
+    #[tokio::test]
+    async fn test_token_store() {
+        use crate::auth::MemoryTokenStore;
+
+        let mut server = Server::new_async().await;
+        let store = Arc::new(MemoryTokenStore::new());
+        let kiteconnect = KiteConnect::builder("API_KEY")
+            .base_url(&server.url())
+            .token_store(store.clone())
+            .build()
+            .unwrap();
+        assert_eq!(kiteconnect.access_token(), "");
+
+        let _session = server.mock("POST", "/session/token")
+            .with_body_from_file("mocks/generate_session.json")
+            .create_async()
+            .await;
+        kiteconnect.generate_session("request_token", "secret").await.unwrap();
+        let saved = store.load().unwrap().unwrap();
+        assert_eq!(saved.access_token, "yyyyyy");
+        assert_eq!(saved.user_id, "AB1234");
+
+        // A restarted client resumes the saved session
+        let restarted = KiteConnect::builder("API_KEY")
+            .base_url(&server.url())
+            .token_store(store.clone())
+            .build()
+            .unwrap();
+        assert_eq!(restarted.access_token(), "yyyyyy");
+
+        let _renew = server.mock("POST", "/session/refresh_token")
+            .with_body(r#"{"status":"success","data":{"access_token":"renewed","refresh_token":"next"}}"#)
+            .create_async()
+            .await;
+        restarted.renew_access_token("refresh", "secret").await.unwrap();
+        assert_eq!(restarted.access_token(), "renewed");
+        let saved = store.load().unwrap().unwrap();
+        assert_eq!((saved.access_token.as_str(), saved.refresh_token.as_str()), ("renewed", "next"));
+        assert_eq!(saved.user_id, "AB1234");
+    }
+
+    #[tokio::test]
+    async fn test_interceptors() {
+        use crate::interceptor::Interceptor;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Default)]
+        struct Audit {
+            responses: AtomicUsize,
+            errors: AtomicUsize,
+        }
+
+        impl Interceptor for Audit {
+            fn before_request(&self, request: &mut reqwest::Request) -> Result<()> {
+                request.headers_mut().insert("X-Audit", "1".parse().unwrap());
+                Ok(())
+            }
+
+            fn after_response(&self, _response: &reqwest::Response) {
+                self.responses.fetch_add(1, Ordering::SeqCst);
+            }
+
+            fn on_error(&self, _error: &KiteError) {
+                self.errors.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        struct NoOrders;
+
+        impl Interceptor for NoOrders {
+            fn before_request(&self, request: &mut reqwest::Request) -> Result<()> {
+                if request.method() == reqwest::Method::POST {
+                    return Err(KiteError::Other("order placement disabled".to_string()));
                 }
+                Ok(())
             }
-            result.push(JsonValue::Object(obj));
         }
-        
-        Ok(JsonValue::Array(result))
-    }
 
-    /// Get mutual fund instruments list (WASM version - returns raw CSV as string)
-    #[cfg(target_arch = "wasm32")]
-    pub async fn mf_instruments(&self) -> Result<JsonValue> {
-        let url = self.build_url("/mf/instruments", None);
-        let resp = self.send_request(url, "GET", None).await?;
-        let body = resp.text().await?;
-        
-        // For WASM, return the raw CSV data as a string
-        // Users can parse it client-side using JS CSV libraries
-        Ok(JsonValue::String(body))
+        let mut server = Server::new_async().await;
+        let audit = Arc::new(Audit::default());
+        let kiteconnect = KiteConnect::builder("API_KEY")
+            .base_url(&server.url())
+            .interceptor(audit.clone())
+            .interceptor(Arc::new(NoOrders))
+            .build()
+            .unwrap();
+
+        let mock = server.mock("GET", "/portfolio/holdings")
+            .match_header("X-Audit", "1")
+            .with_body_from_file("mocks/holdings.json")
+            .create_async()
+            .await;
+        kiteconnect.holdings().await.unwrap();
+        mock.assert_async().await;
+        assert_eq!(audit.responses.load(Ordering::SeqCst), 1);
+
+        let err = kiteconnect
+            .place_order(
+                "regular", "NSE", "INFY", "BUY", "1", Some("CNC"), Some("MARKET"),
+                None, None, None, None, None, None, None, None,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "order placement disabled");
+        assert_eq!(audit.errors.load(Ordering::SeqCst), 1);
     }
-}
 
-/// Implement the async request handler for KiteConnect struct
-impl RequestHandler for KiteConnect {
-    async fn send_request(
-        &self,
-        url: reqwest::Url,
-        method: &str,
-        data: Option<HashMap<&str, &str>>,
-    ) -> Result<reqwest::Response> {
-        let mut headers = HeaderMap::new();
-        headers.insert("XKiteVersion", "3".parse().unwrap());
-        headers.insert(
-            AUTHORIZATION,
-            format!("token {}:{}", self.api_key, self.access_token)
-                .parse()
-                .unwrap(),
+    #[tokio::test]
+    async fn test_retry_policy() {
+        let mut server = Server::new_async().await;
+        let mut kiteconnect = mock_client(&server);
+        kiteconnect.set_retry_policy(
+            RetryPolicy::default()
+                .base_delay(std::time::Duration::from_millis(1))
+                .jitter(false),
         );
-        headers.insert(USER_AGENT, "Rust".parse().unwrap());
 
-        let response = match method {
-            "GET" => self.client.get(url).headers(headers).send().await?,
-            "POST" => self.client.post(url).headers(headers).form(&data).send().await?,
-            "DELETE" => self.client.delete(url).headers(headers).json(&data).send().await?,
-            "PUT" => self.client.put(url).headers(headers).form(&data).send().await?,
-            _ => return Err(anyhow!("Unknown method!")),
+        let unavailable = server.mock("GET", "/portfolio/holdings")
+            .with_status(503)
+            .with_body("<html><title>Service Unavailable</title></html>")
+            .expect(2)
+            .create_async()
+            .await;
+        let ok = server.mock("GET", "/portfolio/holdings")
+            .with_body_from_file("mocks/holdings.json")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let data: JsonValue = kiteconnect.holdings().await.unwrap();
+        assert!(data.is_object());
+        unavailable.assert_async().await;
+        ok.assert_async().await;
+
+        // Order placement is never retried
+        let rejected = server.mock("POST", "/orders/regular")
+            .with_status(503)
+            .with_body("Service Unavailable")
+            .expect(1)
+            .create_async()
+            .await;
+        let err = kiteconnect
+            .place_order(
+                "regular", "NSE", "INFY", "BUY", "1", Some("CNC"), Some("MARKET"),
+                None, None, None, None, None, None, None, None,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KiteError::Gateway { status: 503, .. }));
+        rejected.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_huge_retry_after() {
+        let mut server = Server::new_async().await;
+        let kiteconnect = KiteConnect::builder("API_KEY")
+            .access_token("ACCESS_TOKEN")
+            .base_url(&server.url())
+            .rate_limits(RateLimits::default())
+            .build()
+            .unwrap();
+        let limited = server.mock("GET", "/portfolio/holdings")
+            .with_status(429)
+            .with_header("retry-after", "1e20")
+            .with_body(r#"{"status": "error", "message": "Too many requests", "error_type": "NetworkException"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        // Beyond the retry policy's max delay, so it is not retried
+        let err = kiteconnect.holdings().await.unwrap_err();
+        assert!(matches!(err, KiteError::RateLimited { retry_after, .. } if retry_after == retry::MAX_RETRY_AFTER));
+        limited.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_order_checks() {
+        let mut server = Server::new_async().await;
+        let nifty = Instrument {
+            tradingsymbol: "NIFTY24JUNFUT".to_string(),
+            exchange: "NFO".to_string(),
+            tick_size: "0.05".parse().unwrap(),
+            lot_size: 25,
+            ..Default::default()
         };
+        let kiteconnect = KiteConnect::builder("API_KEY")
+            .access_token("ACCESS_TOKEN")
+            .base_url(&server.url())
+            .order_checks(Arc::new(InstrumentStore::new(vec![nifty])))
+            .build()
+            .unwrap();
+        let placed = server.mock("POST", "/orders/regular")
+            .with_body(r#"{"status": "success", "data": {"order_id": "151220000000000"}}"#)
+            .expect(3)
+            .create_async()
+            .await;
 
-        Ok(response)
+        let place = |tradingsymbol, quantity, price, trigger_price| {
+            kiteconnect.place_order(
+                "regular", "NFO", tradingsymbol, "BUY", quantity, Some("NRML"), Some("SL"),
+                price, None, None, trigger_price, None, None, None, None,
+            )
+        };
+        place("NIFTY24JUNFUT", "50", Some("22000.05"), Some("21990")).await.unwrap();
+        place("NIFTY24JUNFUT", "25", Some("0"), None).await.unwrap();
+        // Instruments missing from the store are not checked
+        place("BANKNIFTY24JUNFUT", "1", Some("0.01"), None).await.unwrap();
+
+        let err = place("NIFTY24JUNFUT", "30", Some("22000"), None).await.unwrap_err();
+        assert_eq!(err.to_string(), "quantity 30 of NFO:NIFTY24JUNFUT is not a multiple of its lot size 25");
+        let err = place("NIFTY24JUNFUT", "25", Some("22000"), Some("21990.02")).await.unwrap_err();
+        assert!(err.to_string().contains("the nearest valid price is 21990"), "{}", err);
+        assert!(place("NIFTY24JUNFUT", "lots", None, None).await.is_err());
+        placed.assert_async().await;
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use mockito::{Server, Matcher};
+    #[tokio::test]
+    async fn test_place_order_sliced() {
+        let mut server = Server::new_async().await;
+        let kiteconnect = mock_client(&server);
+        let full = server.mock("POST", "/orders/regular")
+            .match_body(Matcher::UrlEncoded("quantity".into(), "1800".into()))
+            .with_body(r#"{"status": "success", "data": {"order_id": "1"}}"#)
+            .expect(2)
+            .create_async()
+            .await;
+        let rest = server.mock("POST", "/orders/regular")
+            .match_body(Matcher::UrlEncoded("quantity".into(), "900".into()))
+            .with_body(r#"{"status": "success", "data": {"order_id": "2"}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let place = |tradingsymbol, quantity| {
+            kiteconnect.place_order_sliced(
+                "regular", "NFO", tradingsymbol, "BUY", quantity, Some("NRML"), Some("MARKET"),
+                None, None, None, None, None, None, None, None,
+            )
+        };
+        let order_ids = place("NIFTY24JUNFUT", "4500").await.unwrap();
+        assert_eq!(order_ids, ["1", "1", "2"]);
+        full.assert_async().await;
+        rest.assert_async().await;
+
+        // Underlyings without a freeze quantity are placed whole
+        let whole = server.mock("POST", "/orders/regular")
+            .match_body(Matcher::UrlEncoded("quantity".into(), "5000".into()))
+            .with_status(400)
+            .with_body(r#"{"status": "error", "message": "Quantity exceeds limit", "error_type": "InputException"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let err = place("INFY24JUNFUT", "5000").await.unwrap_err();
+        assert!(matches!(err, KiteError::Input { .. }), "{:?}", err);
+        whole.assert_async().await;
+        assert!(place("NIFTY24JUNFUT", "many").await.is_err());
+    }
 
     #[tokio::test]
-    async fn test_build_url() {
-        let kiteconnect = KiteConnect::new("key", "token");
-        let url = kiteconnect.build_url("/my-holdings", None);
-        assert_eq!(url.as_str(), format!("{}/my-holdings", URL).as_str());
+    async fn test_auto_slice() {
+        let mut server = Server::new_async().await;
+        let mut kiteconnect = KiteConnect::builder("API_KEY")
+            .access_token("ACCESS_TOKEN")
+            .base_url(&server.url())
+            .auto_slice(AutoSlice::new().max_clip(400).delay(Duration::from_millis(10)))
+            .build()
+            .unwrap();
+        let clip = server.mock("POST", "/orders/regular")
+            .match_body(Matcher::UrlEncoded("quantity".into(), "400".into()))
+            .with_body(r#"{"status": "success", "data": {"order_id": "1"}}"#)
+            .expect(3)
+            .create_async()
+            .await;
+        let rest = server.mock("POST", "/orders/regular")
+            .match_body(Matcher::UrlEncoded("quantity".into(), "200".into()))
+            .with_body(r#"{"status": "success", "data": {"order_id": "2"}}"#)
+            .expect(2)
+            .create_async()
+            .await;
 
-        let mut params: Vec<(&str, &str)> = Vec::new();
-        params.push(("one", "1"));
-        let url = kiteconnect.build_url("/my-holdings", Some(params));
-        assert_eq!(url.as_str(), format!("{}/my-holdings?one=1", URL).as_str());
+        let place = |kiteconnect: &KiteConnect, quantity: &'static str| {
+            let kiteconnect = kiteconnect.clone();
+            async move {
+                kiteconnect
+                    .place_order(
+                        "regular", "NSE", "INFY", "BUY", quantity, Some("CNC"), Some("MARKET"),
+                        None, None, None, None, None, None, None, None,
+                    )
+                    .await
+            }
+        };
+        let response = place(&kiteconnect, "1000").await.unwrap();
+        assert_eq!(response["data"]["order_id"], "1");
+        assert_eq!(response["data"]["order_ids"], serde_json::json!(["1", "1", "2"]));
+        // Quantities within the clip go out as they are
+        let response = place(&kiteconnect, "200").await.unwrap();
+        assert_eq!(response["data"]["order_id"], "2");
+        assert!(response["data"].get("order_ids").is_none());
+        let order_ids = kiteconnect.place_order_sliced(
+            "regular", "NSE", "INFY", "BUY", "400", None, None, None, None, None, None, None, None, None, None,
+        ).await.unwrap();
+        assert_eq!(order_ids, ["1"]);
+        clip.assert_async().await;
+        rest.assert_async().await;
+
+        kiteconnect.set_auto_slice(None);
+        let whole = server.mock("POST", "/orders/regular")
+            .match_body(Matcher::UrlEncoded("quantity".into(), "1000".into()))
+            .with_body(r#"{"status": "success", "data": {"order_id": "3"}}"#)
+            .create_async()
+            .await;
+        assert_eq!(place(&kiteconnect, "1000").await.unwrap()["data"]["order_id"], "3");
+        whole.assert_async().await;
     }
 
     #[tokio::test]
-    async fn test_set_access_token() {
-        let mut kiteconnect = KiteConnect::new("key", "token");
-        assert_eq!(kiteconnect.access_token(), "token");
-        kiteconnect.set_access_token("my_token");
-        assert_eq!(kiteconnect.access_token(), "my_token");
+    async fn test_read_only() {
+        let mut server = Server::new_async().await;
+        let mut kiteconnect = KiteConnect::builder("API_KEY")
+            .access_token("ACCESS_TOKEN")
+            .base_url(&server.url())
+            .read_only(ReadOnlyMode::Reject)
+            .build()
+            .unwrap();
+        let orders = server.mock("POST", "/orders/regular").expect(0).create_async().await;
+        let cancels = server.mock("DELETE", "/orders/regular/1").expect(0).create_async().await;
+        let holdings = server.mock("GET", "/portfolio/holdings")
+            .with_body_from_file("mocks/holdings.json")
+            .create_async()
+            .await;
+
+        let place = |kiteconnect: &KiteConnect| {
+            let kiteconnect = kiteconnect.clone();
+            async move {
+                kiteconnect
+                    .place_order(
+                        "regular", "NSE", "INFY", "BUY", "1", Some("CNC"), Some("MARKET"),
+                        None, None, None, None, None, None, None, None,
+                    )
+                    .await
+            }
+        };
+        let err = place(&kiteconnect).await.unwrap_err();
+        assert!(matches!(err, KiteError::ReadOnly { ref method, ref endpoint } if method == "POST" && endpoint == "/orders/regular"));
+        // Reads go out as usual
+        kiteconnect.holdings().await.unwrap();
+        holdings.assert_async().await;
+
+        kiteconnect.set_read_only(Some(ReadOnlyMode::Simulate));
+        let response = place(&kiteconnect).await.unwrap();
+        assert!(response["data"]["order_id"].as_str().unwrap().starts_with("DRYRUN"));
+        let response = kiteconnect.cancel_order("1", "regular", None).await.unwrap();
+        assert_eq!(response["data"]["order_id"], "1");
+        orders.assert_async().await;
+        cancels.assert_async().await;
     }
 
     #[tokio::test]
-    async fn test_session_expiry_hook() {
-        let mut kiteconnect = KiteConnect::new("key", "token");
-        assert_eq!(kiteconnect.session_expiry_hook(), None);
+    async fn test_kill_switch() {
+        let mut server = Server::new_async().await;
+        let kiteconnect = KiteConnect::builder("API_KEY")
+            .access_token("ACCESS_TOKEN")
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+        let orders = server.mock("POST", "/orders/regular")
+            .with_body(r#"{"status": "success", "data": {"order_id": "1"}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let cancels = server.mock("DELETE", "/orders/regular/1").expect(0).create_async().await;
+        server.mock("GET", "/orders")
+            .with_body_from_file("mocks/orders.json")
+            .create_async()
+            .await;
 
-        fn mock_hook() { 
-            println!("Session expired");
-        }
+        let place = |kiteconnect: &KiteConnect| {
+            let kiteconnect = kiteconnect.clone();
+            async move {
+                kiteconnect
+                    .place_order(
+                        "regular", "NSE", "INFY", "BUY", "1", Some("CNC"), Some("MARKET"),
+                        None, None, None, None, None, None, None, None,
+                    )
+                    .await
+            }
+        };
+        let risk = kiteconnect.clone();
+        risk.kill_switch(true);
+        assert!(kiteconnect.is_kill_switch_active());
+        assert!(matches!(place(&kiteconnect).await, Err(KiteError::KillSwitchActive { .. })));
+        assert!(matches!(
+            kiteconnect.cancel_order("1", "regular", None).await,
+            Err(KiteError::KillSwitchActive { ref method, .. }) if method == "DELETE"
+        ));
+        // Reads go out as usual
+        kiteconnect.orders().await.unwrap();
+
+        risk.kill_switch(false);
+        assert_eq!(place(&kiteconnect).await.unwrap()["data"]["order_id"], "1");
+        orders.assert_async().await;
+        cancels.assert_async().await;
+    }
 
-        kiteconnect.set_session_expiry_hook(mock_hook);
-        assert_ne!(kiteconnect.session_expiry_hook(), None);
+    #[tokio::test]
+    async fn test_order_processing_retry() {
+        let mut server = Server::new_async().await;
+        let mut kiteconnect = KiteConnect::builder("API_KEY")
+            .access_token("ACCESS_TOKEN")
+            .base_url(&server.url())
+            .retry_policy(RetryPolicy::default().base_delay(Duration::from_millis(10)).jitter(false))
+            .build()
+            .unwrap();
+        let processing = r#"{"status": "error", "message": "Order is being processed. Try again.", "error_type": "OrderException"}"#;
+        let busy = server.mock("PUT", "/orders/regular/1")
+            .with_status(400)
+            .with_body(processing)
+            .expect(2)
+            .create_async()
+            .await;
+        let modified = server.mock("PUT", "/orders/regular/1")
+            .with_body(r#"{"status": "success", "data": {"order_id": "1"}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let response = kiteconnect
+            .modify_order("1", "regular", None, Some("1500"), None, None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(response["data"]["order_id"], "1");
+        busy.assert_async().await;
+        modified.assert_async().await;
+
+        // Other rejections and exhausted retries come back as they are
+        let cancel = server.mock("DELETE", "/orders/regular/2")
+            .with_status(400)
+            .with_body(processing)
+            .expect(2)
+            .create_async()
+            .await;
+        kiteconnect.set_retry_policy(RetryPolicy::none().order_processing_retries(1));
+        let err = kiteconnect.cancel_order("2", "regular", None).await.unwrap_err();
+        assert!(err.is_order_processing());
+        cancel.assert_async().await;
+
+        let rejected = server.mock("DELETE", "/orders/regular/3")
+            .with_status(400)
+            .with_body(r#"{"status": "error", "message": "Order cannot be cancelled.", "error_type": "OrderException"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        assert!(!kiteconnect.cancel_order("3", "regular", None).await.unwrap_err().is_order_processing());
+        rejected.assert_async().await;
+    }
+
+    #[test]
+    fn test_after_hours() {
+        let mut kiteconnect = KiteConnect::builder("API_KEY").after_hours(AfterHours::Amo).build().unwrap();
+        let open = ist::parse_timestamp("2024-03-21 10:00:00").unwrap();
+        let closed = ist::parse_timestamp("2024-03-21 18:00:00").unwrap();
+        assert_eq!(kiteconnect.session_variety("regular", "NSE", open).unwrap(), "regular");
+        let pre_open = ist::parse_timestamp("2024-03-21 09:05:00").unwrap();
+        assert_eq!(kiteconnect.session_variety("regular", "NSE", pre_open).unwrap(), "regular");
+        // Futures and options hold no pre-open auction but take AMOs until 09:10
+        assert_eq!(kiteconnect.session_variety("regular", "NFO", pre_open).unwrap(), "amo");
+        // Closed, but before the AMO window opens
+        let after_close = ist::parse_timestamp("2024-03-21 15:45:00").unwrap();
+        let err = kiteconnect.session_variety("regular", "NSE", after_close).unwrap_err();
+        assert!(matches!(err, KiteError::MarketClosed { ref exchange } if exchange == "NSE"));
+        let holiday = ist::parse_timestamp("2024-03-25 10:00:00").unwrap();
+        assert_eq!(kiteconnect.session_variety("regular", "NSE", holiday).unwrap(), "amo");
+        assert_eq!(kiteconnect.session_variety("regular", "NSE", closed).unwrap(), "amo");
+        assert_eq!(kiteconnect.session_variety("regular", "MCX", closed).unwrap(), "regular");
+        // Commodity orders are left to the OMS, even after the close
+        let night = ist::parse_timestamp("2024-03-21 23:50:00").unwrap();
+        assert_eq!(kiteconnect.session_variety("regular", "MCX", night).unwrap(), "regular");
+        assert_eq!(kiteconnect.session_variety("co", "NSE", closed).unwrap(), "co");
+
+        kiteconnect.set_after_hours(Some(AfterHours::Reject));
+        let err = kiteconnect.session_variety("regular", "NFO", closed).unwrap_err();
+        assert!(matches!(err, KiteError::MarketClosed { ref exchange } if exchange == "NFO"));
+        assert_eq!(kiteconnect.session_variety("regular", "NCO", night).unwrap(), "regular");
+
+        kiteconnect.set_after_hours(None);
+        assert_eq!(kiteconnect.session_variety("regular", "NSE", closed).unwrap(), "regular");
     }
 
     #[tokio::test]
     async fn test_login_url() {
         let kiteconnect = KiteConnect::new("key", "token");
         assert_eq!(kiteconnect.login_url(), "https://kite.trade/connect/login?api_key=key&v3");
+        assert_eq!(kiteconnect.login_url_with_params(&[]), kiteconnect.login_url());
+        assert_eq!(
+            kiteconnect.login_url_with_params(&[("user", "a b"), ("next", "x&y=1")]),
+            "https://kite.trade/connect/login?api_key=key&v3&redirect_params=user%3Da%2Bb%26next%3Dx%2526y%253D1"
+        );
+        assert_eq!(
+            kiteconnect.login_url_with_state("s1"),
+            "https://kite.trade/connect/login?api_key=key&v3&redirect_params=state%3Ds1"
+        );
     }
 
     #[tokio::test]
@@ -1006,7 +4028,7 @@ mod tests {
         let mut server = Server::new_async().await;
         
         // Create KiteConnect instance that uses the mock server URL
-        let kiteconnect = TestKiteConnect::new("API_KEY", "ACCESS_TOKEN", &server.url());
+        let kiteconnect = mock_client(&server);
 
         let _mock1 = server.mock("GET", Matcher::Regex(r"^/user/margins".to_string()))
             .with_body_from_file("mocks/margins.json")
@@ -1028,7 +4050,7 @@ mod tests {
     #[tokio::test]
     async fn test_holdings() {
         let mut server = Server::new_async().await;
-        let kiteconnect = TestKiteConnect::new("API_KEY", "ACCESS_TOKEN", &server.url());
+        let kiteconnect = mock_client(&server);
 
         let _mock = server.mock("GET", Matcher::Regex(r"^/portfolio/holdings".to_string()))
             .with_body_from_file("mocks/holdings.json")
@@ -1043,7 +4065,7 @@ mod tests {
     #[tokio::test]
     async fn test_positions() {
         let mut server = Server::new_async().await;
-        let kiteconnect = TestKiteConnect::new("API_KEY", "ACCESS_TOKEN", &server.url());
+        let kiteconnect = mock_client(&server);
 
         let _mock = server.mock("GET", Matcher::Regex(r"^/portfolio/positions".to_string()))
             .with_body_from_file("mocks/positions.json")
@@ -1058,7 +4080,7 @@ mod tests {
     #[tokio::test]
     async fn test_order_trades() {
         let mut server = Server::new_async().await;
-        let kiteconnect = TestKiteConnect::new("API_KEY", "ACCESS_TOKEN", &server.url());
+        let kiteconnect = mock_client(&server);
 
         let _mock2 = server.mock(
             "GET", Matcher::Regex(r"^/orders/171229000724687/trades".to_string())
@@ -1075,7 +4097,7 @@ mod tests {
     #[tokio::test]
     async fn test_orders() {
         let mut server = Server::new_async().await;
-        let kiteconnect = TestKiteConnect::new("API_KEY", "ACCESS_TOKEN", &server.url());
+        let kiteconnect = mock_client(&server);
 
         let _mock2 = server.mock(
             "GET", Matcher::Regex(r"^/orders".to_string())
@@ -1093,7 +4115,7 @@ mod tests {
     #[tokio::test]
     async fn test_order_history() {
         let mut server = Server::new_async().await;
-        let kiteconnect = TestKiteConnect::new("API_KEY", "ACCESS_TOKEN", &server.url());
+        let kiteconnect = mock_client(&server);
 
         let _mock2 = server.mock(
             "GET", Matcher::Regex(r"^/orders".to_string())
@@ -1110,7 +4132,7 @@ mod tests {
     #[tokio::test]
     async fn test_trades() {
         let mut server = Server::new_async().await;
-        let kiteconnect = TestKiteConnect::new("API_KEY", "ACCESS_TOKEN", &server.url());
+        let kiteconnect = mock_client(&server);
 
         let _mock1 = server.mock("GET", Matcher::Regex(r"^/trades".to_string()))
             .with_body_from_file("mocks/trades.json")
@@ -1125,7 +4147,7 @@ mod tests {
     #[tokio::test]
     async fn test_mf_orders() {
         let mut server = Server::new_async().await;
-        let kiteconnect = TestKiteConnect::new("API_KEY", "ACCESS_TOKEN", &server.url());
+        let kiteconnect = mock_client(&server);
 
         let _mock1 = server.mock(
             "GET", Matcher::Regex(r"^/mf/orders$".to_string())
@@ -1149,10 +4171,49 @@ mod tests {
         assert!(data.is_object());
     }
 
+    #[tokio::test]
+    async fn test_quote() {
+        let mut server = Server::new_async().await;
+        let kiteconnect = mock_client(&server);
+
+        let _mock1 = server.mock("GET", "/quote")
+        .match_query(Matcher::Exact("i=NSE%3AINFY&i=408065".to_string()))
+        .with_body_from_file("mocks/quote.json")
+        .create_async()
+        .await;
+
+        let _mock2 = server.mock("GET", "/quote/ltp")
+        .match_query(Matcher::Exact("i=NSE%3AINFY&i=BSE%3ASENSEX".to_string()))
+        .with_body_from_file("mocks/ltp.json")
+        .create_async()
+        .await;
+
+        let data: JsonValue = kiteconnect.quote(vec!["NSE:INFY", "408065"]).await.unwrap();
+        assert_eq!(data["data"]["NSE:INFY"]["instrument_token"], 408065);
+        assert_eq!(data["data"]["NSE:INFY"]["depth"]["buy"][0]["quantity"], 12);
+        let data: JsonValue = kiteconnect.ltp(vec!["NSE:INFY", "BSE:SENSEX"]).await.unwrap();
+        assert_eq!(data["data"]["BSE:SENSEX"]["last_price"], 52402.6);
+
+        let _mock3 = server.mock("GET", "/quote")
+        .match_query(Matcher::Exact("i=408065&i=884737".to_string()))
+        .with_body_from_file("mocks/quote.json")
+        .create_async()
+        .await;
+        let _mock4 = server.mock("GET", "/quote/ltp")
+        .match_query(Matcher::Exact("i=256265".to_string()))
+        .with_body_from_file("mocks/ltp.json")
+        .create_async()
+        .await;
+
+        let tokens = [InstrumentToken(408065), InstrumentToken(884737)];
+        assert!(kiteconnect.quote_tokens(&tokens).await.unwrap()["data"].is_object());
+        assert!(kiteconnect.ltp_tokens([InstrumentToken(256265)]).await.unwrap()["data"].is_object());
+    }
+
     #[tokio::test]
     async fn test_trigger_range() {
         let mut server = Server::new_async().await;
-        let kiteconnect = TestKiteConnect::new("API_KEY", "ACCESS_TOKEN", &server.url());
+        let kiteconnect = mock_client(&server);
 
         let _mock2 = server.mock(
             "GET", Matcher::Regex(r"^/instruments/trigger_range".to_string())
@@ -1164,12 +4225,46 @@ mod tests {
         let data: JsonValue = kiteconnect.trigger_range("BUY", vec!["NSE:INFY", "NSE:RELIANCE"]).await.unwrap();
         println!("{:?}", data);
         assert!(data.is_object());
+
+        let ranges = kiteconnect
+            .trigger_range_typed("BUY", &[(Exchange::Nse, "INFY"), (Exchange::Nse, "RELIANCE")])
+            .await
+            .unwrap();
+        let infy = &ranges["NSE:INFY"];
+        assert_eq!((infy.lower.to_string(), infy.upper.to_string()), ("1075.599".to_string(), "1138.2".to_string()));
+        assert!(infy.contains("1100".parse().unwrap()));
+        assert!(!infy.contains("1140".parse().unwrap()));
+        assert_eq!(infy.percentage, None);
+    }
+
+    #[tokio::test]
+    async fn test_historical_data() {
+        let mut server = Server::new_async().await;
+        let kiteconnect = mock_client(&server);
+
+        let _mock = server.mock("GET", "/instruments/historical/5633/minute")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("from".into(), "2019-12-04 09:15:00".into()),
+                Matcher::UrlEncoded("to".into(), "2019-12-04 09:18:00".into()),
+                Matcher::UrlEncoded("continuous".into(), "0".into()),
+                Matcher::UrlEncoded("oi".into(), "1".into()),
+            ]))
+            .with_body_from_file("mocks/historical_oi.json")
+            .create_async()
+            .await;
+
+        let data = kiteconnect
+            .historical_data(InstrumentToken(5633), "minute", "2019-12-04 09:15:00", "2019-12-04 09:18:00", false, true)
+            .await
+            .unwrap();
+        assert_eq!(data["data"]["candles"].as_array().unwrap().len(), 3);
+        assert_eq!(data["data"]["candles"][0][6], 13667775);
     }
 
     #[tokio::test]
     async fn test_instruments() {
         let mut server = Server::new_async().await;
-        let kiteconnect = TestKiteConnect::new("API_KEY", "ACCESS_TOKEN", &server.url());
+        let kiteconnect = mock_client(&server);
 
         let _mock2 = server.mock(
             "GET", Matcher::Regex(r"^/instruments".to_string())
@@ -1183,10 +4278,110 @@ mod tests {
         assert_eq!(data[0]["instrument_token"].as_str(), Some("408065"));
     }
 
+    #[tokio::test]
+    async fn test_instruments_stream() {
+        let mut server = Server::new_async().await;
+        let kiteconnect = mock_client(&server);
+
+        let _mock = server.mock("GET", "/instruments/NFO")
+            .with_body_from_file("mocks/instruments.csv")
+            .create_async()
+            .await;
+        let _denied = server.mock("GET", "/instruments/BSE")
+            .with_status(403)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"status":"error","error_type":"TokenException","message":"Invalid token"}"#)
+            .create_async()
+            .await;
+
+        let instruments = kiteconnect.instruments_typed(Some("NFO")).await.unwrap();
+        assert_eq!(instruments.len(), 4);
+        let option = &instruments[2];
+        assert_eq!((option.tradingsymbol.as_str(), option.instrument_type.as_str()), ("NIFTY159500CE", "CE"));
+        assert_eq!(option.expiry, chrono::NaiveDate::from_ymd_opt(2015, 12, 31));
+        assert_eq!(option.lot_size, 75);
+
+        let err = kiteconnect.instruments_stream(Some("BSE")).await.err().unwrap();
+        assert!(err.is_token_error());
+
+        // Compressed dumps are asked for and decompressed while parsing
+        let _compressed = server.mock("GET", "/instruments/NSE")
+            .match_header("accept-encoding", Matcher::Regex("gzip".to_string()))
+            .with_header("content-encoding", "gzip")
+            .with_body_from_file("mocks/instruments.csv.gz")
+            .create_async()
+            .await;
+        let instruments = kiteconnect.instruments_typed(Some("NSE")).await.unwrap();
+        assert_eq!(instruments.len(), 4);
+        assert_eq!(instruments[0].tradingsymbol, "INFY");
+    }
+
+    #[tokio::test]
+    async fn test_instruments_filtered() {
+        let mut server = Server::new_async().await;
+        let kiteconnect = mock_client(&server);
+        let exchange_dump = server.mock("GET", "/instruments/NFO")
+            .with_body_from_file("mocks/instruments.csv")
+            .create_async()
+            .await;
+        let _full_dump = server.mock("GET", "/instruments")
+            .with_body_from_file("mocks/instruments.csv")
+            .create_async()
+            .await;
+
+        let filter = InstrumentFilter::new().exchange("NFO");
+        let symbols: Vec<String> = kiteconnect
+            .instruments_filtered(&filter)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|instrument| instrument.tradingsymbol)
+            .collect();
+        assert_eq!(symbols, ["NIFTY15DECFUT", "NIFTY159500CE"]);
+        exchange_dump.assert_async().await;
+
+        let filter = InstrumentFilter::new().instrument_type("FUT");
+        assert_eq!(kiteconnect.instruments_filtered(&filter).await.unwrap().len(), 2);
+        let filter = InstrumentFilter::new().name_contains("infos");
+        let infy = kiteconnect.instruments_filtered(&filter).await.unwrap();
+        assert_eq!(infy[0].instrument_token, InstrumentToken(408065));
+        assert_eq!(infy.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_instruments_to_file() {
+        let mut server = Server::new_async().await;
+        let kiteconnect = mock_client(&server);
+
+        let _mock = server.mock("GET", "/instruments/NSE")
+            .with_header("content-encoding", "gzip")
+            .with_body_from_file("mocks/instruments.csv.gz")
+            .create_async()
+            .await;
+        let _denied = server.mock("GET", "/instruments/BSE")
+            .with_status(403)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"status":"error","error_type":"TokenException","message":"Invalid token"}"#)
+            .create_async()
+            .await;
+
+        let path = std::env::temp_dir().join(format!("kiteconnect-instruments-{}.csv", std::process::id()));
+        let written = kiteconnect.instruments_to_file(Some("NSE"), &path).await.unwrap();
+        let expected = std::fs::read("mocks/instruments.csv").unwrap();
+        assert_eq!(written, expected.len() as u64);
+        assert_eq!(std::fs::read(&path).unwrap(), expected);
+
+        // Error responses leave the file untouched
+        let err = kiteconnect.instruments_to_file(Some("BSE"), &path).await.err().unwrap();
+        assert!(err.is_token_error());
+        assert_eq!(std::fs::read(&path).unwrap(), expected);
+        std::fs::remove_file(&path).unwrap();
+    }
+
     #[tokio::test]
     async fn test_mf_instruments() {
         let mut server = Server::new_async().await;
-        let kiteconnect = TestKiteConnect::new("API_KEY", "ACCESS_TOKEN", &server.url());
+        let kiteconnect = mock_client(&server);
 
         let _mock2 = server.mock(
             "GET", Matcher::Regex(r"^/mf/instruments".to_string())
@@ -1198,221 +4393,10 @@ mod tests {
         let data: JsonValue = kiteconnect.mf_instruments().await.unwrap();
         println!("{:?}", data);
         assert_eq!(data[0]["tradingsymbol"].as_str(), Some("INF846K01DP8"));
-    }
-
-    // Helper struct to override the URL for testing
-    #[derive(Clone, Debug)]
-    struct TestKiteConnect {
-        api_key: String,
-        access_token: String,
-        client: reqwest::Client,
-        base_url: String,
-    }
-
-    impl TestKiteConnect {
-        fn new(api_key: &str, access_token: &str, base_url: &str) -> Self {
-            Self {
-                api_key: api_key.to_string(),
-                access_token: access_token.to_string(),
-                client: reqwest::Client::new(),
-                base_url: base_url.to_string(),
-            }
-        }
-
-        fn build_url(&self, path: &str, param: Option<Vec<(&str, &str)>>) -> reqwest::Url {
-            let url: &str = &format!("{}/{}", self.base_url, &path[1..]);
-            let mut url = reqwest::Url::parse(url).unwrap();
-
-            if let Some(data) = param {
-                url.query_pairs_mut().extend_pairs(data.iter());
-            }
-            url
-        }
-
-        async fn send_request(
-            &self,
-            url: reqwest::Url,
-            method: &str,
-            data: Option<HashMap<&str, &str>>,
-        ) -> Result<reqwest::Response> {
-            let mut headers = HeaderMap::new();
-            headers.insert("XKiteVersion", "3".parse().unwrap());
-            headers.insert(
-                AUTHORIZATION,
-                format!("token {}:{}", self.api_key, self.access_token)
-                    .parse()
-                    .unwrap(),
-            );
-            headers.insert(USER_AGENT, "Rust".parse().unwrap());
-
-            let response = match method {
-                "GET" => self.client.get(url).headers(headers).send().await?,
-                "POST" => self.client.post(url).headers(headers).form(&data).send().await?,
-                "DELETE" => self.client.delete(url).headers(headers).json(&data).send().await?,
-                "PUT" => self.client.put(url).headers(headers).form(&data).send().await?,
-                _ => return Err(anyhow!("Unknown method!")),
-            };
-
-            Ok(response)
-        }
-
-        async fn raise_or_return_json(&self, resp: reqwest::Response) -> Result<JsonValue> {
-            if resp.status().is_success() {
-                let jsn: JsonValue = resp.json().await.with_context(|| "Serialization failed")?;
-                Ok(jsn)
-            } else {
-                let error_text = resp.text().await?;
-                Err(anyhow!(error_text))
-            }
-        }
-
-        async fn holdings(&self) -> Result<JsonValue> {
-            let url = self.build_url("/portfolio/holdings", None);
-            let resp = self.send_request(url, "GET", None).await?;
-            self.raise_or_return_json(resp).await
-        }
-
-        async fn positions(&self) -> Result<JsonValue> {
-            let url = self.build_url("/portfolio/positions", None);
-            let resp = self.send_request(url, "GET", None).await?;
-            self.raise_or_return_json(resp).await
-        }
-
-        async fn orders(&self) -> Result<JsonValue> {
-            let url = self.build_url("/orders", None);
-            let resp = self.send_request(url, "GET", None).await?;
-            self.raise_or_return_json(resp).await
-        }
-
-        async fn margins(&self, segment: Option<String>) -> Result<JsonValue> {
-            let url: reqwest::Url = if let Some(segment) = segment {
-                self.build_url(&format!("/user/margins/{}", segment), None)
-            } else {
-                self.build_url("/user/margins", None)
-            };
-
-            let resp = self.send_request(url, "GET", None).await?;
-            self.raise_or_return_json(resp).await
-        }
-
-        async fn order_trades(&self, order_id: &str) -> Result<JsonValue> {
-            let url = self.build_url(&format!("/orders/{}/trades", order_id), None);
-            let resp = self.send_request(url, "GET", None).await?;
-            self.raise_or_return_json(resp).await
-        }
-
-        async fn order_history(&self, order_id: &str) -> Result<JsonValue> {
-            let params = vec![("order_id", order_id)];
-            let url = self.build_url("/orders", Some(params));
-            let resp = self.send_request(url, "GET", None).await?;
-            self.raise_or_return_json(resp).await
-        }
-
-        async fn trades(&self) -> Result<JsonValue> {
-            let url = self.build_url("/trades", None);
-            let resp = self.send_request(url, "GET", None).await?;
-            self.raise_or_return_json(resp).await
-        }
-
-        async fn mf_orders(&self, order_id: Option<&str>) -> Result<JsonValue> {
-            let url: reqwest::Url = if let Some(order_id) = order_id {
-                self.build_url(&format!("/mf/orders/{}", order_id), None)
-            } else {
-                self.build_url("/mf/orders", None)
-            };
-
-            let resp = self.send_request(url, "GET", None).await?;
-            self.raise_or_return_json(resp).await
-        }
-
-        async fn trigger_range(
-            &self,
-            transaction_type: &str,
-            instruments: Vec<&str>,
-        ) -> Result<JsonValue> {
-            let mut params: Vec<(&str, &str)> = Vec::new();
-            params.push(("transaction_type", transaction_type));
-            
-            for instrument in instruments {
-                params.push(("instruments", instrument));
-            }
-
-            let url = self.build_url("/instruments/trigger_range", Some(params));
-            let resp = self.send_request(url, "GET", None).await?;
-            self.raise_or_return_json(resp).await
-        }
-
-        async fn instruments(&self, exchange: Option<&str>) -> Result<JsonValue> {
-            let url: reqwest::Url = if let Some(exchange) = exchange {
-                self.build_url(&format!("/instruments/{}", exchange), None)
-            } else {
-                self.build_url("/instruments", None)
-            };
-
-            let resp = self.send_request(url, "GET", None).await?;
-            let body = resp.text().await?;
-            
-            // Parse CSV response
-            #[cfg(not(target_arch = "wasm32"))]
-            {
-                use csv::ReaderBuilder;
-                let mut rdr = ReaderBuilder::new().from_reader(body.as_bytes());
-                let mut result = Vec::new();
-                
-                let headers = rdr.headers()?.clone();
-                for record in rdr.records() {
-                    let record = record?;
-                    let mut obj = serde_json::Map::new();
-                    
-                    for (i, field) in record.iter().enumerate() {
-                        if let Some(header) = headers.get(i) {
-                            obj.insert(header.to_string(), JsonValue::String(field.to_string()));
-                        }
-                    }
-                    result.push(JsonValue::Object(obj));
-                }
-                
-                Ok(JsonValue::Array(result))
-            }
-            
-            #[cfg(target_arch = "wasm32")]
-            {
-                Ok(JsonValue::String(body))
-            }
-        }
 
-        async fn mf_instruments(&self) -> Result<JsonValue> {
-            let url = self.build_url("/mf/instruments", None);
-            let resp = self.send_request(url, "GET", None).await?;
-            let body = resp.text().await?;
-            
-            // Parse CSV response
-            #[cfg(not(target_arch = "wasm32"))]
-            {
-                use csv::ReaderBuilder;
-                let mut rdr = ReaderBuilder::new().from_reader(body.as_bytes());
-                let mut result = Vec::new();
-                
-                let headers = rdr.headers()?.clone();
-                for record in rdr.records() {
-                    let record = record?;
-                    let mut obj = serde_json::Map::new();
-                    
-                    for (i, field) in record.iter().enumerate() {
-                        if let Some(header) = headers.get(i) {
-                            obj.insert(header.to_string(), JsonValue::String(field.to_string()));
-                        }
-                    }
-                    result.push(JsonValue::Object(obj));
-                }
-                
-                Ok(JsonValue::Array(result))
-            }
-            
-            #[cfg(target_arch = "wasm32")]
-            {
-                Ok(JsonValue::String(body))
-            }
-        }
+        let schemes = kiteconnect.mf_instruments_typed().await.unwrap();
+        assert_eq!(schemes.len(), 4);
+        assert_eq!(schemes[0].tradingsymbol, "INF846K01DP8");
+        assert_eq!(schemes[2].amc, "KOTAKMAHINDRAMF");
     }
 }
@@ -0,0 +1,544 @@
+//! # Error Types
+//!
+//! All fallible operations in this crate return [`Result<T>`], an alias for
+//! `std::result::Result<T, KiteError>`. [`KiteError`] distinguishes transport
+//! failures, malformed responses and errors reported by the Kite API itself, so
+//! callers can decide programmatically whether to retry, re-authenticate or
+//! surface the problem to the user.
+//!
+//! ```rust,no_run
+//! use kiteconnect::connect::KiteConnect;
+//! use kiteconnect::error::KiteError;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let client = KiteConnect::new("api_key", "access_token");
+//!
+//! match client.holdings().await {
+//!     Ok(holdings) => println!("Holdings: {:?}", holdings),
+//!     Err(KiteError::Token { message, .. }) => eprintln!("Please log in again: {}", message),
+//!     Err(e) if e.is_retryable() => eprintln!("Transient failure, retry later: {}", e),
+//!     Err(e) => eprintln!("Request failed: {}", e),
+//! }
+//! # }
+//! ```
+
+use serde_json::Value as JsonValue;
+use std::time::Duration;
+
+/// Delay assumed for `429` responses that carry no `Retry-After` header
+pub const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Maximum number of characters of a non-JSON error body kept in [`KiteError::Gateway`]
+const MAX_SNIPPET_CHARS: usize = 200;
+
+/// Message of the `OrderException` returned when an order is modified or
+/// cancelled while the OMS is still processing it
+const ORDER_PROCESSING_MESSAGE: &str = "Order is being processed. Try again.";
+
+/// Result type used throughout the crate
+pub type Result<T> = std::result::Result<T, KiteError>;
+
+/// Errors returned by the KiteConnect client
+#[derive(Debug, thiserror::Error)]
+pub enum KiteError {
+    /// The request could not be sent or the response could not be read
+    #[error("network error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// A response body could not be deserialized
+    #[error("deserialization failed: {0}")]
+    Deserialize(#[from] serde_json::Error),
+
+    /// A typed response contained fields unknown to the model (strict mode only)
+    #[error("unknown fields in response: {}", .0.join(", "))]
+    UnknownFields(Vec<String>),
+
+    /// A CSV response could not be parsed
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("CSV parsing failed: {0}")]
+    Csv(#[from] csv::Error),
+
+    /// Ticks, candles or instruments could not be converted to Arrow record batches
+    #[cfg(feature = "arrow")]
+    #[error("Arrow conversion failed: {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+
+    /// A Parquet file could not be written
+    #[cfg(feature = "arrow")]
+    #[error("Parquet writing failed: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    /// Instruments or candles could not be converted to a Polars DataFrame
+    #[cfg(all(feature = "polars", not(target_arch = "wasm32")))]
+    #[error("DataFrame conversion failed: {0}")]
+    Polars(#[from] polars::error::PolarsError),
+
+    /// Instruments could not be written to or read from a SQLite database
+    #[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+    #[error("SQLite export failed: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    /// Session expiry or invalidation (`TokenException`); the user should log in again
+    #[error("TokenException ({status}): {message}")]
+    Token { status: u16, message: String },
+
+    /// User account related error (`UserException`)
+    #[error("UserException ({status}): {message}")]
+    User { status: u16, message: String },
+
+    /// Order placement or modification failure (`OrderException`)
+    #[error("OrderException ({status}): {message}")]
+    Order { status: u16, message: String },
+
+    /// Missing or invalid request parameters (`InputException`)
+    #[error("InputException ({status}): {message}")]
+    Input { status: u16, message: String },
+
+    /// Insufficient funds for order placement (`MarginException`)
+    #[error("MarginException ({status}): {message}")]
+    Margin { status: u16, message: String },
+
+    /// Insufficient holdings for a sell order (`HoldingException`)
+    #[error("HoldingException ({status}): {message}")]
+    Holding { status: u16, message: String },
+
+    /// Kite could not reach the exchange or OMS (`NetworkException`)
+    #[error("NetworkException ({status}): {message}")]
+    Network { status: u16, message: String },
+
+    /// Internal error in parsing the OMS response (`DataException`)
+    #[error("DataException ({status}): {message}")]
+    Data { status: u16, message: String },
+
+    /// The API key lacks permission for the operation (`PermissionException`)
+    #[error("PermissionException ({status}): {message}")]
+    Permission { status: u16, message: String },
+
+    /// Unclassified server side error (`GeneralException`)
+    #[error("GeneralException ({status}): {message}")]
+    General { status: u16, message: String },
+
+    /// The request was rejected with `429 Too Many Requests`
+    #[error("rate limited, retry after {}s: {message}", .retry_after.as_secs_f64())]
+    RateLimited {
+        /// How long to wait before retrying, from the `Retry-After` header or
+        /// [`DEFAULT_RETRY_AFTER`] when the header is missing
+        retry_after: Duration,
+        /// Human readable error message
+        message: String,
+    },
+
+    /// A non-JSON error response, such as an HTML page from a gateway or load balancer
+    #[error("gateway error ({status}): {snippet}")]
+    Gateway {
+        /// HTTP status code of the response
+        status: u16,
+        /// Cleaned up and truncated text of the response body
+        snippet: String,
+    },
+
+    /// An error response with an `error_type` not known to this crate
+    #[error("{error_type} ({status}): {message}")]
+    Api {
+        /// HTTP status code of the response
+        status: u16,
+        /// Kite exception type
+        error_type: String,
+        /// Human readable error message
+        message: String,
+    },
+
+    /// An order was still open when waiting for it to complete timed out
+    #[error("order {order_id} still {status} when waiting for it timed out")]
+    OrderTimeout {
+        /// ID of the order
+        order_id: String,
+        /// Last status seen, e.g. `OPEN` or `TRIGGER PENDING`
+        status: String,
+    },
+
+    /// An order was rejected by the OMS or the exchange
+    #[error("order {order_id} rejected: {message}")]
+    OrderRejected {
+        /// ID of the order
+        order_id: String,
+        /// Reason given in the status message of the order
+        message: String,
+    },
+
+    /// An order was cancelled before it was filled completely
+    #[error("order {order_id} cancelled after filling {filled_quantity}")]
+    OrderCancelled {
+        /// ID of the order
+        order_id: String,
+        /// Quantity filled before the cancellation
+        filled_quantity: i64,
+    },
+
+    /// An order was not placed as its exchange is closed
+    ///
+    /// See [`AfterHours`](crate::calendar::AfterHours).
+    #[error("{exchange} is closed")]
+    MarketClosed {
+        /// Exchange of the order
+        exchange: String,
+    },
+
+    /// A request that would trade was not sent as the kill switch is on
+    ///
+    /// See [`KiteConnect::kill_switch`](crate::connect::KiteConnect::kill_switch).
+    #[error("kill switch active: {method} {endpoint} not sent")]
+    KillSwitchActive {
+        /// HTTP method of the request
+        method: String,
+        /// Path of the endpoint, e.g. `/orders/regular`
+        endpoint: String,
+    },
+
+    /// A request that would trade was not sent as the client is read-only
+    ///
+    /// See [`ReadOnlyMode`](crate::readonly::ReadOnlyMode).
+    #[error("read-only mode: {method} {endpoint} not sent")]
+    ReadOnly {
+        /// HTTP method of the request
+        method: String,
+        /// Path of the endpoint, e.g. `/orders/regular`
+        endpoint: String,
+    },
+
+    /// The daily order limit of an [`OrderGateway`] was reached
+    ///
+    /// [`OrderGateway`]: crate::orders::OrderGateway
+    #[error("daily limit of {limit} orders reached")]
+    OrderLimitReached {
+        /// Orders allowed per day
+        limit: u32,
+    },
+
+    /// A postback payload failed checksum verification
+    #[error("postback checksum mismatch")]
+    InvalidChecksum,
+
+    /// Any other failure, such as invalid input or missing response data
+    #[error("{0}")]
+    Other(String),
+}
+
+impl KiteError {
+    /// Builds an error from the HTTP status, `Content-Type` and body of a failed
+    /// API response, telling JSON error envelopes from HTML gateway pages
+    pub(crate) fn from_response(status: u16, content_type: Option<&str>, body: &str) -> Self {
+        let envelope = serde_json::from_str::<JsonValue>(body)
+            .ok()
+            .filter(JsonValue::is_object);
+
+        if status == 429 {
+            let message = envelope
+                .as_ref()
+                .and_then(|jsn| jsn["message"].as_str().map(str::to_string))
+                .unwrap_or_else(|| snippet(content_type, body));
+            return KiteError::RateLimited {
+                retry_after: DEFAULT_RETRY_AFTER,
+                message,
+            };
+        }
+        match envelope {
+            Some(jsn) => Self::from_envelope(status, &jsn),
+            None => KiteError::Gateway {
+                status,
+                snippet: snippet(content_type, body),
+            },
+        }
+    }
+
+    /// Sets the retry hint of a [`KiteError::RateLimited`] error, if one was sent
+    pub(crate) fn with_retry_after(self, hint: Option<Duration>) -> Self {
+        match (self, hint) {
+            (KiteError::RateLimited { message, .. }, Some(retry_after)) => {
+                KiteError::RateLimited { retry_after, message }
+            }
+            (err, _) => err,
+        }
+    }
+
+    /// Builds an error from a `{ "status": "error", ... }` response envelope
+    pub(crate) fn from_envelope(status: u16, jsn: &JsonValue) -> Self {
+        let error_type = jsn["error_type"].as_str().unwrap_or("GeneralException");
+        let message = jsn["message"].as_str().unwrap_or("Unknown error").to_string();
+        Self::from_error_type(status, error_type, message)
+    }
+
+    /// Maps a Kite `error_type` to its dedicated variant
+    pub(crate) fn from_error_type(status: u16, error_type: &str, message: String) -> Self {
+        match error_type {
+            "TokenException" => KiteError::Token { status, message },
+            "UserException" => KiteError::User { status, message },
+            "OrderException" => KiteError::Order { status, message },
+            "InputException" => KiteError::Input { status, message },
+            "MarginException" => KiteError::Margin { status, message },
+            "HoldingException" => KiteError::Holding { status, message },
+            "NetworkException" => KiteError::Network { status, message },
+            "DataException" => KiteError::Data { status, message },
+            "PermissionException" => KiteError::Permission { status, message },
+            "GeneralException" => KiteError::General { status, message },
+            other => KiteError::Api {
+                status,
+                error_type: other.to_string(),
+                message,
+            },
+        }
+    }
+
+    /// Returns the Kite `error_type` for errors reported by the API
+    pub fn error_type(&self) -> Option<&str> {
+        let error_type = match self {
+            KiteError::Token { .. } => "TokenException",
+            KiteError::User { .. } => "UserException",
+            KiteError::Order { .. } => "OrderException",
+            KiteError::Input { .. } => "InputException",
+            KiteError::Margin { .. } => "MarginException",
+            KiteError::Holding { .. } => "HoldingException",
+            KiteError::Network { .. } => "NetworkException",
+            KiteError::Data { .. } => "DataException",
+            KiteError::Permission { .. } => "PermissionException",
+            KiteError::General { .. } => "GeneralException",
+            KiteError::Api { error_type, .. } => error_type,
+            _ => return None,
+        };
+        Some(error_type)
+    }
+
+    /// Returns the message sent by the API for errors reported by the API
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            KiteError::Token { message, .. }
+            | KiteError::User { message, .. }
+            | KiteError::Order { message, .. }
+            | KiteError::Input { message, .. }
+            | KiteError::Margin { message, .. }
+            | KiteError::Holding { message, .. }
+            | KiteError::Network { message, .. }
+            | KiteError::Data { message, .. }
+            | KiteError::Permission { message, .. }
+            | KiteError::General { message, .. }
+            | KiteError::Api { message, .. }
+            | KiteError::RateLimited { message, .. } => Some(message),
+            _ => None,
+        }
+    }
+
+    /// Returns the HTTP status code for errors that originate from a response
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            KiteError::Token { status, .. }
+            | KiteError::User { status, .. }
+            | KiteError::Order { status, .. }
+            | KiteError::Input { status, .. }
+            | KiteError::Margin { status, .. }
+            | KiteError::Holding { status, .. }
+            | KiteError::Network { status, .. }
+            | KiteError::Data { status, .. }
+            | KiteError::Permission { status, .. }
+            | KiteError::General { status, .. }
+            | KiteError::Api { status, .. }
+            | KiteError::Gateway { status, .. } => Some(*status),
+            KiteError::RateLimited { .. } => Some(429),
+            KiteError::Http(e) => e.status().map(|s| s.as_u16()),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the session is no longer valid and the user must log in again
+    pub fn is_token_error(&self) -> bool {
+        matches!(self, KiteError::Token { .. })
+    }
+
+    /// Returns how long to wait before retrying a rate limited request
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            KiteError::RateLimited { retry_after, .. } => Some(*retry_after),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the OMS turned down a modification or cancellation
+    /// because the order was still being processed, e.g. right after it was
+    /// placed, so that sending it again shortly may succeed
+    pub fn is_order_processing(&self) -> bool {
+        matches!(self, KiteError::Order { message, .. } if message.trim() == ORDER_PROCESSING_MESSAGE)
+    }
+
+    /// Returns `true` for transient failures where retrying the request may succeed
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            KiteError::Http(e) => e.is_timeout() || e.is_connect(),
+            #[cfg(target_arch = "wasm32")]
+            KiteError::Http(e) => e.is_timeout(),
+            KiteError::Network { .. } => true,
+            _ => matches!(self.status(), Some(429) | Some(500..=599)),
+        }
+    }
+}
+
+/// Turns a non-JSON body into a short single line snippet
+///
+/// HTML pages are reduced to their `<title>` when present, or to their text
+/// content otherwise.
+fn snippet(content_type: Option<&str>, body: &str) -> String {
+    let is_html = content_type.is_some_and(|ct| ct.contains("html"))
+        || body.trim_start().starts_with('<');
+
+    let text = if is_html {
+        html_title(body).unwrap_or_else(|| strip_tags(body))
+    } else {
+        body.to_string()
+    };
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    if text.chars().count() > MAX_SNIPPET_CHARS {
+        let truncated: String = text.chars().take(MAX_SNIPPET_CHARS).collect();
+        format!("{}...", truncated.trim_end())
+    } else if text.is_empty() {
+        "empty response body".to_string()
+    } else {
+        text
+    }
+}
+
+/// Returns the contents of the `<title>` element of an HTML page
+fn html_title(body: &str) -> Option<String> {
+    let lower = body.to_ascii_lowercase();
+    let start = lower.find("<title")?;
+    let start = start + lower[start..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = body[start..end].trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+/// Removes tags, scripts and styles from an HTML page
+fn strip_tags(body: &str) -> String {
+    let mut text = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(open) = rest.find('<') {
+        text.push_str(&rest[..open]);
+        text.push(' ');
+        let tag = &rest[open..];
+        let lower = tag.get(..7).unwrap_or(tag).to_ascii_lowercase();
+        let skip_to = if lower.starts_with("<script") {
+            "</script>"
+        } else if lower.starts_with("<style") {
+            "</style>"
+        } else {
+            ">"
+        };
+        rest = match tag.to_ascii_lowercase().find(skip_to) {
+            Some(end) => &tag[end + skip_to.len()..],
+            None => "",
+        };
+    }
+    text.push_str(rest);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_response() {
+        let body = r#"{"status": "error", "error_type": "InputException", "message": "Invalid quantity"}"#;
+        match KiteError::from_response(400, None, body) {
+            KiteError::Input { status, message } => {
+                assert_eq!(status, 400);
+                assert_eq!(message, "Invalid quantity");
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let body = r#"{"status": "error", "error_type": "NetworkException", "message": "Too many requests"}"#;
+        let err = KiteError::from_response(429, None, body);
+        assert_eq!(err.retry_after(), Some(DEFAULT_RETRY_AFTER));
+        assert_eq!(err.message(), Some("Too many requests"));
+        assert_eq!(err.status(), Some(429));
+        assert!(err.is_retryable());
+        let err = err.with_retry_after(Some(Duration::from_secs(5)));
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(5)));
+
+        let err = KiteError::from_response(500, None, "Internal Server Error");
+        assert_eq!(err.status(), Some(500));
+        assert_eq!(err.to_string(), "gateway error (500): Internal Server Error");
+        assert!(err.is_retryable());
+    }
+
+    #[test]
+    fn test_gateway_errors() {
+        let page = "<html>\n<head><title>502 Bad Gateway</title></head>\n<body><center><h1>502 Bad Gateway</h1></center></body></html>";
+        match KiteError::from_response(502, Some("text/html"), page) {
+            KiteError::Gateway { status, snippet } => {
+                assert_eq!(status, 502);
+                assert_eq!(snippet, "502 Bad Gateway");
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let page = "<html><style>p { color: red; }</style><body><p>Down for\n   maintenance</p><script>alert(1)</script></body></html>";
+        let err = KiteError::from_response(503, Some("text/html; charset=utf-8"), page);
+        assert_eq!(err.to_string(), "gateway error (503): Down for maintenance");
+        assert!(err.is_retryable());
+
+        let err = KiteError::from_response(500, None, &"x".repeat(1000));
+        let snippet = match err {
+            KiteError::Gateway { snippet, .. } => snippet,
+            e => panic!("unexpected error: {:?}", e),
+        };
+        assert_eq!(snippet.len(), MAX_SNIPPET_CHARS + 3);
+
+        let err = KiteError::from_response(429, Some("text/html"), "<title>Too Many Requests</title>");
+        assert_eq!(err.message(), Some("Too Many Requests"));
+    }
+
+    #[test]
+    fn test_error_type_mapping() {
+        for error_type in [
+            "TokenException",
+            "UserException",
+            "OrderException",
+            "InputException",
+            "MarginException",
+            "HoldingException",
+            "NetworkException",
+            "DataException",
+            "PermissionException",
+            "GeneralException",
+        ] {
+            let err = KiteError::from_error_type(400, error_type, "message".to_string());
+            assert!(!matches!(err, KiteError::Api { .. }), "{} not mapped", error_type);
+            assert_eq!(err.error_type(), Some(error_type));
+            assert_eq!(err.message(), Some("message"));
+        }
+
+        let err = KiteError::from_error_type(403, "TokenException", "expired".to_string());
+        assert!(err.is_token_error());
+        assert!(!err.is_retryable());
+
+        let err = KiteError::from_error_type(502, "NetworkException", "OMS down".to_string());
+        assert!(err.is_retryable());
+
+        let err = KiteError::from_error_type(400, "ShinyNewException", "new".to_string());
+        assert_eq!(err.error_type(), Some("ShinyNewException"));
+    }
+
+    #[test]
+    fn test_order_processing() {
+        let err = KiteError::from_error_type(400, "OrderException", ORDER_PROCESSING_MESSAGE.to_string());
+        assert!(err.is_order_processing());
+
+        let err = KiteError::from_error_type(400, "InputException", ORDER_PROCESSING_MESSAGE.to_string());
+        assert!(!err.is_order_processing());
+
+        let err = KiteError::from_error_type(400, "OrderException", "Margin check in process".to_string());
+        assert!(!err.is_order_processing());
+    }
+}
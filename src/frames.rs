@@ -0,0 +1,189 @@
+//! # DataFrames
+//!
+//! With the `polars` feature, instruments and historical candles are returned
+//! as Polars [`DataFrame`]s with typed columns, instead of JSON that quant code
+//! converts to frames anyway:
+//!
+//! ```rust,no_run
+//! use kiteconnect::connect::KiteConnect;
+//! use kiteconnect::models::InstrumentToken;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> kiteconnect::error::Result<()> {
+//! let client = KiteConnect::new("api_key", "access_token");
+//! let instruments = client.instruments_df(Some("NFO")).await?;
+//! let candles = client
+//!     .historical_data_df(InstrumentToken(5633), "minute", "2024-06-03 09:15:00", "2024-06-03 15:30:00", false, false)
+//!     .await?;
+//! println!("{} instruments, {} candles", instruments.height(), candles.height());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Dates and timestamps are stored as `Date` and `Datetime` columns, the
+//! latter in milliseconds in the `Asia/Kolkata` time zone. Prices are `f64`
+//! columns, also with the `rust_decimal` feature.
+
+use crate::connect::KiteConnect;
+use crate::error::{KiteError, Result};
+use crate::models::{ist, price_to_f64, Instrument, InstrumentToken, Price};
+use chrono::NaiveDate;
+use polars::prelude::{Column, DataFrame, DataType, TimeUnit, TimeZone};
+use serde_json::Value as JsonValue;
+
+impl KiteConnect {
+    /// Get the instruments list as a DataFrame with a column per field of [`Instrument`]
+    pub async fn instruments_df(&self, exchange: Option<&str>) -> Result<DataFrame> {
+        instruments_frame(&self.instruments_typed(exchange).await?)
+    }
+
+    /// Get historical candles as a DataFrame of `date`, `open`, `high`, `low`,
+    /// `close` and `volume` columns, and `oi` if `oi` is set
+    ///
+    /// See [`historical_data`](Self::historical_data) for the parameters.
+    pub async fn historical_data_df(
+        &self,
+        instrument_token: impl Into<InstrumentToken>,
+        interval: &str,
+        from: &str,
+        to: &str,
+        continuous: bool,
+        oi: bool,
+    ) -> Result<DataFrame> {
+        let data = self
+            .historical_data(instrument_token, interval, from, to, continuous, oi)
+            .await?;
+        candles_frame(data.get("data").unwrap_or(&data))
+    }
+}
+
+/// Converts instruments into a DataFrame with a column per field of [`Instrument`]
+pub fn instruments_frame(instruments: &[Instrument]) -> Result<DataFrame> {
+    let epoch = NaiveDate::default();
+    let text = |name: &str, field: fn(&Instrument) -> &str| {
+        Column::new(name.into(), instruments.iter().map(field).collect::<Vec<_>>())
+    };
+    let number = |name: &str, field: fn(&Instrument) -> u32| {
+        Column::new(name.into(), instruments.iter().map(field).collect::<Vec<_>>())
+    };
+    let price = |name: &str, field: fn(&Instrument) -> Price| {
+        Column::new(name.into(), instruments.iter().map(|i| price_to_f64(field(i))).collect::<Vec<_>>())
+    };
+    let expiry: Vec<Option<i32>> = instruments
+        .iter()
+        .map(|i| i.expiry.map(|expiry| (expiry - epoch).num_days() as i32))
+        .collect();
+
+    Ok(DataFrame::new(vec![
+        number("instrument_token", |i| i.instrument_token.0),
+        number("exchange_token", |i| i.exchange_token),
+        text("tradingsymbol", |i| &i.tradingsymbol),
+        text("name", |i| &i.name),
+        price("last_price", |i| i.last_price),
+        Column::new("expiry".into(), expiry).cast(&DataType::Date)?,
+        price("strike", |i| i.strike),
+        price("tick_size", |i| i.tick_size),
+        number("lot_size", |i| i.lot_size),
+        text("instrument_type", |i| &i.instrument_type),
+        text("segment", |i| &i.segment),
+        text("exchange", |i| &i.exchange),
+    ])?)
+}
+
+/// Converts the `data` of a historical data response, holding `candles` as
+/// `[timestamp, open, high, low, close, volume, oi?]` arrays, into a DataFrame
+pub fn candles_frame(data: &JsonValue) -> Result<DataFrame> {
+    let candles = data["candles"]
+        .as_array()
+        .ok_or_else(|| KiteError::Other("historical data lacks candles".to_string()))?;
+
+    let mut dates = Vec::with_capacity(candles.len());
+    let mut prices: [Vec<f64>; 4] = Default::default();
+    let mut volumes = Vec::with_capacity(candles.len());
+    let mut open_interest = Vec::with_capacity(candles.len());
+    for candle in candles {
+        let invalid = || KiteError::Other(format!("invalid candle {}", candle));
+        let fields = candle.as_array().ok_or_else(invalid)?;
+        let date = fields
+            .first()
+            .and_then(JsonValue::as_str)
+            .and_then(ist::parse_timestamp)
+            .ok_or_else(invalid)?;
+        dates.push(date.timestamp_millis());
+        for (column, field) in prices.iter_mut().zip(1..5) {
+            column.push(fields.get(field).and_then(JsonValue::as_f64).ok_or_else(invalid)?);
+        }
+        volumes.push(fields.get(5).and_then(JsonValue::as_f64).ok_or_else(invalid)? as i64);
+        open_interest.push(fields.get(6).and_then(JsonValue::as_f64).map(|oi| oi as i64));
+    }
+
+    let zone = TimeZone::opt_try_new(Some("Asia/Kolkata"))?;
+    let [open, high, low, close] = prices;
+    let mut columns = vec![
+        Column::new("date".into(), dates).cast(&DataType::Datetime(TimeUnit::Milliseconds, zone))?,
+        Column::new("open".into(), open),
+        Column::new("high".into(), high),
+        Column::new("low".into(), low),
+        Column::new("close".into(), close),
+        Column::new("volume".into(), volumes),
+    ];
+    if open_interest.iter().any(Option::is_some) {
+        columns.push(Column::new("oi".into(), open_interest));
+    }
+    Ok(DataFrame::new(columns)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_client;
+    use mockito::Server;
+
+    #[tokio::test]
+    async fn test_instruments_df() {
+        let mut server = Server::new_async().await;
+        let _mock = server
+            .mock("GET", "/instruments")
+            .with_body_from_file("mocks/instruments.csv")
+            .create_async()
+            .await;
+
+        let frame = mock_client(&server).instruments_df(None).await.unwrap();
+        assert_eq!(frame.shape(), (4, 12));
+        assert_eq!(frame.column("instrument_token").unwrap().dtype(), &DataType::UInt32);
+        assert_eq!(frame.column("expiry").unwrap().dtype(), &DataType::Date);
+        assert_eq!(frame.column("expiry").unwrap().null_count(), 1);
+        assert_eq!(frame.column("lot_size").unwrap().u32().unwrap().get(1), Some(75));
+    }
+
+    #[tokio::test]
+    async fn test_historical_data_df() {
+        let mut server = Server::new_async().await;
+        let _mock = server
+            .mock("GET", mockito::Matcher::Regex("^/instruments/historical/5633/minute".to_string()))
+            .with_body_from_file("mocks/historical_oi.json")
+            .create_async()
+            .await;
+
+        let frame = mock_client(&server)
+            .historical_data_df(
+                InstrumentToken(5633),
+                "minute",
+                "2019-12-04 09:15:00",
+                "2019-12-04 09:18:00",
+                false,
+                true,
+            )
+            .await
+            .unwrap();
+        let names: Vec<&str> = frame.get_column_names().iter().map(|name| name.as_str()).collect();
+        assert_eq!(names, ["date", "open", "high", "low", "close", "volume", "oi"]);
+        assert_eq!(frame.column("close").unwrap().f64().unwrap().get(0), Some(12001.5));
+        let date = frame.column("date").unwrap().datetime().unwrap().phys.get(0);
+        assert_eq!(date, Some(ist::parse_timestamp("2019-12-04 09:15:00").unwrap().timestamp_millis()));
+
+        assert!(candles_frame(&serde_json::json!({"candles": [["2019-12-04", 1]]})).is_err());
+        let frame = candles_frame(&serde_json::json!({"candles": []})).unwrap();
+        assert_eq!(frame.width(), 6);
+    }
+}
@@ -0,0 +1,332 @@
+//! Local copy of the instruments dump, refreshed once per trading day
+
+use super::{Instrument, InstrumentStore};
+use crate::connect::KiteConnect;
+use crate::error::{KiteError, Result};
+use crate::models::{ist, InstrumentToken, Timestamp};
+use bytes::Bytes;
+use chrono::{Datelike, NaiveDate, TimeDelta, Weekday};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tokio::io::AsyncWriteExt;
+
+/// Minutes after midnight IST by which Kite has regenerated the day's dump
+const DUMP_PUBLISHED_MINUTES: i64 = 8 * 60 + 30;
+
+/// Instruments dump kept on disk and in memory, downloaded again only once
+/// it may have changed
+///
+/// The dump is regenerated once a day before the market opens, so a process
+/// starting up later that day loads the copy on disk instead of downloading it.
+/// Once a new trading day has begun, [`refresh`](Self::refresh) asks for the
+/// dump again with the `ETag` and `Last-Modified` validators of the copy, and
+/// the server only sends it if it changed. Lookups are served from memory by
+/// an [`InstrumentStore`].
+///
+/// ```rust,no_run
+/// use kiteconnect::connect::KiteConnect;
+/// use kiteconnect::instruments::InstrumentCache;
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let client = KiteConnect::new("api_key", "access_token");
+/// let cache = InstrumentCache::new(client, "/var/cache/kite/nfo.csv").exchange("NFO");
+/// cache.refresh().await?;
+/// if let Some(instrument) = cache.store().find("NFO", "NIFTY24JUNFUT") {
+///     println!("{} expires on {:?}", instrument.instrument_token, instrument.expiry);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct InstrumentCache {
+    client: KiteConnect,
+    exchange: Option<String>,
+    path: PathBuf,
+    state: RwLock<State>,
+    refreshing: tokio::sync::Mutex<()>,
+}
+
+/// Instruments in memory along with the validators of the copy they were read from
+#[derive(Default)]
+struct State {
+    validators: Option<Validators>,
+    store: Arc<InstrumentStore>,
+}
+
+/// Validators of the copy on disk, stored next to it
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// When the server last confirmed or sent the copy
+    fetched_at: Timestamp,
+}
+
+impl InstrumentCache {
+    /// Creates a cache of the full dump, kept in the CSV file at `path`
+    ///
+    /// Nothing is read or downloaded until the first [`refresh`](Self::refresh).
+    pub fn new(client: KiteConnect, path: impl Into<PathBuf>) -> Self {
+        InstrumentCache {
+            client,
+            exchange: None,
+            path: path.into(),
+            state: RwLock::new(State::default()),
+            refreshing: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Caches the instruments of `exchange` only
+    pub fn exchange(mut self, exchange: &str) -> Self {
+        self.exchange = Some(exchange.to_string());
+        self
+    }
+
+    /// Brings the cache up to date, returning `true` if a new dump was downloaded
+    ///
+    /// The first call loads the copy on disk. The dump is requested at most once
+    /// per trading day, conditionally, so calling this often is cheap.
+    pub async fn refresh(&self) -> Result<bool> {
+        self.refresh_at(ist::now()).await
+    }
+
+    async fn refresh_at(&self, now: Timestamp) -> Result<bool> {
+        let _refreshing = self.refreshing.lock().await;
+        let mut validators = self.state.read().unwrap().validators.clone();
+        if validators.is_none() {
+            validators = self.load().await;
+        }
+        if let Some(validators) = &validators {
+            if dump_day(validators.fetched_at) >= dump_day(now) {
+                return Ok(false);
+            }
+        }
+
+        let mut options = self.client.request_options().clone();
+        if let Some(etag) = validators.as_ref().and_then(|v| v.etag.as_deref()) {
+            options = options.header("If-None-Match", etag)?;
+        }
+        if let Some(modified) = validators.as_ref().and_then(|v| v.last_modified.as_deref()) {
+            options = options.header("If-Modified-Since", modified)?;
+        }
+        let client = self.client.with_options(options);
+        let Some(mut resp) = client.instruments_if_modified(self.exchange.as_deref()).await? else {
+            log::debug!("Instruments dump at {} is unchanged", self.path.display());
+            let validators = Validators {
+                fetched_at: now,
+                ..validators.unwrap_or_default()
+            };
+            self.save_validators(&validators).await?;
+            self.state.write().unwrap().validators = Some(validators);
+            return Ok(false);
+        };
+
+        let header = |name| {
+            resp.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let validators = Validators {
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+            fetched_at: now,
+        };
+
+        // Written aside first, so that a failed download leaves the copy intact
+        let partial = with_suffix(&self.path, ".part");
+        let mut file = tokio::fs::File::create(&partial).await.map_err(|e| io_error(&partial, e))?;
+        let mut dump = Vec::new();
+        while let Some(chunk) = resp.chunk().await? {
+            file.write_all(&chunk).await.map_err(|e| io_error(&partial, e))?;
+            dump.extend_from_slice(&chunk);
+        }
+        file.flush().await.map_err(|e| io_error(&partial, e))?;
+        drop(file);
+        let instruments = parse(dump).await?;
+        tokio::fs::rename(&partial, &self.path)
+            .await
+            .map_err(|e| io_error(&self.path, e))?;
+        self.save_validators(&validators).await?;
+        log::debug!("Downloaded {} instruments to {}", instruments.len(), self.path.display());
+        self.replace(instruments, validators);
+        Ok(true)
+    }
+
+    /// Returns the cached instruments
+    ///
+    /// The store is a snapshot; later refreshes replace it without changing
+    /// the stores handed out before.
+    pub fn store(&self) -> Arc<InstrumentStore> {
+        self.state.read().unwrap().store.clone()
+    }
+
+    /// Looks up an instrument by its instrument token
+    pub fn get(&self, instrument_token: impl Into<InstrumentToken>) -> Option<Instrument> {
+        self.state.read().unwrap().store.get(instrument_token).cloned()
+    }
+
+    /// Looks up an instrument by its exchange and tradingsymbol
+    pub fn find(&self, exchange: &str, tradingsymbol: &str) -> Option<Instrument> {
+        self.state.read().unwrap().store.find(exchange, tradingsymbol).cloned()
+    }
+
+    /// Returns when the server last sent or confirmed the cached dump
+    pub fn fetched_at(&self) -> Option<Timestamp> {
+        Some(self.state.read().unwrap().validators.as_ref()?.fetched_at)
+    }
+
+    /// Loads the copy left on disk by an earlier refresh, if it is usable
+    async fn load(&self) -> Option<Validators> {
+        let meta = tokio::fs::read(with_suffix(&self.path, ".meta.json")).await.ok()?;
+        let validators: Validators = match serde_json::from_slice(&meta) {
+            Ok(validators) => validators,
+            Err(e) => {
+                log::warn!("Ignoring cached instruments at {}: {}", self.path.display(), e);
+                return None;
+            }
+        };
+        let loaded = match tokio::fs::read(&self.path).await {
+            Ok(dump) => parse(dump).await,
+            Err(e) => Err(io_error(&self.path, e)),
+        };
+        match loaded {
+            Ok(instruments) => {
+                self.replace(instruments, validators.clone());
+                Some(validators)
+            }
+            Err(e) => {
+                log::warn!("Ignoring cached instruments at {}: {}", self.path.display(), e);
+                None
+            }
+        }
+    }
+
+    async fn save_validators(&self, validators: &Validators) -> Result<()> {
+        let path = with_suffix(&self.path, ".meta.json");
+        tokio::fs::write(&path, serde_json::to_vec(validators)?)
+            .await
+            .map_err(|e| io_error(&path, e))
+    }
+
+    fn replace(&self, instruments: Vec<Instrument>, validators: Validators) {
+        let store = Arc::new(InstrumentStore::new(instruments));
+        *self.state.write().unwrap() = State {
+            validators: Some(validators),
+            store,
+        };
+    }
+}
+
+impl fmt::Debug for InstrumentCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.read().unwrap();
+        f.debug_struct("InstrumentCache")
+            .field("exchange", &self.exchange)
+            .field("path", &self.path)
+            .field("instruments", &state.store.len())
+            .field("fetched_at", &state.validators.as_ref().map(|v| v.fetched_at))
+            .finish()
+    }
+}
+
+/// Returns the trading day whose dump was current at `at`
+///
+/// The dump of a trading day is published that morning and stays current over
+/// the following weekend.
+fn dump_day(at: Timestamp) -> NaiveDate {
+    let day = (at.with_timezone(&ist::offset()) - TimeDelta::minutes(DUMP_PUBLISHED_MINUTES)).date_naive();
+    match day.weekday() {
+        Weekday::Sat => day - TimeDelta::days(1),
+        Weekday::Sun => day - TimeDelta::days(2),
+        _ => day,
+    }
+}
+
+async fn parse(dump: Vec<u8>) -> Result<Vec<Instrument>> {
+    let chunks = futures::stream::iter([Ok::<_, KiteError>(Bytes::from(dump))]);
+    super::parse_stream(chunks).try_collect().await
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path);
+    name.push(suffix);
+    name.into()
+}
+
+fn io_error(path: &Path, error: std::io::Error) -> KiteError {
+    KiteError::Other(format!("failed to access cached instruments at {}: {}", path.display(), error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Server;
+
+    #[test]
+    fn test_dump_day() {
+        let day = |value| dump_day(ist::parse_timestamp(value).unwrap());
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(day("2024-06-12 09:15:00"), date(2024, 6, 12));
+        // Before the morning's dump is published
+        assert_eq!(day("2024-06-12 07:00:00"), date(2024, 6, 11));
+        assert_eq!(day("2024-06-10 07:00:00"), date(2024, 6, 7));
+        assert_eq!(day("2024-06-16 23:00:00"), date(2024, 6, 14));
+    }
+
+    #[tokio::test]
+    async fn test_instrument_cache() {
+        let mut server = Server::new_async().await;
+        let client = KiteConnect::builder("key")
+            .access_token("token")
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+        let path = std::env::temp_dir().join(format!("kiteconnect-cache-{}.csv", std::process::id()));
+
+        let download = server
+            .mock("GET", "/instruments/NSE")
+            .match_header("if-none-match", mockito::Matcher::Missing)
+            .with_header("etag", "\"v1\"")
+            .with_body_from_file("mocks/instruments.csv")
+            .expect(1)
+            .create_async()
+            .await;
+        let unchanged = server
+            .mock("GET", "/instruments/NSE")
+            .match_header("if-none-match", "\"v1\"")
+            .with_status(304)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let monday = ist::parse_timestamp("2024-06-10 09:00:00").unwrap();
+        let cache = InstrumentCache::new(client.clone(), &path).exchange("NSE");
+        assert!(cache.refresh_at(monday).await.unwrap());
+        assert_eq!(cache.store().len(), 4);
+        assert_eq!(cache.get(InstrumentToken(408065)).unwrap().tradingsymbol, "INFY");
+        assert_eq!(cache.find("NFO", "NIFTY15DECFUT").unwrap().lot_size, 75);
+        assert!(cache.get(InstrumentToken(1)).is_none());
+
+        // Another process on the same day reads the copy without a request
+        let cache = InstrumentCache::new(client, &path).exchange("NSE");
+        assert!(!cache.refresh_at(monday + TimeDelta::hours(6)).await.unwrap());
+        assert_eq!(cache.get(InstrumentToken(408065)).unwrap().tradingsymbol, "INFY");
+        download.assert_async().await;
+
+        // The next day the dump is asked for again, conditionally
+        let tuesday = monday + TimeDelta::days(1);
+        assert!(!cache.refresh_at(tuesday).await.unwrap());
+        assert!(!cache.refresh_at(tuesday).await.unwrap());
+        assert_eq!(cache.fetched_at(), Some(tuesday));
+        assert_eq!(cache.store().len(), 4);
+        unchanged.assert_async().await;
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(with_suffix(&path, ".meta.json")).unwrap();
+    }
+}
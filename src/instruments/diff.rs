@@ -0,0 +1,129 @@
+//! Changes between two daily snapshots of the instruments dump
+
+use super::{Instrument, InstrumentStore};
+
+/// Changes from one instruments dump to the next, see [`InstrumentStore::diff`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InstrumentsDiff {
+    /// Instruments listed since the older dump
+    pub listed: Vec<Instrument>,
+    /// Contracts with an expiry date that are no longer listed
+    pub expired: Vec<Instrument>,
+    /// Instruments without an expiry date that are no longer listed, such as
+    /// delisted or suspended stocks
+    pub removed: Vec<Instrument>,
+    /// Instruments listed in both dumps whose lot size was revised
+    pub lot_size_changes: Vec<LotSizeChange>,
+}
+
+/// Revised lot size of an instrument
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LotSizeChange {
+    /// The instrument as listed in the newer dump
+    pub instrument: Instrument,
+    /// Lot size in the older dump
+    pub previous_lot_size: u32,
+}
+
+impl InstrumentsDiff {
+    /// Returns `true` if nothing was listed, expired, removed or revised
+    pub fn is_empty(&self) -> bool {
+        self.listed.is_empty()
+            && self.expired.is_empty()
+            && self.removed.is_empty()
+            && self.lot_size_changes.is_empty()
+    }
+}
+
+impl InstrumentStore {
+    /// Compares this dump with a `newer` one, e.g. yesterday's with today's
+    ///
+    /// Instruments are matched by exchange and tradingsymbol, since exchanges
+    /// reuse the tokens of expired contracts. Every list keeps the order of
+    /// the dump it comes from.
+    ///
+    /// ```rust
+    /// use kiteconnect::instruments::{Instrument, InstrumentStore};
+    ///
+    /// let future = |tradingsymbol: &str, lot_size| Instrument {
+    ///     tradingsymbol: tradingsymbol.to_string(),
+    ///     exchange: "NFO".to_string(),
+    ///     expiry: "2024-06-27".parse().ok(),
+    ///     lot_size,
+    ///     ..Default::default()
+    /// };
+    /// let yesterday = InstrumentStore::new(vec![future("NIFTY24MAYFUT", 25), future("NIFTY24JUNFUT", 25)]);
+    /// let today = InstrumentStore::new(vec![future("NIFTY24JUNFUT", 75), future("NIFTY24JULFUT", 75)]);
+    ///
+    /// let diff = yesterday.diff(&today);
+    /// assert_eq!(diff.listed[0].tradingsymbol, "NIFTY24JULFUT");
+    /// assert_eq!(diff.expired[0].tradingsymbol, "NIFTY24MAYFUT");
+    /// assert_eq!(diff.lot_size_changes[0].previous_lot_size, 25);
+    /// ```
+    pub fn diff(&self, newer: &InstrumentStore) -> InstrumentsDiff {
+        let mut diff = InstrumentsDiff::default();
+        for instrument in self {
+            match newer.find(&instrument.exchange, &instrument.tradingsymbol) {
+                Some(listed) if listed.lot_size != instrument.lot_size => diff.lot_size_changes.push(LotSizeChange {
+                    instrument: listed.clone(),
+                    previous_lot_size: instrument.lot_size,
+                }),
+                Some(_) => {}
+                None if instrument.expiry.is_some() => diff.expired.push(instrument.clone()),
+                None => diff.removed.push(instrument.clone()),
+            }
+        }
+        diff.listed = newer
+            .iter()
+            .filter(|instrument| self.find(&instrument.exchange, &instrument.tradingsymbol).is_none())
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instrument(exchange: &str, tradingsymbol: &str, expiry: Option<&str>, lot_size: u32) -> Instrument {
+        Instrument {
+            exchange: exchange.to_string(),
+            tradingsymbol: tradingsymbol.to_string(),
+            expiry: expiry.map(|expiry| expiry.parse().unwrap()),
+            lot_size,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let older = InstrumentStore::new(vec![
+            instrument("NSE", "INFY", None, 1),
+            instrument("NSE", "DHFL", None, 1),
+            instrument("NFO", "INFY24MAYFUT", Some("2024-05-30"), 400),
+            instrument("NFO", "INFY24JUNFUT", Some("2024-06-27"), 400),
+        ]);
+        let newer = InstrumentStore::new(vec![
+            instrument("NSE", "INFY", None, 1),
+            instrument("NFO", "INFY24JUNFUT", Some("2024-06-27"), 300),
+            instrument("NFO", "INFY24JULFUT", Some("2024-07-25"), 300),
+            instrument("BSE", "INFY", None, 1),
+        ]);
+
+        let diff = older.diff(&newer);
+        let symbols = |instruments: &[Instrument]| {
+            instruments
+                .iter()
+                .map(|i| format!("{}:{}", i.exchange, i.tradingsymbol))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(symbols(&diff.listed), ["NFO:INFY24JULFUT", "BSE:INFY"]);
+        assert_eq!(symbols(&diff.expired), ["NFO:INFY24MAYFUT"]);
+        assert_eq!(symbols(&diff.removed), ["NSE:DHFL"]);
+        assert_eq!(diff.lot_size_changes.len(), 1);
+        assert_eq!(diff.lot_size_changes[0].instrument.lot_size, 300);
+        assert_eq!(diff.lot_size_changes[0].previous_lot_size, 400);
+        assert!(newer.diff(&newer).is_empty());
+    }
+}
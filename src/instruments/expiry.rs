@@ -0,0 +1,86 @@
+//! Expiry dates of derivatives, as listed in the instruments dump
+
+use super::InstrumentStore;
+use chrono::{Datelike, NaiveDate};
+use std::collections::BTreeSet;
+
+impl InstrumentStore {
+    /// Returns the distinct expiry dates of the futures and options on the
+    /// underlying `name`, e.g. `NIFTY`, earliest first
+    ///
+    /// The dates come from the listed contracts, so expiries moved for holidays
+    /// are where the exchange put them.
+    pub fn expiries(&self, name: &str) -> Vec<NaiveDate> {
+        self.iter()
+            .filter(|instrument| instrument.name == name)
+            .filter_map(|instrument| instrument.expiry)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns the monthly expiries of the underlying `name`, the last expiry
+    /// of every month, earliest first
+    pub fn monthly_expiries(&self, name: &str) -> Vec<NaiveDate> {
+        let mut monthly: Vec<NaiveDate> = Vec::new();
+        for expiry in self.expiries(name) {
+            match monthly.last_mut() {
+                Some(last) if (last.year(), last.month()) == (expiry.year(), expiry.month()) => *last = expiry,
+                _ => monthly.push(expiry),
+            }
+        }
+        monthly
+    }
+
+    /// Returns the first expiry of the underlying `name` on or after `date`,
+    /// which is the weekly expiry for underlyings with weekly options
+    ///
+    /// Contracts trade until the close on their expiry day, so an expiry on
+    /// `date` itself is returned.
+    pub fn next_expiry(&self, name: &str, date: NaiveDate) -> Option<NaiveDate> {
+        self.expiries(name).into_iter().find(|&expiry| expiry >= date)
+    }
+
+    /// Returns the first monthly expiry of the underlying `name` on or after `date`
+    pub fn next_monthly_expiry(&self, name: &str, date: NaiveDate) -> Option<NaiveDate> {
+        self.monthly_expiries(name).into_iter().find(|&expiry| expiry >= date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruments::Instrument;
+
+    #[test]
+    fn test_expiries() {
+        let date = |value: &str| value.parse::<NaiveDate>().unwrap();
+        let contract = |name: &str, expiry: &str| Instrument {
+            name: name.to_string(),
+            expiry: Some(date(expiry)),
+            ..Default::default()
+        };
+        let store = InstrumentStore::new(vec![
+            contract("NIFTY", "2024-06-27"),
+            contract("NIFTY", "2024-06-13"),
+            contract("NIFTY", "2024-06-20"),
+            // Moved to Wednesday for a holiday
+            contract("NIFTY", "2024-07-03"),
+            contract("NIFTY", "2024-07-25"),
+            contract("NIFTY", "2024-07-25"),
+            contract("BANKNIFTY", "2024-06-12"),
+            Instrument {
+                name: "NIFTY".to_string(),
+                ..Default::default()
+            },
+        ]);
+
+        assert_eq!(store.expiries("NIFTY").len(), 5);
+        assert_eq!(store.monthly_expiries("NIFTY"), [date("2024-06-27"), date("2024-07-25")]);
+        assert_eq!(store.next_expiry("NIFTY", date("2024-06-13")), Some(date("2024-06-13")));
+        assert_eq!(store.next_expiry("NIFTY", date("2024-06-28")), Some(date("2024-07-03")));
+        assert_eq!(store.next_monthly_expiry("NIFTY", date("2024-06-28")), Some(date("2024-07-25")));
+        assert_eq!(store.next_expiry("NIFTY", date("2024-07-26")), None);
+        assert!(store.expiries("SENSEX").is_empty());
+    }
+}
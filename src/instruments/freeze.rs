@@ -0,0 +1,253 @@
+//! Freeze quantities, the largest order quantities exchanges accept for derivatives
+
+use super::records::read_records;
+use super::Instrument;
+use crate::error::{KiteError, Result};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Freeze quantities of NFO index derivatives, as published by NSE in its
+/// `qtyfreeze` file
+const NFO_INDEX_LIMITS: [(&str, u32); 5] = [
+    ("NIFTY", 1801),
+    ("BANKNIFTY", 901),
+    ("FINNIFTY", 1801),
+    ("MIDCPNIFTY", 2801),
+    ("NIFTYNXT50", 601),
+];
+
+/// Freeze quantities of derivatives by exchange and underlying
+///
+/// Exchanges reject derivative orders of the freeze quantity of their
+/// underlying or more, so larger quantities have to be placed as several
+/// orders. The default limits cover the NFO index derivatives. Stock
+/// derivatives and later revisions are loaded from the `qtyfreeze` CSV file
+/// NSE publishes with [`from_csv`](Self::from_csv), or set one by one:
+///
+/// ```rust
+/// use kiteconnect::instruments::{FreezeLimits, Instrument};
+///
+/// let limits = FreezeLimits::default().with("NFO", "RELIANCE", 10001);
+/// let nifty = Instrument {
+///     name: "NIFTY".to_string(),
+///     exchange: "NFO".to_string(),
+///     lot_size: 75,
+///     ..Default::default()
+/// };
+/// assert_eq!(limits.slice(4500, &nifty), [1800, 1800, 900]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct FreezeLimits {
+    limits: HashMap<String, HashMap<String, u32>>,
+}
+
+impl Default for FreezeLimits {
+    fn default() -> Self {
+        NFO_INDEX_LIMITS
+            .iter()
+            .fold(FreezeLimits::empty(), |limits, &(name, quantity)| limits.with("NFO", name, quantity))
+    }
+}
+
+impl FreezeLimits {
+    /// Creates a table without any limits, for slicing nothing
+    pub fn empty() -> Self {
+        FreezeLimits { limits: HashMap::new() }
+    }
+
+    /// Parses NSE's `qtyfreeze` file, with the underlyings in a `SYMBOL` column
+    /// and their freeze quantities in a `VOL_FRZ_QTY` column, into limits of
+    /// `exchange`
+    pub fn from_csv(exchange: &str, body: &[u8]) -> Result<Self> {
+        let mut records = read_records(body).into_iter();
+        let headers = records.next().unwrap_or_default();
+        let column = |name: &str| {
+            headers
+                .iter()
+                .position(|header| header.trim().eq_ignore_ascii_case(name))
+                .ok_or_else(|| KiteError::Other(format!("freeze quantities lack a {} column", name)))
+        };
+        let (symbol, quantity) = (column("SYMBOL")?, column("VOL_FRZ_QTY")?);
+
+        let mut limits = FreezeLimits::empty();
+        for record in records {
+            let (Some(name), Some(value)) = (record.get(symbol), record.get(quantity)) else {
+                continue;
+            };
+            let value = value
+                .trim()
+                .parse()
+                .map_err(|_| KiteError::Other(format!("invalid freeze quantity {:?} of {}", value, name.trim())))?;
+            limits = limits.with(exchange, name.trim(), value);
+        }
+        Ok(limits)
+    }
+
+    /// Sets the freeze quantity of the derivatives of `name` on `exchange`
+    pub fn with(mut self, exchange: &str, name: &str, quantity: u32) -> Self {
+        self.limits
+            .entry(exchange.to_string())
+            .or_default()
+            .insert(name.to_string(), quantity);
+        self
+    }
+
+    /// Returns the freeze quantity of the derivatives of `name` on `exchange`
+    pub fn get(&self, exchange: &str, name: &str) -> Option<u32> {
+        self.limits.get(exchange)?.get(name).copied()
+    }
+
+    /// Returns the largest quantity of `instrument` a single order may have,
+    /// a multiple of its lot size below its freeze quantity
+    pub fn max_order_quantity(&self, instrument: &Instrument) -> Option<u32> {
+        let freeze = self.get(&instrument.exchange, &instrument.name)?;
+        let max = freeze.saturating_sub(1);
+        match instrument.lot_size {
+            lot_size if lot_size > 1 && max >= lot_size => Some(max - max % lot_size),
+            _ => Some(max).filter(|&max| max > 0),
+        }
+    }
+
+    /// Splits `quantity` of `instrument` into order quantities below its freeze
+    /// quantity, the largest first
+    pub fn slice(&self, quantity: u32, instrument: &Instrument) -> Vec<u32> {
+        split(quantity, self.max_order_quantity(instrument))
+    }
+}
+
+/// Opt-in slicing of every order placed, set with
+/// [`KiteConnect::set_auto_slice`](crate::connect::KiteConnect::set_auto_slice)
+///
+/// Orders are split at the [freeze quantity](FreezeLimits) of their
+/// instrument and, with a maximum clip, into orders no larger than the clip,
+/// rounded down to a multiple of the lot size. A delay between the slices
+/// spreads a large order over time to reduce its market impact.
+///
+/// ```rust
+/// use kiteconnect::instruments::{AutoSlice, FreezeLimits, Instrument};
+/// use std::time::Duration;
+///
+/// let auto_slice = AutoSlice::new().max_clip(1000).delay(Duration::from_millis(200));
+/// let nifty = Instrument {
+///     name: "NIFTY".to_string(),
+///     exchange: "NFO".to_string(),
+///     lot_size: 75,
+///     ..Default::default()
+/// };
+/// assert_eq!(auto_slice.slice(&FreezeLimits::default(), 2400, &nifty), [975, 975, 450]);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AutoSlice {
+    /// Largest quantity of a slice on top of the freeze quantity, if any
+    pub max_clip: Option<u32>,
+    /// Pause between two slices
+    pub delay: Duration,
+}
+
+impl AutoSlice {
+    /// Slices at the freeze quantities only, without a delay
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the largest quantity of a slice
+    pub fn max_clip(mut self, max_clip: u32) -> Self {
+        self.max_clip = Some(max_clip);
+        self
+    }
+
+    /// Sets the pause between two slices
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Returns the largest quantity of `instrument` a slice may have, the
+    /// smaller of its freeze limit and the clip
+    ///
+    /// A clip below the lot size still allows a single lot.
+    pub fn max_order_quantity(&self, limits: &FreezeLimits, instrument: &Instrument) -> Option<u32> {
+        let clip = self.max_clip.map(|clip| match instrument.lot_size {
+            lot_size if lot_size > 1 => (clip - clip % lot_size).max(lot_size),
+            _ => clip.max(1),
+        });
+        match (limits.max_order_quantity(instrument), clip) {
+            (Some(freeze), Some(clip)) => Some(freeze.min(clip)),
+            (freeze, clip) => freeze.or(clip),
+        }
+    }
+
+    /// Splits `quantity` of `instrument` into slices, the largest first
+    pub fn slice(&self, limits: &FreezeLimits, quantity: u32, instrument: &Instrument) -> Vec<u32> {
+        split(quantity, self.max_order_quantity(limits, instrument))
+    }
+}
+
+/// Splits `quantity` into parts of at most `max`, the remainder last
+fn split(quantity: u32, max: Option<u32>) -> Vec<u32> {
+    let Some(max) = max else {
+        return vec![quantity];
+    };
+    let mut slices = vec![max; (quantity / max) as usize];
+    if !quantity.is_multiple_of(max) || slices.is_empty() {
+        slices.push(quantity % max);
+    }
+    slices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn future(exchange: &str, name: &str, lot_size: u32) -> Instrument {
+        Instrument {
+            name: name.to_string(),
+            exchange: exchange.to_string(),
+            lot_size,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_slice() {
+        let limits = FreezeLimits::default();
+        let nifty = future("NFO", "NIFTY", 75);
+        assert_eq!(limits.max_order_quantity(&nifty), Some(1800));
+        assert_eq!(limits.slice(3600, &nifty), [1800, 1800]);
+        assert_eq!(limits.slice(75, &nifty), [75]);
+        assert_eq!(limits.slice(0, &nifty), [0]);
+        // The freeze quantity is not a lot multiple for a revised lot size
+        assert_eq!(limits.max_order_quantity(&future("NFO", "BANKNIFTY", 35)), Some(875));
+        assert_eq!(limits.max_order_quantity(&future("NFO", "NIFTY", 1)), Some(1800));
+        assert_eq!(limits.slice(100_000, &future("NSE", "NIFTY", 1)), [100_000]);
+        assert_eq!(FreezeLimits::empty().slice(5000, &nifty), [5000]);
+        assert_eq!(FreezeLimits::empty().with("NFO", "NIFTY", 1).max_order_quantity(&nifty), None);
+    }
+
+    #[test]
+    fn test_auto_slice() {
+        let limits = FreezeLimits::default();
+        let nifty = future("NFO", "NIFTY", 75);
+        assert_eq!(AutoSlice::new().slice(&limits, 4500, &nifty), limits.slice(4500, &nifty));
+        let clipped = AutoSlice::new().max_clip(1000);
+        assert_eq!(clipped.max_order_quantity(&limits, &nifty), Some(975));
+        assert_eq!(clipped.slice(&limits, 1500, &nifty), [975, 525]);
+        // The freeze quantity still applies below a larger clip
+        assert_eq!(AutoSlice::new().max_clip(5000).slice(&limits, 3600, &nifty), [1800, 1800]);
+        assert_eq!(AutoSlice::new().max_clip(50).max_order_quantity(&limits, &nifty), Some(75));
+        let equity = future("NSE", "INFY", 1);
+        assert_eq!(AutoSlice::new().max_clip(400).slice(&limits, 1000, &equity), [400, 400, 200]);
+        assert_eq!(AutoSlice::new().slice(&limits, 1000, &equity), [1000]);
+    }
+
+    #[test]
+    fn test_from_csv() {
+        let body = "SR.NO.,SYMBOL ,VOL_FRZ_QTY\n1,NIFTY ,1801\n2,RELIANCE,10001\n";
+        let limits = FreezeLimits::from_csv("NFO", body.as_bytes()).unwrap();
+        assert_eq!(limits.get("NFO", "RELIANCE"), Some(10001));
+        assert_eq!(limits.get("NFO", "NIFTY"), Some(1801));
+        assert_eq!(limits.get("NFO", "BANKNIFTY"), None);
+        assert!(FreezeLimits::from_csv("NFO", b"SYMBOL,QTY\nNIFTY,1801").is_err());
+        assert!(FreezeLimits::from_csv("NFO", b"SYMBOL,VOL_FRZ_QTY\nNIFTY,lots").is_err());
+    }
+}
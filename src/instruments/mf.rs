@@ -0,0 +1,236 @@
+//! Mutual fund schemes indexed by ISIN, filtered and searched like instruments
+
+use super::search::rank;
+use crate::models::MfInstrument;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Schemes of the mutual fund instruments dump, indexed by ISIN
+///
+/// The counterpart of [`InstrumentStore`](super::InstrumentStore) for mutual
+/// funds: schemes are looked up by their ISIN, selected with an
+/// [`MfInstrumentFilter`] and [searched](Self::search) by name.
+///
+/// ```rust,no_run
+/// use kiteconnect::connect::KiteConnect;
+/// use kiteconnect::instruments::{MfInstrumentFilter, MfInstrumentStore};
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let client = KiteConnect::new("api_key", "access_token");
+/// let store = MfInstrumentStore::new(client.mf_instruments_typed().await?);
+///
+/// let filter = MfInstrumentFilter::new().scheme_type("elss").plan("direct").growth(true);
+/// for scheme in store.filter(&filter) {
+///     println!("{} {} from {}", scheme.tradingsymbol, scheme.name, scheme.minimum_purchase_amount);
+/// }
+/// let axis = store.search("axis long term");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct MfInstrumentStore {
+    instruments: Vec<MfInstrument>,
+    by_symbol: HashMap<String, usize>,
+}
+
+impl MfInstrumentStore {
+    /// Indexes `instruments`
+    ///
+    /// Should an ISIN occur twice, lookups return the later row.
+    pub fn new(instruments: Vec<MfInstrument>) -> Self {
+        let by_symbol = instruments
+            .iter()
+            .enumerate()
+            .map(|(index, instrument)| (instrument.tradingsymbol.clone(), index))
+            .collect();
+        MfInstrumentStore { instruments, by_symbol }
+    }
+
+    /// Looks up a scheme by its ISIN, e.g. `INF846K01DP8`
+    pub fn get(&self, tradingsymbol: &str) -> Option<&MfInstrument> {
+        self.by_symbol.get(tradingsymbol).map(|&index| &self.instruments[index])
+    }
+
+    /// Returns the schemes matching `filter`, in the order of the dump
+    pub fn filter<'a>(&'a self, filter: &'a MfInstrumentFilter) -> impl Iterator<Item = &'a MfInstrument> + 'a {
+        self.instruments.iter().filter(move |instrument| filter.matches(instrument))
+    }
+
+    /// Searches names and ISINs, best matches first
+    ///
+    /// Ranks schemes like [`InstrumentStore::search`](super::InstrumentStore::search),
+    /// with the AMC, scheme type, plan and dividend type as attributes, so
+    /// `axis elss direct` finds the direct plans of Axis' ELSS schemes.
+    pub fn search(&self, query: &str) -> Vec<&MfInstrument> {
+        let terms: Vec<String> = query.split_whitespace().map(str::to_uppercase).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        rank(&terms, self.iter(), |instrument| {
+            let attributes = [&instrument.amc, &instrument.scheme_type, &instrument.plan, &instrument.dividend_type];
+            (&instrument.tradingsymbol, &instrument.name, attributes)
+        })
+    }
+
+    /// Returns every scheme, in the order of the dump
+    pub fn instruments(&self) -> &[MfInstrument] {
+        &self.instruments
+    }
+
+    /// Returns an iterator over every scheme
+    pub fn iter(&self) -> std::slice::Iter<'_, MfInstrument> {
+        self.instruments.iter()
+    }
+
+    /// Returns the number of schemes
+    pub fn len(&self) -> usize {
+        self.instruments.len()
+    }
+
+    /// Returns `true` if the store holds no schemes
+    pub fn is_empty(&self) -> bool {
+        self.instruments.is_empty()
+    }
+}
+
+impl From<Vec<MfInstrument>> for MfInstrumentStore {
+    fn from(instruments: Vec<MfInstrument>) -> Self {
+        MfInstrumentStore::new(instruments)
+    }
+}
+
+impl FromIterator<MfInstrument> for MfInstrumentStore {
+    fn from_iter<I: IntoIterator<Item = MfInstrument>>(iter: I) -> Self {
+        MfInstrumentStore::new(iter.into_iter().collect())
+    }
+}
+
+impl<'a> IntoIterator for &'a MfInstrumentStore {
+    type Item = &'a MfInstrument;
+    type IntoIter = std::slice::Iter<'a, MfInstrument>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.instruments.iter()
+    }
+}
+
+impl fmt::Debug for MfInstrumentStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MfInstrumentStore")
+            .field("instruments", &self.instruments.len())
+            .finish()
+    }
+}
+
+/// Criteria for [`MfInstrumentStore::filter`]; schemes must match all that are set
+///
+/// Text criteria are matched ignoring case.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MfInstrumentFilter {
+    /// Asset management company, e.g. `AXISMUTUALFUND_MF`
+    pub amc: Option<String>,
+    /// Scheme type, e.g. `equity`, `debt` or `elss`
+    pub scheme_type: Option<String>,
+    /// `direct` or `regular`
+    pub plan: Option<String>,
+    /// Growth schemes if `true`, dividend schemes if `false`
+    pub growth: Option<bool>,
+    /// Whether the scheme accepts purchases
+    pub purchase_allowed: Option<bool>,
+}
+
+impl MfInstrumentFilter {
+    /// Creates a filter matching every scheme
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches schemes of the asset management company `amc`
+    pub fn amc(mut self, amc: &str) -> Self {
+        self.amc = Some(amc.to_string());
+        self
+    }
+
+    /// Matches schemes of `scheme_type`
+    pub fn scheme_type(mut self, scheme_type: &str) -> Self {
+        self.scheme_type = Some(scheme_type.to_string());
+        self
+    }
+
+    /// Matches `direct` or `regular` plans
+    pub fn plan(mut self, plan: &str) -> Self {
+        self.plan = Some(plan.to_string());
+        self
+    }
+
+    /// Matches growth schemes, or dividend schemes if `growth` is `false`
+    pub fn growth(mut self, growth: bool) -> Self {
+        self.growth = Some(growth);
+        self
+    }
+
+    /// Matches schemes that accept purchases, or that do not
+    pub fn purchase_allowed(mut self, allowed: bool) -> Self {
+        self.purchase_allowed = Some(allowed);
+        self
+    }
+
+    /// Returns `true` if `instrument` meets every criterion
+    pub fn matches(&self, instrument: &MfInstrument) -> bool {
+        let text = |criterion: &Option<String>, value: &str| {
+            criterion.as_deref().is_none_or(|c| c.eq_ignore_ascii_case(value))
+        };
+        text(&self.amc, &instrument.amc)
+            && text(&self.scheme_type, &instrument.scheme_type)
+            && text(&self.plan, &instrument.plan)
+            && self.growth.is_none_or(|growth| growth == instrument.is_growth())
+            && self.purchase_allowed.is_none_or(|allowed| allowed == instrument.purchase_allowed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheme(tradingsymbol: &str, amc: &str, name: &str, scheme_type: &str, plan: &str, dividend_type: &str) -> MfInstrument {
+        MfInstrument {
+            tradingsymbol: tradingsymbol.to_string(),
+            amc: amc.to_string(),
+            name: name.to_string(),
+            purchase_allowed: true,
+            scheme_type: scheme_type.to_string(),
+            plan: plan.to_string(),
+            dividend_type: dividend_type.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_mf_instrument_store() {
+        let store: MfInstrumentStore = [
+            scheme("INF846K01DP8", "AXISMUTUALFUND_MF", "Axis Equity Fund - Direct Plan - Growth", "equity", "direct", "growth"),
+            scheme("INF846K01EW2", "AXISMUTUALFUND_MF", "Axis Long Term Equity Fund - Direct Growth", "elss", "direct", "growth"),
+            scheme("INF846K01EX0", "AXISMUTUALFUND_MF", "Axis Long Term Equity Fund - Regular Dividend", "elss", "regular", "payout"),
+            scheme("INF174K01LS2", "KOTAKMAHINDRAMF", "Kotak Select Focus Fund - Direct Plan - Growth", "equity", "direct", "growth"),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(store.len(), 4);
+        assert_eq!(store.get("INF174K01LS2").unwrap().amc, "KOTAKMAHINDRAMF");
+        assert!(store.get("INF000000000").is_none());
+
+        let symbols = |schemes: Vec<&MfInstrument>| schemes.iter().map(|s| s.tradingsymbol.clone()).collect::<Vec<_>>();
+        let filter = MfInstrumentFilter::new().scheme_type("ELSS");
+        assert_eq!(symbols(store.filter(&filter).collect()), ["INF846K01EW2", "INF846K01EX0"]);
+        let filter = MfInstrumentFilter::new().amc("AXISMUTUALFUND_MF").growth(false);
+        assert_eq!(symbols(store.filter(&filter).collect()), ["INF846K01EX0"]);
+        let filter = MfInstrumentFilter::new().plan("direct").growth(true).purchase_allowed(true);
+        assert_eq!(store.filter(&filter).count(), 3);
+
+        assert_eq!(symbols(store.search("axis long term direct")), ["INF846K01EW2"]);
+        assert_eq!(symbols(store.search("kotak")), ["INF174K01LS2"]);
+        assert_eq!(symbols(store.search("INF846K01DP8")), ["INF846K01DP8"]);
+        assert!(store.search("").is_empty());
+    }
+}
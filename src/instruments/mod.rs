@@ -0,0 +1,119 @@
+//! # Instruments
+//!
+//! Kite publishes every tradable instrument and index as a CSV dump, refreshed
+//! once a day. The full dump runs to several megabytes, so
+//! [`KiteConnect::instruments_stream`] parses it while it downloads and yields
+//! typed [`Instrument`]s one by one, without holding the whole dump in memory.
+//! Natively the dump is requested with `Accept-Encoding: gzip` and decompressed
+//! on the fly, a tenth of its plain size over the wire; in the browser the
+//! `fetch` API negotiates compression itself.
+//!
+//!
+//! ```rust,no_run
+//! use futures::TryStreamExt;
+//! use kiteconnect::connect::KiteConnect;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> kiteconnect::error::Result<()> {
+//! let client = KiteConnect::new("api_key", "access_token");
+//! let mut instruments = client.instruments_stream(Some("NFO")).await?;
+//! while let Some(instrument) = instruments.try_next().await? {
+//!     if instrument.name == "NIFTY" && instrument.instrument_type == "FUT" {
+//!         println!("{} {}", instrument.tradingsymbol, instrument.instrument_token);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! To keep the dump itself, e.g. for archiving it or loading it into other
+//! tools, [`KiteConnect::instruments_to_file`] writes the CSV straight to disk.
+//! An [`InstrumentCache`] keeps such a copy up to date across process restarts,
+//! downloading the dump again only once a new trading day has begun and the
+//! dump has changed, and answers lookups from memory.
+//!
+//! An [`InstrumentStore`] indexes instruments for constant time lookups by
+//! instrument token and by exchange and tradingsymbol, and selects them by
+//! segment, type or expiry with an [`InstrumentFilter`].
+//! [`KiteConnect::instruments_filtered`] applies such a filter while the dump
+//! downloads, keeping only the matching instruments in memory. The store's
+//! [`search`](InstrumentStore::search) ranks instruments by how well their
+//! names and tradingsymbols match a query such as `infy fut`, tolerating
+//! typos, for symbol pickers. The expiries of an underlying, such as its
+//! [next weekly](InstrumentStore::next_expiry) or
+//! [monthly](InstrumentStore::next_monthly_expiry) one, are read off the listed
+//! contracts, so holiday-shifted expiries need no special casing.
+//!
+//! Mutual fund schemes, fetched with [`KiteConnect::mf_instruments_typed`],
+//! are indexed by ISIN in an [`MfInstrumentStore`], which filters them by AMC,
+//! scheme type, plan and growth or dividend option and searches them like
+//! instruments.
+//!
+//! [`DerivativeSymbol`] splits derivative tradingsymbols such as
+//! `NIFTY24MAY22000CE` into underlying, expiry, strike and option type, and
+//! formats them back.
+//!
+//! [`InstrumentStore::diff`] compares two daily dumps, reporting new listings,
+//! expired or removed contracts and revised lot sizes, so strategies can roll
+//! contracts and resize orders as the exchange changes them.
+//!
+//! [`round_to_tick`] snaps prices to the tick size of an instrument, and
+//! [`validate_order_price`] and [`validate_order_quantity`] check prices and
+//! quantities against its tick and lot size, catching the orders the exchange
+//! would reject. A client with
+//! [order checks](crate::connect::KiteConnect::set_order_checks) runs these
+//! checks before every order it places.
+//!
+//! Orders of derivatives at or above the freeze quantity of their underlying
+//! are rejected by the exchange. [`FreezeLimits`] holds these quantities,
+//! for the NFO indices out of the box and for stocks from NSE's published
+//! file, and splits larger quantities into orders the exchange accepts, as
+//! [`KiteConnect::place_order_sliced`] does. [`AutoSlice`] opts every order
+//! of a client into slicing, optionally into clips smaller than the freeze
+//! quantity and with a pause between them.
+//!
+//! The typed dump can be shared with other processes and tools as a SQLite
+//! table, through `write_sqlite` with the `sqlite` feature, or as a Parquet
+//! file, through `ticker::ParquetWriter::<Instrument>` with the `arrow`
+//! feature. Both keep integers, prices and expiry dates typed, so the table
+//! answers SQL lookups such as all NIFTY options of an expiry directly.
+//!
+//! [`KiteConnect::instruments_stream`]: crate::connect::KiteConnect::instruments_stream
+//! [`KiteConnect::instruments_to_file`]: crate::connect::KiteConnect::instruments_to_file
+//! [`KiteConnect::instruments_filtered`]: crate::connect::KiteConnect::instruments_filtered
+//! [`KiteConnect::mf_instruments_typed`]: crate::connect::KiteConnect::mf_instruments_typed
+//! [`KiteConnect::place_order_sliced`]: crate::connect::KiteConnect::place_order_sliced
+
+#[cfg(not(target_arch = "wasm32"))]
+mod cache;
+mod diff;
+mod expiry;
+mod freeze;
+mod mf;
+#[cfg(not(target_arch = "wasm32"))]
+mod parser;
+mod records;
+mod search;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+mod sqlite;
+mod store;
+mod symbol;
+mod validation;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use cache::InstrumentCache;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use parser::parse_stream;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+pub use sqlite::{read_sqlite, write_sqlite};
+pub(crate) use records::{csv_to_json, parse_mf_instruments};
+#[cfg(target_arch = "wasm32")]
+pub(crate) use records::parse_instruments;
+
+pub use crate::models::{Instrument, InstrumentToken, MfInstrument};
+pub use diff::{InstrumentsDiff, LotSizeChange};
+pub use freeze::{AutoSlice, FreezeLimits};
+pub use mf::{MfInstrumentFilter, MfInstrumentStore};
+pub use store::{InstrumentFilter, InstrumentStore};
+pub use symbol::{DerivativeSymbol, OptionType, SymbolExpiry};
+pub use validation::{round_to_tick, validate_order_price, validate_order_quantity};
@@ -0,0 +1,123 @@
+//! Incremental parsing of the instruments dump
+
+use crate::error::{KiteError, Result};
+use crate::models::{Instrument, InstrumentColumns};
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use std::collections::VecDeque;
+
+/// Parser fed with the chunks of an instruments dump as they arrive
+///
+/// Only complete lines are parsed; the rest of a chunk is kept until the next
+/// one. Fields of the dump never span lines.
+#[derive(Debug, Default)]
+struct Parser {
+    partial: Vec<u8>,
+    columns: Option<InstrumentColumns>,
+}
+
+impl Parser {
+    /// Parses the lines completed by `chunk` into `parsed`
+    fn feed(&mut self, chunk: &[u8], parsed: &mut VecDeque<Result<Instrument>>) {
+        let Some(end) = chunk.iter().rposition(|&byte| byte == b'\n') else {
+            self.partial.extend_from_slice(chunk);
+            return;
+        };
+        if self.partial.is_empty() {
+            self.parse_lines(&chunk[..=end], parsed);
+        } else {
+            let mut lines = std::mem::take(&mut self.partial);
+            lines.extend_from_slice(&chunk[..=end]);
+            self.parse_lines(&lines, parsed);
+        }
+        self.partial.extend_from_slice(&chunk[end + 1..]);
+    }
+
+    /// Parses the last line, which may lack a line break
+    fn finish(&mut self, parsed: &mut VecDeque<Result<Instrument>>) {
+        let lines = std::mem::take(&mut self.partial);
+        self.parse_lines(&lines, parsed);
+    }
+
+    fn parse_lines(&mut self, lines: &[u8], parsed: &mut VecDeque<Result<Instrument>>) {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(lines);
+        let mut record = csv::StringRecord::new();
+        loop {
+            match reader.read_record(&mut record) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => {
+                    parsed.push_back(Err(e.into()));
+                    continue;
+                }
+            }
+            match &self.columns {
+                Some(columns) => parsed.push_back(columns.parse(|index| record.get(index))),
+                None => match InstrumentColumns::new(record.iter()) {
+                    Ok(columns) => self.columns = Some(columns),
+                    Err(e) => parsed.push_back(Err(e)),
+                },
+            }
+        }
+    }
+}
+
+/// Parses the chunks of an instruments dump into instruments as they arrive
+pub(crate) fn parse_stream<S, E>(chunks: S) -> impl Stream<Item = Result<Instrument>> + Send + 'static
+where
+    S: Stream<Item = std::result::Result<Bytes, E>> + Send + 'static,
+    E: Into<KiteError>,
+{
+    let state = (Box::pin(chunks), Parser::default(), VecDeque::new(), false);
+    Box::pin(futures::stream::unfold(state, |(mut chunks, mut parser, mut parsed, mut done)| async move {
+        loop {
+            if let Some(instrument) = parsed.pop_front() {
+                return Some((instrument, (chunks, parser, parsed, done)));
+            }
+            if done {
+                return None;
+            }
+            match chunks.next().await {
+                Some(Ok(chunk)) => parser.feed(&chunk, &mut parsed),
+                Some(Err(e)) => {
+                    parsed.push_back(Err(e.into()));
+                    done = true;
+                }
+                None => {
+                    parser.finish(&mut parsed);
+                    done = true;
+                }
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::InstrumentToken;
+    use futures::TryStreamExt;
+
+    #[tokio::test]
+    async fn test_parse_stream() {
+        let dump = std::fs::read("mocks/instruments.csv").unwrap();
+        // Chunks of 7 bytes split every line, the last one lacking a line break
+        let chunks: Vec<std::result::Result<Bytes, KiteError>> =
+            dump.chunks(7).map(|chunk| Ok(Bytes::copy_from_slice(chunk))).collect();
+        let instruments: Vec<Instrument> = parse_stream(futures::stream::iter(chunks)).try_collect().await.unwrap();
+        assert_eq!(instruments.len(), 4);
+        assert_eq!(instruments[0].instrument_token, InstrumentToken(408065));
+        assert_eq!(instruments[0].tradingsymbol, "INFY");
+        assert_eq!(instruments[3].tradingsymbol, "SILVER15DECFUT");
+
+        // A malformed row fails alone
+        let chunks = [Ok::<_, KiteError>(Bytes::from("instrument_token,tradingsymbol\n1,A\nx,B\n3,C"))];
+        let results: Vec<Result<Instrument>> = parse_stream(futures::stream::iter(chunks)).collect().await;
+        assert_eq!(results.len(), 3);
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap().instrument_token, InstrumentToken(3));
+    }
+}
@@ -0,0 +1,217 @@
+//! Whole-body CSV parsing shared by all targets, built on `csv-core`
+
+use crate::error::{KiteError, Result};
+use crate::models::{MfInstrument, Price};
+#[cfg(target_arch = "wasm32")]
+use crate::models::{Instrument, InstrumentColumns};
+use chrono::NaiveDate;
+use csv_core::{ReadRecordResult, Reader};
+use serde_json::Value as JsonValue;
+
+/// Splits a CSV body into records of fields, skipping empty lines
+pub(crate) fn read_records(mut input: &[u8]) -> Vec<Vec<String>> {
+    let mut reader = Reader::new();
+    let mut output = vec![0; 1024];
+    let mut ends = vec![0; 16];
+    let (mut output_len, mut ends_len) = (0, 0);
+    let mut records = Vec::new();
+    loop {
+        let (result, read, written, ended) =
+            reader.read_record(input, &mut output[output_len..], &mut ends[ends_len..]);
+        input = &input[read..];
+        output_len += written;
+        ends_len += ended;
+        match result {
+            // An empty input tells the reader the body has ended
+            ReadRecordResult::InputEmpty => {}
+            ReadRecordResult::OutputFull => output.resize(output.len() * 2, 0),
+            ReadRecordResult::OutputEndsFull => ends.resize(ends.len() * 2, 0),
+            ReadRecordResult::Record => {
+                let mut start = 0;
+                let fields = ends[..ends_len]
+                    .iter()
+                    .map(|&end| {
+                        let field = String::from_utf8_lossy(&output[start..end]).into_owned();
+                        start = end;
+                        field
+                    })
+                    .collect();
+                records.push(fields);
+                output_len = 0;
+                ends_len = 0;
+            }
+            ReadRecordResult::End => return records,
+        }
+    }
+}
+
+/// Parses a CSV body into an array of objects keyed by the header row, with
+/// every field as a string
+pub(crate) fn csv_to_json(body: &[u8]) -> Result<JsonValue> {
+    let mut records = read_records(body).into_iter();
+    let headers = records.next().unwrap_or_default();
+    let rows = records
+        .enumerate()
+        .map(|(index, record)| {
+            if record.len() != headers.len() {
+                return Err(unequal_lengths(index + 2, record.len(), headers.len()));
+            }
+            Ok(JsonValue::Object(
+                headers
+                    .iter()
+                    .cloned()
+                    .zip(record.into_iter().map(JsonValue::String))
+                    .collect(),
+            ))
+        })
+        .collect::<Result<_>>()?;
+    Ok(JsonValue::Array(rows))
+}
+
+/// Parses an instruments dump into typed instruments
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn parse_instruments(body: &[u8]) -> Result<Vec<Instrument>> {
+    let mut records = read_records(body).into_iter();
+    let headers = records.next().unwrap_or_default();
+    let columns = InstrumentColumns::new(headers.iter().map(String::as_str))?;
+    records
+        .map(|record| columns.parse(|index| record.get(index).map(String::as_str)))
+        .collect()
+}
+
+/// Parses a mutual fund instruments dump into typed schemes
+///
+/// Columns are matched by name, and empty fields take their default.
+pub(crate) fn parse_mf_instruments(body: &[u8]) -> Result<Vec<MfInstrument>> {
+    let mut records = read_records(body).into_iter();
+    let headers = records.next().unwrap_or_default();
+    let position = |name: &str| headers.iter().position(|header| header.trim() == name);
+    if position("tradingsymbol").is_none() {
+        return Err(KiteError::Other("MF instruments dump lacks the tradingsymbol column".to_string()));
+    }
+    let columns: Vec<Option<usize>> = MF_COLUMNS.iter().map(|name| position(name)).collect();
+
+    records
+        .map(|record| {
+            let text = |column: usize| {
+                columns[column]
+                    .and_then(|index| record.get(index))
+                    .map_or("", |field| field.trim())
+            };
+            let parse = |column: usize| -> Result<f64> {
+                match text(column) {
+                    "" => Ok(0.0),
+                    value => value.parse().map_err(|_| invalid_mf(MF_COLUMNS[column], value)),
+                }
+            };
+            let price = |column: usize| -> Result<Price> {
+                match text(column) {
+                    "" => Ok(Price::default()),
+                    value => value.parse().map_err(|_| invalid_mf(MF_COLUMNS[column], value)),
+                }
+            };
+            let last_price_date = match text(15) {
+                "" => None,
+                value => Some(NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| invalid_mf(MF_COLUMNS[15], value))?),
+            };
+            Ok(MfInstrument {
+                tradingsymbol: text(0).to_string(),
+                amc: text(1).to_string(),
+                name: text(2).to_string(),
+                purchase_allowed: text(3) == "1",
+                redemption_allowed: text(4) == "1",
+                minimum_purchase_amount: price(5)?,
+                purchase_amount_multiplier: price(6)?,
+                minimum_additional_purchase_amount: price(7)?,
+                minimum_redemption_quantity: parse(8)?,
+                redemption_quantity_multiplier: parse(9)?,
+                dividend_type: text(10).to_string(),
+                scheme_type: text(11).to_string(),
+                plan: text(12).to_string(),
+                settlement_type: text(13).to_string(),
+                last_price: price(14)?,
+                last_price_date,
+            })
+        })
+        .collect()
+}
+
+/// Columns of the mutual fund instruments dump, in the order of the fields of [`MfInstrument`]
+const MF_COLUMNS: [&str; 16] = [
+    "tradingsymbol",
+    "amc",
+    "name",
+    "purchase_allowed",
+    "redemption_allowed",
+    "minimum_purchase_amount",
+    "purchase_amount_multiplier",
+    "minimum_additional_purchase_amount",
+    "minimum_redemption_quantity",
+    "redemption_quantity_multiplier",
+    "dividend_type",
+    "scheme_type",
+    "plan",
+    "settlement_type",
+    "last_price",
+    "last_price_date",
+];
+
+fn invalid_mf(name: &str, value: &str) -> KiteError {
+    KiteError::Other(format!("invalid {} {:?} in MF instruments dump", name, value))
+}
+
+fn unequal_lengths(line: usize, fields: usize, headers: usize) -> KiteError {
+    KiteError::Other(format!(
+        "CSV parsing failed: line {} has {} fields, but the header has {}",
+        line, fields, headers
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_records() {
+        let body = "a,b\n\"quoted, with comma\",\"\"\"escaped\"\"\"\r\n\n1,2";
+        assert_eq!(
+            read_records(body.as_bytes()),
+            [vec!["a", "b"], vec!["quoted, with comma", "\"escaped\""], vec!["1", "2"]]
+        );
+        // Fields longer than the initial buffers
+        let long = "x".repeat(5000);
+        let body = format!("{}\n", vec![long.as_str(); 40].join(","));
+        assert_eq!(read_records(body.as_bytes()), [vec![long; 40]]);
+        assert!(read_records(b"").is_empty());
+    }
+
+    #[test]
+    fn test_csv_to_json() {
+        let dump = std::fs::read("mocks/mf_instruments.csv").unwrap();
+        let json = csv_to_json(&dump).unwrap();
+        assert_eq!(json[0]["tradingsymbol"].as_str(), Some("INF846K01DP8"));
+
+        assert_eq!(csv_to_json(b"").unwrap(), JsonValue::Array(Vec::new()));
+        assert!(csv_to_json(b"a,b\n1,2\n3").is_err());
+    }
+
+    #[test]
+    fn test_parse_mf_instruments() {
+        let dump = std::fs::read("mocks/mf_instruments.csv").unwrap();
+        let schemes = parse_mf_instruments(&dump).unwrap();
+        assert_eq!(schemes.len(), 4);
+        let elss = &schemes[1];
+        assert_eq!(elss.tradingsymbol, "INF846K01EW2");
+        assert_eq!(elss.amc, "AXISMUTUALFUND_MF");
+        assert!(elss.purchase_allowed && elss.redemption_allowed);
+        assert_eq!(elss.purchase_amount_multiplier, "500".parse::<Price>().unwrap());
+        assert_eq!(elss.redemption_quantity_multiplier, 0.001);
+        assert_eq!(elss.scheme_type, "elss");
+        assert!(elss.is_growth() && elss.is_direct() && !elss.is_dividend());
+        assert_eq!(elss.last_price.to_string(), "33.0425");
+        assert_eq!(elss.last_price_date, NaiveDate::from_ymd_opt(2016, 11, 11));
+
+        assert!(parse_mf_instruments(b"isin,amc\nINF1,X").is_err());
+        assert!(parse_mf_instruments(b"tradingsymbol,last_price\nINF1,nav").is_err());
+    }
+}
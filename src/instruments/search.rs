@@ -0,0 +1,204 @@
+//! Ranked search over instrument names and tradingsymbols, for symbol pickers
+
+use super::{Instrument, InstrumentStore};
+
+/// Score of a term equal to the tradingsymbol
+const EXACT_SYMBOL: u32 = 100;
+/// Score of a term the tradingsymbol starts with
+const SYMBOL_PREFIX: u32 = 60;
+/// Score of a term equal to the name
+const EXACT_NAME: u32 = 50;
+/// Score of a term a word of the name starts with
+const NAME_PREFIX: u32 = 40;
+/// Score of a term equal to an attribute, such as the instrument type, segment
+/// or exchange, e.g. `fut`
+const ATTRIBUTE: u32 = 30;
+/// Score of a term contained in the tradingsymbol or name
+const CONTAINED: u32 = 20;
+/// Score of a term one or two typos away from the tradingsymbol or a word of the name
+const TYPO: u32 = 10;
+/// Score of a term whose letters occur in order in the tradingsymbol
+const SUBSEQUENCE: u32 = 5;
+
+impl InstrumentStore {
+    /// Searches names and tradingsymbols, best matches first
+    ///
+    /// The query is split into terms, matched case insensitively, and every
+    /// term has to match. Terms rank higher the closer they match: the whole
+    /// tradingsymbol, its start, the name or the start of one of its words, the
+    /// instrument type, segment or exchange, then anywhere in the tradingsymbol
+    /// or name. Terms with a typo or with letters left out still match, at the
+    /// lowest rank. Equally ranked instruments are ordered by the length of
+    /// their tradingsymbol, so `infy fut` lists the INFY futures first:
+    ///
+    /// ```rust
+    /// use kiteconnect::instruments::{Instrument, InstrumentStore};
+    ///
+    /// let instrument = |tradingsymbol: &str, instrument_type: &str| Instrument {
+    ///     tradingsymbol: tradingsymbol.to_string(),
+    ///     name: "INFOSYS".to_string(),
+    ///     instrument_type: instrument_type.to_string(),
+    ///     ..Default::default()
+    /// };
+    /// let store = InstrumentStore::new(vec![
+    ///     instrument("INFY24JUN1500CE", "CE"),
+    ///     instrument("INFY24JUNFUT", "FUT"),
+    ///     instrument("INFY", "EQ"),
+    /// ]);
+    /// let symbols = |query| store.search(query).iter().map(|i| i.tradingsymbol.as_str()).collect::<Vec<_>>();
+    /// assert_eq!(symbols("infy fut"), ["INFY24JUNFUT"]);
+    /// assert_eq!(symbols("infosis"), ["INFY", "INFY24JUNFUT", "INFY24JUN1500CE"]);
+    /// ```
+    pub fn search(&self, query: &str) -> Vec<&Instrument> {
+        let terms: Vec<String> = query.split_whitespace().map(str::to_uppercase).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        rank(&terms, self.iter(), |instrument| {
+            let attributes = [&instrument.instrument_type, &instrument.segment, &instrument.exchange];
+            (&instrument.tradingsymbol, &instrument.name, attributes)
+        })
+    }
+}
+
+/// Ranks the `items` every upper case term of `terms` matches, best first,
+/// given the tradingsymbol, name and attributes of each
+pub(super) fn rank<'a, T, const N: usize>(
+    terms: &[String],
+    items: impl Iterator<Item = &'a T>,
+    fields: impl Fn(&'a T) -> (&'a String, &'a String, [&'a String; N]),
+) -> Vec<&'a T> {
+    let mut ranked: Vec<(u32, &String, &T)> = items
+        .filter_map(|item| {
+            let (symbol, name, attributes) = fields(item);
+            let mut total = 0;
+            for term in terms {
+                total += score(term, symbol, name, &attributes)?;
+            }
+            Some((total, symbol, item))
+        })
+        .collect();
+    ranked.sort_by(|(a_score, a, _), (b_score, b, _)| {
+        b_score
+            .cmp(a_score)
+            .then(a.len().cmp(&b.len()))
+            .then_with(|| a.cmp(b))
+    });
+    ranked.into_iter().map(|(_, _, item)| item).collect()
+}
+
+/// Scores how well an upper case `term` matches an instrument, `None` if it does not
+fn score(term: &str, tradingsymbol: &str, name: &str, attributes: &[&String]) -> Option<u32> {
+    let symbol = tradingsymbol.to_uppercase();
+    let name = name.to_uppercase();
+    let mut words = name.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty());
+
+    let score = if symbol == term {
+        EXACT_SYMBOL
+    } else if symbol.starts_with(term) {
+        SYMBOL_PREFIX
+    } else if name == term {
+        EXACT_NAME
+    } else if words.clone().any(|word| word.starts_with(term)) {
+        NAME_PREFIX
+    } else if attributes.iter().any(|attribute| attribute.eq_ignore_ascii_case(term))
+    {
+        ATTRIBUTE
+    } else if symbol.contains(term) || name.contains(term) {
+        CONTAINED
+    } else if within_typos(term, &symbol) || words.any(|word| within_typos(term, word)) {
+        TYPO
+    } else if is_subsequence(term, &symbol) {
+        SUBSEQUENCE
+    } else {
+        return None;
+    };
+    Some(score)
+}
+
+/// Returns `true` if `term` is at most one typo, or two for longer terms, away
+/// from `word` or from a start of it as long as `term`
+fn within_typos(term: &str, word: &str) -> bool {
+    let term: Vec<char> = term.chars().collect();
+    let allowed = match term.len() {
+        0..=3 => return false,
+        4..=7 => 1,
+        _ => 2,
+    };
+    let word: Vec<char> = word.chars().collect();
+    let start = &word[..word.len().min(term.len())];
+    edit_distance(&term, &word) <= allowed || edit_distance(&term, start) <= allowed
+}
+
+/// Levenshtein distance between `a` and `b`
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Returns `true` if the characters of `term` occur in `text` in order
+fn is_subsequence(term: &str, text: &str) -> bool {
+    let mut text = text.chars();
+    term.chars().all(|c| text.any(|t| t == c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instrument(tradingsymbol: &str, name: &str, instrument_type: &str, exchange: &str) -> Instrument {
+        Instrument {
+            tradingsymbol: tradingsymbol.to_string(),
+            name: name.to_string(),
+            instrument_type: instrument_type.to_string(),
+            exchange: exchange.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let store = InstrumentStore::new(vec![
+            instrument("NIFTY24JUNFUT", "NIFTY", "FUT", "NFO"),
+            instrument("INFY24JUN1500CE", "INFY", "CE", "NFO"),
+            instrument("INFY24JUNFUT", "INFY", "FUT", "NFO"),
+            instrument("INFY", "INFOSYS", "EQ", "BSE"),
+            instrument("INFY", "INFOSYS", "EQ", "NSE"),
+            instrument("HDFCBANK", "HDFC BANK", "EQ", "NSE"),
+        ]);
+        let found = |query| {
+            store
+                .search(query)
+                .iter()
+                .map(|i| format!("{}:{}", i.exchange, i.tradingsymbol))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(found("infy"), ["BSE:INFY", "NSE:INFY", "NFO:INFY24JUNFUT", "NFO:INFY24JUN1500CE"]);
+        assert_eq!(found("infy fut"), ["NFO:INFY24JUNFUT"]);
+        assert_eq!(found("INFY nse"), ["NSE:INFY"]);
+        assert_eq!(found("bank"), ["NSE:HDFCBANK"]);
+        // A typo, and letters left out
+        assert_eq!(found("hdfcbnak"), ["NSE:HDFCBANK"]);
+        assert_eq!(found("nfty fut"), ["NFO:NIFTY24JUNFUT"]);
+        assert!(found("reliance").is_empty());
+        assert!(found("  ").is_empty());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(edit_distance(&chars("INFOSIS"), &chars("INFOSYS")), 1);
+        assert_eq!(edit_distance(&chars("BNAK"), &chars("BANK")), 2);
+        assert_eq!(edit_distance(&chars(""), &chars("INFY")), 4);
+    }
+}
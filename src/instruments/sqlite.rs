@@ -0,0 +1,163 @@
+//! Export of instruments into SQLite tables
+
+use crate::error::Result;
+use crate::models::{price_to_f64, Instrument, InstrumentToken, Price};
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, Row};
+
+/// Columns of an instruments table, in the order of the fields of [`Instrument`]
+const COLUMNS: &str = "instrument_token INTEGER NOT NULL, \
+    exchange_token INTEGER NOT NULL, \
+    tradingsymbol TEXT NOT NULL, \
+    name TEXT NOT NULL, \
+    last_price REAL NOT NULL, \
+    expiry TEXT, \
+    strike REAL NOT NULL, \
+    tick_size REAL NOT NULL, \
+    lot_size INTEGER NOT NULL, \
+    instrument_type TEXT NOT NULL, \
+    segment TEXT NOT NULL, \
+    exchange TEXT NOT NULL";
+
+/// Replaces the contents of the SQLite table `table` with `instruments`
+///
+/// The table is created with a column per field of [`Instrument`], prices as
+/// `REAL` and the expiry as `YYYY-MM-DD` text, so SQLite's date functions
+/// apply, and is indexed by instrument token and by exchange and
+/// tradingsymbol. The table is replaced in a single transaction, so other
+/// processes reading the database see either the old or the new dump.
+///
+/// ```rust,no_run
+/// use kiteconnect::connect::KiteConnect;
+/// use kiteconnect::instruments;
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let client = KiteConnect::new("api_key", "access_token");
+/// let mut connection = rusqlite::Connection::open("instruments.db")?;
+/// instruments::write_sqlite(&mut connection, "instruments", &client.instruments_typed(None).await?)?;
+///
+/// let token: u32 = connection.query_row(
+///     "SELECT instrument_token FROM instruments WHERE exchange = 'NSE' AND tradingsymbol = 'INFY'",
+///     [],
+///     |row| row.get(0),
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn write_sqlite(connection: &mut Connection, table: &str, instruments: &[Instrument]) -> Result<()> {
+    let name = quote(table);
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(&format!(
+        "DROP TABLE IF EXISTS {name};
+         CREATE TABLE {name} ({COLUMNS});
+         CREATE INDEX {token_index} ON {name} (instrument_token);
+         CREATE INDEX {symbol_index} ON {name} (exchange, tradingsymbol);",
+        name = name,
+        COLUMNS = COLUMNS,
+        token_index = quote(&format!("{}_instrument_token", table)),
+        symbol_index = quote(&format!("{}_tradingsymbol", table)),
+    ))?;
+    {
+        let mut insert = transaction.prepare(&format!(
+            "INSERT INTO {} VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            name
+        ))?;
+        for instrument in instruments {
+            insert.execute(params![
+                instrument.instrument_token.0,
+                instrument.exchange_token,
+                instrument.tradingsymbol,
+                instrument.name,
+                price_to_f64(instrument.last_price),
+                instrument.expiry.map(|expiry| expiry.to_string()),
+                price_to_f64(instrument.strike),
+                price_to_f64(instrument.tick_size),
+                instrument.lot_size,
+                instrument.instrument_type,
+                instrument.segment,
+                instrument.exchange,
+            ])?;
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+/// Reads back the instruments written to `table` by [`write_sqlite`]
+pub fn read_sqlite(connection: &Connection, table: &str) -> Result<Vec<Instrument>> {
+    // Prices are read as text, which SQLite formats with up to 15 significant
+    // digits, so they parse into decimals without binary noise
+    let mut select = connection.prepare(&format!(
+        "SELECT instrument_token, exchange_token, tradingsymbol, name, CAST(last_price AS TEXT), \
+         expiry, CAST(strike AS TEXT), CAST(tick_size AS TEXT), lot_size, instrument_type, segment, \
+         exchange FROM {}",
+        quote(table)
+    ))?;
+    let instruments = select
+        .query_map([], |row| {
+            Ok(Instrument {
+                instrument_token: InstrumentToken(row.get(0)?),
+                exchange_token: row.get(1)?,
+                tradingsymbol: row.get(2)?,
+                name: row.get(3)?,
+                last_price: price(row, 4)?,
+                expiry: row
+                    .get::<_, Option<String>>(5)?
+                    .map(|expiry| expiry.parse())
+                    .transpose()
+                    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(5, Type::Text, Box::new(e)))?,
+                strike: price(row, 6)?,
+                tick_size: price(row, 7)?,
+                lot_size: row.get(8)?,
+                instrument_type: row.get(9)?,
+                segment: row.get(10)?,
+                exchange: row.get(11)?,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(instruments)
+}
+
+fn price(row: &Row<'_>, index: usize) -> rusqlite::Result<Price> {
+    row.get::<_, String>(index)?
+        .parse()
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Real, Box::new(e)))
+}
+
+/// Quotes an SQL identifier
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_write_sqlite() {
+        let dump = std::fs::read("mocks/instruments.csv").unwrap();
+        let chunks = futures::stream::iter([Ok::<_, crate::error::KiteError>(bytes::Bytes::from(dump))]);
+        let instruments: Vec<Instrument> =
+            futures::TryStreamExt::try_collect(crate::instruments::parse_stream(chunks)).await.unwrap();
+
+        let mut connection = Connection::open_in_memory().unwrap();
+        write_sqlite(&mut connection, "nse \"dump\"", &instruments[..1]).unwrap();
+        write_sqlite(&mut connection, "nse \"dump\"", &instruments).unwrap();
+        assert_eq!(read_sqlite(&connection, "nse \"dump\"").unwrap(), instruments);
+
+        let (lot_size, expiry): (u32, String) = connection
+            .query_row(
+                "SELECT lot_size, expiry FROM \"nse \"\"dump\"\"\" WHERE tradingsymbol = 'NIFTY15DECFUT'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((lot_size, expiry.as_str()), (75, "2015-12-31"));
+        let column_type: String = connection
+            .query_row("SELECT typeof(tick_size) FROM \"nse \"\"dump\"\"\" LIMIT 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(column_type, "real");
+        assert!(read_sqlite(&connection, "missing").is_err());
+    }
+}
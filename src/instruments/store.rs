@@ -0,0 +1,381 @@
+//! Instruments indexed for lookups by token and by tradingsymbol
+
+use super::{Instrument, OptionType};
+use crate::models::{InstrumentToken, Price};
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Instruments of a dump, indexed by instrument token and by exchange and
+/// tradingsymbol
+///
+/// Lookups take constant time, while [`filter`](Self::filter) selects
+/// instruments by segment, type, expiry and underlying, and
+/// [`derivatives_of`](Self::derivatives_of) returns the futures and options on
+/// an underlying.
+///
+/// ```rust,no_run
+/// use kiteconnect::connect::KiteConnect;
+/// use kiteconnect::instruments::{InstrumentFilter, InstrumentStore};
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let client = KiteConnect::new("api_key", "access_token");
+/// let store = InstrumentStore::new(client.instruments_typed(None).await?);
+///
+/// let infy = store.find("NSE", "INFY").expect("INFY is listed");
+/// assert_eq!(store.get(infy.instrument_token), Some(infy));
+///
+/// let filter = InstrumentFilter::new().name("NIFTY").segment("NFO-FUT");
+/// for future in store.filter(&filter) {
+///     println!("{} expires on {:?}", future.tradingsymbol, future.expiry);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct InstrumentStore {
+    instruments: Vec<Instrument>,
+    by_token: HashMap<InstrumentToken, usize>,
+    by_symbol: HashMap<String, HashMap<String, usize>>,
+    /// Futures and options by exchange and underlying name
+    derivatives: HashMap<String, HashMap<String, Vec<usize>>>,
+}
+
+/// Derivatives exchanges of the exchanges underlyings are listed on
+const DERIVATIVE_EXCHANGES: [(&str, &str); 4] = [("NSE", "NFO"), ("BSE", "BFO"), ("MCX", "MCX"), ("CDS", "CDS")];
+
+/// Names derivatives on indices go by, which differ from the tradingsymbols of the indices
+const INDEX_NAMES: [(&str, &str); 7] = [
+    ("NIFTY 50", "NIFTY"),
+    ("NIFTY BANK", "BANKNIFTY"),
+    ("NIFTY FIN SERVICE", "FINNIFTY"),
+    ("NIFTY MID SELECT", "MIDCPNIFTY"),
+    ("NIFTY NEXT 50", "NIFTYNXT50"),
+    ("SENSEX", "SENSEX"),
+    ("BANKEX", "BANKEX"),
+];
+
+impl InstrumentStore {
+    /// Indexes `instruments`
+    ///
+    /// Should a token or tradingsymbol occur twice, lookups return the later row.
+    pub fn new(instruments: Vec<Instrument>) -> Self {
+        let mut by_token = HashMap::with_capacity(instruments.len());
+        let mut by_symbol: HashMap<String, HashMap<String, usize>> = HashMap::new();
+        let mut derivatives: HashMap<String, HashMap<String, Vec<usize>>> = HashMap::new();
+        for (index, instrument) in instruments.iter().enumerate() {
+            by_token.insert(instrument.instrument_token, index);
+            by_symbol
+                .entry(instrument.exchange.clone())
+                .or_default()
+                .insert(instrument.tradingsymbol.clone(), index);
+            if matches!(instrument.instrument_type.as_str(), "FUT" | "CE" | "PE") {
+                derivatives
+                    .entry(instrument.exchange.clone())
+                    .or_default()
+                    .entry(instrument.name.clone())
+                    .or_default()
+                    .push(index);
+            }
+        }
+        InstrumentStore {
+            instruments,
+            by_token,
+            by_symbol,
+            derivatives,
+        }
+    }
+
+    /// Looks up an instrument by its instrument token
+    pub fn get(&self, instrument_token: impl Into<InstrumentToken>) -> Option<&Instrument> {
+        self.by_token.get(&instrument_token.into()).map(|&index| &self.instruments[index])
+    }
+
+    /// Looks up an instrument by its exchange and tradingsymbol, e.g. `("NSE", "INFY")`
+    pub fn find(&self, exchange: &str, tradingsymbol: &str) -> Option<&Instrument> {
+        let &index = self.by_symbol.get(exchange)?.get(tradingsymbol)?;
+        Some(&self.instruments[index])
+    }
+
+    /// Returns the futures and options on an underlying given as
+    /// `EXCHANGE:TRADINGSYMBOL`, e.g. `NSE:RELIANCE` or `NSE:NIFTY 50`
+    ///
+    /// The contracts of every expiry are returned, ordered by expiry, futures
+    /// first, then by strike with calls before puts. Derivatives of NSE and BSE
+    /// listings trade on NFO and BFO respectively.
+    pub fn derivatives_of(&self, underlying: &str) -> Vec<&Instrument> {
+        let Some(indices) = self.derivative_indices(underlying) else {
+            return Vec::new();
+        };
+        let mut derivatives: Vec<&Instrument> = indices.iter().map(|&index| &self.instruments[index]).collect();
+        derivatives.sort_by(|a, b| {
+            a.expiry
+                .cmp(&b.expiry)
+                .then_with(|| (a.instrument_type != "FUT").cmp(&(b.instrument_type != "FUT")))
+                .then_with(|| a.strike.partial_cmp(&b.strike).unwrap_or(std::cmp::Ordering::Equal))
+                .then_with(|| a.instrument_type.cmp(&b.instrument_type))
+        });
+        derivatives
+    }
+
+    /// Looks up an option on an underlying given as `EXCHANGE:TRADINGSYMBOL`,
+    /// like in [`derivatives_of`](Self::derivatives_of), by expiry, strike and type
+    pub fn option(
+        &self,
+        underlying: &str,
+        expiry: NaiveDate,
+        strike: Price,
+        option_type: OptionType,
+    ) -> Option<&Instrument> {
+        self.derivative_indices(underlying)?
+            .iter()
+            .map(|&index| &self.instruments[index])
+            .find(|instrument| {
+                instrument.expiry == Some(expiry)
+                    && instrument.strike == strike
+                    && instrument.instrument_type == option_type.as_str()
+            })
+    }
+
+    fn derivative_indices(&self, underlying: &str) -> Option<&Vec<usize>> {
+        let (exchange, tradingsymbol) = underlying.split_once(':')?;
+        let exchange = DERIVATIVE_EXCHANGES
+            .iter()
+            .find(|(listed, _)| *listed == exchange)
+            .map_or(exchange, |(_, derivatives)| derivatives);
+        let name = INDEX_NAMES
+            .iter()
+            .find(|(index, _)| *index == tradingsymbol)
+            .map_or(tradingsymbol, |(_, name)| name);
+        self.derivatives.get(exchange)?.get(name)
+    }
+
+    /// Returns the instruments matching `filter`, in the order of the dump
+    pub fn filter<'a>(&'a self, filter: &'a InstrumentFilter) -> impl Iterator<Item = &'a Instrument> + 'a {
+        self.instruments.iter().filter(move |instrument| filter.matches(instrument))
+    }
+
+    /// Returns every instrument, in the order of the dump
+    pub fn instruments(&self) -> &[Instrument] {
+        &self.instruments
+    }
+
+    /// Returns an iterator over every instrument
+    pub fn iter(&self) -> std::slice::Iter<'_, Instrument> {
+        self.instruments.iter()
+    }
+
+    /// Returns the number of instruments
+    pub fn len(&self) -> usize {
+        self.instruments.len()
+    }
+
+    /// Returns `true` if the store holds no instruments
+    pub fn is_empty(&self) -> bool {
+        self.instruments.is_empty()
+    }
+}
+
+impl From<Vec<Instrument>> for InstrumentStore {
+    fn from(instruments: Vec<Instrument>) -> Self {
+        InstrumentStore::new(instruments)
+    }
+}
+
+impl FromIterator<Instrument> for InstrumentStore {
+    fn from_iter<I: IntoIterator<Item = Instrument>>(iter: I) -> Self {
+        InstrumentStore::new(iter.into_iter().collect())
+    }
+}
+
+impl<'a> IntoIterator for &'a InstrumentStore {
+    type Item = &'a Instrument;
+    type IntoIter = std::slice::Iter<'a, Instrument>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.instruments.iter()
+    }
+}
+
+impl fmt::Debug for InstrumentStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InstrumentStore")
+            .field("instruments", &self.instruments.len())
+            .finish()
+    }
+}
+
+/// Criteria for [`InstrumentStore::filter`]; instruments must match all that are set
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InstrumentFilter {
+    /// Exchange, e.g. `NFO`
+    pub exchange: Option<String>,
+    /// Segment, e.g. `NFO-OPT`
+    pub segment: Option<String>,
+    /// EQ, FUT, CE or PE
+    pub instrument_type: Option<String>,
+    /// Name of the underlying, e.g. `NIFTY`
+    pub name: Option<String>,
+    /// Part of the name, matched ignoring case
+    pub name_contains: Option<String>,
+    /// Exact expiry date
+    pub expiry: Option<NaiveDate>,
+    /// Earliest expiry date
+    pub expiry_from: Option<NaiveDate>,
+    /// Latest expiry date
+    pub expiry_to: Option<NaiveDate>,
+}
+
+impl InstrumentFilter {
+    /// Creates a filter matching every instrument
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches instruments of `exchange`
+    pub fn exchange(mut self, exchange: &str) -> Self {
+        self.exchange = Some(exchange.to_string());
+        self
+    }
+
+    /// Matches instruments of `segment`
+    pub fn segment(mut self, segment: &str) -> Self {
+        self.segment = Some(segment.to_string());
+        self
+    }
+
+    /// Matches instruments of `instrument_type`
+    pub fn instrument_type(mut self, instrument_type: &str) -> Self {
+        self.instrument_type = Some(instrument_type.to_string());
+        self
+    }
+
+    /// Matches instruments named `name`, such as the derivatives of an underlying
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Matches instruments whose name contains `text`, ignoring case, e.g.
+    /// `bank` for the Bank Nifty derivatives and banking stocks
+    pub fn name_contains(mut self, text: &str) -> Self {
+        self.name_contains = Some(text.to_string());
+        self
+    }
+
+    /// Matches instruments expiring on `expiry`
+    pub fn expiry(mut self, expiry: NaiveDate) -> Self {
+        self.expiry = Some(expiry);
+        self
+    }
+
+    /// Matches instruments expiring between `from` and `to`, both inclusive
+    pub fn expiring_between(mut self, from: NaiveDate, to: NaiveDate) -> Self {
+        self.expiry_from = Some(from);
+        self.expiry_to = Some(to);
+        self
+    }
+
+    /// Returns `true` if `instrument` meets every criterion
+    pub fn matches(&self, instrument: &Instrument) -> bool {
+        let text = |criterion: &Option<String>, value: &str| criterion.as_deref().is_none_or(|c| c == value);
+        let expiry = |bound: Option<NaiveDate>, within: fn(NaiveDate, NaiveDate) -> bool| {
+            bound.is_none_or(|bound| instrument.expiry.is_some_and(|expiry| within(expiry, bound)))
+        };
+        text(&self.exchange, &instrument.exchange)
+            && text(&self.segment, &instrument.segment)
+            && text(&self.instrument_type, &instrument.instrument_type)
+            && text(&self.name, &instrument.name)
+            && self.name_contains.as_deref().is_none_or(|part| {
+                instrument.name.to_ascii_lowercase().contains(&part.to_ascii_lowercase())
+            })
+            && expiry(self.expiry, |expiry, bound| expiry == bound)
+            && expiry(self.expiry_from, |expiry, bound| expiry >= bound)
+            && expiry(self.expiry_to, |expiry, bound| expiry <= bound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Price;
+
+    fn instrument(token: u32, exchange: &str, tradingsymbol: &str, segment: &str, expiry: Option<&str>) -> Instrument {
+        Instrument {
+            instrument_token: InstrumentToken(token),
+            tradingsymbol: tradingsymbol.to_string(),
+            name: "INFOSYS".to_string(),
+            expiry: expiry.map(|expiry| expiry.parse().unwrap()),
+            instrument_type: segment.rsplit('-').next().unwrap().to_string(),
+            segment: segment.to_string(),
+            exchange: exchange.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_instrument_store() {
+        let store: InstrumentStore = [
+            instrument(408065, "NSE", "INFY", "NSE", None),
+            instrument(1, "BSE", "INFY", "BSE", None),
+            instrument(2, "NFO", "INFY24JUNFUT", "NFO-FUT", Some("2024-06-27")),
+            instrument(3, "NFO", "INFY24JULFUT", "NFO-FUT", Some("2024-07-25")),
+            instrument(4, "NFO", "INFY24JUN1500CE", "NFO-OPT", Some("2024-06-27")),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(store.len(), 5);
+        assert_eq!(store.get(InstrumentToken(408065)).unwrap().exchange, "NSE");
+        assert_eq!(store.find("BSE", "INFY").unwrap().instrument_token, InstrumentToken(1));
+        assert!(store.find("NFO", "INFY").is_none());
+        assert!(store.get(InstrumentToken(5)).is_none());
+
+        let tokens = |filter: InstrumentFilter| store.filter(&filter).map(|i| i.instrument_token.0).collect::<Vec<_>>();
+        assert_eq!(tokens(InstrumentFilter::new().segment("NFO-FUT")), [2, 3]);
+        assert_eq!(tokens(InstrumentFilter::new().exchange("NFO").expiry("2024-06-27".parse().unwrap())), [2, 4]);
+        let june = ("2024-06-01".parse().unwrap(), "2024-06-30".parse().unwrap());
+        assert_eq!(tokens(InstrumentFilter::new().instrument_type("FUT").expiring_between(june.0, june.1)), [2]);
+        assert_eq!(tokens(InstrumentFilter::new().expiring_between(june.0, june.1)), [2, 4]);
+        assert_eq!(tokens(InstrumentFilter::new()).len(), 5);
+        assert_eq!(tokens(InstrumentFilter::new().name_contains("fOsY").segment("NSE")), [408065]);
+        assert!(tokens(InstrumentFilter::new().name_contains("TCS")).is_empty());
+    }
+
+    #[test]
+    fn test_derivatives_of() {
+        let contract = |token, tradingsymbol: &str, name: &str, instrument_type: &str, expiry: &str, strike| Instrument {
+            instrument_token: InstrumentToken(token),
+            tradingsymbol: tradingsymbol.to_string(),
+            name: name.to_string(),
+            instrument_type: instrument_type.to_string(),
+            expiry: Some(expiry.parse().unwrap()),
+            strike,
+            exchange: "NFO".to_string(),
+            ..Default::default()
+        };
+        let store = InstrumentStore::new(vec![
+            instrument(738561, "NSE", "RELIANCE", "NSE", None),
+            contract(1, "RELIANCE24JUL3000CE", "RELIANCE", "CE", "2024-07-25", Price::from(3000)),
+            contract(2, "RELIANCE24JUN3000PE", "RELIANCE", "PE", "2024-06-27", Price::from(3000)),
+            contract(3, "RELIANCE24JUNFUT", "RELIANCE", "FUT", "2024-06-27", Price::default()),
+            contract(4, "RELIANCE24JUN2900CE", "RELIANCE", "CE", "2024-06-27", Price::from(2900)),
+            contract(5, "RELIANCE24JUN3000CE", "RELIANCE", "CE", "2024-06-27", Price::from(3000)),
+            contract(6, "NIFTY24JUNFUT", "NIFTY", "FUT", "2024-06-27", Price::default()),
+        ]);
+
+        let tokens = |underlying| {
+            store.derivatives_of(underlying).iter().map(|i| i.instrument_token.0).collect::<Vec<_>>()
+        };
+        assert_eq!(tokens("NSE:RELIANCE"), [3, 4, 5, 2, 1]);
+        assert_eq!(tokens("NSE:NIFTY 50"), [6]);
+        assert!(tokens("BSE:RELIANCE").is_empty());
+        assert!(tokens("RELIANCE").is_empty());
+
+        let june = "2024-06-27".parse().unwrap();
+        let option = store.option("NSE:RELIANCE", june, Price::from(3000), OptionType::Put).unwrap();
+        assert_eq!(option.tradingsymbol, "RELIANCE24JUN3000PE");
+        assert!(store.option("NSE:RELIANCE", june, Price::from(3100), OptionType::Put).is_none());
+        assert!(store.option("NSE:NIFTY 50", june, Price::from(3000), OptionType::Call).is_none());
+    }
+}
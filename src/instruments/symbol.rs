@@ -0,0 +1,227 @@
+//! Decomposition of derivative tradingsymbols such as `NIFTY24MAY22000CE`
+
+use crate::error::{KiteError, Result};
+use crate::models::Price;
+use chrono::{Datelike, NaiveDate};
+use std::fmt;
+use std::str::FromStr;
+
+const MONTHS: [&str; 12] = ["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"];
+
+/// Month codes of weekly expiries, October to December being `O`, `N` and `D`
+const WEEK_MONTHS: [char; 12] = ['1', '2', '3', '4', '5', '6', '7', '8', '9', 'O', 'N', 'D'];
+
+/// Tradingsymbol of a future or option, split into its parts
+///
+/// Futures and monthly options carry the year and month of their expiry, as in
+/// `RELIANCE24JUNFUT` and `NIFTY24MAY22000CE`; weekly options carry the full
+/// date, as in `NIFTY2461322000CE` for the 13th of June 2024. Parsing and
+/// formatting are inverse:
+///
+/// ```rust
+/// use kiteconnect::instruments::{DerivativeSymbol, OptionType, SymbolExpiry};
+///
+/// let symbol: DerivativeSymbol = "NIFTY24MAY22000CE".parse().unwrap();
+/// assert_eq!(symbol.underlying, "NIFTY");
+/// assert_eq!(symbol.expiry, SymbolExpiry::Monthly { year: 2024, month: 5 });
+/// assert_eq!(symbol.option_type, Some(OptionType::Call));
+/// assert_eq!(symbol.to_string(), "NIFTY24MAY22000CE");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DerivativeSymbol {
+    /// Tradingsymbol of the underlying, e.g. `NIFTY`
+    pub underlying: String,
+    /// Expiry encoded in the symbol
+    pub expiry: SymbolExpiry,
+    /// Strike price, for options
+    pub strike: Option<Price>,
+    /// Call or put, `None` for futures
+    pub option_type: Option<OptionType>,
+}
+
+/// Expiry as encoded in a derivative tradingsymbol
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SymbolExpiry {
+    /// Futures and monthly options name the month only, expiring on its last expiry day
+    Monthly { year: i32, month: u32 },
+    /// Weekly options name the date
+    Weekly(NaiveDate),
+}
+
+impl SymbolExpiry {
+    /// Returns `true` if a contract expiring on `date` may carry this expiry
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        match *self {
+            SymbolExpiry::Monthly { year, month } => (date.year(), date.month()) == (year, month),
+            SymbolExpiry::Weekly(expiry) => date == expiry,
+        }
+    }
+}
+
+/// Kind of an option
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OptionType {
+    /// Call option, `CE`
+    Call,
+    /// Put option, `PE`
+    Put,
+}
+
+impl OptionType {
+    /// Returns the instrument type of the option in the dump, `CE` or `PE`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OptionType::Call => "CE",
+            OptionType::Put => "PE",
+        }
+    }
+}
+
+impl DerivativeSymbol {
+    /// Splits `tradingsymbol`, returning `None` if it is not a derivative
+    pub fn parse(tradingsymbol: &str) -> Option<Self> {
+        let symbol = tradingsymbol.trim();
+        if !symbol.is_ascii() {
+            return None;
+        }
+        if let Some(rest) = symbol.strip_suffix("FUT") {
+            let at = rest.len().checked_sub(5).filter(|&at| at > 0)?;
+            return Some(DerivativeSymbol {
+                underlying: rest[..at].to_string(),
+                expiry: monthly(&rest[at..])?,
+                strike: None,
+                option_type: None,
+            });
+        }
+        let (rest, option_type) = match symbol {
+            _ if symbol.ends_with("CE") => (&symbol[..symbol.len() - 2], OptionType::Call),
+            _ if symbol.ends_with("PE") => (&symbol[..symbol.len() - 2], OptionType::Put),
+            _ => return None,
+        };
+        // The underlying may end in digits itself, as NIFTYNXT50 does, so the
+        // expiry is the first place where a date and a strike follow
+        (1..rest.len().saturating_sub(5)).find_map(|at| {
+            let code = &rest[at..at + 5];
+            let expiry = monthly(code).or_else(|| weekly(code))?;
+            let strike = &rest[at + 5..];
+            if strike.starts_with('0') || !strike.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+                return None;
+            }
+            Some(DerivativeSymbol {
+                underlying: rest[..at].to_string(),
+                expiry,
+                strike: Some(strike.parse().ok()?),
+                option_type: Some(option_type),
+            })
+        })
+    }
+
+    /// Returns `true` if this is a future
+    pub fn is_future(&self) -> bool {
+        self.option_type.is_none()
+    }
+}
+
+/// Parses a `24JUN` style year and month
+fn monthly(code: &str) -> Option<SymbolExpiry> {
+    let year = two_digits(&code[..2])?;
+    let month = MONTHS.iter().position(|&month| month == &code[2..])?;
+    Some(SymbolExpiry::Monthly {
+        year: 2000 + year as i32,
+        month: month as u32 + 1,
+    })
+}
+
+/// Parses a `24613` style year, month code and day
+fn weekly(code: &str) -> Option<SymbolExpiry> {
+    let year = two_digits(&code[..2])?;
+    let month_code = code[2..].chars().next()?;
+    let month = WEEK_MONTHS.iter().position(|&m| m == month_code)?;
+    let day = two_digits(&code[3..])?;
+    NaiveDate::from_ymd_opt(2000 + year as i32, month as u32 + 1, day).map(SymbolExpiry::Weekly)
+}
+
+fn two_digits(digits: &str) -> Option<u32> {
+    if digits.len() == 2 && digits.bytes().all(|b| b.is_ascii_digit()) {
+        digits.parse().ok()
+    } else {
+        None
+    }
+}
+
+impl FromStr for DerivativeSymbol {
+    type Err = KiteError;
+
+    fn from_str(tradingsymbol: &str) -> Result<Self> {
+        DerivativeSymbol::parse(tradingsymbol)
+            .ok_or_else(|| KiteError::Other(format!("{:?} is not a derivative tradingsymbol", tradingsymbol)))
+    }
+}
+
+impl fmt::Display for DerivativeSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.underlying)?;
+        match self.expiry {
+            SymbolExpiry::Monthly { year, month } => {
+                write!(f, "{:02}{}", year % 100, MONTHS[(month as usize + 11) % 12])?
+            }
+            SymbolExpiry::Weekly(date) => write!(
+                f,
+                "{:02}{}{:02}",
+                date.year() % 100,
+                WEEK_MONTHS[date.month0() as usize],
+                date.day()
+            )?,
+        }
+        match (self.option_type, &self.strike) {
+            (Some(option_type), Some(strike)) => write!(f, "{}{}", strike, option_type.as_str()),
+            (Some(option_type), None) => f.write_str(option_type.as_str()),
+            (None, _) => f.write_str("FUT"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derivative_symbol() {
+        let date = |value: &str| value.parse::<NaiveDate>().unwrap();
+        let cases = [
+            ("RELIANCE24JUNFUT", "RELIANCE", SymbolExpiry::Monthly { year: 2024, month: 6 }, None, None),
+            ("NIFTY24MAY22000CE", "NIFTY", SymbolExpiry::Monthly { year: 2024, month: 5 }, Some("22000"), Some(OptionType::Call)),
+            ("NIFTY2461322000PE", "NIFTY", SymbolExpiry::Weekly(date("2024-06-13")), Some("22000"), Some(OptionType::Put)),
+            ("NIFTY24O0324500CE", "NIFTY", SymbolExpiry::Weekly(date("2024-10-03")), Some("24500"), Some(OptionType::Call)),
+            ("NIFTYNXT5024JUN70000CE", "NIFTYNXT50", SymbolExpiry::Monthly { year: 2024, month: 6 }, Some("70000"), Some(OptionType::Call)),
+            ("USDINR24JUN83.25PE", "USDINR", SymbolExpiry::Monthly { year: 2024, month: 6 }, Some("83.25"), Some(OptionType::Put)),
+            ("M&M24DEC3000CE", "M&M", SymbolExpiry::Monthly { year: 2024, month: 12 }, Some("3000"), Some(OptionType::Call)),
+        ];
+        for (tradingsymbol, underlying, expiry, strike, option_type) in cases {
+            let symbol: DerivativeSymbol = tradingsymbol.parse().unwrap();
+            assert_eq!(symbol.underlying, underlying, "{}", tradingsymbol);
+            assert_eq!(symbol.expiry, expiry, "{}", tradingsymbol);
+            assert_eq!(symbol.strike.map(|strike| strike.to_string()).as_deref(), strike, "{}", tradingsymbol);
+            assert_eq!(symbol.option_type, option_type, "{}", tradingsymbol);
+            assert_eq!(symbol.to_string(), tradingsymbol);
+        }
+        for tradingsymbol in ["INFY", "NIFTY 50", "FUT", "24JUNFUT", "NIFTY24XYZ22000CE", "NIFTY2461322000", "TATAPOWER-BE"] {
+            assert!(DerivativeSymbol::parse(tradingsymbol).is_none(), "{}", tradingsymbol);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_dump_symbols() {
+        // Derivatives of the dump agree with their own tradingsymbols
+        let dump = std::fs::read("mocks/instruments.csv").unwrap();
+        let chunks = futures::stream::iter([Ok::<_, KiteError>(bytes::Bytes::from(dump))]);
+        let instruments: Vec<crate::instruments::Instrument> =
+            futures::TryStreamExt::try_collect(crate::instruments::parse_stream(chunks)).await.unwrap();
+        let future = instruments.iter().find(|i| i.instrument_type == "FUT").unwrap();
+        let symbol = DerivativeSymbol::parse(&future.tradingsymbol).unwrap();
+        assert!(symbol.is_future());
+        assert!(symbol.expiry.contains(future.expiry.unwrap()));
+        assert!(DerivativeSymbol::parse(&instruments[0].tradingsymbol).is_none());
+    }
+}
@@ -0,0 +1,124 @@
+//! Checks of order prices and quantities against the tick and lot size of an instrument
+
+use super::Instrument;
+use crate::error::{KiteError, Result};
+use crate::models::Price;
+
+/// Rounds `price` to the nearest multiple of the tick size of `instrument`
+///
+/// Prices that are not a multiple of the tick size are rejected by the
+/// exchange. Instruments without a tick size leave `price` as it is.
+///
+/// ```rust
+/// use kiteconnect::instruments::{round_to_tick, Instrument};
+///
+/// let instrument = Instrument {
+///     tick_size: "0.05".parse().unwrap(),
+///     ..Default::default()
+/// };
+/// assert_eq!(round_to_tick("1524.13".parse().unwrap(), &instrument).to_string(), "1524.15");
+/// ```
+pub fn round_to_tick(price: Price, instrument: &Instrument) -> Price {
+    let tick = instrument.tick_size;
+    if tick <= Price::default() {
+        return price;
+    }
+    snap(price, tick)
+}
+
+#[cfg(not(feature = "rust_decimal"))]
+fn snap(price: Price, tick: Price) -> Price {
+    // Multiplying by a binary tick such as 0.05 leaves noise in the last
+    // digits, so the result is rounded to the decimals of the tick
+    let decimals = tick.to_string().split('.').nth(1).map_or(0, str::len) as i32;
+    let scale = 10f64.powi(decimals);
+    ((price / tick).round() * tick * scale).round() / scale
+}
+
+#[cfg(feature = "rust_decimal")]
+fn snap(price: Price, tick: Price) -> Price {
+    ((price / tick).round() * tick).normalize()
+}
+
+/// Checks that `price` is a multiple of the tick size of `instrument`
+pub fn validate_order_price(price: Price, instrument: &Instrument) -> Result<()> {
+    let rounded = round_to_tick(price, instrument);
+    if rounded == price {
+        return Ok(());
+    }
+    Err(KiteError::Other(format!(
+        "price {} of {}:{} is not a multiple of its tick size {}, the nearest valid price is {}",
+        price, instrument.exchange, instrument.tradingsymbol, instrument.tick_size, rounded
+    )))
+}
+
+/// Checks that `quantity` is a positive multiple of the lot size of `instrument`
+///
+/// Quantities of derivatives are given in units, not lots, so a NIFTY future
+/// with a lot size of 25 takes 25, 50 or 75, but not 30.
+pub fn validate_order_quantity(quantity: u32, instrument: &Instrument) -> Result<()> {
+    if quantity == 0 {
+        return Err(KiteError::Other(format!(
+            "quantity of {}:{} must be positive",
+            instrument.exchange, instrument.tradingsymbol
+        )));
+    }
+    if instrument.lot_size > 1 && !quantity.is_multiple_of(instrument.lot_size) {
+        return Err(KiteError::Other(format!(
+            "quantity {} of {}:{} is not a multiple of its lot size {}",
+            quantity, instrument.exchange, instrument.tradingsymbol, instrument.lot_size
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instrument(tick_size: &str, lot_size: u32) -> Instrument {
+        Instrument {
+            tradingsymbol: "NIFTY24JUNFUT".to_string(),
+            exchange: "NFO".to_string(),
+            tick_size: tick_size.parse().unwrap(),
+            lot_size,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_round_to_tick() {
+        let price = |value: &str| value.parse::<Price>().unwrap();
+        let cases = [
+            ("0.05", "101.03", "101.05"),
+            ("0.05", "101.02", "101"),
+            ("0.05", "22000.1", "22000.1"),
+            ("0.1", "0.26", "0.3"),
+            ("0.0025", "83.2513", "83.2525"),
+            ("1", "54321.5", "54322"),
+            ("0", "101.03", "101.03"),
+        ];
+        for (tick_size, input, rounded) in cases {
+            let instrument = instrument(tick_size, 1);
+            assert_eq!(round_to_tick(price(input), &instrument).to_string(), rounded, "{} at {}", input, tick_size);
+        }
+
+        let nifty = instrument("0.05", 25);
+        assert!(validate_order_price(price("22000.05"), &nifty).is_ok());
+        let err = validate_order_price(price("22000.03"), &nifty).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "price 22000.03 of NFO:NIFTY24JUNFUT is not a multiple of its tick size 0.05, the nearest valid price is 22000.05"
+        );
+    }
+
+    #[test]
+    fn test_validate_order_quantity() {
+        let nifty = instrument("0.05", 25);
+        assert!(validate_order_quantity(75, &nifty).is_ok());
+        assert!(validate_order_quantity(30, &nifty).is_err());
+        assert!(validate_order_quantity(0, &nifty).is_err());
+        assert!(validate_order_quantity(7, &instrument("0.05", 1)).is_ok());
+        assert!(validate_order_quantity(7, &instrument("0.05", 0)).is_ok());
+    }
+}
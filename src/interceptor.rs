@@ -0,0 +1,58 @@
+//! # Interceptors
+//!
+//! An [`Interceptor`] registered on the client sees every HTTP request right
+//! before it is sent and every response right after it arrives. Interceptors can
+//! add or rewrite headers, change the URL, veto requests and audit responses
+//! without modifying the crate.
+//!
+//! Interceptors run in registration order for every attempt, so a request that
+//! is retried passes through them again.
+//!
+//! ```rust
+//! use kiteconnect::connect::KiteConnect;
+//! use kiteconnect::error::Result;
+//! use kiteconnect::interceptor::Interceptor;
+//! use std::sync::Arc;
+//!
+//! struct AuditLog;
+//!
+//! impl Interceptor for AuditLog {
+//!     fn before_request(&self, request: &mut reqwest::Request) -> Result<()> {
+//!         request.headers_mut().insert("X-Strategy", "momentum".parse().unwrap());
+//!         Ok(())
+//!     }
+//!
+//!     fn after_response(&self, response: &reqwest::Response) {
+//!         println!("{} -> {}", response.url().path(), response.status());
+//!     }
+//! }
+//!
+//! # fn main() -> kiteconnect::error::Result<()> {
+//! let client = KiteConnect::builder("api_key")
+//!     .access_token("access_token")
+//!     .interceptor(Arc::new(AuditLog))
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{KiteError, Result};
+
+/// Hooks invoked around every HTTP request made by the client
+pub trait Interceptor: Send + Sync {
+    /// Called before a request is sent; may modify it or abort it with an error
+    fn before_request(&self, request: &mut reqwest::Request) -> Result<()> {
+        let _ = request;
+        Ok(())
+    }
+
+    /// Called for every response received, before its body is read
+    fn after_response(&self, response: &reqwest::Response) {
+        let _ = response;
+    }
+
+    /// Called when a request fails without a response, or is aborted by an interceptor
+    fn on_error(&self, error: &KiteError) {
+        let _ = error;
+    }
+}
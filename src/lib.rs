@@ -61,6 +61,7 @@
 //! 
 //! ### Authentication
 //! - `login_url()` - Generate login URL
+//! - `login_url_with_state()` - Generate login URL carrying state back to the redirect
 //! - `generate_session()` - Create session with request token
 //! - `invalidate_session()` - Logout user
 //! 
@@ -68,23 +69,58 @@
 //! - `holdings()` - Get user holdings
 //! - `positions()` - Get user positions
 //! - `margins()` - Get account margins
+//! - `order_margins()` - Get the margins orders would block
 //! 
 //! ### Orders
 //! - `orders()` - Get all orders
 //! - `order_trades()` - Get trades for specific order
 //! - `trades()` - Get all trades
+//! - `orders_by_tag()`, `cancel_orders_by_tag()` - Find or cancel the orders of a strategy by tag
+//! - `orders::Tag` - Encode a strategy ID and client order ID into an order tag
+//! - `square_off_positions()` - Flatten open positions with offsetting orders
+//! - `await_order_completion()` - Poll an order until it is complete, rejected or cancelled
+//! - `place_order_and_wait()` - Place an order and wait for its fills
+//! - `FillTracker`, `poll_fills()` - Follow the filled quantity and average price of an order
+//! - `place_basket()` - Place several orders with a policy for failures
+//! - `place_spread()` - Place vertical spreads, straddles and iron condors
+//! - `place_gtt()`, `gtts()`, `gtt()`, `delete_gtt()` - Manage GTT triggers
+//! - `place_gtt_oco()` - Exit a position at a target or stoploss with a two-leg GTT
+//! - `place_bracket()` - Emulate a bracket order with a target and stoploss on fill
+//! - `can_afford()` - Check the margin of an order against the available funds
+//! - `OrderGateway` - Queue order mutations within the per second, minute and day order limits
+//! - `set_dedupe_window()` - Ignore repeats of orders placed within a time window
+//! - `set_read_only()` - Reject or simulate orders instead of sending them, for dry runs
+//! - `set_after_hours()` - Place orders as AMO, or reject them, while the exchange is closed
+//! - `kill_switch()` - Block every order mutation of a client and its clones until turned off
+//! 
+//! ### Charges
+//! - `charges::RateTable` - Brokerage, STT, GST and other charges of orders, computed offline
 //! 
 //! ### Market Data
 //! - `instruments()` - Get instrument list
 //! - `trigger_range()` - Get trigger range for instruments
+//! - `trigger_range_typed()` - Get trigger ranges as `TriggerRange`s by instrument
 //! 
 //! ### Mutual Funds
 //! - `mf_orders()` - Get mutual fund orders
 //! - `mf_instruments()` - Get mutual fund instruments
+//! - `mf_instruments_typed()` - Get mutual fund instruments as `MfInstrument`s
+//! 
+//! ### Market Data Streaming
+//! - `ticker::KiteTicker` - Live ticks over the websocket API (`ticker` feature)
+//! 
+//! ## Typed Models
+//! 
+//! Most endpoints also have a `*_typed` variant (e.g. `holdings_typed()`) that unwraps
+//! the response envelope and deserializes the payload into the structs found in
+//! [`models`]. Unknown fields are captured in each model's `extra` map; enable
+//! strict mode with `set_strict_mode(true)` to reject them instead.
 //! 
 //! ## Error Handling
 //! 
-//! The library uses `anyhow::Result` for comprehensive error handling:
+//! All methods return [`error::Result`], whose error type [`error::KiteError`]
+//! separates network failures, deserialization failures and API errors carrying
+//! the HTTP status, Kite `error_type` and `message`:
 //! 
 //! ```rust,no_run
 //! # use kiteconnect::connect::KiteConnect;
@@ -99,16 +135,39 @@
 //! # }
 //! ```
 //! 
+//! Transient failures (`429`, `5xx`, timeouts) can be retried automatically by
+//! setting a [`retry::RetryPolicy`] with `KiteConnect::set_retry_policy`, and
+//! [`ratelimit::RateLimits`] keeps requests within Kite's documented limits:
+//! 
+//! ```rust
+//! use kiteconnect::connect::KiteConnect;
+//! use kiteconnect::ratelimit::RateLimits;
+//! use kiteconnect::retry::RetryPolicy;
+//! 
+//! # fn main() -> kiteconnect::error::Result<()> {
+//! let kiteconnect = KiteConnect::builder("api_key")
+//!     .access_token("access_token")
+//!     .retry_policy(RetryPolicy::default())
+//!     .rate_limits(RateLimits::default())
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+//! 
 //! ## Platform-Specific Features
 //! 
 //! ### Native (Tokio)
 //! - Full CSV parsing for instruments
 //! - Complete async/await support
 //! - High-performance HTTP client
+//! - TLS backend selected with the `native-tls` (default), `native-tls-vendored`,
+//!   `rustls` or `rustls-native-roots` features; use
+//!   `default-features = false, features = ["native", "rustls"]` for builds
+//!   without OpenSSL
 //! 
 //! ### WASM (Browser)
 //! - All APIs supported
-//! - Raw CSV returned for client-side parsing
+//! - CSV instruments parsed as natively, into JSON rows or typed instruments
 //! - Compatible with web frameworks
 //! 
 //! ## Examples
@@ -145,4 +204,30 @@
 #[cfg(test)]
 extern crate mockito;
 
+pub mod accounts;
+pub mod auth;
+pub mod calendar;
+pub mod charges;
+pub mod config;
 pub mod connect;
+pub mod error;
+#[cfg(all(feature = "polars", not(target_arch = "wasm32")))]
+pub mod frames;
+pub mod instruments;
+pub mod interceptor;
+pub mod metrics;
+pub mod models;
+pub mod options;
+pub mod orders;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod postback;
+pub mod proxy;
+pub mod ratelimit;
+pub mod readonly;
+mod redact;
+pub mod retry;
+mod rt;
+#[cfg(test)]
+mod testing;
+#[cfg(feature = "ticker")]
+pub mod ticker;
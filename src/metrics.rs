@@ -0,0 +1,96 @@
+//! # Metrics
+//!
+//! Implement [`MetricsSink`] to export request timings and error counts to
+//! Prometheus, StatsD or any other backend. The sink is called once per API
+//! call, after retries, with a [`RequestMetrics`] record.
+//!
+//! ```rust
+//! use kiteconnect::connect::KiteConnect;
+//! use kiteconnect::metrics::{MetricsSink, RequestMetrics};
+//! use std::sync::Arc;
+//!
+//! struct LogSink;
+//!
+//! impl MetricsSink for LogSink {
+//!     fn record(&self, metrics: &RequestMetrics<'_>) {
+//!         println!(
+//!             "{} {} -> {:?} in {:?} ({} retries)",
+//!             metrics.method, metrics.endpoint, metrics.status, metrics.duration, metrics.retries
+//!         );
+//!     }
+//! }
+//!
+//! # fn main() -> kiteconnect::error::Result<()> {
+//! let client = KiteConnect::builder("api_key")
+//!     .access_token("access_token")
+//!     .metrics_sink(Arc::new(LogSink))
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::KiteError;
+use std::time::Duration;
+
+/// Timing and outcome of a single API call
+#[derive(Debug)]
+pub struct RequestMetrics<'a> {
+    /// HTTP method
+    pub method: &'a str,
+    /// URL path with identifiers replaced by `:id`, e.g. `/orders/:id/trades`,
+    /// suitable as a low-cardinality metric label
+    pub endpoint: &'a str,
+    /// Actual URL path of the request
+    pub path: &'a str,
+    /// Total time spent, including rate limiting and retries
+    pub duration: Duration,
+    /// HTTP status of the final response, if one was received
+    pub status: Option<u16>,
+    /// Number of retries made after the first attempt
+    pub retries: u32,
+    /// Transport error of the final attempt, if it failed without a response
+    pub error: Option<&'a KiteError>,
+}
+
+impl RequestMetrics<'_> {
+    /// Returns `true` if a response with a `2xx` status was received
+    pub fn is_success(&self) -> bool {
+        matches!(self.status, Some(200..=299))
+    }
+}
+
+/// Receiver of per-request metrics
+pub trait MetricsSink: Send + Sync {
+    /// Called once for every API call after it completed or failed
+    fn record(&self, metrics: &RequestMetrics<'_>);
+}
+
+/// Replaces path segments containing digits with `:id`
+pub(crate) fn endpoint_template(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            if segment.chars().any(|c| c.is_ascii_digit()) {
+                ":id"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_template() {
+        assert_eq!(endpoint_template("/portfolio/holdings"), "/portfolio/holdings");
+        assert_eq!(endpoint_template("/orders/171229000724687/trades"), "/orders/:id/trades");
+        assert_eq!(
+            endpoint_template("/instruments/historical/5633/minute"),
+            "/instruments/historical/:id/minute"
+        );
+        assert_eq!(endpoint_template("/user/margins/equity"), "/user/margins/equity");
+    }
+}
@@ -0,0 +1,225 @@
+//! Instruments dump models
+
+use super::Price;
+use crate::error::{KiteError, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Numerical identifier of an instrument, as used for market data
+///
+/// Instrument tokens are not the exchange tokens of the instruments dump, and
+/// the two are easily mixed up as plain integers. Models, ticks and the market
+/// data methods use this type instead, so an integer has to be wrapped
+/// explicitly to be taken as an instrument token:
+///
+/// ```rust
+/// use kiteconnect::models::InstrumentToken;
+///
+/// let infy = InstrumentToken(408065);
+/// assert_eq!(infy.to_string(), "408065");
+/// assert_eq!("408065".parse::<InstrumentToken>().unwrap(), infy);
+/// assert_eq!(u32::from(infy), 408065);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct InstrumentToken(pub u32);
+
+impl From<&InstrumentToken> for InstrumentToken {
+    fn from(token: &InstrumentToken) -> Self {
+        *token
+    }
+}
+
+impl From<InstrumentToken> for u32 {
+    fn from(token: InstrumentToken) -> Self {
+        token.0
+    }
+}
+
+impl fmt::Display for InstrumentToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for InstrumentToken {
+    type Err = KiteError;
+
+    fn from_str(token: &str) -> Result<Self> {
+        token
+            .trim()
+            .parse()
+            .map(InstrumentToken)
+            .map_err(|_| KiteError::Other(format!("invalid instrument token {:?}", token)))
+    }
+}
+
+/// A tradable instrument or index, one row of the instruments dump
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Instrument {
+    /// Numerical identifier used for subscribing to live market data
+    pub instrument_token: InstrumentToken,
+    /// Numerical identifier issued by the exchange
+    pub exchange_token: u32,
+    /// Exchange tradingsymbol of the instrument
+    pub tradingsymbol: String,
+    /// Name of the company, for equity instruments
+    pub name: String,
+    /// Last traded market price, as of the dump
+    pub last_price: Price,
+    /// Expiry date, for derivatives
+    pub expiry: Option<NaiveDate>,
+    /// Strike price, for options
+    pub strike: Price,
+    /// Value of a single price tick
+    pub tick_size: Price,
+    /// Quantity of a single lot
+    pub lot_size: u32,
+    /// EQ, FUT, CE or PE
+    pub instrument_type: String,
+    /// Segment the instrument belongs to, e.g. `NFO-OPT`
+    pub segment: String,
+    /// Exchange
+    pub exchange: String,
+}
+
+impl Instrument {
+    /// Returns the instrument token as an [`InstrumentToken`]
+    pub fn token(&self) -> InstrumentToken {
+        self.instrument_token
+    }
+}
+
+/// Positions of the instrument fields in the columns of an instruments dump
+///
+/// Columns are matched by name, so reordered, padded or additional columns are
+/// tolerated.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct InstrumentColumns {
+    instrument_token: Option<usize>,
+    exchange_token: Option<usize>,
+    tradingsymbol: Option<usize>,
+    name: Option<usize>,
+    last_price: Option<usize>,
+    expiry: Option<usize>,
+    strike: Option<usize>,
+    tick_size: Option<usize>,
+    lot_size: Option<usize>,
+    instrument_type: Option<usize>,
+    segment: Option<usize>,
+    exchange: Option<usize>,
+}
+
+impl InstrumentColumns {
+    /// Locates the fields in the header row of a dump
+    pub(crate) fn new<'a>(headers: impl IntoIterator<Item = &'a str>) -> Result<Self> {
+        let mut columns = InstrumentColumns::default();
+        for (index, header) in headers.into_iter().enumerate() {
+            let column = match header.trim() {
+                "instrument_token" => &mut columns.instrument_token,
+                "exchange_token" => &mut columns.exchange_token,
+                "tradingsymbol" => &mut columns.tradingsymbol,
+                "name" => &mut columns.name,
+                "last_price" => &mut columns.last_price,
+                "expiry" => &mut columns.expiry,
+                "strike" => &mut columns.strike,
+                "tick_size" => &mut columns.tick_size,
+                "lot_size" => &mut columns.lot_size,
+                "instrument_type" => &mut columns.instrument_type,
+                "segment" => &mut columns.segment,
+                "exchange" => &mut columns.exchange,
+                _ => continue,
+            };
+            *column = Some(index);
+        }
+        if columns.instrument_token.is_none() || columns.tradingsymbol.is_none() {
+            return Err(KiteError::Other(
+                "instruments dump lacks the instrument_token or tradingsymbol column".to_string(),
+            ));
+        }
+        Ok(columns)
+    }
+
+    /// Builds an instrument from the fields of a row, given by their position
+    ///
+    /// Empty fields, such as the expiry of equities, take their default.
+    pub(crate) fn parse<'a>(&self, field: impl Fn(usize) -> Option<&'a str>) -> Result<Instrument> {
+        let text = |column: Option<usize>| column.and_then(&field).map(str::trim).unwrap_or_default();
+        let number = |name: &str, column: Option<usize>| -> Result<u32> {
+            match text(column) {
+                "" => Ok(0),
+                value => value.parse().map_err(|_| invalid(name, value)),
+            }
+        };
+        let price = |name: &str, column: Option<usize>| -> Result<Price> {
+            match text(column) {
+                "" => Ok(Price::default()),
+                value => value.parse().map_err(|_| invalid(name, value)),
+            }
+        };
+        let expiry = match text(self.expiry) {
+            "" => None,
+            value => Some(NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| invalid("expiry", value))?),
+        };
+        Ok(Instrument {
+            instrument_token: InstrumentToken(number("instrument_token", self.instrument_token)?),
+            exchange_token: number("exchange_token", self.exchange_token)?,
+            tradingsymbol: text(self.tradingsymbol).to_string(),
+            name: text(self.name).to_string(),
+            last_price: price("last_price", self.last_price)?,
+            expiry,
+            strike: price("strike", self.strike)?,
+            tick_size: price("tick_size", self.tick_size)?,
+            lot_size: number("lot_size", self.lot_size)?,
+            instrument_type: text(self.instrument_type).to_string(),
+            segment: text(self.segment).to_string(),
+            exchange: text(self.exchange).to_string(),
+        })
+    }
+}
+
+fn invalid(name: &str, value: &str) -> KiteError {
+    KiteError::Other(format!("invalid {} {:?} in instruments dump", name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_instrument() {
+        let columns = InstrumentColumns::new(
+            "instrument_token, exchange_token, tradingsymbol, name, last_price, expiry, strike, tick_size, lot_size, instrument_type, segment, exchange"
+                .split(','),
+        )
+        .unwrap();
+        let row: Vec<&str> = "5720578,22346,NIFTY159500CE,,23.0,2015-12-31,9500,0.05,75,CE,NFO-OPT,NFO".split(',').collect();
+        let option = columns.parse(|index| row.get(index).copied()).unwrap();
+        assert_eq!(option.instrument_token, InstrumentToken(5720578));
+        assert_eq!(option.tradingsymbol, "NIFTY159500CE");
+        assert_eq!(option.expiry, NaiveDate::from_ymd_opt(2015, 12, 31));
+        assert_eq!((option.strike.to_string(), option.lot_size), ("9500".to_string(), 75));
+        assert_eq!((option.segment.as_str(), option.exchange.as_str()), ("NFO-OPT", "NFO"));
+
+        let row = ["408065", "", "INFY", "INFOSYS", "0", "", "", "0.05", "1", "EQ", "NSE", "NSE"];
+        let equity = columns.parse(|index| row.get(index).copied()).unwrap();
+        assert_eq!((equity.expiry, equity.exchange_token), (None, 0));
+
+        let row = ["40806x", "", "INFY"];
+        assert!(columns.parse(|index| row.get(index).copied()).is_err());
+        assert!(InstrumentColumns::new(["exchange", "name"]).is_err());
+    }
+
+    #[test]
+    fn test_instrument_token() {
+        let token = InstrumentToken(408065);
+        assert_eq!(u32::from(token), 408065);
+        assert_eq!(token.to_string(), "408065");
+        assert_eq!(" 408065".parse::<InstrumentToken>().unwrap(), token);
+        assert!("NSE:INFY".parse::<InstrumentToken>().is_err());
+        assert_eq!(serde_json::to_value(token).unwrap(), 408065);
+        assert_eq!(serde_json::from_value::<InstrumentToken>(408065.into()).unwrap(), token);
+    }
+}
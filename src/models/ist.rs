@@ -0,0 +1,184 @@
+//! Indian Standard Time helpers and serde adapters
+//!
+//! Kite returns naive timestamps in IST using a handful of formats
+//! (`2017-12-29 11:06:52`, `2017-12-28 11:44`, `2017-12-15T09:15:00+0530`, ...).
+//! The functions and `serde(with = ...)` modules here parse all of them into
+//! [`Timestamp`] values carrying the `+05:30` offset.
+//!
+//! ```rust
+//! use kiteconnect::models::ist;
+//!
+//! let ts = ist::parse_timestamp("2017-12-29 11:06:52").unwrap();
+//! assert_eq!(ts.to_rfc3339(), "2017-12-29T11:06:52+05:30");
+//! ```
+
+use super::Timestamp;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+/// Offset of Indian Standard Time from UTC in seconds
+pub const OFFSET_SECS: i32 = 5 * 3600 + 30 * 60;
+
+/// Format used by Kite for naive timestamps, also used when serializing
+pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+const NAIVE_FORMATS: [&str; 3] = [TIMESTAMP_FORMAT, "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"];
+const OFFSET_FORMATS: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%z", "%Y-%m-%d %H:%M:%S%z"];
+
+/// Returns the `+05:30` IST offset
+pub fn offset() -> FixedOffset {
+    FixedOffset::east_opt(OFFSET_SECS).expect("IST offset is in range")
+}
+
+/// Returns the current time in IST
+pub fn now() -> Timestamp {
+    Utc::now().with_timezone(&offset())
+}
+
+/// Interprets a naive date-time as IST
+pub fn from_naive(naive: NaiveDateTime) -> Timestamp {
+    offset()
+        .from_local_datetime(&naive)
+        .single()
+        .expect("fixed offsets have no ambiguous local times")
+}
+
+/// Parses any of the timestamp formats used by Kite into an IST timestamp
+///
+/// Timestamps carrying an explicit offset are converted to IST, naive timestamps
+/// are interpreted as IST and bare dates resolve to midnight. Returns `None` for
+/// empty or unrecognised input.
+pub fn parse_timestamp(value: &str) -> Option<Timestamp> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    for format in OFFSET_FORMATS {
+        if let Ok(ts) = DateTime::parse_from_str(value, format) {
+            return Some(ts.with_timezone(&offset()));
+        }
+    }
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Some(ts.with_timezone(&offset()));
+    }
+    for format in NAIVE_FORMATS {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(from_naive(naive));
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(from_naive)
+}
+
+/// Parses a time of day, accepting either a bare time or a full timestamp
+pub fn parse_time(value: &str) -> Option<NaiveTime> {
+    let value = value.trim();
+    NaiveTime::parse_from_str(value, "%H:%M:%S")
+        .ok()
+        .or_else(|| parse_timestamp(value).map(|ts| ts.time()))
+}
+
+/// Formats a timestamp in IST using Kite's naive timestamp format
+pub fn format_timestamp(ts: &Timestamp) -> String {
+    ts.with_timezone(&offset()).format(TIMESTAMP_FORMAT).to_string()
+}
+
+/// Serde adapter for required [`Timestamp`] fields
+pub mod timestamp {
+    use super::*;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(ts: &Timestamp, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_timestamp(ts))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Timestamp, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        parse_timestamp(&value)
+            .ok_or_else(|| D::Error::custom(format!("invalid timestamp: {:?}", value)))
+    }
+}
+
+/// Serde adapter for optional [`Timestamp`] fields, treating `null` and `""` as `None`
+pub mod option_timestamp {
+    use super::*;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(ts: &Option<Timestamp>, serializer: S) -> Result<S::Ok, S::Error> {
+        match ts {
+            Some(ts) => serializer.serialize_str(&format_timestamp(ts)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Timestamp>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(value) if !value.trim().is_empty() => parse_timestamp(&value)
+                .map(Some)
+                .ok_or_else(|| D::Error::custom(format!("invalid timestamp: {:?}", value))),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Serde adapter for optional time-of-day fields, treating `null` and `""` as `None`
+pub mod option_time {
+    use super::*;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(time: &Option<NaiveTime>, serializer: S) -> Result<S::Ok, S::Error> {
+        match time {
+            Some(time) => serializer.serialize_str(&time.format("%H:%M:%S").to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NaiveTime>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(value) if !value.trim().is_empty() => parse_time(&value)
+                .map(Some)
+                .ok_or_else(|| D::Error::custom(format!("invalid time: {:?}", value))),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp_formats() {
+        let expected = "2017-12-29T11:06:52+05:30";
+        assert_eq!(parse_timestamp("2017-12-29 11:06:52").unwrap().to_rfc3339(), expected);
+        assert_eq!(parse_timestamp("2017-12-29T11:06:52").unwrap().to_rfc3339(), expected);
+        assert_eq!(parse_timestamp("2017-12-29T11:06:52+0530").unwrap().to_rfc3339(), expected);
+        assert_eq!(parse_timestamp("2017-12-29T05:36:52Z").unwrap().to_rfc3339(), expected);
+        assert_eq!(
+            parse_timestamp("2017-12-28 11:44").unwrap().to_rfc3339(),
+            "2017-12-28T11:44:00+05:30"
+        );
+        assert_eq!(
+            parse_timestamp("2017-12-28").unwrap().to_rfc3339(),
+            "2017-12-28T00:00:00+05:30"
+        );
+        assert_eq!(parse_timestamp(""), None);
+        assert_eq!(parse_timestamp("not a date"), None);
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let expected = NaiveTime::from_hms_opt(12, 2, 5);
+        assert_eq!(parse_time("12:02:05"), expected);
+        assert_eq!(parse_time("2017-12-29 12:02:05"), expected);
+    }
+
+    #[test]
+    fn test_format_round_trip() {
+        let ts = parse_timestamp("2017-12-29T05:36:52Z").unwrap();
+        assert_eq!(format_timestamp(&ts), "2017-12-29 11:06:52");
+        assert_eq!(parse_timestamp(&format_timestamp(&ts)), Some(ts));
+    }
+}
@@ -0,0 +1,127 @@
+//! Exchange and market data models
+
+use super::{kite_model, ExtraFields, InstrumentToken, Price};
+use crate::error::{KiteError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// An exchange or segment instruments are listed on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Exchange {
+    /// National Stock Exchange, equities
+    #[serde(rename = "NSE")]
+    Nse,
+    /// Bombay Stock Exchange, equities
+    #[serde(rename = "BSE")]
+    Bse,
+    /// NSE futures and options
+    #[serde(rename = "NFO")]
+    Nfo,
+    /// BSE futures and options
+    #[serde(rename = "BFO")]
+    Bfo,
+    /// NSE currency derivatives
+    #[serde(rename = "CDS")]
+    Cds,
+    /// BSE currency derivatives
+    #[serde(rename = "BCD")]
+    Bcd,
+    /// Multi Commodity Exchange
+    #[serde(rename = "MCX")]
+    Mcx,
+}
+
+impl Exchange {
+    /// Every exchange
+    pub const ALL: [Exchange; 7] = [
+        Exchange::Nse,
+        Exchange::Bse,
+        Exchange::Nfo,
+        Exchange::Bfo,
+        Exchange::Cds,
+        Exchange::Bcd,
+        Exchange::Mcx,
+    ];
+
+    /// Returns the code of the exchange used by the API, e.g. `NSE`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Exchange::Nse => "NSE",
+            Exchange::Bse => "BSE",
+            Exchange::Nfo => "NFO",
+            Exchange::Bfo => "BFO",
+            Exchange::Cds => "CDS",
+            Exchange::Bcd => "BCD",
+            Exchange::Mcx => "MCX",
+        }
+    }
+
+    /// Returns the `EXCHANGE:TRADINGSYMBOL` name of an instrument of this
+    /// exchange, as taken by the quote and trigger range endpoints
+    pub fn instrument(&self, tradingsymbol: &str) -> String {
+        format!("{}:{}", self.as_str(), tradingsymbol)
+    }
+}
+
+impl fmt::Display for Exchange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Exchange {
+    type Err = KiteError;
+
+    fn from_str(code: &str) -> Result<Self> {
+        Exchange::ALL
+            .into_iter()
+            .find(|exchange| exchange.as_str().eq_ignore_ascii_case(code.trim()))
+            .ok_or_else(|| KiteError::Other(format!("unknown exchange {:?}", code)))
+    }
+}
+
+/// Range the trigger price of a cover order has to lie in
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TriggerRange {
+    /// Numerical identifier of the instrument
+    #[serde(default)]
+    pub instrument_token: InstrumentToken,
+    /// Lowest allowed trigger price
+    pub lower: Price,
+    /// Highest allowed trigger price
+    pub upper: Price,
+    /// Allowed distance of the trigger price from the last price, in percent
+    #[serde(default)]
+    pub percentage: Option<f64>,
+    /// Fields not known to this model
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+kite_model!(TriggerRange);
+
+impl TriggerRange {
+    /// Returns `true` if `trigger_price` lies within the range, bounds included
+    pub fn contains(&self, trigger_price: Price) -> bool {
+        self.lower <= trigger_price && trigger_price <= self.upper
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exchange() {
+        for exchange in Exchange::ALL {
+            assert_eq!(exchange.to_string().parse::<Exchange>().unwrap(), exchange);
+            let json = serde_json::to_value(exchange).unwrap();
+            assert_eq!(json, exchange.as_str());
+            assert_eq!(serde_json::from_value::<Exchange>(json).unwrap(), exchange);
+        }
+        assert_eq!(" nfo".parse::<Exchange>().unwrap(), Exchange::Nfo);
+        assert!("NSEX".parse::<Exchange>().is_err());
+        assert_eq!(Exchange::Nse.instrument("INFY"), "NSE:INFY");
+    }
+}
@@ -0,0 +1,229 @@
+//! # Typed Models
+//!
+//! Strongly typed representations of the payloads returned by the KiteConnect API.
+//!
+//! ## Forward Compatibility
+//!
+//! Kite regularly adds fields to its responses. Every model captures fields it does
+//! not know about in an `extra` map instead of failing, so a new field on the API
+//! side never breaks deserialization:
+//!
+//! ```rust
+//! use kiteconnect::models::Holding;
+//!
+//! let holding: Holding = serde_json::from_str(r#"{
+//!     "tradingsymbol": "INFY",
+//!     "exchange": "NSE",
+//!     "instrument_token": 408065,
+//!     "quantity": 10,
+//!     "average_price": 1500.0,
+//!     "last_price": 1550.0,
+//!     "brand_new_field": true
+//! }"#).unwrap();
+//!
+//! assert_eq!(holding.extra["brand_new_field"], true);
+//! ```
+//!
+//! ## Strict Mode
+//!
+//! Users who want to detect schema drift can enable strict mode on the client with
+//! [`KiteConnect::set_strict_mode`](crate::connect::KiteConnect::set_strict_mode).
+//! Typed endpoint methods then return an error whenever a response contains fields
+//! that are not part of the model, as reported by [`KiteModel::unknown_fields`].
+//!
+//! ## Monetary Fields
+//!
+//! Prices, amounts and P&L values use the [`Price`] type, which is `f64` by default.
+//! Enabling the `rust_decimal` feature switches it to [`rust_decimal::Decimal`] so
+//! accounting code can avoid floating point rounding errors.
+//!
+//! ## Timestamps
+//!
+//! Kite sends naive timestamps in Indian Standard Time. Typed models expose them as
+//! [`Timestamp`] values (`DateTime<FixedOffset>` at `+05:30`); see [`ist`] for the
+//! parsing helpers and serde adapters.
+
+use chrono::{DateTime, FixedOffset};
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeSet, HashMap};
+
+mod instruments;
+pub mod ist;
+mod market;
+mod mutual_funds;
+mod orders;
+mod portfolio;
+mod user;
+
+pub(crate) use instruments::InstrumentColumns;
+pub use instruments::{Instrument, InstrumentToken};
+pub use market::{Exchange, TriggerRange};
+pub use mutual_funds::{MfInstrument, MfOrder};
+pub use orders::{Order, Trade};
+pub use portfolio::{Holding, Position, Positions};
+pub use user::{
+    AvailableMargin, MarginPnl, OrderMargin, Profile, SegmentMargin, UserMargins, UserSession, UtilisedMargin,
+};
+
+/// Map of fields present in a response that are not part of the typed model
+pub type ExtraFields = HashMap<String, JsonValue>;
+
+/// Numeric type used for prices and monetary amounts in typed models
+#[cfg(not(feature = "rust_decimal"))]
+pub type Price = f64;
+
+/// Numeric type used for prices and monetary amounts in typed models
+#[cfg(feature = "rust_decimal")]
+pub type Price = rust_decimal::Decimal;
+
+/// Converts a price to `f64`, for storage formats without a decimal type
+#[cfg(all(not(feature = "rust_decimal"), any(feature = "arrow", feature = "polars", feature = "sqlite")))]
+pub(crate) fn price_to_f64(price: Price) -> f64 {
+    price
+}
+
+/// Converts a price to `f64`, for storage formats without a decimal type
+#[cfg(all(feature = "rust_decimal", any(feature = "arrow", feature = "polars", feature = "sqlite")))]
+pub(crate) fn price_to_f64(price: Price) -> f64 {
+    rust_decimal::prelude::ToPrimitive::to_f64(&price).unwrap_or(f64::NAN)
+}
+
+/// Timestamp type used in typed models, always carrying the IST (`+05:30`) offset
+pub type Timestamp = DateTime<FixedOffset>;
+
+/// Common behaviour shared by all typed API models
+pub trait KiteModel {
+    /// Returns the names of fields that were present in the payload but are not
+    /// known to the model, including those of nested models (as dotted paths)
+    fn unknown_fields(&self) -> Vec<String>;
+}
+
+impl<T: KiteModel> KiteModel for Vec<T> {
+    fn unknown_fields(&self) -> Vec<String> {
+        let fields: BTreeSet<String> = self.iter().flat_map(|item| item.unknown_fields()).collect();
+        fields.into_iter().collect()
+    }
+}
+
+impl<T: KiteModel> KiteModel for Option<T> {
+    fn unknown_fields(&self) -> Vec<String> {
+        self.as_ref().map(|item| item.unknown_fields()).unwrap_or_default()
+    }
+}
+
+impl<T: KiteModel> KiteModel for HashMap<String, T> {
+    fn unknown_fields(&self) -> Vec<String> {
+        let fields: BTreeSet<String> = self.values().flat_map(|item| item.unknown_fields()).collect();
+        fields.into_iter().collect()
+    }
+}
+
+/// Implements [`KiteModel`] for a struct with an `extra` field, optionally
+/// descending into the listed nested model fields
+macro_rules! kite_model {
+    ($ty:ty) => {
+        kite_model!($ty {});
+    };
+    ($ty:ty { $($field:ident),* }) => {
+        impl $crate::models::KiteModel for $ty {
+            fn unknown_fields(&self) -> Vec<String> {
+                #[allow(unused_mut)]
+                let mut fields: Vec<String> = self.extra.keys().cloned().collect();
+                $(
+                    fields.extend(
+                        $crate::models::KiteModel::unknown_fields(&self.$field)
+                            .into_iter()
+                            .map(|name| format!("{}.{}", stringify!($field), name)),
+                    );
+                )*
+                fields.sort();
+                fields
+            }
+        }
+    };
+}
+pub(crate) use kite_model;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connect::extract_data;
+    use serde::de::DeserializeOwned;
+
+    fn load<T: DeserializeOwned>(path: &str) -> T {
+        let body = std::fs::read_to_string(path).unwrap();
+        let data = extract_data(serde_json::from_str(&body).unwrap()).unwrap();
+        serde_json::from_value(data).unwrap()
+    }
+
+    #[test]
+    fn test_deserialize_mocks() {
+        let profile: Profile = load("mocks/profile.json");
+        assert_eq!(profile.user_id, "AB1234");
+        assert!(profile.unknown_fields().is_empty());
+
+        let session: UserSession = load("mocks/generate_session.json");
+        assert_eq!(session.access_token, "yyyyyy");
+        assert_eq!(
+            session.login_time.map(|ts| ts.to_rfc3339()).as_deref(),
+            Some("2021-01-01T16:15:14+05:30")
+        );
+        assert!(session.unknown_fields().is_empty());
+
+        let margins: UserMargins = load("mocks/margins.json");
+        let net = margins.equity.as_ref().map(|m| m.net.to_string());
+        assert_eq!(net.as_deref(), Some("15481.524"));
+        assert!(margins.unknown_fields().is_empty());
+
+        let order_margins: Vec<OrderMargin> = load("mocks/order_margins.json");
+        assert_eq!(order_margins[0].segment, "equity");
+        assert_eq!(order_margins[0].total, Price::from(1498));
+        assert!(order_margins.unknown_fields().is_empty());
+
+        let holdings: Vec<Holding> = load("mocks/holdings.json");
+        assert_eq!(holdings[0].tradingsymbol, "BENGALASM");
+        assert!(holdings.unknown_fields().is_empty());
+
+        let positions: Positions = load("mocks/positions.json");
+        assert_eq!(positions.net[0].tradingsymbol, "LEADMINI17DECFUT");
+
+        let orders: Vec<Order> = load("mocks/orders.json");
+        assert_eq!(orders[0].order_id, "171228000850038");
+        assert_eq!(
+            orders[0].order_timestamp.map(|ts| ts.to_rfc3339()).as_deref(),
+            Some("2017-12-28T11:39:14+05:30")
+        );
+        assert_eq!(orders[0].exchange_update_timestamp, None);
+
+        let history: Vec<Order> = load("mocks/order_info.json");
+        assert_eq!(history[0].exchange_order_id, None);
+
+        let trades: Vec<Trade> = load("mocks/trades.json");
+        assert_eq!(trades[0].trade_id, "75894751");
+        assert_eq!(trades[0].order_timestamp, chrono::NaiveTime::from_hms_opt(12, 2, 5));
+
+        let mf_orders: Vec<MfOrder> = load("mocks/mf_orders.json");
+        assert_eq!(mf_orders[0].tradingsymbol, "INF174K01LS2");
+
+        let ranges: HashMap<String, TriggerRange> = load("mocks/trigger_range.json");
+        assert_eq!(ranges["NSE:RELIANCE"].upper.to_string(), "902.15");
+        assert!(ranges.unknown_fields().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_fields_are_captured() {
+        // The positions mock still carries the long-retired `token` field
+        let positions: Positions = load("mocks/positions.json");
+        assert_eq!(positions.unknown_fields(), vec!["day.token", "net.token"]);
+        assert!(positions.net[0].extra.contains_key("token"));
+
+        let margins: SegmentMargin = serde_json::from_value(serde_json::json!({
+            "enabled": true,
+            "net": 100.0,
+            "available": {"cash": 100.0, "new_balance": 1.0},
+            "utilised": {"debits": 0.0}
+        }))
+        .unwrap();
+        assert_eq!(margins.unknown_fields(), vec!["available.new_balance"]);
+    }
+}
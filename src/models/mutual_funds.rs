@@ -0,0 +1,122 @@
+//! Mutual fund models
+
+use super::{ist, kite_model, ExtraFields, Price, Timestamp};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// A mutual fund order
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MfOrder {
+    /// Unique order ID
+    pub order_id: String,
+    /// Exchange generated order ID
+    #[serde(default)]
+    pub exchange_order_id: Option<String>,
+    /// ISIN of the fund
+    pub tradingsymbol: String,
+    /// Current status of the order
+    pub status: String,
+    /// Textual description of the order's status
+    #[serde(default)]
+    pub status_message: Option<String>,
+    /// Folio number of the fund, if allotted
+    #[serde(default)]
+    pub folio: Option<String>,
+    /// Name of the fund
+    #[serde(default)]
+    pub fund: String,
+    /// Timestamp at which the order was registered by the API
+    #[serde(default, with = "ist::option_timestamp")]
+    pub order_timestamp: Option<Timestamp>,
+    /// Timestamp at which the order was registered by the exchange
+    #[serde(default, with = "ist::option_timestamp")]
+    pub exchange_timestamp: Option<Timestamp>,
+    /// Settlement ID of the order
+    #[serde(default)]
+    pub settlement_id: Option<String>,
+    /// BUY or SELL
+    pub transaction_type: String,
+    /// Order variety
+    #[serde(default)]
+    pub variety: String,
+    /// FRESH or ADDITIONAL purchase
+    #[serde(default)]
+    pub purchase_type: String,
+    /// Quantity to SELL
+    #[serde(default)]
+    pub quantity: f64,
+    /// Amount to BUY
+    #[serde(default)]
+    pub amount: Price,
+    /// NAV at the time of the order
+    #[serde(default)]
+    pub last_price: Price,
+    /// Average price at which the order was executed
+    #[serde(default)]
+    pub average_price: Price,
+    /// ID of the user that placed the order
+    #[serde(default)]
+    pub placed_by: String,
+    /// An optional tag to apply to an order to identify it
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Fields not known to this model
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+kite_model!(MfOrder);
+
+/// A mutual fund scheme of the MF instruments dump
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MfInstrument {
+    /// ISIN of the scheme, used as its tradingsymbol
+    pub tradingsymbol: String,
+    /// Asset management company, e.g. `AXISMUTUALFUND_MF`
+    pub amc: String,
+    /// Name of the scheme
+    pub name: String,
+    /// Whether the scheme accepts purchases
+    pub purchase_allowed: bool,
+    /// Whether the scheme accepts redemptions
+    pub redemption_allowed: bool,
+    /// Minimum amount of a fresh purchase
+    pub minimum_purchase_amount: Price,
+    /// Purchase amounts must be a multiple of this
+    pub purchase_amount_multiplier: Price,
+    /// Minimum amount of an additional purchase
+    pub minimum_additional_purchase_amount: Price,
+    /// Minimum number of units of a redemption
+    pub minimum_redemption_quantity: f64,
+    /// Redeemed units must be a multiple of this
+    pub redemption_quantity_multiplier: f64,
+    /// `growth`, or `payout` or `reinvestment` for dividend schemes
+    pub dividend_type: String,
+    /// Scheme type, e.g. `equity`, `debt` or `elss`
+    pub scheme_type: String,
+    /// `direct` or `regular`
+    pub plan: String,
+    /// Settlement cycle, e.g. `T3`
+    pub settlement_type: String,
+    /// Last published NAV
+    pub last_price: Price,
+    /// Date of the last published NAV
+    pub last_price_date: Option<NaiveDate>,
+}
+
+impl MfInstrument {
+    /// Returns `true` for growth schemes, which reinvest instead of paying dividends
+    pub fn is_growth(&self) -> bool {
+        self.dividend_type.eq_ignore_ascii_case("growth")
+    }
+
+    /// Returns `true` for dividend schemes, paying out or reinvesting dividends
+    pub fn is_dividend(&self) -> bool {
+        !self.dividend_type.is_empty() && !self.is_growth()
+    }
+
+    /// Returns `true` for direct plans, bought without a distributor's commission
+    pub fn is_direct(&self) -> bool {
+        self.plan.eq_ignore_ascii_case("direct")
+    }
+}
@@ -0,0 +1,172 @@
+//! Order and trade models
+
+use super::{ist, kite_model, ExtraFields, InstrumentToken, Price, Timestamp};
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// An order, as returned by the orderbook and order history endpoints
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Order {
+    /// Unique order ID
+    pub order_id: String,
+    /// ID of the parent order (only applicable in case of multi-legged orders like CO)
+    #[serde(default)]
+    pub parent_order_id: Option<String>,
+    /// Exchange generated order ID
+    #[serde(default)]
+    pub exchange_order_id: Option<String>,
+    /// ID of the user that placed the order
+    #[serde(default)]
+    pub placed_by: String,
+    /// Order variety (regular, amo, co, iceberg, auction)
+    pub variety: String,
+    /// Current status of the order
+    pub status: String,
+    /// Textual description of the order's status
+    #[serde(default)]
+    pub status_message: Option<String>,
+    /// Raw textual description of the order's status received from the OMS
+    #[serde(default)]
+    pub status_message_raw: Option<String>,
+    /// Timestamp at which the order was registered by the API
+    #[serde(default, with = "ist::option_timestamp")]
+    pub order_timestamp: Option<Timestamp>,
+    /// Timestamp at which the order was registered by the exchange
+    #[serde(default, with = "ist::option_timestamp")]
+    pub exchange_timestamp: Option<Timestamp>,
+    /// Timestamp at which an order's state changed at the exchange
+    #[serde(default, with = "ist::option_timestamp")]
+    pub exchange_update_timestamp: Option<Timestamp>,
+    /// Exchange
+    pub exchange: String,
+    /// Exchange tradingsymbol of the instrument
+    pub tradingsymbol: String,
+    /// Unique instrument identifier
+    pub instrument_token: InstrumentToken,
+    /// Order type (MARKET, LIMIT, SL, SL-M)
+    pub order_type: String,
+    /// BUY or SELL
+    pub transaction_type: String,
+    /// Order validity (DAY, IOC, TTL)
+    pub validity: String,
+    /// Order life span in minutes for TTL validity orders
+    #[serde(default)]
+    pub validity_ttl: Option<i64>,
+    /// Margin product to use for the order
+    pub product: String,
+    /// Quantity ordered
+    pub quantity: i64,
+    /// Quantity to be disclosed to the public exchange orderbook
+    #[serde(default)]
+    pub disclosed_quantity: i64,
+    /// Price at which the order was placed (LIMIT orders)
+    #[serde(default)]
+    pub price: Price,
+    /// Trigger price (for SL, SL-M, CO orders)
+    #[serde(default)]
+    pub trigger_price: Price,
+    /// Average price at which the order was executed
+    #[serde(default)]
+    pub average_price: Price,
+    /// Quantity that has been filled
+    #[serde(default)]
+    pub filled_quantity: i64,
+    /// Quantity that's pending
+    #[serde(default)]
+    pub pending_quantity: i64,
+    /// Quantity that's cancelled
+    #[serde(default)]
+    pub cancelled_quantity: i64,
+    /// Market protection percentage applied to market orders
+    #[serde(default)]
+    pub market_protection: f64,
+    /// Map of arbitrary fields that the system may attach to an order
+    #[serde(default)]
+    pub meta: Option<JsonValue>,
+    /// An optional tag to apply to an order to identify it
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Unusual activity guid
+    #[serde(default)]
+    pub guid: Option<String>,
+    /// Fields not known to this model
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+kite_model!(Order);
+
+impl Order {
+    /// Returns `true` once the order can no longer change: complete,
+    /// rejected or cancelled
+    pub fn is_terminal(&self) -> bool {
+        self.is_complete() || self.is_rejected() || self.is_cancelled()
+    }
+
+    /// Returns `true` if the order was filled completely
+    pub fn is_complete(&self) -> bool {
+        self.status == "COMPLETE"
+    }
+
+    /// Returns `true` if the order was rejected by the OMS or the exchange
+    pub fn is_rejected(&self) -> bool {
+        self.status == "REJECTED"
+    }
+
+    /// Returns `true` if the order was cancelled, possibly after a partial fill
+    pub fn is_cancelled(&self) -> bool {
+        self.status.starts_with("CANCELLED")
+    }
+
+    /// Returns `true` if the order carries `tag`, either as its tag or among
+    /// the `tags` newer responses list
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tag.as_deref() == Some(tag)
+            || self
+                .extra
+                .get("tags")
+                .and_then(JsonValue::as_array)
+                .is_some_and(|tags| tags.iter().any(|t| t.as_str() == Some(tag)))
+    }
+}
+
+/// An executed trade
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Trade {
+    /// Exchange generated trade ID
+    pub trade_id: String,
+    /// Unique order ID
+    pub order_id: String,
+    /// Exchange generated order ID
+    #[serde(default)]
+    pub exchange_order_id: Option<String>,
+    /// Exchange tradingsymbol of the instrument
+    pub tradingsymbol: String,
+    /// Exchange
+    pub exchange: String,
+    /// Unique instrument identifier
+    pub instrument_token: InstrumentToken,
+    /// BUY or SELL
+    pub transaction_type: String,
+    /// Margin product to use for the order
+    pub product: String,
+    /// Price at which the quantity was filled
+    pub average_price: Price,
+    /// Filled quantity
+    pub quantity: i64,
+    /// Timestamp at which the trade was filled at the exchange
+    #[serde(default, with = "ist::option_timestamp")]
+    pub fill_timestamp: Option<Timestamp>,
+    /// Time of day (IST) at which the order was registered by the API
+    #[serde(default, with = "ist::option_time")]
+    pub order_timestamp: Option<NaiveTime>,
+    /// Timestamp at which the order was registered by the exchange
+    #[serde(default, with = "ist::option_timestamp")]
+    pub exchange_timestamp: Option<Timestamp>,
+    /// Fields not known to this model
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+kite_model!(Trade);
@@ -0,0 +1,165 @@
+//! Holdings and positions models
+
+use super::{kite_model, ExtraFields, InstrumentToken, Price};
+use serde::{Deserialize, Serialize};
+
+/// A long term equity holding in the user's demat account
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Holding {
+    /// Exchange tradingsymbol of the instrument
+    pub tradingsymbol: String,
+    /// Exchange
+    pub exchange: String,
+    /// Unique instrument identifier
+    pub instrument_token: InstrumentToken,
+    /// The standard ISIN representing stocks listed on multiple exchanges
+    #[serde(default)]
+    pub isin: String,
+    /// Margin product applied to the holding
+    #[serde(default)]
+    pub product: String,
+    /// Price of the holding
+    #[serde(default)]
+    pub price: Price,
+    /// Net quantity (T+1 + realised)
+    pub quantity: i64,
+    /// Quantity on T+1 day after order execution
+    #[serde(default)]
+    pub t1_quantity: i64,
+    /// Quantity delivered to the demat account
+    #[serde(default)]
+    pub realised_quantity: i64,
+    /// Quantity used as collateral
+    #[serde(default)]
+    pub collateral_quantity: i64,
+    /// Type of collateral
+    #[serde(default)]
+    pub collateral_type: String,
+    /// Average price at which the net holding quantity was acquired
+    pub average_price: Price,
+    /// Last traded market price of the instrument
+    pub last_price: Price,
+    /// Closing price of the instrument from the last trading day
+    #[serde(default)]
+    pub close_price: Price,
+    /// Net returns on the stock; profit and loss
+    #[serde(default)]
+    pub pnl: Price,
+    /// Day's change in absolute value for the stock
+    #[serde(default)]
+    pub day_change: Price,
+    /// Day's change in percentage for the stock
+    #[serde(default)]
+    pub day_change_percentage: f64,
+    /// Fields not known to this model
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+kite_model!(Holding);
+
+/// Net and day positions returned by `/portfolio/positions`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Positions {
+    /// Actual current obligations
+    #[serde(default)]
+    pub net: Vec<Position>,
+    /// Snapshot of buying and selling activity for the day
+    #[serde(default)]
+    pub day: Vec<Position>,
+    /// Fields not known to this model
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+kite_model!(Positions { net, day });
+
+/// A single open or closed position
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    /// Exchange tradingsymbol of the instrument
+    pub tradingsymbol: String,
+    /// Exchange
+    pub exchange: String,
+    /// Unique instrument identifier
+    pub instrument_token: InstrumentToken,
+    /// Margin product applied to the position
+    pub product: String,
+    /// Quantity held
+    pub quantity: i64,
+    /// Quantity held previously and carried forward over night
+    #[serde(default)]
+    pub overnight_quantity: i64,
+    /// The quantity/lot size multiplier used for calculating P&Ls
+    #[serde(default)]
+    pub multiplier: f64,
+    /// Average price at which the net position quantity was acquired
+    pub average_price: Price,
+    /// Closing price of the instrument from the last trading day
+    #[serde(default)]
+    pub close_price: Price,
+    /// Last traded market price of the instrument
+    pub last_price: Price,
+    /// Net value of the position
+    #[serde(default)]
+    pub value: Price,
+    /// Net returns on the position; profit and loss
+    #[serde(default)]
+    pub pnl: Price,
+    /// Mark to market returns (computed based on the last close and the last traded price)
+    #[serde(default)]
+    pub m2m: Price,
+    /// Unrealised intraday returns
+    #[serde(default)]
+    pub unrealised: Price,
+    /// Realised intraday returns
+    #[serde(default)]
+    pub realised: Price,
+    /// Quantity bought and added to the position
+    #[serde(default)]
+    pub buy_quantity: i64,
+    /// Average price at which quantities were bought
+    #[serde(default)]
+    pub buy_price: Price,
+    /// Net value of the bought quantities
+    #[serde(default)]
+    pub buy_value: Price,
+    /// Mark to market returns on the bought quantities
+    #[serde(default)]
+    pub buy_m2m_value: Price,
+    /// Quantity sold off from the position
+    #[serde(default)]
+    pub sell_quantity: i64,
+    /// Average price at which quantities were sold
+    #[serde(default)]
+    pub sell_price: Price,
+    /// Net value of the sold quantities
+    #[serde(default)]
+    pub sell_value: Price,
+    /// Mark to market returns on the sold quantities
+    #[serde(default)]
+    pub sell_m2m_value: Price,
+    /// Quantity bought during the day
+    #[serde(default)]
+    pub day_buy_quantity: i64,
+    /// Average price of the quantities bought during the day
+    #[serde(default)]
+    pub day_buy_price: Price,
+    /// Value of the quantities bought during the day
+    #[serde(default)]
+    pub day_buy_value: Price,
+    /// Quantity sold during the day
+    #[serde(default)]
+    pub day_sell_quantity: i64,
+    /// Average price of the quantities sold during the day
+    #[serde(default)]
+    pub day_sell_price: Price,
+    /// Value of the quantities sold during the day
+    #[serde(default)]
+    pub day_sell_value: Price,
+    /// Fields not known to this model
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+kite_model!(Position);
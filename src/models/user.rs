@@ -0,0 +1,254 @@
+//! User profile and funds models
+
+use super::{ist, kite_model, ExtraFields, Price, Timestamp};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// User profile returned by `/user/profile`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    /// Unique Zerodha client ID
+    pub user_id: String,
+    /// User's real name
+    #[serde(default)]
+    pub user_name: String,
+    /// Shortened version of the user's name
+    #[serde(default)]
+    pub user_shortname: String,
+    /// Email address
+    #[serde(default)]
+    pub email: String,
+    /// Account type
+    #[serde(default)]
+    pub user_type: String,
+    /// Broker ID
+    #[serde(default)]
+    pub broker: String,
+    /// Exchanges enabled for trading on the account
+    #[serde(default)]
+    pub exchanges: Vec<String>,
+    /// Margin products enabled for the account
+    #[serde(default)]
+    pub products: Vec<String>,
+    /// Order types enabled for the account
+    #[serde(default)]
+    pub order_types: Vec<String>,
+    /// Full URL to the user's avatar, if any
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+    /// Additional account metadata
+    #[serde(default)]
+    pub meta: Option<JsonValue>,
+    /// Fields not known to this model
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+kite_model!(Profile);
+
+/// Session details returned by `/session/token` after a successful login
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UserSession {
+    /// Unique Zerodha client ID
+    pub user_id: String,
+    /// User's real name
+    #[serde(default)]
+    pub user_name: String,
+    /// Shortened version of the user's name
+    #[serde(default)]
+    pub user_shortname: String,
+    /// Email address
+    #[serde(default)]
+    pub email: String,
+    /// Account type
+    #[serde(default)]
+    pub user_type: String,
+    /// Broker ID
+    #[serde(default)]
+    pub broker: String,
+    /// Exchanges enabled for trading on the account
+    #[serde(default)]
+    pub exchanges: Vec<String>,
+    /// Margin products enabled for the account
+    #[serde(default)]
+    pub products: Vec<String>,
+    /// Order types enabled for the account
+    #[serde(default)]
+    pub order_types: Vec<String>,
+    /// API key the session was created for
+    #[serde(default)]
+    pub api_key: String,
+    /// Authentication token for subsequent API calls
+    #[serde(default)]
+    pub access_token: String,
+    /// Token for public session validation where requests may be exposed to the public
+    #[serde(default)]
+    pub public_token: String,
+    /// Token for obtaining new access tokens (only issued to approved platforms)
+    #[serde(default)]
+    pub refresh_token: String,
+    /// Session token of the Kite web frontend
+    #[serde(default)]
+    pub enctoken: String,
+    /// Time at which the user logged in
+    #[serde(default, with = "ist::option_timestamp")]
+    pub login_time: Option<Timestamp>,
+    /// Full URL to the user's avatar, if any
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+    /// Additional account metadata
+    #[serde(default)]
+    pub meta: Option<JsonValue>,
+    /// Fields not known to this model
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+kite_model!(UserSession);
+
+/// Funds and margins for all segments returned by `/user/margins`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UserMargins {
+    /// Equity segment margins
+    #[serde(default)]
+    pub equity: Option<SegmentMargin>,
+    /// Commodity segment margins
+    #[serde(default)]
+    pub commodity: Option<SegmentMargin>,
+    /// Fields not known to this model
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+kite_model!(UserMargins { equity, commodity });
+
+/// Funds and margins for a single segment
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SegmentMargin {
+    /// Whether the segment is enabled for the user
+    pub enabled: bool,
+    /// Net cash balance available for trading
+    pub net: Price,
+    /// Available margin components
+    pub available: AvailableMargin,
+    /// Utilised margin components
+    pub utilised: UtilisedMargin,
+    /// Fields not known to this model
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+kite_model!(SegmentMargin { available, utilised });
+
+/// Breakdown of available margin
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AvailableMargin {
+    /// Additional margin provided by the broker
+    pub adhoc_margin: Price,
+    /// Raw cash balance
+    pub cash: Price,
+    /// Opening balance at the day start
+    pub opening_balance: Price,
+    /// Current available balance
+    pub live_balance: Price,
+    /// Margin derived from pledged stocks
+    pub collateral: Price,
+    /// Amount that was deposited during the day
+    pub intraday_payin: Price,
+    /// Fields not known to this model
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+kite_model!(AvailableMargin);
+
+/// Breakdown of utilised margin
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UtilisedMargin {
+    /// Sum of all utilised margins
+    pub debits: Price,
+    /// Exposure margin blocked for all open F&O positions
+    pub exposure: Price,
+    /// Booked intraday profits and losses
+    pub m2m_realised: Price,
+    /// Un-booked (open) intraday profits and losses
+    pub m2m_unrealised: Price,
+    /// Value of options premium received by shorting
+    pub option_premium: Price,
+    /// Funds paid out or withdrawn to bank account during the day
+    pub payout: Price,
+    /// SPAN margin blocked for all open F&O positions
+    pub span: Price,
+    /// Value of holdings sold during the day
+    pub holding_sales: Price,
+    /// Utilised portion of the maximum turnover limit
+    pub turnover: Price,
+    /// Margin utilised against pledged liquidbees ETFs and liquid mutual funds
+    pub liquid_collateral: Price,
+    /// Margin utilised against pledged stocks/ETFs
+    pub stock_collateral: Price,
+    /// Margin blocked when you sell securities from demat
+    pub delivery: Price,
+    /// Fields not known to this model
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+kite_model!(UtilisedMargin);
+
+/// Margin an order would block, returned by `/margins/orders`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OrderMargin {
+    /// Segment the margin is blocked in, `equity` or `commodity`
+    #[serde(rename = "type")]
+    pub segment: String,
+    /// Exchange tradingsymbol of the instrument
+    pub tradingsymbol: String,
+    /// Exchange of the instrument
+    pub exchange: String,
+    /// SPAN margin
+    pub span: Price,
+    /// Exposure margin
+    pub exposure: Price,
+    /// Option premium paid
+    pub option_premium: Price,
+    /// Additional margin
+    pub additional: Price,
+    /// Bracket order margin
+    pub bo: Price,
+    /// Cash credit
+    pub cash: Price,
+    /// VAR margin of equity delivery
+    pub var: Price,
+    /// Profits and losses of the existing position
+    pub pnl: MarginPnl,
+    /// Leverage the product offers
+    pub leverage: f64,
+    /// Charges of the order, such as brokerage and taxes
+    pub charges: Option<JsonValue>,
+    /// Total margin blocked
+    pub total: Price,
+    /// Fields not known to this model
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+kite_model!(OrderMargin { pnl });
+
+/// Profits and losses accounted in an [`OrderMargin`]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarginPnl {
+    /// Booked profits and losses
+    pub realised: Price,
+    /// Profits and losses of the open position
+    pub unrealised: Price,
+    /// Fields not known to this model
+    #[serde(flatten)]
+    pub extra: ExtraFields,
+}
+
+kite_model!(MarginPnl);
@@ -0,0 +1,108 @@
+//! # Request Options
+//!
+//! [`RequestOptions`] override the client configuration for a subset of calls,
+//! such as a tight timeout for LTP polling or a long one for instrument dumps.
+//! Options are applied with [`KiteConnect::with_options`], which returns a cheap
+//! clone of the client sharing its access token, rate limiter and connection pool:
+//!
+//! ```rust,no_run
+//! use kiteconnect::connect::KiteConnect;
+//! use kiteconnect::options::RequestOptions;
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = KiteConnect::new("api_key", "access_token");
+//!
+//! let fast = client.with_options(RequestOptions::new().timeout(Duration::from_millis(500)));
+//! let holdings = fast.holdings().await?;
+//!
+//! let slow = client.with_options(RequestOptions::new().timeout(Duration::from_secs(60)));
+//! let instruments = slow.instruments(None).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`KiteConnect::with_options`]: crate::connect::KiteConnect::with_options
+
+use crate::error::{KiteError, Result};
+use crate::retry::RetryPolicy;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::time::Duration;
+
+/// Per-call overrides for timeouts, retries and request headers
+#[derive(Clone, Debug, Default)]
+pub struct RequestOptions {
+    /// Timeout for each attempt of a request
+    pub timeout: Option<Duration>,
+    /// Retry policy used instead of the client's policy
+    pub retry_policy: Option<RetryPolicy>,
+    /// Extra headers sent with every request, replacing defaults of the same name
+    pub headers: HeaderMap,
+}
+
+impl RequestOptions {
+    /// Creates empty options that leave the client configuration untouched
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the timeout for each attempt of a request
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Overrides the client's retry policy
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Adds a header to every request
+    ///
+    /// Returns an error if the name or value is not a valid HTTP header.
+    pub fn header(mut self, name: &str, value: &str) -> Result<Self> {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| KiteError::Other(format!("invalid header name {:?}: {}", name, e)))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| KiteError::Other(format!("invalid header value for {}: {}", name, e)))?;
+        self.headers.insert(name, value);
+        Ok(self)
+    }
+
+    /// Applies the options' headers on top of the given default headers
+    pub(crate) fn apply_headers(&self, headers: &mut HeaderMap) {
+        for (name, value) in &self.headers {
+            headers.insert(name.clone(), value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::USER_AGENT;
+
+    #[test]
+    fn test_request_options() {
+        let options = RequestOptions::new()
+            .timeout(Duration::from_secs(2))
+            .header("User-Agent", "my-strategy")
+            .unwrap()
+            .header("X-Request-Id", "42")
+            .unwrap();
+        assert_eq!(options.timeout, Some(Duration::from_secs(2)));
+        assert_eq!(options.retry_policy, None);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static("Rust"));
+        headers.insert("XKiteVersion", HeaderValue::from_static("3"));
+        options.apply_headers(&mut headers);
+        assert_eq!(headers[USER_AGENT], "my-strategy");
+        assert_eq!(headers["x-request-id"], "42");
+        assert_eq!(headers["XKiteVersion"], "3");
+
+        assert!(RequestOptions::new().header("bad header", "1").is_err());
+    }
+}
@@ -0,0 +1,282 @@
+//! Placing several orders as a basket
+
+use super::OrderParams;
+use crate::connect::KiteConnect;
+use crate::error::{KiteError, Result};
+
+/// What [`KiteConnect::place_basket`] does when an order of the basket fails
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnFailure {
+    /// Stop placing orders; those already placed stay open
+    #[default]
+    Abort,
+    /// Place every other order regardless
+    Continue,
+    /// Stop placing orders and cancel those already placed
+    CancelPlaced,
+}
+
+/// How [`KiteConnect::place_basket`] places its orders
+///
+/// Orders are placed one after another by default. Placed
+/// [concurrently](Self::concurrent), every order is sent at once, so a
+/// failure cannot stop the others and [`OnFailure::Abort`] behaves like
+/// [`OnFailure::Continue`].
+///
+/// ```rust
+/// use kiteconnect::orders::{BasketPolicy, OnFailure};
+///
+/// let policy = BasketPolicy::cancel_on_failure().concurrent(true);
+/// assert_eq!(policy.on_failure, OnFailure::CancelPlaced);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BasketPolicy {
+    /// What to do when an order fails
+    pub on_failure: OnFailure,
+    /// Whether the orders are placed at once rather than one after another
+    pub concurrent: bool,
+}
+
+impl BasketPolicy {
+    /// Stops at the first failed order, leaving the placed ones open
+    pub fn abort_on_failure() -> Self {
+        BasketPolicy {
+            on_failure: OnFailure::Abort,
+            concurrent: false,
+        }
+    }
+
+    /// Places every order, whatever happens to the others
+    pub fn best_effort() -> Self {
+        BasketPolicy {
+            on_failure: OnFailure::Continue,
+            concurrent: false,
+        }
+    }
+
+    /// Cancels the placed orders once one fails, placing all or none
+    pub fn cancel_on_failure() -> Self {
+        BasketPolicy {
+            on_failure: OnFailure::CancelPlaced,
+            concurrent: false,
+        }
+    }
+
+    /// Places the orders at once rather than one after another
+    pub fn concurrent(mut self, concurrent: bool) -> Self {
+        self.concurrent = concurrent;
+        self
+    }
+}
+
+/// Outcome of one order of a basket
+#[derive(Debug)]
+pub enum LegStatus {
+    /// The order was placed
+    Placed(String),
+    /// The order could not be placed
+    Failed(KiteError),
+    /// The order was not placed because an earlier one failed
+    Skipped,
+    /// The order was placed and cancelled after another one failed
+    Cancelled(String),
+    /// The order was placed, but cancelling it after another one failed did
+    /// not succeed, so it may still be open
+    CancelFailed {
+        /// ID of the order
+        order_id: String,
+        /// Error of the cancellation
+        error: KiteError,
+    },
+}
+
+/// An order of a basket and its outcome
+#[derive(Debug)]
+pub struct BasketLeg {
+    /// Parameters of the order
+    pub params: OrderParams,
+    /// Outcome of the order
+    pub status: LegStatus,
+}
+
+/// Outcome of [`KiteConnect::place_basket`], one leg per order in the order
+/// of the basket
+#[derive(Debug, Default)]
+pub struct BasketReport {
+    /// Orders of the basket and their outcomes
+    pub legs: Vec<BasketLeg>,
+}
+
+impl BasketReport {
+    /// Returns `true` if every order of the basket was placed
+    pub fn is_complete(&self) -> bool {
+        self.legs.iter().all(|leg| matches!(leg.status, LegStatus::Placed(_)))
+    }
+
+    /// Returns the IDs of the orders that were placed and not cancelled
+    pub fn order_ids(&self) -> impl Iterator<Item = &str> {
+        self.legs.iter().filter_map(|leg| match &leg.status {
+            LegStatus::Placed(order_id) | LegStatus::CancelFailed { order_id, .. } => Some(order_id.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Returns the legs that could not be placed with their errors
+    pub fn failures(&self) -> impl Iterator<Item = (&OrderParams, &KiteError)> {
+        self.legs.iter().filter_map(|leg| match &leg.status {
+            LegStatus::Failed(error) => Some((&leg.params, error)),
+            _ => None,
+        })
+    }
+}
+
+impl KiteConnect {
+    /// Places a basket of orders, handling failures as `policy` says
+    ///
+    /// Every order is placed like with [`place_order_params`](Self::place_order_params).
+    /// The report holds one leg per order: placed, failed, skipped after a
+    /// failure, or cancelled after a failure with [`OnFailure::CancelPlaced`].
+    /// Cancelling withdraws only the part of an order that is still open, so
+    /// market orders are usually filled before they can be cancelled.
+    ///
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    /// use kiteconnect::orders::{BasketPolicy, OrderParams};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> kiteconnect::error::Result<()> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// let orders = vec![
+    ///     OrderParams::buy("NSE", "INFY", 10).product("CNC").order_type("LIMIT").price("1500".parse().unwrap()),
+    ///     OrderParams::buy("NSE", "TCS", 5).product("CNC").order_type("LIMIT").price("3900".parse().unwrap()),
+    /// ];
+    /// let report = client.place_basket(orders, BasketPolicy::cancel_on_failure()).await;
+    /// for (params, error) in report.failures() {
+    ///     eprintln!("{} failed: {}", params.tradingsymbol, error);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn place_basket(&self, orders: Vec<OrderParams>, policy: BasketPolicy) -> BasketReport {
+        let mut statuses = Vec::with_capacity(orders.len());
+        if policy.concurrent {
+            let placed = futures::future::join_all(orders.iter().map(|params| self.place_order_params(params))).await;
+            statuses.extend(placed.into_iter().map(leg_status));
+        } else {
+            for params in &orders {
+                let failed = statuses.iter().any(|status| matches!(status, LegStatus::Failed(_)));
+                if failed && policy.on_failure != OnFailure::Continue {
+                    statuses.push(LegStatus::Skipped);
+                } else {
+                    statuses.push(leg_status(self.place_order_params(params).await));
+                }
+            }
+        }
+
+        let failed = statuses.iter().any(|status| matches!(status, LegStatus::Failed(_)));
+        if failed && policy.on_failure == OnFailure::CancelPlaced {
+            for (params, status) in orders.iter().zip(statuses.iter_mut()) {
+                if let LegStatus::Placed(order_id) = status {
+                    let order_id = std::mem::take(order_id);
+                    *status = match self.cancel_order(&order_id, &params.variety, None).await {
+                        Ok(_) => LegStatus::Cancelled(order_id),
+                        Err(error) => {
+                            log::warn!("Failed to cancel order {} of a failed basket: {}", order_id, error);
+                            LegStatus::CancelFailed { order_id, error }
+                        }
+                    };
+                }
+            }
+        }
+
+        let legs = orders
+            .into_iter()
+            .zip(statuses)
+            .map(|(params, status)| BasketLeg { params, status })
+            .collect();
+        BasketReport { legs }
+    }
+}
+
+fn leg_status(placed: Result<String>) -> LegStatus {
+    match placed {
+        Ok(order_id) => LegStatus::Placed(order_id),
+        Err(error) => LegStatus::Failed(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_client;
+    use mockito::{Matcher, Server};
+
+    #[tokio::test]
+    async fn test_place_basket() {
+        let mut server = Server::new_async().await;
+        let client = mock_client(&server);
+        for (tradingsymbol, response) in [
+            ("INFY", r#"{"status": "success", "data": {"order_id": "1"}}"#),
+            ("TCS", r#"{"status": "error", "message": "Insufficient funds", "error_type": "MarginException"}"#),
+            ("SBIN", r#"{"status": "success", "data": {"order_id": "3"}}"#),
+        ] {
+            server
+                .mock("POST", "/orders/regular")
+                .match_body(Matcher::UrlEncoded("tradingsymbol".into(), tradingsymbol.into()))
+                .with_status(if response.contains("error") { 400 } else { 200 })
+                .with_body(response)
+                .create_async()
+                .await;
+        }
+        let cancel = server
+            .mock("DELETE", "/orders/regular/1")
+            .with_body(r#"{"status": "success", "data": {"order_id": "1"}}"#)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let basket = || {
+            vec![
+                OrderParams::buy("NSE", "INFY", 1),
+                OrderParams::buy("NSE", "TCS", 1),
+                OrderParams::buy("NSE", "SBIN", 1),
+            ]
+        };
+        let statuses = |report: &BasketReport| {
+            report
+                .legs
+                .iter()
+                .map(|leg| match &leg.status {
+                    LegStatus::Placed(_) => "placed",
+                    LegStatus::Failed(_) => "failed",
+                    LegStatus::Skipped => "skipped",
+                    LegStatus::Cancelled(_) => "cancelled",
+                    LegStatus::CancelFailed { .. } => "cancel failed",
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let report = client.place_basket(basket(), BasketPolicy::abort_on_failure()).await;
+        assert_eq!(statuses(&report), ["placed", "failed", "skipped"]);
+        assert_eq!(report.order_ids().collect::<Vec<_>>(), ["1"]);
+        assert_eq!(report.failures().next().unwrap().0.tradingsymbol, "TCS");
+
+        let report = client.place_basket(basket(), BasketPolicy::best_effort()).await;
+        assert_eq!(statuses(&report), ["placed", "failed", "placed"]);
+        assert!(!report.is_complete());
+
+        let report = client.place_basket(basket(), BasketPolicy::cancel_on_failure()).await;
+        assert_eq!(statuses(&report), ["cancelled", "failed", "skipped"]);
+        assert_eq!(report.order_ids().count(), 0);
+
+        let report = client
+            .place_basket(basket(), BasketPolicy::cancel_on_failure().concurrent(true))
+            .await;
+        assert_eq!(statuses(&report), ["cancelled", "failed", "cancel failed"]);
+        assert_eq!(report.order_ids().collect::<Vec<_>>(), ["3"]);
+        cancel.assert_async().await;
+
+        let report = client.place_basket(vec![OrderParams::buy("NSE", "INFY", 1)], BasketPolicy::default()).await;
+        assert!(report.is_complete());
+    }
+}
@@ -0,0 +1,434 @@
+//! Emulated bracket orders: an entry protected by a target and a stoploss
+
+use super::{GttExit, OrderExecution, OrderParams};
+use crate::connect::KiteConnect;
+use crate::error::{KiteError, Result};
+use crate::models::{Order, Price};
+use std::time::Duration;
+
+/// How [`KiteConnect::place_bracket`] protects a filled entry
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BracketExit {
+    /// A two-leg GTT, cancelled by the OMS once either leg triggers
+    #[default]
+    Gtt,
+    /// A limit order at the target and an `SL-M` order at the stoploss, the
+    /// other one cancelled by [`KiteConnect::update_bracket`] once one fills
+    LinkedOrders,
+}
+
+/// Which exit of a bracket closed it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitLeg {
+    /// The exit at the target
+    Target,
+    /// The exit at the stoploss
+    Stoploss,
+}
+
+/// An entry order with its target and stoploss, taken by
+/// [`KiteConnect::place_bracket`]
+///
+/// The exits take the opposite side of the entry for its filled quantity,
+/// with the product of the entry, `CNC` if unset.
+///
+/// ```rust
+/// use kiteconnect::orders::{BracketExit, BracketOrder, OrderParams};
+///
+/// let entry = OrderParams::buy("NSE", "INFY", 10).product("MIS").order_type("MARKET");
+/// let bracket = BracketOrder::new(entry, "1550".parse().unwrap(), "1480".parse().unwrap())
+///     .exit(BracketExit::LinkedOrders);
+/// assert_eq!(bracket.exit, BracketExit::LinkedOrders);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct BracketOrder {
+    /// The entry order
+    pub entry: OrderParams,
+    /// Price at which the position is closed with a profit
+    pub target: Price,
+    /// Price at which the position is closed with a loss
+    pub stoploss: Price,
+    /// How the exits are placed, a GTT by default
+    pub exit: BracketExit,
+    /// How long the entry may take to fill, 30 seconds by default
+    pub fill_timeout: Duration,
+}
+
+impl BracketOrder {
+    /// Creates a bracket of `entry` exiting at `target` or `stoploss`
+    pub fn new(entry: OrderParams, target: Price, stoploss: Price) -> Self {
+        BracketOrder {
+            entry,
+            target,
+            stoploss,
+            exit: BracketExit::default(),
+            fill_timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Sets how the exits are placed
+    pub fn exit(mut self, exit: BracketExit) -> Self {
+        self.exit = exit;
+        self
+    }
+
+    /// Sets how long the entry may take to fill
+    pub fn fill_timeout(mut self, fill_timeout: Duration) -> Self {
+        self.fill_timeout = fill_timeout;
+        self
+    }
+}
+
+/// State of a bracket after its entry filled
+#[derive(Debug)]
+pub enum BracketState {
+    /// The position is protected by a two-leg GTT
+    Gtt {
+        /// ID of the GTT trigger
+        trigger_id: u64,
+    },
+    /// The position is protected by two open exit orders
+    Linked {
+        /// ID of the target order
+        target_order_id: String,
+        /// ID of the stoploss order
+        stoploss_order_id: String,
+    },
+    /// An exit filled and the other one still has to be cancelled
+    Closing {
+        /// The exit that filled
+        leg: ExitLeg,
+        /// ID of the order to cancel
+        cancel_order_id: String,
+    },
+    /// The position is closed, by the given leg when known
+    Closed {
+        /// The exit that filled; unknown for a triggered GTT
+        leg: Option<ExitLeg>,
+    },
+    /// The exits could not be placed or were removed without filling, so the
+    /// position is open without protection
+    Unprotected(KiteError),
+}
+
+/// A filled entry and the state of its exits, returned by
+/// [`KiteConnect::place_bracket`]
+#[derive(Debug)]
+pub struct Bracket {
+    /// The filled entry order
+    pub entry: OrderExecution,
+    /// Variety of the exit orders
+    pub variety: String,
+    /// State of the exits
+    pub state: BracketState,
+}
+
+impl Bracket {
+    /// Returns `true` once an exit filled and its sibling is cancelled
+    pub fn is_closed(&self) -> bool {
+        matches!(self.state, BracketState::Closed { .. })
+    }
+
+    /// Returns `true` while the position is open with its exits in place
+    pub fn is_protected(&self) -> bool {
+        matches!(self.state, BracketState::Gtt { .. } | BracketState::Linked { .. })
+    }
+
+    /// Moves the linked exits on an update of one of them; updates of other
+    /// orders and non-terminal statuses are ignored
+    fn on_order(&mut self, order: &Order) {
+        let BracketState::Linked {
+            target_order_id,
+            stoploss_order_id,
+        } = &self.state
+        else {
+            return;
+        };
+        let (leg, sibling) = if order.order_id == *target_order_id {
+            (ExitLeg::Target, stoploss_order_id)
+        } else if order.order_id == *stoploss_order_id {
+            (ExitLeg::Stoploss, target_order_id)
+        } else {
+            return;
+        };
+        if order.is_complete() {
+            self.state = BracketState::Closing {
+                leg,
+                cancel_order_id: sibling.clone(),
+            };
+        } else if order.is_rejected() || order.is_cancelled() {
+            // Nothing filled, so the position is still open with only the sibling left
+            self.state = BracketState::Unprotected(KiteError::Other(format!(
+                "exit {} of the bracket ended {}, leaving order {} open",
+                order.order_id, order.status, sibling
+            )));
+        }
+    }
+}
+
+impl KiteConnect {
+    /// Emulates a bracket order: places the entry and, once it fills,
+    /// protects the position with a target and a stoploss
+    ///
+    /// The entry is placed and awaited like with
+    /// [`place_order_and_wait`](Self::place_order_and_wait) and fails the
+    /// same way; an entry still open after the fill timeout stays open. The
+    /// exits are then placed as [`BracketOrder::exit`] says. A GTT leaves
+    /// cancelling the other leg to the OMS, but must have the target and the
+    /// stoploss on either side of the fill price. Linked orders are cancelled
+    /// by [`update_bracket`](Self::update_bracket) or
+    /// [`poll_bracket`](Self::poll_bracket), so one of them has to be fed
+    /// the order updates.
+    ///
+    /// Once the entry filled the bracket is returned even if its exits fail,
+    /// in [`BracketState::Unprotected`] with the error.
+    ///
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    /// use kiteconnect::orders::{BracketOrder, OrderParams};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> kiteconnect::error::Result<()> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// let entry = OrderParams::buy("NSE", "INFY", 10).product("MIS").order_type("MARKET");
+    /// let order = BracketOrder::new(entry, "1550".parse().unwrap(), "1480".parse().unwrap());
+    /// let bracket = client.place_bracket(&order).await?;
+    /// println!("{:?}", bracket.state);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn place_bracket(&self, order: &BracketOrder) -> Result<Bracket> {
+        let entry = self.place_order_and_wait(&order.entry, order.fill_timeout).await?;
+        let params = &order.entry;
+        let side = if params.transaction_type.eq_ignore_ascii_case("BUY") { "SELL" } else { "BUY" };
+        let quantity = entry.filled_quantity().max(0) as u32;
+        let product = params.product.as_deref().unwrap_or("CNC");
+
+        let state = match order.exit {
+            BracketExit::Gtt => {
+                let exit = GttExit::new(side, quantity).product(product);
+                let placed = self
+                    .place_gtt_oco(
+                        &params.exchange,
+                        &params.tradingsymbol,
+                        entry.average_price(),
+                        order.target,
+                        order.stoploss,
+                        &exit,
+                    )
+                    .await;
+                match placed {
+                    Ok(trigger_id) => BracketState::Gtt { trigger_id },
+                    Err(error) => BracketState::Unprotected(error),
+                }
+            }
+            BracketExit::LinkedOrders => {
+                let exit = |order_type: &str| {
+                    let mut exit = OrderParams::new(&params.exchange, &params.tradingsymbol, side, quantity)
+                        .variety(&params.variety)
+                        .product(product)
+                        .order_type(order_type);
+                    exit.tag = params.tag.clone();
+                    exit
+                };
+                // The stoploss goes first, a position without it is the worse risk
+                let mut stoploss_exit = exit("SL-M").trigger_price(order.stoploss);
+                stoploss_exit.market_protection = params.market_protection;
+                let stoploss = self.place_order_params(&stoploss_exit).await;
+                let target = match &stoploss {
+                    Ok(_) => self.place_order_params(&exit("LIMIT").price(order.target)).await,
+                    Err(_) => Err(KiteError::Other("stoploss of the bracket not placed".to_string())),
+                };
+                match (stoploss, target) {
+                    (Ok(stoploss_order_id), Ok(target_order_id)) => BracketState::Linked {
+                        target_order_id,
+                        stoploss_order_id,
+                    },
+                    (Ok(stoploss_order_id), Err(error)) => {
+                        if let Err(e) = self.cancel_order(&stoploss_order_id, &params.variety, None).await {
+                            log::warn!("Failed to cancel stoploss {} of a bracket: {}", stoploss_order_id, e);
+                        }
+                        BracketState::Unprotected(error)
+                    }
+                    (Err(error), _) => BracketState::Unprotected(error),
+                }
+            }
+        };
+        if let BracketState::Unprotected(error) = &state {
+            log::warn!("Exits of bracket entry {} not placed: {}", entry.order_id(), error);
+        }
+        Ok(Bracket {
+            entry,
+            variety: params.variety.clone(),
+            state,
+        })
+    }
+
+    /// Advances a bracket with an order update, cancelling the sibling of a
+    /// filled exit
+    ///
+    /// `order` is an update of the user, such as one delivered by the ticker
+    /// or a [`PostbackServer`](crate::postback); updates of other orders are
+    /// ignored. Should the cancellation fail, the bracket stays in
+    /// [`BracketState::Closing`] and the next update or poll tries again.
+    pub async fn update_bracket(&self, bracket: &mut Bracket, order: &Order) -> Result<()> {
+        bracket.on_order(order);
+        self.cancel_sibling(bracket).await
+    }
+
+    /// Advances a bracket by polling its exits
+    ///
+    /// Linked orders are looked up in the order history, a GTT by its
+    /// trigger: a triggered GTT closes the bracket, one removed otherwise
+    /// leaves it [unprotected](BracketState::Unprotected).
+    pub async fn poll_bracket(&self, bracket: &mut Bracket) -> Result<()> {
+        match &bracket.state {
+            BracketState::Gtt { trigger_id } => {
+                let trigger_id = *trigger_id;
+                let data = self.gtt(trigger_id).await?;
+                let status = data.get("data").unwrap_or(&data)["status"].as_str().unwrap_or_default();
+                match status {
+                    "active" => {}
+                    "triggered" => bracket.state = BracketState::Closed { leg: None },
+                    _ => {
+                        let error = KiteError::Other(format!("GTT {} of the bracket is {}", trigger_id, status));
+                        bracket.state = BracketState::Unprotected(error);
+                    }
+                }
+                Ok(())
+            }
+            BracketState::Linked {
+                target_order_id,
+                stoploss_order_id,
+            } => {
+                for order_id in [target_order_id.clone(), stoploss_order_id.clone()] {
+                    if let Some(order) = self.order_history_typed(&order_id).await?.last() {
+                        bracket.on_order(order);
+                    }
+                    if !matches!(bracket.state, BracketState::Linked { .. }) {
+                        break;
+                    }
+                }
+                self.cancel_sibling(bracket).await
+            }
+            _ => self.cancel_sibling(bracket).await,
+        }
+    }
+
+    /// Cancels the remaining exit of a closing bracket
+    async fn cancel_sibling(&self, bracket: &mut Bracket) -> Result<()> {
+        if let BracketState::Closing { leg, cancel_order_id } = &bracket.state {
+            let leg = *leg;
+            self.cancel_order(cancel_order_id, &bracket.variety, None).await?;
+            bracket.state = BracketState::Closed { leg: Some(leg) };
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{mock_client, order_update};
+    use mockito::{Matcher, Server};
+
+    async fn mock_entry(server: &mut Server) {
+        server
+            .mock("POST", "/orders/regular")
+            .match_body(Matcher::UrlEncoded("order_type".into(), "MARKET".into()))
+            .with_body(r#"{"status": "success", "data": {"order_id": "1"}}"#)
+            .create_async()
+            .await;
+        let history = serde_json::json!({"status": "success", "data": [order_update("1", "COMPLETE")]});
+        server
+            .mock("GET", "/orders")
+            .match_query(Matcher::UrlEncoded("order_id".into(), "1".into()))
+            .with_body(history.to_string())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/orders/1/trades")
+            .with_body_from_file("mocks/order_trades.json")
+            .create_async()
+            .await;
+    }
+
+    fn bracket_order() -> BracketOrder {
+        let entry = OrderParams::buy("NSE", "SBIN", 1).product("MIS").order_type("MARKET");
+        BracketOrder::new(entry, "330".parse().unwrap(), "315".parse().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_bracket_linked_orders() {
+        let mut server = Server::new_async().await;
+        let client = mock_client(&server);
+        mock_entry(&mut server).await;
+        for (order_type, order_id) in [("SL-M", "2"), ("LIMIT", "3")] {
+            server
+                .mock("POST", "/orders/regular")
+                .match_body(Matcher::AllOf(vec![
+                    Matcher::UrlEncoded("order_type".into(), order_type.into()),
+                    Matcher::UrlEncoded("transaction_type".into(), "SELL".into()),
+                    Matcher::UrlEncoded("product".into(), "MIS".into()),
+                ]))
+                .with_body(format!(r#"{{"status": "success", "data": {{"order_id": "{}"}}}}"#, order_id))
+                .create_async()
+                .await;
+        }
+        let cancel = server
+            .mock("DELETE", "/orders/regular/2")
+            .with_body(r#"{"status": "success", "data": {"order_id": "2"}}"#)
+            .create_async()
+            .await;
+
+        let order = bracket_order().exit(BracketExit::LinkedOrders);
+        let mut bracket = client.place_bracket(&order).await.unwrap();
+        assert_eq!(bracket.entry.order_id(), "1");
+        assert!(bracket.is_protected(), "{:?}", bracket.state);
+        assert!(matches!(
+            &bracket.state,
+            BracketState::Linked { target_order_id, stoploss_order_id }
+                if target_order_id == "3" && stoploss_order_id == "2"
+        ));
+
+        // Updates of other orders and open exits leave the bracket be
+        client.update_bracket(&mut bracket, &order_update("9", "COMPLETE")).await.unwrap();
+        client.update_bracket(&mut bracket, &order_update("3", "OPEN")).await.unwrap();
+        assert!(bracket.is_protected());
+
+        client.update_bracket(&mut bracket, &order_update("3", "COMPLETE")).await.unwrap();
+        cancel.assert_async().await;
+        assert!(bracket.is_closed());
+        assert!(matches!(bracket.state, BracketState::Closed { leg: Some(ExitLeg::Target) }));
+    }
+
+    #[tokio::test]
+    async fn test_bracket_gtt() {
+        let mut server = Server::new_async().await;
+        let client = mock_client(&server);
+        mock_entry(&mut server).await;
+        let gtt = server
+            .mock("POST", "/gtt/triggers")
+            .match_body(Matcher::UrlEncoded("type".into(), "two-leg".into()))
+            .with_body(r#"{"status": "success", "data": {"trigger_id": 123}}"#)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/gtt/triggers/123")
+            .with_body(r#"{"status": "success", "data": {"id": 123, "status": "triggered"}}"#)
+            .create_async()
+            .await;
+
+        let mut bracket = client.place_bracket(&bracket_order()).await.unwrap();
+        gtt.assert_async().await;
+        assert!(matches!(bracket.state, BracketState::Gtt { trigger_id: 123 }));
+        client.poll_bracket(&mut bracket).await.unwrap();
+        assert!(matches!(bracket.state, BracketState::Closed { leg: None }));
+
+        // A target below the fill of a long entry cannot be protected by a GTT
+        let mut order = bracket_order();
+        order.target = "300".parse().unwrap();
+        let bracket = client.place_bracket(&order).await.unwrap();
+        assert!(matches!(bracket.state, BracketState::Unprotected(_)));
+    }
+}
@@ -0,0 +1,127 @@
+//! Waiting for orders to reach a terminal status
+
+use crate::connect::KiteConnect;
+use crate::error::{KiteError, Result};
+use crate::models::Order;
+use crate::retry::RetryPolicy;
+use crate::rt;
+use std::time::Duration;
+
+/// Delay before the second poll of the order history
+const FIRST_POLL_DELAY: Duration = Duration::from_millis(250);
+
+/// Upper bound for the delay between two polls
+const MAX_POLL_DELAY: Duration = Duration::from_secs(2);
+
+impl KiteConnect {
+    /// Polls the history of an order until it is complete, rejected or
+    /// cancelled, returning its final state
+    ///
+    /// The history is polled right away and then with exponential backoff,
+    /// from 250ms up to every 2s. Transient failures of a poll, such as
+    /// timeouts or gateway errors, are logged and polled again; other errors
+    /// end the wait. A rejected or cancelled order is returned like a complete
+    /// one, so check [`Order::is_complete`]. If the order is still open after
+    /// `timeout`, [`KiteError::OrderTimeout`] carries its last status.
+    ///
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> kiteconnect::error::Result<()> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// let order = client.await_order_completion("151220000000000", Duration::from_secs(30)).await?;
+    /// if order.is_complete() {
+    ///     println!("Filled {} at {}", order.filled_quantity, order.average_price);
+    /// } else {
+    ///     println!("Order {}: {:?}", order.status, order.status_message);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn await_order_completion(&self, order_id: &str, timeout: Duration) -> Result<Order> {
+        let backoff = RetryPolicy::none()
+            .base_delay(FIRST_POLL_DELAY)
+            .max_delay(MAX_POLL_DELAY)
+            .jitter(false);
+        let deadline = rt::now_secs() + timeout.as_secs_f64();
+        let mut status = String::from("UNKNOWN");
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match self.order_history_typed(order_id).await {
+                Ok(history) => {
+                    if let Some(order) = history.into_iter().last() {
+                        if order.is_terminal() {
+                            return Ok(order);
+                        }
+                        status = order.status;
+                    }
+                }
+                Err(e) if e.is_retryable() => {
+                    log::debug!("Polling order {} failed, polling again: {}", order_id, e);
+                }
+                Err(e) => return Err(e),
+            }
+
+            let remaining = deadline - rt::now_secs();
+            if remaining <= 0.0 {
+                return Err(KiteError::OrderTimeout {
+                    order_id: order_id.to_string(),
+                    status,
+                });
+            }
+            rt::sleep(backoff.delay(attempt).min(Duration::from_secs_f64(remaining))).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_client;
+    use mockito::{Matcher, Server};
+    use serde_json::Value as JsonValue;
+
+    #[tokio::test]
+    async fn test_await_order_completion() {
+        let mut server = Server::new_async().await;
+        let client = mock_client(&server);
+        let open = server
+            .mock("GET", "/orders")
+            .match_query(Matcher::UrlEncoded("order_id".into(), "171229000724687".into()))
+            .with_body_from_file("mocks/order_info.json")
+            .expect_at_least(2)
+            .create_async()
+            .await;
+        let err = client
+            .await_order_completion("171229000724687", Duration::from_millis(300))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, KiteError::OrderTimeout { order_id, status } if order_id == "171229000724687" && status == "OPEN"),
+            "{:?}",
+            err
+        );
+        open.assert_async().await;
+        open.remove_async().await;
+
+        let mut history: JsonValue = serde_json::from_str(&std::fs::read_to_string("mocks/order_info.json").unwrap()).unwrap();
+        let last = history["data"].as_array_mut().unwrap().last_mut().unwrap();
+        last["status"] = "COMPLETE".into();
+        last["filled_quantity"] = 1.into();
+        let _complete = server
+            .mock("GET", "/orders")
+            .match_query(Matcher::Any)
+            .with_body(history.to_string())
+            .create_async()
+            .await;
+        let order = client
+            .await_order_completion("171229000724687", Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert!(order.is_complete() && order.is_terminal());
+        assert_eq!(order.filled_quantity, 1);
+    }
+}
@@ -0,0 +1,339 @@
+//! Guarding against placing the same order twice within a time window
+
+use crate::connect::KiteConnect;
+use crate::error::{KiteError, Result};
+use crate::models::Order;
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Seconds an order timestamp may precede the placement it answers, as the
+/// clocks of the OMS and of the client differ
+const CLOCK_SLACK: f64 = 5.0;
+
+/// What identifies an order as a repeat of another
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct DedupeKey {
+    exchange: String,
+    tradingsymbol: String,
+    transaction_type: String,
+    quantity: String,
+    tag: Option<String>,
+}
+
+impl DedupeKey {
+    pub(crate) fn new(
+        exchange: &str,
+        tradingsymbol: &str,
+        transaction_type: &str,
+        quantity: &str,
+        tag: Option<&str>,
+    ) -> Self {
+        DedupeKey {
+            exchange: exchange.to_uppercase(),
+            tradingsymbol: tradingsymbol.to_uppercase(),
+            transaction_type: transaction_type.to_uppercase(),
+            quantity: quantity.trim().to_string(),
+            tag: tag.map(str::to_string),
+        }
+    }
+
+    /// Whether `order` is this order placed at `since` or later
+    fn matches(&self, order: &Order, since: f64) -> bool {
+        order.exchange.eq_ignore_ascii_case(&self.exchange)
+            && order.tradingsymbol.eq_ignore_ascii_case(&self.tradingsymbol)
+            && order.transaction_type.eq_ignore_ascii_case(&self.transaction_type)
+            && order.quantity.to_string() == self.quantity
+            && order.tag == self.tag
+            && order
+                .order_timestamp
+                .is_some_and(|placed| placed.timestamp() as f64 >= since - CLOCK_SLACK)
+    }
+}
+
+/// Outcome of the last placement of an order
+#[derive(Debug)]
+enum Attempt {
+    /// Still being placed
+    InFlight,
+    /// Placed with this response
+    Placed(JsonValue),
+    /// Failed in a way that leaves open whether the order reached the OMS
+    Unknown,
+}
+
+#[derive(Debug)]
+struct Entry {
+    at: f64,
+    attempt: Attempt,
+}
+
+/// Placements of the dedupe window, shared by clones of a client
+#[derive(Debug)]
+pub(crate) struct OrderDedupe {
+    window: Duration,
+    entries: Mutex<HashMap<DedupeKey, Entry>>,
+}
+
+/// What [`OrderDedupe::begin`] knows of earlier placements of an order
+pub(crate) enum Prior<'a> {
+    /// The order was placed within the window, with this response
+    Placed(JsonValue),
+    /// The order may be placed, holding the claim until it settles
+    New(Claim<'a>),
+}
+
+/// The right to place an order, turning into an unknown outcome when dropped
+/// before it settles
+pub(crate) struct Claim<'a> {
+    dedupe: &'a OrderDedupe,
+    key: DedupeKey,
+    /// When an earlier placement with an unknown outcome was made
+    unknown_since: Option<f64>,
+    settled: bool,
+}
+
+impl Claim<'_> {
+    /// Records how the placement ended
+    fn settle(mut self, result: &Result<JsonValue>, now: f64) {
+        self.settled = true;
+        let mut entries = self.dedupe.lock();
+        let attempt = match result {
+            Ok(data) => Attempt::Placed(data.clone()),
+            Err(e) if is_ambiguous(e) => Attempt::Unknown,
+            Err(_) => {
+                entries.remove(&self.key);
+                return;
+            }
+        };
+        let at = self.unknown_since.unwrap_or(now);
+        entries.insert(self.key.clone(), Entry { at, attempt });
+    }
+}
+
+impl Drop for Claim<'_> {
+    fn drop(&mut self) {
+        if self.settled {
+            return;
+        }
+        let mut entries = self.dedupe.lock();
+        if let Some(entry) = entries.get_mut(&self.key) {
+            entry.attempt = Attempt::Unknown;
+        }
+    }
+}
+
+impl OrderDedupe {
+    pub(crate) fn new(window: Duration) -> Self {
+        OrderDedupe {
+            window,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Looks up earlier placements of an order within the window, claiming
+    /// it for placement unless it was placed already
+    ///
+    /// Fails if the same order is being placed concurrently.
+    pub(crate) fn begin(&self, key: DedupeKey, now: f64) -> Result<Prior<'_>> {
+        let mut entries = self.lock();
+        let window = self.window.as_secs_f64();
+        entries.retain(|_, entry| matches!(entry.attempt, Attempt::InFlight) || entry.at + window > now);
+
+        let unknown_since = match entries.get(&key) {
+            Some(Entry { attempt: Attempt::InFlight, .. }) => {
+                return Err(KiteError::Other(format!(
+                    "{} {} {}:{} is already being placed",
+                    key.transaction_type, key.quantity, key.exchange, key.tradingsymbol
+                )));
+            }
+            Some(Entry { attempt: Attempt::Placed(data), .. }) => return Ok(Prior::Placed(data.clone())),
+            Some(Entry { at, attempt: Attempt::Unknown }) => Some(*at),
+            None => None,
+        };
+        entries.insert(
+            key.clone(),
+            Entry {
+                at: unknown_since.unwrap_or(now),
+                attempt: Attempt::InFlight,
+            },
+        );
+        Ok(Prior::New(Claim {
+            dedupe: self,
+            key,
+            unknown_since,
+            settled: false,
+        }))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<DedupeKey, Entry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Whether a failed placement may still have reached the OMS
+fn is_ambiguous(e: &KiteError) -> bool {
+    match e {
+        KiteError::Http(_) | KiteError::Network { .. } => true,
+        KiteError::RateLimited { .. } => false,
+        _ => e.status().is_some_and(|status| status >= 500),
+    }
+}
+
+impl KiteConnect {
+    /// Places an order with `place` unless it repeats one placed within the
+    /// dedupe window
+    ///
+    /// A repeat of a placement that succeeded returns its response without a
+    /// request. A repeat of one whose outcome is unknown, e.g. after a
+    /// timeout, returns the order that placement left in the order book, and
+    /// is placed only if there is none.
+    pub(crate) async fn place_deduped<F>(&self, dedupe: &OrderDedupe, key: DedupeKey, place: F) -> Result<JsonValue>
+    where
+        F: Future<Output = Result<JsonValue>>,
+    {
+        let claim = match dedupe.begin(key, crate::rt::now_secs())? {
+            Prior::Placed(data) => {
+                log::warn!("Not placing a repeat of {:?} within the dedupe window", data);
+                return Ok(data);
+            }
+            Prior::New(claim) => claim,
+        };
+
+        if let Some(since) = claim.unknown_since {
+            // A failed lookup drops the claim, leaving the outcome unknown
+            let orders = self.orders_typed().await?;
+            if let Some(order) = orders.iter().rev().find(|order| claim.key.matches(order, since)) {
+                log::warn!("Order {} found placed by an earlier attempt, not placing it again", order.order_id);
+                let data = json!({ "order_id": order.order_id });
+                let data = if self.unwrap_envelope() {
+                    data
+                } else {
+                    json!({ "status": "success", "data": data })
+                };
+                let result = Ok(data);
+                claim.settle(&result, crate::rt::now_secs());
+                return result;
+            }
+        }
+
+        let result = place.await;
+        claim.settle(&result, crate::rt::now_secs());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ist;
+    use mockito::{Matcher, Server};
+
+    fn key(quantity: &str) -> DedupeKey {
+        DedupeKey::new("NSE", "INFY", "BUY", quantity, Some("rebalance"))
+    }
+
+    #[test]
+    fn test_dedupe_window() {
+        let dedupe = OrderDedupe::new(Duration::from_secs(10));
+        let Ok(Prior::New(claim)) = dedupe.begin(key("1"), 100.0) else { panic!("not claimed") };
+        assert!(dedupe.begin(key("1"), 100.5).is_err());
+        assert!(matches!(dedupe.begin(key("2"), 100.5), Ok(Prior::New(_))));
+        claim.settle(&Ok(json!({"order_id": "1"})), 101.0);
+
+        assert!(matches!(dedupe.begin(key("1"), 110.5), Ok(Prior::Placed(data)) if data["order_id"] == "1"));
+        assert!(matches!(dedupe.begin(key("1"), 111.0), Ok(Prior::New(_))));
+
+        // A dropped placement has an unknown outcome from when it started
+        let Ok(Prior::New(claim)) = dedupe.begin(key("3"), 200.0) else { panic!("not claimed") };
+        drop(claim);
+        let Ok(Prior::New(claim)) = dedupe.begin(key("3"), 205.0) else { panic!("not claimed") };
+        assert_eq!(claim.unknown_since, Some(200.0));
+
+        // Definite failures are forgotten
+        claim.settle(&Err(KiteError::Other("rejected".to_string())), 206.0);
+        let Ok(Prior::New(claim)) = dedupe.begin(key("3"), 207.0) else { panic!("not claimed") };
+        assert_eq!(claim.unknown_since, None);
+    }
+
+    #[tokio::test]
+    async fn test_order_dedupe() {
+        let mut server = Server::new_async().await;
+        let client = KiteConnect::builder("API_KEY")
+            .access_token("ACCESS_TOKEN")
+            .base_url(&server.url())
+            .dedupe_window(Duration::from_secs(60))
+            .build()
+            .unwrap();
+        let placed = server
+            .mock("POST", "/orders/regular")
+            .match_body(Matcher::UrlEncoded("quantity".into(), "1".into()))
+            .with_body(r#"{"status": "success", "data": {"order_id": "1"}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let timed_out = server
+            .mock("POST", "/orders/regular")
+            .match_body(Matcher::UrlEncoded("quantity".into(), "2".into()))
+            .with_status(504)
+            .with_header("content-type", "text/html")
+            .with_body("<html><title>504 Gateway Time-out</title></html>")
+            .expect(1)
+            .create_async()
+            .await;
+        let rejected = server
+            .mock("POST", "/orders/regular")
+            .match_body(Matcher::UrlEncoded("quantity".into(), "3".into()))
+            .with_status(400)
+            .with_body(r#"{"status": "error", "message": "Invalid price", "error_type": "InputException"}"#)
+            .expect(2)
+            .create_async()
+            .await;
+        let mut orders: JsonValue =
+            serde_json::from_str(&std::fs::read_to_string("mocks/orders.json").unwrap()).unwrap();
+        let order = &mut orders["data"][0];
+        order["order_id"] = json!("2");
+        order["order_timestamp"] = json!(ist::format_timestamp(&ist::now()));
+        order["exchange"] = json!("NSE");
+        order["tradingsymbol"] = json!("INFY");
+        order["transaction_type"] = json!("BUY");
+        order["quantity"] = json!(2);
+        order["tag"] = json!("rebalance");
+        server
+            .mock("GET", "/orders")
+            .with_body(orders.to_string())
+            .create_async()
+            .await;
+
+        let place = |quantity: &str| {
+            let quantity = quantity.to_string();
+            let client = client.clone();
+            async move {
+                client
+                    .place_order(
+                        "regular", "NSE", "INFY", "BUY", &quantity, Some("CNC"), Some("MARKET"), None, None, None,
+                        None, None, None, None, Some("rebalance"),
+                    )
+                    .await
+            }
+        };
+
+        assert_eq!(place("1").await.unwrap()["data"]["order_id"], "1");
+        assert_eq!(place("1").await.unwrap()["data"]["order_id"], "1");
+        placed.assert_async().await;
+
+        assert!(place("2").await.is_err());
+        assert_eq!(place("2").await.unwrap()["data"]["order_id"], "2");
+        timed_out.assert_async().await;
+
+        assert!(place("3").await.is_err());
+        assert!(place("3").await.is_err());
+        rejected.assert_async().await;
+    }
+}
@@ -0,0 +1,213 @@
+//! Placing an order and waiting for it to fill
+
+use super::OrderParams;
+use crate::connect::KiteConnect;
+use crate::error::{KiteError, Result};
+use crate::models::{Order, Price, Trade};
+use crate::rt;
+use futures::future::{self, Either};
+use futures::{Stream, StreamExt};
+use std::time::Duration;
+
+/// A completely filled order and its trades
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderExecution {
+    /// Final state of the order
+    pub order: Order,
+    /// Trades that filled the order
+    pub trades: Vec<Trade>,
+}
+
+impl OrderExecution {
+    /// Returns the ID of the order
+    pub fn order_id(&self) -> &str {
+        &self.order.order_id
+    }
+
+    /// Returns the filled quantity
+    pub fn filled_quantity(&self) -> i64 {
+        self.order.filled_quantity
+    }
+
+    /// Returns the average price of the fills
+    pub fn average_price(&self) -> Price {
+        self.order.average_price
+    }
+}
+
+impl KiteConnect {
+    /// Places an order and waits until it is filled, returning the order and
+    /// its trades
+    ///
+    /// The order is polled like in [`await_order_completion`](Self::await_order_completion).
+    /// A rejected order fails with [`KiteError::OrderRejected`] carrying the
+    /// reason, a cancelled one with [`KiteError::OrderCancelled`], and an order
+    /// still open after `timeout` with [`KiteError::OrderTimeout`]; the order
+    /// stays open then.
+    ///
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    /// use kiteconnect::orders::OrderParams;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> kiteconnect::error::Result<()> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// let params = OrderParams::buy("NSE", "INFY", 10).product("CNC").order_type("MARKET");
+    /// let execution = client.place_order_and_wait(&params, Duration::from_secs(10)).await?;
+    /// println!("Bought {} at {}", execution.filled_quantity(), execution.average_price());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn place_order_and_wait(&self, params: &OrderParams, timeout: Duration) -> Result<OrderExecution> {
+        self.place_order_and_wait_with(params, timeout, futures::stream::empty()).await
+    }
+
+    /// Places an order and waits until it is filled, watching `updates` for
+    /// its final state instead of polling
+    ///
+    /// `updates` are order updates of the user, such as the orders delivered
+    /// by a [`PostbackServer`](crate::postback) or the order updates of the
+    /// ticker. Updates of other orders are skipped. Should `updates` end
+    /// before the order does, its history is polled for the rest of `timeout`.
+    /// Fails like [`place_order_and_wait`](Self::place_order_and_wait).
+    ///
+    /// ```rust,no_run
+    /// use futures::StreamExt;
+    /// use kiteconnect::connect::KiteConnect;
+    /// use kiteconnect::orders::OrderParams;
+    /// use kiteconnect::ticker::{KiteTicker, TickerEvent};
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> kiteconnect::error::Result<()> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// let mut ticker = KiteTicker::connect("api_key", "access_token").await?;
+    /// let updates = ticker.stream().filter_map(|event| async move {
+    ///     match event {
+    ///         TickerEvent::OrderUpdate(order) => Some(*order),
+    ///         _ => None,
+    ///     }
+    /// });
+    ///
+    /// let params = OrderParams::sell("NSE", "INFY", 10).product("CNC").order_type("MARKET");
+    /// let execution = client.place_order_and_wait_with(&params, Duration::from_secs(10), updates).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn place_order_and_wait_with<S>(
+        &self,
+        params: &OrderParams,
+        timeout: Duration,
+        updates: S,
+    ) -> Result<OrderExecution>
+    where
+        S: Stream<Item = Order>,
+    {
+        let order_id = self.place_order_params(params).await?;
+        let deadline = rt::now_secs() + timeout.as_secs_f64();
+
+        let update = {
+            let final_update = std::pin::pin!(final_update(&order_id, updates));
+            let expiry = std::pin::pin!(rt::sleep(timeout));
+            match future::select(final_update, expiry).await {
+                Either::Left((update, _)) => update,
+                Either::Right(_) => None,
+            }
+        };
+        let order = match update {
+            Some(order) => order,
+            // The updates ended or timed out, the history tells the final word
+            None => {
+                let remaining = (deadline - rt::now_secs()).max(0.0);
+                self.await_order_completion(&order_id, Duration::from_secs_f64(remaining))
+                    .await?
+            }
+        };
+
+        if order.is_rejected() {
+            return Err(KiteError::OrderRejected {
+                order_id,
+                message: order.status_message.unwrap_or_default(),
+            });
+        }
+        if order.is_cancelled() {
+            return Err(KiteError::OrderCancelled {
+                order_id,
+                filled_quantity: order.filled_quantity,
+            });
+        }
+        let trades = self.order_trades_typed(&order_id).await?;
+        Ok(OrderExecution { order, trades })
+    }
+}
+
+/// Returns the first update of `order_id` in a terminal status, or `None`
+/// if `updates` end before
+async fn final_update<S: Stream<Item = Order>>(order_id: &str, updates: S) -> Option<Order> {
+    let mut updates = std::pin::pin!(updates);
+    while let Some(order) = updates.next().await {
+        if order.order_id == order_id && order.is_terminal() {
+            return Some(order);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{mock_client, order_update};
+    use mockito::{Matcher, Server};
+
+    #[tokio::test]
+    async fn test_place_order_and_wait() {
+        let mut server = Server::new_async().await;
+        let client = mock_client(&server);
+        let _place = server
+            .mock("POST", "/orders/regular")
+            .with_body(r#"{"status": "success", "data": {"order_id": "171229000724687"}}"#)
+            .create_async()
+            .await;
+        let _trades = server
+            .mock("GET", "/orders/171229000724687/trades")
+            .with_body_from_file("mocks/order_trades.json")
+            .create_async()
+            .await;
+        let params = OrderParams::buy("NSE", "INFY", 1).order_type("MARKET");
+
+        // Updates of other orders are skipped
+        let updates =
+            futures::stream::iter([order_update("1", "REJECTED"), order_update("171229000724687", "COMPLETE")]);
+        let execution = client
+            .place_order_and_wait_with(&params, Duration::from_secs(5), updates)
+            .await
+            .unwrap();
+        assert_eq!(execution.order_id(), "171229000724687");
+        assert_eq!(execution.filled_quantity(), 1);
+        assert_eq!(execution.average_price(), "320.7".parse::<Price>().unwrap());
+        assert_eq!(execution.trades.len(), 1);
+
+        let mut rejected = order_update("171229000724687", "REJECTED");
+        rejected.status_message = Some("Insufficient funds".to_string());
+        let err = client
+            .place_order_and_wait_with(&params, Duration::from_secs(5), futures::stream::iter([rejected]))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "order 171229000724687 rejected: Insufficient funds");
+
+        // Without updates the history is polled
+        let mut history: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string("mocks/order_info.json").unwrap()).unwrap();
+        let last = history["data"].as_array_mut().unwrap().last_mut().unwrap();
+        last["status"] = "CANCELLED".into();
+        let _history = server
+            .mock("GET", "/orders")
+            .match_query(Matcher::UrlEncoded("order_id".into(), "171229000724687".into()))
+            .with_body(history.to_string())
+            .create_async()
+            .await;
+        let err = client.place_order_and_wait(&params, Duration::from_secs(5)).await.unwrap_err();
+        assert!(matches!(err, KiteError::OrderCancelled { filled_quantity: 0, .. }), "{:?}", err);
+    }
+}
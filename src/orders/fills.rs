@@ -0,0 +1,256 @@
+//! Following the fills of an order as they come in
+
+use crate::connect::KiteConnect;
+use crate::error::Result;
+use crate::models::{Order, Price, Trade};
+use std::collections::HashSet;
+
+/// Filled quantity and average fill price of an order, kept up to date from
+/// its order updates or trades
+///
+/// Order updates, e.g. from the ticker or postbacks, carry the filled
+/// quantity and average price so far; trades carry one fill each and are
+/// counted once however often they are seen. Both can be fed to the same
+/// tracker, which reports whichever knows of more fills.
+///
+/// ```rust
+/// use kiteconnect::orders::FillTracker;
+///
+/// let tracker = FillTracker::new("151220000000000", 100);
+/// assert_eq!(tracker.remaining_quantity(), 100);
+/// assert!(!tracker.is_filled());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct FillTracker {
+    order_id: String,
+    quantity: i64,
+    status: Option<String>,
+    /// Fills reported by the last order update
+    order_filled: i64,
+    order_average: Price,
+    /// Fills summed from the trades seen
+    trade_ids: HashSet<String>,
+    trade_filled: i64,
+    trade_value: Price,
+}
+
+impl FillTracker {
+    /// Creates a tracker for order `order_id` of `quantity`
+    pub fn new(order_id: &str, quantity: u32) -> Self {
+        FillTracker {
+            order_id: order_id.to_string(),
+            quantity: i64::from(quantity),
+            status: None,
+            order_filled: 0,
+            order_average: Price::default(),
+            trade_ids: HashSet::new(),
+            trade_filled: 0,
+            trade_value: Price::default(),
+        }
+    }
+
+    /// Returns the ID of the tracked order
+    pub fn order_id(&self) -> &str {
+        &self.order_id
+    }
+
+    /// Returns the quantity of the order, as last modified
+    pub fn quantity(&self) -> i64 {
+        self.quantity
+    }
+
+    /// Returns the status of the last order update, if any
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+
+    /// Returns the quantity filled so far
+    pub fn filled_quantity(&self) -> i64 {
+        self.order_filled.max(self.trade_filled)
+    }
+
+    /// Returns the quantity still to be filled
+    pub fn remaining_quantity(&self) -> i64 {
+        (self.quantity - self.filled_quantity()).max(0)
+    }
+
+    /// Returns the volume weighted average price of the fills so far, zero
+    /// before the first fill
+    pub fn average_price(&self) -> Price {
+        if self.trade_filled > self.order_filled {
+            self.trade_value / quantity_price(self.trade_filled)
+        } else {
+            self.order_average
+        }
+    }
+
+    /// Returns `true` once the whole quantity is filled
+    pub fn is_filled(&self) -> bool {
+        self.quantity > 0 && self.remaining_quantity() == 0
+    }
+
+    /// Takes in an order update, returning `true` if it changed the fills,
+    /// quantity or status
+    ///
+    /// Updates of other orders and updates older than the fills already known
+    /// are skipped.
+    pub fn on_order(&mut self, order: &Order) -> bool {
+        if order.order_id != self.order_id || order.filled_quantity < self.order_filled {
+            return false;
+        }
+        let changed = order.filled_quantity != self.order_filled
+            || order.quantity != self.quantity
+            || self.status.as_deref() != Some(order.status.as_str());
+        self.order_filled = order.filled_quantity;
+        self.order_average = order.average_price;
+        self.quantity = order.quantity;
+        self.status = Some(order.status.clone());
+        changed
+    }
+
+    /// Takes in a trade, returning `true` if it is a fill of the order not
+    /// seen before
+    pub fn on_trade(&mut self, trade: &Trade) -> bool {
+        if trade.order_id != self.order_id || !self.trade_ids.insert(trade.trade_id.clone()) {
+            return false;
+        }
+        self.trade_filled += trade.quantity;
+        self.trade_value += trade.average_price * quantity_price(trade.quantity);
+        true
+    }
+}
+
+impl KiteConnect {
+    /// Fetches the trades of the order of `tracker` and takes them in,
+    /// returning `true` if there were new fills
+    ///
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    /// use kiteconnect::orders::FillTracker;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> kiteconnect::error::Result<()> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// let mut tracker = FillTracker::new("151220000000000", 100);
+    /// while !tracker.is_filled() {
+    ///     if client.poll_fills(&mut tracker).await? {
+    ///         println!("{} filled at {}", tracker.filled_quantity(), tracker.average_price());
+    ///     }
+    ///     tokio::time::sleep(Duration::from_secs(1)).await;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn poll_fills(&self, tracker: &mut FillTracker) -> Result<bool> {
+        let trades = self.order_trades_typed(&tracker.order_id).await?;
+        let mut changed = false;
+        for trade in &trades {
+            changed |= tracker.on_trade(trade);
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(not(feature = "rust_decimal"))]
+fn quantity_price(quantity: i64) -> Price {
+    quantity as Price
+}
+
+#[cfg(feature = "rust_decimal")]
+fn quantity_price(quantity: i64) -> Price {
+    Price::from(quantity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Server;
+    use serde_json::{json, Value as JsonValue};
+
+    fn price(value: &str) -> Price {
+        value.parse().unwrap()
+    }
+
+    fn fixture(path: &str) -> JsonValue {
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn trade(trade_id: &str, quantity: i64, average_price: &str) -> JsonValue {
+        let mut trade = fixture("mocks/trades.json")["data"][0].clone();
+        trade["order_id"] = json!("1");
+        trade["trade_id"] = json!(trade_id);
+        trade["quantity"] = json!(quantity);
+        trade["average_price"] = json!(average_price.parse::<f64>().unwrap());
+        trade
+    }
+
+    #[test]
+    fn test_fill_tracker() {
+        let mut tracker = FillTracker::new("1", 100);
+        let mut order: Order = serde_json::from_value(fixture("mocks/orders.json")["data"][0].clone()).unwrap();
+        order.order_id = "1".to_string();
+        order.quantity = 100;
+        order.filled_quantity = 40;
+        order.average_price = price("10");
+        order.status = "OPEN".to_string();
+        assert!(tracker.on_order(&order));
+        assert!(!tracker.on_order(&order));
+        assert_eq!(tracker.filled_quantity(), 40);
+        assert_eq!(tracker.remaining_quantity(), 60);
+        assert_eq!(tracker.average_price(), price("10"));
+        assert_eq!(tracker.status(), Some("OPEN"));
+
+        // An update delivered late is ignored
+        let stale = Order { filled_quantity: 20, ..order.clone() };
+        assert!(!tracker.on_order(&stale));
+        assert_eq!(tracker.filled_quantity(), 40);
+
+        // Trades take over once they know of more fills
+        let trades: Vec<Trade> = serde_json::from_value(json!([
+            trade("a", 40, "10"),
+            trade("b", 20, "13"),
+            trade("b", 20, "13"),
+        ]))
+        .unwrap();
+        assert!(tracker.on_trade(&trades[0]));
+        assert!(tracker.on_trade(&trades[1]));
+        assert!(!tracker.on_trade(&trades[2]));
+        assert_eq!(tracker.filled_quantity(), 60);
+        assert_eq!(tracker.average_price(), price("11"));
+
+        let done = Order {
+            filled_quantity: 100,
+            average_price: price("12"),
+            status: "COMPLETE".to_string(),
+            ..order
+        };
+        assert!(tracker.on_order(&done));
+        assert!(tracker.is_filled());
+        assert_eq!(tracker.average_price(), price("12"));
+    }
+
+    #[tokio::test]
+    async fn test_poll_fills() {
+        let mut server = Server::new_async().await;
+        let client = KiteConnect::builder("API_KEY")
+            .access_token("ACCESS_TOKEN")
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+        let body = json!({"status": "success", "data": [trade("a", 30, "100"), trade("b", 10, "104")]});
+        server
+            .mock("GET", "/orders/1/trades")
+            .with_body(body.to_string())
+            .create_async()
+            .await;
+
+        let mut tracker = FillTracker::new("1", 50);
+        assert!(client.poll_fills(&mut tracker).await.unwrap());
+        assert!(!client.poll_fills(&mut tracker).await.unwrap());
+        assert_eq!(tracker.filled_quantity(), 40);
+        assert_eq!(tracker.remaining_quantity(), 10);
+        assert_eq!(tracker.average_price(), price("101"));
+        assert!(!tracker.is_filled());
+    }
+}
@@ -0,0 +1,430 @@
+//! Dispatching order mutations within the order limits of Kite
+
+use super::params::order_id;
+use super::OrderParams;
+use crate::connect::KiteConnect;
+use crate::error::{KiteError, Result};
+use crate::models::{ist, Price};
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeSet, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How often a mutation queued behind another checks whether its turn came
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Orders an [`OrderGateway`] lets through, zero lifting a limit
+///
+/// Kite blocks an API key for the day once it places, modifies or cancels
+/// more orders than these.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrderLimits {
+    /// Orders per second
+    pub per_second: u32,
+    /// Orders per minute
+    pub per_minute: u32,
+    /// Orders per trading day, counted in IST
+    pub per_day: u32,
+}
+
+impl Default for OrderLimits {
+    /// The limits documented by Kite
+    fn default() -> Self {
+        OrderLimits {
+            per_second: 10,
+            per_minute: 200,
+            per_day: 3000,
+        }
+    }
+}
+
+/// Priority of a queued order mutation, the first variants sent first
+///
+/// Cancels go ahead of modifications and modifications ahead of new orders, so
+/// a busy queue never holds back taking risk off.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// An order cancellation
+    Cancel,
+    /// An order modification
+    Modify,
+    /// A new order
+    New,
+}
+
+/// Counters of an [`OrderGateway`], returned by [`OrderGateway::metrics`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GatewayMetrics {
+    /// New orders sent
+    pub orders_sent: u64,
+    /// Modifications sent
+    pub modifies_sent: u64,
+    /// Cancellations sent
+    pub cancels_sent: u64,
+    /// Mutations sent in the current IST day
+    pub sent_today: u32,
+    /// Mutations waiting for their turn
+    pub queued: usize,
+    /// Mutations that had to wait for a limit or for mutations ahead of them
+    pub throttled: u64,
+    /// Total time mutations waited
+    pub total_wait: Duration,
+    /// Longest time a mutation waited
+    pub max_wait: Duration,
+    /// Mutations refused as the daily limit was reached
+    pub rejected: u64,
+}
+
+/// Queue of an [`OrderGateway`], shared by its clones
+#[derive(Debug)]
+struct GatewayState {
+    limits: OrderLimits,
+    /// Send times of the mutations of the last minute
+    sent: VecDeque<f64>,
+    /// IST day of `metrics.sent_today`
+    day: i64,
+    waiting: BTreeSet<(Priority, u64)>,
+    next_ticket: u64,
+    metrics: GatewayMetrics,
+}
+
+impl GatewayState {
+    fn new(limits: OrderLimits) -> Self {
+        GatewayState {
+            limits,
+            sent: VecDeque::new(),
+            day: 0,
+            waiting: BTreeSet::new(),
+            next_ticket: 0,
+            metrics: GatewayMetrics::default(),
+        }
+    }
+
+    /// Queues a mutation, returning its place in the queue
+    fn enqueue(&mut self, priority: Priority) -> (Priority, u64) {
+        let key = (priority, self.next_ticket);
+        self.next_ticket += 1;
+        self.waiting.insert(key);
+        key
+    }
+
+    fn dequeue(&mut self, key: (Priority, u64)) {
+        self.waiting.remove(&key);
+    }
+
+    /// Sends the queued mutation if its turn came, otherwise returns how long
+    /// to wait before asking again
+    fn poll(&mut self, key: (Priority, u64), now: f64) -> Result<Option<Duration>> {
+        let day = ((now + ist::OFFSET_SECS as f64) / 86400.0).floor() as i64;
+        if day != self.day {
+            self.day = day;
+            self.metrics.sent_today = 0;
+        }
+        if self.limits.per_day > 0 && self.metrics.sent_today >= self.limits.per_day {
+            self.dequeue(key);
+            self.metrics.rejected += 1;
+            return Err(KiteError::OrderLimitReached {
+                limit: self.limits.per_day,
+            });
+        }
+
+        while self.sent.front().is_some_and(|&sent| sent <= now - 60.0) {
+            self.sent.pop_front();
+        }
+        let wait = self.window_wait(now);
+        if self.waiting.first() != Some(&key) {
+            return Ok(Some(wait.max(POLL_INTERVAL)));
+        }
+        if !wait.is_zero() {
+            return Ok(Some(wait));
+        }
+
+        self.dequeue(key);
+        self.sent.push_back(now);
+        self.metrics.sent_today += 1;
+        match key.0 {
+            Priority::Cancel => self.metrics.cancels_sent += 1,
+            Priority::Modify => self.metrics.modifies_sent += 1,
+            Priority::New => self.metrics.orders_sent += 1,
+        }
+        Ok(None)
+    }
+
+    /// Returns how long until the per second and per minute limits allow
+    /// another mutation
+    fn window_wait(&self, now: f64) -> Duration {
+        let len = self.sent.len();
+        let mut until = now;
+        let per_second = self.limits.per_second as usize;
+        if per_second > 0 && len >= per_second {
+            until = until.max(self.sent[len - per_second] + 1.0);
+        }
+        let per_minute = self.limits.per_minute as usize;
+        if per_minute > 0 && len >= per_minute {
+            until = until.max(self.sent[len - per_minute] + 60.0);
+        }
+        Duration::from_secs_f64(until - now)
+    }
+
+    fn record_wait(&mut self, wait: Duration) {
+        if wait >= POLL_INTERVAL {
+            self.metrics.throttled += 1;
+        }
+        self.metrics.total_wait += wait;
+        self.metrics.max_wait = self.metrics.max_wait.max(wait);
+    }
+}
+
+/// Takes a mutation out of the queue if its future is dropped while waiting
+struct QueueGuard<'a> {
+    state: &'a Mutex<GatewayState>,
+    key: (Priority, u64),
+}
+
+impl Drop for QueueGuard<'_> {
+    fn drop(&mut self) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).dequeue(self.key);
+    }
+}
+
+/// Sends order placements, modifications and cancellations through a queue
+/// that keeps within the order limits of Kite
+///
+/// Mutations over the per second or per minute limit wait for their turn,
+/// cancels ahead of modifications and modifications ahead of new orders, and
+/// ones past the daily limit fail with [`KiteError::OrderLimitReached`]
+/// instead of getting the API key blocked. Clones share the queue, so every
+/// task of a strategy should send its orders through clones of one gateway.
+///
+/// A placement split by [`KiteConnect::set_auto_slice`] counts as one
+/// mutation, so orders large enough to be sliced are best placed through the
+/// gateway slice by slice.
+///
+/// ```rust,no_run
+/// use kiteconnect::connect::KiteConnect;
+/// use kiteconnect::orders::{OrderGateway, OrderLimits, OrderParams};
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let client = KiteConnect::new("api_key", "access_token");
+/// let gateway = OrderGateway::new(client, OrderLimits::default());
+/// let params = OrderParams::buy("NSE", "INFY", 10).product("CNC").order_type("MARKET");
+/// let order_id = gateway.place(&params).await?;
+/// gateway.cancel(&order_id, "regular").await?;
+/// println!("{:?}", gateway.metrics());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct OrderGateway {
+    client: KiteConnect,
+    state: Arc<Mutex<GatewayState>>,
+}
+
+impl OrderGateway {
+    /// Creates a gateway sending the mutations of `client` within `limits`
+    pub fn new(client: KiteConnect, limits: OrderLimits) -> Self {
+        OrderGateway {
+            client,
+            state: Arc::new(Mutex::new(GatewayState::new(limits))),
+        }
+    }
+
+    /// Returns the client mutations are sent with
+    pub fn client(&self) -> &KiteConnect {
+        &self.client
+    }
+
+    /// Returns the limits enforced by this gateway
+    pub fn limits(&self) -> OrderLimits {
+        self.lock().limits
+    }
+
+    /// Returns a snapshot of the counters of this gateway
+    pub fn metrics(&self) -> GatewayMetrics {
+        let state = self.lock();
+        GatewayMetrics {
+            queued: state.waiting.len(),
+            ..state.metrics.clone()
+        }
+    }
+
+    /// Places an order once its turn comes, returning its order ID
+    pub async fn place(&self, params: &OrderParams) -> Result<String> {
+        self.submit(Priority::New, self.client.place_order_params(params)).await
+    }
+
+    /// Modifies the quantity, price or trigger price of an order once its
+    /// turn comes
+    ///
+    /// Other modifications can be sent with [`OrderGateway::submit`].
+    pub async fn modify(
+        &self,
+        order_id: &str,
+        variety: &str,
+        quantity: Option<u32>,
+        price: Option<Price>,
+        trigger_price: Option<Price>,
+    ) -> Result<String> {
+        let quantity = quantity.map(|quantity| quantity.to_string());
+        let price = price.map(|price| price.to_string());
+        let trigger_price = trigger_price.map(|price| price.to_string());
+        let request = self.client.modify_order(
+            order_id,
+            variety,
+            quantity.as_deref(),
+            price.as_deref(),
+            None,
+            None,
+            None,
+            trigger_price.as_deref(),
+            None,
+        );
+        let data = self.submit(Priority::Modify, request).await?;
+        Ok(order_id_or(&data, order_id))
+    }
+
+    /// Cancels an order once its turn comes
+    pub async fn cancel(&self, order_id: &str, variety: &str) -> Result<String> {
+        let data = self.submit(Priority::Cancel, self.client.cancel_order(order_id, variety, None)).await?;
+        Ok(order_id_or(&data, order_id))
+    }
+
+    /// Sends any order mutation once its turn comes at `priority`
+    ///
+    /// `request` is not polled before its turn, so it can be built from any
+    /// method of the client.
+    pub async fn submit<T, F>(&self, priority: Priority, request: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        self.acquire(priority).await?;
+        request.await
+    }
+
+    /// Waits until a mutation at `priority` may be sent
+    async fn acquire(&self, priority: Priority) -> Result<()> {
+        let started = crate::rt::now_secs();
+        let guard = QueueGuard {
+            state: &self.state,
+            key: self.lock().enqueue(priority),
+        };
+        loop {
+            let now = crate::rt::now_secs();
+            let poll = self.lock().poll(guard.key, now);
+            match poll? {
+                None => {
+                    let wait = Duration::from_secs_f64((now - started).max(0.0));
+                    self.lock().record_wait(wait);
+                    return Ok(());
+                }
+                Some(wait) => {
+                    log::debug!("Holding back {:?} order mutation for {:?}", priority, wait);
+                    crate::rt::sleep(wait).await;
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, GatewayState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Reads the order ID of a modify or cancel response, which may lack one
+fn order_id_or(data: &JsonValue, fallback: &str) -> String {
+    order_id(data).unwrap_or_else(|_| fallback.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Server;
+
+    #[test]
+    fn test_gateway_state() {
+        let mut state = GatewayState::new(OrderLimits {
+            per_second: 2,
+            per_minute: 3,
+            per_day: 4,
+        });
+        let first = state.enqueue(Priority::New);
+        let second = state.enqueue(Priority::New);
+        let cancel = state.enqueue(Priority::Cancel);
+
+        // The cancel goes first although queued last
+        assert_eq!(state.poll(first, 100.0).unwrap(), Some(POLL_INTERVAL));
+        assert_eq!(state.poll(cancel, 100.0).unwrap(), None);
+        assert_eq!(state.poll(second, 100.0).unwrap(), Some(POLL_INTERVAL));
+        assert_eq!(state.poll(first, 100.0).unwrap(), None);
+
+        // Two a second, then three a minute
+        assert_eq!(state.poll(second, 100.0).unwrap(), Some(Duration::from_secs(1)));
+        assert_eq!(state.poll(second, 101.0).unwrap(), None);
+        let third = state.enqueue(Priority::Modify);
+        assert_eq!(state.poll(third, 101.0).unwrap(), Some(Duration::from_secs(59)));
+        assert_eq!(state.poll(third, 160.0).unwrap(), None);
+
+        // Four a day, counted again the next day
+        let fourth = state.enqueue(Priority::New);
+        assert!(matches!(state.poll(fourth, 170.0), Err(KiteError::OrderLimitReached { limit: 4 })));
+        assert!(state.waiting.is_empty());
+        let fifth = state.enqueue(Priority::New);
+        assert_eq!(state.poll(fifth, 170.0 + 86400.0).unwrap(), None);
+
+        assert_eq!(state.metrics.orders_sent, 3);
+        assert_eq!(state.metrics.modifies_sent, 1);
+        assert_eq!(state.metrics.cancels_sent, 1);
+        assert_eq!(state.metrics.sent_today, 1);
+        assert_eq!(state.metrics.rejected, 1);
+    }
+
+    #[tokio::test]
+    async fn test_order_gateway() {
+        let mut server = Server::new_async().await;
+        let client = KiteConnect::builder("API_KEY")
+            .access_token("ACCESS_TOKEN")
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+        server
+            .mock("POST", "/orders/regular")
+            .with_body(r#"{"status": "success", "data": {"order_id": "151220000000000"}}"#)
+            .create_async()
+            .await;
+        server
+            .mock("PUT", "/orders/regular/151220000000000")
+            .with_body(r#"{"status": "success", "data": {"order_id": "151220000000000"}}"#)
+            .create_async()
+            .await;
+        server
+            .mock("DELETE", "/orders/regular/151220000000000")
+            .with_body(r#"{"status": "success", "data": {"order_id": "151220000000000"}}"#)
+            .create_async()
+            .await;
+
+        let gateway = OrderGateway::new(
+            client,
+            OrderLimits {
+                per_day: 3,
+                ..OrderLimits::default()
+            },
+        );
+        let params = OrderParams::buy("NSE", "INFY", 1).product("CNC").order_type("MARKET");
+        let order_id = gateway.place(&params).await.unwrap();
+        assert_eq!(order_id, "151220000000000");
+        let price = "1500".parse().ok();
+        assert_eq!(gateway.modify(&order_id, "regular", None, price, None).await.unwrap(), order_id);
+        assert_eq!(gateway.cancel(&order_id, "regular").await.unwrap(), order_id);
+        assert!(matches!(gateway.place(&params).await, Err(KiteError::OrderLimitReached { limit: 3 })));
+
+        let metrics = gateway.metrics();
+        assert_eq!(metrics.orders_sent, 1);
+        assert_eq!(metrics.modifies_sent, 1);
+        assert_eq!(metrics.cancels_sent, 1);
+        assert_eq!(metrics.sent_today, 3);
+        assert_eq!(metrics.queued, 0);
+        assert_eq!(metrics.rejected, 1);
+    }
+}
@@ -0,0 +1,241 @@
+//! Two-leg GTT triggers exiting a position at a target or a stoploss
+
+use super::params::price_json;
+use crate::connect::KiteConnect;
+use crate::error::{KiteError, Result};
+use crate::models::Price;
+use serde_json::{json, Value as JsonValue};
+
+/// The exit order of [`KiteConnect::place_gtt_oco`], placed as a limit
+/// order by whichever leg triggers first
+///
+/// Both legs are limit orders at their trigger price unless a limit price is
+/// set. A stoploss limit beyond the trigger, e.g. a little lower for a sell,
+/// makes the order more likely to fill in a falling market.
+///
+/// ```rust
+/// use kiteconnect::orders::GttExit;
+///
+/// let exit = GttExit::sell(10).product("CNC").stoploss_price("1390".parse().unwrap());
+/// assert_eq!(exit.transaction_type, "SELL");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct GttExit {
+    /// `SELL` to exit a long position, `BUY` to exit a short one
+    pub transaction_type: String,
+    /// Quantity of the exit order
+    pub quantity: u32,
+    /// Margin product, `CNC` by default
+    pub product: String,
+    /// Limit price of the target leg, its trigger price by default
+    pub target_price: Option<Price>,
+    /// Limit price of the stoploss leg, its trigger price by default
+    pub stoploss_price: Option<Price>,
+}
+
+impl GttExit {
+    /// Creates an exit of `quantity` with `transaction_type`
+    pub fn new(transaction_type: &str, quantity: u32) -> Self {
+        GttExit {
+            transaction_type: transaction_type.to_string(),
+            quantity,
+            product: "CNC".to_string(),
+            target_price: None,
+            stoploss_price: None,
+        }
+    }
+
+    /// Creates the exit of a long position
+    pub fn sell(quantity: u32) -> Self {
+        Self::new("SELL", quantity)
+    }
+
+    /// Creates the exit of a short position
+    pub fn buy(quantity: u32) -> Self {
+        Self::new("BUY", quantity)
+    }
+
+    /// Sets the margin product
+    pub fn product(mut self, product: &str) -> Self {
+        self.product = product.to_string();
+        self
+    }
+
+    /// Sets the limit price of the target leg
+    pub fn target_price(mut self, price: Price) -> Self {
+        self.target_price = Some(price);
+        self
+    }
+
+    /// Sets the limit price of the stoploss leg
+    pub fn stoploss_price(mut self, price: Price) -> Self {
+        self.stoploss_price = Some(price);
+        self
+    }
+}
+
+impl KiteConnect {
+    /// Places a two-leg GTT that exits a position at `target` or `stoploss`,
+    /// whichever is reached first, returning its trigger ID
+    ///
+    /// The trigger values are ordered and the exit orders matched to them as
+    /// the API expects. Fails without a request if the target and stoploss
+    /// are on the wrong sides of `last_price` for the side of the exit: a
+    /// sell needs the stoploss below and the target above, a buy the reverse.
+    ///
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    /// use kiteconnect::orders::GttExit;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> kiteconnect::error::Result<()> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// let price = |value: &str| value.parse().unwrap();
+    /// let trigger_id = client
+    ///     .place_gtt_oco("NSE", "INFY", price("1500"), price("1650"), price("1400"), &GttExit::sell(10))
+    ///     .await?;
+    /// println!("GTT {} placed", trigger_id);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn place_gtt_oco(
+        &self,
+        exchange: &str,
+        tradingsymbol: &str,
+        last_price: Price,
+        target: Price,
+        stoploss: Price,
+        exit: &GttExit,
+    ) -> Result<u64> {
+        let (condition, orders) = oco_payload(exchange, tradingsymbol, last_price, target, stoploss, exit)?;
+        let data = self.place_gtt("two-leg", &condition, &orders).await?;
+        data.get("data")
+            .unwrap_or(&data)["trigger_id"]
+            .as_u64()
+            .ok_or_else(|| KiteError::Other("GTT response lacks a trigger_id".to_string()))
+    }
+}
+
+/// Builds the condition and orders of a two-leg GTT, the lower trigger first
+fn oco_payload(
+    exchange: &str,
+    tradingsymbol: &str,
+    last_price: Price,
+    target: Price,
+    stoploss: Price,
+    exit: &GttExit,
+) -> Result<(JsonValue, JsonValue)> {
+    let sell = match exit.transaction_type.to_uppercase().as_str() {
+        "SELL" => true,
+        "BUY" => false,
+        other => return Err(KiteError::Other(format!("invalid GTT exit transaction type {:?}", other))),
+    };
+    let ordered = if sell {
+        stoploss < last_price && last_price < target
+    } else {
+        target < last_price && last_price < stoploss
+    };
+    if !ordered {
+        return Err(KiteError::Other(format!(
+            "a {} exit of {}:{} at {} needs the stoploss {} and the target {}, not {} and {}",
+            exit.transaction_type,
+            exchange,
+            tradingsymbol,
+            last_price,
+            if sell { "below" } else { "above" },
+            if sell { "above" } else { "below" },
+            stoploss,
+            target
+        )));
+    }
+    if exit.quantity == 0 {
+        let message = format!("quantity of the GTT exit of {}:{} must be positive", exchange, tradingsymbol);
+        return Err(KiteError::Other(message));
+    }
+
+    let order = |price: Price| {
+        json!({
+            "exchange": exchange,
+            "tradingsymbol": tradingsymbol,
+            "transaction_type": exit.transaction_type.to_uppercase(),
+            "quantity": exit.quantity,
+            "order_type": "LIMIT",
+            "product": exit.product,
+            "price": price_json(price),
+        })
+    };
+    let target_order = order(exit.target_price.unwrap_or(target));
+    let stoploss_order = order(exit.stoploss_price.unwrap_or(stoploss));
+    let (triggers, orders) = if sell {
+        ([stoploss, target], [stoploss_order, target_order])
+    } else {
+        ([target, stoploss], [target_order, stoploss_order])
+    };
+    let condition = json!({
+        "exchange": exchange,
+        "tradingsymbol": tradingsymbol,
+        "trigger_values": triggers.map(price_json),
+        "last_price": price_json(last_price),
+    });
+    Ok((condition, JsonValue::from(orders.to_vec())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+
+    fn price(value: &str) -> Price {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_oco_payload() {
+        let exit = GttExit::sell(10).stoploss_price(price("1390"));
+        let (condition, orders) =
+            oco_payload("NSE", "INFY", price("1500"), price("1650"), price("1400"), &exit).unwrap();
+        assert_eq!(condition["trigger_values"], json!([1400, 1650]));
+        assert_eq!(condition["last_price"], json!(1500));
+        assert_eq!(orders[0]["price"], json!(1390));
+        assert_eq!(orders[1]["price"], json!(1650));
+        assert_eq!(orders[1]["transaction_type"], "SELL");
+        assert_eq!(orders[1]["order_type"], "LIMIT");
+        assert_eq!(orders[1]["quantity"], 10);
+
+        // Exits of short positions trigger on the target first
+        let (condition, orders) =
+            oco_payload("NSE", "INFY", price("1500"), price("1400"), price("1550.5"), &GttExit::buy(5)).unwrap();
+        assert_eq!(condition["trigger_values"], json!([1400, 1550.5]));
+        assert_eq!(orders[1]["price"], json!(1550.5));
+
+        assert!(oco_payload("NSE", "INFY", price("1500"), price("1400"), price("1650"), &GttExit::sell(10)).is_err());
+        assert!(oco_payload("NSE", "INFY", price("1500"), price("1650"), price("1400"), &GttExit::buy(10)).is_err());
+        assert!(oco_payload("NSE", "INFY", price("1500"), price("1650"), price("1400"), &GttExit::sell(0)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_place_gtt_oco() {
+        let mut server = Server::new_async().await;
+        let client = KiteConnect::builder("API_KEY")
+            .access_token("ACCESS_TOKEN")
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+        let gtt = server
+            .mock("POST", "/gtt/triggers")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("type".into(), "two-leg".into()),
+                Matcher::Regex("condition=".into()),
+                Matcher::Regex("orders=".into()),
+            ]))
+            .with_body(r#"{"status": "success", "data": {"trigger_id": 123}}"#)
+            .create_async()
+            .await;
+        let trigger_id = client
+            .place_gtt_oco("NSE", "INFY", price("1500"), price("1650"), price("1400"), &GttExit::sell(10))
+            .await
+            .unwrap();
+        assert_eq!(trigger_id, 123);
+        gtt.assert_async().await;
+    }
+}
@@ -0,0 +1,163 @@
+//! Checking an order against the available funds before placing it
+
+use super::params::price_json;
+use super::OrderParams;
+use crate::connect::KiteConnect;
+use crate::error::{KiteError, Result};
+use crate::models::Price;
+use serde_json::{json, Value as JsonValue};
+
+/// Margin an order needs against the funds available for it, returned by
+/// [`KiteConnect::can_afford`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarginCheck {
+    /// Segment the margin is blocked in, `equity` or `commodity`
+    pub segment: String,
+    /// Margin the order would block
+    pub required: Price,
+    /// Net funds available in the segment
+    pub available: Price,
+    /// Whether the available funds cover the required margin
+    pub affordable: bool,
+}
+
+impl MarginCheck {
+    /// Returns the funds missing for the order, zero if it is affordable
+    pub fn shortfall(&self) -> Price {
+        if self.required > self.available {
+            self.required - self.available
+        } else {
+            Price::default()
+        }
+    }
+}
+
+impl KiteConnect {
+    /// Checks whether the funds available cover the margin an order would
+    /// block, without placing it
+    ///
+    /// The margin comes from the order margins endpoint, the funds from the
+    /// live net balance of the segment of the order, both fetched at once.
+    /// Orders failing the check would be rejected for insufficient funds, so
+    /// a strategy can skip them instead of placing them.
+    ///
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    /// use kiteconnect::orders::OrderParams;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> kiteconnect::error::Result<()> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// let params = OrderParams::buy("NSE", "INFY", 10).product("CNC").order_type("MARKET");
+    /// let check = client.can_afford(&params).await?;
+    /// if check.affordable {
+    ///     client.place_order_params(&params).await?;
+    /// } else {
+    ///     println!("Short of {} for {}", check.shortfall(), params.tradingsymbol);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn can_afford(&self, params: &OrderParams) -> Result<MarginCheck> {
+        let orders = JsonValue::from(vec![margin_order(params)]);
+        let (margins, funds) = futures::try_join!(self.order_margins_typed(&orders), self.margins_typed())?;
+        let margin = margins
+            .into_iter()
+            .next()
+            .ok_or_else(|| KiteError::Other(format!("no margin returned for {}", params.tradingsymbol)))?;
+
+        let segment = match margin.segment.as_str() {
+            "" if matches!(params.exchange.as_str(), "MCX" | "NCO") => "commodity".to_string(),
+            "" => "equity".to_string(),
+            segment => segment.to_string(),
+        };
+        let funds = match segment.as_str() {
+            "commodity" => funds.commodity,
+            _ => funds.equity,
+        };
+        let available = funds
+            .map(|funds| funds.net)
+            .ok_or_else(|| KiteError::Other(format!("no funds returned for the {} segment", segment)))?;
+        Ok(MarginCheck {
+            segment,
+            required: margin.total,
+            available,
+            affordable: margin.total <= available,
+        })
+    }
+}
+
+/// Describes an order the way the order margins endpoint takes it
+fn margin_order(params: &OrderParams) -> JsonValue {
+    let mut order = json!({
+        "exchange": params.exchange,
+        "tradingsymbol": params.tradingsymbol,
+        "transaction_type": params.transaction_type,
+        "variety": params.variety,
+        "quantity": params.quantity,
+        "price": params.price.map_or(json!(0), price_json),
+        "trigger_price": params.trigger_price.map_or(json!(0), price_json),
+    });
+    if let Some(product) = &params.product {
+        order["product"] = json!(product);
+    }
+    if let Some(order_type) = &params.order_type {
+        order["order_type"] = json!(order_type);
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_client;
+    use mockito::{Matcher, Server};
+
+    #[tokio::test]
+    async fn test_can_afford() {
+        let mut server = Server::new_async().await;
+        let client = mock_client(&server);
+        server
+            .mock("GET", "/user/margins")
+            .with_body_from_file("mocks/margins.json")
+            .create_async()
+            .await;
+        let margins = server
+            .mock("POST", "/margins/orders")
+            .match_body(Matcher::PartialJson(json!([{
+                "exchange": "NSE",
+                "tradingsymbol": "INFY",
+                "quantity": 1,
+                "product": "CNC",
+                "price": 1498,
+            }])))
+            .with_body_from_file("mocks/order_margins.json")
+            .create_async()
+            .await;
+        let mut large: JsonValue =
+            serde_json::from_str(&std::fs::read_to_string("mocks/order_margins.json").unwrap()).unwrap();
+        large["data"][0]["total"] = json!(29960);
+        server
+            .mock("POST", "/margins/orders")
+            .match_body(Matcher::PartialJson(json!([{"quantity": 20}])))
+            .with_body(large.to_string())
+            .create_async()
+            .await;
+
+        let params = OrderParams::buy("NSE", "INFY", 1)
+            .product("CNC")
+            .order_type("LIMIT")
+            .price(Price::from(1498));
+        let check = client.can_afford(&params).await.unwrap();
+        margins.assert_async().await;
+        assert_eq!(check.segment, "equity");
+        assert_eq!(check.required, Price::from(1498));
+        assert_eq!(check.available, "15481.524".parse::<Price>().unwrap());
+        assert!(check.affordable);
+        assert_eq!(check.shortfall(), Price::default());
+
+        let check = client.can_afford(&OrderParams { quantity: 20, ..params }).await.unwrap();
+        assert!(!check.affordable);
+        assert!(check.shortfall() > Price::from(14478) && check.shortfall() < Price::from(14479));
+    }
+}
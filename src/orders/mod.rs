@@ -0,0 +1,109 @@
+//! # Order Workflows
+//!
+//! Helpers built on the order endpoints of [`KiteConnect`] for tasks that
+//! otherwise take a loop of requests and bookkeeping in every strategy.
+//!
+//! [`KiteConnect::square_off_positions`] flattens open net positions, e.g. at
+//! the end of the day or when a risk limit is hit, selecting them with a
+//! [`PositionFilter`] and reporting the orders placed for each position and
+//! the ones that failed:
+//!
+//! ```rust,no_run
+//! use kiteconnect::connect::KiteConnect;
+//! use kiteconnect::orders::PositionFilter;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> kiteconnect::error::Result<()> {
+//! let client = KiteConnect::new("api_key", "access_token");
+//! let report = client
+//!     .square_off_positions(&PositionFilter::new().product("MIS"), "MARKET")
+//!     .await?;
+//! for failed in report.failed() {
+//!     eprintln!("{} not squared off: {:?}", failed.tradingsymbol, failed.result);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`KiteConnect::await_order_completion`] polls the history of an order with
+//! backoff until it is complete, rejected or cancelled, so strategies need no
+//! polling loop of their own. [`KiteConnect::place_order_and_wait`] places an
+//! order described by [`OrderParams`] and waits for its fills, turning
+//! rejections and cancellations into errors.
+//!
+//! A [`FillTracker`] follows the fills of an order as they come in, from its
+//! order updates or from its trades fetched with
+//! [`KiteConnect::poll_fills`], reporting the filled and remaining quantity
+//! and the average fill price.
+//!
+//! [`KiteConnect::place_basket`] places several orders one after another or at
+//! once, and on a failure stops, carries on or cancels the orders already
+//! placed, as its [`BasketPolicy`] says. [`KiteConnect::place_spread`] places
+//! the legs of verticals, straddles and iron condors this way, resolving the
+//! options from an [`InstrumentStore`] and buying the hedges first unless told
+//! otherwise.
+//!
+//! [`KiteConnect::place_gtt_oco`] places a two-leg GTT exiting a position at a
+//! target or a stoploss, ordering the trigger values and matching the exit
+//! orders to them as the API expects.
+//!
+//! [`KiteConnect::can_afford`] checks the margin an order would block against
+//! the funds available before it is placed, so orders bound to be rejected
+//! for funds can be skipped.
+//!
+//! [`KiteConnect::place_bracket`] emulates the bracket orders Kite no longer
+//! offers: once the entry fills, a GTT or a pair of linked orders closes the
+//! position at the target or the stoploss, whichever comes first.
+//!
+//! [`KiteConnect::orders_by_tag`] finds the orders of the day carrying a tag,
+//! the only attribution Kite keeps, and [`KiteConnect::cancel_orders_by_tag`]
+//! cancels the open ones, e.g. to stop a strategy without touching others.
+//! A [`Tag`] packs a strategy ID and a client order ID into a tag and reads
+//! them back from orders and trades, the same way in every process.
+//!
+//! An [`OrderGateway`] queues order placements, modifications and
+//! cancellations within the 10 a second, 200 a minute and 3000 a day Kite
+//! allows, sending cancels first, so rebalancing many orders at once does not
+//! get the API key blocked.
+//!
+//! [`KiteConnect`]: crate::connect::KiteConnect
+//! [`KiteConnect::await_order_completion`]: crate::connect::KiteConnect::await_order_completion
+//! [`KiteConnect::can_afford`]: crate::connect::KiteConnect::can_afford
+//! [`KiteConnect::cancel_orders_by_tag`]: crate::connect::KiteConnect::cancel_orders_by_tag
+//! [`KiteConnect::orders_by_tag`]: crate::connect::KiteConnect::orders_by_tag
+//! [`KiteConnect::place_basket`]: crate::connect::KiteConnect::place_basket
+//! [`KiteConnect::place_bracket`]: crate::connect::KiteConnect::place_bracket
+//! [`KiteConnect::place_spread`]: crate::connect::KiteConnect::place_spread
+//! [`InstrumentStore`]: crate::instruments::InstrumentStore
+//! [`KiteConnect::place_gtt_oco`]: crate::connect::KiteConnect::place_gtt_oco
+//! [`KiteConnect::poll_fills`]: crate::connect::KiteConnect::poll_fills
+//! [`KiteConnect::place_order_and_wait`]: crate::connect::KiteConnect::place_order_and_wait
+//! [`KiteConnect::square_off_positions`]: crate::connect::KiteConnect::square_off_positions
+
+mod basket;
+mod bracket;
+mod completion;
+mod dedupe;
+mod execution;
+mod fills;
+mod gateway;
+mod gtt;
+mod margin;
+mod params;
+mod spread;
+mod squareoff;
+mod tags;
+
+pub use basket::{BasketLeg, BasketPolicy, BasketReport, LegStatus, OnFailure};
+pub use bracket::{Bracket, BracketExit, BracketOrder, BracketState, ExitLeg};
+pub(crate) use dedupe::{DedupeKey, OrderDedupe};
+pub use execution::OrderExecution;
+pub use fills::FillTracker;
+pub use gateway::{GatewayMetrics, OrderGateway, OrderLimits, Priority};
+pub use gtt::GttExit;
+pub use margin::MarginCheck;
+pub use params::{MarketProtection, OrderParams};
+pub(crate) use params::order_id;
+pub use spread::{LegOrder, Spread, SpreadOrder};
+pub use squareoff::{PositionFilter, SquareOff, SquareOffReport};
+pub use tags::{CancelReport, Cancellation, Tag, MAX_TAG_LEN};
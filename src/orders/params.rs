@@ -0,0 +1,307 @@
+//! Parameters of an order, for the workflows placing several orders
+
+use crate::connect::KiteConnect;
+use crate::error::{KiteError, Result};
+use crate::models::Price;
+use serde_json::Value as JsonValue;
+use std::fmt;
+
+/// Exchanges of the derivative segments, where market protection applies
+const DERIVATIVE_EXCHANGES: [&str; 6] = ["NFO", "BFO", "CDS", "BCD", "MCX", "NCO"];
+
+/// How far from the last price a market or SL-M order in derivatives may
+/// fill, beyond which the rest of it is cancelled
+///
+/// Sent as the `market_protection` parameter of the order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarketProtection {
+    /// The protection Kite applies by default to the instrument
+    Auto,
+    /// Within this percentage of the last price, 1 to 100
+    Percent(u32),
+}
+
+impl fmt::Display for MarketProtection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarketProtection::Auto => write!(f, "-1"),
+            MarketProtection::Percent(percent) => write!(f, "{}", percent),
+        }
+    }
+}
+
+/// Parameters of an order, taken by [`KiteConnect::place_order_params`] and
+/// the order workflows
+///
+/// The fields mirror the parameters of [`KiteConnect::place_order`]; those
+/// left unset are not sent.
+///
+/// ```rust
+/// use kiteconnect::orders::OrderParams;
+///
+/// let params = OrderParams::buy("NSE", "INFY", 10)
+///     .product("CNC")
+///     .order_type("LIMIT")
+///     .price("1500.5".parse().unwrap())
+///     .tag("rebalance");
+/// assert_eq!(params.variety, "regular");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderParams {
+    /// Variety, `regular` by default
+    pub variety: String,
+    /// Exchange, e.g. `NSE`
+    pub exchange: String,
+    /// Exchange tradingsymbol of the instrument
+    pub tradingsymbol: String,
+    /// `BUY` or `SELL`
+    pub transaction_type: String,
+    /// Quantity in units, a multiple of the lot size for derivatives
+    pub quantity: u32,
+    /// Margin product, e.g. `CNC`, `MIS` or `NRML`
+    pub product: Option<String>,
+    /// `MARKET`, `LIMIT`, `SL` or `SL-M`
+    pub order_type: Option<String>,
+    /// Price of limit orders
+    pub price: Option<Price>,
+    /// Trigger price of stoploss orders
+    pub trigger_price: Option<Price>,
+    /// `DAY`, `IOC` or `TTL`
+    pub validity: Option<String>,
+    /// Quantity disclosed to the market
+    pub disclosed_quantity: Option<u32>,
+    /// Tag of up to 20 characters identifying the order
+    pub tag: Option<String>,
+    /// Bound on the fill price of market and SL-M orders in derivatives
+    pub market_protection: Option<MarketProtection>,
+}
+
+impl OrderParams {
+    /// Creates a regular order of `quantity` units of an instrument
+    pub fn new(exchange: &str, tradingsymbol: &str, transaction_type: &str, quantity: u32) -> Self {
+        OrderParams {
+            variety: "regular".to_string(),
+            exchange: exchange.to_string(),
+            tradingsymbol: tradingsymbol.to_string(),
+            transaction_type: transaction_type.to_string(),
+            quantity,
+            product: None,
+            order_type: None,
+            price: None,
+            trigger_price: None,
+            validity: None,
+            disclosed_quantity: None,
+            tag: None,
+            market_protection: None,
+        }
+    }
+
+    /// Creates a regular buy order
+    pub fn buy(exchange: &str, tradingsymbol: &str, quantity: u32) -> Self {
+        Self::new(exchange, tradingsymbol, "BUY", quantity)
+    }
+
+    /// Creates a regular sell order
+    pub fn sell(exchange: &str, tradingsymbol: &str, quantity: u32) -> Self {
+        Self::new(exchange, tradingsymbol, "SELL", quantity)
+    }
+
+    /// Sets the variety, e.g. `amo` or `co`
+    pub fn variety(mut self, variety: &str) -> Self {
+        self.variety = variety.to_string();
+        self
+    }
+
+    /// Sets the margin product
+    pub fn product(mut self, product: &str) -> Self {
+        self.product = Some(product.to_string());
+        self
+    }
+
+    /// Sets the order type
+    pub fn order_type(mut self, order_type: &str) -> Self {
+        self.order_type = Some(order_type.to_string());
+        self
+    }
+
+    /// Sets the price of a limit order
+    pub fn price(mut self, price: Price) -> Self {
+        self.price = Some(price);
+        self
+    }
+
+    /// Sets the trigger price of a stoploss order
+    pub fn trigger_price(mut self, trigger_price: Price) -> Self {
+        self.trigger_price = Some(trigger_price);
+        self
+    }
+
+    /// Sets the validity
+    pub fn validity(mut self, validity: &str) -> Self {
+        self.validity = Some(validity.to_string());
+        self
+    }
+
+    /// Sets the quantity disclosed to the market
+    pub fn disclosed_quantity(mut self, disclosed_quantity: u32) -> Self {
+        self.disclosed_quantity = Some(disclosed_quantity);
+        self
+    }
+
+    /// Sets the tag
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.tag = Some(tag.to_string());
+        self
+    }
+
+    /// Sets the market protection of a market or SL-M order in derivatives
+    ///
+    /// ```rust
+    /// use kiteconnect::orders::{MarketProtection, OrderParams};
+    ///
+    /// let params = OrderParams::buy("NFO", "NIFTY24MARFUT", 50)
+    ///     .product("NRML")
+    ///     .order_type("MARKET")
+    ///     .market_protection(MarketProtection::Percent(2));
+    /// assert!(params.validate().is_ok());
+    /// assert!(params.order_type("LIMIT").validate().is_err());
+    /// ```
+    pub fn market_protection(mut self, market_protection: MarketProtection) -> Self {
+        self.market_protection = Some(market_protection);
+        self
+    }
+
+    /// Checks the parameters that can be checked without the instrument
+    ///
+    /// Market protection is only taken by market and SL-M orders on the
+    /// derivative exchanges, as a percentage from 1 to 100.
+    pub fn validate(&self) -> Result<()> {
+        let Some(market_protection) = self.market_protection else {
+            return Ok(());
+        };
+        if let MarketProtection::Percent(percent) = market_protection {
+            if !(1..=100).contains(&percent) {
+                return Err(KiteError::Other(format!(
+                    "market protection of {}% is not between 1% and 100%",
+                    percent
+                )));
+            }
+        }
+        let order_type = self.order_type.as_deref().unwrap_or_default();
+        if !order_type.eq_ignore_ascii_case("MARKET") && !order_type.eq_ignore_ascii_case("SL-M") {
+            return Err(KiteError::Other(format!(
+                "market protection applies to MARKET and SL-M orders, not {:?}",
+                order_type
+            )));
+        }
+        if !DERIVATIVE_EXCHANGES.iter().any(|exchange| self.exchange.eq_ignore_ascii_case(exchange)) {
+            return Err(KiteError::Other(format!(
+                "market protection applies to derivatives, not to orders on {}",
+                self.exchange
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl KiteConnect {
+    /// Places the order described by `params`, returning its order ID
+    ///
+    /// Behaves like [`place_order`](Self::place_order), order checks included.
+    /// Fails without a request if the parameters do not
+    /// [validate](OrderParams::validate).
+    pub async fn place_order_params(&self, params: &OrderParams) -> Result<String> {
+        params.validate()?;
+        let price = params.price.map(|price| price.to_string());
+        let trigger_price = params.trigger_price.map(|price| price.to_string());
+        let disclosed_quantity = params.disclosed_quantity.map(|quantity| quantity.to_string());
+        let market_protection = params.market_protection.map(|protection| protection.to_string());
+        let data = self
+            .place_order_protected(
+                &params.variety,
+                &params.exchange,
+                &params.tradingsymbol,
+                &params.transaction_type,
+                &params.quantity.to_string(),
+                params.product.as_deref(),
+                params.order_type.as_deref(),
+                price.as_deref(),
+                params.validity.as_deref(),
+                disclosed_quantity.as_deref(),
+                trigger_price.as_deref(),
+                None,
+                None,
+                None,
+                params.tag.as_deref(),
+                market_protection.as_deref(),
+            )
+            .await?;
+        order_id(&data)
+    }
+}
+
+/// Reads the order ID from the response to an order placement
+pub(crate) fn order_id(data: &JsonValue) -> Result<String> {
+    data.get("data")
+        .unwrap_or(data)["order_id"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| KiteError::Other("order response lacks an order_id".to_string()))
+}
+
+/// Converts a price into a JSON number, as decimal prices serialize to strings
+pub(crate) fn price_json(price: Price) -> JsonValue {
+    price
+        .to_string()
+        .parse::<serde_json::Number>()
+        .map_or(JsonValue::Null, JsonValue::Number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+
+    #[test]
+    fn test_market_protection() {
+        let params = OrderParams::sell("MCX", "CRUDEOIL24APRFUT", 100)
+            .order_type("SL-M")
+            .market_protection(MarketProtection::Auto);
+        assert!(params.validate().is_ok());
+        assert_eq!(MarketProtection::Auto.to_string(), "-1");
+
+        let percent = |percent| params.clone().market_protection(MarketProtection::Percent(percent));
+        assert!(percent(100).validate().is_ok());
+        assert!(percent(0).validate().is_err());
+        assert!(percent(101).validate().is_err());
+        assert!(params.clone().order_type("SL").validate().is_err());
+        assert!(OrderParams { exchange: "NSE".to_string(), ..params }.validate().is_err());
+        assert!(OrderParams::buy("NSE", "INFY", 1).order_type("LIMIT").validate().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_place_order_market_protection() {
+        let mut server = Server::new_async().await;
+        let client = KiteConnect::builder("API_KEY")
+            .access_token("ACCESS_TOKEN")
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+        let placed = server
+            .mock("POST", "/orders/regular")
+            .match_body(Matcher::UrlEncoded("market_protection".into(), "3".into()))
+            .with_body(r#"{"status": "success", "data": {"order_id": "1"}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let params = OrderParams::buy("NFO", "NIFTY24MARFUT", 50)
+            .product("NRML")
+            .order_type("MARKET")
+            .market_protection(MarketProtection::Percent(3));
+        assert_eq!(client.place_order_params(&params).await.unwrap(), "1");
+        let rejected = params.order_type("LIMIT").price("22000".parse().unwrap());
+        assert!(client.place_order_params(&rejected).await.is_err());
+        placed.assert_async().await;
+    }
+}
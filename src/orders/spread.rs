@@ -0,0 +1,345 @@
+//! Multi-leg option spreads placed as baskets
+
+use super::{BasketPolicy, BasketReport, OrderParams};
+use crate::connect::KiteConnect;
+use crate::error::{KiteError, Result};
+use crate::instruments::{InstrumentStore, OptionType};
+use crate::models::Price;
+use chrono::NaiveDate;
+
+/// Option strategies [`KiteConnect::place_spread`] places
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Spread {
+    /// Buys the option at `buy_strike` and sells the one at `sell_strike`,
+    /// e.g. a bull call spread with the lower strike bought
+    Vertical {
+        /// Calls or puts
+        option_type: OptionType,
+        /// Strike of the bought option
+        buy_strike: Price,
+        /// Strike of the sold option
+        sell_strike: Price,
+    },
+    /// Buys a call and a put at `strike`, or sells both if `short`
+    Straddle {
+        /// Strike of both options
+        strike: Price,
+        /// Whether the options are sold
+        short: bool,
+    },
+    /// Sells a put and a call and buys the further out put and call as
+    /// wings, or the reverse if not `short`
+    IronCondor {
+        /// Strike of the outer put
+        put_wing: Price,
+        /// Strike of the inner put
+        put_strike: Price,
+        /// Strike of the inner call
+        call_strike: Price,
+        /// Strike of the outer call
+        call_wing: Price,
+        /// Whether the inner options are sold and the wings bought
+        short: bool,
+    },
+}
+
+impl Spread {
+    /// Returns the option type, strike and side of every leg
+    fn legs(&self) -> Vec<(OptionType, Price, &'static str)> {
+        let sides = |short: bool| if short { ("SELL", "BUY") } else { ("BUY", "SELL") };
+        match *self {
+            Spread::Vertical {
+                option_type,
+                buy_strike,
+                sell_strike,
+            } => vec![(option_type, buy_strike, "BUY"), (option_type, sell_strike, "SELL")],
+            Spread::Straddle { strike, short } => {
+                let (side, _) = sides(short);
+                vec![(OptionType::Call, strike, side), (OptionType::Put, strike, side)]
+            }
+            Spread::IronCondor {
+                put_wing,
+                put_strike,
+                call_strike,
+                call_wing,
+                short,
+            } => {
+                let (inner, wing) = sides(short);
+                vec![
+                    (OptionType::Put, put_wing, wing),
+                    (OptionType::Put, put_strike, inner),
+                    (OptionType::Call, call_strike, inner),
+                    (OptionType::Call, call_wing, wing),
+                ]
+            }
+        }
+    }
+}
+
+/// Which legs of a spread are placed first
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LegOrder {
+    /// Bought legs first, so the hedges lower the margin of the sold legs
+    #[default]
+    BuyFirst,
+    /// Sold legs first, e.g. to collect the premium before paying for hedges
+    SellFirst,
+}
+
+/// A spread on an underlying, taken by [`KiteConnect::place_spread`]
+///
+/// Legs are regular `NRML` market orders of `lots` lots each unless set
+/// otherwise, bought legs first, placed one after another and stopping at
+/// the first failure.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpreadOrder {
+    /// Underlying as `EXCHANGE:TRADINGSYMBOL`, e.g. `NSE:NIFTY 50`
+    pub underlying: String,
+    /// Expiry of the options
+    pub expiry: NaiveDate,
+    /// Strategy and strikes
+    pub spread: Spread,
+    /// Lots of every leg
+    pub lots: u32,
+    /// Margin product of the legs
+    pub product: String,
+    /// Order type of the legs
+    pub order_type: String,
+    /// Which legs go first
+    pub leg_order: LegOrder,
+    /// How the legs are placed and failures handled
+    pub policy: BasketPolicy,
+    /// Tag of the leg orders
+    pub tag: Option<String>,
+}
+
+impl SpreadOrder {
+    /// Creates a spread of `lots` lots per leg on the options of `underlying`
+    /// expiring on `expiry`
+    pub fn new(underlying: &str, expiry: NaiveDate, spread: Spread, lots: u32) -> Self {
+        SpreadOrder {
+            underlying: underlying.to_string(),
+            expiry,
+            spread,
+            lots,
+            product: "NRML".to_string(),
+            order_type: "MARKET".to_string(),
+            leg_order: LegOrder::default(),
+            policy: BasketPolicy::abort_on_failure(),
+            tag: None,
+        }
+    }
+
+    /// Sets the margin product of the legs
+    pub fn product(mut self, product: &str) -> Self {
+        self.product = product.to_string();
+        self
+    }
+
+    /// Sets the order type of the legs
+    pub fn order_type(mut self, order_type: &str) -> Self {
+        self.order_type = order_type.to_string();
+        self
+    }
+
+    /// Sets which legs go first
+    pub fn leg_order(mut self, leg_order: LegOrder) -> Self {
+        self.leg_order = leg_order;
+        self
+    }
+
+    /// Sets how the legs are placed and failures handled
+    pub fn policy(mut self, policy: BasketPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the tag of the leg orders
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.tag = Some(tag.to_string());
+        self
+    }
+
+    /// Resolves the options of the legs in `store`, returning the leg orders
+    /// in the order they are placed
+    ///
+    /// Fails if an option is not listed.
+    pub fn legs(&self, store: &InstrumentStore) -> Result<Vec<OrderParams>> {
+        let mut legs = Vec::new();
+        for (option_type, strike, side) in self.spread.legs() {
+            let option = store
+                .option(&self.underlying, self.expiry, strike, option_type)
+                .ok_or_else(|| {
+                    KiteError::Other(format!(
+                        "no {} {} option at {} expiring on {}",
+                        self.underlying,
+                        option_type.as_str(),
+                        strike,
+                        self.expiry
+                    ))
+                })?;
+            let mut params = OrderParams::new(
+                &option.exchange,
+                &option.tradingsymbol,
+                side,
+                self.lots * option.lot_size.max(1),
+            )
+            .product(&self.product)
+            .order_type(&self.order_type);
+            params.tag = self.tag.clone();
+            legs.push(params);
+        }
+        let first = match self.leg_order {
+            LegOrder::BuyFirst => "BUY",
+            LegOrder::SellFirst => "SELL",
+        };
+        legs.sort_by_key(|leg| leg.transaction_type != first);
+        Ok(legs)
+    }
+}
+
+impl KiteConnect {
+    /// Places the legs of an option spread as a basket
+    ///
+    /// The options are resolved in `store` before any order is placed, so an
+    /// unlisted strike fails the call without placing anything. The legs are
+    /// then placed like with [`place_basket`](Self::place_basket), in the
+    /// [leg order](SpreadOrder::leg_order) and with the
+    /// [policy](SpreadOrder::policy) of the spread.
+    ///
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    /// use kiteconnect::instruments::InstrumentStore;
+    /// use kiteconnect::orders::{Spread, SpreadOrder};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> kiteconnect::error::Result<()> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// let store = InstrumentStore::new(client.instruments_typed(Some("NFO")).await?);
+    /// let expiry = store.next_expiry("NIFTY", chrono::Local::now().date_naive()).unwrap();
+    ///
+    /// let condor = Spread::IronCondor {
+    ///     put_wing: 21500.into(),
+    ///     put_strike: 21800.into(),
+    ///     call_strike: 22500.into(),
+    ///     call_wing: 22800.into(),
+    ///     short: true,
+    /// };
+    /// let report = client.place_spread(&store, &SpreadOrder::new("NSE:NIFTY 50", expiry, condor, 2)).await?;
+    /// println!("Placed {:?}", report.order_ids().collect::<Vec<_>>());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn place_spread(&self, store: &InstrumentStore, order: &SpreadOrder) -> Result<BasketReport> {
+        let legs = order.legs(store)?;
+        Ok(self.place_basket(legs, order.policy).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruments::Instrument;
+    use crate::models::InstrumentToken;
+    use mockito::{Matcher, Server};
+
+    fn store() -> InstrumentStore {
+        let option = |strike: u32, option_type: &str| Instrument {
+            instrument_token: InstrumentToken(strike * 10 + (option_type == "PE") as u32),
+            tradingsymbol: format!("NIFTY24JUN{}{}", strike, option_type),
+            name: "NIFTY".to_string(),
+            instrument_type: option_type.to_string(),
+            expiry: Some("2024-06-27".parse().unwrap()),
+            strike: Price::from(strike),
+            lot_size: 25,
+            segment: "NFO-OPT".to_string(),
+            exchange: "NFO".to_string(),
+            ..Default::default()
+        };
+        [21500, 21800, 22000, 22500, 22800]
+            .into_iter()
+            .flat_map(|strike| [option(strike, "CE"), option(strike, "PE")])
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    #[test]
+    fn test_spread_legs() {
+        let store = store();
+        let expiry = "2024-06-27".parse().unwrap();
+        let legs = |spread, leg_order| {
+            SpreadOrder::new("NSE:NIFTY 50", expiry, spread, 2)
+                .leg_order(leg_order)
+                .legs(&store)
+                .unwrap()
+                .into_iter()
+                .map(|leg| format!("{} {} {}", leg.transaction_type, leg.quantity, leg.tradingsymbol))
+                .collect::<Vec<_>>()
+        };
+
+        let condor = Spread::IronCondor {
+            put_wing: Price::from(21500),
+            put_strike: Price::from(21800),
+            call_strike: Price::from(22500),
+            call_wing: Price::from(22800),
+            short: true,
+        };
+        assert_eq!(
+            legs(condor, LegOrder::BuyFirst),
+            [
+                "BUY 50 NIFTY24JUN21500PE",
+                "BUY 50 NIFTY24JUN22800CE",
+                "SELL 50 NIFTY24JUN21800PE",
+                "SELL 50 NIFTY24JUN22500CE"
+            ]
+        );
+        let straddle = Spread::Straddle {
+            strike: Price::from(22000),
+            short: true,
+        };
+        assert_eq!(legs(straddle, LegOrder::BuyFirst), ["SELL 50 NIFTY24JUN22000CE", "SELL 50 NIFTY24JUN22000PE"]);
+        let bear_put = Spread::Vertical {
+            option_type: OptionType::Put,
+            buy_strike: Price::from(22000),
+            sell_strike: Price::from(21800),
+        };
+        assert_eq!(legs(bear_put, LegOrder::SellFirst), ["SELL 50 NIFTY24JUN21800PE", "BUY 50 NIFTY24JUN22000PE"]);
+
+        let unlisted = Spread::Straddle {
+            strike: Price::from(22100),
+            short: false,
+        };
+        assert!(SpreadOrder::new("NSE:NIFTY 50", expiry, unlisted, 1).legs(&store).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_place_spread() {
+        let mut server = Server::new_async().await;
+        let client = KiteConnect::builder("API_KEY")
+            .access_token("ACCESS_TOKEN")
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+        let place = server
+            .mock("POST", "/orders/regular")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("product".into(), "NRML".into()),
+                Matcher::UrlEncoded("quantity".into(), "25".into()),
+            ]))
+            .with_body(r#"{"status": "success", "data": {"order_id": "1"}}"#)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let spread = Spread::Vertical {
+            option_type: OptionType::Call,
+            buy_strike: Price::from(22000),
+            sell_strike: Price::from(22500),
+        };
+        let order = SpreadOrder::new("NSE:NIFTY 50", "2024-06-27".parse().unwrap(), spread, 1);
+        let report = client.place_spread(&store(), &order).await.unwrap();
+        place.assert_async().await;
+        assert_eq!(report.order_ids().count(), 2);
+        assert!(report.is_complete());
+    }
+}
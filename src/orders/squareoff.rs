@@ -0,0 +1,304 @@
+//! Squaring off open positions with offsetting orders
+
+use crate::connect::KiteConnect;
+use crate::error::{KiteError, Result};
+use crate::instruments::round_to_tick;
+use crate::models::{Position, Price};
+
+/// Criteria for [`KiteConnect::square_off_positions`]; positions must match
+/// all that are set
+///
+/// Text criteria are matched ignoring case.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PositionFilter {
+    /// Exchange, e.g. `NFO`
+    pub exchange: Option<String>,
+    /// Margin product, e.g. `MIS` or `NRML`
+    pub product: Option<String>,
+    /// Start of the tradingsymbol, e.g. `BANKNIFTY` for all its contracts
+    pub tradingsymbol_prefix: Option<String>,
+    /// Long positions if `true`, short positions if `false`
+    pub long: Option<bool>,
+}
+
+impl PositionFilter {
+    /// Creates a filter matching every position
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches positions on `exchange`
+    pub fn exchange(mut self, exchange: &str) -> Self {
+        self.exchange = Some(exchange.to_string());
+        self
+    }
+
+    /// Matches positions of `product`
+    pub fn product(mut self, product: &str) -> Self {
+        self.product = Some(product.to_string());
+        self
+    }
+
+    /// Matches positions whose tradingsymbol starts with `prefix`
+    pub fn tradingsymbol_prefix(mut self, prefix: &str) -> Self {
+        self.tradingsymbol_prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Matches long positions, or short positions if `long` is `false`
+    pub fn long(mut self, long: bool) -> Self {
+        self.long = Some(long);
+        self
+    }
+
+    /// Returns `true` if `position` meets every criterion
+    pub fn matches(&self, position: &Position) -> bool {
+        let text = |criterion: &Option<String>, value: &str| {
+            criterion.as_deref().is_none_or(|c| c.eq_ignore_ascii_case(value))
+        };
+        text(&self.exchange, &position.exchange)
+            && text(&self.product, &position.product)
+            && self
+                .tradingsymbol_prefix
+                .as_deref()
+                .is_none_or(|prefix| position.tradingsymbol.to_uppercase().starts_with(&prefix.to_uppercase()))
+            && self.long.is_none_or(|long| long == (position.quantity > 0))
+    }
+}
+
+/// The offsetting order of one position and its outcome
+#[derive(Debug)]
+pub struct SquareOff {
+    /// Exchange of the position
+    pub exchange: String,
+    /// Tradingsymbol of the position
+    pub tradingsymbol: String,
+    /// Margin product of the position
+    pub product: String,
+    /// `SELL` for long positions, `BUY` for short ones
+    pub transaction_type: String,
+    /// Quantity ordered, the absolute net quantity of the position
+    pub quantity: u32,
+    /// IDs of the placed orders, several if the quantity was sliced, or the
+    /// error that stopped placement
+    pub result: Result<Vec<String>>,
+}
+
+/// Outcome of [`KiteConnect::square_off_positions`], one entry per position
+#[derive(Debug, Default)]
+pub struct SquareOffReport {
+    /// Offsetting orders in the order of the positions
+    pub orders: Vec<SquareOff>,
+}
+
+impl SquareOffReport {
+    /// Returns the positions whose offsetting orders were all placed
+    pub fn placed(&self) -> impl Iterator<Item = &SquareOff> {
+        self.orders.iter().filter(|order| order.result.is_ok())
+    }
+
+    /// Returns the positions that could not be squared off completely
+    pub fn failed(&self) -> impl Iterator<Item = &SquareOff> {
+        self.orders.iter().filter(|order| order.result.is_err())
+    }
+
+    /// Returns the IDs of every placed order
+    pub fn order_ids(&self) -> impl Iterator<Item = &str> {
+        self.orders
+            .iter()
+            .filter_map(|order| order.result.as_ref().ok())
+            .flatten()
+            .map(String::as_str)
+    }
+
+    /// Returns `true` if every matching position was squared off
+    pub fn is_complete(&self) -> bool {
+        self.orders.iter().all(|order| order.result.is_ok())
+    }
+}
+
+impl KiteConnect {
+    /// Squares off the open net positions matching `filter`, returning the
+    /// orders placed for each of them
+    ///
+    /// Every position gets a regular order of the opposite side for its net
+    /// quantity, in its product, sliced below the
+    /// [freeze quantity](Self::set_freeze_limits) where needed. `order_type`
+    /// is `MARKET`, or `LIMIT` to offer at the last price of the position,
+    /// rounded to the tick size if the instrument is in the
+    /// [order checks](Self::set_order_checks) store; a limit order is not
+    /// guaranteed to fill.
+    ///
+    /// Positions are squared off one after another and a rejected order does
+    /// not stop the others; failures are reported per position in the
+    /// [`SquareOffReport`]. Only fetching the positions fails the call.
+    pub async fn square_off_positions(&self, filter: &PositionFilter, order_type: &str) -> Result<SquareOffReport> {
+        let limit = match order_type.to_uppercase().as_str() {
+            "MARKET" => false,
+            "LIMIT" => true,
+            _ => {
+                return Err(KiteError::Other(format!(
+                    "positions are squared off with MARKET or LIMIT orders, not {}",
+                    order_type
+                )))
+            }
+        };
+
+        let positions = self.positions_typed().await?;
+        let mut report = SquareOffReport::default();
+        for position in positions.net.iter().filter(|p| p.quantity != 0 && filter.matches(p)) {
+            let transaction_type = if position.quantity > 0 { "SELL" } else { "BUY" };
+            let quantity = u32::try_from(position.quantity.unsigned_abs()).unwrap_or(u32::MAX);
+            let result = if limit {
+                match self.square_off_price(position) {
+                    Ok(price) => {
+                        let price = price.to_string();
+                        self.place_square_off(position, transaction_type, quantity, "LIMIT", Some(&price)).await
+                    }
+                    Err(e) => Err(e),
+                }
+            } else {
+                self.place_square_off(position, transaction_type, quantity, "MARKET", None).await
+            };
+            if let Err(e) = &result {
+                log::warn!("Failed to square off {}:{}: {}", position.exchange, position.tradingsymbol, e);
+            }
+            report.orders.push(SquareOff {
+                exchange: position.exchange.clone(),
+                tradingsymbol: position.tradingsymbol.clone(),
+                product: position.product.clone(),
+                transaction_type: transaction_type.to_string(),
+                quantity,
+                result,
+            });
+        }
+        Ok(report)
+    }
+
+    fn square_off_price(&self, position: &Position) -> Result<Price> {
+        if position.last_price <= Price::default() {
+            return Err(KiteError::Other(format!(
+                "{}:{} has no last price to square off at",
+                position.exchange, position.tradingsymbol
+            )));
+        }
+        let instrument = self
+            .order_checks()
+            .and_then(|store| store.find(&position.exchange, &position.tradingsymbol));
+        Ok(match instrument {
+            Some(instrument) => round_to_tick(position.last_price, instrument),
+            None => position.last_price,
+        })
+    }
+
+    async fn place_square_off(
+        &self,
+        position: &Position,
+        transaction_type: &str,
+        quantity: u32,
+        order_type: &str,
+        price: Option<&str>,
+    ) -> Result<Vec<String>> {
+        self.place_order_sliced(
+            "regular",
+            &position.exchange,
+            &position.tradingsymbol,
+            transaction_type,
+            &quantity.to_string(),
+            Some(&position.product),
+            Some(order_type),
+            price,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_client;
+    use mockito::{Matcher, Server};
+
+    fn position(exchange: &str, tradingsymbol: &str, product: &str, quantity: i64) -> Position {
+        serde_json::from_value(serde_json::json!({
+            "tradingsymbol": tradingsymbol,
+            "exchange": exchange,
+            "instrument_token": 0,
+            "product": product,
+            "quantity": quantity,
+            "average_price": 0,
+            "last_price": 0,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_position_filter() {
+        let short = position("NFO", "BANKNIFTY24JUNFUT", "NRML", -15);
+        let long = position("NSE", "INFY", "MIS", 10);
+        assert!(PositionFilter::new().matches(&short));
+        assert!(PositionFilter::new().exchange("nfo").tradingsymbol_prefix("banknifty").matches(&short));
+        assert!(!PositionFilter::new().tradingsymbol_prefix("NIFTY").matches(&short));
+        assert!(PositionFilter::new().product("MIS").long(true).matches(&long));
+        assert!(!PositionFilter::new().long(true).matches(&short));
+    }
+
+    #[tokio::test]
+    async fn test_square_off_positions() {
+        let mut server = Server::new_async().await;
+        let _positions = server
+            .mock("GET", "/portfolio/positions")
+            .with_body_from_file("mocks/positions.json")
+            .create_async()
+            .await;
+        let market = server
+            .mock("POST", "/orders/regular")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("tradingsymbol".into(), "LEADMINI17DECFUT".into()),
+                Matcher::UrlEncoded("transaction_type".into(), "SELL".into()),
+                Matcher::UrlEncoded("quantity".into(), "1".into()),
+                Matcher::UrlEncoded("product".into(), "NRML".into()),
+                Matcher::UrlEncoded("order_type".into(), "MARKET".into()),
+            ]))
+            .with_body(r#"{"status": "success", "data": {"order_id": "151220000000000"}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = mock_client(&server);
+        let report = client.square_off_positions(&PositionFilter::new(), "market").await.unwrap();
+        market.assert_async().await;
+        assert_eq!(report.orders.len(), 1);
+        assert!(report.is_complete());
+        assert_eq!(report.order_ids().collect::<Vec<_>>(), ["151220000000000"]);
+        assert_eq!(report.placed().next().unwrap().transaction_type, "SELL");
+
+        let limit = server
+            .mock("POST", "/orders/regular")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("order_type".into(), "LIMIT".into()),
+                Matcher::UrlEncoded("price".into(), "161.05".into()),
+            ]))
+            .with_status(400)
+            .with_body(r#"{"status": "error", "message": "Insufficient funds", "error_type": "MarginException"}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let report = client.square_off_positions(&PositionFilter::new().exchange("MCX"), "LIMIT").await.unwrap();
+        limit.assert_async().await;
+        assert!(!report.is_complete());
+        assert_eq!(report.failed().next().unwrap().tradingsymbol, "LEADMINI17DECFUT");
+        assert_eq!(report.order_ids().count(), 0);
+
+        let report = client.square_off_positions(&PositionFilter::new().exchange("NSE"), "MARKET").await.unwrap();
+        assert!(report.orders.is_empty());
+        assert!(client.square_off_positions(&PositionFilter::new(), "SL").await.is_err());
+    }
+}
@@ -0,0 +1,339 @@
+//! Attributing orders to strategies with their tag, and finding and
+//! cancelling them by it
+
+use crate::connect::KiteConnect;
+use crate::error::{KiteError, Result};
+use crate::models::{Order, Trade};
+use std::fmt;
+use std::str::FromStr;
+
+/// Longest tag Kite accepts
+pub const MAX_TAG_LEN: usize = 20;
+
+/// Longest strategy ID a [`Tag`] holds
+const MAX_STRATEGY_LEN: usize = 9;
+
+/// A strategy ID and a client order ID packed into the tag of an order
+///
+/// Tags have up to 20 alphanumeric characters, and every process placing
+/// orders for a strategy should fill them the same way. A tag is the length of
+/// the strategy ID as one digit, the strategy ID and the client order ID: the
+/// order `17` of strategy `MOM` is tagged `3MOM17`. Strategy IDs have 1 to 9
+/// characters and client order IDs fill the rest.
+///
+/// ```rust
+/// use kiteconnect::orders::{OrderParams, Tag};
+///
+/// # fn main() -> kiteconnect::error::Result<()> {
+/// let tag = Tag::new("MOM", "17")?;
+/// let params = OrderParams::buy("NSE", "INFY", 10).tag(&tag.to_string());
+/// assert_eq!(params.tag.as_deref(), Some("3MOM17"));
+/// assert_eq!("3MOM17".parse::<Tag>()?, tag);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Tag {
+    strategy: String,
+    client_order_id: String,
+}
+
+impl Tag {
+    /// Creates the tag of order `client_order_id` of `strategy`
+    ///
+    /// Fails unless both are alphanumeric and fit a tag, the strategy ID in 9
+    /// characters and the client order ID in the rest.
+    pub fn new(strategy: &str, client_order_id: &str) -> Result<Self> {
+        let alphanumeric = |id: &str| !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric());
+        if !alphanumeric(strategy) || strategy.len() > MAX_STRATEGY_LEN {
+            return Err(KiteError::Other(format!(
+                "strategy ID {:?} must be 1 to {} alphanumeric characters",
+                strategy, MAX_STRATEGY_LEN
+            )));
+        }
+        let max = MAX_TAG_LEN - 1 - strategy.len();
+        if !alphanumeric(client_order_id) || client_order_id.len() > max {
+            return Err(KiteError::Other(format!(
+                "client order ID {:?} of strategy {} must be 1 to {} alphanumeric characters",
+                client_order_id, strategy, max
+            )));
+        }
+        Ok(Tag {
+            strategy: strategy.to_string(),
+            client_order_id: client_order_id.to_string(),
+        })
+    }
+
+    /// Creates the tag of the `number`th order of `strategy`, written in base
+    /// 36 to fit more orders in the tag
+    pub fn numbered(strategy: &str, number: u64) -> Result<Self> {
+        Self::new(strategy, &base36(number))
+    }
+
+    /// Returns the strategy ID
+    pub fn strategy(&self) -> &str {
+        &self.strategy
+    }
+
+    /// Returns the client order ID
+    pub fn client_order_id(&self) -> &str {
+        &self.client_order_id
+    }
+
+    /// Returns the number of a tag made by [`Tag::numbered`]
+    pub fn number(&self) -> Option<u64> {
+        u64::from_str_radix(&self.client_order_id, 36).ok()
+    }
+
+    /// Reads the tag of an order, if it has one in this format
+    pub fn from_order(order: &Order) -> Option<Self> {
+        let tags = order.extra.get("tags").and_then(|tags| tags.as_array());
+        order
+            .tag
+            .iter()
+            .map(String::as_str)
+            .chain(tags.into_iter().flatten().filter_map(|tag| tag.as_str()))
+            .find_map(|tag| tag.parse().ok())
+    }
+
+    /// Reads the tag of the order of a trade among `orders`, as trades carry
+    /// no tag of their own
+    pub fn from_trade(trade: &Trade, orders: &[Order]) -> Option<Self> {
+        orders
+            .iter()
+            .find(|order| order.order_id == trade.order_id)
+            .and_then(Self::from_order)
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.strategy.len(), self.strategy, self.client_order_id)
+    }
+}
+
+impl FromStr for Tag {
+    type Err = KiteError;
+
+    fn from_str(tag: &str) -> Result<Self> {
+        let invalid = || KiteError::Other(format!("{:?} is not a strategy tag", tag));
+        let len = tag
+            .get(..1)
+            .and_then(|len| len.parse::<usize>().ok())
+            .filter(|&len| len > 0)
+            .ok_or_else(invalid)?;
+        let strategy = tag.get(1..1 + len).ok_or_else(invalid)?;
+        let client_order_id = tag.get(1 + len..).ok_or_else(invalid)?;
+        Self::new(strategy, client_order_id).map_err(|_| invalid())
+    }
+}
+
+/// Writes `number` in base 36 with upper case letters
+fn base36(mut number: u64) -> String {
+    let mut digits = Vec::new();
+    loop {
+        digits.push(std::char::from_digit((number % 36) as u32, 36).unwrap_or('0').to_ascii_uppercase());
+        number /= 36;
+        if number == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
+/// The cancellation of one order and its outcome
+#[derive(Debug)]
+pub struct Cancellation {
+    /// ID of the order
+    pub order_id: String,
+    /// Variety of the order
+    pub variety: String,
+    /// Tradingsymbol of the order
+    pub tradingsymbol: String,
+    /// Whether the order was cancelled, or the error that prevented it
+    pub result: Result<()>,
+}
+
+/// Outcome of [`KiteConnect::cancel_orders_by_tag`], one entry per open
+/// order with the tag
+#[derive(Debug, Default)]
+pub struct CancelReport {
+    /// Cancellations in the order of the order book
+    pub orders: Vec<Cancellation>,
+}
+
+impl CancelReport {
+    /// Returns the orders that were cancelled
+    pub fn cancelled(&self) -> impl Iterator<Item = &Cancellation> {
+        self.orders.iter().filter(|order| order.result.is_ok())
+    }
+
+    /// Returns the orders that could not be cancelled
+    pub fn failed(&self) -> impl Iterator<Item = &Cancellation> {
+        self.orders.iter().filter(|order| order.result.is_err())
+    }
+
+    /// Returns `true` if every open order with the tag was cancelled
+    pub fn is_complete(&self) -> bool {
+        self.orders.iter().all(|order| order.result.is_ok())
+    }
+}
+
+impl KiteConnect {
+    /// Returns the orders of the day carrying `tag`
+    ///
+    /// Tags are the only attribution Kite keeps for orders, so tagging the
+    /// orders of a strategy lets it find them again, e.g. after a restart.
+    ///
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> kiteconnect::error::Result<()> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// for order in client.orders_by_tag("momentum").await? {
+    ///     println!("{} {} {}", order.order_id, order.tradingsymbol, order.status);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn orders_by_tag(&self, tag: &str) -> Result<Vec<Order>> {
+        let mut orders = self.orders_typed().await?;
+        orders.retain(|order| order.has_tag(tag));
+        Ok(orders)
+    }
+
+    /// Cancels every open order of the day carrying `tag`
+    ///
+    /// Orders that are complete, rejected or cancelled already are left out.
+    /// The cancellations are sent at once and a failed one does not stop the
+    /// others; failures are reported per order in the [`CancelReport`]. Only
+    /// fetching the orders fails the call.
+    ///
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> kiteconnect::error::Result<()> {
+    /// let client = KiteConnect::new("api_key", "access_token");
+    /// let report = client.cancel_orders_by_tag("momentum").await?;
+    /// for failed in report.failed() {
+    ///     eprintln!("{} not cancelled: {:?}", failed.order_id, failed.result);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn cancel_orders_by_tag(&self, tag: &str) -> Result<CancelReport> {
+        let open: Vec<Order> = self
+            .orders_by_tag(tag)
+            .await?
+            .into_iter()
+            .filter(|order| !order.is_terminal())
+            .collect();
+        let results = futures::future::join_all(open.iter().map(|order| {
+            self.cancel_order(&order.order_id, &order.variety, order.parent_order_id.as_deref())
+        }))
+        .await;
+
+        let mut report = CancelReport::default();
+        for (order, result) in open.into_iter().zip(results) {
+            if let Err(e) = &result {
+                log::warn!("Failed to cancel order {} tagged {}: {}", order.order_id, tag, e);
+            }
+            report.orders.push(Cancellation {
+                order_id: order.order_id,
+                variety: order.variety,
+                tradingsymbol: order.tradingsymbol,
+                result: result.map(|_| ()),
+            });
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Server;
+    use serde_json::{json, Value as JsonValue};
+
+    #[test]
+    fn test_tag() {
+        let tag = Tag::new("MOM", "17").unwrap();
+        assert_eq!(tag.to_string(), "3MOM17");
+        assert_eq!("3MOM17".parse::<Tag>().unwrap(), tag);
+        assert_eq!(tag.strategy(), "MOM");
+        assert_eq!(tag.client_order_id(), "17");
+
+        let tag = Tag::numbered("pairs9", 123_456_789).unwrap();
+        assert_eq!(tag.to_string(), "6pairs921I3V9");
+        assert_eq!(tag.to_string().parse::<Tag>().unwrap().number(), Some(123_456_789));
+        assert_eq!(Tag::numbered("MOM", 0).unwrap().to_string(), "3MOM0");
+
+        let longest = Tag::new("STRATEGY9", "ABCDEFGHIJ").unwrap().to_string();
+        assert_eq!(longest.len(), MAX_TAG_LEN);
+        assert!(Tag::new("STRATEGY9", "ABCDEFGHIJK").is_err());
+        assert!(Tag::new("STRATEGY10", "1").is_err());
+        assert!(Tag::new("MOM", "").is_err());
+        assert!(Tag::new("MO-M", "1").is_err());
+        for invalid in ["", "0MOM1", "3MOM", "9MOM1", "momentum", "3MOM1-2"] {
+            assert!(invalid.parse::<Tag>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_orders_by_tag() {
+        let mut server = Server::new_async().await;
+        let client = KiteConnect::builder("API_KEY")
+            .access_token("ACCESS_TOKEN")
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+        let mut orders: JsonValue =
+            serde_json::from_str(&std::fs::read_to_string("mocks/orders.json").unwrap()).unwrap();
+        let template = orders["data"][0].clone();
+        let order = |order_id: &str, status: &str, tag: JsonValue, tags: JsonValue| {
+            let mut order = template.clone();
+            order["order_id"] = json!(order_id);
+            order["variety"] = json!("regular");
+            order["parent_order_id"] = JsonValue::Null;
+            order["status"] = json!(status);
+            order["tag"] = tag;
+            order["tags"] = tags;
+            order
+        };
+        orders["data"] = json!([
+            order("1", "OPEN", json!("momentum"), json!(["momentum"])),
+            order("2", "COMPLETE", json!("momentum"), json!(["momentum"])),
+            order("3", "TRIGGER PENDING", JsonValue::Null, json!(["momentum", "hedge"])),
+            order("4", "OPEN", json!("meanrev"), json!(["meanrev"])),
+        ]);
+        server
+            .mock("GET", "/orders")
+            .with_body(orders.to_string())
+            .create_async()
+            .await;
+        let cancelled = server
+            .mock("DELETE", "/orders/regular/1")
+            .with_body(r#"{"status": "success", "data": {"order_id": "1"}}"#)
+            .create_async()
+            .await;
+        server
+            .mock("DELETE", "/orders/regular/3")
+            .with_status(400)
+            .with_body(r#"{"status": "error", "message": "Order cannot be cancelled", "error_type": "OrderException"}"#)
+            .create_async()
+            .await;
+
+        let tagged = client.orders_by_tag("momentum").await.unwrap();
+        let ids: Vec<_> = tagged.iter().map(|order| order.order_id.as_str()).collect();
+        assert_eq!(ids, ["1", "2", "3"]);
+
+        let report = client.cancel_orders_by_tag("momentum").await.unwrap();
+        cancelled.assert_async().await;
+        assert_eq!(report.orders.len(), 2);
+        assert_eq!(report.cancelled().map(|order| order.order_id.as_str()).collect::<Vec<_>>(), ["1"]);
+        assert_eq!(report.failed().map(|order| order.order_id.as_str()).collect::<Vec<_>>(), ["3"]);
+        assert!(!report.is_complete());
+    }
+}
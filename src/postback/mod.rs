@@ -0,0 +1,108 @@
+//! # Order Postbacks
+//!
+//! Kite notifies the postback URL of a Connect app with a JSON `POST` request
+//! whenever one of its orders changes status. Each payload carries a `checksum`,
+//! the SHA-256 hex digest of `order_id + order_timestamp + api_secret`, which
+//! proves it was sent by Kite.
+//!
+//! [`verify_and_parse`] checks the checksum and returns the typed [`Order`]:
+//!
+//! ```rust
+//! use kiteconnect::postback;
+//!
+//! # fn handle(body: &[u8]) -> kiteconnect::error::Result<()> {
+//! let order = postback::verify_and_parse(body, "api_secret")?;
+//! println!("{} is now {}", order.order_id, order.status);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Fields of the postback that are not part of [`Order`], such as `user_id`,
+//! `app_id` and `unfilled_quantity`, are kept in its `extra` map.
+//!
+//! With the `postback-server` feature, [`PostbackServer`] serves the postback
+//! URL itself and delivers verified orders over a channel.
+
+#[cfg(feature = "postback-server")]
+mod server;
+
+#[cfg(feature = "postback-server")]
+pub use server::{PostbackHandle, PostbackServer};
+
+use crate::error::{KiteError, Result};
+use crate::models::Order;
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+
+/// Computes the checksum Kite sends with the postback of an order
+///
+/// `order_timestamp` must be the timestamp exactly as sent in the payload.
+pub fn checksum(order_id: &str, order_timestamp: &str, api_secret: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(order_id.as_bytes());
+    hasher.update(order_timestamp.as_bytes());
+    hasher.update(api_secret.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Verifies the checksum of a postback body without parsing it into a model
+///
+/// Returns [`KiteError::InvalidChecksum`] if the checksum is missing or does not
+/// match, and a deserialization error if the body is not a JSON object.
+pub fn verify(body: &[u8], api_secret: &str) -> Result<JsonValue> {
+    let payload: JsonValue = serde_json::from_slice(body)?;
+    let field = |name: &str| payload.get(name).and_then(JsonValue::as_str).unwrap_or_default();
+
+    let expected = checksum(field("order_id"), field("order_timestamp"), api_secret);
+    if !constant_time_eq(expected.as_bytes(), field("checksum").to_ascii_lowercase().as_bytes()) {
+        return Err(KiteError::InvalidChecksum);
+    }
+    Ok(payload)
+}
+
+/// Verifies the checksum of a postback body and parses it into an [`Order`]
+pub fn verify_and_parse(body: &[u8], api_secret: &str) -> Result<Order> {
+    Ok(serde_json::from_value(verify(body, api_secret)?)?)
+}
+
+/// Compares two byte strings in time independent of where they differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_and_parse() {
+        let body = std::fs::read("mocks/postback.json").unwrap();
+        let order = verify_and_parse(&body, "api_secret").unwrap();
+        assert_eq!(order.order_id, "16032300017157");
+        assert_eq!(order.status, "COMPLETE");
+        assert_eq!(order.filled_quantity, 1);
+        assert_eq!(order.extra["user_id"], "AB1234");
+
+        assert!(matches!(
+            verify_and_parse(&body, "wrong_secret"),
+            Err(KiteError::InvalidChecksum)
+        ));
+
+        let tampered = String::from_utf8(body).unwrap().replace("16032300017157", "16032300017158");
+        assert!(matches!(
+            verify_and_parse(tampered.as_bytes(), "api_secret"),
+            Err(KiteError::InvalidChecksum)
+        ));
+
+        assert!(matches!(verify(b"{}", "api_secret"), Err(KiteError::InvalidChecksum)));
+        assert!(matches!(verify(b"not json", "api_secret"), Err(KiteError::Deserialize(_))));
+    }
+
+    #[test]
+    fn test_checksum() {
+        assert_eq!(
+            checksum("16032300017157", "2016-03-23 08:58:29", "api_secret"),
+            "7afd1818622d804854c75c09eca09fb66df533c4aacc577596543aacc51d9747"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Embedded HTTP server receiving order postbacks
+
+use super::verify_and_parse;
+use crate::error::{KiteError, Result};
+use crate::models::Order;
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::convert::Infallible;
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio::sync::mpsc;
+
+/// Largest postback body accepted
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Ready-made server for the postback URL of a Connect app
+///
+/// Verifies the checksum of every postback and delivers the orders over a
+/// channel. Invalid checksums are answered with `401` and never delivered.
+///
+/// ```rust,no_run
+/// use kiteconnect::postback::PostbackServer;
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let (server, mut orders) = PostbackServer::new("api_secret")
+///     .path("/kite/postback")
+///     .bind("0.0.0.0:8080")
+///     .await?;
+/// println!("Listening on {}", server.local_addr());
+/// while let Some(order) = orders.recv().await {
+///     println!("{} is now {}", order.order_id, order.status);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct PostbackServer {
+    api_secret: String,
+    path: Option<String>,
+    capacity: usize,
+}
+
+impl fmt::Debug for PostbackServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PostbackServer")
+            .field("api_secret", &"***")
+            .field("path", &self.path)
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+impl PostbackServer {
+    /// Accepts postbacks on any path, verified with the given API secret
+    pub fn new(api_secret: &str) -> Self {
+        PostbackServer {
+            api_secret: api_secret.to_string(),
+            path: None,
+            capacity: 256,
+        }
+    }
+
+    /// Only accepts postbacks on this path, answering others with `404`
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// Sets how many orders are buffered before requests wait for the receiver, 256 by default
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Binds the address and serves postbacks in a background task
+    ///
+    /// The server stops when the returned handle or the receiver is dropped.
+    pub async fn bind(self, addr: impl ToSocketAddrs) -> Result<(PostbackHandle, mpsc::Receiver<Order>)> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| KiteError::Other(format!("failed to bind postback server: {}", e)))?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| KiteError::Other(format!("failed to bind postback server: {}", e)))?;
+
+        let (tx, rx) = mpsc::channel(self.capacity);
+        let task = tokio::spawn(serve(listener, Arc::new(self), tx));
+        Ok((PostbackHandle { local_addr, task }, rx))
+    }
+}
+
+/// Handle of a running postback server; dropping it stops the server
+#[derive(Debug)]
+pub struct PostbackHandle {
+    local_addr: SocketAddr,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl PostbackHandle {
+    /// Returns the address the server listens on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns `true` once the server has stopped, e.g. after the receiver was dropped
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Stops the server
+    pub fn stop(self) {}
+}
+
+impl Drop for PostbackHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Accepts connections until the receiver is dropped
+async fn serve(listener: TcpListener, server: Arc<PostbackServer>, tx: mpsc::Sender<Order>) {
+    loop {
+        let stream = tokio::select! {
+            _ = tx.closed() => return,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    log::warn!("Failed to accept postback connection: {}", e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            },
+        };
+
+        let server = server.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |req| {
+                let server = server.clone();
+                let tx = tx.clone();
+                async move { Ok::<_, Infallible>(handle(req, &server, &tx).await) }
+            });
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                log::debug!("Postback connection failed: {}", e);
+            }
+        });
+    }
+}
+
+/// Verifies one postback request and forwards its order
+async fn handle(req: Request<Incoming>, server: &PostbackServer, tx: &mpsc::Sender<Order>) -> Response<Full<Bytes>> {
+    if server.path.as_deref().is_some_and(|path| path != req.uri().path()) {
+        return respond(StatusCode::NOT_FOUND, "not found");
+    }
+    if req.method() != Method::POST {
+        return respond(StatusCode::METHOD_NOT_ALLOWED, "method not allowed");
+    }
+
+    let body = match Limited::new(req.into_body(), MAX_BODY_BYTES).collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(e) if e.is::<LengthLimitError>() => {
+            return respond(StatusCode::PAYLOAD_TOO_LARGE, "payload too large");
+        }
+        Err(_) => return respond(StatusCode::BAD_REQUEST, "failed to read body"),
+    };
+
+    match verify_and_parse(&body, &server.api_secret) {
+        Ok(order) => match tx.send(order).await {
+            Ok(()) => respond(StatusCode::OK, "ok"),
+            Err(_) => respond(StatusCode::SERVICE_UNAVAILABLE, "shutting down"),
+        },
+        Err(KiteError::InvalidChecksum) => {
+            log::warn!("Rejected postback with invalid checksum");
+            respond(StatusCode::UNAUTHORIZED, "invalid checksum")
+        }
+        Err(e) => {
+            log::warn!("Rejected malformed postback: {}", e);
+            respond(StatusCode::BAD_REQUEST, "malformed postback")
+        }
+    }
+}
+
+fn respond(status: StatusCode, body: &'static str) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from_static(body.as_bytes())));
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_postback_server() {
+        let (server, mut orders) = PostbackServer::new("api_secret")
+            .path("/postback")
+            .bind("127.0.0.1:0")
+            .await
+            .unwrap();
+        let url = format!("http://{}/postback", server.local_addr());
+        let body = std::fs::read("mocks/postback.json").unwrap();
+        let client = reqwest::Client::new();
+
+        let resp = client.post(&url).body(body.clone()).send().await.unwrap();
+        assert_eq!(resp.status(), 200);
+        let order = orders.recv().await.unwrap();
+        assert_eq!(order.order_id, "16032300017157");
+        assert_eq!(order.status, "COMPLETE");
+
+        let tampered = String::from_utf8(body.clone()).unwrap().replace("16032300017157", "16032300017158");
+        let resp = client.post(&url).body(tampered).send().await.unwrap();
+        assert_eq!(resp.status(), 401);
+        let resp = client.post(&url).body("not json").send().await.unwrap();
+        assert_eq!(resp.status(), 400);
+        let resp = client.get(&url).send().await.unwrap();
+        assert_eq!(resp.status(), 405);
+        let resp = client
+            .post(format!("http://{}/other", server.local_addr()))
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 404);
+        assert!(orders.try_recv().is_err());
+
+        drop(orders);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !server.is_finished() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,196 @@
+//! # Proxy Configuration
+//!
+//! Corporate networks and servers outside India often have to reach Kite through
+//! a proxy. A [`ProxyConfig`] set on the [`KiteConnectBuilder`] is applied to the
+//! REST client. The websocket ticker is routed through it by passing it on to
+//! [`TickerBuilder::proxy`], which tunnels the connection with HTTP `CONNECT`
+//! or SOCKS5.
+//!
+//! HTTP and HTTPS proxies work out of the box for REST requests; SOCKS proxies
+//! (`socks5://`, `socks5h://`, `socks4://`) require the `socks` feature. The
+//! ticker supports `http://`, `socks5://` and `socks5h://` proxies.
+//!
+//! ```rust
+//! use kiteconnect::connect::KiteConnect;
+//! use kiteconnect::proxy::ProxyConfig;
+//!
+//! # fn main() -> kiteconnect::error::Result<()> {
+//! let client = KiteConnect::builder("api_key")
+//!     .access_token("access_token")
+//!     .proxy(
+//!         ProxyConfig::all("http://proxy.internal:3128")
+//!             .basic_auth("user", "secret")
+//!             .no_proxy("localhost,127.0.0.1"),
+//!     )
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! On wasm targets the browser manages proxies for both fetch requests and
+//! websockets, so the configuration is only validated.
+//!
+//! [`KiteConnectBuilder`]: crate::connect::KiteConnectBuilder
+//! [`TickerBuilder::proxy`]: crate::ticker::TickerBuilder::proxy
+
+use crate::error::{KiteError, Result};
+use std::fmt;
+
+/// Which requests are sent through a proxy
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProxyScope {
+    /// Every request
+    All,
+    /// Plain `http://` requests only
+    Http,
+    /// `https://` (and `wss://`) requests only
+    Https,
+}
+
+/// Proxy used for REST requests and the websocket ticker
+#[derive(Clone, PartialEq, Eq)]
+pub struct ProxyConfig {
+    /// Proxy URL, e.g. `http://proxy:3128` or `socks5h://proxy:1080`
+    pub url: String,
+    /// Which requests are proxied
+    pub scope: ProxyScope,
+    /// Credentials for proxies requiring basic authentication
+    pub credentials: Option<(String, String)>,
+    /// Comma separated hosts, domains or CIDR ranges that bypass the proxy
+    pub no_proxy: Option<String>,
+}
+
+impl fmt::Debug for ProxyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyConfig")
+            .field("url", &self.url)
+            .field("scope", &self.scope)
+            .field("credentials", &self.credentials.as_ref().map(|(user, _)| (user, "***")))
+            .field("no_proxy", &self.no_proxy)
+            .finish()
+    }
+}
+
+impl ProxyConfig {
+    fn new(url: &str, scope: ProxyScope) -> Self {
+        ProxyConfig {
+            url: url.to_string(),
+            scope,
+            credentials: None,
+            no_proxy: None,
+        }
+    }
+
+    /// Proxies every request through the given URL
+    pub fn all(url: &str) -> Self {
+        Self::new(url, ProxyScope::All)
+    }
+
+    /// Proxies plain `http://` requests through the given URL
+    pub fn http(url: &str) -> Self {
+        Self::new(url, ProxyScope::Http)
+    }
+
+    /// Proxies `https://` and `wss://` requests through the given URL
+    pub fn https(url: &str) -> Self {
+        Self::new(url, ProxyScope::Https)
+    }
+
+    /// Authenticates with the proxy using basic authentication
+    pub fn basic_auth(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    /// Sets the comma separated list of hosts that bypass the proxy
+    pub fn no_proxy(mut self, hosts: &str) -> Self {
+        self.no_proxy = Some(hosts.to_string());
+        self
+    }
+
+    /// Returns `true` for SOCKS proxies
+    pub fn is_socks(&self) -> bool {
+        self.url.to_ascii_lowercase().starts_with("socks")
+    }
+
+    /// Returns `true` if requests to the given URL go through this proxy
+    pub fn applies_to(&self, url: &url::Url) -> bool {
+        let secure = matches!(url.scheme(), "https" | "wss");
+        let in_scope = match self.scope {
+            ProxyScope::All => true,
+            ProxyScope::Http => !secure,
+            ProxyScope::Https => secure,
+        };
+        in_scope && !self.bypasses(url.host_str().unwrap_or_default())
+    }
+
+    /// Returns `true` if the host matches an entry of the `no_proxy` list
+    fn bypasses(&self, host: &str) -> bool {
+        let Some(no_proxy) = &self.no_proxy else {
+            return false;
+        };
+        no_proxy.split(',').map(str::trim).any(|entry| {
+            let entry = entry.trim_start_matches('.');
+            entry == "*" || host == entry || host.ends_with(&format!(".{}", entry))
+        })
+    }
+
+    /// Checks that the proxy URL is valid and supported by this build
+    pub(crate) fn validate(&self) -> Result<()> {
+        let url = url::Url::parse(&self.url)
+            .map_err(|e| KiteError::Other(format!("invalid proxy URL {:?}: {}", self.url, e)))?;
+        match url.scheme() {
+            "http" | "https" => Ok(()),
+            "socks4" | "socks4a" | "socks5" | "socks5h" if cfg!(feature = "socks") => Ok(()),
+            "socks4" | "socks4a" | "socks5" | "socks5h" => Err(KiteError::Other(
+                "SOCKS proxies require the `socks` feature".to_string(),
+            )),
+            scheme => Err(KiteError::Other(format!("unsupported proxy scheme {:?}", scheme))),
+        }
+    }
+
+    /// Converts the configuration into a reqwest proxy
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn to_reqwest(&self) -> Result<reqwest::Proxy> {
+        self.validate()?;
+        let proxy = match self.scope {
+            ProxyScope::All => reqwest::Proxy::all(&self.url)?,
+            ProxyScope::Http => reqwest::Proxy::http(&self.url)?,
+            ProxyScope::Https => reqwest::Proxy::https(&self.url)?,
+        };
+        let proxy = match &self.credentials {
+            Some((username, password)) => proxy.basic_auth(username, password),
+            None => proxy,
+        };
+        Ok(proxy.no_proxy(self.no_proxy.as_deref().and_then(reqwest::NoProxy::from_string)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proxy_config() {
+        let proxy = ProxyConfig::https("http://proxy:3128")
+            .basic_auth("user", "secret")
+            .no_proxy("localhost, .internal");
+        assert!(proxy.validate().is_ok());
+        assert!(!proxy.is_socks());
+        assert!(!format!("{:?}", proxy).contains("secret"));
+
+        let url = |s: &str| url::Url::parse(s).unwrap();
+        assert!(proxy.applies_to(&url("https://api.kite.trade/quote")));
+        assert!(proxy.applies_to(&url("wss://ws.kite.trade")));
+        assert!(!proxy.applies_to(&url("http://api.kite.trade/quote")));
+        assert!(!proxy.applies_to(&url("https://localhost/quote")));
+        assert!(!proxy.applies_to(&url("https://kite.internal/quote")));
+
+        assert!(ProxyConfig::all("not a url").validate().is_err());
+        assert!(ProxyConfig::all("ftp://proxy:21").validate().is_err());
+        assert_eq!(
+            ProxyConfig::all("socks5h://proxy:1080").validate().is_ok(),
+            cfg!(feature = "socks")
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//! # Rate Limiting
+//!
+//! Kite enforces per-endpoint request rate limits and answers with `429 Too Many
+//! Requests` once they are exceeded. The client-side [`RateLimiter`] keeps a token
+//! bucket per [`EndpointCategory`] and delays requests that would exceed the limit,
+//! so concurrent tasks sharing a `KiteConnect` (and its clones) stay within budget.
+//!
+//! Rate limiting is opt-in and configured through the builder:
+//!
+//! ```rust
+//! use kiteconnect::connect::KiteConnect;
+//! use kiteconnect::ratelimit::RateLimits;
+//!
+//! # fn main() -> kiteconnect::error::Result<()> {
+//! let client = KiteConnect::builder("api_key")
+//!     .access_token("access_token")
+//!     .rate_limits(RateLimits::default())
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Groups of endpoints sharing a rate limit
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EndpointCategory {
+    /// Market quotes (`/quote`, `/quote/ohlc`, `/quote/ltp`)
+    Quote,
+    /// Historical candle data (`/instruments/historical`)
+    Historical,
+    /// Order placement, modification and cancellation
+    Orders,
+    /// Every other endpoint
+    Default,
+}
+
+impl EndpointCategory {
+    /// Classifies a request by its method and URL path
+    pub fn for_request(method: &str, path: &str) -> Self {
+        if path.starts_with("/quote") {
+            EndpointCategory::Quote
+        } else if path.starts_with("/instruments/historical") {
+            EndpointCategory::Historical
+        } else if method != "GET" && path.starts_with("/orders") {
+            EndpointCategory::Orders
+        } else {
+            EndpointCategory::Default
+        }
+    }
+}
+
+/// Requests per second allowed for each [`EndpointCategory`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimits {
+    /// Limit for market quotes
+    pub quote: f64,
+    /// Limit for historical candle data
+    pub historical: f64,
+    /// Limit for order placement, modification and cancellation
+    pub orders: f64,
+    /// Limit for every other endpoint
+    pub default: f64,
+}
+
+impl Default for RateLimits {
+    /// The limits documented by Kite
+    fn default() -> Self {
+        RateLimits {
+            quote: 3.0,
+            historical: 1.0,
+            orders: 10.0,
+            default: 10.0,
+        }
+    }
+}
+
+impl RateLimits {
+    /// Returns the requests per second allowed for a category
+    pub fn limit(&self, category: EndpointCategory) -> f64 {
+        match category {
+            EndpointCategory::Quote => self.quote,
+            EndpointCategory::Historical => self.historical,
+            EndpointCategory::Orders => self.orders,
+            EndpointCategory::Default => self.default,
+        }
+    }
+}
+
+/// Token bucket that refills continuously at `rate` tokens per second
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    updated: f64,
+}
+
+impl Bucket {
+    fn new(rate: f64, now: f64) -> Self {
+        Bucket {
+            rate,
+            tokens: rate.max(1.0),
+            updated: now,
+        }
+    }
+
+    /// Takes a token and returns how long to wait before it may be used
+    ///
+    /// The balance may go negative, which queues callers in arrival order.
+    fn reserve(&mut self, now: f64) -> Duration {
+        let capacity = self.rate.max(1.0);
+        let elapsed = (now - self.updated).max(0.0);
+        self.tokens = (self.tokens + elapsed * self.rate).min(capacity);
+        self.updated = now;
+        self.tokens -= 1.0;
+
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+
+    /// Drains the bucket so the next request waits at least `wait`
+    fn pause(&mut self, wait: Duration, now: f64) {
+        let elapsed = (now - self.updated).max(0.0);
+        self.tokens = (self.tokens + elapsed * self.rate).min(0.0) + 1.0 - wait.as_secs_f64() * self.rate;
+        self.updated = now;
+    }
+}
+
+/// Per-category token bucket rate limiter shared by clones of a client
+#[derive(Debug)]
+pub struct RateLimiter {
+    limits: RateLimits,
+    buckets: Mutex<HashMap<EndpointCategory, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Creates a rate limiter enforcing the given limits
+    pub fn new(limits: RateLimits) -> Self {
+        RateLimiter {
+            limits,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the limits enforced by this rate limiter
+    pub fn limits(&self) -> &RateLimits {
+        &self.limits
+    }
+
+    /// Waits until a request in the given category may be sent
+    pub async fn acquire(&self, category: EndpointCategory) {
+        let wait = self.reserve(category, crate::rt::now_secs());
+        if !wait.is_zero() {
+            log::debug!("Rate limiting {:?} request for {:?}", category, wait);
+            crate::rt::sleep(wait).await;
+        }
+    }
+
+    /// Holds back further requests in a category after the API asked to slow down
+    ///
+    /// Called with the `Retry-After` delay of a `429` response so that concurrent
+    /// tasks sharing the limiter back off together.
+    pub fn pause(&self, category: EndpointCategory, wait: Duration) {
+        self.pause_at(category, wait, crate::rt::now_secs());
+    }
+
+    fn pause_at(&self, category: EndpointCategory, wait: Duration, now: f64) {
+        let rate = self.limits.limit(category);
+        if rate <= 0.0 || !rate.is_finite() {
+            return;
+        }
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        buckets
+            .entry(category)
+            .or_insert_with(|| Bucket::new(rate, now))
+            .pause(wait, now);
+    }
+
+    fn reserve(&self, category: EndpointCategory, now: f64) -> Duration {
+        let rate = self.limits.limit(category);
+        if rate <= 0.0 || !rate.is_finite() {
+            return Duration::ZERO;
+        }
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        buckets
+            .entry(category)
+            .or_insert_with(|| Bucket::new(rate, now))
+            .reserve(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_category() {
+        assert_eq!(EndpointCategory::for_request("GET", "/quote/ltp"), EndpointCategory::Quote);
+        assert_eq!(
+            EndpointCategory::for_request("GET", "/instruments/historical/5633/minute"),
+            EndpointCategory::Historical
+        );
+        assert_eq!(EndpointCategory::for_request("POST", "/orders/regular"), EndpointCategory::Orders);
+        assert_eq!(EndpointCategory::for_request("GET", "/orders"), EndpointCategory::Default);
+        assert_eq!(EndpointCategory::for_request("GET", "/instruments"), EndpointCategory::Default);
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let limiter = RateLimiter::new(RateLimits::default());
+
+        // A full bucket allows a burst of `quote` requests, then spaces them out
+        for _ in 0..3 {
+            assert_eq!(limiter.reserve(EndpointCategory::Quote, 100.0), Duration::ZERO);
+        }
+        let wait = limiter.reserve(EndpointCategory::Quote, 100.0);
+        assert!((wait.as_secs_f64() - 1.0 / 3.0).abs() < 1e-9);
+        let wait = limiter.reserve(EndpointCategory::Quote, 100.0);
+        assert!((wait.as_secs_f64() - 2.0 / 3.0).abs() < 1e-9);
+
+        // Categories are independent
+        assert_eq!(limiter.reserve(EndpointCategory::Historical, 100.0), Duration::ZERO);
+        assert_eq!(limiter.reserve(EndpointCategory::Historical, 100.5), Duration::from_millis(500));
+
+        // The bucket refills over time
+        assert_eq!(limiter.reserve(EndpointCategory::Quote, 102.0), Duration::ZERO);
+
+        // A `Retry-After` hint holds back the next request
+        limiter.pause_at(EndpointCategory::Default, Duration::from_secs(2), 200.0);
+        let wait = limiter.reserve(EndpointCategory::Default, 200.0);
+        assert!((wait.as_secs_f64() - 2.0).abs() < 1e-9);
+    }
+}
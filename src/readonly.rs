@@ -0,0 +1,72 @@
+//! # Read-only Mode
+//!
+//! A client in read-only mode reads live data as usual but never sends order
+//! placements, modifications or cancellations, GTT triggers or position
+//! conversions. Each of those is logged with its payload and either fails
+//! with [`KiteError::ReadOnly`] or answers with a simulated success, so a
+//! strategy can run against the live market without trading:
+//!
+//! ```rust
+//! use kiteconnect::connect::KiteConnect;
+//! use kiteconnect::readonly::ReadOnlyMode;
+//!
+//! # fn main() -> kiteconnect::error::Result<()> {
+//! let client = KiteConnect::builder("api_key")
+//!     .access_token("access_token")
+//!     .read_only(ReadOnlyMode::Simulate)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Simulated orders never reach the OMS, so helpers that wait for an order to
+//! fill, such as [`place_order_and_wait`], fail to find them.
+//!
+//! [`KiteError::ReadOnly`]: crate::error::KiteError::ReadOnly
+//! [`place_order_and_wait`]: crate::connect::KiteConnect::place_order_and_wait
+
+use serde_json::{json, Value as JsonValue};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Number of the last simulated order or trigger ID
+static SIMULATED_IDS: AtomicU64 = AtomicU64::new(0);
+
+/// What a client in read-only mode does with requests that would trade
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReadOnlyMode {
+    /// Fail with [`KiteError::ReadOnly`](crate::error::KiteError::ReadOnly)
+    Reject,
+    /// Answer as the API would on success, with order IDs starting with
+    /// `DRYRUN` and made up trigger IDs for new orders and triggers
+    Simulate,
+}
+
+/// Returns the `data` the API would answer a successful mutation with
+pub(crate) fn simulated_data(method: &str, path: &str) -> JsonValue {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let next_id = || SIMULATED_IDS.fetch_add(1, Ordering::Relaxed) + 1;
+    match segments.as_slice() {
+        ["orders", _, order_id] => json!({ "order_id": order_id }),
+        ["orders", _] => json!({ "order_id": format!("DRYRUN{}", next_id()) }),
+        ["gtt", "triggers", trigger_id] => json!({ "trigger_id": trigger_id.parse::<u64>().unwrap_or_default() }),
+        ["gtt", "triggers"] if method == "POST" => json!({ "trigger_id": next_id() }),
+        ["portfolio", "positions"] => json!(true),
+        _ => json!({}),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulated_data() {
+        let placed = simulated_data("POST", "/orders/regular");
+        assert!(placed["order_id"].as_str().unwrap().starts_with("DRYRUN"));
+        assert_ne!(simulated_data("POST", "/orders/regular"), placed);
+        assert_eq!(simulated_data("PUT", "/orders/regular/151220000000000"), json!({"order_id": "151220000000000"}));
+        assert_eq!(simulated_data("DELETE", "/gtt/triggers/123"), json!({"trigger_id": 123}));
+        assert!(simulated_data("POST", "/gtt/triggers")["trigger_id"].as_u64().unwrap() > 0);
+        assert_eq!(simulated_data("PUT", "/portfolio/positions"), json!(true));
+    }
+}
@@ -0,0 +1,133 @@
+//! Masking of credentials in logged URLs and payloads
+
+use reqwest::header::{HeaderMap, AUTHORIZATION};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+/// Query and form parameters whose values must never be logged
+pub(crate) const SENSITIVE_KEYS: [&str; 11] = [
+    "api_key",
+    "api_secret",
+    "access_token",
+    "request_token",
+    "refresh_token",
+    "public_token",
+    "checksum",
+    "password",
+    "totp",
+    "twofa_value",
+    "enctoken",
+];
+
+/// Placeholder written in place of sensitive values
+pub(crate) const MASK: &str = "***";
+
+/// Returns `true` if values of the given parameter must be masked
+pub(crate) fn is_sensitive(key: &str) -> bool {
+    SENSITIVE_KEYS.iter().any(|sensitive| key.eq_ignore_ascii_case(sensitive))
+}
+
+/// Renders a URL with the values of sensitive query parameters masked
+pub(crate) fn redact_url(url: &url::Url) -> String {
+    if !url.query_pairs().any(|(key, _)| is_sensitive(&key)) {
+        return url.to_string();
+    }
+    let mut redacted = url.clone();
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(key, value)| {
+            let value = if is_sensitive(&key) { MASK.to_string() } else { value.into_owned() };
+            (key.into_owned(), value)
+        })
+        .collect();
+    redacted.query_pairs_mut().clear().extend_pairs(pairs);
+    redacted.to_string()
+}
+
+/// Renders request headers with the `Authorization` credentials masked
+pub(crate) fn redact_headers(headers: &HeaderMap) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if name == AUTHORIZATION {
+                let scheme = value.to_str().unwrap_or_default().split(' ').next().unwrap_or_default();
+                format!("{} {}", scheme, MASK)
+            } else {
+                value.to_str().unwrap_or("<binary>").to_string()
+            };
+            format!("{}: {}", name, value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Renders form parameters with sensitive values masked, sorted by key
+pub(crate) fn redact_form(data: &HashMap<&str, &str>) -> String {
+    let mut pairs: Vec<String> = data
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, if is_sensitive(key) { MASK } else { value }))
+        .collect();
+    pairs.sort();
+    pairs.join("&")
+}
+
+/// Masks the values of sensitive keys anywhere in a JSON body
+///
+/// Bodies that are not JSON are returned unchanged.
+pub(crate) fn redact_body(body: &str) -> String {
+    match serde_json::from_str::<JsonValue>(body) {
+        Ok(mut jsn) => {
+            redact_json(&mut jsn);
+            jsn.to_string()
+        }
+        Err(_) => body.to_string(),
+    }
+}
+
+fn redact_json(jsn: &mut JsonValue) {
+    match jsn {
+        JsonValue::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_sensitive(key) && !value.is_null() {
+                    *value = JsonValue::String(MASK.to_string());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        JsonValue::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_url() {
+        let url = url::Url::parse("https://kite.trade/connect/login?api_key=secret&v=3").unwrap();
+        assert_eq!(redact_url(&url), "https://kite.trade/connect/login?api_key=***&v=3");
+
+        let url = url::Url::parse("https://api.kite.trade/quote?i=NSE:INFY").unwrap();
+        assert_eq!(redact_url(&url), "https://api.kite.trade/quote?i=NSE:INFY");
+    }
+
+    #[test]
+    fn test_redact_payloads() {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, "token key:secret".parse().unwrap());
+        headers.insert("XKiteVersion", "3".parse().unwrap());
+        assert_eq!(redact_headers(&headers), "authorization: token ***, xkiteversion: 3");
+
+        let form = HashMap::from([("api_key", "key"), ("checksum", "abc"), ("request_token", "rt"), ("v", "3")]);
+        assert_eq!(redact_form(&form), "api_key=***&checksum=***&request_token=***&v=3");
+
+        let body = std::fs::read_to_string("mocks/generate_session.json").unwrap();
+        let redacted = redact_body(&body);
+        assert!(!redacted.contains("yyyyyy"));
+        assert!(redacted.contains(r#""access_token":"***""#));
+        assert!(redacted.contains(r#""user_id""#));
+        assert_eq!(redact_body("<html></html>"), "<html></html>");
+    }
+}
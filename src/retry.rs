@@ -0,0 +1,264 @@
+//! # Retry Policy
+//!
+//! Transient failures such as `429 Too Many Requests`, gateway `5xx` responses and
+//! connection timeouts are common when talking to the Kite API. A [`RetryPolicy`]
+//! configured on the client retries idempotent `GET` requests with exponential
+//! backoff, so these hiccups don't have to be handled in every strategy loop.
+//!
+//! Orders are never placed, modified or cancelled twice: only `GET` requests are
+//! retried, and reads of the orderbook and tradebook (`/orders`, `/trades`) are
+//! only retried when [`RetryPolicy::retry_order_reads`] is enabled. The one
+//! exception are modifications and cancellations the OMS turned down because
+//! the order was still being processed, e.g. right after it was placed; they
+//! had no effect and are sent again up to
+//! [`RetryPolicy::order_processing_retries`] times.
+//!
+//! `429` responses are retried after the delay requested by the `Retry-After`
+//! header (or [`DEFAULT_RETRY_AFTER`] without one, and at most
+//! [`MAX_RETRY_AFTER`]) instead of the backoff delay.
+//! If the requested delay exceeds [`RetryPolicy::max_delay`], the request is not
+//! retried and the [`KiteError::RateLimited`](crate::error::KiteError::RateLimited)
+//! error is returned right away.
+//!
+//! ```rust
+//! use kiteconnect::connect::KiteConnect;
+//! use kiteconnect::retry::RetryPolicy;
+//! use std::time::Duration;
+//!
+//! let mut client = KiteConnect::new("api_key", "access_token");
+//! client.set_retry_policy(
+//!     RetryPolicy::default()
+//!         .max_attempts(5)
+//!         .base_delay(Duration::from_millis(250))
+//!         .retry_order_reads(true),
+//! );
+//! ```
+
+use crate::error::{Result, DEFAULT_RETRY_AFTER};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::time::Duration;
+
+/// Longest `Retry-After` delay taken from a response; longer ones are cut to it
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Configuration for retrying failed requests with exponential backoff
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for every subsequent retry
+    pub base_delay: Duration,
+    /// Upper bound for the delay between two attempts
+    pub max_delay: Duration,
+    /// Whether to randomise delays to avoid retrying in lockstep
+    pub jitter: bool,
+    /// Whether orderbook and tradebook reads are retried as well
+    pub retry_order_reads: bool,
+    /// Retries of an order modification or cancellation rejected as the
+    /// order is still being processed
+    pub order_processing_retries: u32,
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts starting at 200ms with jitter, order reads excluded,
+    /// and three retries of orders still being processed
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            jitter: true,
+            retry_order_reads: false,
+            order_processing_retries: 3,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            order_processing_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the total number of attempts, including the first one
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Sets the delay before the first retry
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Sets the upper bound for the delay between two attempts
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Enables or disables jitter
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Enables or disables retrying orderbook and tradebook reads
+    pub fn retry_order_reads(mut self, retry_order_reads: bool) -> Self {
+        self.retry_order_reads = retry_order_reads;
+        self
+    }
+
+    /// Sets how often an order modification or cancellation rejected as the
+    /// order is still being processed is retried
+    pub fn order_processing_retries(mut self, retries: u32) -> Self {
+        self.order_processing_retries = retries;
+        self
+    }
+
+    /// Returns the number of attempts to make for a request
+    pub(crate) fn attempts_for(&self, method: &str, path: &str) -> u32 {
+        if method != "GET" || (is_order_read(path) && !self.retry_order_reads) {
+            1
+        } else {
+            self.max_attempts.max(1)
+        }
+    }
+
+    /// Returns the delay to wait after the given (1-based) failed attempt
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .saturating_mul(1u32 << exponent)
+            .min(self.max_delay);
+        if self.jitter {
+            // Equal jitter: keep half of the delay, randomise the other half
+            delay / 2 + delay.mul_f64(crate::rt::random() / 2.0)
+        } else {
+            delay
+        }
+    }
+}
+
+/// Returns `true` for endpoints reading the orderbook or tradebook
+fn is_order_read(path: &str) -> bool {
+    ["/orders", "/trades", "/mf/orders"]
+        .iter()
+        .any(|prefix| path == *prefix || path.starts_with(&format!("{}/", prefix)))
+}
+
+/// Returns `true` if the outcome of an attempt is worth retrying
+pub(crate) fn should_retry(outcome: &Result<reqwest::Response>) -> bool {
+    match outcome {
+        Ok(resp) => is_retryable_status(resp.status().as_u16()),
+        Err(err) => err.is_retryable(),
+    }
+}
+
+/// Parses the `Retry-After` header, given either in seconds or as an HTTP date,
+/// capped at [`MAX_RETRY_AFTER`]
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<f64>() {
+        if secs.is_nan() || secs < 0.0 {
+            return None;
+        }
+        let wait = Duration::try_from_secs_f64(secs).unwrap_or(MAX_RETRY_AFTER);
+        return Some(wait.min(MAX_RETRY_AFTER));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.timestamp_millis().saturating_sub(chrono::Utc::now().timestamp_millis());
+    Some(Duration::from_millis(wait.max(0) as u64).min(MAX_RETRY_AFTER))
+}
+
+/// Returns the delay to honour before retrying a `429` response, if any
+pub(crate) fn rate_limit_delay(outcome: &Result<reqwest::Response>) -> Option<Duration> {
+    match outcome {
+        Ok(resp) if resp.status().as_u16() == 429 => {
+            Some(retry_after(resp.headers()).unwrap_or(DEFAULT_RETRY_AFTER))
+        }
+        _ => None,
+    }
+}
+
+/// Returns `true` for `429 Too Many Requests` and `5xx` statuses
+pub(crate) fn is_retryable_status(status: u16) -> bool {
+    status == 429 || (500..=599).contains(&status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attempts_for() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.attempts_for("GET", "/portfolio/holdings"), 3);
+        assert_eq!(policy.attempts_for("POST", "/orders/regular"), 1);
+        assert_eq!(policy.attempts_for("GET", "/orders"), 1);
+        assert_eq!(policy.attempts_for("GET", "/orders/171228000850038/trades"), 1);
+
+        let policy = policy.retry_order_reads(true);
+        assert_eq!(policy.attempts_for("GET", "/orders"), 3);
+        assert_eq!(policy.attempts_for("DELETE", "/orders/regular/1"), 1);
+
+        assert_eq!(RetryPolicy::none().attempts_for("GET", "/quote"), 1);
+        assert_eq!(RetryPolicy::none().order_processing_retries, 0);
+        assert_eq!(RetryPolicy::default().max_attempts(0).attempts_for("GET", "/quote"), 1);
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let policy = RetryPolicy::default()
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(350))
+            .jitter(false);
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(350));
+        assert_eq!(policy.delay(40), Duration::from_millis(350));
+
+        let policy = policy.jitter(true);
+        for attempt in 1..5 {
+            let delay = policy.delay(attempt);
+            assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(350));
+        }
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, "3".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(3)));
+
+        headers.insert(RETRY_AFTER, "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, "soon".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+
+        // Values beyond what a Duration holds are capped rather than panicking
+        for huge in ["1e20", "inf", "Fri, 31 Dec 9999 23:59:59 GMT"] {
+            headers.insert(RETRY_AFTER, huge.parse().unwrap());
+            assert_eq!(retry_after(&headers), Some(MAX_RETRY_AFTER));
+        }
+        headers.insert(RETRY_AFTER, "NaN".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_retryable_status() {
+        assert!(is_retryable_status(429));
+        assert!(is_retryable_status(502));
+        assert!(!is_retryable_status(400));
+        assert!(!is_retryable_status(403));
+    }
+}
@@ -0,0 +1,56 @@
+//! Small runtime helpers that work on both native and wasm targets
+
+use std::time::Duration;
+
+/// Sleeps for the given duration without blocking the executor
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+/// Sleeps for the given duration without blocking the executor
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep(duration: Duration) {
+    let millis = duration.as_millis().min(i32::MAX as u128) as i32;
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let scheduled = web_sys::window().map(|window| {
+            window.set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, millis)
+        });
+        if !matches!(scheduled, Some(Ok(_))) {
+            let _ = resolve.call0(&wasm_bindgen::JsValue::NULL);
+        }
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// Returns a pseudo-random number in `[0, 1)`, good enough for jitter
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn random() -> f64 {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    // Every `RandomState` is seeded differently, which avoids pulling in `rand`
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Returns a pseudo-random number in `[0, 1)`, good enough for jitter
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn random() -> f64 {
+    js_sys::Math::random()
+}
+
+/// Returns the current wall clock time in seconds, usable for measuring intervals
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now_secs() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs_f64())
+        .unwrap_or_default()
+}
+
+/// Returns the current wall clock time in seconds, usable for measuring intervals
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now_secs() -> f64 {
+    js_sys::Date::now() / 1000.0
+}
@@ -0,0 +1,22 @@
+//! Fixtures shared by the unit tests
+
+use crate::connect::KiteConnect;
+use crate::models::Order;
+use mockito::Server;
+
+/// Creates a client talking to the given mock server
+pub(crate) fn mock_client(server: &Server) -> KiteConnect {
+    KiteConnect::builder("API_KEY")
+        .access_token("ACCESS_TOKEN")
+        .base_url(&server.url())
+        .build()
+        .unwrap()
+}
+
+/// Loads the postback fixture as an update for the given order and status
+pub(crate) fn order_update(order_id: &str, status: &str) -> Order {
+    let mut order: Order = serde_json::from_slice(&std::fs::read("mocks/postback.json").unwrap()).unwrap();
+    order.order_id = order_id.to_string();
+    order.status = status.to_string();
+    order
+}
@@ -0,0 +1,172 @@
+//! Fan-out of one ticker connection to several consumers
+
+use super::{KiteTicker, Tick, TickerEvent, TickerHandle};
+use crate::models::InstrumentToken;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, watch};
+
+/// Latest tick of each watched instrument token
+type Watches = Arc<Mutex<HashMap<InstrumentToken, watch::Sender<Option<Tick>>>>>;
+
+/// One ticker connection shared by several consumers
+///
+/// Kite limits the number of websocket connections per access token, so
+/// strategies running in separate tasks should share a connection instead of
+/// opening their own. Every [`subscribe`](Self::subscribe)r receives all events,
+/// while [`watch`](Self::watch) only keeps the latest tick of one instrument.
+///
+/// ```rust,no_run
+/// use kiteconnect::models::InstrumentToken;
+/// use kiteconnect::ticker::{KiteTicker, TickerEvent};
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let ticker = KiteTicker::connect("api_key", "access_token").await?;
+/// ticker.subscribe([InstrumentToken(408065), InstrumentToken(884737)])?;
+/// let broadcast = ticker.broadcast(1024);
+///
+/// let mut events = broadcast.subscribe();
+/// tokio::spawn(async move {
+///     while let Ok(event) = events.recv().await {
+///         if let TickerEvent::Ticks(ticks) = event {
+///             println!("{:?}", ticks);
+///         }
+///     }
+/// });
+///
+/// let mut infy = broadcast.watch(InstrumentToken(408065));
+/// while infy.changed().await.is_ok() {
+///     if let Some(tick) = infy.borrow_and_update().as_ref() {
+///         println!("INFY at {}", tick.last_price);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TickerBroadcast {
+    handle: TickerHandle,
+    events: broadcast::Sender<TickerEvent>,
+    watches: Watches,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl KiteTicker {
+    /// Forwards the events of this ticker to any number of consumers
+    ///
+    /// Each subscriber buffers up to `capacity` events; one that falls further
+    /// behind misses the oldest and receives [`broadcast::error::RecvError::Lagged`].
+    pub fn broadcast(self, capacity: usize) -> TickerBroadcast {
+        let handle = self.handle();
+        let (events, _) = broadcast::channel(capacity.max(1));
+        let watches = Watches::default();
+        let task = tokio::spawn(forward(self, events.clone(), watches.clone()));
+        TickerBroadcast {
+            handle,
+            events,
+            watches,
+            task,
+        }
+    }
+}
+
+impl TickerBroadcast {
+    /// Returns a receiver of all events delivered from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<TickerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Returns a receiver of the latest tick of `instrument_token`
+    ///
+    /// The token must be subscribed separately, e.g. through [`handle`](Self::handle).
+    /// The value is `None` until the first tick arrives.
+    pub fn watch(&self, instrument_token: impl Into<InstrumentToken>) -> watch::Receiver<Option<Tick>> {
+        self.watches
+            .lock()
+            .unwrap()
+            .entry(instrument_token.into())
+            .or_insert_with(|| watch::channel(None).0)
+            .subscribe()
+    }
+
+    /// Returns a handle changing the subscriptions of the shared connection
+    pub fn handle(&self) -> TickerHandle {
+        self.handle.clone()
+    }
+
+    /// Returns `true` once the ticker has stopped and delivered [`TickerEvent::Closed`]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl Drop for TickerBroadcast {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn forward(mut ticker: KiteTicker, events: broadcast::Sender<TickerEvent>, watches: Watches) {
+    while let Some(event) = ticker.next_event().await {
+        if let TickerEvent::Ticks(ticks) = &event {
+            let watches = watches.lock().unwrap();
+            for tick in ticks {
+                if let Some(latest) = watches.get(&tick.instrument_token) {
+                    latest.send_replace(Some(tick.clone()));
+                }
+            }
+        }
+        // Events are only dropped while nobody subscribed
+        let _ = events.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    #[tokio::test]
+    async fn test_broadcast() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut server = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while !matches!(server.next().await, Some(Ok(Message::Text(_)))) {}
+            // LTP packets of 408065 at 1500.01 and 884737 at 910.00
+            let frame = [0, 2, 0, 8, 0, 6, 58, 1, 0, 2, 73, 241, 0, 8, 0, 13, 128, 1, 0, 1, 99, 120];
+            server.send(Message::binary(frame.to_vec())).await.unwrap();
+            while server.next().await.is_some() {}
+        });
+
+        let ticker = KiteTicker::builder("key", "token")
+            .root_url(&url)
+            .connect()
+            .await
+            .unwrap();
+        let broadcast = ticker.broadcast(16);
+        let mut first = broadcast.subscribe();
+        let mut second = broadcast.subscribe();
+        let mut watched = broadcast.watch(InstrumentToken(884737));
+        broadcast.handle().subscribe([InstrumentToken(408065), InstrumentToken(884737)]).unwrap();
+
+        for receiver in [&mut first, &mut second] {
+            loop {
+                if let TickerEvent::Ticks(ticks) = receiver.recv().await.unwrap() {
+                    assert_eq!(ticks.len(), 2);
+                    break;
+                }
+            }
+        }
+        watched.changed().await.unwrap();
+        assert_eq!(watched.borrow().as_ref().unwrap().last_price, 910.0);
+        assert!(broadcast.watch(InstrumentToken(408065)).borrow().is_none());
+
+        broadcast.handle().close();
+        assert_eq!(first.recv().await.unwrap(), TickerEvent::Closed);
+    }
+}
@@ -0,0 +1,317 @@
+//! Aggregation of ticks into OHLCV candles
+
+use super::Tick;
+use crate::models::{ist, InstrumentToken, Timestamp};
+use chrono::DateTime;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Open, high, low, close and volume of an instrument over one interval
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candle {
+    /// Instrument token the candle belongs to
+    pub instrument_token: InstrumentToken,
+    /// Start of the interval, aligned to the IST clock
+    pub start: Timestamp,
+    /// Length of the interval
+    pub interval: Duration,
+    /// First price of the interval
+    pub open: f64,
+    /// Highest price of the interval
+    pub high: f64,
+    /// Lowest price of the interval
+    pub low: f64,
+    /// Latest price of the interval
+    pub close: f64,
+    /// Quantity traded during the interval; zero for ticks without volume, e.g. in LTP mode
+    pub volume: u64,
+}
+
+impl Candle {
+    /// Returns the end of the interval, which is the start of the next candle
+    pub fn end(&self) -> Timestamp {
+        self.start + self.interval
+    }
+}
+
+/// Candles of one instrument
+#[derive(Debug, Default)]
+struct Series {
+    /// Candle being built
+    building: Option<Candle>,
+    /// Time of the tick that set the close of the candle being built
+    last_at: Option<Timestamp>,
+    /// Last candle emitted, possibly a flat one
+    emitted: Option<Candle>,
+    /// Cumulative day volume of the last tick
+    day_volume: Option<u32>,
+}
+
+/// Builds candles of a fixed interval from live ticks
+///
+/// Intervals are aligned to the IST clock, so five minute candles start at
+/// 09:15, 09:20 and so on. Ticks are placed by their exchange timestamp, or by
+/// the time they are passed in when they carry none. A candle is complete once
+/// a tick of a later interval arrives or [`flush`](Self::flush) is called past
+/// its end. Ticks older than the candle being built belong to a candle already
+/// emitted and are dropped; late ticks within the current candle update its
+/// high, low and volume but not its close. Intervals without any tick produce
+/// no candle unless [`fill_gaps`](Self::fill_gaps) is enabled.
+///
+/// ```rust,no_run
+/// use kiteconnect::models::{ist, InstrumentToken};
+/// use kiteconnect::ticker::{CandleBuilder, KiteTicker, TickerEvent};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let mut ticker = KiteTicker::connect("api_key", "access_token").await?;
+/// ticker.subscribe([InstrumentToken(408065)])?;
+/// let mut candles = CandleBuilder::new(Duration::from_secs(60));
+/// let mut clock = tokio::time::interval(Duration::from_secs(1));
+/// loop {
+///     let completed = tokio::select! {
+///         event = ticker.next_event() => match event {
+///             Some(TickerEvent::Ticks(ticks)) => candles.update(&ticks, ist::now()),
+///             Some(_) => continue,
+///             None => break,
+///         },
+///         _ = clock.tick() => candles.flush(ist::now()),
+///     };
+///     for candle in completed {
+///         println!("{:?}", candle);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct CandleBuilder {
+    interval: Duration,
+    fill_gaps: bool,
+    series: HashMap<InstrumentToken, Series>,
+}
+
+impl CandleBuilder {
+    /// Builds candles of `interval`, at least one second
+    pub fn new(interval: Duration) -> Self {
+        CandleBuilder {
+            interval: Duration::from_secs(interval.as_secs().max(1)),
+            fill_gaps: false,
+            series: HashMap::new(),
+        }
+    }
+
+    /// Emits flat candles, at the previous close and without volume, for
+    /// intervals in which an instrument did not trade
+    pub fn fill_gaps(mut self, fill: bool) -> Self {
+        self.fill_gaps = fill;
+        self
+    }
+
+    /// Adds ticks received at `received_at`, returning the candles they completed
+    pub fn update(&mut self, ticks: &[Tick], received_at: Timestamp) -> Vec<Candle> {
+        let mut completed = Vec::new();
+        for tick in ticks {
+            let at = tick.exchange_timestamp.or(tick.last_trade_time).unwrap_or(received_at);
+            self.add(tick, at, &mut completed);
+        }
+        completed
+    }
+
+    /// Returns the candles whose interval ended by `now`, e.g. called from a timer
+    /// so that candles of instruments that stopped ticking are not held back
+    pub fn flush(&mut self, now: Timestamp) -> Vec<Candle> {
+        let current = self.align(now);
+        let mut completed = Vec::new();
+        for series in self.series.values_mut() {
+            if series.building.as_ref().is_some_and(|candle| candle.start < current) {
+                let candle = series.building.take().expect("checked above");
+                series.complete(candle, &mut completed);
+            }
+            if self.fill_gaps && series.building.is_none() {
+                series.fill_until(current, &mut completed);
+            }
+        }
+        completed.sort_by_key(|candle| (candle.start, candle.instrument_token));
+        completed
+    }
+
+    /// Returns the candle being built for an instrument, if it ticked in the current interval
+    pub fn current(&self, instrument_token: impl Into<InstrumentToken>) -> Option<&Candle> {
+        self.series.get(&instrument_token.into())?.building.as_ref()
+    }
+
+    fn add(&mut self, tick: &Tick, at: Timestamp, completed: &mut Vec<Candle>) {
+        let start = self.align(at);
+        let series = self.series.entry(tick.instrument_token).or_default();
+        let volume = match series.day_volume.replace(tick.volume_traded) {
+            // Day volume restarts at zero with a new session
+            Some(previous) if tick.volume_traded >= previous => u64::from(tick.volume_traded - previous),
+            _ => 0,
+        };
+
+        if series.emitted.as_ref().is_some_and(|emitted| start < emitted.end()) {
+            log::debug!("Dropping tick of {} at {} for an emitted candle", tick.instrument_token, at);
+            return;
+        }
+        if let Some(candle) = series.building.as_mut() {
+            if start < candle.start {
+                log::debug!("Dropping tick of {} at {} for an emitted candle", tick.instrument_token, at);
+                return;
+            }
+            if start == candle.start {
+                candle.high = candle.high.max(tick.last_price);
+                candle.low = candle.low.min(tick.last_price);
+                candle.volume += volume;
+                if series.last_at.is_none_or(|last_at| at >= last_at) {
+                    candle.close = tick.last_price;
+                    series.last_at = Some(at);
+                }
+                return;
+            }
+            let candle = series.building.take().expect("checked above");
+            series.complete(candle, completed);
+        }
+        if self.fill_gaps {
+            series.fill_until(start, completed);
+        }
+        series.building = Some(Candle {
+            instrument_token: tick.instrument_token,
+            start,
+            interval: self.interval,
+            open: tick.last_price,
+            high: tick.last_price,
+            low: tick.last_price,
+            close: tick.last_price,
+            volume,
+        });
+        series.last_at = Some(at);
+    }
+
+    /// Returns the start of the interval containing `at`
+    fn align(&self, at: Timestamp) -> Timestamp {
+        let interval = self.interval.as_millis() as i64;
+        let millis = at.timestamp_millis();
+        let local = millis + i64::from(ist::offset().local_minus_utc()) * 1000;
+        DateTime::from_timestamp_millis(millis - local.rem_euclid(interval))
+            .map(|start| start.with_timezone(&ist::offset()))
+            .unwrap_or(at)
+    }
+}
+
+impl Series {
+    fn complete(&mut self, candle: Candle, completed: &mut Vec<Candle>) {
+        self.emitted = Some(candle.clone());
+        completed.push(candle);
+    }
+
+    /// Emits flat candles after the last emitted one, for the intervals before `until`
+    fn fill_until(&mut self, until: Timestamp, completed: &mut Vec<Candle>) {
+        while let Some(previous) = self.emitted.as_ref().filter(|previous| previous.end() < until) {
+            let flat = Candle {
+                start: previous.end(),
+                open: previous.close,
+                high: previous.close,
+                low: previous.close,
+                volume: 0,
+                ..previous.clone()
+            };
+            self.complete(flat, completed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32, second: u32) -> Timestamp {
+        ist::offset().with_ymd_and_hms(2024, 3, 21, hour, minute, second).unwrap()
+    }
+
+    fn tick(instrument_token: u32, last_price: f64, volume_traded: u32, time: Timestamp) -> Tick {
+        Tick {
+            instrument_token: InstrumentToken(instrument_token),
+            last_price,
+            volume_traded,
+            exchange_timestamp: Some(time),
+            ..Tick::default()
+        }
+    }
+
+    #[test]
+    fn test_candle_builder() {
+        let mut candles = CandleBuilder::new(Duration::from_secs(300));
+        assert!(candles
+            .update(
+                &[
+                    tick(408065, 1500.0, 1000, at(9, 15, 2)),
+                    tick(408065, 1504.5, 1200, at(9, 17, 40)),
+                    tick(408065, 1498.0, 1250, at(9, 19, 59)),
+                ],
+                at(9, 20, 0)
+            )
+            .is_empty());
+        // Late within the candle: extends the range but keeps the close
+        candles.update(&[tick(408065, 1497.0, 1300, at(9, 18, 0))], at(9, 20, 0));
+        let current = candles.current(InstrumentToken(408065)).unwrap();
+        assert_eq!(current.start, at(9, 15, 0));
+        assert_eq!((current.low, current.close, current.volume), (1497.0, 1498.0, 300));
+
+        let completed = candles.update(&[tick(408065, 1501.0, 1400, at(9, 20, 1))], at(9, 20, 1));
+        assert_eq!(
+            completed,
+            [Candle {
+                instrument_token: InstrumentToken(408065),
+                start: at(9, 15, 0),
+                interval: Duration::from_secs(300),
+                open: 1500.0,
+                high: 1504.5,
+                low: 1497.0,
+                close: 1498.0,
+                volume: 300,
+            }]
+        );
+        assert_eq!(completed[0].end(), at(9, 20, 0));
+        // Belongs to the emitted candle
+        candles.update(&[tick(408065, 1600.0, 1450, at(9, 19, 0))], at(9, 20, 2));
+        assert_eq!(candles.current(InstrumentToken(408065)).unwrap().high, 1501.0);
+
+        assert!(candles.flush(at(9, 24, 59)).is_empty());
+        let flushed = candles.flush(at(9, 25, 0));
+        assert_eq!(flushed.len(), 1);
+        assert_eq!((flushed[0].start, flushed[0].volume), (at(9, 20, 0), 100));
+        assert!(candles.current(InstrumentToken(408065)).is_none());
+        // Without gap filling, quiet intervals produce nothing
+        let completed = candles.update(&[tick(408065, 1502.0, 1500, at(9, 36, 0))], at(9, 36, 0));
+        assert!(completed.is_empty());
+        assert_eq!(candles.current(InstrumentToken(408065)).unwrap().start, at(9, 35, 0));
+    }
+
+    #[test]
+    fn test_fill_gaps() {
+        let mut candles = CandleBuilder::new(Duration::from_secs(60)).fill_gaps(true);
+        candles.update(&[tick(256265, 22000.0, 0, at(9, 15, 30))], at(9, 15, 30));
+        let flushed = candles.flush(at(9, 17, 10));
+        assert_eq!(
+            flushed.iter().map(|candle| candle.start).collect::<Vec<_>>(),
+            [at(9, 15, 0), at(9, 16, 0)]
+        );
+        assert_eq!((flushed[1].open, flushed[1].close), (22000.0, 22000.0));
+
+        // Ticks without a timestamp are placed at the time they were received
+        let untimed = Tick {
+            instrument_token: InstrumentToken(256265),
+            last_price: 22010.0,
+            ..Tick::default()
+        };
+        let completed = candles.update(&[untimed], at(9, 19, 5));
+        assert_eq!(
+            completed.iter().map(|candle| candle.start).collect::<Vec<_>>(),
+            [at(9, 17, 0), at(9, 18, 0)]
+        );
+        assert_eq!(candles.current(InstrumentToken(256265)).unwrap().open, 22010.0);
+    }
+}
@@ -0,0 +1,369 @@
+//! Arrow record batches and Parquet files of ticks, candles and instruments
+
+use super::{Candle, Tick, DEPTH_LEVELS};
+use crate::error::{KiteError, Result};
+use crate::models::{price_to_f64, Instrument, Timestamp};
+use arrow_array::types::ArrowPrimitiveType;
+use arrow_array::{
+    Array, ArrayRef, BooleanArray, Date32Array, DurationSecondArray, PrimitiveArray, RecordBatch,
+    StringArray, TimestampMillisecondArray,
+};
+use chrono::NaiveDate;
+use arrow_schema::{Field, Schema, SchemaRef};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Time zone of the timestamp columns
+const TIMEZONE: &str = "+05:30";
+
+/// Rows that convert to Arrow record batches, one row per value
+///
+/// Implemented for [`Tick`], with the market depth flattened into
+/// `buy_price_1` to `sell_orders_5` columns, for [`Candle`] and for
+/// [`Instrument`], with its expiry as a date. Timestamps are stored in
+/// milliseconds with the IST offset.
+///
+/// ```rust
+/// use kiteconnect::ticker::{decode_frame, Columnar, Tick};
+///
+/// # fn main() -> kiteconnect::error::Result<()> {
+/// let frame = [0, 1, 0, 8, 0, 6, 58, 1, 0, 2, 73, 241];
+/// let ticks: Vec<Tick> = decode_frame(&frame).collect();
+/// let batch = Tick::to_record_batch(&ticks)?;
+/// assert_eq!(batch.num_rows(), 1);
+/// assert!(batch.column_by_name("last_price").is_some());
+/// # Ok(())
+/// # }
+/// ```
+pub trait Columnar: Sized {
+    /// Returns the schema of the record batches
+    fn schema() -> SchemaRef;
+
+    /// Converts rows into a record batch
+    fn to_record_batch(rows: &[Self]) -> Result<RecordBatch>;
+}
+
+impl Columnar for Tick {
+    fn schema() -> SchemaRef {
+        schema(tick_columns(&[]))
+    }
+
+    fn to_record_batch(ticks: &[Self]) -> Result<RecordBatch> {
+        record_batch(tick_columns(ticks))
+    }
+}
+
+impl Columnar for Candle {
+    fn schema() -> SchemaRef {
+        schema(candle_columns(&[]))
+    }
+
+    fn to_record_batch(candles: &[Self]) -> Result<RecordBatch> {
+        record_batch(candle_columns(candles))
+    }
+}
+
+impl Columnar for Instrument {
+    fn schema() -> SchemaRef {
+        schema(instrument_columns(&[]))
+    }
+
+    fn to_record_batch(instruments: &[Self]) -> Result<RecordBatch> {
+        record_batch(instrument_columns(instruments))
+    }
+}
+
+/// Named columns of a record batch
+type Columns = Vec<(Field, ArrayRef)>;
+
+fn schema(columns: Columns) -> SchemaRef {
+    Arc::new(Schema::new(columns.into_iter().map(|(field, _)| field).collect::<Vec<_>>()))
+}
+
+fn record_batch(columns: Columns) -> Result<RecordBatch> {
+    let (fields, arrays): (Vec<Field>, Vec<ArrayRef>) = columns.into_iter().unzip();
+    Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?)
+}
+
+fn column<T: ArrowPrimitiveType>(name: &str, values: impl IntoIterator<Item = T::Native>) -> (Field, ArrayRef) {
+    let array = PrimitiveArray::<T>::from_iter_values(values);
+    (Field::new(name, array.data_type().clone(), false), Arc::new(array))
+}
+
+fn optional<T: ArrowPrimitiveType>(name: &str, values: impl IntoIterator<Item = Option<T::Native>>) -> (Field, ArrayRef) {
+    let array: PrimitiveArray<T> = values.into_iter().collect();
+    (Field::new(name, array.data_type().clone(), true), Arc::new(array))
+}
+
+fn timestamps(name: &str, values: impl IntoIterator<Item = Option<Timestamp>>) -> (Field, ArrayRef) {
+    let array: TimestampMillisecondArray = values
+        .into_iter()
+        .map(|ts| ts.map(|ts| ts.timestamp_millis()))
+        .collect();
+    let array = array.with_timezone(TIMEZONE);
+    (Field::new(name, array.data_type().clone(), true), Arc::new(array))
+}
+
+fn tick_columns(ticks: &[Tick]) -> Columns {
+    use arrow_array::types::{Float64Type, UInt16Type, UInt32Type};
+
+    let mode = StringArray::from_iter_values(ticks.iter().map(|tick| tick.mode.as_str()));
+    let tradable = BooleanArray::from_iter(ticks.iter().map(|tick| Some(tick.tradable)));
+    let mut columns: Columns = vec![
+        (Field::new("mode", mode.data_type().clone(), false), Arc::new(mode)),
+        column::<UInt32Type>("instrument_token", ticks.iter().map(|tick| tick.instrument_token.0)),
+        (Field::new("tradable", tradable.data_type().clone(), false), Arc::new(tradable)),
+        column::<Float64Type>("last_price", ticks.iter().map(|tick| tick.last_price)),
+        column::<UInt32Type>("last_traded_quantity", ticks.iter().map(|tick| tick.last_traded_quantity)),
+        column::<Float64Type>("average_traded_price", ticks.iter().map(|tick| tick.average_traded_price)),
+        column::<UInt32Type>("volume_traded", ticks.iter().map(|tick| tick.volume_traded)),
+        column::<UInt32Type>("total_buy_quantity", ticks.iter().map(|tick| tick.total_buy_quantity)),
+        column::<UInt32Type>("total_sell_quantity", ticks.iter().map(|tick| tick.total_sell_quantity)),
+        column::<Float64Type>("open", ticks.iter().map(|tick| tick.ohlc.open)),
+        column::<Float64Type>("high", ticks.iter().map(|tick| tick.ohlc.high)),
+        column::<Float64Type>("low", ticks.iter().map(|tick| tick.ohlc.low)),
+        column::<Float64Type>("close", ticks.iter().map(|tick| tick.ohlc.close)),
+        column::<Float64Type>("change", ticks.iter().map(|tick| tick.change)),
+        timestamps("last_trade_time", ticks.iter().map(|tick| tick.last_trade_time)),
+        column::<UInt32Type>("oi", ticks.iter().map(|tick| tick.oi)),
+        column::<UInt32Type>("oi_day_high", ticks.iter().map(|tick| tick.oi_day_high)),
+        column::<UInt32Type>("oi_day_low", ticks.iter().map(|tick| tick.oi_day_low)),
+        timestamps("exchange_timestamp", ticks.iter().map(|tick| tick.exchange_timestamp)),
+    ];
+    // Ticks without depth have nulls in the depth columns
+    for side in ["buy", "sell"] {
+        for level in 0..DEPTH_LEVELS {
+            let levels = || {
+                ticks.iter().map(move |tick| {
+                    tick.depth
+                        .map(|depth| if side == "buy" { depth.buy[level] } else { depth.sell[level] })
+                })
+            };
+            let n = level + 1;
+            columns.push(optional::<Float64Type>(
+                &format!("{}_price_{}", side, n),
+                levels().map(|level| level.map(|level| level.price)),
+            ));
+            columns.push(optional::<UInt32Type>(
+                &format!("{}_quantity_{}", side, n),
+                levels().map(|level| level.map(|level| level.quantity)),
+            ));
+            columns.push(optional::<UInt16Type>(
+                &format!("{}_orders_{}", side, n),
+                levels().map(|level| level.map(|level| level.orders)),
+            ));
+        }
+    }
+    columns
+}
+
+fn candle_columns(candles: &[Candle]) -> Columns {
+    use arrow_array::types::{Float64Type, UInt32Type, UInt64Type};
+
+    let interval = DurationSecondArray::from_iter_values(candles.iter().map(|candle| candle.interval.as_secs() as i64));
+    let mut start = timestamps("start", candles.iter().map(|candle| Some(candle.start)));
+    start.0 = start.0.with_nullable(false);
+    vec![
+        column::<UInt32Type>("instrument_token", candles.iter().map(|candle| candle.instrument_token.0)),
+        start,
+        (Field::new("interval", interval.data_type().clone(), false), Arc::new(interval)),
+        column::<Float64Type>("open", candles.iter().map(|candle| candle.open)),
+        column::<Float64Type>("high", candles.iter().map(|candle| candle.high)),
+        column::<Float64Type>("low", candles.iter().map(|candle| candle.low)),
+        column::<Float64Type>("close", candles.iter().map(|candle| candle.close)),
+        column::<UInt64Type>("volume", candles.iter().map(|candle| candle.volume)),
+    ]
+}
+
+fn instrument_columns(instruments: &[Instrument]) -> Columns {
+    use arrow_array::types::{Float64Type, UInt32Type};
+
+    let text = |name: &str, field: fn(&Instrument) -> &str| -> (Field, ArrayRef) {
+        let array = StringArray::from_iter_values(instruments.iter().map(field));
+        (Field::new(name, array.data_type().clone(), false), Arc::new(array))
+    };
+    let epoch = NaiveDate::default();
+    let expiry: Date32Array = instruments
+        .iter()
+        .map(|instrument| instrument.expiry.map(|expiry| (expiry - epoch).num_days() as i32))
+        .collect();
+    vec![
+        column::<UInt32Type>("instrument_token", instruments.iter().map(|i| i.instrument_token.0)),
+        column::<UInt32Type>("exchange_token", instruments.iter().map(|i| i.exchange_token)),
+        text("tradingsymbol", |i| &i.tradingsymbol),
+        text("name", |i| &i.name),
+        column::<Float64Type>("last_price", instruments.iter().map(|i| price_to_f64(i.last_price))),
+        (Field::new("expiry", expiry.data_type().clone(), true), Arc::new(expiry)),
+        column::<Float64Type>("strike", instruments.iter().map(|i| price_to_f64(i.strike))),
+        column::<Float64Type>("tick_size", instruments.iter().map(|i| price_to_f64(i.tick_size))),
+        column::<UInt32Type>("lot_size", instruments.iter().map(|i| i.lot_size)),
+        text("instrument_type", |i| &i.instrument_type),
+        text("segment", |i| &i.segment),
+        text("exchange", |i| &i.exchange),
+    ]
+}
+
+/// Writes ticks, candles or instruments to a Snappy compressed Parquet file
+///
+/// Every call to [`write`](Self::write) adds the rows to the current row
+/// group; the file is only complete once [`close`](Self::close) returns.
+///
+/// ```rust,no_run
+/// use kiteconnect::models::InstrumentToken;
+/// use kiteconnect::ticker::{KiteTicker, ParquetWriter, Tick, TickerEvent};
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let mut ticker = KiteTicker::connect("api_key", "access_token").await?;
+/// ticker.subscribe([InstrumentToken(408065)])?;
+/// let mut writer = ParquetWriter::<Tick>::create("ticks.parquet")?;
+/// while let Some(event) = ticker.next_event().await {
+///     if let TickerEvent::Ticks(ticks) = event {
+///         writer.write(&ticks)?;
+///     }
+/// }
+/// writer.close()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ParquetWriter<T: Columnar> {
+    writer: ArrowWriter<File>,
+    rows: PhantomData<fn(&[T])>,
+}
+
+impl<T: Columnar> std::fmt::Debug for ParquetWriter<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParquetWriter").finish_non_exhaustive()
+    }
+}
+
+impl<T: Columnar> ParquetWriter<T> {
+    /// Creates the file, replacing any existing one
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path).map_err(|e| {
+            KiteError::Other(format!("failed to create {}: {}", path.display(), e))
+        })?;
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let writer = ArrowWriter::try_new(file, T::schema(), Some(properties))?;
+        Ok(ParquetWriter {
+            writer,
+            rows: PhantomData,
+        })
+    }
+
+    /// Appends rows to the file
+    pub fn write(&mut self, rows: &[T]) -> Result<()> {
+        if !rows.is_empty() {
+            self.writer.write(&T::to_record_batch(rows)?)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered rows and the file footer
+    pub fn close(self) -> Result<()> {
+        self.writer.close()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ist, InstrumentToken};
+    use crate::ticker::{decode_frame, Mode};
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float64Type, UInt16Type, UInt64Type};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use std::time::Duration;
+
+    #[test]
+    fn test_ticks_to_parquet() {
+        let mock = std::fs::read("mocks/ticker_frame.bin").unwrap();
+        let ticks: Vec<Tick> = decode_frame(&mock).collect();
+        let batch = Tick::to_record_batch(&ticks).unwrap();
+        assert_eq!(batch.schema(), Tick::schema());
+        assert_eq!(batch.num_rows(), ticks.len());
+        assert_eq!(batch.num_columns(), 19 + 2 * DEPTH_LEVELS * 3);
+
+        let full = ticks.iter().position(|tick| tick.mode == Mode::Full && tick.depth.is_some()).unwrap();
+        let ltp = ticks.iter().position(|tick| tick.depth.is_none()).unwrap();
+        let prices = batch.column_by_name("last_price").unwrap().as_primitive::<Float64Type>();
+        assert_eq!(prices.value(full), ticks[full].last_price);
+        let orders = batch.column_by_name("sell_orders_1").unwrap().as_primitive::<UInt16Type>();
+        assert_eq!(orders.value(full), ticks[full].depth.unwrap().sell[0].orders);
+        assert!(orders.is_null(ltp));
+
+        let path = std::env::temp_dir().join(format!("kite-ticks-{}.parquet", std::process::id()));
+        let mut writer = ParquetWriter::<Tick>::create(&path).unwrap();
+        writer.write(&ticks).unwrap();
+        writer.write(&ticks[..2]).unwrap();
+        writer.close().unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|batch| batch.unwrap()).collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(batches.iter().map(RecordBatch::num_rows).sum::<usize>(), ticks.len() + 2);
+        assert_eq!(batches[0].schema(), Tick::schema());
+        assert_eq!(batches[0].slice(0, ticks.len()), batch);
+    }
+
+    #[test]
+    fn test_candles_to_record_batch() {
+        let start = ist::parse_timestamp("2024-03-21 09:15:00").unwrap();
+        let candle = Candle {
+            instrument_token: InstrumentToken(408065),
+            start,
+            interval: Duration::from_secs(60),
+            open: 1500.0,
+            high: 1504.5,
+            low: 1497.0,
+            close: 1498.0,
+            volume: 300,
+        };
+        let batch = Candle::to_record_batch(&[candle.clone(), candle]).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema(), Candle::schema());
+        let starts = batch.column_by_name("start").unwrap().as_primitive::<arrow_array::types::TimestampMillisecondType>();
+        assert_eq!(starts.value(0), start.timestamp_millis());
+        assert_eq!(starts.timezone(), Some(TIMEZONE));
+        let volumes = batch.column_by_name("volume").unwrap().as_primitive::<UInt64Type>();
+        assert_eq!(volumes.value(1), 300);
+    }
+
+    #[test]
+    fn test_instruments_to_record_batch() {
+        let instruments = [
+            Instrument {
+                instrument_token: InstrumentToken(408065),
+                tradingsymbol: "INFY".to_string(),
+                exchange: "NSE".to_string(),
+                ..Default::default()
+            },
+            Instrument {
+                instrument_token: InstrumentToken(5720322),
+                tradingsymbol: "NIFTY15DECFUT".to_string(),
+                expiry: NaiveDate::from_ymd_opt(2015, 12, 31),
+                lot_size: 75,
+                ..Default::default()
+            },
+        ];
+        let batch = Instrument::to_record_batch(&instruments).unwrap();
+        assert_eq!(batch.schema(), Instrument::schema());
+        assert_eq!((batch.num_rows(), batch.num_columns()), (2, 12));
+        let expiries = batch.column_by_name("expiry").unwrap().as_primitive::<arrow_array::types::Date32Type>();
+        assert!(expiries.is_null(0));
+        assert_eq!(expiries.value_as_date(1), instruments[1].expiry);
+        let symbols = batch.column_by_name("tradingsymbol").unwrap().as_string::<i32>();
+        assert_eq!(symbols.value(1), "NIFTY15DECFUT");
+    }
+}
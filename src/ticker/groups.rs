@@ -0,0 +1,261 @@
+//! Named groups of subscriptions, such as watchlists
+
+use super::{collect_tokens, Mode, TickerHandle};
+use crate::error::{KiteError, Result};
+use crate::models::InstrumentToken;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Instruments of a group and the mode they are streamed in
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Group {
+    mode: Mode,
+    tokens: BTreeSet<InstrumentToken>,
+    /// Whether the group is subscribed
+    active: bool,
+}
+
+/// Named groups of instrument tokens subscribed and unsubscribed together
+///
+/// Each group has its own mode. An instrument in several subscribed groups is
+/// streamed in the most detailed of their modes, and stays subscribed until
+/// the last of them is unsubscribed. Tokens subscribed directly through the
+/// [`TickerHandle`] should not overlap with the groups, which would unsubscribe
+/// them along with a group.
+///
+/// ```rust,no_run
+/// use kiteconnect::models::InstrumentToken;
+/// use kiteconnect::ticker::{KiteTicker, Mode, SubscriptionGroups};
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let ticker = KiteTicker::connect("api_key", "access_token").await?;
+/// let mut groups = SubscriptionGroups::new(ticker.handle());
+/// groups.define("indices", Mode::Ltp, [InstrumentToken(256265), InstrumentToken(260105)])?;
+/// groups.define("hedges", Mode::Full, [InstrumentToken(13368834)])?;
+/// groups.subscribe("indices")?;
+/// groups.subscribe("hedges")?;
+///
+/// groups.unsubscribe("hedges")?;
+/// groups.save("watchlists.json")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct SubscriptionGroups {
+    handle: TickerHandle,
+    groups: BTreeMap<String, Group>,
+    /// Tokens and modes currently requested from the ticker
+    applied: BTreeMap<InstrumentToken, Mode>,
+}
+
+impl SubscriptionGroups {
+    /// Manages groups on the ticker of `handle`, starting without any group
+    pub fn new(handle: TickerHandle) -> Self {
+        SubscriptionGroups {
+            handle,
+            groups: BTreeMap::new(),
+            applied: BTreeMap::new(),
+        }
+    }
+
+    /// Loads groups saved with [`save`](Self::save) and subscribes those that were subscribed
+    pub fn load(handle: TickerHandle, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let document = std::fs::read_to_string(path).map_err(|e| {
+            KiteError::Other(format!("failed to read subscription groups {}: {}", path.display(), e))
+        })?;
+        let mut groups = Self::new(handle);
+        groups.groups = serde_json::from_str(&document)?;
+        groups.apply()?;
+        Ok(groups)
+    }
+
+    /// Writes the groups, their modes and whether they are subscribed to a JSON file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let document = serde_json::to_string_pretty(&self.groups)?;
+        std::fs::write(path, document).map_err(|e| {
+            KiteError::Other(format!("failed to write subscription groups {}: {}", path.display(), e))
+        })
+    }
+
+    /// Creates or replaces a group; a subscribed group is updated on the ticker right away
+    pub fn define(&mut self, name: &str, mode: Mode, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Result<()> {
+        let group = self.groups.entry(name.to_string()).or_default();
+        group.mode = mode;
+        group.tokens = collect_tokens(tokens).into_iter().collect();
+        self.apply()
+    }
+
+    /// Adds instruments to a group
+    pub fn add(&mut self, name: &str, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Result<()> {
+        self.group_mut(name)?.tokens.extend(collect_tokens(tokens));
+        self.apply()
+    }
+
+    /// Removes instruments from a group
+    pub fn remove(&mut self, name: &str, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Result<()> {
+        let group = self.group_mut(name)?;
+        for token in collect_tokens(tokens) {
+            group.tokens.remove(&token);
+        }
+        self.apply()
+    }
+
+    /// Changes the mode of a group
+    pub fn set_mode(&mut self, name: &str, mode: Mode) -> Result<()> {
+        self.group_mut(name)?.mode = mode;
+        self.apply()
+    }
+
+    /// Deletes a group, unsubscribing its instruments not needed by other groups
+    pub fn delete(&mut self, name: &str) -> Result<()> {
+        self.groups
+            .remove(name)
+            .ok_or_else(|| unknown_group(name))?;
+        self.apply()
+    }
+
+    /// Subscribes to the instruments of a group
+    pub fn subscribe(&mut self, name: &str) -> Result<()> {
+        self.group_mut(name)?.active = true;
+        self.apply()
+    }
+
+    /// Unsubscribes from the instruments of a group not needed by other subscribed groups
+    pub fn unsubscribe(&mut self, name: &str) -> Result<()> {
+        self.group_mut(name)?.active = false;
+        self.apply()
+    }
+
+    /// Returns the names of the groups
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.groups.keys().map(String::as_str)
+    }
+
+    /// Returns the instruments of a group
+    pub fn tokens(&self, name: &str) -> Option<Vec<InstrumentToken>> {
+        self.groups.get(name).map(|group| group.tokens.iter().copied().collect())
+    }
+
+    /// Returns the mode of a group
+    pub fn mode(&self, name: &str) -> Option<Mode> {
+        self.groups.get(name).map(|group| group.mode)
+    }
+
+    /// Returns `true` if a group is subscribed
+    pub fn is_subscribed(&self, name: &str) -> bool {
+        self.groups.get(name).is_some_and(|group| group.active)
+    }
+
+    fn group_mut(&mut self, name: &str) -> Result<&mut Group> {
+        self.groups.get_mut(name).ok_or_else(|| unknown_group(name))
+    }
+
+    /// Brings the ticker's subscriptions in line with the subscribed groups
+    fn apply(&mut self) -> Result<()> {
+        let mut wanted: BTreeMap<InstrumentToken, Mode> = BTreeMap::new();
+        for group in self.groups.values().filter(|group| group.active) {
+            for &token in &group.tokens {
+                let mode = wanted.entry(token).or_insert(group.mode);
+                *mode = (*mode).max(group.mode);
+            }
+        }
+
+        let removed: Vec<InstrumentToken> = self
+            .applied
+            .keys()
+            .filter(|token| !wanted.contains_key(token))
+            .copied()
+            .collect();
+        if !removed.is_empty() {
+            self.handle.unsubscribe(&removed)?;
+        }
+        for mode in [Mode::Ltp, Mode::Quote, Mode::Full, Mode::Depth20] {
+            let changed: Vec<InstrumentToken> = wanted
+                .iter()
+                .filter(|&(token, &wanted)| wanted == mode && self.applied.get(token) != Some(&mode))
+                .map(|(&token, _)| token)
+                .collect();
+            if !changed.is_empty() {
+                self.handle.set_mode(mode, &changed)?;
+            }
+        }
+        self.applied = wanted;
+        Ok(())
+    }
+}
+
+fn unknown_group(name: &str) -> KiteError {
+    KiteError::Other(format!("unknown subscription group {:?}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ticker::KiteTicker;
+    use futures::StreamExt;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_subscription_groups() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut server = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while server.next().await.is_some() {}
+        });
+        let ticker = KiteTicker::builder("key", "token")
+            .root_url(&url)
+            .connect()
+            .await
+            .unwrap();
+
+        let mut groups = SubscriptionGroups::new(ticker.handle());
+        groups.define("indices", Mode::Ltp, [InstrumentToken(256265), InstrumentToken(260105)]).unwrap();
+        groups.define("banks", Mode::Full, [InstrumentToken(260105), InstrumentToken(1510401)]).unwrap();
+        assert!(ticker.subscriptions().is_empty());
+
+        groups.subscribe("indices").unwrap();
+        groups.subscribe("banks").unwrap();
+        // The shared index is streamed in the more detailed mode
+        assert_eq!(
+            ticker.subscriptions(),
+            BTreeMap::from([
+                (InstrumentToken(256265), Mode::Ltp),
+                (InstrumentToken(260105), Mode::Full),
+                (InstrumentToken(1510401), Mode::Full),
+            ])
+        );
+
+        groups.unsubscribe("banks").unwrap();
+        assert_eq!(
+            ticker.subscriptions(),
+            BTreeMap::from([(InstrumentToken(256265), Mode::Ltp), (InstrumentToken(260105), Mode::Ltp)])
+        );
+        groups.add("indices", [InstrumentToken(264969)]).unwrap();
+        assert!(groups.subscribe("missing").is_err());
+
+        let path = std::env::temp_dir().join(format!("kite-groups-{}.json", std::process::id()));
+        groups.save(&path).unwrap();
+        groups.delete("indices").unwrap();
+        assert!(ticker.subscriptions().is_empty());
+
+        let restored = SubscriptionGroups::load(ticker.handle(), &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(restored.names().collect::<Vec<_>>(), ["banks", "indices"]);
+        assert!(restored.is_subscribed("indices") && !restored.is_subscribed("banks"));
+        assert_eq!(restored.mode("banks"), Some(Mode::Full));
+        assert_eq!(
+            ticker.subscriptions(),
+            BTreeMap::from([
+                (InstrumentToken(256265), Mode::Ltp),
+                (InstrumentToken(260105), Mode::Ltp),
+                (InstrumentToken(264969), Mode::Ltp),
+            ])
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Callback API driving a [`TickerHandler`] with the events of a ticker
+
+use super::{KiteTicker, Tick, TickerError, TickerEvent, TickerHandle};
+use crate::models::Order;
+use std::time::Duration;
+
+/// Callbacks for the events of a [`KiteTicker`], in the style of pykiteconnect
+///
+/// An alternative to consuming [`TickerEvent`]s directly: pass the handler to
+/// [`KiteTicker::run`], which calls it for every event until the ticker stops.
+/// All methods except [`on_tick`](Self::on_tick) default to doing nothing.
+///
+/// ```rust,no_run
+/// use kiteconnect::models::InstrumentToken;
+/// use kiteconnect::ticker::{KiteTicker, Mode, Tick, TickerHandle, TickerHandler};
+///
+/// struct Strategy;
+///
+/// impl TickerHandler for Strategy {
+///     fn on_connect(&mut self, ticker: &TickerHandle) {
+///         let _ = ticker.set_mode(Mode::Full, [InstrumentToken(408065)]);
+///     }
+///
+///     fn on_tick(&mut self, _ticker: &TickerHandle, ticks: &[Tick]) {
+///         for tick in ticks {
+///             println!("{}: {}", tick.instrument_token, tick.last_price);
+///         }
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let ticker = KiteTicker::connect("api_key", "access_token").await?;
+/// ticker.run(Strategy).await;
+/// # Ok(())
+/// # }
+/// ```
+pub trait TickerHandler: Send {
+    /// Called with the ticks decoded from each binary frame
+    fn on_tick(&mut self, ticker: &TickerHandle, ticks: &[Tick]);
+
+    /// Called once connected and after every reconnection
+    fn on_connect(&mut self, ticker: &TickerHandle) {
+        let _ = ticker;
+    }
+
+    /// Called with malformed messages, the error messages sent by Kite and
+    /// connection failures
+    fn on_error(&mut self, ticker: &TickerHandle, error: &TickerError) {
+        let _ = (ticker, error);
+    }
+
+    /// Called with the order updates sent for the user's orders
+    fn on_order_update(&mut self, ticker: &TickerHandle, order: &Order) {
+        let _ = (ticker, order);
+    }
+
+    /// Called when the connection dropped, before reconnecting after `delay`
+    fn on_reconnect(&mut self, attempt: u32, delay: Duration) {
+        let _ = (attempt, delay);
+    }
+
+    /// Called once the ticker has stopped
+    fn on_close(&mut self) {}
+}
+
+impl KiteTicker {
+    /// Calls `handler` for every event until the ticker stops, then returns it
+    pub async fn run<H: TickerHandler>(mut self, mut handler: H) -> H {
+        let ticker = self.handle();
+        while let Some(event) = self.next_event().await {
+            match event {
+                TickerEvent::Connected => handler.on_connect(&ticker),
+                TickerEvent::Ticks(ticks) => handler.on_tick(&ticker, &ticks),
+                TickerEvent::Error(error) => handler.on_error(&ticker, &error),
+                TickerEvent::OrderUpdate(order) => handler.on_order_update(&ticker, &order),
+                TickerEvent::Reconnecting { attempt, delay } => handler.on_reconnect(attempt, delay),
+                TickerEvent::Closed => {
+                    handler.on_close();
+                    break;
+                }
+            }
+        }
+        handler
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::InstrumentToken;
+    use crate::retry::RetryPolicy;
+    use futures::{SinkExt, StreamExt};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    #[derive(Default)]
+    struct Recorder {
+        calls: Vec<String>,
+    }
+
+    impl TickerHandler for Recorder {
+        fn on_connect(&mut self, ticker: &TickerHandle) {
+            self.calls.push("connect".to_string());
+            ticker.subscribe([InstrumentToken(408065)]).unwrap();
+        }
+
+        fn on_tick(&mut self, ticker: &TickerHandle, ticks: &[Tick]) {
+            self.calls.push(format!("tick {}", ticks[0].last_price));
+            ticker.close();
+        }
+
+        fn on_error(&mut self, _ticker: &TickerHandle, error: &TickerError) {
+            self.calls.push(format!("error {}", error));
+        }
+
+        fn on_close(&mut self) {
+            self.calls.push("close".to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_handler() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut server = tokio_tungstenite::accept_async(stream).await.unwrap();
+            // Wait for the subscription sent from on_connect
+            while !matches!(server.next().await, Some(Ok(Message::Text(_)))) {}
+            server
+                .send(Message::text(r#"{"type":"error","data":"Invalid mode"}"#))
+                .await
+                .unwrap();
+            let frame = [0, 1, 0, 8, 0, 6, 58, 1, 0, 2, 73, 241];
+            server.send(Message::binary(frame.to_vec())).await.unwrap();
+            while server.next().await.is_some() {}
+        });
+
+        let ticker = KiteTicker::builder("key", "token")
+            .root_url(&url)
+            .reconnect(RetryPolicy::none())
+            .connect()
+            .await
+            .unwrap();
+        let recorder = ticker.run(Recorder::default()).await;
+        assert_eq!(recorder.calls, ["connect", "error Invalid mode", "tick 1500.01", "close"]);
+    }
+}
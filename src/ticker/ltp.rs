@@ -0,0 +1,189 @@
+//! Latest prices of instruments, kept current by a shared ticker connection
+
+use super::{collect_tokens, TickerBroadcast, TickerEvent};
+use crate::error::Result;
+use crate::models::{ist, InstrumentToken, Timestamp};
+use chrono::DateTime;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Last traded price of an instrument and when it was received
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LastPrice {
+    /// Last traded price
+    pub price: f64,
+    /// When the tick carrying the price was received
+    pub updated_at: Timestamp,
+}
+
+/// Latest price of one instrument, written by the cache's task only
+///
+/// The version is odd while a write is in progress, so readers retry instead
+/// of seeing a price with the timestamp of another.
+#[derive(Debug, Default)]
+struct Slot {
+    version: AtomicU64,
+    price: AtomicU64,
+    /// Milliseconds since the epoch, zero until the first tick
+    updated_at: AtomicI64,
+}
+
+impl Slot {
+    fn store(&self, price: f64, updated_at: Timestamp) {
+        self.version.fetch_add(1, Ordering::AcqRel);
+        self.price.store(price.to_bits(), Ordering::Release);
+        self.updated_at.store(updated_at.timestamp_millis(), Ordering::Release);
+        self.version.fetch_add(1, Ordering::AcqRel);
+    }
+
+    fn load(&self) -> Option<LastPrice> {
+        loop {
+            let version = self.version.load(Ordering::Acquire);
+            let price = f64::from_bits(self.price.load(Ordering::Acquire));
+            let millis = self.updated_at.load(Ordering::Acquire);
+            if version.is_multiple_of(2) && self.version.load(Ordering::Acquire) == version {
+                if millis == 0 {
+                    return None;
+                }
+                let updated_at = DateTime::from_timestamp_millis(millis)?.with_timezone(&ist::offset());
+                return Some(LastPrice { price, updated_at });
+            }
+            std::hint::spin_loop();
+        }
+    }
+}
+
+/// Last traded prices of a fixed set of instruments, fed by a [`TickerBroadcast`]
+///
+/// Reads with [`get`](Self::get) take no lock and make no request, so sizing
+/// orders from current prices costs next to nothing. The prices are kept
+/// current by a task that stops when the cache is dropped. Prices are not
+/// cleared when the connection drops; check [`LastPrice::updated_at`] where
+/// staleness matters.
+///
+/// ```rust,no_run
+/// use kiteconnect::models::InstrumentToken;
+/// use kiteconnect::ticker::{KiteTicker, LtpCache};
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let ticker = KiteTicker::connect("api_key", "access_token").await?;
+/// let broadcast = ticker.broadcast(1024);
+/// let prices = LtpCache::new(&broadcast, [InstrumentToken(408065), InstrumentToken(884737)])?;
+///
+/// if let Some(infy) = prices.get(InstrumentToken(408065)) {
+///     let quantity = (100_000.0 / infy.price).floor();
+///     println!("{} shares at {}", quantity, infy.price);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct LtpCache {
+    slots: Arc<HashMap<InstrumentToken, Slot>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl LtpCache {
+    /// Subscribes to `tokens` on the shared connection and caches their prices
+    ///
+    /// Tokens that are already subscribed keep their mode.
+    pub fn new(broadcast: &TickerBroadcast, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Result<Self> {
+        let tokens = collect_tokens(tokens);
+        let slots: Arc<HashMap<InstrumentToken, Slot>> =
+            Arc::new(tokens.iter().map(|&token| (token, Slot::default())).collect());
+        // Listening before subscribing so that the first ticks are not missed
+        let events = broadcast.subscribe();
+        broadcast.handle().subscribe(&tokens)?;
+        let task = tokio::spawn(update(events, slots.clone()));
+        Ok(LtpCache { slots, task })
+    }
+
+    /// Returns the latest price of an instrument, `None` until its first tick
+    /// or if it is not one of the cached tokens
+    pub fn get(&self, instrument_token: impl Into<InstrumentToken>) -> Option<LastPrice> {
+        self.slots.get(&instrument_token.into())?.load()
+    }
+
+    /// Returns the cached instrument tokens
+    pub fn tokens(&self) -> impl Iterator<Item = InstrumentToken> + '_ {
+        self.slots.keys().copied()
+    }
+}
+
+impl Drop for LtpCache {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn update(mut events: tokio::sync::broadcast::Receiver<TickerEvent>, slots: Arc<HashMap<InstrumentToken, Slot>>) {
+    loop {
+        match events.recv().await {
+            Ok(TickerEvent::Ticks(ticks)) => {
+                let received_at = ist::now();
+                for tick in ticks {
+                    if let Some(slot) = slots.get(&tick.instrument_token) {
+                        slot.store(tick.last_price, received_at);
+                    }
+                }
+            }
+            Ok(_) => {}
+            // Missed ticks are superseded by the next ones
+            Err(RecvError::Lagged(missed)) => log::warn!("LTP cache missed {} ticker events", missed),
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ticker::KiteTicker;
+    use futures::{SinkExt, StreamExt};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    #[tokio::test]
+    async fn test_ltp_cache() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut server = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while !matches!(server.next().await, Some(Ok(Message::Text(_)))) {}
+            // LTP packets of 408065 at 1500.01 and 884737 at 910.00
+            let frame = [0, 2, 0, 8, 0, 6, 58, 1, 0, 2, 73, 241, 0, 8, 0, 13, 128, 1, 0, 1, 99, 120];
+            server.send(Message::binary(frame.to_vec())).await.unwrap();
+            while server.next().await.is_some() {}
+        });
+
+        let ticker = KiteTicker::builder("key", "token")
+            .root_url(&url)
+            .connect()
+            .await
+            .unwrap();
+        let broadcast = ticker.broadcast(16);
+        let prices = LtpCache::new(&broadcast, [InstrumentToken(408065)]).unwrap();
+        assert_eq!(prices.tokens().collect::<Vec<_>>(), [InstrumentToken(408065)]);
+        assert!(broadcast.handle().subscriptions().contains_key(&InstrumentToken(408065)));
+        assert_eq!(prices.get(InstrumentToken(408065)), None);
+
+        let infy = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                if let Some(infy) = prices.get(InstrumentToken(408065)) {
+                    break infy;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(infy.price, 1500.01);
+        assert!((ist::now() - infy.updated_at).num_seconds() < 60);
+        // Ticks of other instruments are not cached
+        assert_eq!(prices.get(InstrumentToken(884737)), None);
+    }
+}
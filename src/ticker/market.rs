@@ -0,0 +1,327 @@
+//! Quotes from the ticker when it has them, and from the REST API otherwise
+
+use super::{
+    collect_tokens, Depth, DepthLevel, Mode, Ohlc, Tick, TickerBroadcast, TickerEvent, TickerHandle, DEPTH_LEVELS,
+};
+use crate::connect::KiteConnect;
+use crate::error::Result;
+use crate::models::{ist, InstrumentToken};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Latest ticks of the shared connection and whether it is up
+#[derive(Debug, Default)]
+struct State {
+    connected: AtomicBool,
+    ticks: Mutex<HashMap<InstrumentToken, Tick>>,
+}
+
+impl State {
+    fn disconnected(&self) {
+        self.connected.store(false, Ordering::Release);
+        // Ticks from before the drop would otherwise be served as current
+        self.ticks.lock().unwrap().clear();
+    }
+}
+
+/// Market data served from a shared ticker connection, with the REST API as fallback
+///
+/// Prices and quotes of subscribed instruments come from their latest tick.
+/// Instruments that are not subscribed, or have not ticked yet, are fetched
+/// with [`KiteConnect::ltp`] and [`KiteConnect::quote`], as is everything
+/// while the ticker is disconnected. Strategy code thereby gets the same
+/// answers whether or not the websocket is up.
+///
+/// Quotes are returned as [`Tick`]s. Those from the ticker carry the fields of
+/// the mode the instrument is streamed in, so only instruments streamed in
+/// [`Mode::Quote`] or [`Mode::Full`] are quoted from the ticker; those from the
+/// REST API always include the market depth.
+///
+/// ```rust,no_run
+/// use kiteconnect::connect::KiteConnect;
+/// use kiteconnect::models::InstrumentToken;
+/// use kiteconnect::ticker::{KiteTicker, MarketData};
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let ticker = KiteTicker::connect("api_key", "access_token").await?;
+/// ticker.subscribe([InstrumentToken(408065)])?;
+/// let broadcast = ticker.broadcast(1024);
+/// let market = MarketData::new(KiteConnect::new("api_key", "access_token"), &broadcast);
+///
+/// // INFY comes from the ticker, RELIANCE from the REST API
+/// let prices = market.ltp([InstrumentToken(408065), InstrumentToken(738561)]).await?;
+/// println!("{:?}", prices);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MarketData {
+    client: KiteConnect,
+    handle: TickerHandle,
+    state: Arc<State>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl MarketData {
+    /// Serves market data from the connection shared by `broadcast`, fetching
+    /// the rest with `client`
+    pub fn new(client: KiteConnect, broadcast: &TickerBroadcast) -> Self {
+        let state = Arc::new(State::default());
+        // Broadcasts are made from connected tickers
+        state.connected.store(!broadcast.is_finished(), Ordering::Release);
+        let task = tokio::spawn(track(broadcast.subscribe(), state.clone()));
+        MarketData {
+            client,
+            handle: broadcast.handle(),
+            state,
+            task,
+        }
+    }
+
+    /// Returns `true` while the ticker is connected
+    pub fn is_connected(&self) -> bool {
+        self.state.connected.load(Ordering::Acquire)
+    }
+
+    /// Returns a handle changing the subscriptions of the shared connection
+    pub fn handle(&self) -> TickerHandle {
+        self.handle.clone()
+    }
+
+    /// Returns the last traded prices of instruments by instrument token
+    ///
+    /// Instruments unknown to Kite are missing from the result.
+    pub async fn ltp(
+        &self,
+        tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>,
+    ) -> Result<HashMap<InstrumentToken, f64>> {
+        let tokens = &collect_tokens(tokens);
+        let mut prices: HashMap<InstrumentToken, f64> = self
+            .live(tokens, Mode::Ltp)
+            .into_iter()
+            .map(|(token, tick)| (token, tick.last_price))
+            .collect();
+        let missing = missing(tokens, &prices);
+        if !missing.is_empty() {
+            let response = self.client.ltp_tokens(missing).await?;
+            for quote in quotes(&response) {
+                if let (Some(token), Some(price)) = (instrument_token(quote), quote["last_price"].as_f64()) {
+                    prices.entry(token).or_insert(price);
+                }
+            }
+        }
+        Ok(prices)
+    }
+
+    /// Returns the quotes of instruments by instrument token
+    ///
+    /// Instruments unknown to Kite are missing from the result.
+    pub async fn quote(
+        &self,
+        tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>,
+    ) -> Result<HashMap<InstrumentToken, Tick>> {
+        let tokens = &collect_tokens(tokens);
+        let mut ticks = self.live(tokens, Mode::Quote);
+        let missing = missing(tokens, &ticks);
+        if !missing.is_empty() {
+            let response = self.client.quote_tokens(missing).await?;
+            for tick in quotes(&response).filter_map(quote_tick) {
+                ticks.entry(tick.instrument_token).or_insert(tick);
+            }
+        }
+        Ok(ticks)
+    }
+
+    /// Returns the latest ticks of the instruments streamed in at least `mode`
+    fn live(&self, tokens: &[InstrumentToken], mode: Mode) -> HashMap<InstrumentToken, Tick> {
+        if !self.is_connected() {
+            return HashMap::new();
+        }
+        let subscriptions = self.handle.subscriptions();
+        let ticks = self.state.ticks.lock().unwrap();
+        tokens
+            .iter()
+            .filter(|token| subscriptions.get(token).is_some_and(|&subscribed| subscribed >= mode))
+            .filter_map(|token| ticks.get(token))
+            .filter(|tick| tick.mode >= mode)
+            .map(|tick| (tick.instrument_token, tick.clone()))
+            .collect()
+    }
+}
+
+impl Drop for MarketData {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn track(mut events: broadcast::Receiver<TickerEvent>, state: Arc<State>) {
+    loop {
+        match events.recv().await {
+            Ok(TickerEvent::Ticks(ticks)) => {
+                let mut latest = state.ticks.lock().unwrap();
+                for tick in ticks {
+                    latest.insert(tick.instrument_token, tick);
+                }
+            }
+            Ok(TickerEvent::Connected) => state.connected.store(true, Ordering::Release),
+            Ok(TickerEvent::Reconnecting { .. }) | Ok(TickerEvent::Closed) => state.disconnected(),
+            Err(RecvError::Closed) => {
+                state.disconnected();
+                break;
+            }
+            Ok(_) => {}
+            Err(RecvError::Lagged(missed)) => log::warn!("Market data missed {} ticker events", missed),
+        }
+    }
+}
+
+/// Returns the tokens without a value, to be fetched from the REST API
+fn missing<T>(tokens: &[InstrumentToken], found: &HashMap<InstrumentToken, T>) -> Vec<InstrumentToken> {
+    tokens.iter().filter(|token| !found.contains_key(token)).copied().collect()
+}
+
+/// Returns the quotes of a REST response, with or without its envelope
+fn quotes(response: &JsonValue) -> impl Iterator<Item = &JsonValue> {
+    let data = response.get("data").unwrap_or(response);
+    data.as_object().into_iter().flat_map(|quotes| quotes.values())
+}
+
+fn instrument_token(quote: &JsonValue) -> Option<InstrumentToken> {
+    quote["instrument_token"].as_u64()?.try_into().ok().map(InstrumentToken)
+}
+
+/// Converts a full quote of the REST API into a tick
+fn quote_tick(quote: &JsonValue) -> Option<Tick> {
+    let price = |value: &JsonValue| value.as_f64().unwrap_or_default();
+    let quantity = |value: &JsonValue| value.as_u64().unwrap_or_default() as u32;
+    let timestamp = |value: &JsonValue| value.as_str().and_then(ist::parse_timestamp);
+    let levels = |side: &JsonValue| {
+        let mut levels = [DepthLevel::default(); DEPTH_LEVELS];
+        for (level, value) in levels.iter_mut().zip(side.as_array().into_iter().flatten()) {
+            *level = DepthLevel {
+                price: price(&value["price"]),
+                quantity: quantity(&value["quantity"]),
+                orders: quantity(&value["orders"]) as u16,
+            };
+        }
+        levels
+    };
+
+    let depth = quote.get("depth").map(|depth| Depth {
+        buy: levels(&depth["buy"]),
+        sell: levels(&depth["sell"]),
+    });
+    let ohlc = Ohlc {
+        open: price(&quote["ohlc"]["open"]),
+        high: price(&quote["ohlc"]["high"]),
+        low: price(&quote["ohlc"]["low"]),
+        close: price(&quote["ohlc"]["close"]),
+    };
+    let last_price = price(&quote["last_price"]);
+    Some(Tick {
+        mode: if depth.is_some() { Mode::Full } else { Mode::Quote },
+        instrument_token: instrument_token(quote)?,
+        // Indices are quoted without depth
+        tradable: depth.is_some(),
+        last_price,
+        last_traded_quantity: quantity(&quote["last_quantity"]),
+        average_traded_price: price(&quote["average_price"]),
+        volume_traded: quantity(&quote["volume"]),
+        total_buy_quantity: quantity(&quote["buy_quantity"]),
+        total_sell_quantity: quantity(&quote["sell_quantity"]),
+        ohlc,
+        change: if ohlc.close != 0.0 {
+            (last_price - ohlc.close) * 100.0 / ohlc.close
+        } else {
+            0.0
+        },
+        last_trade_time: timestamp(&quote["last_trade_time"]),
+        oi: quantity(&quote["oi"]),
+        oi_day_high: quantity(&quote["oi_day_high"]),
+        oi_day_low: quantity(&quote["oi_day_low"]),
+        exchange_timestamp: timestamp(&quote["timestamp"]),
+        depth,
+        depth_20: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ticker::KiteTicker;
+    use futures::{SinkExt, StreamExt};
+    use mockito::{Matcher, Server};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    #[tokio::test]
+    async fn test_market_data() {
+        let mut server = Server::new_async().await;
+        // Only the instruments without ticks are requested
+        let ltp = server
+            .mock("GET", "/quote/ltp")
+            .match_query(Matcher::Exact("i=265".to_string()))
+            .with_body_from_file("mocks/ltp.json")
+            .expect(1)
+            .create_async()
+            .await;
+        let quote = server
+            .mock("GET", "/quote")
+            .match_query(Matcher::Exact("i=408065".to_string()))
+            .with_body_from_file("mocks/quote.json")
+            .expect(2)
+            .create_async()
+            .await;
+        let client = KiteConnect::builder("API_KEY")
+            .access_token("ACCESS_TOKEN")
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while !matches!(ws.next().await, Some(Ok(Message::Text(_)))) {}
+            // LTP packet of 408065 at 1500.01
+            ws.send(Message::binary(vec![0, 1, 0, 8, 0, 6, 58, 1, 0, 2, 73, 241])).await.unwrap();
+            while ws.next().await.is_some() {}
+        });
+        let ticker = KiteTicker::builder("key", "token")
+            .root_url(&url)
+            .connect()
+            .await
+            .unwrap();
+        let broadcast = ticker.broadcast(16);
+        let market = MarketData::new(client, &broadcast);
+        assert!(market.is_connected());
+        market.handle().set_mode(Mode::Ltp, [InstrumentToken(408065)]).unwrap();
+        while market.live(&[InstrumentToken(408065)], Mode::Ltp).is_empty() {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        let prices = market.ltp([InstrumentToken(408065), InstrumentToken(265)]).await.unwrap();
+        assert_eq!(prices, HashMap::from([(InstrumentToken(408065), 1500.01), (InstrumentToken(265), 52402.6)]));
+        ltp.assert_async().await;
+
+        // LTP ticks do not make a quote
+        let quotes = market.quote([InstrumentToken(408065)]).await.unwrap();
+        let infy = &quotes[&InstrumentToken(408065)];
+        assert_eq!((infy.mode, infy.last_price, infy.volume_traded), (Mode::Full, 1412.95, 7360198));
+        assert_eq!(infy.depth.unwrap().buy[0].quantity, 12);
+        assert_eq!(ist::format_timestamp(&infy.last_trade_time.unwrap()), "2021-06-08 15:45:52");
+
+        market.handle().close();
+        while market.is_connected() {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        market.quote([InstrumentToken(408065)]).await.unwrap();
+        quote.assert_async().await;
+    }
+}
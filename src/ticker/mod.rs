@@ -0,0 +1,276 @@
+//! # Websocket Ticker
+//!
+//! [`KiteTicker`] streams live market data over Kite's websocket API at
+//! `wss://ws.kite.trade`. After connecting with the API key and the access token
+//! of a session, subscribe to instrument tokens and read the decoded ticks:
+//!
+//! ```rust,no_run
+//! use kiteconnect::models::InstrumentToken;
+//! use kiteconnect::ticker::{KiteTicker, Mode, TickerEvent};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> kiteconnect::error::Result<()> {
+//! let mut ticker = KiteTicker::connect("api_key", "access_token").await?;
+//! ticker.subscribe([InstrumentToken(408065), InstrumentToken(884737)])?;
+//! ticker.set_mode(Mode::Full, [InstrumentToken(408065)])?;
+//!
+//! while let Some(event) = ticker.next_event().await {
+//!     match event {
+//!         TickerEvent::Ticks(ticks) => {
+//!             for tick in ticks {
+//!                 println!("{}: {}", tick.instrument_token, tick.last_price);
+//!             }
+//!         }
+//!         TickerEvent::Closed => break,
+//!         other => println!("{:?}", other),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The events are also available as a `futures` [`Stream`] through
+//! [`KiteTicker::stream`], while a cloneable [`TickerHandle`] changes the
+//! subscriptions. Subscriptions are counted per handle, so one consumer
+//! unsubscribing does not stop data another one still needs. Updates of the user's orders arrive on the same connection
+//! as [`TickerEvent::OrderUpdate`], while malformed messages, errors sent by
+//! Kite and connection failures are reported as a [`TickerError`]. Code ported from pykiteconnect can
+//! implement the callbacks of [`TickerHandler`] instead and hand it to
+//! [`KiteTicker::run`], while [`KiteTicker::forward`] pipes the events into a
+//! message bus through a [`TickSink`]. Several tasks can share one connection
+//! through [`KiteTicker::broadcast`], and an [`LtpCache`] keeps the last
+//! prices of instruments for lock-free reads. [`MarketData`] answers price and
+//! quote requests from the ticker, falling back to the REST API.
+//!
+//! Instruments are identified by their instrument token; a [`SymbolResolver`]
+//! looks the tokens of `"NSE:INFY"`-style names up in the instruments dump for
+//! [`TickerHandle::subscribe_symbols`], and [`SubscriptionGroups`] manage
+//! named watchlists with their own modes. They are streamed in [`Mode::Quote`]
+//! once subscribed, until [`KiteTicker::set_mode`] changes their mode. Binary
+//! frames are decoded into [`Tick`]s according to the mode of each packet: LTP
+//! packets carry the last price only, quote packets add volume and OHLC, and
+//! full packets add open interest, timestamps and five levels of market
+//! [`Depth`]. Accounts entitled to the extended depth feed can stream
+//! [`Mode::Depth20`], whose ticks carry twenty levels in [`Tick::depth_20`];
+//! Kite answers others with an error. Indices, which are not tradable, only
+//! carry prices.
+//! [`decode_frame`] decodes frames obtained elsewhere without allocating, and
+//! a [`TickRecorder`] captures the raw frames to disk for later analysis.
+//! [`CandleBuilder`] aggregates ticks into OHLCV candles aligned to the IST
+//! clock. With the `arrow` feature, ticks, candles and instruments convert to
+//! Arrow record batches through `Columnar` and are written to Parquet files by
+//! `ParquetWriter`.
+//!
+//! Dropped connections are re-established with exponential backoff and the
+//! subscriptions are restored with their modes, announced by
+//! [`TickerEvent::Reconnecting`] and [`TickerEvent::Connected`]. Connections
+//! that go silent, without even the heartbeats Kite sends every second, are
+//! treated as dropped after [`TickerBuilder::stale_timeout`].
+//!
+//! [`KiteTicker::builder`] configures reconnection, timeouts, the event queue,
+//! a different URL and the subscriptions to apply once connected.
+//! [`TickerHandle::stats`] counts messages, ticks, reconnections and decoding
+//! errors for monitoring. Events are queued without limit by default. A
+//! consumer that may fall behind can bound the number of queued tick events
+//! with [`TickerBuilder::channel_capacity`] and choose which ticks are dropped,
+//! if any, with [`TickerBuilder::overflow_policy`].
+//!
+//! The protocol runs over a [`WsTransport`], which only moves messages. The
+//! default [`TungsteniteConnector`] opens them with `tokio-tungstenite`; a
+//! different [`WsConnector`] set with [`TickerBuilder::connector`] runs the
+//! ticker over another websocket stack or a scripted connection in tests.
+//! [`TickerBuilder::proxy`] tunnels the connection through an HTTP or SOCKS5
+//! proxy, such as the one configured on the REST client.
+//!
+//! On `wasm32` the ticker runs on the browser's `WebSocket` and decodes the
+//! same frames. Browsers do not expose why a connection failed, and the
+//! ticker stops instead of reconnecting when the connection drops. Requires
+//! the `ticker` feature (enabled by default).
+
+#[cfg(not(target_arch = "wasm32"))]
+mod broadcast;
+mod candles;
+#[cfg(feature = "arrow")]
+mod columnar;
+#[cfg(not(target_arch = "wasm32"))]
+mod groups;
+#[cfg(not(target_arch = "wasm32"))]
+mod handler;
+#[cfg(not(target_arch = "wasm32"))]
+mod ltp;
+#[cfg(not(target_arch = "wasm32"))]
+mod market;
+#[cfg(not(target_arch = "wasm32"))]
+mod native;
+mod packet;
+#[cfg(not(target_arch = "wasm32"))]
+mod queue;
+#[cfg(not(target_arch = "wasm32"))]
+mod recorder;
+#[cfg(not(target_arch = "wasm32"))]
+mod sink;
+#[cfg(not(target_arch = "wasm32"))]
+mod stats;
+#[cfg(not(target_arch = "wasm32"))]
+mod symbols;
+mod subscriptions;
+mod transport;
+#[cfg(not(target_arch = "wasm32"))]
+mod tunnel;
+#[cfg(target_arch = "wasm32")]
+mod web;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use broadcast::TickerBroadcast;
+pub use candles::{Candle, CandleBuilder};
+#[cfg(feature = "arrow")]
+pub use columnar::{Columnar, ParquetWriter};
+#[cfg(not(target_arch = "wasm32"))]
+pub use groups::SubscriptionGroups;
+#[cfg(not(target_arch = "wasm32"))]
+pub use handler::TickerHandler;
+#[cfg(not(target_arch = "wasm32"))]
+pub use ltp::{LastPrice, LtpCache};
+#[cfg(not(target_arch = "wasm32"))]
+pub use market::MarketData;
+#[cfg(not(target_arch = "wasm32"))]
+pub use native::{KiteTicker, TickerBuilder, TickerHandle};
+pub use packet::{decode_frame, Depth, Depth20, DepthLevel, Mode, Ohlc, Packets, Tick, DEPTH_20_LEVELS, DEPTH_LEVELS};
+#[cfg(not(target_arch = "wasm32"))]
+pub use queue::OverflowPolicy;
+#[cfg(not(target_arch = "wasm32"))]
+pub use recorder::{read_recording, RecordedFrame, Recording, Rotation, TickRecorder};
+#[cfg(not(target_arch = "wasm32"))]
+pub use sink::TickSink;
+#[cfg(not(target_arch = "wasm32"))]
+pub use stats::{TickerRates, TickerStats};
+#[cfg(not(target_arch = "wasm32"))]
+pub use symbols::SymbolResolver;
+#[cfg(not(target_arch = "wasm32"))]
+pub use transport::{TungsteniteConnector, WsConnector};
+pub use transport::{WsMessage, WsTransport};
+#[cfg(target_arch = "wasm32")]
+pub use web::{KiteTicker, TickerHandle};
+
+use crate::models::{InstrumentToken, Order};
+use serde_json::{json, Value as JsonValue};
+use std::time::Duration;
+
+/// Root URL of the Kite websocket API
+pub const DEFAULT_TICKER_URL: &str = "wss://ws.kite.trade";
+
+/// Events delivered by a [`KiteTicker`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum TickerEvent {
+    /// The connection was established, or re-established with subscriptions restored
+    Connected,
+    /// Ticks decoded from one binary frame
+    Ticks(Vec<Tick>),
+    /// A message that could not be decoded, an error sent by Kite or a failure
+    /// of the connection
+    Error(TickerError),
+    /// A change of one of the user's orders, with the same payload as a postback
+    OrderUpdate(Box<Order>),
+    /// The connection dropped and is re-established after `delay`
+    Reconnecting {
+        /// Number of the reconnection attempt, starting at 1
+        attempt: u32,
+        /// Delay before the attempt
+        delay: Duration,
+    },
+    /// The ticker stopped after [`KiteTicker::close`] or when reconnecting gave up
+    Closed,
+}
+
+/// Error reported by a [`KiteTicker`] as [`TickerEvent::Error`]
+///
+/// None of them stop the ticker: malformed messages are skipped, and failed
+/// connections are followed by [`TickerEvent::Reconnecting`] or
+/// [`TickerEvent::Closed`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum TickerError {
+    /// A binary frame or text message could not be decoded and was skipped, in
+    /// whole or in part
+    #[error("malformed ticker message of {frame_len} bytes: {message}")]
+    Parse {
+        /// What could not be decoded
+        message: String,
+        /// Length of the frame or text message in bytes
+        frame_len: usize,
+        /// Instrument token of the skipped packet, if it was readable
+        instrument_token: Option<InstrumentToken>,
+    },
+    /// An error message sent by Kite, e.g. for an invalid subscription
+    #[error("{0}")]
+    Protocol(String),
+    /// The connection failed or went silent, or a reconnection attempt failed
+    #[error("{0}")]
+    Connection(String),
+}
+
+/// Instructions sent from the handle to the connection task
+#[derive(Debug)]
+enum Command {
+    Subscribe(Vec<InstrumentToken>),
+    Unsubscribe(Vec<InstrumentToken>),
+    SetMode(Mode, Vec<InstrumentToken>),
+    Close,
+}
+
+impl Command {
+    /// Returns the protocol message of the command, `None` for [`Command::Close`]
+    fn text(&self) -> Option<String> {
+        let message = match self {
+            Command::Subscribe(tokens) => json!({ "a": "subscribe", "v": tokens }),
+            Command::Unsubscribe(tokens) => json!({ "a": "unsubscribe", "v": tokens }),
+            Command::SetMode(mode, tokens) => json!({ "a": "mode", "v": [mode.as_str(), tokens] }),
+            Command::Close => return None,
+        };
+        Some(message.to_string())
+    }
+}
+
+/// Collects instrument tokens given by value or by reference
+fn collect_tokens<I>(tokens: I) -> Vec<InstrumentToken>
+where
+    I: IntoIterator,
+    I::Item: Into<InstrumentToken>,
+{
+    tokens.into_iter().map(Into::into).collect()
+}
+
+/// Decodes a text message, which carries JSON such as order updates and error
+/// notices, into its event; `None` for messages of other types
+fn parse_text(text: &str) -> Result<Option<TickerEvent>, TickerError> {
+    let malformed = |message: String| TickerError::Parse {
+        message,
+        frame_len: text.len(),
+        instrument_token: None,
+    };
+    let mut message: JsonValue = serde_json::from_str(text).map_err(|e| malformed(format!("{}: {}", e, text)))?;
+    match message["type"].as_str() {
+        Some("order") => serde_json::from_value::<Order>(message["data"].take())
+            .map(|order| Some(TickerEvent::OrderUpdate(Box::new(order))))
+            .map_err(|e| malformed(format!("order update: {}", e))),
+        Some("error") => {
+            let error = message["data"].as_str().map(str::to_string).unwrap_or_else(|| message["data"].to_string());
+            Ok(Some(TickerEvent::Error(TickerError::Protocol(error))))
+        }
+        _ => {
+            log::debug!("Ignoring ticker message of type {}", message["type"]);
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_tokens() {
+        let tokens = vec![InstrumentToken(408065), InstrumentToken(884737)];
+        assert_eq!(collect_tokens(&tokens), tokens);
+        assert_eq!(collect_tokens(tokens.clone()), tokens);
+    }
+}
@@ -0,0 +1,996 @@
+//! Ticker connection served by a tokio task
+
+use super::queue::EventQueue;
+use super::subscriptions::{HolderId, Subscriptions};
+use super::recorder::{FrameSender, TickRecorder};
+use super::transport::{TungsteniteConnector, WsConnector, WsMessage, WsTransport};
+use super::{
+    collect_tokens, packet, stats, Command, Mode, OverflowPolicy, TickerError, TickerEvent, TickerStats,
+    DEFAULT_TICKER_URL,
+};
+use crate::error::{KiteError, Result};
+use crate::models::InstrumentToken;
+use crate::proxy::ProxyConfig;
+use crate::retry::RetryPolicy;
+use futures::Stream;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+type Socket = Box<dyn WsTransport + Send>;
+
+/// State shared between the handles and the connection task
+#[derive(Debug, Default)]
+struct Shared {
+    /// Subscribed instrument tokens and the handles holding them
+    subscriptions: Mutex<Subscriptions>,
+    /// When the last message, including heartbeats, was received
+    last_message_at: Mutex<Option<Instant>>,
+    stats: stats::Counters,
+}
+
+/// Live market data connection
+///
+/// The connection is served by a background task, which stops when the ticker
+/// is dropped. Events can be read one by one with [`next_event`](Self::next_event)
+/// or as a [`Stream`] with [`stream`](Self::stream) and
+/// [`into_stream`](Self::into_stream).
+#[derive(Debug)]
+pub struct KiteTicker {
+    handle: TickerHandle,
+    events: Arc<EventQueue>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl KiteTicker {
+    /// Connects to the Kite websocket API with the default settings
+    ///
+    /// Returns an error if the connection cannot be established, e.g. a
+    /// [`KiteError::Token`] if the access token was rejected.
+    pub async fn connect(api_key: &str, access_token: &str) -> Result<Self> {
+        Self::builder(api_key, access_token).connect().await
+    }
+
+    /// Returns a builder for a ticker with custom settings
+    pub fn builder(api_key: &str, access_token: &str) -> TickerBuilder {
+        TickerBuilder {
+            api_key: api_key.to_string(),
+            access_token: access_token.to_string(),
+            root_url: DEFAULT_TICKER_URL.to_string(),
+            reconnect: default_reconnect_policy(),
+            stale_timeout: DEFAULT_STALE_TIMEOUT,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            capacity: None,
+            overflow: OverflowPolicy::default(),
+            subscriptions: BTreeMap::new(),
+            recorder: None,
+            connector: Arc::new(TungsteniteConnector::new()),
+        }
+    }
+
+    /// Returns a handle changing the subscriptions of this ticker, e.g. while its
+    /// events are consumed as a stream
+    pub fn handle(&self) -> TickerHandle {
+        self.handle.clone()
+    }
+
+    /// Subscribes to market data of the given instrument tokens, see [`TickerHandle::subscribe`]
+    pub fn subscribe(&self, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Result<()> {
+        self.handle.subscribe(tokens)
+    }
+
+    /// Stops market data of the given instrument tokens, see [`TickerHandle::unsubscribe`]
+    pub fn unsubscribe(&self, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Result<()> {
+        self.handle.unsubscribe(tokens)
+    }
+
+    /// Streams the given instrument tokens in `mode`, see [`TickerHandle::set_mode`]
+    pub fn set_mode(&self, mode: Mode, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Result<()> {
+        self.handle.set_mode(mode, tokens)
+    }
+
+    /// Returns the subscribed instrument tokens and their modes
+    pub fn subscriptions(&self) -> BTreeMap<InstrumentToken, Mode> {
+        self.handle.subscriptions()
+    }
+
+    /// Returns when the last message, including heartbeats, was received
+    pub fn last_message_at(&self) -> Option<Instant> {
+        self.handle.last_message_at()
+    }
+
+    /// Returns the counters of the connection, see [`TickerHandle::stats`]
+    pub fn stats(&self) -> TickerStats {
+        self.handle.stats()
+    }
+
+    /// Closes the connection; the ticker delivers [`TickerEvent::Closed`] and stops
+    pub fn close(&self) {
+        self.handle.close()
+    }
+
+    /// Waits for the next event, returning `None` after [`TickerEvent::Closed`]
+    pub async fn next_event(&mut self) -> Option<TickerEvent> {
+        self.events.pop().await
+    }
+
+    /// Returns the events as a stream, which ends after [`TickerEvent::Closed`]
+    ///
+    /// ```rust,no_run
+    /// use futures::StreamExt;
+    /// use kiteconnect::models::InstrumentToken;
+    /// use kiteconnect::ticker::{KiteTicker, TickerEvent};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> kiteconnect::error::Result<()> {
+    /// let mut ticker = KiteTicker::connect("api_key", "access_token").await?;
+    /// let handle = ticker.handle();
+    /// handle.subscribe([InstrumentToken(408065)])?;
+    ///
+    /// let mut events = ticker.stream();
+    /// while let Some(event) = events.next().await {
+    ///     if let TickerEvent::Ticks(ticks) = event {
+    ///         println!("{:?}", ticks);
+    ///         handle.subscribe([InstrumentToken(884737)])?;
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stream(&mut self) -> impl Stream<Item = TickerEvent> + Send + '_ {
+        let events = &*self.events;
+        Box::pin(futures::stream::unfold(events, |events| async move {
+            let event = events.pop().await?;
+            Some((event, events))
+        }))
+    }
+
+    /// Turns the ticker into a stream of its events, e.g. to move it into a task
+    ///
+    /// The connection stops when the stream is dropped. Use a [`handle`](Self::handle)
+    /// taken beforehand to change the subscriptions.
+    pub fn into_stream(self) -> impl Stream<Item = TickerEvent> + Send + 'static {
+        futures::stream::unfold(self, |mut ticker| async move {
+            let event = ticker.next_event().await?;
+            Some((event, ticker))
+        })
+    }
+}
+
+/// Cloneable handle changing the subscriptions of a [`KiteTicker`]
+///
+/// Handles keep working while the ticker reconnects, and return an error once
+/// it has stopped. Every clone is a consumer of its own: a token stays
+/// subscribed until all handles that subscribed to it unsubscribe, and is
+/// streamed in the most detailed mode any of them set. Subscriptions outlive
+/// the handle that made them, see [`unsubscribe_all`](Self::unsubscribe_all).
+#[derive(Debug)]
+pub struct TickerHandle {
+    commands: mpsc::UnboundedSender<Command>,
+    shared: Arc<Shared>,
+    holder: HolderId,
+}
+
+impl Clone for TickerHandle {
+    fn clone(&self) -> Self {
+        TickerHandle {
+            commands: self.commands.clone(),
+            shared: self.shared.clone(),
+            holder: self.shared.subscriptions.lock().unwrap().new_holder(),
+        }
+    }
+}
+
+impl TickerHandle {
+    /// Subscribes to market data of the given instrument tokens in [`Mode::Quote`]
+    ///
+    /// Tokens this handle already subscribed to keep their mode. Subscriptions
+    /// are kept across reconnects. Returns an error if the ticker has stopped.
+    pub fn subscribe(&self, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Result<()> {
+        self.ensure_running()?;
+        let tokens = collect_tokens(tokens);
+        let commands = self.shared.subscriptions.lock().unwrap().subscribe(self.holder, &tokens);
+        self.send(commands)
+    }
+
+    /// Stops market data of the given instrument tokens, unless other handles
+    /// are subscribed to them
+    pub fn unsubscribe(&self, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Result<()> {
+        self.ensure_running()?;
+        let tokens = collect_tokens(tokens);
+        let commands = self.shared.subscriptions.lock().unwrap().unsubscribe(self.holder, &tokens);
+        self.send(commands)
+    }
+
+    /// Releases every subscription of this handle, e.g. once its consumer is done
+    pub fn unsubscribe_all(&self) -> Result<()> {
+        self.ensure_running()?;
+        let commands = self.shared.subscriptions.lock().unwrap().unsubscribe_all(self.holder);
+        self.send(commands)
+    }
+
+    /// Streams the given instrument tokens in `mode`, subscribing those that are not yet
+    ///
+    /// Tokens another handle streams in a more detailed mode keep that mode
+    /// until it unsubscribes.
+    pub fn set_mode(&self, mode: Mode, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Result<()> {
+        self.ensure_running()?;
+        let tokens = collect_tokens(tokens);
+        let commands = self.shared.subscriptions.lock().unwrap().set_mode(self.holder, mode, &tokens);
+        self.send(commands)
+    }
+
+    /// Returns the instrument tokens subscribed by any handle and the modes
+    /// they are streamed in
+    pub fn subscriptions(&self) -> BTreeMap<InstrumentToken, Mode> {
+        self.shared.subscriptions.lock().unwrap().modes()
+    }
+
+    /// Returns when the last message, including heartbeats, was received
+    ///
+    /// Kite sends a heartbeat every second while no market data is streamed,
+    /// so a value older than a few seconds indicates a stale connection.
+    pub fn last_message_at(&self) -> Option<Instant> {
+        *self.shared.last_message_at.lock().unwrap()
+    }
+
+    /// Returns a snapshot of the message, tick, reconnection and error counters
+    pub fn stats(&self) -> TickerStats {
+        self.shared.stats.snapshot()
+    }
+
+    /// Closes the connection; the ticker delivers [`TickerEvent::Closed`] and stops
+    pub fn close(&self) {
+        let _ = self.commands.send(Command::Close);
+    }
+
+    fn ensure_running(&self) -> Result<()> {
+        if self.commands.is_closed() {
+            return Err(KiteError::Other("ticker is closed".to_string()));
+        }
+        Ok(())
+    }
+
+    fn send(&self, commands: Vec<Command>) -> Result<()> {
+        for command in commands {
+            self.commands
+                .send(command)
+                .map_err(|_| KiteError::Other("ticker is closed".to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for KiteTicker {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Builder for a [`KiteTicker`] with custom settings
+///
+/// ```rust,no_run
+/// use kiteconnect::models::InstrumentToken;
+/// use kiteconnect::retry::RetryPolicy;
+/// use kiteconnect::ticker::{KiteTicker, Mode};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let ticker = KiteTicker::builder("api_key", "access_token")
+///     .reconnect(RetryPolicy::default().max_attempts(10))
+///     .connect_timeout(Duration::from_secs(5))
+///     .channel_capacity(1024)
+///     .subscribe([InstrumentToken(408065), InstrumentToken(884737)])
+///     .set_mode(Mode::Full, [InstrumentToken(408065)])
+///     .connect()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct TickerBuilder {
+    api_key: String,
+    access_token: String,
+    root_url: String,
+    reconnect: RetryPolicy,
+    stale_timeout: Duration,
+    connect_timeout: Duration,
+    capacity: Option<usize>,
+    overflow: OverflowPolicy,
+    subscriptions: BTreeMap<InstrumentToken, Mode>,
+    recorder: Option<TickRecorder>,
+    connector: Arc<dyn WsConnector>,
+}
+
+impl fmt::Debug for TickerBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TickerBuilder")
+            .field("api_key", &self.api_key)
+            .field("access_token", &"***")
+            .field("root_url", &self.root_url)
+            .field("reconnect", &self.reconnect)
+            .field("stale_timeout", &self.stale_timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("capacity", &self.capacity)
+            .field("overflow", &self.overflow)
+            .field("subscriptions", &self.subscriptions)
+            .field("recorder", &self.recorder)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TickerBuilder {
+    /// Sets how long the connection may go without any message, heartbeats
+    /// included, before it is considered stale and re-established; 5s by default
+    pub fn stale_timeout(mut self, timeout: Duration) -> Self {
+        self.stale_timeout = timeout;
+        self
+    }
+
+    /// Bounds the event queue to `capacity` tick events, which are dropped or
+    /// wait according to the [`overflow_policy`](Self::overflow_policy) once full
+    ///
+    /// The queue is unbounded by default. Other events never count against the
+    /// capacity and are always delivered.
+    pub fn channel_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity.max(1));
+        self
+    }
+
+    /// Sets what happens to ticks arriving while a bounded queue is full;
+    /// [`OverflowPolicy::DropOldest`] by default
+    pub fn overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+
+    /// Sets how long opening the websocket may take, for the first connection
+    /// and every reconnection; 10s by default
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Sets how dropped connections are re-established; by default up to 50
+    /// attempts, backing off from one second to a minute
+    ///
+    /// [`RetryPolicy::none`] stops the ticker as soon as the connection drops.
+    pub fn reconnect(mut self, policy: RetryPolicy) -> Self {
+        self.reconnect = policy;
+        self
+    }
+
+    /// Overrides the websocket URL, [`DEFAULT_TICKER_URL`] by default, e.g. to
+    /// connect to a mock server in tests
+    pub fn root_url(mut self, root_url: &str) -> Self {
+        self.root_url = root_url.to_string();
+        self
+    }
+
+    /// Subscribes to the given instrument tokens in [`Mode::Quote`] as soon as connected
+    pub fn subscribe(mut self, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Self {
+        for token in collect_tokens(tokens) {
+            self.subscriptions.entry(token).or_insert(Mode::Quote);
+        }
+        self
+    }
+
+    /// Subscribes to the given instrument tokens in `mode` as soon as connected
+    pub fn set_mode(mut self, mode: Mode, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Self {
+        self.subscriptions.extend(collect_tokens(tokens).into_iter().map(|token| (token, mode)));
+        self
+    }
+
+    /// Opens the websocket connections with `connector` instead of
+    /// [`TungsteniteConnector`], e.g. to run the ticker over a scripted
+    /// transport in tests
+    pub fn connector(mut self, connector: Arc<dyn WsConnector>) -> Self {
+        self.connector = connector;
+        self
+    }
+
+    /// Connects through a proxy, e.g. the one of the REST client
+    ///
+    /// Replaces the [`connector`](Self::connector) with a
+    /// [`TungsteniteConnector`] tunnelling through `proxy`, which has to be an
+    /// `http://`, `socks5://` or `socks5h://` proxy.
+    ///
+    /// ```rust,no_run
+    /// use kiteconnect::connect::KiteConnect;
+    /// use kiteconnect::proxy::ProxyConfig;
+    /// use kiteconnect::ticker::KiteTicker;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> kiteconnect::error::Result<()> {
+    /// let client = KiteConnect::builder("api_key")
+    ///     .access_token("access_token")
+    ///     .proxy(ProxyConfig::all("http://proxy.internal:3128"))
+    ///     .build()?;
+    /// let mut builder = KiteTicker::builder("api_key", &client.access_token());
+    /// if let Some(proxy) = client.proxy() {
+    ///     builder = builder.proxy(proxy.clone());
+    /// }
+    /// let ticker = builder.connect().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.connector = Arc::new(TungsteniteConnector::with_proxy(proxy));
+        self
+    }
+
+    /// Writes every binary frame received to disk, see [`TickRecorder`]
+    pub fn record(mut self, recorder: TickRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Connects to the websocket API and applies the initial subscriptions
+    ///
+    /// Returns an error if the connection cannot be established, e.g. a
+    /// [`KiteError::Token`] if the access token was rejected.
+    pub async fn connect(self) -> Result<KiteTicker> {
+        let mut url = url::Url::parse(&self.root_url)
+            .map_err(|e| KiteError::Other(format!("invalid ticker URL {:?}: {}", self.root_url, e)))?;
+        url.query_pairs_mut()
+            .append_pair("api_key", &self.api_key)
+            .append_pair("access_token", &self.access_token);
+
+        let (commands, command_rx) = mpsc::unbounded_channel();
+        let events = Arc::new(EventQueue::new(self.capacity, self.overflow));
+        let shared = Arc::new(Shared::default());
+        let holder = {
+            let mut subscriptions = shared.subscriptions.lock().unwrap();
+            let holder = subscriptions.new_holder();
+            for (&token, &mode) in &self.subscriptions {
+                subscriptions.set_mode(holder, mode, &[token]);
+            }
+            holder
+        };
+        let connection = Connection {
+            url,
+            reconnect: self.reconnect,
+            stale_timeout: self.stale_timeout,
+            connect_timeout: self.connect_timeout,
+            recorder: self.recorder.map(TickRecorder::start).transpose()?,
+            connector: self.connector,
+            shared: shared.clone(),
+            events: events.clone(),
+        };
+        let mut socket = connection.open().await?;
+        connection
+            .restore(&mut socket)
+            .await
+            .map_err(|e| KiteError::Other(format!("failed to subscribe on the ticker: {}", e)))?;
+        connection.emit(TickerEvent::Connected).await;
+        let task = tokio::spawn(connection.run(socket, command_rx));
+        Ok(KiteTicker {
+            handle: TickerHandle { commands, shared, holder },
+            events,
+            task,
+        })
+    }
+}
+
+/// Time without any message after which a connection is considered stale
+const DEFAULT_STALE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time allowed for opening the websocket
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Reconnects up to 50 times, backing off from one second to a minute
+fn default_reconnect_policy() -> RetryPolicy {
+    RetryPolicy::default()
+        .max_attempts(50)
+        .base_delay(Duration::from_secs(1))
+        .max_delay(Duration::from_secs(60))
+}
+
+/// How serving a connection ended
+enum Exit {
+    /// Closed on request; the ticker stops
+    Closed,
+    /// Dropped by the network or the server; the ticker reconnects
+    Dropped,
+}
+
+/// State of the connection task
+struct Connection {
+    url: url::Url,
+    reconnect: RetryPolicy,
+    stale_timeout: Duration,
+    connect_timeout: Duration,
+    recorder: Option<FrameSender>,
+    connector: Arc<dyn WsConnector>,
+    shared: Arc<Shared>,
+    events: Arc<EventQueue>,
+}
+
+impl Connection {
+    async fn run(mut self, mut socket: Socket, mut commands: mpsc::UnboundedReceiver<Command>) {
+        loop {
+            match self.serve(&mut socket, &mut commands).await {
+                Exit::Closed => break,
+                Exit::Dropped => match self.reconnect(&mut commands).await {
+                    Some(reconnected) => {
+                        socket = reconnected;
+                        self.shared.stats.reconnect();
+                        self.emit(TickerEvent::Connected).await;
+                    }
+                    None => break,
+                },
+            }
+        }
+        self.emit(TickerEvent::Closed).await;
+    }
+
+    /// Serves the connection until it ends or goes stale
+    async fn serve(&mut self, socket: &mut Socket, commands: &mut mpsc::UnboundedReceiver<Command>) -> Exit {
+        let mut deadline = tokio::time::Instant::now() + self.stale_timeout;
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep_until(deadline) => {
+                    log::warn!("No ticker data for {:?}, reconnecting", self.stale_timeout);
+                    self.connection_error(format!("no ticker data for {:?}", self.stale_timeout)).await;
+                    return Exit::Dropped;
+                }
+                command = commands.recv() => match command.as_ref().and_then(Command::text) {
+                    Some(text) => {
+                        if let Err(e) = socket.send(text).await {
+                            log::warn!("Failed to send ticker command: {}", e);
+                            self.connection_error(e.to_string()).await;
+                            return Exit::Dropped;
+                        }
+                    }
+                    None => {
+                        let _ = socket.close().await;
+                        return Exit::Closed;
+                    }
+                },
+                message = socket.recv() => {
+                    if let Some(exit) = self.handle_message(message).await {
+                        return exit;
+                    }
+                    deadline = tokio::time::Instant::now() + self.stale_timeout;
+                }
+            }
+        }
+    }
+
+    /// Handles a message read from the socket, returning how the connection ended if it did
+    async fn handle_message(&self, message: Option<Result<WsMessage>>) -> Option<Exit> {
+        let message = match message {
+            Some(Ok(message)) => message,
+            Some(Err(e)) => {
+                log::warn!("Ticker connection failed: {}", e);
+                self.connection_error(e.to_string()).await;
+                return Some(Exit::Dropped);
+            }
+            None => return Some(Exit::Dropped),
+        };
+        *self.shared.last_message_at.lock().unwrap() = Some(Instant::now());
+        self.shared.stats.message(message.len());
+
+        match message {
+            // Heartbeats are single byte frames without packets
+            WsMessage::Binary(frame) => {
+                let packets = packet::packet_count(&frame);
+                if let (Some(recorder), true) = (&self.recorder, packets > 0) {
+                    recorder.record(&frame);
+                }
+                let (ticks, errors) = packet::parse_frame(&frame);
+                self.shared.stats.ticks(ticks.len());
+                self.shared.stats.parse_errors(packets - ticks.len());
+                if !ticks.is_empty() {
+                    self.emit(TickerEvent::Ticks(ticks)).await;
+                }
+                for error in errors {
+                    log::warn!("Skipping ticker data: {}", error);
+                    self.emit(TickerEvent::Error(error)).await;
+                }
+            }
+            WsMessage::Text(text) => self.handle_text(&text).await,
+        }
+        None
+    }
+
+    /// Reconnects with backoff, returning `None` if the ticker should stop instead
+    async fn reconnect(&mut self, commands: &mut mpsc::UnboundedReceiver<Command>) -> Option<Socket> {
+        for attempt in 1..=self.reconnect.max_attempts {
+            let delay = self.reconnect.delay(attempt);
+            self.emit(TickerEvent::Reconnecting { attempt, delay }).await;
+
+            let sleep = tokio::time::sleep(delay);
+            tokio::pin!(sleep);
+            loop {
+                tokio::select! {
+                    _ = &mut sleep => break,
+                    // Subscription changes are restored from the shared table once reconnected
+                    command = commands.recv() => {
+                        if matches!(command, Some(Command::Close) | None) {
+                            return None;
+                        }
+                    }
+                }
+            }
+
+            match self.open().await {
+                Ok(mut socket) => match self.restore(&mut socket).await {
+                    Ok(()) => return Some(socket),
+                    Err(e) => {
+                        log::warn!("Failed to restore ticker subscriptions: {}", e);
+                        self.connection_error(e.to_string()).await;
+                    }
+                },
+                Err(e) if e.is_token_error() => {
+                    log::error!("Ticker access token rejected, not reconnecting: {}", e);
+                    self.connection_error(e.to_string()).await;
+                    return None;
+                }
+                Err(e) => {
+                    log::warn!("Ticker reconnection attempt {} failed: {}", attempt, e);
+                    self.connection_error(e.to_string()).await;
+                }
+            }
+        }
+        log::error!("Giving up reconnecting the ticker after {} attempts", self.reconnect.max_attempts);
+        None
+    }
+
+    /// Opens a websocket to the ticker within the connect timeout
+    async fn open(&self) -> Result<Socket> {
+        tokio::time::timeout(self.connect_timeout, self.connector.connect(&self.url))
+            .await
+            .map_err(|_| KiteError::Other(format!("timed out connecting to the ticker after {:?}", self.connect_timeout)))?
+    }
+
+    /// Re-creates the current subscriptions on a new connection
+    async fn restore(&self, socket: &mut Socket) -> Result<()> {
+        for text in self.restore_commands().iter().filter_map(Command::text) {
+            socket.send(text).await?;
+        }
+        Ok(())
+    }
+
+    fn restore_commands(&self) -> Vec<Command> {
+        let subscriptions = self.shared.subscriptions.lock().unwrap().modes();
+        if subscriptions.is_empty() {
+            return Vec::new();
+        }
+        let mut commands = vec![Command::Subscribe(subscriptions.keys().copied().collect())];
+        // Subscribed tokens start in quote mode
+        for mode in [Mode::Ltp, Mode::Full, Mode::Depth20] {
+            let tokens: Vec<InstrumentToken> = subscriptions
+                .iter()
+                .filter(|(_, &subscribed)| subscribed == mode)
+                .map(|(&token, _)| token)
+                .collect();
+            if !tokens.is_empty() {
+                commands.push(Command::SetMode(mode, tokens));
+            }
+        }
+        commands
+    }
+
+    /// Handles a text message, which carries JSON such as order updates and error notices
+    async fn handle_text(&self, text: &str) {
+        match super::parse_text(text) {
+            Ok(Some(event)) => self.emit(event).await,
+            Ok(None) => {}
+            Err(e) => {
+                log::warn!("Ignoring malformed ticker message: {}", e);
+                self.shared.stats.parse_errors(1);
+                self.emit(TickerEvent::Error(e)).await;
+            }
+        }
+    }
+
+    async fn connection_error(&self, message: String) {
+        self.emit(TickerEvent::Error(TickerError::Connection(message))).await;
+    }
+
+    async fn emit(&self, event: TickerEvent) {
+        let dropped = self.events.push(event).await;
+        if dropped > 0 {
+            self.shared.stats.dropped_ticks(dropped);
+            log::debug!("Ticker event queue is full, dropped {} ticks", dropped);
+        }
+    }
+}
+
+impl Drop for Connection {
+    /// Ends the events once the task stops, including when it is aborted
+    fn drop(&mut self) {
+        self.events.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ticker::transport::mock::MockConnector;
+    use futures::{SinkExt, StreamExt};
+    use serde_json::{json, Value as JsonValue};
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::WebSocketStream;
+
+    /// Builds a frame with an LTP packet for each `(token, price in paise)` pair
+    fn ltp_frame(packets: &[(u32, i32)]) -> Vec<u8> {
+        let mut frame = (packets.len() as u16).to_be_bytes().to_vec();
+        for (token, price) in packets {
+            frame.extend_from_slice(&8u16.to_be_bytes());
+            frame.extend_from_slice(&token.to_be_bytes());
+            frame.extend_from_slice(&price.to_be_bytes());
+        }
+        frame
+    }
+
+    async fn next_text(server: &mut WebSocketStream<TcpStream>) -> JsonValue {
+        loop {
+            match server.next().await.unwrap().unwrap() {
+                Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+                _ => continue,
+            }
+        }
+    }
+
+    fn test_policy() -> RetryPolicy {
+        RetryPolicy::default()
+            .max_attempts(3)
+            .base_delay(Duration::from_millis(10))
+            .jitter(false)
+    }
+
+    #[tokio::test]
+    async fn test_ticker() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut server = tokio_tungstenite::accept_async(stream).await.unwrap();
+            assert_eq!(next_text(&mut server).await, json!({"a": "subscribe", "v": [408065, 884737]}));
+            assert_eq!(next_text(&mut server).await, json!({"a": "subscribe", "v": [260105]}));
+            assert_eq!(next_text(&mut server).await, json!({"a": "mode", "v": ["full", [408065, 260105]]}));
+            assert_eq!(next_text(&mut server).await, json!({"a": "unsubscribe", "v": [884737]}));
+            server
+                .send(Message::binary(ltp_frame(&[(408065, 150_025), (884737, 91_000)])))
+                .await
+                .unwrap();
+            server
+                .send(Message::text(r#"{"type":"error","data":"Invalid token"}"#))
+                .await
+                .unwrap();
+            let postback = std::fs::read_to_string("mocks/postback.json").unwrap();
+            server
+                .send(Message::text(format!(r#"{{"type":"order","data":{}}}"#, postback)))
+                .await
+                .unwrap();
+            server.send(Message::text(r#"{"type":"order","data":"oops"}"#)).await.unwrap();
+            // Drop the connection; the ticker reconnects and restores subscriptions and modes
+            drop(server);
+
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut server = tokio_tungstenite::accept_async(stream).await.unwrap();
+            assert_eq!(next_text(&mut server).await, json!({"a": "subscribe", "v": [260105, 408065]}));
+            assert_eq!(next_text(&mut server).await, json!({"a": "mode", "v": ["full", [260105, 408065]]}));
+        });
+
+        let mut ticker = KiteTicker::builder("key", "token")
+            .root_url(&url)
+            .reconnect(test_policy())
+            .connect()
+            .await
+            .unwrap();
+        assert_eq!(ticker.next_event().await, Some(TickerEvent::Connected));
+        ticker.subscribe([InstrumentToken(408065), InstrumentToken(884737)]).unwrap();
+        ticker.set_mode(Mode::Full, [InstrumentToken(408065), InstrumentToken(260105)]).unwrap();
+        ticker.unsubscribe([InstrumentToken(884737), InstrumentToken(1)]).unwrap();
+        // Already subscribed, so neither sent again nor reset to quote mode
+        ticker.subscribe([InstrumentToken(408065)]).unwrap();
+        assert_eq!(
+            ticker.subscriptions(),
+            BTreeMap::from([(InstrumentToken(260105), Mode::Full), (InstrumentToken(408065), Mode::Full)])
+        );
+
+        let Some(TickerEvent::Ticks(ticks)) = ticker.stream().next().await else {
+            panic!("expected ticks");
+        };
+        assert_eq!(ticks.len(), 2);
+        assert_eq!(ticks[0].instrument_token, InstrumentToken(408065));
+        assert_eq!(ticks[0].last_price, 1500.25);
+        assert_eq!(ticks[1].last_price, 910.0);
+        assert_eq!(ticker.next_event().await, Some(TickerEvent::Error(TickerError::Protocol("Invalid token".to_string()))));
+        let Some(TickerEvent::OrderUpdate(order)) = ticker.next_event().await else {
+            panic!("expected an order update");
+        };
+        assert_eq!(order.order_id, "16032300017157");
+        assert_eq!(order.status, "COMPLETE");
+        // The malformed order update is skipped and reported
+        assert!(matches!(
+            ticker.next_event().await,
+            Some(TickerEvent::Error(TickerError::Parse { instrument_token: None, .. }))
+        ));
+        // The connection was reset without a close frame
+        assert!(matches!(ticker.next_event().await, Some(TickerEvent::Error(TickerError::Connection(_)))));
+        assert!(matches!(ticker.next_event().await, Some(TickerEvent::Reconnecting { attempt: 1, .. })));
+        assert_eq!(ticker.next_event().await, Some(TickerEvent::Connected));
+        server.await.unwrap();
+        let stats = ticker.stats();
+        assert_eq!((stats.messages, stats.ticks), (4, 2));
+        assert_eq!((stats.reconnects, stats.parse_errors, stats.dropped_ticks), (1, 1, 0));
+
+        let handle = ticker.handle();
+        handle.close();
+        let events: Vec<TickerEvent> = ticker.into_stream().collect().await;
+        assert_eq!(events.last(), Some(&TickerEvent::Closed));
+        assert!(handle.subscribe([InstrumentToken(1)]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ticker_mock_transport() {
+        let connector = Arc::new(MockConnector::default());
+        let mut first = connector.accept();
+        let mut second = connector.accept();
+        let mut ticker = KiteTicker::builder("key", "token")
+            .connector(connector)
+            .reconnect(test_policy())
+            .set_mode(Mode::Ltp, [InstrumentToken(408065)])
+            .connect()
+            .await
+            .unwrap();
+        let sent = |text: String| serde_json::from_str::<JsonValue>(&text).unwrap();
+        assert_eq!(sent(first.sent.recv().await.unwrap()), json!({"a": "subscribe", "v": [408065]}));
+        assert_eq!(sent(first.sent.recv().await.unwrap()), json!({"a": "mode", "v": ["ltp", [408065]]}));
+
+        first.incoming.send(Ok(WsMessage::Binary(vec![0].into()))).unwrap();
+        first
+            .incoming
+            .send(Ok(WsMessage::Binary(ltp_frame(&[(408065, 150_025)]).into())))
+            .unwrap();
+        assert_eq!(ticker.next_event().await, Some(TickerEvent::Connected));
+        let Some(TickerEvent::Ticks(ticks)) = ticker.next_event().await else {
+            panic!("expected ticks");
+        };
+        assert_eq!(ticks[0].last_price, 1500.25);
+
+        // Closing the first connection moves on to the second
+        drop(first);
+        assert!(matches!(ticker.next_event().await, Some(TickerEvent::Reconnecting { attempt: 1, .. })));
+        assert_eq!(ticker.next_event().await, Some(TickerEvent::Connected));
+        assert_eq!(sent(second.sent.recv().await.unwrap()), json!({"a": "subscribe", "v": [408065]}));
+        assert_eq!(sent(second.sent.recv().await.unwrap()), json!({"a": "mode", "v": ["ltp", [408065]]}));
+
+        // No connection is left, so every attempt fails and is reported
+        drop(second);
+        let events: Vec<TickerEvent> = ticker.into_stream().collect().await;
+        assert_eq!(events.len(), 7);
+        let refused = TickerEvent::Error(TickerError::Connection("connection refused".to_string()));
+        assert_eq!(events.iter().filter(|&event| *event == refused).count(), 3);
+        assert_eq!(events.last(), Some(&TickerEvent::Closed));
+    }
+
+    #[tokio::test]
+    async fn test_ticker_shared_subscriptions() {
+        let connector = Arc::new(MockConnector::default());
+        let mut server = connector.accept();
+        let ticker = KiteTicker::builder("key", "token")
+            .connector(connector)
+            .connect()
+            .await
+            .unwrap();
+        let sent = |text: String| serde_json::from_str::<JsonValue>(&text).unwrap();
+        let (strategy, cache) = (ticker.handle(), ticker.handle());
+
+        strategy.subscribe([InstrumentToken(408065), InstrumentToken(884737)]).unwrap();
+        cache.set_mode(Mode::Ltp, [InstrumentToken(408065)]).unwrap();
+        // Still needed by the cache, which keeps it in its own mode
+        strategy.unsubscribe([InstrumentToken(408065)]).unwrap();
+        cache.unsubscribe_all().unwrap();
+        assert_eq!(sent(server.sent.recv().await.unwrap()), json!({"a": "subscribe", "v": [408065, 884737]}));
+        assert_eq!(sent(server.sent.recv().await.unwrap()), json!({"a": "mode", "v": ["ltp", [408065]]}));
+        assert_eq!(sent(server.sent.recv().await.unwrap()), json!({"a": "unsubscribe", "v": [408065]}));
+        assert_eq!(ticker.subscriptions(), BTreeMap::from([(InstrumentToken(884737), Mode::Quote)]));
+    }
+
+    #[tokio::test]
+    async fn test_ticker_rejected_token() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let body = r#"{"status":"error","message":"Invalid access token","error_type":"TokenException"}"#;
+            let response = format!(
+                "HTTP/1.1 403 Forbidden\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let mut request = [0u8; 4096];
+            let _ = tokio::io::AsyncReadExt::read(&mut stream, &mut request).await;
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let err = KiteTicker::builder("key", "token")
+            .root_url(&url)
+            .connect()
+            .await
+            .unwrap_err();
+        assert!(err.is_token_error(), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_ticker_initial_subscriptions() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut server = tokio_tungstenite::accept_async(stream).await.unwrap();
+            assert_eq!(next_text(&mut server).await, json!({"a": "subscribe", "v": [408065, 884737]}));
+            assert_eq!(next_text(&mut server).await, json!({"a": "mode", "v": ["ltp", [408065]]}));
+        });
+
+        let ticker = KiteTicker::builder("key", "token")
+            .root_url(&url)
+            .subscribe([InstrumentToken(884737), InstrumentToken(408065)])
+            .set_mode(Mode::Ltp, [InstrumentToken(408065)])
+            .connect()
+            .await
+            .unwrap();
+        assert_eq!(
+            ticker.subscriptions(),
+            BTreeMap::from([(InstrumentToken(408065), Mode::Ltp), (InstrumentToken(884737), Mode::Quote)])
+        );
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ticker_connect_timeout() {
+        // Accepts the connection but never completes the handshake
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let err = KiteTicker::builder("key", "token")
+            .root_url(&url)
+            .connect_timeout(Duration::from_millis(100))
+            .connect()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{}", err);
+        drop(listener);
+    }
+
+    #[tokio::test]
+    async fn test_ticker_stale_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut server = tokio_tungstenite::accept_async(stream).await.unwrap();
+            // A heartbeat, then silence without closing the connection
+            server.send(Message::binary(vec![0])).await.unwrap();
+            let (stream, _) = listener.accept().await.unwrap();
+            let _server = tokio_tungstenite::accept_async(stream).await.unwrap();
+            std::future::pending::<()>().await;
+        });
+
+        let mut ticker = KiteTicker::builder("key", "token")
+            .root_url(&url)
+            .reconnect(test_policy())
+            .stale_timeout(Duration::from_millis(200))
+            .connect()
+            .await
+            .unwrap();
+        assert_eq!(ticker.next_event().await, Some(TickerEvent::Connected));
+        assert_eq!(
+            ticker.next_event().await,
+            Some(TickerEvent::Error(TickerError::Connection("no ticker data for 200ms".to_string())))
+        );
+        assert!(matches!(ticker.next_event().await, Some(TickerEvent::Reconnecting { attempt: 1, .. })));
+        let heartbeat = ticker.last_message_at().unwrap();
+        assert!(heartbeat.elapsed() >= Duration::from_millis(200));
+        assert_eq!(ticker.next_event().await, Some(TickerEvent::Connected));
+    }
+}
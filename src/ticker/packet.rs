@@ -0,0 +1,536 @@
+//! Decoding of the binary market data frames sent by the ticker
+
+use super::TickerError;
+use crate::models::{ist, InstrumentToken, Timestamp};
+use serde::{Deserialize, Serialize};
+
+/// Length of an LTP mode packet
+const LTP_LEN: usize = 8;
+/// Length of a quote mode packet of an index
+const INDEX_QUOTE_LEN: usize = 28;
+/// Length of a full mode packet of an index
+const INDEX_FULL_LEN: usize = 32;
+/// Length of a quote mode packet of a tradable instrument
+const QUOTE_LEN: usize = 44;
+/// Length of a full mode packet of a tradable instrument
+const FULL_LEN: usize = 184;
+/// Length of a 20 level depth packet, the full mode fields followed by the
+/// extended depth
+const DEPTH_20_LEN: usize = DEPTH_OFFSET + 2 * DEPTH_20_LEVELS * DEPTH_ENTRY_LEN;
+
+/// Offset of the market depth in a full mode packet
+const DEPTH_OFFSET: usize = 64;
+/// Length of one depth entry
+const DEPTH_ENTRY_LEN: usize = 12;
+/// Number of price levels on each side of the market depth
+pub const DEPTH_LEVELS: usize = 5;
+/// Number of price levels on each side of the extended market depth
+pub const DEPTH_20_LEVELS: usize = 20;
+
+/// Segment of index instruments, the lowest byte of their instrument token
+const INDICES_SEGMENT: u32 = 9;
+
+/// Amount of market data streamed for an instrument
+///
+/// Modes are ordered from the least to the most detailed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Last traded price only
+    Ltp,
+    /// Prices, volume and OHLC, without market depth
+    #[default]
+    Quote,
+    /// Everything, including open interest, timestamps and market depth
+    Full,
+    /// Everything in [`Mode::Full`] with 20 levels of market depth, for
+    /// accounts entitled to the extended depth feed
+    Depth20,
+}
+
+impl Mode {
+    /// Returns the name of the mode in the websocket protocol
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mode::Ltp => "ltp",
+            Mode::Quote => "quote",
+            Mode::Full => "full",
+            Mode::Depth20 => "depth20",
+        }
+    }
+}
+
+/// Open, high, low and close prices of the day
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Ohlc {
+    /// Opening price
+    pub open: f64,
+    /// Highest price
+    pub high: f64,
+    /// Lowest price
+    pub low: f64,
+    /// Closing price
+    pub close: f64,
+}
+
+/// One price level of the market depth
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DepthLevel {
+    /// Price of the level
+    pub price: f64,
+    /// Total quantity of the orders at this price
+    pub quantity: u32,
+    /// Number of orders at this price
+    pub orders: u16,
+}
+
+/// Best five bid and ask levels of an instrument
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Depth {
+    /// Bids, best (highest) price first
+    pub buy: [DepthLevel; DEPTH_LEVELS],
+    /// Asks, best (lowest) price first
+    pub sell: [DepthLevel; DEPTH_LEVELS],
+}
+
+/// Best twenty bid and ask levels of an instrument, streamed in [`Mode::Depth20`]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Depth20 {
+    /// Bids, best (highest) price first
+    pub buy: [DepthLevel; DEPTH_20_LEVELS],
+    /// Asks, best (lowest) price first
+    pub sell: [DepthLevel; DEPTH_20_LEVELS],
+}
+
+/// Market data of one instrument, decoded from a binary packet
+///
+/// Fields not carried by the packet's [`Mode`] are zero or `None`. Index
+/// packets carry prices only.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tick {
+    /// Mode of the packet
+    pub mode: Mode,
+    /// Instrument token the packet belongs to
+    pub instrument_token: InstrumentToken,
+    /// `false` for indices, which cannot be traded
+    pub tradable: bool,
+    /// Last traded price
+    pub last_price: f64,
+    /// Quantity of the last trade
+    pub last_traded_quantity: u32,
+    /// Volume weighted average price of the day
+    pub average_traded_price: f64,
+    /// Quantity traded during the day
+    pub volume_traded: u32,
+    /// Quantity of all pending buy orders
+    pub total_buy_quantity: u32,
+    /// Quantity of all pending sell orders
+    pub total_sell_quantity: u32,
+    /// Prices of the day; the close is that of the previous session
+    pub ohlc: Ohlc,
+    /// Change of the last price from the close, in percent
+    pub change: f64,
+    /// Time of the last trade
+    pub last_trade_time: Option<Timestamp>,
+    /// Open interest
+    pub oi: u32,
+    /// Highest open interest of the day
+    pub oi_day_high: u32,
+    /// Lowest open interest of the day
+    pub oi_day_low: u32,
+    /// Time the exchange sent the data
+    pub exchange_timestamp: Option<Timestamp>,
+    /// Market depth, carried by full mode packets of tradable instruments
+    pub depth: Option<Depth>,
+    /// Extended market depth, carried by [`Mode::Depth20`] packets; the best
+    /// five levels are in [`depth`](Self::depth) as well
+    pub depth_20: Option<Box<Depth20>>,
+}
+
+/// Decodes a binary frame into the ticks of its packets, along with an error
+/// for every packet that was skipped and for a truncated frame
+pub(crate) fn parse_frame(frame: &[u8]) -> (Vec<Tick>, Vec<TickerError>) {
+    let mut packets = decode_frame(frame);
+    let mut ticks = Vec::with_capacity(packets.remaining as usize);
+    let mut errors = Vec::new();
+    while let Some(packet) = packets.next_packet() {
+        match packet {
+            Ok(tick) => ticks.push(tick),
+            Err(error) => errors.push(error),
+        }
+    }
+    (ticks, errors)
+}
+
+/// Returns the number of packets a frame announces, zero for heartbeats
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn packet_count(frame: &[u8]) -> usize {
+    read_u16(frame, 0).unwrap_or(0).into()
+}
+
+/// Lazily decodes the packets of a binary frame, without allocating
+///
+/// A frame starts with the number of packets, followed by every packet prefixed
+/// with its length. Truncated frames yield the packets before the cut, and
+/// packets of unknown layout are skipped. Useful to process the raw frames of
+/// a recording, or to decode into a reused buffer:
+///
+/// ```rust
+/// use kiteconnect::ticker::decode_frame;
+///
+/// let frame = [0, 1, 0, 8, 0, 6, 58, 1, 0, 2, 73, 241];
+/// let mut ticks = Vec::new();
+/// ticks.extend(decode_frame(&frame));
+/// assert_eq!(ticks[0].last_price, 1500.01);
+/// ```
+pub fn decode_frame(frame: &[u8]) -> Packets<'_> {
+    Packets {
+        remaining: read_u16(frame, 0).unwrap_or(0),
+        frame,
+        offset: 2,
+    }
+}
+
+/// Iterator over the ticks of a binary frame, returned by [`decode_frame`]
+#[derive(Clone, Debug)]
+pub struct Packets<'a> {
+    frame: &'a [u8],
+    offset: usize,
+    remaining: u16,
+}
+
+impl Packets<'_> {
+    /// Decodes the next packet, or describes why it was skipped
+    fn next_packet(&mut self) -> Option<Result<Tick, TickerError>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let packet = read_u16(self.frame, self.offset).and_then(|len| {
+            let start = self.offset + 2;
+            self.offset = start + len as usize;
+            self.frame.get(start..self.offset)
+        });
+        let Some(packet) = packet else {
+            let missing = self.remaining + 1;
+            self.remaining = 0;
+            return Some(Err(TickerError::Parse {
+                message: format!("frame is truncated, missing {} packets", missing),
+                frame_len: self.frame.len(),
+                instrument_token: None,
+            }));
+        };
+        Some(parse_packet(packet).ok_or_else(|| TickerError::Parse {
+            message: format!("packet of {} bytes has an unknown layout", packet.len()),
+            frame_len: self.frame.len(),
+            instrument_token: read_u32(packet, 0).map(InstrumentToken),
+        }))
+    }
+}
+
+impl Iterator for Packets<'_> {
+    type Item = Tick;
+
+    fn next(&mut self) -> Option<Tick> {
+        while let Some(packet) = self.next_packet() {
+            if let Ok(tick) = packet {
+                return Some(tick);
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining as usize))
+    }
+}
+
+/// Decodes a single packet, laid out according to its length and segment
+fn parse_packet(packet: &[u8]) -> Option<Tick> {
+    let instrument_token = read_u32(packet, 0)?;
+    let divisor = price_divisor(instrument_token);
+    let price = |offset| read_i32(packet, offset).map(|value| value as f64 / divisor);
+    let tradable = instrument_token & 0xff != INDICES_SEGMENT;
+
+    let mut tick = Tick {
+        instrument_token: InstrumentToken(instrument_token),
+        tradable,
+        last_price: price(4)?,
+        ..Tick::default()
+    };
+
+    if packet.len() == LTP_LEN {
+        tick.mode = Mode::Ltp;
+    } else if !tradable && (packet.len() == INDEX_QUOTE_LEN || packet.len() == INDEX_FULL_LEN) {
+        tick.mode = if packet.len() == INDEX_FULL_LEN { Mode::Full } else { Mode::Quote };
+        tick.ohlc = Ohlc {
+            high: price(8)?,
+            low: price(12)?,
+            open: price(16)?,
+            close: price(20)?,
+        };
+        if packet.len() == INDEX_FULL_LEN {
+            tick.exchange_timestamp = timestamp(read_u32(packet, 28)?);
+        }
+    } else if packet.len() >= QUOTE_LEN {
+        tick.mode = match packet.len() {
+            DEPTH_20_LEN => Mode::Depth20,
+            len if len >= FULL_LEN => Mode::Full,
+            _ => Mode::Quote,
+        };
+        tick.last_traded_quantity = read_u32(packet, 8)?;
+        tick.average_traded_price = price(12)?;
+        tick.volume_traded = read_u32(packet, 16)?;
+        tick.total_buy_quantity = read_u32(packet, 20)?;
+        tick.total_sell_quantity = read_u32(packet, 24)?;
+        tick.ohlc = Ohlc {
+            open: price(28)?,
+            high: price(32)?,
+            low: price(36)?,
+            close: price(40)?,
+        };
+        if packet.len() >= FULL_LEN {
+            tick.last_trade_time = timestamp(read_u32(packet, 44)?);
+            tick.oi = read_u32(packet, 48)?;
+            tick.oi_day_high = read_u32(packet, 52)?;
+            tick.oi_day_low = read_u32(packet, 56)?;
+            tick.exchange_timestamp = timestamp(read_u32(packet, 60)?);
+            if tick.mode == Mode::Depth20 {
+                let depth = parse_depth_20(packet, divisor)?;
+                tick.depth = Some(Depth {
+                    buy: *depth.buy.first_chunk()?,
+                    sell: *depth.sell.first_chunk()?,
+                });
+                tick.depth_20 = Some(Box::new(depth));
+            } else {
+                tick.depth = Some(parse_depth(packet, divisor)?);
+            }
+        }
+    } else {
+        log::debug!("Skipping ticker packet of unknown length {}", packet.len());
+        return None;
+    }
+
+    if tick.ohlc.close != 0.0 {
+        tick.change = (tick.last_price - tick.ohlc.close) * 100.0 / tick.ohlc.close;
+    }
+    Some(tick)
+}
+
+/// Decodes the five buy levels followed by the five sell levels of a full packet
+fn parse_depth(packet: &[u8], divisor: f64) -> Option<Depth> {
+    let (buy, sell) = parse_levels(packet, divisor)?;
+    Some(Depth { buy, sell })
+}
+
+/// Decodes the twenty buy levels followed by the twenty sell levels of a
+/// 20 level depth packet
+fn parse_depth_20(packet: &[u8], divisor: f64) -> Option<Depth20> {
+    let (buy, sell) = parse_levels(packet, divisor)?;
+    Some(Depth20 { buy, sell })
+}
+
+/// Decodes `N` buy levels followed by `N` sell levels
+fn parse_levels<const N: usize>(packet: &[u8], divisor: f64) -> Option<([DepthLevel; N], [DepthLevel; N])> {
+    let level = |index: usize| {
+        let offset = DEPTH_OFFSET + index * DEPTH_ENTRY_LEN;
+        Some(DepthLevel {
+            quantity: read_u32(packet, offset)?,
+            price: read_i32(packet, offset + 4)? as f64 / divisor,
+            orders: read_u16(packet, offset + 8)?,
+        })
+    };
+    let mut buy = [DepthLevel::default(); N];
+    let mut sell = [DepthLevel::default(); N];
+    for index in 0..N {
+        buy[index] = level(index)?;
+        sell[index] = level(N + index)?;
+    }
+    Some((buy, sell))
+}
+
+/// Returns the divisor turning the integer prices of a segment into rupees
+///
+/// Prices are sent in paise, except for currency derivatives which carry more
+/// decimal places.
+fn price_divisor(instrument_token: u32) -> f64 {
+    match instrument_token & 0xff {
+        // CDS
+        3 => 10_000_000.0,
+        // BCD
+        6 => 10_000.0,
+        _ => 100.0,
+    }
+}
+
+/// Converts Unix seconds to an IST timestamp; zero means not set
+fn timestamp(secs: u32) -> Option<Timestamp> {
+    if secs == 0 {
+        return None;
+    }
+    chrono::DateTime::from_timestamp(secs.into(), 0).map(|ts| ts.with_timezone(&ist::offset()))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..)?.first_chunk()?;
+    Some(u16::from_be_bytes(*bytes))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..)?.first_chunk()?;
+    Some(u32::from_be_bytes(*bytes))
+}
+
+fn read_i32(data: &[u8], offset: usize) -> Option<i32> {
+    read_u32(data, offset).map(|value| value as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame() -> Vec<u8> {
+        std::fs::read("mocks/ticker_frame.bin").unwrap()
+    }
+
+    #[test]
+    fn test_parse_frame() {
+        let (ticks, _) = parse_frame(&frame());
+        assert_eq!(ticks.len(), 7);
+
+        // LTP packets, in paise and in the finer currency derivative unit
+        assert_eq!(ticks[0].mode, Mode::Ltp);
+        assert_eq!(ticks[0].instrument_token, InstrumentToken(408065));
+        assert!(ticks[0].tradable);
+        assert_eq!(ticks[0].last_price, 1500.25);
+        assert_eq!(ticks[0].volume_traded, 0);
+        assert_eq!(ticks[1].last_price, 83.5);
+
+        // Index quote and full packets
+        let nifty = &ticks[2];
+        assert_eq!((nifty.mode, nifty.instrument_token, nifty.tradable), (Mode::Quote, InstrumentToken(256265), false));
+        assert_eq!(nifty.last_price, 22450.5);
+        assert_eq!(nifty.ohlc, Ohlc { open: 22350.0, high: 22500.0, low: 22300.0, close: 22400.0 });
+        assert!((nifty.change - 0.2254).abs() < 1e-4);
+        assert_eq!(nifty.exchange_timestamp, None);
+        let bank = &ticks[3];
+        assert_eq!(bank.mode, Mode::Full);
+        assert_eq!(ist::format_timestamp(&bank.exchange_timestamp.unwrap()), "2024-03-21 11:16:40");
+
+        // Quote packet
+        let quote = &ticks[4];
+        assert_eq!(quote.mode, Mode::Quote);
+        assert_eq!(quote.instrument_token, InstrumentToken(884737));
+        assert_eq!(quote.last_price, 990.25);
+        assert_eq!(quote.last_traded_quantity, 10);
+        assert_eq!(quote.average_traded_price, 988.7);
+        assert_eq!(quote.volume_traded, 1520345);
+        assert_eq!((quote.total_buy_quantity, quote.total_sell_quantity), (250000, 310000));
+        assert_eq!(quote.ohlc, Ohlc { open: 980.0, high: 995.0, low: 977.5, close: 975.0 });
+        assert_eq!(quote.last_trade_time, None);
+
+        // Full packets of an equity and a future
+        let equity = &ticks[5];
+        assert_eq!(equity.mode, Mode::Full);
+        assert_eq!(equity.last_price, 2900.1);
+        assert_eq!(ist::format_timestamp(&equity.last_trade_time.unwrap()), "2024-03-21 11:18:43");
+        assert_eq!(ist::format_timestamp(&equity.exchange_timestamp.unwrap()), "2024-03-21 11:18:44");
+        assert_eq!(equity.oi, 0);
+        let depth = equity.depth.unwrap();
+        assert_eq!(depth.buy[0], DepthLevel { price: 2900.05, quantity: 100, orders: 3 });
+        assert_eq!(depth.buy[4], DepthLevel { price: 2899.85, quantity: 104, orders: 7 });
+        assert_eq!(depth.sell[0], DepthLevel { price: 2900.15, quantity: 200, orders: 4 });
+        assert_eq!(depth.sell[4], DepthLevel { price: 2900.35, quantity: 204, orders: 8 });
+        assert!(ticks[..5].iter().all(|tick| tick.depth.is_none()));
+        let future = &ticks[6];
+        assert_eq!(future.instrument_token, InstrumentToken(13368834));
+        assert_eq!((future.oi, future.oi_day_high, future.oi_day_low), (12500000, 12750000, 12100000));
+    }
+
+    #[test]
+    fn test_parse_depth_20() {
+        // Full mode fields of 408065 at 1500.25, then levels 0.05 apart
+        let mut packet = vec![0u8; DEPTH_20_LEN];
+        packet[..4].copy_from_slice(&408065u32.to_be_bytes());
+        packet[4..8].copy_from_slice(&150_025i32.to_be_bytes());
+        for index in 0..2 * DEPTH_20_LEVELS {
+            let (side, level) = (index / DEPTH_20_LEVELS, (index % DEPTH_20_LEVELS) as i32);
+            let price = if side == 0 { 150_020 - 5 * level } else { 150_030 + 5 * level };
+            let offset = DEPTH_OFFSET + index * DEPTH_ENTRY_LEN;
+            packet[offset..offset + 4].copy_from_slice(&(100 + index as u32).to_be_bytes());
+            packet[offset + 4..offset + 8].copy_from_slice(&price.to_be_bytes());
+            packet[offset + 8..offset + 10].copy_from_slice(&(1 + level as u16).to_be_bytes());
+        }
+        let mut depth_frame = vec![0, 1];
+        depth_frame.extend_from_slice(&(DEPTH_20_LEN as u16).to_be_bytes());
+        depth_frame.extend_from_slice(&packet);
+
+        let (ticks, errors) = parse_frame(&depth_frame);
+        assert!(errors.is_empty());
+        let tick = &ticks[0];
+        assert_eq!((tick.mode, tick.last_price), (Mode::Depth20, 1500.25));
+        let depth_20 = tick.depth_20.as_deref().unwrap();
+        assert_eq!(depth_20.buy[0], DepthLevel { price: 1500.2, quantity: 100, orders: 1 });
+        assert_eq!(depth_20.buy[19], DepthLevel { price: 1499.25, quantity: 119, orders: 20 });
+        assert_eq!(depth_20.sell[19], DepthLevel { price: 1501.25, quantity: 139, orders: 20 });
+        let depth = tick.depth.unwrap();
+        assert_eq!((depth.buy, depth.sell), (depth_20.buy[..5].try_into().unwrap(), depth_20.sell[..5].try_into().unwrap()));
+        // Packets of the other modes carry no extended depth
+        assert!(parse_frame(&frame()).0.iter().all(|tick| tick.depth_20.is_none()));
+    }
+
+    #[test]
+    fn test_parse_malformed_frame() {
+        let frame = frame();
+        assert!(parse_frame(&[]).0.is_empty());
+        // Heartbeats are a single byte
+        assert!(parse_frame(&[0]).0.is_empty());
+        // A frame cut inside the third packet yields the first two
+        assert_eq!(parse_frame(&frame[..30]).0.len(), 2);
+        // Packets of unknown length are skipped
+        let odd = [0, 2, 0, 4, 0, 0, 0, 1, 0, 8, 0, 6, 58, 1, 0, 0, 39, 16];
+        let (ticks, _) = parse_frame(&odd);
+        assert_eq!(ticks.len(), 1);
+        assert_eq!(ticks[0].instrument_token, InstrumentToken(408065));
+        assert_eq!(ticks[0].last_price, 100.0);
+    }
+
+    #[test]
+    fn test_parse_frame_errors() {
+        let frame = frame();
+        let (ticks, errors) = parse_frame(&frame);
+        assert_eq!((ticks.len(), errors.len()), (7, 0));
+        assert_eq!(parse_frame(&[0]), (Vec::new(), Vec::new()));
+
+        let (ticks, errors) = parse_frame(&frame[..30]);
+        assert_eq!(ticks.len(), 2);
+        assert_eq!(
+            errors,
+            [TickerError::Parse {
+                message: "frame is truncated, missing 5 packets".to_string(),
+                frame_len: 30,
+                instrument_token: None,
+            }]
+        );
+
+        let odd = [0, 2, 0, 6, 0, 6, 58, 1, 0, 0, 0, 8, 0, 6, 58, 1, 0, 0, 39, 16];
+        let (ticks, errors) = parse_frame(&odd);
+        assert_eq!(ticks.len(), 1);
+        assert!(matches!(
+            &errors[..],
+            [TickerError::Parse { frame_len: 20, instrument_token: Some(InstrumentToken(408065)), .. }]
+        ));
+    }
+
+    #[test]
+    fn test_decode_frame() {
+        let frame = frame();
+        let mut packets = decode_frame(&frame);
+        assert_eq!(packets.size_hint(), (0, Some(7)));
+        assert_eq!(packets.next().unwrap().instrument_token, InstrumentToken(408065));
+        assert_eq!(packets.size_hint(), (0, Some(6)));
+        assert_eq!(packets.collect::<Vec<_>>(), parse_frame(&frame).0[1..]);
+        assert_eq!(decode_frame(&frame[..30]).count(), 2);
+        assert_eq!(decode_frame(&[0]).next(), None);
+    }
+}
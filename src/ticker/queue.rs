@@ -0,0 +1,181 @@
+//! Event queue between the connection task and the consumer of a ticker
+
+use super::TickerEvent;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+/// What happens to ticks arriving while the event queue is full
+///
+/// Only [`TickerEvent::Ticks`] count against the capacity and are ever dropped;
+/// connection events, errors and order updates are always delivered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the oldest queued ticks to make room, keeping the freshest prices
+    #[default]
+    DropOldest,
+    /// Drop the arriving ticks
+    DropNewest,
+    /// Stop reading from the websocket until the consumer catches up
+    ///
+    /// Kite may disconnect clients that fall too far behind.
+    Block,
+}
+
+/// Queue of ticker events, unbounded or holding up to a number of tick events
+#[derive(Debug)]
+pub(crate) struct EventQueue {
+    state: Mutex<State>,
+    capacity: Option<usize>,
+    policy: OverflowPolicy,
+    readable: Notify,
+    writable: Notify,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    events: VecDeque<TickerEvent>,
+    /// Number of [`TickerEvent::Ticks`] in `events`
+    tick_events: usize,
+    closed: bool,
+}
+
+impl EventQueue {
+    pub(crate) fn new(capacity: Option<usize>, policy: OverflowPolicy) -> Self {
+        EventQueue {
+            state: Mutex::new(State::default()),
+            capacity: capacity.map(|capacity| capacity.max(1)),
+            policy,
+            readable: Notify::new(),
+            writable: Notify::new(),
+        }
+    }
+
+    /// Queues an event, applying the overflow policy to ticks
+    ///
+    /// Returns the number of ticks dropped to respect the capacity.
+    pub(crate) async fn push(&self, event: TickerEvent) -> usize {
+        let is_ticks = matches!(event, TickerEvent::Ticks(_));
+        let mut event = Some(event);
+        loop {
+            let dropped = {
+                let mut state = self.state.lock().unwrap();
+                let full = is_ticks && self.capacity.is_some_and(|capacity| state.tick_events >= capacity);
+                match (full, self.policy) {
+                    (true, OverflowPolicy::Block) => None,
+                    (true, OverflowPolicy::DropNewest) => return event.as_ref().map_or(0, tick_count),
+                    (full, _) => {
+                        let mut dropped = 0;
+                        if full {
+                            let oldest = state
+                                .events
+                                .iter()
+                                .position(|queued| matches!(queued, TickerEvent::Ticks(_)))
+                                .expect("a full queue holds tick events");
+                            let removed = state.events.remove(oldest).expect("index is in bounds");
+                            state.tick_events -= 1;
+                            dropped = tick_count(&removed);
+                        }
+                        state.tick_events += usize::from(is_ticks);
+                        state.events.extend(event.take());
+                        Some(dropped)
+                    }
+                }
+            };
+            match dropped {
+                Some(dropped) => {
+                    self.readable.notify_one();
+                    return dropped;
+                }
+                None => self.writable.notified().await,
+            }
+        }
+    }
+
+    /// Waits for the next event, returning `None` once the queue is closed and drained
+    pub(crate) async fn pop(&self) -> Option<TickerEvent> {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                if let Some(event) = state.events.pop_front() {
+                    if matches!(event, TickerEvent::Ticks(_)) {
+                        state.tick_events -= 1;
+                        drop(state);
+                        self.writable.notify_one();
+                    }
+                    return Some(event);
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            self.readable.notified().await;
+        }
+    }
+
+    /// Marks the end of the events; queued events are still delivered
+    pub(crate) fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.readable.notify_one();
+    }
+}
+
+fn tick_count(event: &TickerEvent) -> usize {
+    match event {
+        TickerEvent::Ticks(ticks) => ticks.len(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::InstrumentToken;
+    use crate::ticker::Tick;
+    use std::time::Duration;
+
+    fn ticks(token: u32) -> TickerEvent {
+        TickerEvent::Ticks(vec![Tick {
+            instrument_token: InstrumentToken(token),
+            ..Tick::default()
+        }])
+    }
+
+    #[tokio::test]
+    async fn test_overflow_policies() {
+        let queue = EventQueue::new(Some(2), OverflowPolicy::DropOldest);
+        assert_eq!(queue.push(ticks(1)).await, 0);
+        assert_eq!(queue.push(TickerEvent::Connected).await, 0);
+        assert_eq!(queue.push(ticks(2)).await, 0);
+        assert_eq!(queue.push(ticks(3)).await, 1);
+        queue.close();
+        assert_eq!(queue.pop().await, Some(TickerEvent::Connected));
+        assert_eq!(queue.pop().await, Some(ticks(2)));
+        assert_eq!(queue.pop().await, Some(ticks(3)));
+        assert_eq!(queue.pop().await, None);
+
+        let queue = EventQueue::new(Some(1), OverflowPolicy::DropNewest);
+        assert_eq!(queue.push(ticks(1)).await, 0);
+        assert_eq!(queue.push(ticks(2)).await, 1);
+        assert_eq!(queue.push(TickerEvent::Closed).await, 0);
+        assert_eq!(queue.pop().await, Some(ticks(1)));
+        assert_eq!(queue.pop().await, Some(TickerEvent::Closed));
+
+        let queue = std::sync::Arc::new(EventQueue::new(Some(1), OverflowPolicy::Block));
+        queue.push(ticks(1)).await;
+        let producer = {
+            let queue = queue.clone();
+            tokio::spawn(async move { queue.push(ticks(2)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!producer.is_finished());
+        assert_eq!(queue.pop().await, Some(ticks(1)));
+        assert_eq!(producer.await.unwrap(), 0);
+        assert_eq!(queue.pop().await, Some(ticks(2)));
+
+        let queue = EventQueue::new(None, OverflowPolicy::DropNewest);
+        for token in 0..100 {
+            assert_eq!(queue.push(ticks(token)).await, 0);
+        }
+    }
+}
@@ -0,0 +1,285 @@
+//! Recording of raw ticker frames to disk, and reading them back
+
+use super::{decode_frame, Packets};
+use crate::error::{KiteError, Result};
+use crate::models::{ist, Timestamp};
+use chrono::{DateTime, Timelike};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use tokio_tungstenite::tungstenite::Bytes;
+
+/// First bytes of every recording file
+const MAGIC: &[u8; 8] = b"KITETCK1";
+
+/// When a recording moves on to a new file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rotation {
+    /// Keep writing to the first file
+    Never,
+    /// Start a file every hour, IST
+    Hourly,
+    /// Start a file every day, IST
+    #[default]
+    Daily,
+    /// Start a file once the current one holds this many bytes
+    MaxBytes(u64),
+}
+
+/// Writes the binary frames received by a ticker to timestamped files
+///
+/// Files are named `<prefix>-<YYYYMMDD-HHMMSS>.bin` after the IST time they
+/// were started, and hold every frame with the time it was received.
+/// Heartbeats are not recorded. Writing happens on a separate thread, so a
+/// slow disk does not hold up the ticker. Read recordings back with
+/// [`read_recording`].
+///
+/// ```rust,no_run
+/// use kiteconnect::ticker::{KiteTicker, Rotation, TickRecorder};
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let ticker = KiteTicker::builder("api_key", "access_token")
+///     .record(TickRecorder::new("recordings").rotation(Rotation::Hourly))
+///     .connect()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct TickRecorder {
+    dir: PathBuf,
+    prefix: String,
+    rotation: Rotation,
+}
+
+impl TickRecorder {
+    /// Records into `dir`, which is created if needed, starting a file every day
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        TickRecorder {
+            dir: dir.into(),
+            prefix: "ticks".to_string(),
+            rotation: Rotation::default(),
+        }
+    }
+
+    /// Sets the start of the file names, `ticks` by default
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Sets when a new file is started
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Starts the writer thread, which stops once the returned sender is dropped
+    pub(crate) fn start(self) -> Result<FrameSender> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| {
+            KiteError::Other(format!("failed to create recording directory {}: {}", self.dir.display(), e))
+        })?;
+        let (tx, rx) = mpsc::channel::<(Timestamp, Bytes)>();
+        std::thread::Builder::new()
+            .name("kite-tick-recorder".to_string())
+            .spawn(move || {
+                let mut writer = Writer { recorder: self, file: None };
+                for (received_at, frame) in rx {
+                    if let Err(e) = writer.write(received_at, &frame) {
+                        log::error!("Failed to record ticker frame: {}", e);
+                        writer.file = None;
+                    }
+                }
+                if let Some(file) = writer.file.as_mut() {
+                    let _ = file.out.flush();
+                }
+            })
+            .map_err(|e| KiteError::Other(format!("failed to start the tick recorder: {}", e)))?;
+        Ok(FrameSender(tx))
+    }
+}
+
+/// Hands frames to the writer thread of a [`TickRecorder`]
+#[derive(Debug)]
+pub(crate) struct FrameSender(mpsc::Sender<(Timestamp, Bytes)>);
+
+impl FrameSender {
+    pub(crate) fn record(&self, frame: &Bytes) {
+        let _ = self.0.send((ist::now(), frame.clone()));
+    }
+}
+
+struct Writer {
+    recorder: TickRecorder,
+    file: Option<OpenFile>,
+}
+
+struct OpenFile {
+    out: BufWriter<File>,
+    started_at: Timestamp,
+    bytes: u64,
+}
+
+impl Writer {
+    fn write(&mut self, received_at: Timestamp, frame: &[u8]) -> std::io::Result<()> {
+        let rotate = self.file.as_ref().is_some_and(|file| match self.recorder.rotation {
+            Rotation::Never => false,
+            Rotation::Hourly => {
+                (file.started_at.date_naive(), file.started_at.hour()) != (received_at.date_naive(), received_at.hour())
+            }
+            Rotation::Daily => file.started_at.date_naive() != received_at.date_naive(),
+            Rotation::MaxBytes(max) => file.bytes >= max,
+        });
+        if rotate {
+            if let Some(mut file) = self.file.take() {
+                file.out.flush()?;
+            }
+        }
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => self.file.insert(self.open(received_at)?),
+        };
+
+        file.out.write_all(&received_at.timestamp_millis().to_be_bytes())?;
+        file.out.write_all(&(frame.len() as u32).to_be_bytes())?;
+        file.out.write_all(frame)?;
+        file.bytes += 12 + frame.len() as u64;
+        Ok(())
+    }
+
+    fn open(&self, started_at: Timestamp) -> std::io::Result<OpenFile> {
+        let name = format!("{}-{}", self.recorder.prefix, started_at.format("%Y%m%d-%H%M%S"));
+        let mut path = self.recorder.dir.join(format!("{}.bin", name));
+        // Files started within the same second get a counter
+        let mut counter = 1;
+        while path.exists() {
+            path = self.recorder.dir.join(format!("{}-{}.bin", name, counter));
+            counter += 1;
+        }
+        log::debug!("Recording ticker frames to {}", path.display());
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        Ok(OpenFile {
+            out,
+            started_at,
+            bytes: MAGIC.len() as u64,
+        })
+    }
+}
+
+/// A frame read from a recording
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedFrame {
+    /// When the ticker received the frame
+    pub received_at: Timestamp,
+    /// The binary frame as sent by Kite
+    pub frame: Vec<u8>,
+}
+
+impl RecordedFrame {
+    /// Decodes the ticks of the frame
+    pub fn ticks(&self) -> Packets<'_> {
+        decode_frame(&self.frame)
+    }
+}
+
+/// Opens a file written by a [`TickRecorder`], returning its frames in order
+///
+/// ```rust,no_run
+/// use kiteconnect::ticker::read_recording;
+///
+/// # fn main() -> kiteconnect::error::Result<()> {
+/// for frame in read_recording("recordings/ticks-20240321-091500.bin")? {
+///     let frame = frame?;
+///     for tick in frame.ticks() {
+///         println!("{} {}: {}", frame.received_at, tick.instrument_token, tick.last_price);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn read_recording(path: impl AsRef<Path>) -> Result<Recording> {
+    let path = path.as_ref();
+    let error = |e: std::io::Error| KiteError::Other(format!("failed to read recording {}: {}", path.display(), e));
+    let mut input = BufReader::new(File::open(path).map_err(error)?);
+    let mut magic = [0; MAGIC.len()];
+    input.read_exact(&mut magic).map_err(error)?;
+    if &magic != MAGIC {
+        return Err(KiteError::Other(format!("{} is not a tick recording", path.display())));
+    }
+    Ok(Recording { input })
+}
+
+/// Iterator over the frames of a recording, returned by [`read_recording`]
+#[derive(Debug)]
+pub struct Recording {
+    input: BufReader<File>,
+}
+
+impl Iterator for Recording {
+    type Item = Result<RecordedFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut header = [0; 12];
+        match self.input.read_exact(&mut header) {
+            Ok(()) => {}
+            // A recording ends after a complete frame
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return None,
+            Err(e) => return Some(Err(KiteError::Other(format!("failed to read recording: {}", e)))),
+        }
+        let millis = i64::from_be_bytes(header[..8].try_into().unwrap());
+        let len = u32::from_be_bytes(header[8..].try_into().unwrap());
+        let mut frame = vec![0; len as usize];
+        if let Err(e) = self.input.read_exact(&mut frame) {
+            return Some(Err(KiteError::Other(format!("truncated recording: {}", e))));
+        }
+        let received_at = DateTime::from_timestamp_millis(millis)?.with_timezone(&ist::offset());
+        Some(Ok(RecordedFrame { received_at, frame }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_read() {
+        let dir = std::env::temp_dir().join(format!("kite-recorder-{}", std::process::id()));
+        let mock = std::fs::read("mocks/ticker_frame.bin").unwrap();
+        let recorder = TickRecorder::new(&dir).prefix("test").rotation(Rotation::MaxBytes(530));
+        let sender = recorder.start().unwrap();
+        sender.record(&Bytes::from(mock.clone()));
+        sender.record(&Bytes::from_static(&[0, 1, 0, 8, 0, 6, 58, 1, 0, 2, 73, 241]));
+        // The first file is full, so the third frame starts another
+        sender.record(&Bytes::from(mock.clone()));
+        drop(sender);
+
+        let mut files = Vec::new();
+        for _ in 0..100 {
+            files = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+            if files.len() == 2 && files.iter().all(|file| read_recording(file).is_ok_and(|frames| frames.count() > 0)) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(files.len(), 2);
+        assert!(files[0].file_name().unwrap().to_str().unwrap().starts_with("test-"));
+        let mut recordings: Vec<Vec<RecordedFrame>> = files
+            .iter()
+            .map(|file| read_recording(file).unwrap().map(Result::unwrap).collect())
+            .collect();
+        recordings.sort_by_key(|frames| std::cmp::Reverse(frames.len()));
+
+        let frames = &recordings[0];
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].frame, mock);
+        assert_eq!(frames[0].ticks().count(), 7);
+        assert_eq!(frames[1].ticks().next().unwrap().last_price, 1500.01);
+        assert!((ist::now() - frames[0].received_at).num_seconds() < 60);
+        assert_eq!(recordings[1].len(), 1);
+        assert!(read_recording("mocks/ticker_frame.bin").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
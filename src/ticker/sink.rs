@@ -0,0 +1,159 @@
+//! Forwarding of ticker events into message buses and other consumers
+
+use super::{KiteTicker, TickerEvent};
+use crate::error::{KiteError, Result};
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+/// Destination the events of a ticker are forwarded into, such as a Kafka
+/// producer, a Redis stream or a NATS subject
+///
+/// Pass the sink to [`KiteTicker::forward`], which hands it every event in
+/// order and waits for each to be accepted, so a slow sink holds up the
+/// ticker instead of losing events. Sinks interested in ticks only ignore the
+/// other events. Tokio `mpsc` senders of events are sinks themselves, serving
+/// as the reference implementation and as a bridge into code reading a channel.
+///
+/// ```rust,no_run
+/// use async_trait::async_trait;
+/// use kiteconnect::error::Result;
+/// use kiteconnect::models::InstrumentToken;
+/// use kiteconnect::ticker::{KiteTicker, TickSink, TickerEvent};
+///
+/// struct Stdout;
+///
+/// #[async_trait]
+/// impl TickSink for Stdout {
+///     async fn send(&mut self, event: TickerEvent) -> Result<()> {
+///         if let TickerEvent::Ticks(ticks) = event {
+///             for tick in ticks {
+///                 println!("{} {}", tick.instrument_token, tick.last_price);
+///             }
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<()> {
+/// let ticker = KiteTicker::connect("api_key", "access_token").await?;
+/// ticker.subscribe([InstrumentToken(408065)])?;
+/// ticker.forward(Stdout).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait TickSink: Send {
+    /// Accepts the next event; an error stops the forwarding
+    async fn send(&mut self, event: TickerEvent) -> Result<()>;
+
+    /// Called once the ticker has stopped, after [`TickerEvent::Closed`]
+    async fn close(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TickSink for mpsc::Sender<TickerEvent> {
+    async fn send(&mut self, event: TickerEvent) -> Result<()> {
+        mpsc::Sender::send(self, event).await.map_err(|_| receiver_dropped())
+    }
+}
+
+#[async_trait]
+impl TickSink for mpsc::UnboundedSender<TickerEvent> {
+    async fn send(&mut self, event: TickerEvent) -> Result<()> {
+        mpsc::UnboundedSender::send(self, event).map_err(|_| receiver_dropped())
+    }
+}
+
+#[async_trait]
+impl<S: TickSink + ?Sized> TickSink for Box<S> {
+    async fn send(&mut self, event: TickerEvent) -> Result<()> {
+        (**self).send(event).await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        (**self).close().await
+    }
+}
+
+fn receiver_dropped() -> KiteError {
+    KiteError::Other("tick sink receiver was dropped".to_string())
+}
+
+impl KiteTicker {
+    /// Forwards every event into `sink` until the ticker stops, then closes the
+    /// sink and returns it
+    ///
+    /// Returns the first error of the sink, dropping the ticker and with it
+    /// the connection.
+    pub async fn forward<S: TickSink>(mut self, mut sink: S) -> Result<S> {
+        while let Some(event) = self.next_event().await {
+            sink.send(event).await?;
+        }
+        sink.close().await?;
+        Ok(sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::RetryPolicy;
+    use futures::SinkExt;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    async fn mock_ticker() -> KiteTicker {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut server = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let frame = [0, 1, 0, 8, 0, 6, 58, 1, 0, 2, 73, 241];
+            server.send(Message::binary(frame.to_vec())).await.unwrap();
+            server.close(None).await.unwrap();
+        });
+        KiteTicker::builder("key", "token")
+            .root_url(&url)
+            .reconnect(RetryPolicy::none())
+            .connect()
+            .await
+            .unwrap()
+    }
+
+    #[derive(Default)]
+    struct Collect {
+        events: Vec<TickerEvent>,
+        closed: bool,
+    }
+
+    #[async_trait]
+    impl TickSink for Collect {
+        async fn send(&mut self, event: TickerEvent) -> Result<()> {
+            self.events.push(event);
+            Ok(())
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            self.closed = true;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forward() {
+        let sink = mock_ticker().await.forward(Collect::default()).await.unwrap();
+        assert!(sink.closed);
+        assert_eq!(sink.events.first(), Some(&TickerEvent::Connected));
+        assert!(matches!(&sink.events[1], TickerEvent::Ticks(ticks) if ticks[0].last_price == 1500.01));
+        assert_eq!(sink.events.last(), Some(&TickerEvent::Closed));
+
+        let (tx, mut rx) = mpsc::channel(1);
+        let forwarding = tokio::spawn(mock_ticker().await.forward(tx));
+        assert_eq!(rx.recv().await, Some(TickerEvent::Connected));
+        drop(rx);
+        assert!(forwarding.await.unwrap().is_err());
+    }
+}
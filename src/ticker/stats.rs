@@ -0,0 +1,168 @@
+//! Runtime statistics of a ticker connection
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Counters updated by the connection task
+#[derive(Debug)]
+pub(crate) struct Counters {
+    started: Instant,
+    messages: AtomicU64,
+    bytes: AtomicU64,
+    ticks: AtomicU64,
+    dropped_ticks: AtomicU64,
+    reconnects: AtomicU64,
+    parse_errors: AtomicU64,
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Counters {
+            started: Instant::now(),
+            messages: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            ticks: AtomicU64::new(0),
+            dropped_ticks: AtomicU64::new(0),
+            reconnects: AtomicU64::new(0),
+            parse_errors: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Counters {
+    pub(crate) fn message(&self, bytes: usize) {
+        self.messages.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn ticks(&self, ticks: usize) {
+        self.ticks.fetch_add(ticks as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn dropped_ticks(&self, ticks: usize) {
+        self.dropped_ticks.fetch_add(ticks as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn reconnect(&self) {
+        self.reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn parse_errors(&self, errors: usize) {
+        self.parse_errors.fetch_add(errors as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> TickerStats {
+        TickerStats {
+            uptime: self.started.elapsed(),
+            messages: self.messages.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            ticks: self.ticks.load(Ordering::Relaxed),
+            dropped_ticks: self.dropped_ticks.load(Ordering::Relaxed),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+            parse_errors: self.parse_errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Snapshot of the counters of a ticker since it connected
+///
+/// Counters only grow; rates over an interval are the difference of two
+/// snapshots, see [`rates_since`](Self::rates_since).
+///
+/// ```rust,no_run
+/// use kiteconnect::ticker::{KiteTicker, TickerStats};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let ticker = KiteTicker::connect("api_key", "access_token").await?;
+/// let handle = ticker.handle();
+/// tokio::spawn(async move {
+///     let mut previous = TickerStats::default();
+///     loop {
+///         tokio::time::sleep(Duration::from_secs(10)).await;
+///         let stats = handle.stats();
+///         let rates = stats.rates_since(&previous);
+///         println!("{:.0} msg/s, {:.0} B/s, {} ticks dropped", rates.messages, rates.bytes, stats.dropped_ticks);
+///         previous = stats;
+///     }
+/// });
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TickerStats {
+    /// Time since the ticker connected
+    pub uptime: Duration,
+    /// Websocket messages received, including heartbeats
+    pub messages: u64,
+    /// Payload bytes received
+    pub bytes: u64,
+    /// Ticks decoded
+    pub ticks: u64,
+    /// Ticks dropped because the event queue was full, see [`OverflowPolicy`](super::OverflowPolicy)
+    pub dropped_ticks: u64,
+    /// Successful reconnections after the connection dropped
+    pub reconnects: u64,
+    /// Packets and text messages that could not be decoded
+    pub parse_errors: u64,
+}
+
+impl TickerStats {
+    /// Returns the average rates between an earlier snapshot and this one
+    ///
+    /// Passing [`TickerStats::default()`] gives the averages since the ticker connected.
+    pub fn rates_since(&self, earlier: &TickerStats) -> TickerRates {
+        let secs = self.uptime.saturating_sub(earlier.uptime).as_secs_f64();
+        let rate = |now: u64, then: u64| {
+            if secs > 0.0 {
+                now.saturating_sub(then) as f64 / secs
+            } else {
+                0.0
+            }
+        };
+        TickerRates {
+            messages: rate(self.messages, earlier.messages),
+            bytes: rate(self.bytes, earlier.bytes),
+            ticks: rate(self.ticks, earlier.ticks),
+        }
+    }
+}
+
+/// Per-second rates between two [`TickerStats`] snapshots
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TickerRates {
+    /// Messages per second
+    pub messages: f64,
+    /// Bytes per second
+    pub bytes: f64,
+    /// Ticks per second
+    pub ticks: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rates_since() {
+        let earlier = TickerStats {
+            uptime: Duration::from_secs(10),
+            messages: 100,
+            bytes: 4000,
+            ticks: 300,
+            ..TickerStats::default()
+        };
+        let now = TickerStats {
+            uptime: Duration::from_secs(12),
+            messages: 120,
+            bytes: 5000,
+            ticks: 400,
+            ..TickerStats::default()
+        };
+        let rates = now.rates_since(&earlier);
+        assert_eq!(rates, TickerRates { messages: 10.0, bytes: 500.0, ticks: 50.0 });
+        assert_eq!(now.rates_since(&TickerStats::default()).messages, 10.0);
+        assert_eq!(now.rates_since(&now), TickerRates::default());
+    }
+}
@@ -0,0 +1,165 @@
+//! Subscription table shared by the handles of a ticker
+
+use super::{Command, Mode};
+use crate::models::InstrumentToken;
+use std::collections::BTreeMap;
+
+/// Identifies a handle holding subscriptions; every clone of a handle gets its own
+pub(crate) type HolderId = u64;
+
+/// Subscribed instrument tokens, reference counted by the handles holding them
+///
+/// A token stays subscribed while any handle holds it, and is streamed in the
+/// most detailed mode any of them asked for.
+#[derive(Debug, Default)]
+pub(crate) struct Subscriptions {
+    tokens: BTreeMap<InstrumentToken, BTreeMap<HolderId, Mode>>,
+    next_holder: HolderId,
+}
+
+impl Subscriptions {
+    /// Returns the id of a new holder without subscriptions
+    pub(crate) fn new_holder(&mut self) -> HolderId {
+        self.next_holder += 1;
+        self.next_holder
+    }
+
+    /// Returns the subscribed tokens and the modes they are streamed in
+    pub(crate) fn modes(&self) -> BTreeMap<InstrumentToken, Mode> {
+        self.tokens
+            .iter()
+            .filter_map(|(&token, holders)| Some((token, *holders.values().max()?)))
+            .collect()
+    }
+
+    /// Holds `tokens` for `holder` in quote mode, keeping the mode of those it already holds
+    pub(crate) fn subscribe(&mut self, holder: HolderId, tokens: &[InstrumentToken]) -> Vec<Command> {
+        self.update(holder, tokens, |held| Some(held.unwrap_or(Mode::Quote)))
+    }
+
+    /// Holds `tokens` for `holder` in `mode`
+    pub(crate) fn set_mode(&mut self, holder: HolderId, mode: Mode, tokens: &[InstrumentToken]) -> Vec<Command> {
+        self.update(holder, tokens, |_| Some(mode))
+    }
+
+    /// Releases the `tokens` held by `holder`
+    pub(crate) fn unsubscribe(&mut self, holder: HolderId, tokens: &[InstrumentToken]) -> Vec<Command> {
+        self.update(holder, tokens, |_| None)
+    }
+
+    /// Releases every token held by `holder`
+    pub(crate) fn unsubscribe_all(&mut self, holder: HolderId) -> Vec<Command> {
+        let held: Vec<InstrumentToken> = self
+            .tokens
+            .iter()
+            .filter(|(_, holders)| holders.contains_key(&holder))
+            .map(|(&token, _)| token)
+            .collect();
+        self.unsubscribe(holder, &held)
+    }
+
+    /// Changes the mode `holder` holds each token in, `None` releasing it, and
+    /// returns the commands bringing the connection in line
+    fn update(
+        &mut self,
+        holder: HolderId,
+        tokens: &[InstrumentToken],
+        change: impl Fn(Option<Mode>) -> Option<Mode>,
+    ) -> Vec<Command> {
+        let mut subscribed = Vec::new();
+        let mut unsubscribed = Vec::new();
+        let mut modes: Vec<(Mode, Vec<InstrumentToken>)> = Vec::new();
+        for &token in tokens {
+            let holders = self.tokens.entry(token).or_default();
+            let before = holders.values().max().copied();
+            match change(holders.get(&holder).copied()) {
+                Some(mode) => holders.insert(holder, mode),
+                None => holders.remove(&holder),
+            };
+            let after = holders.values().max().copied();
+            if holders.is_empty() {
+                self.tokens.remove(&token);
+            }
+
+            let mode = match (before, after) {
+                (None, None) => continue,
+                (Some(_), None) => {
+                    unsubscribed.push(token);
+                    continue;
+                }
+                // Tokens are subscribed in quote mode
+                (None, Some(mode)) => {
+                    subscribed.push(token);
+                    if mode == Mode::Quote {
+                        continue;
+                    }
+                    mode
+                }
+                (Some(before), Some(after)) if before != after => after,
+                (Some(_), Some(_)) => continue,
+            };
+            match modes.iter_mut().find(|(changed, _)| *changed == mode) {
+                Some((_, changed)) => changed.push(token),
+                None => modes.push((mode, vec![token])),
+            }
+        }
+
+        let mut commands = Vec::new();
+        if !unsubscribed.is_empty() {
+            commands.push(Command::Unsubscribe(unsubscribed));
+        }
+        if !subscribed.is_empty() {
+            commands.push(Command::Subscribe(subscribed));
+        }
+        commands.extend(modes.into_iter().map(|(mode, tokens)| Command::SetMode(mode, tokens)));
+        commands
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens<const N: usize>(tokens: [u32; N]) -> [InstrumentToken; N] {
+        tokens.map(InstrumentToken)
+    }
+
+    fn texts(commands: Vec<Command>) -> Vec<String> {
+        commands.iter().filter_map(Command::text).collect()
+    }
+
+    #[test]
+    fn test_subscriptions() {
+        let mut table = Subscriptions::default();
+        let (strategy, cache) = (table.new_holder(), table.new_holder());
+
+        assert_eq!(
+            texts(table.subscribe(strategy, &tokens([408065, 884737]))),
+            [r#"{"a":"subscribe","v":[408065,884737]}"#]
+        );
+        // Already subscribed by another holder, so only the mode is raised
+        assert_eq!(
+            texts(table.set_mode(cache, Mode::Full, &tokens([408065, 256265]))),
+            [r#"{"a":"subscribe","v":[256265]}"#, r#"{"a":"mode","v":["full",[408065,256265]]}"#]
+        );
+        assert!(table.subscribe(cache, &tokens([884737])).is_empty());
+        assert_eq!(
+            table.modes(),
+            BTreeMap::from([
+                (InstrumentToken(256265), Mode::Full),
+                (InstrumentToken(408065), Mode::Full),
+                (InstrumentToken(884737), Mode::Quote),
+            ])
+        );
+
+        // Tokens another holder still needs stay subscribed
+        assert_eq!(texts(table.unsubscribe(strategy, &tokens([408065, 884737, 1]))), Vec::<String>::new());
+        assert_eq!(texts(table.unsubscribe_all(cache)), [r#"{"a":"unsubscribe","v":[256265,408065,884737]}"#]);
+        assert!(table.modes().is_empty());
+
+        // Releasing the most detailed mode lowers the mode to the next one
+        table.set_mode(strategy, Mode::Ltp, &tokens([408065]));
+        table.set_mode(cache, Mode::Full, &tokens([408065]));
+        assert_eq!(texts(table.unsubscribe(cache, &tokens([408065]))), [r#"{"a":"mode","v":["ltp",[408065]]}"#]);
+    }
+}
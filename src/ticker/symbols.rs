@@ -0,0 +1,194 @@
+//! Resolution of `EXCHANGE:TRADINGSYMBOL` names to instrument tokens
+
+use super::{Mode, TickerHandle};
+use crate::connect::KiteConnect;
+use crate::error::{KiteError, Result};
+use crate::models::InstrumentToken;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Instrument tokens of one exchange by tradingsymbol
+type SymbolTable = HashMap<String, InstrumentToken>;
+
+/// Cache resolving `"NSE:INFY"`-style names to instrument tokens
+///
+/// The instruments dump of an exchange is downloaded the first time one of
+/// its symbols is resolved and kept until [`clear`](Self::clear). Tokens of
+/// derivatives change with every expiry, so long running programs should clear
+/// the cache daily. Clones share the cache.
+///
+/// ```rust,no_run
+/// use kiteconnect::connect::KiteConnect;
+/// use kiteconnect::ticker::{KiteTicker, Mode, SymbolResolver};
+///
+/// # #[tokio::main]
+/// # async fn main() -> kiteconnect::error::Result<()> {
+/// let resolver = SymbolResolver::new(KiteConnect::new("api_key", "access_token"));
+/// let ticker = KiteTicker::connect("api_key", "access_token").await?;
+/// let tokens = ticker
+///     .handle()
+///     .subscribe_symbols(&resolver, &["NSE:INFY", "NSE:RELIANCE"])
+///     .await?;
+/// ticker.set_mode(Mode::Full, &tokens)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SymbolResolver {
+    client: KiteConnect,
+    /// Loaded exchanges; the async lock makes concurrent lookups share one download
+    exchanges: Arc<tokio::sync::Mutex<HashMap<String, SymbolTable>>>,
+    /// Names of the resolved tokens, for [`symbol`](Self::symbol)
+    names: Arc<Mutex<HashMap<InstrumentToken, String>>>,
+}
+
+impl std::fmt::Debug for SymbolResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SymbolResolver").finish_non_exhaustive()
+    }
+}
+
+impl SymbolResolver {
+    /// Resolves symbols with the instruments dump fetched by `client`
+    pub fn new(client: KiteConnect) -> Self {
+        SymbolResolver {
+            client,
+            exchanges: Arc::default(),
+            names: Arc::default(),
+        }
+    }
+
+    /// Returns the instrument token of an `EXCHANGE:TRADINGSYMBOL` name
+    pub async fn resolve(&self, symbol: &str) -> Result<InstrumentToken> {
+        let (exchange, tradingsymbol) = symbol
+            .split_once(':')
+            .ok_or_else(|| KiteError::Other(format!("expected EXCHANGE:TRADINGSYMBOL, got {:?}", symbol)))?;
+
+        let mut exchanges = self.exchanges.lock().await;
+        if !exchanges.contains_key(exchange) {
+            let table = self.load(exchange).await?;
+            exchanges.insert(exchange.to_string(), table);
+        }
+        let token = exchanges[exchange]
+            .get(tradingsymbol)
+            .copied()
+            .ok_or_else(|| KiteError::Other(format!("unknown instrument {:?}", symbol)))?;
+        self.names.lock().unwrap().insert(token, symbol.to_string());
+        Ok(token)
+    }
+
+    /// Returns the instrument tokens of several names, in the same order
+    pub async fn resolve_all(&self, symbols: &[&str]) -> Result<Vec<InstrumentToken>> {
+        let mut tokens = Vec::with_capacity(symbols.len());
+        for symbol in symbols {
+            tokens.push(self.resolve(symbol).await?);
+        }
+        Ok(tokens)
+    }
+
+    /// Returns the name an instrument token was resolved from, e.g. to label ticks
+    pub fn symbol(&self, instrument_token: impl Into<InstrumentToken>) -> Option<String> {
+        self.names.lock().unwrap().get(&instrument_token.into()).cloned()
+    }
+
+    /// Forgets the downloaded instruments, which are fetched again when needed
+    pub async fn clear(&self) {
+        self.exchanges.lock().await.clear();
+        self.names.lock().unwrap().clear();
+    }
+
+    async fn load(&self, exchange: &str) -> Result<SymbolTable> {
+        let instruments = self.client.instruments(Some(exchange)).await?;
+        let mut table = SymbolTable::new();
+        for instrument in instruments.as_array().into_iter().flatten() {
+            let (Some(tradingsymbol), Some(token)) = (
+                field(instrument, "tradingsymbol"),
+                field(instrument, "instrument_token").and_then(|token| token.parse().ok()),
+            ) else {
+                continue;
+            };
+            if field(instrument, "exchange").is_none_or(|listed| listed == exchange) {
+                table.insert(tradingsymbol.to_string(), token);
+            }
+        }
+        log::debug!("Loaded {} instruments of {}", table.len(), exchange);
+        Ok(table)
+    }
+}
+
+/// Reads a column of an instruments dump row, tolerating padded headers
+fn field<'a>(instrument: &'a JsonValue, name: &str) -> Option<&'a str> {
+    instrument
+        .as_object()?
+        .iter()
+        .find(|(key, _)| key.trim() == name)
+        .and_then(|(_, value)| value.as_str())
+        .map(str::trim)
+}
+
+impl TickerHandle {
+    /// Subscribes to `EXCHANGE:TRADINGSYMBOL` names in [`Mode::Quote`],
+    /// returning their instrument tokens in the same order
+    ///
+    /// Nothing is subscribed if any name cannot be resolved.
+    pub async fn subscribe_symbols(&self, resolver: &SymbolResolver, symbols: &[&str]) -> Result<Vec<InstrumentToken>> {
+        let tokens = resolver.resolve_all(symbols).await?;
+        self.subscribe(&tokens)?;
+        Ok(tokens)
+    }
+
+    /// Streams `EXCHANGE:TRADINGSYMBOL` names in `mode`, returning their instrument tokens
+    pub async fn set_mode_symbols(
+        &self,
+        mode: Mode,
+        resolver: &SymbolResolver,
+        symbols: &[&str],
+    ) -> Result<Vec<InstrumentToken>> {
+        let tokens = resolver.resolve_all(symbols).await?;
+        self.set_mode(mode, &tokens)?;
+        Ok(tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Server;
+
+    #[tokio::test]
+    async fn test_resolve_symbols() {
+        let mut server = Server::new_async().await;
+        let nse = server
+            .mock("GET", "/instruments/NSE")
+            .with_body_from_file("mocks/instruments.csv")
+            .expect(1)
+            .create_async()
+            .await;
+        let nfo = server
+            .mock("GET", "/instruments/NFO")
+            .with_body_from_file("mocks/instruments.csv")
+            .expect(1)
+            .create_async()
+            .await;
+        let client = KiteConnect::builder("API_KEY")
+            .access_token("ACCESS_TOKEN")
+            .base_url(&server.url())
+            .build()
+            .unwrap();
+        let resolver = SymbolResolver::new(client);
+
+        assert_eq!(resolver.resolve("NSE:INFY").await.unwrap(), InstrumentToken(408065));
+        assert_eq!(
+            resolver.resolve_all(&["NFO:NIFTY15DECFUT", "NSE:INFY"]).await.unwrap(),
+            [InstrumentToken(5720322), InstrumentToken(408065)]
+        );
+        // Listed on another exchange of the dump
+        assert!(resolver.resolve("NSE:NIFTY15DECFUT").await.is_err());
+        assert!(resolver.resolve("INFY").await.is_err());
+        assert_eq!(resolver.symbol(InstrumentToken(5720322)).as_deref(), Some("NFO:NIFTY15DECFUT"));
+        assert_eq!(resolver.symbol(InstrumentToken(645639)), None);
+        nse.assert_async().await;
+        nfo.assert_async().await;
+    }
+}
@@ -0,0 +1,284 @@
+//! Websocket transports the ticker protocol runs over
+
+use crate::error::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+
+/// Message received from a websocket transport
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WsMessage {
+    /// JSON message, such as an order update or an error notice
+    Text(String),
+    /// Binary frame of market data packets, or a one byte heartbeat
+    Binary(Bytes),
+}
+
+impl WsMessage {
+    /// Returns the size of the payload in bytes
+    pub fn len(&self) -> usize {
+        match self {
+            WsMessage::Text(text) => text.len(),
+            WsMessage::Binary(frame) => frame.len(),
+        }
+    }
+
+    /// Returns `true` if the payload is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Open websocket connection carrying the ticker protocol
+///
+/// The ticker sends its commands as text messages and decodes the messages it
+/// receives; the transport only moves them. Natively the ticker runs over
+/// `tokio-tungstenite`, and over the browser's `WebSocket` on `wasm32`. Other
+/// implementations, e.g. scripted ones in tests, are plugged in through
+/// [`TickerBuilder::connector`](super::TickerBuilder::connector).
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait WsTransport {
+    /// Sends a text message
+    async fn send(&mut self, text: String) -> Result<()>;
+
+    /// Waits for the next message, returning `None` once the connection is closed
+    ///
+    /// Control frames such as pings are handled by the transport and not returned.
+    async fn recv(&mut self) -> Option<Result<WsMessage>>;
+
+    /// Closes the connection
+    async fn close(&mut self) -> Result<()>;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use native::{TungsteniteConnector, WsConnector};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use super::{WsMessage, WsTransport};
+    use crate::error::{KiteError, Result};
+    use crate::proxy::ProxyConfig;
+    use async_trait::async_trait;
+    use futures::{SinkExt, StreamExt};
+    use tokio::net::TcpStream;
+    use tokio_tungstenite::tungstenite::{self, Message};
+    use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+    /// Opens the websocket connections of a ticker, for the first connection
+    /// and every reconnection
+    #[async_trait]
+    pub trait WsConnector: Send + Sync {
+        /// Connects to `url`, which carries the API key and access token
+        ///
+        /// Rejected handshakes should be mapped with the status and body of the
+        /// response, so that a rejected access token is a [`KiteError::Token`]
+        /// and stops the reconnection attempts.
+        async fn connect(&self, url: &url::Url) -> Result<Box<dyn WsTransport + Send>>;
+    }
+
+    /// Connects with `tokio-tungstenite`, the default connector
+    ///
+    /// With a proxy, the connection is tunnelled through it with HTTP
+    /// `CONNECT` or SOCKS5 before the TLS and websocket handshakes. HTTPS and
+    /// SOCKS4 proxies are not supported.
+    #[derive(Clone, Debug, Default)]
+    pub struct TungsteniteConnector {
+        proxy: Option<ProxyConfig>,
+    }
+
+    impl TungsteniteConnector {
+        /// Creates a connector connecting directly
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Creates a connector going through `proxy` for the URLs it applies to
+        pub fn with_proxy(proxy: ProxyConfig) -> Self {
+            TungsteniteConnector { proxy: Some(proxy) }
+        }
+
+        /// Returns the proxy of this connector, if any
+        pub fn proxy(&self) -> Option<&ProxyConfig> {
+            self.proxy.as_ref()
+        }
+    }
+
+    #[async_trait]
+    impl WsConnector for TungsteniteConnector {
+        async fn connect(&self, url: &url::Url) -> Result<Box<dyn WsTransport + Send>> {
+            let connected = match self.proxy.as_ref().filter(|proxy| proxy.applies_to(url)) {
+                Some(proxy) => {
+                    let stream = crate::ticker::tunnel::open(proxy, url).await?;
+                    tokio_tungstenite::client_async_tls_with_config(url.as_str(), stream, None, None).await
+                }
+                None => tokio_tungstenite::connect_async(url.as_str()).await,
+            };
+            match connected {
+                Ok((socket, _)) => Ok(Box::new(Tungstenite(socket))),
+                Err(tungstenite::Error::Http(response)) => {
+                    let content_type = response
+                        .headers()
+                        .get("content-type")
+                        .and_then(|value| value.to_str().ok());
+                    let body = response
+                        .body()
+                        .as_deref()
+                        .map(String::from_utf8_lossy)
+                        .unwrap_or_default();
+                    Err(KiteError::from_response(response.status().as_u16(), content_type, &body))
+                }
+                Err(e) => Err(KiteError::Other(format!("failed to connect to the ticker: {}", e))),
+            }
+        }
+    }
+
+    struct Tungstenite(WebSocketStream<MaybeTlsStream<TcpStream>>);
+
+    #[async_trait]
+    impl WsTransport for Tungstenite {
+        async fn send(&mut self, text: String) -> Result<()> {
+            self.0.send(Message::text(text)).await.map_err(socket_error)
+        }
+
+        async fn recv(&mut self) -> Option<Result<WsMessage>> {
+            loop {
+                match self.0.next().await? {
+                    Ok(Message::Binary(frame)) => return Some(Ok(WsMessage::Binary(frame))),
+                    Ok(Message::Text(text)) => return Some(Ok(WsMessage::Text(text.as_str().to_owned()))),
+                    Ok(Message::Close(frame)) => {
+                        log::warn!("Ticker connection closed by server: {:?}", frame);
+                        return None;
+                    }
+                    // Pings are answered by tungstenite
+                    Ok(_) => {}
+                    Err(e) => return Some(Err(socket_error(e))),
+                }
+            }
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            self.0.close(None).await.map_err(socket_error)
+        }
+    }
+
+    fn socket_error(error: tungstenite::Error) -> KiteError {
+        KiteError::Other(format!("ticker connection failed: {}", error))
+    }
+}
+
+/// Scripted transport for testing the ticker without a server
+#[cfg(all(test, not(target_arch = "wasm32")))]
+pub(crate) mod mock {
+    use super::{WsConnector, WsMessage, WsTransport};
+    use crate::error::{KiteError, Result};
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+    use tokio::sync::mpsc;
+
+    /// Ends of a mock connection held by the test
+    pub(crate) struct MockServer {
+        /// Messages for the ticker to receive; dropping it closes the connection
+        pub(crate) incoming: mpsc::UnboundedSender<Result<WsMessage>>,
+        /// Commands sent by the ticker
+        pub(crate) sent: mpsc::UnboundedReceiver<String>,
+    }
+
+    /// Hands out one prepared connection per connection attempt, failing once
+    /// none are left
+    #[derive(Default)]
+    pub(crate) struct MockConnector {
+        connections: Mutex<Vec<MockTransport>>,
+    }
+
+    impl MockConnector {
+        /// Prepares the next connection, returning the test's ends of it
+        pub(crate) fn accept(&self) -> MockServer {
+            let (incoming, incoming_rx) = mpsc::unbounded_channel();
+            let (sent_tx, sent) = mpsc::unbounded_channel();
+            self.connections.lock().unwrap().insert(
+                0,
+                MockTransport {
+                    incoming: incoming_rx,
+                    sent: sent_tx,
+                },
+            );
+            MockServer { incoming, sent }
+        }
+    }
+
+    #[async_trait]
+    impl WsConnector for MockConnector {
+        async fn connect(&self, _url: &url::Url) -> Result<Box<dyn WsTransport + Send>> {
+            match self.connections.lock().unwrap().pop() {
+                Some(transport) => Ok(Box::new(transport)),
+                None => Err(KiteError::Other("connection refused".to_string())),
+            }
+        }
+    }
+
+    struct MockTransport {
+        incoming: mpsc::UnboundedReceiver<Result<WsMessage>>,
+        sent: mpsc::UnboundedSender<String>,
+    }
+
+    #[async_trait]
+    impl WsTransport for MockTransport {
+        async fn send(&mut self, text: String) -> Result<()> {
+            self.sent
+                .send(text)
+                .map_err(|_| KiteError::Other("mock connection closed".to_string()))
+        }
+
+        async fn recv(&mut self) -> Option<Result<WsMessage>> {
+            self.incoming.recv().await
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            self.incoming.close();
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::proxy::ProxyConfig;
+    use futures::{SinkExt, StreamExt};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    #[tokio::test]
+    async fn test_connect_through_proxy() {
+        // The proxy grants the tunnel and then serves the websocket itself
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = ProxyConfig::all(&format!("http://{}", listener.local_addr().unwrap())).basic_auth("user", "secret");
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            while !head.ends_with(b"\r\n\r\n") {
+                head.push(socket.read_u8().await.unwrap());
+            }
+            socket.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            ws.send(Message::text(r#"{"type": "message"}"#)).await.unwrap();
+            let command = ws.next().await.unwrap().unwrap();
+            (String::from_utf8(head).unwrap(), command.into_text().unwrap().to_string())
+        });
+
+        let connector = TungsteniteConnector::with_proxy(proxy);
+        let url = url::Url::parse("ws://ws.kite.invalid/?api_key=key&access_token=token").unwrap();
+        let mut transport = connector.connect(&url).await.unwrap();
+        assert_eq!(
+            transport.recv().await.unwrap().unwrap(),
+            WsMessage::Text(r#"{"type": "message"}"#.to_string())
+        );
+        transport.send(r#"{"a": "subscribe", "v": [408065]}"#.to_string()).await.unwrap();
+
+        let (head, command) = server.await.unwrap();
+        assert!(head.starts_with("CONNECT ws.kite.invalid:80 HTTP/1.1\r\n"));
+        assert!(head.contains("Proxy-Authorization: Basic dXNlcjpzZWNyZXQ=\r\n"));
+        assert_eq!(command, r#"{"a": "subscribe", "v": [408065]}"#);
+    }
+}
@@ -0,0 +1,218 @@
+//! Tunnels through HTTP and SOCKS5 proxies for the websocket connection
+
+use crate::error::{KiteError, Result};
+use crate::proxy::ProxyConfig;
+use base64::Engine;
+use std::io;
+use std::net::IpAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Longest proxy response head read before giving up on the proxy
+const MAX_RESPONSE_HEAD: usize = 8 * 1024;
+
+/// Opens a TCP connection to the host of `url` through `proxy`, ready for the
+/// TLS and websocket handshakes
+pub(crate) async fn open(proxy: &ProxyConfig, url: &url::Url) -> Result<TcpStream> {
+    let proxy_url = url::Url::parse(&proxy.url)
+        .map_err(|e| KiteError::Other(format!("invalid proxy URL {:?}: {}", proxy.url, e)))?;
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return Err(KiteError::Other(format!("ticker URL {} has no host", url)));
+    };
+    let proxy_host = proxy_url.host_str().unwrap_or_default();
+    let tunnel = match proxy_url.scheme() {
+        "http" => {
+            let proxy_port = proxy_url.port_or_known_default().unwrap_or(80);
+            http_connect(proxy_host, proxy_port, proxy, host, port).await
+        }
+        "socks5" | "socks5h" => {
+            let proxy_port = proxy_url.port().unwrap_or(1080);
+            // Without the `h` the proxy is given an address resolved here
+            let resolve = proxy_url.scheme() == "socks5";
+            socks5_connect(proxy_host, proxy_port, proxy, host, port, resolve).await
+        }
+        scheme => {
+            return Err(KiteError::Other(format!(
+                "{} proxies are not supported by the ticker, use an http:// or socks5:// proxy",
+                scheme
+            )))
+        }
+    };
+    tunnel.map_err(|e| KiteError::Other(format!("failed to tunnel to {} through proxy {}: {}", host, proxy_host, e)))
+}
+
+/// Asks an HTTP proxy for a tunnel with `CONNECT`
+async fn http_connect(
+    proxy_host: &str,
+    proxy_port: u16,
+    proxy: &ProxyConfig,
+    host: &str,
+    port: u16,
+) -> io::Result<TcpStream> {
+    let mut stream = TcpStream::connect((proxy_host, proxy_port)).await?;
+    let mut request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+    if let Some((username, password)) = &proxy.credentials {
+        let credentials = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // Read byte by byte so nothing of the tunnelled stream is consumed
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_RESPONSE_HEAD {
+            return Err(io::Error::other("response head too long"));
+        }
+        head.push(stream.read_u8().await?);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let status_line = head.lines().next().unwrap_or_default();
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(stream),
+        _ => Err(io::Error::other(format!("tunnel refused with {:?}", status_line))),
+    }
+}
+
+/// Opens a tunnel through a SOCKS5 proxy (RFC 1928), authenticating with
+/// username and password (RFC 1929) if credentials are set
+async fn socks5_connect(
+    proxy_host: &str,
+    proxy_port: u16,
+    proxy: &ProxyConfig,
+    host: &str,
+    port: u16,
+    resolve: bool,
+) -> io::Result<TcpStream> {
+    const NO_AUTH: u8 = 0x00;
+    const PASSWORD: u8 = 0x02;
+
+    let mut request = vec![0x05, 0x01, 0x00];
+    if resolve {
+        let address = tokio::net::lookup_host((host, port))
+            .await?
+            .next()
+            .ok_or_else(|| io::Error::other(format!("could not resolve {}", host)))?;
+        match address.ip() {
+            IpAddr::V4(ip) => request.extend(std::iter::once(0x01).chain(ip.octets())),
+            IpAddr::V6(ip) => request.extend(std::iter::once(0x04).chain(ip.octets())),
+        }
+    } else {
+        request.extend([0x03, field_len(host)?]);
+        request.extend_from_slice(host.as_bytes());
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+
+    let mut stream = TcpStream::connect((proxy_host, proxy_port)).await?;
+    let method = if proxy.credentials.is_some() { PASSWORD } else { NO_AUTH };
+    stream.write_all(&[0x05, 0x01, method]).await?;
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await?;
+    if reply != [0x05, method] {
+        return Err(io::Error::other("authentication method refused"));
+    }
+    if let Some((username, password)) = &proxy.credentials {
+        let mut auth = vec![0x01, field_len(username)?];
+        auth.extend_from_slice(username.as_bytes());
+        auth.push(field_len(password)?);
+        auth.extend_from_slice(password.as_bytes());
+        stream.write_all(&auth).await?;
+        stream.read_exact(&mut reply).await?;
+        if reply[1] != 0x00 {
+            return Err(io::Error::other("credentials rejected"));
+        }
+    }
+
+    stream.write_all(&request).await?;
+    let mut head = [0u8; 4];
+    stream.read_exact(&mut head).await?;
+    if head[1] != 0x00 {
+        return Err(io::Error::other(format!("tunnel refused with reply {}", head[1])));
+    }
+    // Skip the bound address the reply ends with
+    let address_len = match head[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => usize::from(stream.read_u8().await?),
+        other => return Err(io::Error::other(format!("unknown address type {}", other))),
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound).await?;
+    Ok(stream)
+}
+
+/// Length of a SOCKS field, which takes at most 255 bytes
+fn field_len(field: &str) -> io::Result<u8> {
+    u8::try_from(field.len()).map_err(|_| io::Error::other("field longer than 255 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Accepts one connection, answering its handshake with `reply` and
+    /// returning what the client sent
+    async fn proxy_server(reply: &'static [u8]) -> (String, tokio::task::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let task = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut received = vec![0u8; 1024];
+            let n = socket.read(&mut received).await.unwrap();
+            received.truncate(n);
+            socket.write_all(reply).await.unwrap();
+            received
+        });
+        (address.to_string(), task)
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_refused() {
+        let (address, task) = proxy_server(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n").await;
+        let proxy = ProxyConfig::all(&format!("http://{}", address)).basic_auth("user", "secret");
+        let url = url::Url::parse("wss://ws.kite.trade").unwrap();
+        let err = open(&proxy, &url).await.unwrap_err();
+        assert!(err.to_string().contains("407"));
+
+        let request = String::from_utf8(task.await.unwrap()).unwrap();
+        assert!(request.starts_with("CONNECT ws.kite.trade:443 HTTP/1.1\r\n"));
+        assert!(request.contains("Proxy-Authorization: Basic dXNlcjpzZWNyZXQ=\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_socks5_connect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = ProxyConfig::all(&format!("socks5h://{}", listener.local_addr().unwrap())).basic_auth("u", "pw");
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut greeting = [0u8; 3];
+            socket.read_exact(&mut greeting).await.unwrap();
+            socket.write_all(&[0x05, 0x02]).await.unwrap();
+            let mut auth = [0u8; 6];
+            socket.read_exact(&mut auth).await.unwrap();
+            socket.write_all(&[0x01, 0x00]).await.unwrap();
+            let mut request = vec![0u8; 5 + "ws.kite.trade".len() + 2];
+            socket.read_exact(&mut request).await.unwrap();
+            socket.write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0]).await.unwrap();
+            socket.write_all(b"tunnelled").await.unwrap();
+            (greeting, auth, request)
+        });
+
+        let url = url::Url::parse("wss://ws.kite.trade").unwrap();
+        let mut stream = open(&proxy, &url).await.unwrap();
+        let mut data = [0u8; 9];
+        stream.read_exact(&mut data).await.unwrap();
+        assert_eq!(&data, b"tunnelled");
+
+        let (greeting, auth, request) = server.await.unwrap();
+        assert_eq!(greeting, [0x05, 0x01, 0x02]);
+        assert_eq!(auth, [0x01, 1, b'u', 2, b'p', b'w']);
+        assert_eq!(&request[..5], &[0x05, 0x01, 0x00, 0x03, 13]);
+        assert_eq!(&request[5..18], b"ws.kite.trade");
+        assert_eq!(&request[18..], &443u16.to_be_bytes());
+
+        let proxy = ProxyConfig::all("socks4://127.0.0.1:1080");
+        assert!(open(&proxy, &url).await.is_err());
+    }
+}
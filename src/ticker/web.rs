@@ -0,0 +1,355 @@
+//! Ticker connection on the browser's `WebSocket`
+
+use super::{
+    collect_tokens, packet, parse_text, Command, Mode, TickerError, TickerEvent, WsMessage, WsTransport,
+    DEFAULT_TICKER_URL,
+};
+use super::subscriptions::{HolderId, Subscriptions};
+use crate::error::{KiteError, Result};
+use crate::models::InstrumentToken;
+use async_trait::async_trait;
+use futures::channel::{mpsc, oneshot};
+use futures::{Stream, StreamExt};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{BinaryType, CloseEvent, Event, MessageEvent, WebSocket};
+
+/// Live market data connection over the browser's `WebSocket`
+///
+/// Events are decoded as they are read with [`next_event`](Self::next_event)
+/// or as a [`Stream`]. The socket is closed when the ticker is dropped.
+pub struct KiteTicker {
+    handle: TickerHandle,
+    transport: WebTransport,
+    /// Events decoded from the last message and not yet delivered
+    pending: VecDeque<TickerEvent>,
+    /// Whether [`TickerEvent::Connected`] was delivered
+    announced: bool,
+    /// Whether [`TickerEvent::Closed`] was delivered
+    closed: bool,
+}
+
+impl fmt::Debug for KiteTicker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KiteTicker")
+            .field("handle", &self.handle)
+            .finish_non_exhaustive()
+    }
+}
+
+impl KiteTicker {
+    /// Connects to the Kite websocket API
+    ///
+    /// Returns an error if the connection cannot be established. Browsers do not
+    /// reveal the reason, so a rejected access token fails the same way.
+    pub async fn connect(api_key: &str, access_token: &str) -> Result<Self> {
+        Self::connect_url(DEFAULT_TICKER_URL, api_key, access_token).await
+    }
+
+    /// Connects to the websocket API at a different URL, e.g. a mock server
+    pub async fn connect_url(root_url: &str, api_key: &str, access_token: &str) -> Result<Self> {
+        let mut url = url::Url::parse(root_url)
+            .map_err(|e| KiteError::Other(format!("invalid ticker URL {:?}: {}", root_url, e)))?;
+        url.query_pairs_mut()
+            .append_pair("api_key", api_key)
+            .append_pair("access_token", access_token);
+        let transport = WebTransport::open(url.as_str()).await?;
+        let mut subscriptions = Subscriptions::default();
+        let holder = subscriptions.new_holder();
+        Ok(KiteTicker {
+            handle: TickerHandle {
+                socket: transport.socket.clone(),
+                subscriptions: Rc::new(RefCell::new(subscriptions)),
+                holder,
+            },
+            transport,
+            pending: VecDeque::new(),
+            announced: false,
+            closed: false,
+        })
+    }
+
+    /// Returns a handle changing the subscriptions of this ticker
+    pub fn handle(&self) -> TickerHandle {
+        self.handle.clone()
+    }
+
+    /// Subscribes to market data of the given instrument tokens, see [`TickerHandle::subscribe`]
+    pub fn subscribe(&self, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Result<()> {
+        self.handle.subscribe(tokens)
+    }
+
+    /// Stops market data of the given instrument tokens, see [`TickerHandle::unsubscribe`]
+    pub fn unsubscribe(&self, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Result<()> {
+        self.handle.unsubscribe(tokens)
+    }
+
+    /// Streams the given instrument tokens in `mode`, see [`TickerHandle::set_mode`]
+    pub fn set_mode(&self, mode: Mode, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Result<()> {
+        self.handle.set_mode(mode, tokens)
+    }
+
+    /// Returns the subscribed instrument tokens and their modes
+    pub fn subscriptions(&self) -> BTreeMap<InstrumentToken, Mode> {
+        self.handle.subscriptions()
+    }
+
+    /// Closes the connection; the ticker delivers [`TickerEvent::Closed`] and stops
+    pub fn close(&self) {
+        self.handle.close()
+    }
+
+    /// Waits for the next event, returning `None` after [`TickerEvent::Closed`]
+    pub async fn next_event(&mut self) -> Option<TickerEvent> {
+        if !self.announced {
+            self.announced = true;
+            return Some(TickerEvent::Connected);
+        }
+        while !self.closed {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+            match self.transport.recv().await {
+                Some(Ok(message)) => decode_message(message, &mut self.pending),
+                Some(Err(e)) => {
+                    log::warn!("Ticker connection failed: {}", e);
+                    return Some(TickerEvent::Error(TickerError::Connection(e.to_string())));
+                }
+                None => {
+                    self.closed = true;
+                    return Some(TickerEvent::Closed);
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the events as a stream, which ends after [`TickerEvent::Closed`]
+    pub fn stream(&mut self) -> impl Stream<Item = TickerEvent> + '_ {
+        futures::stream::unfold(self, |ticker| async move {
+            let event = ticker.next_event().await?;
+            Some((event, ticker))
+        })
+    }
+
+    /// Turns the ticker into a stream of its events
+    pub fn into_stream(self) -> impl Stream<Item = TickerEvent> + 'static {
+        futures::stream::unfold(self, |mut ticker| async move {
+            let event = ticker.next_event().await?;
+            Some((event, ticker))
+        })
+    }
+}
+
+/// Cloneable handle changing the subscriptions of a [`KiteTicker`]
+///
+/// Every clone is a consumer of its own: a token stays subscribed until all
+/// handles that subscribed to it unsubscribe, and is streamed in the most
+/// detailed mode any of them set.
+#[derive(Debug)]
+pub struct TickerHandle {
+    socket: WebSocket,
+    subscriptions: Rc<RefCell<Subscriptions>>,
+    holder: HolderId,
+}
+
+impl Clone for TickerHandle {
+    fn clone(&self) -> Self {
+        TickerHandle {
+            socket: self.socket.clone(),
+            subscriptions: self.subscriptions.clone(),
+            holder: self.subscriptions.borrow_mut().new_holder(),
+        }
+    }
+}
+
+impl TickerHandle {
+    /// Subscribes to market data of the given instrument tokens in [`Mode::Quote`]
+    ///
+    /// Tokens this handle already subscribed to keep their mode. Returns an
+    /// error if the connection is closed.
+    pub fn subscribe(&self, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Result<()> {
+        self.ensure_open()?;
+        let tokens = collect_tokens(tokens);
+        let commands = self.subscriptions.borrow_mut().subscribe(self.holder, &tokens);
+        self.send(commands)
+    }
+
+    /// Stops market data of the given instrument tokens, unless other handles
+    /// are subscribed to them
+    pub fn unsubscribe(&self, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Result<()> {
+        self.ensure_open()?;
+        let tokens = collect_tokens(tokens);
+        let commands = self.subscriptions.borrow_mut().unsubscribe(self.holder, &tokens);
+        self.send(commands)
+    }
+
+    /// Releases every subscription of this handle, e.g. once its consumer is done
+    pub fn unsubscribe_all(&self) -> Result<()> {
+        self.ensure_open()?;
+        let commands = self.subscriptions.borrow_mut().unsubscribe_all(self.holder);
+        self.send(commands)
+    }
+
+    /// Streams the given instrument tokens in `mode`, subscribing those that are not yet
+    pub fn set_mode(&self, mode: Mode, tokens: impl IntoIterator<Item = impl Into<InstrumentToken>>) -> Result<()> {
+        self.ensure_open()?;
+        let tokens = collect_tokens(tokens);
+        let commands = self.subscriptions.borrow_mut().set_mode(self.holder, mode, &tokens);
+        self.send(commands)
+    }
+
+    /// Returns the instrument tokens subscribed by any handle and the modes
+    /// they are streamed in
+    pub fn subscriptions(&self) -> BTreeMap<InstrumentToken, Mode> {
+        self.subscriptions.borrow().modes()
+    }
+
+    /// Closes the connection; the ticker delivers [`TickerEvent::Closed`] and stops
+    pub fn close(&self) {
+        let _ = self.send(vec![Command::Close]);
+    }
+
+    fn ensure_open(&self) -> Result<()> {
+        if self.socket.ready_state() != WebSocket::OPEN {
+            return Err(KiteError::Other("ticker is closed".to_string()));
+        }
+        Ok(())
+    }
+
+    fn send(&self, commands: Vec<Command>) -> Result<()> {
+        for command in commands {
+            match command.text() {
+                Some(text) => self.socket.send_with_str(&text),
+                None => self.socket.close(),
+            }
+            .map_err(|e| js_error("failed to send ticker command", e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes a message into its events, if any
+fn decode_message(message: WsMessage, events: &mut VecDeque<TickerEvent>) {
+    match message {
+        WsMessage::Text(text) => match parse_text(&text) {
+            Ok(event) => events.extend(event),
+            Err(e) => {
+                log::warn!("Ignoring malformed ticker message: {}", e);
+                events.push_back(TickerEvent::Error(e));
+            }
+        },
+        // Heartbeats are single byte frames without packets
+        WsMessage::Binary(frame) => {
+            let (ticks, errors) = packet::parse_frame(&frame);
+            if !ticks.is_empty() {
+                events.push_back(TickerEvent::Ticks(ticks));
+            }
+            events.extend(errors.into_iter().map(TickerEvent::Error));
+        }
+    }
+}
+
+/// The browser's `WebSocket` as a [`WsTransport`]
+///
+/// Messages are queued by the socket's callbacks, which are detached when the
+/// transport is dropped.
+struct WebTransport {
+    socket: WebSocket,
+    messages: mpsc::UnboundedReceiver<WsMessage>,
+    _on_open: Closure<dyn FnMut(Event)>,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_close: Closure<dyn FnMut(CloseEvent)>,
+}
+
+impl WebTransport {
+    /// Opens a socket and waits until it is connected
+    async fn open(url: &str) -> Result<Self> {
+        let socket = WebSocket::new(url).map_err(|e| js_error("failed to open the ticker", e))?;
+        socket.set_binary_type(BinaryType::Arraybuffer);
+
+        let (messages_tx, messages) = mpsc::unbounded();
+        let (opened_tx, opened) = oneshot::channel();
+        let opened_tx = Rc::new(RefCell::new(Some(opened_tx)));
+
+        let on_open = {
+            let opened_tx = opened_tx.clone();
+            Closure::<dyn FnMut(Event)>::new(move |_: Event| {
+                if let Some(opened) = opened_tx.borrow_mut().take() {
+                    let _ = opened.send(true);
+                }
+            })
+        };
+        let on_message = {
+            let messages = messages_tx.clone();
+            Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                let data = event.data();
+                let message = match data.as_string() {
+                    Some(text) => WsMessage::Text(text),
+                    None => match data.dyn_ref::<js_sys::ArrayBuffer>() {
+                        Some(buffer) => WsMessage::Binary(js_sys::Uint8Array::new(buffer).to_vec().into()),
+                        None => return,
+                    },
+                };
+                let _ = messages.unbounded_send(message);
+            })
+        };
+        let on_close = Closure::<dyn FnMut(CloseEvent)>::new(move |event: CloseEvent| {
+            if let Some(opened) = opened_tx.borrow_mut().take() {
+                let _ = opened.send(false);
+            }
+            log::warn!("Ticker connection closed ({}): {}", event.code(), event.reason());
+            messages_tx.close_channel();
+        });
+        socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+
+        // Constructed before waiting so that dropping it detaches the callbacks
+        let transport = WebTransport {
+            socket,
+            messages,
+            _on_open: on_open,
+            _on_message: on_message,
+            _on_close: on_close,
+        };
+        match opened.await {
+            Ok(true) => Ok(transport),
+            _ => Err(KiteError::Other("failed to connect to the ticker".to_string())),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl WsTransport for WebTransport {
+    async fn send(&mut self, text: String) -> Result<()> {
+        self.socket
+            .send_with_str(&text)
+            .map_err(|e| js_error("failed to send ticker command", e))
+    }
+
+    async fn recv(&mut self) -> Option<Result<WsMessage>> {
+        self.messages.next().await.map(Ok)
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.socket.close().map_err(|e| js_error("failed to close the ticker", e))
+    }
+}
+
+impl Drop for WebTransport {
+    fn drop(&mut self) {
+        self.socket.set_onopen(None);
+        self.socket.set_onmessage(None);
+        self.socket.set_onclose(None);
+        let _ = self.socket.close();
+    }
+}
+
+fn js_error(context: &str, error: JsValue) -> KiteError {
+    KiteError::Other(format!("{}: {:?}", context, error))
+}